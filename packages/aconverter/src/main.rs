@@ -11,7 +11,9 @@ use futures::StreamExt as _;
 use moosicbox_audiotags::Tag;
 use moosicbox_files::{files::track::get_audio_bytes, save_bytes_stream_to_file};
 use moosicbox_music_api::models::TrackSource;
-use moosicbox_music_models::{from_extension_to_audio_format, AudioFormat, TrackApiSource};
+use moosicbox_music_models::{
+    from_extension_to_audio_format, AudioFormat, EncodingSettings, PlaybackQuality, TrackApiSource,
+};
 use thiserror::Error;
 
 #[derive(Parser, Debug)]
@@ -23,6 +25,8 @@ struct Args {
     #[arg(short, long)]
     output: String,
 
+    /// The output format, e.g. `mp3`, `alac`, `vorbis` or `wav`. Defaults to
+    /// the format of the `output` extension
     #[arg(short, long)]
     encoding: Option<String>,
 
@@ -34,6 +38,20 @@ struct Args {
 
     #[arg(short, long, default_value_t = 80)]
     quality: u8,
+
+    /// The constant bitrate, in kbps
+    #[arg(long)]
+    bitrate: Option<u32>,
+
+    /// The variable bitrate quality, from 0 (best) to 9 (worst)
+    #[arg(long)]
+    vbr_quality: Option<u8>,
+
+    #[arg(long)]
+    sample_rate: Option<u32>,
+
+    #[arg(long)]
+    channels: Option<u8>,
 }
 
 #[tokio::main]
@@ -76,8 +94,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             format: source_encoding,
             track_id: None,
             source: TrackApiSource::Local,
+            start_offset: None,
+            end_offset: None,
+        },
+        PlaybackQuality {
+            format: output_encoding,
+            settings: EncodingSettings {
+                bitrate: args.bitrate,
+                vbr_quality: args.vbr_quality,
+                sample_rate: args.sample_rate,
+                channels: args.channels,
+            },
         },
-        output_encoding,
         None,
         None,
         None,
@@ -96,7 +124,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     )
     .await?;
 
-    tag_track_file(&source, &output)?;
+    match output_encoding {
+        #[cfg(feature = "alac")]
+        AudioFormat::Alac => log::debug!("Not tagging fragmented MP4 output"),
+        #[cfg(feature = "vorbis")]
+        AudioFormat::Vorbis => log::debug!("Not tagging Ogg Vorbis output"),
+        #[cfg(feature = "wav")]
+        AudioFormat::Wav => log::debug!("Not tagging WAV output"),
+        _ => tag_track_file(&source, &output)?,
+    }
 
     Ok(())
 }
@@ -7,7 +7,7 @@ use actix_web::{
     route, web, Scope,
 };
 use maud::{html, Markup};
-use moosicbox_database::profiles::LibraryDatabase;
+use moosicbox_database::profiles::{api::ProfileName, LibraryDatabase};
 use moosicbox_json_utils::database::DatabaseFetchError;
 use moosicbox_music_api::MusicApis;
 use moosicbox_scan::ScanOrigin;
@@ -70,10 +70,11 @@ pub async fn delete_scan_paths_endpoint(
 #[route("run-scan", method = "POST")]
 pub async fn start_scan_endpoint(
     _htmx: Htmx,
+    profile: ProfileName,
     db: LibraryDatabase,
     music_apis: MusicApis,
 ) -> Result<Markup, actix_web::Error> {
-    moosicbox_scan::run_scan(Some(vec![ScanOrigin::Local]), &db, music_apis)
+    moosicbox_scan::run_scan(Some(vec![ScanOrigin::Local]), &profile.0, &db, music_apis)
         .await
         .map_err(|e| ErrorInternalServerError(format!("Failed to run scan: {e:?}")))?;
 
@@ -174,10 +174,11 @@ pub async fn get_settings_endpoint(
 #[route("run-scan", method = "POST")]
 pub async fn run_scan_endpoint(
     _htmx: Htmx,
+    profile: moosicbox_database::profiles::api::ProfileName,
     db: LibraryDatabase,
     music_apis: MusicApis,
 ) -> Result<Markup, actix_web::Error> {
-    moosicbox_scan::run_scan(Some(vec![ScanOrigin::Tidal]), &db, music_apis)
+    moosicbox_scan::run_scan(Some(vec![ScanOrigin::Tidal]), &profile.0, &db, music_apis)
         .await
         .map_err(|e| ErrorInternalServerError(format!("Failed to run scan: {e:?}")))?;
 
@@ -204,8 +204,13 @@ async fn set_current_session(session: ApiSession) {
         playlist: Some(ApiUpdateSessionPlaylist {
             session_playlist_id: session.playlist.session_playlist_id,
             tracks: session.playlist.tracks.clone(),
+            unshuffled_positions: session.playlist.unshuffled_positions.clone(),
         }),
         quality: None,
+        shuffle: None,
+        repeat_mode: None,
+        crossfade: None,
+        replay_gain_mode: None,
     };
 
     let state = convert_state(&STATE).await;
@@ -807,6 +812,10 @@ async fn handle_action(action: Action, value: Option<Value>) -> Result<(), AppSt
                                     volume: None,
                                     playlist: None,
                                     quality: None,
+                                    shuffle: None,
+                                    repeat_mode: None,
+                                    crossfade: None,
+                                    replay_gain_mode: None,
                                 },
                             }),
                             true,
@@ -839,6 +848,10 @@ async fn handle_action(action: Action, value: Option<Value>) -> Result<(), AppSt
                                         volume: None,
                                         playlist: None,
                                         quality: None,
+                                        shuffle: None,
+                                        repeat_mode: None,
+                                        crossfade: None,
+                                        replay_gain_mode: None,
                                     },
                                 }),
                                 true,
@@ -871,6 +884,10 @@ async fn handle_action(action: Action, value: Option<Value>) -> Result<(), AppSt
                                         volume: None,
                                         playlist: None,
                                         quality: None,
+                                        shuffle: None,
+                                        repeat_mode: None,
+                                        crossfade: None,
+                                        replay_gain_mode: None,
                                     },
                                 }),
                                 true,
@@ -914,6 +931,10 @@ async fn handle_action(action: Action, value: Option<Value>) -> Result<(), AppSt
                                         volume: Some(f64::from(volume)),
                                         playlist: None,
                                         quality: None,
+                                        shuffle: None,
+                                        repeat_mode: None,
+                                        crossfade: None,
+                                        replay_gain_mode: None,
                                     },
                                 }),
                                 true,
@@ -966,6 +987,10 @@ async fn handle_action(action: Action, value: Option<Value>) -> Result<(), AppSt
                                                     volume: None,
                                                     playlist: None,
                                                     quality: None,
+                                                    shuffle: None,
+                                                    repeat_mode: None,
+                                                    crossfade: None,
+                                                    replay_gain_mode: None,
                                                 },
                                             }),
                                             true,
@@ -1076,8 +1101,13 @@ async fn handle_action(action: Action, value: Option<Value>) -> Result<(), AppSt
                                     playlist: Some(UpdateSessionPlaylist {
                                         session_playlist_id: session.playlist.session_playlist_id,
                                         tracks,
+                                        unshuffled_positions: None,
                                     }),
                                     quality: None,
+                                    shuffle: None,
+                                    repeat_mode: None,
+                                    crossfade: None,
+                                    replay_gain_mode: None,
                                 },
                             }),
                             true,
@@ -1122,8 +1152,13 @@ async fn handle_action(action: Action, value: Option<Value>) -> Result<(), AppSt
                                     playlist: Some(UpdateSessionPlaylist {
                                         session_playlist_id: session.playlist.session_playlist_id,
                                         tracks,
+                                        unshuffled_positions: None,
                                     }),
                                     quality: None,
+                                    shuffle: None,
+                                    repeat_mode: None,
+                                    crossfade: None,
+                                    replay_gain_mode: None,
                                 },
                             }),
                             true,
@@ -223,6 +223,8 @@ pub struct AppState {
     pub current_connections: Arc<RwLock<Vec<ApiConnection>>>,
     pub pending_player_sessions: Arc<RwLock<HashMap<u64, u64>>>,
     pub current_sessions: Arc<RwLock<Vec<ApiSession>>>,
+    /// The revision of the last queue edit applied to each of the `current_sessions`
+    pub session_queue_revisions: Arc<RwLock<HashMap<u64, u64>>>,
     #[allow(clippy::type_complexity)]
     pub on_current_sessions_updated_listeners: Vec<
         Arc<Box<dyn Fn(&[ApiSession]) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>>,
@@ -645,6 +647,10 @@ impl AppState {
                 None,
                 None,
                 *self.playback_quality.read().await,
+                None,
+                None,
+                None,
+                None,
                 Some(session_id),
                 profile,
                 Some(playback_target.into()),
@@ -754,6 +760,10 @@ impl AppState {
                         Some(playback.volume.load(std::sync::atomic::Ordering::SeqCst)),
                         Some(playback.tracks.clone()),
                         Some(playback.quality),
+                        Some(playback.shuffle),
+                        Some(playback.repeat_mode),
+                        playback.crossfade,
+                        Some(playback.replay_gain_mode),
                         Some(playback.session_id),
                         Some(playback.profile),
                         Some(playback_target.clone().into()),
@@ -1421,10 +1431,23 @@ impl AppState {
                     if update.volume.is_none() {
                         update.volume = session.volume;
                     }
+                    if update.shuffle.is_none() {
+                        update.shuffle = Some(session.shuffle);
+                    }
+                    if update.repeat_mode.is_none() {
+                        update.repeat_mode = Some(session.repeat_mode);
+                    }
+                    if update.crossfade.is_none() {
+                        update.crossfade = session.crossfade;
+                    }
+                    if update.replay_gain_mode.is_none() {
+                        update.replay_gain_mode = Some(session.replay_gain_mode);
+                    }
                     if update.playlist.is_none() {
                         update.playlist = Some(ApiUpdateSessionPlaylist {
                             session_playlist_id: session.playlist.session_playlist_id,
                             tracks: session.playlist.tracks,
+                            unshuffled_positions: session.playlist.unshuffled_positions,
                         });
                     }
                 }
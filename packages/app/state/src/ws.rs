@@ -5,7 +5,7 @@ use moosicbox_app_ws::{
 };
 use moosicbox_audio_output::AudioOutputScannerError;
 use moosicbox_player::{PlayerError, DEFAULT_PLAYBACK_RETRY_OPTIONS};
-use moosicbox_session::models::{ApiSession, ApiUpdateSession};
+use moosicbox_session::models::{ApiSession, ApiUpdateSession, SessionQueueUpdated};
 use moosicbox_ws::models::{EmptyPayload, InboundPayload, OutboundPayload};
 use serde::Serialize;
 use thiserror::Error;
@@ -292,6 +292,10 @@ impl AppState {
                                     volume: None,
                                     playlist: None,
                                     quality: None,
+                                    shuffle: None,
+                                    repeat_mode: None,
+                                    crossfade: None,
+                                    replay_gain_mode: None,
                                 },
                                 true,
                             )
@@ -335,6 +339,9 @@ impl AppState {
                     OutboundPayload::SessionUpdated(payload) => {
                         state.handle_playback_update(&payload.payload, true).await?;
                     }
+                    OutboundPayload::SessionQueueUpdated(payload) => {
+                        state.handle_session_queue_update(&payload.payload).await?;
+                    }
                     OutboundPayload::SetSeek(payload) => {
                         #[allow(clippy::cast_precision_loss)]
                         state
@@ -353,6 +360,10 @@ impl AppState {
                                     volume: None,
                                     playlist: None,
                                     quality: None,
+                                    shuffle: None,
+                                    repeat_mode: None,
+                                    crossfade: None,
+                                    replay_gain_mode: None,
                                 },
                                 true,
                             )
@@ -429,6 +440,21 @@ impl AppState {
                                 .await
                                 .retain(|id, _| !player_ids.contains(id));
                         }
+                        // The sessions whose queue changed since the revision the local players
+                        // were last brought to, e.g. after missing queue edits
+                        let resync_sessions = {
+                            let mut revisions = state.session_queue_revisions.write().await;
+                            payload
+                                .payload
+                                .iter()
+                                .filter(|session| {
+                                    revisions
+                                        .insert(session.session_id, session.queue_revision)
+                                        .is_some_and(|previous| previous < session.queue_revision)
+                                })
+                                .cloned()
+                                .collect::<Vec<_>>()
+                        };
                         {
                             (*state.current_sessions.write().await).clone_from(&payload.payload);
 
@@ -447,6 +473,9 @@ impl AppState {
                                     .collect::<Vec<_>>(),
                             )
                             .await?;
+                        for session in &resync_sessions {
+                            state.resync_session_queue(session).await?;
+                        }
                         state.update_playlist().await;
                     }
 
@@ -537,6 +566,142 @@ impl AppState {
         Ok(())
     }
 
+    /// Replaces the queue of the session's local players with the queue of the fetched
+    /// `session`, after they missed some of its queue edits.
+    ///
+    /// # Errors
+    ///
+    /// * If fails to update playback
+    async fn resync_session_queue(&self, session: &ApiSession) -> Result<(), AppStateError> {
+        let profile = self.profile.read().await.clone();
+
+        for mut player in self.get_players(session.session_id, None).await {
+            log::debug!(
+                "resync_session_queue: session_id={} player={} revision={}",
+                session.session_id,
+                player.id,
+                session.queue_revision
+            );
+            player
+                .update_playback(
+                    true,
+                    None,
+                    None,
+                    None,
+                    session.position,
+                    None,
+                    None,
+                    Some(
+                        session
+                            .playlist
+                            .tracks
+                            .iter()
+                            .cloned()
+                            .map(Into::into)
+                            .collect(),
+                    ),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(session.session_id),
+                    profile.clone(),
+                    None,
+                    false,
+                    Some(DEFAULT_PLAYBACK_RETRY_OPTIONS),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies a session queue delta to the current session and its players.
+    ///
+    /// # Errors
+    ///
+    /// * If fails to update playback
+    pub async fn handle_session_queue_update(
+        &self,
+        updated: &SessionQueueUpdated,
+    ) -> Result<(), AppStateError> {
+        log::debug!("handle_session_queue_update: {updated:?}");
+
+        let mut revisions = self.session_queue_revisions.write().await;
+        let previous_revision = match revisions.get(&updated.session_id).copied() {
+            Some(revision) => Some(revision),
+            // Fall back to the revision of the fetched session's queue
+            None => self
+                .current_sessions
+                .read()
+                .await
+                .iter()
+                .find(|x| x.session_id == updated.session_id)
+                .map(|x| x.queue_revision),
+        };
+
+        match previous_revision {
+            Some(previous) if updated.revision <= previous => {
+                drop(revisions);
+                log::debug!(
+                    "handle_session_queue_update: Ignoring stale revision {} (already at {previous})",
+                    updated.revision
+                );
+                return Ok(());
+            }
+            Some(previous) if updated.revision == previous + 1 => {
+                revisions.insert(updated.session_id, updated.revision);
+                drop(revisions);
+
+                let mut binding = self.current_sessions.write().await;
+                let session = binding
+                    .iter_mut()
+                    .find(|x| x.session_id == updated.session_id);
+
+                if let Some(session) = session {
+                    if let Some(unshuffled_positions) = &mut session.playlist.unshuffled_positions {
+                        updated.delta.apply_unshuffled(unshuffled_positions);
+                    }
+                    let position = session.position.unwrap_or_default();
+                    let position = updated.delta.apply(&mut session.playlist.tracks, position);
+                    session.position.replace(position);
+                    session.queue_revision = updated.revision;
+                }
+
+                drop(binding);
+            }
+            previous => {
+                drop(revisions);
+                // The players' queues are resynced from the fetched sessions
+                log::warn!(
+                    "handle_session_queue_update: Missed queue edits between revisions {previous:?} and {}, refetching sessions",
+                    updated.revision
+                );
+                self.queue_ws_message(InboundPayload::GetSessions(EmptyPayload {}), false)
+                    .await?;
+                return Ok(());
+            }
+        }
+
+        let players = self.get_players(updated.session_id, None).await;
+
+        log::debug!(
+            "handle_session_queue_update: player count={}",
+            players.len()
+        );
+
+        for mut player in players {
+            player
+                .edit_queue(&updated.delta, Some(DEFAULT_PLAYBACK_RETRY_OPTIONS))
+                .await?;
+        }
+
+        self.update_playlist().await;
+
+        Ok(())
+    }
+
     /// # Errors
     ///
     /// * If fails to update playback
@@ -622,6 +787,10 @@ impl AppState {
                         .playlist
                         .map(|x| x.tracks.into_iter().map(Into::into).collect()),
                     update.quality,
+                    update.shuffle,
+                    update.repeat_mode,
+                    update.crossfade,
+                    update.replay_gain_mode,
                     Some(update.session_id),
                     Some(update.profile.clone()),
                     Some(update.playback_target.into()),
@@ -276,6 +276,10 @@ async fn set_playback_quality(quality: PlaybackQuality) -> Result<(), TauriPlaye
                 None,
                 None,
                 *STATE.playback_quality.read().await,
+                None,
+                None,
+                None,
+                None,
                 Some(x.session_id),
                 profile.clone(),
                 Some(x.playback_target.clone().into()),
@@ -505,6 +509,10 @@ async fn handle_media_event(
                     volume: None,
                     playlist: None,
                     quality: None,
+                    shuffle: None,
+                    repeat_mode: None,
+                    crossfade: None,
+                    replay_gain_mode: None,
                 },
                 false,
             )
@@ -541,6 +549,10 @@ async fn handle_media_event(
                     volume: None,
                     playlist: None,
                     quality: None,
+                    shuffle: None,
+                    repeat_mode: None,
+                    crossfade: None,
+                    replay_gain_mode: None,
                 },
                 false,
             )
@@ -567,6 +579,10 @@ async fn handle_media_event(
                     volume: None,
                     playlist: None,
                     quality: None,
+                    shuffle: None,
+                    repeat_mode: None,
+                    crossfade: None,
+                    replay_gain_mode: None,
                 },
                 false,
             )
@@ -592,6 +608,10 @@ async fn handle_media_event(
                     volume: None,
                     playlist: None,
                     quality: None,
+                    shuffle: None,
+                    repeat_mode: None,
+                    crossfade: None,
+                    replay_gain_mode: None,
                 },
                 false,
             )
@@ -0,0 +1,140 @@
+//! DSD (1-bit Direct Stream Digital) support.
+//!
+//! The DSF and DFF format readers hand out packets of channel-interleaved DSD
+//! bytes, with the oldest bit of each byte in the MSB, timestamped in DSD
+//! frames (one bit per channel). Those packets are decoded to PCM by
+//! [`DsdDecoder`], or packed into DSD over PCM (`DoP`) frames by
+//! [`DopDecoder`] for outputs that pass them through to a DSD capable DAC.
+
+use std::{fs::File, path::Path};
+
+use symphonia::core::{
+    audio::Channels,
+    codecs::{decl_codec_type, CodecType},
+    errors::Error,
+    formats::FormatReader as _,
+    io::{MediaSourceStream, MediaSourceStreamOptions},
+};
+
+pub mod decoder;
+pub mod dff;
+pub mod dsf;
+
+pub use decoder::{DopDecoder, DsdDecoder};
+pub use dff::DffReader;
+pub use dsf::DsfReader;
+
+/// Raw, uncompressed, DSD
+pub const CODEC_TYPE_DSD: CodecType = decl_codec_type(b"dsd");
+
+/// The number of bytes per channel in each packet handed out by the readers
+pub(crate) const PACKET_BYTES_PER_CHANNEL: usize = 4096;
+
+/// The byte DSD streams idle on, which decodes to silence
+pub(crate) const DSD_SILENCE: u8 = 0x69;
+
+/// The DSD sample rate of DSD64, the SACD rate
+pub const DSD64_SAMPLE_RATE: u32 = 2_822_400;
+
+/// The properties of a DSD file, read from its headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DsdProperties {
+    /// The DSD sample rate, in bits per second per channel
+    pub sample_rate: u32,
+    pub channels: u8,
+    /// The number of DSD frames, in bits per channel
+    pub frames: u64,
+}
+
+impl DsdProperties {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn duration(&self) -> f64 {
+        self.frames as f64 / f64::from(self.sample_rate)
+    }
+
+    /// The bitrate of the DSD stream, in kbps
+    #[must_use]
+    pub fn bitrate(&self) -> u32 {
+        self.sample_rate / 1000 * u32::from(self.channels)
+    }
+
+    /// The rate of the `DoP` stream that carries this DSD stream
+    #[must_use]
+    pub const fn dop_sample_rate(&self) -> u32 {
+        dop_sample_rate(self.sample_rate)
+    }
+}
+
+/// The rate of the `DoP` stream that carries a DSD stream at `sample_rate`.
+/// Each `DoP` frame carries 16 DSD bits per channel.
+#[must_use]
+pub const fn dop_sample_rate(sample_rate: u32) -> u32 {
+    sample_rate / 16
+}
+
+/// Whether the file at `path` is a DSF or DFF file, judging by its extension
+#[must_use]
+pub fn is_dsd_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .is_some_and(|x| x.eq_ignore_ascii_case("dsf") || x.eq_ignore_ascii_case("dff"))
+}
+
+/// Reads the properties of the DSF or DFF file at `path`.
+///
+/// # Errors
+///
+/// * If the file fails to open
+/// * If the file isn't a supported DSF or DFF file
+pub fn read_properties(path: &Path) -> Result<DsdProperties, Error> {
+    let source = MediaSourceStream::new(
+        Box::new(File::open(path)?),
+        MediaSourceStreamOptions::default(),
+    );
+    let options = symphonia::core::formats::FormatOptions::default();
+
+    let is_dff = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .is_some_and(|x| x.eq_ignore_ascii_case("dff"));
+
+    let properties = if is_dff {
+        DffReader::try_new(source, &options)?.properties()
+    } else {
+        DsfReader::try_new(source, &options)?.properties()
+    };
+
+    Ok(properties)
+}
+
+/// The channel layout of a DSD stream with `count` channels
+pub(crate) fn channels(count: u8) -> Result<Channels, Error> {
+    Ok(match count {
+        1 => Channels::FRONT_CENTRE,
+        2 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+        3 => Channels::FRONT_LEFT | Channels::FRONT_RIGHT | Channels::FRONT_CENTRE,
+        4 => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::REAR_LEFT
+                | Channels::REAR_RIGHT
+        }
+        5 => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::FRONT_CENTRE
+                | Channels::REAR_LEFT
+                | Channels::REAR_RIGHT
+        }
+        6 => {
+            Channels::FRONT_LEFT
+                | Channels::FRONT_RIGHT
+                | Channels::FRONT_CENTRE
+                | Channels::LFE1
+                | Channels::REAR_LEFT
+                | Channels::REAR_RIGHT
+        }
+        _ => return Err(Error::Unsupported("dsd: unsupported channel count")),
+    })
+}
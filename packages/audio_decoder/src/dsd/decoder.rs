@@ -0,0 +1,442 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::f64::consts::PI;
+
+use symphonia::core::{
+    audio::{AsAudioBufferRef as _, AudioBuffer, AudioBufferRef, Signal as _, SignalSpec},
+    codecs::{CodecDescriptor, CodecParameters, Decoder, DecoderOptions, FinalizeResult},
+    errors::{unsupported_error, Result},
+    formats::Packet,
+};
+
+use super::{dop_sample_rate, CODEC_TYPE_DSD, DSD_SILENCE, PACKET_BYTES_PER_CHANNEL};
+
+/// The highest PCM rate a DSD stream is decoded to
+const MAX_PCM_SAMPLE_RATE: u32 = 352_800;
+
+/// The cutoff of the decimation low-pass filter, in Hz. DSD pushes its
+/// quantization noise up and out of the audible band, and this keeps that
+/// noise out of the decoded PCM.
+const CUTOFF: f64 = 30_000.0;
+
+/// The length of the decimation filter, in multiples of the decimation ratio
+const TAPS_PER_RATIO: usize = 16;
+
+fn signal_spec(params: &CodecParameters, rate: u32) -> Result<SignalSpec> {
+    let Some(channels) = params.channels else {
+        return unsupported_error("dsd: missing channels");
+    };
+
+    Ok(SignalSpec::new(rate, channels))
+}
+
+fn max_packet_bytes(params: &CodecParameters) -> usize {
+    params
+        .max_frames_per_packet
+        .and_then(|x| usize::try_from(x / 8).ok())
+        .unwrap_or(PACKET_BYTES_PER_CHANNEL)
+}
+
+/// A windowed-sinc low-pass filter of `taps` taps, with a cutoff of
+/// `cutoff` cycles per sample and a DC gain of 1.
+#[allow(clippy::cast_precision_loss)]
+fn low_pass(taps: usize, cutoff: f64) -> Vec<f64> {
+    let center = (taps - 1) as f64 / 2.0;
+
+    let mut coefficients = (0..taps)
+        .map(|n| {
+            let x = n as f64 - center;
+            let sinc = if x.abs() < f64::EPSILON {
+                2.0 * cutoff
+            } else {
+                (2.0 * PI * cutoff * x).sin() / (PI * x)
+            };
+            // Blackman window
+            let phase = 2.0 * PI * n as f64 / (taps - 1) as f64;
+            let (cos, cos2) = (phase.cos(), (2.0 * phase).cos());
+            let window = 0.08f64.mul_add(cos2, 0.5f64.mul_add(-cos, 0.42));
+
+            sinc * window
+        })
+        .collect::<Vec<_>>();
+
+    let sum = coefficients.iter().sum::<f64>();
+    for coefficient in &mut coefficients {
+        *coefficient /= sum;
+    }
+
+    coefficients
+}
+
+/// The decimation filter split into groups of 8 taps, each with the output
+/// of every possible byte precomputed, so a PCM sample takes one lookup per
+/// byte of history instead of one multiply per bit.
+#[allow(clippy::cast_possible_truncation)]
+fn byte_tables(coefficients: &[f64]) -> Vec<[f32; 256]> {
+    coefficients
+        .chunks_exact(8)
+        .map(|taps| {
+            let mut table = [0.0; 256];
+
+            for (byte, value) in table.iter_mut().enumerate() {
+                *value = taps
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, tap)| {
+                        if byte & (0x80 >> bit) == 0 {
+                            -tap
+                        } else {
+                            *tap
+                        }
+                    })
+                    .sum::<f64>() as f32;
+            }
+
+            table
+        })
+        .collect()
+}
+
+/// The DSD bytes of a channel that are within the reach of the filter
+struct History {
+    bytes: Vec<u8>,
+    /// The index of the oldest byte
+    position: usize,
+}
+
+impl History {
+    fn new(len: usize) -> Self {
+        Self {
+            bytes: vec![DSD_SILENCE; len],
+            position: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.bytes[self.position] = byte;
+        self.position = (self.position + 1) % self.bytes.len();
+    }
+
+    fn filter(&self, tables: &[[f32; 256]]) -> f32 {
+        let (newer, older) = self.bytes.split_at(self.position);
+
+        older
+            .iter()
+            .chain(newer)
+            .zip(tables)
+            .map(|(byte, table)| table[usize::from(*byte)])
+            .sum()
+    }
+}
+
+/// Decodes DSD to PCM by low-pass filtering and decimating the 1-bit stream.
+///
+/// DSD64 and DSD128 are decimated by 32, to 88.2kHz and 176.4kHz, while the
+/// higher rates are decimated down to 352.8kHz. The bits are decoded at unity
+/// gain, so the SACD reference level of 50% modulation ends up at -6dBFS.
+pub struct DsdDecoder {
+    params: CodecParameters,
+    tables: Vec<[f32; 256]>,
+    histories: Vec<History>,
+    /// The number of DSD bytes per channel in each PCM frame
+    step: usize,
+    /// The number of DSD bytes per channel pushed since the last PCM frame
+    phase: usize,
+    buf: AudioBuffer<f32>,
+}
+
+impl Decoder for DsdDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        let Some(sample_rate) = params.sample_rate else {
+            return unsupported_error("dsd: missing sample rate");
+        };
+
+        let ratio = (sample_rate / MAX_PCM_SAMPLE_RATE)
+            .next_multiple_of(8)
+            .max(32);
+        let pcm_rate = sample_rate / ratio;
+        let spec = signal_spec(params, pcm_rate)?;
+
+        let ratio = ratio as usize;
+        let taps = ratio * TAPS_PER_RATIO;
+        let cutoff = CUTOFF.min(f64::from(pcm_rate) * 0.45) / f64::from(sample_rate);
+        let tables = byte_tables(&low_pass(taps, cutoff));
+
+        let step = ratio / 8;
+        let capacity = max_packet_bytes(params) / step + 1;
+
+        log::debug!("dsd: decoding {sample_rate}Hz DSD to {pcm_rate}Hz PCM");
+
+        Ok(Self {
+            params: params.clone(),
+            histories: (0..spec.channels.count())
+                .map(|_| History::new(tables.len()))
+                .collect(),
+            tables,
+            step,
+            phase: 0,
+            buf: AudioBuffer::new(capacity as u64, spec),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[CodecDescriptor {
+            codec: CODEC_TYPE_DSD,
+            short_name: "dsd",
+            long_name: "Direct Stream Digital",
+            inst_func: |params, options| Ok(Box::new(Self::try_new(params, options)?)),
+        }]
+    }
+
+    fn reset(&mut self) {
+        for history in &mut self.histories {
+            *history = History::new(self.tables.len());
+        }
+        self.phase = 0;
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        let channels = self.histories.len();
+        let bytes = packet.buf().len() / channels;
+        let frames = (self.phase + bytes) / self.step;
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(frames));
+
+        for (c, history) in self.histories.iter_mut().enumerate() {
+            let output = self.buf.chan_mut(c);
+            let mut phase = self.phase;
+            let mut frame = 0;
+
+            for byte in packet.buf()[c..].iter().step_by(channels).take(bytes) {
+                history.push(*byte);
+                phase += 1;
+
+                if phase == self.step {
+                    output[frame] = history.filter(&self.tables).clamp(-1.0, 1.0);
+                    frame += 1;
+                    phase = 0;
+                }
+            }
+        }
+
+        self.phase = (self.phase + bytes) % self.step;
+
+        Ok(self.buf.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
+
+/// The markers that alternate in the top byte of `DoP` samples
+const DOP_MARKERS: [u8; 2] = [0x05, 0xFA];
+
+/// Packs DSD into DSD over PCM (`DoP`) frames, without decoding it.
+///
+/// Every 24-bit PCM sample carries 16 DSD bits under a marker byte, at a
+/// sixteenth of the DSD rate, for DACs that detect the markers and play the
+/// DSD natively. The samples only survive if they reach the DAC untouched, so
+/// they can't be resampled or have their volume changed on the way.
+pub struct DopDecoder {
+    params: CodecParameters,
+    /// The bytes of each channel that don't fill a `DoP` sample yet
+    pending: Vec<Vec<u8>>,
+    /// The index of the marker of the next frame
+    marker: usize,
+    buf: AudioBuffer<f32>,
+}
+
+impl Decoder for DopDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        let Some(sample_rate) = params.sample_rate else {
+            return unsupported_error("dsd: missing sample rate");
+        };
+
+        let spec = signal_spec(params, dop_sample_rate(sample_rate))?;
+        let capacity = max_packet_bytes(params) / 2 + 1;
+
+        log::debug!("dsd: packing {sample_rate}Hz DSD into {}Hz DoP", spec.rate);
+
+        Ok(Self {
+            params: params.clone(),
+            pending: vec![vec![]; spec.channels.count()],
+            marker: 0,
+            buf: AudioBuffer::new(capacity as u64, spec),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[CodecDescriptor {
+            codec: CODEC_TYPE_DSD,
+            short_name: "dop",
+            long_name: "DSD over PCM",
+            inst_func: |params, options| Ok(Box::new(Self::try_new(params, options)?)),
+        }]
+    }
+
+    fn reset(&mut self) {
+        for pending in &mut self.pending {
+            pending.clear();
+        }
+        self.marker = 0;
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        let channels = self.pending.len();
+
+        for (c, pending) in self.pending.iter_mut().enumerate() {
+            pending.extend(packet.buf()[c..].iter().step_by(channels));
+        }
+
+        let frames = self.pending.first().map_or(0, |x| x.len() / 2);
+
+        self.buf.clear();
+        self.buf.render_reserved(Some(frames));
+
+        for (c, pending) in self.pending.iter_mut().enumerate() {
+            let output = self.buf.chan_mut(c);
+
+            for (frame, (bytes, sample)) in
+                pending.chunks_exact(2).zip(output.iter_mut()).enumerate()
+            {
+                let marker = DOP_MARKERS[(self.marker + frame) % 2];
+                let value = i32::from_be_bytes([marker, bytes[0], bytes[1], 0]) >> 8;
+
+                *sample = value as f32 / 8_388_608.0;
+            }
+
+            pending.drain(..frames * 2);
+        }
+
+        self.marker = (self.marker + frames) % 2;
+
+        Ok(self.buf.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use symphonia::core::audio::Channels;
+
+    use super::*;
+    use crate::dsd::DSD64_SAMPLE_RATE;
+
+    fn params() -> CodecParameters {
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_DSD)
+            .with_sample_rate(DSD64_SAMPLE_RATE)
+            .with_channels(Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+            .with_max_frames_per_packet(PACKET_BYTES_PER_CHANNEL as u64 * 8);
+        params
+    }
+
+    fn packet(data: &[u8]) -> Packet {
+        Packet::new_from_slice(0, 0, data.len() as u64 / 2 * 8, data)
+    }
+
+    /// The `DoP` words, marker and DSD bytes, of each channel of the decoded frames
+    #[allow(clippy::cast_possible_truncation)]
+    fn dop_words(decoded: AudioBufferRef<'_>) -> Vec<Vec<u32>> {
+        let AudioBufferRef::F32(buf) = decoded else {
+            panic!("expected f32 samples");
+        };
+
+        (0..buf.spec().channels.count())
+            .map(|c| {
+                buf.chan(c)
+                    .iter()
+                    .map(|x| ((x * 8_388_608.0) as i32 & 0x00FF_FFFF) as u32)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dop_decoder_outputs_at_a_sixteenth_of_the_dsd_rate() {
+        let decoder = DopDecoder::try_new(&params(), &DecoderOptions::default()).unwrap();
+
+        assert_eq!(decoder.last_decoded().spec().rate, 176_400);
+    }
+
+    #[test]
+    fn dop_decoder_alternates_the_markers_across_packets() {
+        let mut decoder = DopDecoder::try_new(&params(), &DecoderOptions::default()).unwrap();
+
+        let decoded = decoder
+            .decode(&packet(&[0x01, 0x11, 0x02, 0x12, 0x03, 0x13]))
+            .unwrap();
+        assert_eq!(dop_words(decoded), vec![vec![0x05_0102], vec![0x05_1112]]);
+
+        let decoded = decoder
+            .decode(&packet(&[0x04, 0x14, 0x05, 0x15, 0x06, 0x16]))
+            .unwrap();
+        assert_eq!(
+            dop_words(decoded),
+            vec![vec![0xFA_0304, 0x05_0506], vec![0xFA_1314, 0x05_1516]]
+        );
+    }
+
+    #[test]
+    fn dop_decoder_reset_restarts_the_markers() {
+        let mut decoder = DopDecoder::try_new(&params(), &DecoderOptions::default()).unwrap();
+
+        decoder.decode(&packet(&[0x01, 0x11, 0x02, 0x12])).unwrap();
+        decoder.reset();
+
+        let decoded = decoder.decode(&packet(&[0x03, 0x13, 0x04, 0x14])).unwrap();
+        assert_eq!(dop_words(decoded), vec![vec![0x05_0304], vec![0x05_1314]]);
+    }
+
+    #[test]
+    fn dsd_decoder_decimates_dsd64_by_32() {
+        let decoder = DsdDecoder::try_new(&params(), &DecoderOptions::default()).unwrap();
+
+        assert_eq!(decoder.last_decoded().spec().rate, 88_200);
+    }
+
+    #[test]
+    fn dsd_decoder_decodes_full_modulation_and_silence() {
+        let mut decoder = DsdDecoder::try_new(&params(), &DecoderOptions::default()).unwrap();
+
+        let decoded = decoder
+            .decode(&packet(&[0xFF; PACKET_BYTES_PER_CHANNEL * 2]))
+            .unwrap();
+        let AudioBufferRef::F32(buf) = decoded else {
+            panic!("expected f32 samples");
+        };
+        assert_eq!(buf.frames(), PACKET_BYTES_PER_CHANNEL / 4);
+        assert!((buf.chan(0)[buf.frames() - 1] - 1.0).abs() < 1e-3);
+
+        let decoded = decoder
+            .decode(&packet(&[DSD_SILENCE; PACKET_BYTES_PER_CHANNEL * 2]))
+            .unwrap();
+        let AudioBufferRef::F32(buf) = decoded else {
+            panic!("expected f32 samples");
+        };
+        assert!(buf.chan(1)[buf.frames() - 1].abs() < 1e-2);
+    }
+}
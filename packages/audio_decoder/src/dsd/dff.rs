@@ -0,0 +1,338 @@
+//! DFF (DSDIFF) format reader.
+//!
+//! Only uncompressed DSD is supported, DST compressed files are rejected. The
+//! samples of the `DSD ` chunk are already channel-interleaved bytes with the
+//! oldest bit in the MSB, so they are handed out as is.
+
+#![allow(clippy::module_name_repetitions)]
+
+use std::io::{Seek as _, SeekFrom};
+
+use symphonia::core::{
+    codecs::CodecParameters,
+    errors::{
+        decode_error, end_of_stream_error, seek_error, unsupported_error, Result, SeekErrorKind,
+    },
+    formats::{Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track},
+    io::{MediaSourceStream, ReadBytes as _},
+    meta::{Metadata, MetadataLog},
+    probe::{Descriptor, Instantiate, QueryDescriptor},
+    units::TimeBase,
+};
+
+use super::{DsdProperties, CODEC_TYPE_DSD, PACKET_BYTES_PER_CHANNEL};
+
+pub struct DffReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    properties: DsdProperties,
+    data_start: u64,
+    data_size: u64,
+    /// The number of bytes of the `DSD ` chunk read so far
+    position: u64,
+}
+
+impl DffReader {
+    #[must_use]
+    pub const fn properties(&self) -> DsdProperties {
+        self.properties
+    }
+}
+
+/// Skips the rest of a chunk of `size` bytes, of which `read` have been read.
+/// Chunks are padded to an even size.
+fn skip_chunk(source: &mut MediaSourceStream, size: u64, read: u64) -> Result<()> {
+    source.ignore_bytes(size + (size & 1) - read)?;
+    Ok(())
+}
+
+/// Reads the sample rate and channel count from a `PROP` chunk of `size`
+/// bytes.
+fn read_prop_chunk(source: &mut MediaSourceStream, size: u64) -> Result<(u32, u8)> {
+    if source.read_quad_bytes()? != *b"SND " {
+        return decode_error("dff: invalid PROP chunk");
+    }
+
+    let mut read = 4;
+    let mut sample_rate = None;
+    let mut channel_count = None;
+
+    while read < size {
+        let id = source.read_quad_bytes()?;
+        let chunk_size = source.read_be_u64()?;
+
+        match &id {
+            b"FS  " => {
+                sample_rate = Some(source.read_be_u32()?);
+                skip_chunk(source, chunk_size, 4)?;
+            }
+            b"CHNL" => {
+                channel_count = Some(source.read_be_u16()?);
+                skip_chunk(source, chunk_size, 2)?;
+            }
+            b"CMPR" => {
+                if source.read_quad_bytes()? != *b"DSD " {
+                    return unsupported_error("dff: only uncompressed DSD is supported");
+                }
+                skip_chunk(source, chunk_size, 4)?;
+            }
+            _ => skip_chunk(source, chunk_size, 0)?,
+        }
+
+        read += 12 + chunk_size + (chunk_size & 1);
+    }
+
+    let (Some(sample_rate), Some(channel_count)) = (sample_rate, channel_count) else {
+        return decode_error("dff: missing FS or CHNL chunk");
+    };
+    let Ok(channel_count) = u8::try_from(channel_count) else {
+        return unsupported_error("dff: unsupported channel count");
+    };
+
+    Ok((sample_rate, channel_count))
+}
+
+impl QueryDescriptor for DffReader {
+    fn query() -> &'static [Descriptor] {
+        &[Descriptor {
+            short_name: "dff",
+            long_name: "DSD Interchange File Format",
+            extensions: &["dff"],
+            mime_types: &["audio/dff", "audio/x-dff"],
+            markers: &[b"FRM8"],
+            score: Self::score,
+            inst: Instantiate::Format(|source, options| {
+                Ok(Box::new(Self::try_new(source, options)?))
+            }),
+        }]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for DffReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        if source.read_quad_bytes()? != *b"FRM8" {
+            return unsupported_error("dff: missing FRM8 chunk");
+        }
+        let _form_size = source.read_be_u64()?;
+        if source.read_quad_bytes()? != *b"DSD " {
+            return unsupported_error("dff: not a DSD form");
+        }
+
+        let mut prop = None;
+
+        let (data_start, data_size) = loop {
+            let id = source.read_quad_bytes()?;
+            let size = source.read_be_u64()?;
+
+            match &id {
+                b"PROP" => {
+                    prop = Some(read_prop_chunk(&mut source, size)?);
+                    skip_chunk(&mut source, size, size)?;
+                }
+                b"DSD " => break (source.pos(), size),
+                b"DST " => return unsupported_error("dff: DST compression is not supported"),
+                _ => skip_chunk(&mut source, size, 0)?,
+            }
+        };
+
+        let Some((sample_rate, channel_count)) = prop else {
+            return decode_error("dff: missing PROP chunk");
+        };
+        if sample_rate == 0 || channel_count == 0 {
+            return decode_error("dff: invalid PROP chunk");
+        }
+
+        let frames = data_size / u64::from(channel_count) * 8;
+
+        let properties = DsdProperties {
+            sample_rate,
+            channels: channel_count,
+            frames,
+        };
+
+        let mut codec_params = CodecParameters::new();
+        codec_params
+            .for_codec(CODEC_TYPE_DSD)
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_n_frames(frames)
+            .with_channels(super::channels(channel_count)?)
+            .with_bits_per_sample(1)
+            .with_max_frames_per_packet(PACKET_BYTES_PER_CHANNEL as u64 * 8);
+
+        Ok(Self {
+            reader: source,
+            tracks: vec![Track::new(0, codec_params)],
+            cues: vec![],
+            metadata: MetadataLog::default(),
+            properties,
+            data_start,
+            data_size,
+            position: 0,
+        })
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        if !self.reader.is_seekable() {
+            return seek_error(SeekErrorKind::Unseekable);
+        }
+
+        let ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, self.properties.sample_rate).calc_timestamp(time)
+            }
+        };
+
+        if ts >= self.properties.frames {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
+        let position = ts / 8 * u64::from(self.properties.channels);
+
+        self.reader
+            .seek(SeekFrom::Start(self.data_start + position))?;
+        self.position = position;
+
+        Ok(SeekedTo {
+            track_id: 0,
+            required_ts: ts,
+            actual_ts: ts / 8 * 8,
+        })
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let channels = u64::from(self.properties.channels);
+        let remaining = self.data_size - self.position;
+        let size = remaining.min(PACKET_BYTES_PER_CHANNEL as u64 * channels) / channels * channels;
+
+        if size == 0 {
+            return end_of_stream_error();
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let data = self.reader.read_boxed_slice_exact(size as usize)?;
+
+        let ts = self.position / channels * 8;
+        let dur = size / channels * 8;
+
+        self.position += size;
+
+        Ok(Packet::new_from_boxed_slice(0, ts, dur, data))
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use symphonia::core::{errors::Error, io::MediaSourceStreamOptions};
+
+    use super::*;
+
+    fn chunk(bytes: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+        bytes.extend_from_slice(id);
+        bytes.extend_from_slice(&(data.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            bytes.push(0);
+        }
+    }
+
+    fn dff(compression: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut prop = b"SND ".to_vec();
+        chunk(&mut prop, b"FS  ", &2_822_400u32.to_be_bytes());
+        chunk(
+            &mut prop,
+            b"CHNL",
+            &[0x00, 0x02, b'S', b'L', b'F', b'T', b'S', b'R', b'G', b'T'],
+        );
+        let mut cmpr = compression.to_vec();
+        cmpr.push(14);
+        cmpr.extend_from_slice(b"not compressed");
+        chunk(&mut prop, b"CMPR", &cmpr);
+
+        let mut form = b"DSD ".to_vec();
+        chunk(&mut form, b"FVER", &0x0105_0000u32.to_be_bytes());
+        chunk(&mut form, b"PROP", &prop);
+        chunk(&mut form, b"DSD ", data);
+
+        let mut bytes = vec![];
+        chunk(&mut bytes, b"FRM8", &form);
+
+        bytes
+    }
+
+    fn reader(bytes: Vec<u8>) -> Result<DffReader> {
+        let source = MediaSourceStream::new(
+            Box::new(Cursor::new(bytes)),
+            MediaSourceStreamOptions::default(),
+        );
+
+        DffReader::try_new(source, &FormatOptions::default())
+    }
+
+    #[test]
+    fn reads_the_prop_chunk() {
+        let Ok(reader) = reader(dff(b"DSD ", &[0; 10])) else {
+            panic!("failed to read the DFF headers");
+        };
+
+        assert_eq!(
+            reader.properties(),
+            DsdProperties {
+                sample_rate: 2_822_400,
+                channels: 2,
+                frames: 40,
+            }
+        );
+        assert_eq!(reader.tracks()[0].codec_params.n_frames, Some(40));
+    }
+
+    #[test]
+    fn hands_out_the_interleaved_samples_as_is() {
+        let data = [0x01, 0x11, 0x02, 0x12, 0x03, 0x13, 0x04, 0x14, 0x05, 0x15];
+        let Ok(mut reader) = reader(dff(b"DSD ", &data)) else {
+            panic!("failed to read the DFF headers");
+        };
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts(), packet.dur()), (0, 40));
+        assert_eq!(packet.buf(), &data);
+
+        assert!(matches!(
+            reader.next_packet(),
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn rejects_dst_compression() {
+        assert!(matches!(
+            reader(dff(b"DST ", &[0; 10])),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
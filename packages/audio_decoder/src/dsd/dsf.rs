@@ -0,0 +1,352 @@
+//! DSF (DSD Stream File) format reader.
+//!
+//! The samples are stored in blocks of `block_size` bytes per channel, with
+//! the blocks of every channel following each other, and the last block
+//! padded out to the full size.
+
+#![allow(clippy::module_name_repetitions)]
+
+use std::io::{Seek as _, SeekFrom};
+
+use symphonia::core::{
+    codecs::CodecParameters,
+    errors::{
+        decode_error, end_of_stream_error, seek_error, unsupported_error, Result, SeekErrorKind,
+    },
+    formats::{Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track},
+    io::{MediaSourceStream, ReadBytes as _},
+    meta::{Metadata, MetadataLog},
+    probe::{Descriptor, Instantiate, QueryDescriptor},
+    units::TimeBase,
+};
+
+use super::{DsdProperties, CODEC_TYPE_DSD};
+
+pub struct DsfReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    properties: DsdProperties,
+    lsb_first: bool,
+    block_size: usize,
+    data_start: u64,
+    /// The index of the next block
+    block: u64,
+}
+
+impl DsfReader {
+    #[must_use]
+    pub const fn properties(&self) -> DsdProperties {
+        self.properties
+    }
+
+    const fn block_frames(&self) -> u64 {
+        self.block_size as u64 * 8
+    }
+}
+
+impl QueryDescriptor for DsfReader {
+    fn query() -> &'static [Descriptor] {
+        &[Descriptor {
+            short_name: "dsf",
+            long_name: "DSD Stream File",
+            extensions: &["dsf"],
+            mime_types: &["audio/dsf", "audio/x-dsf"],
+            markers: &[b"DSD "],
+            score: Self::score,
+            inst: Instantiate::Format(|source, options| {
+                Ok(Box::new(Self::try_new(source, options)?))
+            }),
+        }]
+    }
+
+    fn score(_context: &[u8]) -> u8 {
+        255
+    }
+}
+
+impl FormatReader for DsfReader {
+    fn try_new(mut source: MediaSourceStream, _options: &FormatOptions) -> Result<Self> {
+        if source.read_quad_bytes()? != *b"DSD " {
+            return unsupported_error("dsf: missing DSD chunk");
+        }
+        let dsd_size = source.read_u64()?;
+        source.ignore_bytes(dsd_size.saturating_sub(12))?;
+
+        if source.read_quad_bytes()? != *b"fmt " {
+            return decode_error("dsf: missing fmt chunk");
+        }
+        let fmt_size = source.read_u64()?;
+        let _version = source.read_u32()?;
+        if source.read_u32()? != 0 {
+            return unsupported_error("dsf: only raw DSD is supported");
+        }
+        let _channel_type = source.read_u32()?;
+        let channel_count = source.read_u32()?;
+        let sample_rate = source.read_u32()?;
+        let bits_per_sample = source.read_u32()?;
+        let frames = source.read_u64()?;
+        let block_size = source.read_u32()?;
+        source.ignore_bytes(fmt_size.saturating_sub(48))?;
+
+        let lsb_first = match bits_per_sample {
+            1 => true,
+            8 => false,
+            _ => return decode_error("dsf: invalid bits per sample"),
+        };
+        let Ok(channel_count) = u8::try_from(channel_count) else {
+            return unsupported_error("dsf: unsupported channel count");
+        };
+        if block_size == 0 || sample_rate == 0 {
+            return decode_error("dsf: invalid fmt chunk");
+        }
+
+        if source.read_quad_bytes()? != *b"data" {
+            return decode_error("dsf: missing data chunk");
+        }
+        let _data_size = source.read_u64()?;
+        let data_start = source.pos();
+
+        let properties = DsdProperties {
+            sample_rate,
+            channels: channel_count,
+            frames,
+        };
+
+        let mut codec_params = CodecParameters::new();
+        codec_params
+            .for_codec(CODEC_TYPE_DSD)
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_n_frames(frames)
+            .with_channels(super::channels(channel_count)?)
+            .with_bits_per_sample(1)
+            .with_max_frames_per_packet(u64::from(block_size) * 8);
+
+        Ok(Self {
+            reader: source,
+            tracks: vec![Track::new(0, codec_params)],
+            cues: vec![],
+            metadata: MetadataLog::default(),
+            properties,
+            lsb_first,
+            block_size: block_size as usize,
+            data_start,
+            block: 0,
+        })
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> Result<SeekedTo> {
+        if !self.reader.is_seekable() {
+            return seek_error(SeekErrorKind::Unseekable);
+        }
+
+        let ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => {
+                TimeBase::new(1, self.properties.sample_rate).calc_timestamp(time)
+            }
+        };
+
+        if ts >= self.properties.frames {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+
+        let block = ts / self.block_frames();
+        let group_size = (self.block_size * usize::from(self.properties.channels)) as u64;
+
+        self.reader
+            .seek(SeekFrom::Start(self.data_start + block * group_size))?;
+        self.block = block;
+
+        Ok(SeekedTo {
+            track_id: 0,
+            required_ts: ts,
+            actual_ts: block * self.block_frames(),
+        })
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn next_packet(&mut self) -> Result<Packet> {
+        let ts = self.block * self.block_frames();
+
+        if ts >= self.properties.frames {
+            return end_of_stream_error();
+        }
+
+        let channels = usize::from(self.properties.channels);
+        let group = self
+            .reader
+            .read_boxed_slice_exact(self.block_size * channels)?;
+
+        let dur = (self.properties.frames - ts).min(self.block_frames());
+        #[allow(clippy::cast_possible_truncation)]
+        let bytes = dur.div_ceil(8) as usize;
+
+        let mut data = Vec::with_capacity(bytes * channels);
+
+        for i in 0..bytes {
+            for c in 0..channels {
+                let byte = group[c * self.block_size + i];
+                data.push(if self.lsb_first {
+                    byte.reverse_bits()
+                } else {
+                    byte
+                });
+            }
+        }
+
+        self.block += 1;
+
+        Ok(Packet::new_from_boxed_slice(
+            0,
+            ts,
+            dur,
+            data.into_boxed_slice(),
+        ))
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use symphonia::core::{errors::Error, io::MediaSourceStreamOptions};
+
+    use super::*;
+
+    fn dsf(bits_per_sample: u32, block_size: u32, frames: u64, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        bytes.extend_from_slice(b"DSD ");
+        bytes.extend_from_slice(&28u64.to_le_bytes());
+        bytes.extend_from_slice(&(28 + 52 + 12 + data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&52u64.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&2_822_400u32.to_le_bytes());
+        bytes.extend_from_slice(&bits_per_sample.to_le_bytes());
+        bytes.extend_from_slice(&frames.to_le_bytes());
+        bytes.extend_from_slice(&block_size.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(12 + data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(data);
+
+        bytes
+    }
+
+    fn reader(bytes: Vec<u8>) -> Result<DsfReader> {
+        let source = MediaSourceStream::new(
+            Box::new(Cursor::new(bytes)),
+            MediaSourceStreamOptions::default(),
+        );
+
+        DsfReader::try_new(source, &FormatOptions::default())
+    }
+
+    #[test]
+    fn reads_the_fmt_chunk() {
+        let Ok(reader) = reader(dsf(8, 4, 48, &[0; 16])) else {
+            panic!("failed to read the DSF headers");
+        };
+
+        assert_eq!(
+            reader.properties(),
+            DsdProperties {
+                sample_rate: 2_822_400,
+                channels: 2,
+                frames: 48,
+            }
+        );
+
+        let params = &reader.tracks()[0].codec_params;
+        assert_eq!(params.codec, CODEC_TYPE_DSD);
+        assert_eq!(params.n_frames, Some(48));
+        assert_eq!(params.max_frames_per_packet, Some(32));
+    }
+
+    #[test]
+    fn interleaves_the_channel_blocks() {
+        let data = [
+            0x01, 0x02, 0x03, 0x04, 0x11, 0x12, 0x13, 0x14, 0x05, 0x06, 0x00, 0x00, 0x15, 0x16,
+            0x00, 0x00,
+        ];
+        let Ok(mut reader) = reader(dsf(8, 4, 48, &data)) else {
+            panic!("failed to read the DSF headers");
+        };
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts(), packet.dur()), (0, 32));
+        assert_eq!(
+            packet.buf(),
+            &[0x01, 0x11, 0x02, 0x12, 0x03, 0x13, 0x04, 0x14]
+        );
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!((packet.ts(), packet.dur()), (32, 16));
+        assert_eq!(packet.buf(), &[0x05, 0x15, 0x06, 0x16]);
+
+        assert!(matches!(
+            reader.next_packet(),
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn reverses_lsb_first_bytes() {
+        let data = [0x01, 0x03, 0x00, 0x00, 0x80, 0x0F, 0x00, 0x00];
+        let Ok(mut reader) = reader(dsf(1, 4, 16, &data)) else {
+            panic!("failed to read the DSF headers");
+        };
+
+        let packet = reader.next_packet().unwrap();
+        assert_eq!(packet.buf(), &[0x80, 0x01, 0xC0, 0xF0]);
+    }
+
+    #[test]
+    fn rejects_an_invalid_bits_per_sample() {
+        assert!(matches!(
+            reader(dsf(4, 4, 48, &[0; 16])),
+            Err(Error::DecodeError(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_a_missing_data_chunk() {
+        let mut bytes = dsf(8, 4, 48, &[0; 16]);
+        bytes[80..84].copy_from_slice(b"junk");
+
+        assert!(matches!(reader(bytes), Err(Error::DecodeError(_))));
+    }
+
+    #[test]
+    fn rejects_a_missing_dsd_chunk() {
+        let mut bytes = dsf(8, 4, 48, &[0; 16]);
+        bytes[0..4].copy_from_slice(b"RIFF");
+
+        assert!(matches!(reader(bytes), Err(Error::Unsupported(_))));
+    }
+}
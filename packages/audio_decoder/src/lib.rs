@@ -5,22 +5,81 @@
 
 use std::fs::File;
 use std::path::Path;
+use std::sync::LazyLock;
 
-use symphonia::core::audio::{AudioBuffer, SignalSpec};
-use symphonia::core::codecs::{DecoderOptions, FinalizeResult, CODEC_TYPE_NULL};
+use symphonia::core::audio::{AudioBuffer, Signal as _, SignalSpec};
+use symphonia::core::codecs::{
+    CodecRegistry, Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_NULL,
+};
 use symphonia::core::errors::Error;
 use symphonia::core::formats::{FormatOptions, FormatReader, Packet, SeekMode, SeekTo, Track};
 use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
 use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
+use symphonia::core::probe::{Hint, Probe};
 use symphonia::core::units::{Duration, Time};
 use thiserror::Error;
 use tokio::task::JoinError;
 use tokio_util::sync::CancellationToken;
 
+#[cfg(feature = "dsd")]
+pub mod dsd;
 pub mod media_sources;
 pub mod unsync;
 
+static CODECS: LazyLock<CodecRegistry> = LazyLock::new(|| {
+    let mut registry = CodecRegistry::new();
+    symphonia::default::register_enabled_codecs(&mut registry);
+    #[cfg(feature = "dsd")]
+    registry.register_all::<dsd::DsdDecoder>();
+    registry
+});
+
+static PROBE: LazyLock<Probe> = LazyLock::new(|| {
+    let mut probe = Probe::default();
+    symphonia::default::register_enabled_formats(&mut probe);
+    #[cfg(feature = "dsd")]
+    {
+        probe.register_all::<dsd::DsfReader>();
+        probe.register_all::<dsd::DffReader>();
+    }
+    probe
+});
+
+/// The symphonia codecs along with the ones added by this crate
+#[must_use]
+pub fn get_codecs() -> &'static CodecRegistry {
+    &CODECS
+}
+
+/// The symphonia format readers along with the ones added by this crate
+#[must_use]
+pub fn get_probe() -> &'static Probe {
+    &PROBE
+}
+
+/// Makes the decoder for the `track`. DSD is packed into `DoP` frames instead
+/// of being decoded to PCM when `dop` is set.
+///
+/// # Errors
+///
+/// * If there is no decoder for the codec of the `track`
+#[allow(unused_variables)]
+pub fn make_decoder(
+    track: &Track,
+    decode_opts: &DecoderOptions,
+    dop: bool,
+) -> Result<Box<dyn Decoder>, Error> {
+    #[cfg(feature = "dsd")]
+    if dop && track.codec_params.codec == dsd::CODEC_TYPE_DSD {
+        return Ok(Box::new(dsd::DopDecoder::try_new(
+            &track.codec_params,
+            decode_opts,
+        )?));
+    }
+
+    get_codecs().make(&track.codec_params, decode_opts)
+}
+
 #[allow(dead_code)]
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
@@ -64,6 +123,8 @@ pub struct AudioDecodeHandler {
     filters: Vec<AudioFilter>,
     open_decode_handlers: Vec<OpenAudioDecodeHandler>,
     outputs: Vec<InnerType>,
+    end_time: Option<f64>,
+    dop: bool,
 }
 
 #[cfg_attr(feature = "profiling", profiling::all_functions)]
@@ -75,6 +136,8 @@ impl AudioDecodeHandler {
             filters: vec![],
             open_decode_handlers: vec![],
             outputs: vec![],
+            end_time: None,
+            dop: false,
         }
     }
 
@@ -96,6 +159,60 @@ impl AudioDecodeHandler {
         self
     }
 
+    /// Stops the decode once the stream reaches `end_time` seconds, dropping any
+    /// audio past it.
+    #[must_use]
+    pub const fn with_end_time(mut self, end_time: f64) -> Self {
+        self.end_time = Some(end_time);
+        self
+    }
+
+    /// Passes DSD through to the outputs as `DoP` frames instead of decoding it
+    /// to PCM.
+    #[must_use]
+    pub const fn with_dop(mut self, dop: bool) -> Self {
+        self.dop = dop;
+        self
+    }
+
+    /// Drops the frames of `decoded` past the end time.
+    ///
+    /// # Errors
+    ///
+    /// * `AudioDecodeError::StreamEnd` if the packet starts at or after the end time
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn apply_end_time(
+        &self,
+        decoded: &mut AudioBuffer<f32>,
+        packet: &Packet,
+        track: &Track,
+    ) -> Result<(), AudioDecodeError> {
+        let (Some(end_time), Some(time_base)) = (self.end_time, track.codec_params.time_base)
+        else {
+            return Ok(());
+        };
+
+        let time = time_base.calc_time(packet.ts());
+        let start = time.seconds as f64 + time.frac;
+
+        if start >= end_time {
+            log::debug!("Reached end time {end_time}");
+            return Err(AudioDecodeError::StreamEnd);
+        }
+
+        let frames = ((end_time - start) * f64::from(decoded.spec().rate)).ceil() as usize;
+
+        if frames < decoded.frames() {
+            decoded.truncate(frames);
+        }
+
+        Ok(())
+    }
+
     fn run_filters(
         &mut self,
         decoded: &mut AudioBuffer<f32>,
@@ -118,6 +235,7 @@ impl AudioDecodeHandler {
         packet: &Packet,
         track: &Track,
     ) -> Result<(), AudioDecodeError> {
+        self.apply_end_time(&mut decoded, packet, track)?;
         self.run_filters(&mut decoded, packet, track)?;
 
         let len = self.outputs.len();
@@ -311,12 +429,7 @@ fn decode_media_source(
     let metadata_opts = MetadataOptions::default();
 
     // Probe the media source stream for metadata and get the format reader.
-    match symphonia::default::get_probe().format(
-        hint,
-        media_source_stream,
-        &format_opts,
-        &metadata_opts,
-    ) {
+    match get_probe().format(hint, media_source_stream, &format_opts, &metadata_opts) {
         Ok(probed) => {
             // If present, parse the seek argument.
             let seek_time = seek;
@@ -463,7 +576,7 @@ fn play_track(
     .clone();
 
     // Create a decoder for the track.
-    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &decode_opts)?;
+    let mut decoder = make_decoder(&track, &decode_opts, audio_output_handler.dop)?;
 
     // Decode and play the packets belonging to the selected track.
     let result = loop {
@@ -91,7 +91,7 @@ fn decode_track(
         .clone();
 
     // Create a decoder for the track.
-    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &decode_opts)?;
+    let mut decoder = crate::get_codecs().make(&track.codec_params, &decode_opts)?;
 
     log::trace!("Spawning decoder loop");
 
@@ -3,12 +3,14 @@
 use fdk_aac::enc::{BitRate, ChannelMode, Encoder, EncoderParams, Transport};
 use thiserror::Error;
 
-use crate::EncodeInfo;
+use crate::{EncodeInfo, EncoderSettings};
 
 #[derive(Debug, Error)]
 pub enum EncoderError {
     #[error("Encoder error")]
     Encoder(fdk_aac::enc::EncoderError),
+    #[error("Invalid encoder settings: {0:?}")]
+    InvalidSettings(EncoderSettings),
 }
 
 impl From<fdk_aac::enc::EncodeInfo> for EncodeInfo {
@@ -30,12 +32,37 @@ impl From<fdk_aac::enc::EncoderError> for EncoderError {
 ///
 /// * If the encoder fails to initialize
 pub fn encoder_aac() -> Result<Encoder, EncoderError> {
+    encoder_aac_with_settings(&EncoderSettings::default())
+}
+
+/// # Errors
+///
+/// * If the `settings` bitrate overflows when converted to bits per second
+/// * If the encoder fails to initialize
+pub fn encoder_aac_with_settings(settings: &EncoderSettings) -> Result<Encoder, EncoderError> {
+    let bit_rate = match (settings.bitrate, settings.vbr_quality) {
+        (Some(bitrate), _) => BitRate::Cbr(
+            bitrate
+                .checked_mul(1000)
+                .ok_or(EncoderError::InvalidSettings(*settings))?,
+        ),
+        (None, None | Some(0..=1)) => BitRate::VbrVeryHigh,
+        (None, Some(2..=3)) => BitRate::VbrHigh,
+        (None, Some(4..=5)) => BitRate::VbrMedium,
+        (None, Some(6..=7)) => BitRate::VbrLow,
+        (None, Some(_)) => BitRate::VbrVeryLow,
+    };
+
     let encoder = Encoder::new(EncoderParams {
         audio_object_type: fdk_aac::enc::AudioObjectType::Mpeg4LowComplexity,
-        bit_rate: BitRate::VbrVeryHigh,
-        sample_rate: 44_100,
+        bit_rate,
+        sample_rate: settings.sample_rate.unwrap_or(44_100),
         transport: Transport::Adts,
-        channels: ChannelMode::Stereo,
+        channels: if settings.channels == Some(1) {
+            ChannelMode::Mono
+        } else {
+            ChannelMode::Stereo
+        },
     })?;
     Ok(encoder)
 }
@@ -52,3 +79,20 @@ pub fn encode_aac(
 
     Ok(info.into())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::EncoderSettings;
+
+    use super::{encoder_aac_with_settings, EncoderError};
+
+    #[test]
+    fn encoder_aac_with_settings_rejects_overflowing_bitrate() {
+        let result = encoder_aac_with_settings(&EncoderSettings {
+            bitrate: Some(u32::MAX),
+            ..Default::default()
+        });
+
+        assert!(matches!(result, Err(EncoderError::InvalidSettings(_))));
+    }
+}
@@ -0,0 +1,431 @@
+//! ALAC in fragmented MP4.
+//!
+//! The stream starts with an initialization segment (`ftyp` and a `moov` with
+//! no samples), followed by a `moof`/`mdat` fragment for every batch of
+//! encoded packets, so that it can be written without knowing its length.
+
+#![allow(clippy::module_name_repetitions)]
+
+use alac_encoder::{AlacEncoder, FormatDescription, MAX_ESCAPE_HEADER_BYTES};
+
+use crate::EncoderSettings;
+
+/// The number of frames in each ALAC packet
+pub const FRAMES_PER_PACKET: u32 = 4096;
+
+const TRACK_ID: u32 = 1;
+
+pub struct Encoder {
+    encoder: AlacEncoder,
+    input_format: FormatDescription,
+    channels: u32,
+    sample_rate: u32,
+    pending: Vec<i16>,
+    sequence: u32,
+    decode_time: u64,
+}
+
+#[must_use]
+pub fn encoder_alac() -> Encoder {
+    encoder_alac_with_settings(&EncoderSettings::default())
+}
+
+/// ALAC is lossless, so only the sample rate and channel count of the
+/// `settings` are used.
+#[must_use]
+pub fn encoder_alac_with_settings(settings: &EncoderSettings) -> Encoder {
+    let channels = u32::from(settings.channels.unwrap_or(2));
+    let sample_rate = settings.sample_rate.unwrap_or(44_100);
+
+    Encoder {
+        encoder: AlacEncoder::new(&FormatDescription::alac(
+            f64::from(sample_rate),
+            FRAMES_PER_PACKET,
+            channels,
+        )),
+        input_format: FormatDescription::pcm::<i16>(f64::from(sample_rate), channels),
+        channels,
+        sample_rate,
+        pending: vec![],
+        sequence: 0,
+        decode_time: 0,
+    }
+}
+
+/// The initialization segment that the stream starts with
+#[must_use]
+pub fn alac_init_segment(encoder: &Encoder) -> Vec<u8> {
+    let ftyp = mp4_box(
+        b"ftyp",
+        &[&b"M4A "[..], &0_u32.to_be_bytes(), b"M4A isomiso5mp42"].concat(),
+    );
+
+    let mvhd = full_box(
+        b"mvhd",
+        0,
+        0,
+        &[
+            &0_u64.to_be_bytes()[..],
+            &encoder.sample_rate.to_be_bytes(),
+            &0_u32.to_be_bytes(),
+            &0x0001_0000_u32.to_be_bytes(),
+            &0x0100_u16.to_be_bytes(),
+            &[0; 10],
+            &MATRIX,
+            &[0; 24],
+            &(TRACK_ID + 1).to_be_bytes(),
+        ]
+        .concat(),
+    );
+
+    let tkhd = full_box(
+        b"tkhd",
+        0,
+        0x3,
+        &[
+            &0_u64.to_be_bytes()[..],
+            &TRACK_ID.to_be_bytes(),
+            &[0; 4],
+            &0_u32.to_be_bytes(),
+            &[0; 8],
+            &0_u32.to_be_bytes(),
+            &0x0100_u16.to_be_bytes(),
+            &[0; 2],
+            &MATRIX,
+            &0_u64.to_be_bytes(),
+        ]
+        .concat(),
+    );
+
+    let mdhd = full_box(
+        b"mdhd",
+        0,
+        0,
+        &[
+            &0_u64.to_be_bytes()[..],
+            &encoder.sample_rate.to_be_bytes(),
+            &0_u32.to_be_bytes(),
+            // 'und'
+            &0x55c4_u16.to_be_bytes(),
+            &[0; 2],
+        ]
+        .concat(),
+    );
+
+    let hdlr = full_box(
+        b"hdlr",
+        0,
+        0,
+        &[&[0; 4][..], b"soun", &[0; 12], b"SoundHandler\0"].concat(),
+    );
+
+    let dinf = mp4_box(
+        b"dinf",
+        &full_box(
+            b"dref",
+            0,
+            0,
+            &[&1_u32.to_be_bytes()[..], &full_box(b"url ", 0, 1, &[])].concat(),
+        ),
+    );
+
+    let stbl = mp4_box(
+        b"stbl",
+        &[
+            full_box(
+                b"stsd",
+                0,
+                0,
+                &[&1_u32.to_be_bytes()[..], &sample_entry(encoder)].concat(),
+            ),
+            full_box(b"stts", 0, 0, &0_u32.to_be_bytes()),
+            full_box(b"stsc", 0, 0, &0_u32.to_be_bytes()),
+            full_box(b"stsz", 0, 0, &0_u64.to_be_bytes()),
+            full_box(b"stco", 0, 0, &0_u32.to_be_bytes()),
+        ]
+        .concat(),
+    );
+
+    let minf = mp4_box(
+        b"minf",
+        &[full_box(b"smhd", 0, 0, &[0; 4]), dinf, stbl].concat(),
+    );
+    let mdia = mp4_box(b"mdia", &[mdhd, hdlr, minf].concat());
+    let trak = mp4_box(b"trak", &[tkhd, mdia].concat());
+
+    let trex = full_box(
+        b"trex",
+        0,
+        0,
+        &[
+            &TRACK_ID.to_be_bytes()[..],
+            &1_u32.to_be_bytes(),
+            &0_u32.to_be_bytes(),
+            &0_u32.to_be_bytes(),
+            &0_u32.to_be_bytes(),
+        ]
+        .concat(),
+    );
+    let mvex = mp4_box(b"mvex", &trex);
+
+    let moov = mp4_box(b"moov", &[mvhd, trak, mvex].concat());
+
+    [ftyp, moov].concat()
+}
+
+/// Encodes interleaved samples, returning a fragment with every packet that
+/// was completed. Samples that don't fill a packet are held until the next
+/// call.
+#[must_use]
+pub fn encode_alac(encoder: &mut Encoder, input: &[i16]) -> Vec<u8> {
+    encoder.pending.extend_from_slice(input);
+
+    let packet_len = FRAMES_PER_PACKET as usize * encoder.channels as usize;
+    let complete = encoder.pending.len() / packet_len * packet_len;
+    let samples = encoder.pending.drain(..complete).collect::<Vec<_>>();
+
+    let packets = samples
+        .chunks(packet_len)
+        .map(|packet| encode_packet(encoder, packet))
+        .collect::<Vec<_>>();
+
+    fragment(encoder, &packets)
+}
+
+/// Encodes the samples held back by `encode_alac` as the last, shorter,
+/// packet of the stream.
+#[must_use]
+pub fn finish_alac(encoder: &mut Encoder) -> Vec<u8> {
+    if encoder.pending.is_empty() {
+        return vec![];
+    }
+
+    let samples = std::mem::take(&mut encoder.pending);
+    let packet = encode_packet(encoder, &samples);
+
+    fragment(encoder, &[packet])
+}
+
+/// Encodes a packet, returning the number of frames and the encoded bytes
+fn encode_packet(encoder: &mut Encoder, samples: &[i16]) -> (u32, Vec<u8>) {
+    let input = samples
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>();
+    let mut output = vec![0_u8; input.len() + MAX_ESCAPE_HEADER_BYTES];
+
+    let size = encoder
+        .encoder
+        .encode(&encoder.input_format, &input, &mut output);
+    output.truncate(size);
+
+    #[allow(clippy::cast_possible_truncation)]
+    let frames = (samples.len() / encoder.channels as usize) as u32;
+
+    (frames, output)
+}
+
+fn fragment(encoder: &mut Encoder, packets: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    if packets.is_empty() {
+        return vec![];
+    }
+
+    encoder.sequence += 1;
+
+    let moof = |data_offset: u32| {
+        let mfhd = full_box(b"mfhd", 0, 0, &encoder.sequence.to_be_bytes());
+        // default-base-is-moof
+        let tfhd = full_box(b"tfhd", 0, 0x02_0000, &TRACK_ID.to_be_bytes());
+        let tfdt = full_box(b"tfdt", 1, 0, &encoder.decode_time.to_be_bytes());
+
+        #[allow(clippy::cast_possible_truncation)]
+        let mut trun = [
+            (packets.len() as u32).to_be_bytes(),
+            data_offset.to_be_bytes(),
+        ]
+        .concat();
+        for (frames, packet) in packets {
+            trun.extend_from_slice(&frames.to_be_bytes());
+            #[allow(clippy::cast_possible_truncation)]
+            trun.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+        }
+        // data-offset, sample-duration and sample-size present
+        let trun = full_box(b"trun", 0, 0x00_0301, &trun);
+
+        mp4_box(
+            b"moof",
+            &[mfhd, mp4_box(b"traf", &[tfhd, tfdt, trun].concat())].concat(),
+        )
+    };
+
+    // The data offset is relative to the start of the moof, and points past
+    // the mdat header
+    #[allow(clippy::cast_possible_truncation)]
+    let data_offset = moof(0).len() as u32 + 8;
+    let moof = moof(data_offset);
+
+    let data = packets
+        .iter()
+        .flat_map(|(_, packet)| packet.iter().copied())
+        .collect::<Vec<_>>();
+
+    encoder.decode_time += packets
+        .iter()
+        .map(|(frames, _)| u64::from(*frames))
+        .sum::<u64>();
+
+    [moof, mp4_box(b"mdat", &data)].concat()
+}
+
+/// The `alac` audio sample entry, holding the encoder's magic cookie
+fn sample_entry(encoder: &Encoder) -> Vec<u8> {
+    #[allow(clippy::cast_possible_truncation)]
+    let channels = encoder.channels as u16;
+    // 16.16 fixed point. Rates above 65535 don't fit, so they're written as 0
+    // and decoders take the rate from the magic cookie instead
+    let sample_rate = u16::try_from(encoder.sample_rate).map_or(0, |rate| u32::from(rate) << 16);
+
+    mp4_box(
+        b"alac",
+        &[
+            &[0; 6][..],
+            &1_u16.to_be_bytes(),
+            &[0; 8],
+            &channels.to_be_bytes(),
+            &16_u16.to_be_bytes(),
+            &[0; 4],
+            &sample_rate.to_be_bytes(),
+            &full_box(b"alac", 0, 0, &encoder.encoder.magic_cookie()),
+        ]
+        .concat(),
+    )
+}
+
+const MATRIX: [u8; 36] = [
+    0x00, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, //
+    0, 0, 0, 0, 0x00, 0x01, 0x00, 0x00, 0, 0, 0, 0, //
+    0, 0, 0, 0, 0, 0, 0, 0, 0x40, 0x00, 0x00, 0x00,
+];
+
+#[allow(clippy::cast_possible_truncation)]
+fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    [
+        &((payload.len() + 8) as u32).to_be_bytes()[..],
+        kind,
+        payload,
+    ]
+    .concat()
+}
+
+fn full_box(kind: &[u8; 4], version: u8, flags: u32, payload: &[u8]) -> Vec<u8> {
+    let flags = flags.to_be_bytes();
+    mp4_box(
+        kind,
+        &[&[version, flags[1], flags[2], flags[3]][..], payload].concat(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EncoderSettings;
+
+    use super::{
+        alac_init_segment, encode_alac, encoder_alac_with_settings, finish_alac, full_box, mp4_box,
+        FRAMES_PER_PACKET,
+    };
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// The first box of `kind` in `data`, including its header
+    fn find_box<'a>(data: &'a [u8], kind: &[u8; 4]) -> &'a [u8] {
+        let start = data.windows(4).position(|x| x == kind).unwrap() - 4;
+        let size = u32_at(data, start) as usize;
+        &data[start..start + size]
+    }
+
+    #[test]
+    fn mp4_box_prefixes_the_size_and_kind() {
+        assert_eq!(mp4_box(b"free", b"ab"), b"\0\0\0\x0afreeab");
+        assert_eq!(full_box(b"url ", 0, 1, &[]), b"\0\0\0\x0curl \0\0\0\x01");
+        assert_eq!(
+            full_box(b"tfhd", 0, 0x02_0000, &[0xff]),
+            b"\0\0\0\x0dtfhd\0\x02\0\0\xff"
+        );
+    }
+
+    #[test]
+    fn alac_init_segment_describes_the_track() {
+        let encoder = encoder_alac_with_settings(&EncoderSettings {
+            sample_rate: Some(48_000),
+            channels: Some(1),
+            ..Default::default()
+        });
+
+        let segment = alac_init_segment(&encoder);
+
+        assert_eq!(u32_at(&segment, 0), 32);
+        assert_eq!(&segment[4..12], b"ftypM4A ");
+        assert_eq!(&segment[36..40], b"moov");
+        assert_eq!(u32_at(&segment, 32) as usize, segment.len() - 32);
+
+        let mdhd = find_box(&segment, b"mdhd");
+        assert_eq!(u32_at(mdhd, 20), 48_000);
+
+        let sample_entry = find_box(&segment, b"alac");
+        assert_eq!(&sample_entry[24..26], &1_u16.to_be_bytes());
+        assert_eq!(&sample_entry[26..28], &16_u16.to_be_bytes());
+        assert_eq!(u32_at(sample_entry, 32), 48_000 << 16);
+        assert_eq!(&sample_entry[40..44], b"alac");
+    }
+
+    #[test]
+    fn alac_sample_entry_zeroes_rates_that_dont_fit_16_16() {
+        let encoder = encoder_alac_with_settings(&EncoderSettings {
+            sample_rate: Some(96_000),
+            ..Default::default()
+        });
+
+        let segment = alac_init_segment(&encoder);
+
+        assert_eq!(u32_at(find_box(&segment, b"mdhd"), 20), 96_000);
+        assert_eq!(u32_at(find_box(&segment, b"alac"), 32), 0);
+    }
+
+    #[test]
+    fn alac_fragments_point_at_their_samples() {
+        let mut encoder = encoder_alac_with_settings(&EncoderSettings::default());
+        let packet = vec![0_i16; FRAMES_PER_PACKET as usize * 2];
+
+        assert!(encode_alac(&mut encoder, &packet[..20]).is_empty());
+        let first = encode_alac(&mut encoder, &packet);
+        let last = finish_alac(&mut encoder);
+
+        assert!(finish_alac(&mut encoder).is_empty());
+
+        for (fragment, sequence, decode_time, frames) in [
+            (&first, 1, 0, FRAMES_PER_PACKET),
+            (&last, 2, u64::from(FRAMES_PER_PACKET), 10),
+        ] {
+            let moof_len = u32_at(fragment, 0) as usize;
+            assert_eq!(&fragment[4..8], b"moof");
+            assert_eq!(&fragment[moof_len + 4..moof_len + 8], b"mdat");
+            let mdat_len = u32_at(fragment, moof_len) as usize;
+            assert_eq!(moof_len + mdat_len, fragment.len());
+
+            assert_eq!(u32_at(find_box(fragment, b"mfhd"), 12), sequence);
+
+            let tfdt = find_box(fragment, b"tfdt");
+            assert_eq!(tfdt[8], 1);
+            assert_eq!(&tfdt[12..20], &decode_time.to_be_bytes());
+
+            let trun = find_box(fragment, b"trun");
+            assert_eq!(&trun[8..12], &[0, 0, 0x03, 0x01]);
+            assert_eq!(u32_at(trun, 12), 1);
+            assert_eq!(u32_at(trun, 16) as usize, moof_len + 8);
+            assert_eq!(u32_at(trun, 20), frames);
+            assert_eq!(u32_at(trun, 24) as usize, mdat_len - 8);
+        }
+    }
+}
@@ -8,11 +8,13 @@ use flacenc::{
 };
 use thiserror::Error;
 
-use crate::EncodeInfo;
+use crate::{EncodeInfo, EncoderSettings};
 
 pub struct Encoder {
     sink: ByteSink,
     pos: usize,
+    channels: usize,
+    sample_rate: usize,
     encoder: Verified<flacenc::config::Encoder>,
 }
 
@@ -36,6 +38,16 @@ impl From<flacenc::error::EncodeError> for EncoderError {
 ///
 /// * If the encoder fails to initialize
 pub fn encoder_flac() -> Result<Encoder, EncoderError> {
+    encoder_flac_with_settings(&EncoderSettings::default())
+}
+
+/// FLAC is lossless, so only the sample rate and channel count of the
+/// `settings` are used.
+///
+/// # Errors
+///
+/// * If the encoder fails to initialize
+pub fn encoder_flac_with_settings(settings: &EncoderSettings) -> Result<Encoder, EncoderError> {
     let mut encoder = flacenc::config::Encoder::default();
     encoder.block_size = 512;
     let encoder = encoder.into_verified().map_err(|e| e.1)?;
@@ -45,6 +57,8 @@ pub fn encoder_flac() -> Result<Encoder, EncoderError> {
     Ok(Encoder {
         sink,
         pos: 0,
+        channels: usize::from(settings.channels.unwrap_or(2)),
+        sample_rate: settings.sample_rate.unwrap_or(44_100) as usize,
         encoder,
     })
 }
@@ -57,7 +71,7 @@ pub fn encode_flac(
     input: &[i32],
     buf: &mut [u8],
 ) -> Result<EncodeInfo, EncoderError> {
-    let (channels, bits_per_sample, sample_rate) = (2, 16, 44100);
+    let (channels, bits_per_sample, sample_rate) = (encoder.channels, 16, encoder.sample_rate);
 
     let source = MemSource::from_samples(input, channels, bits_per_sample, sample_rate);
 
@@ -4,6 +4,9 @@
 #[cfg(feature = "aac")]
 pub mod aac;
 
+#[cfg(feature = "alac")]
+pub mod alac;
+
 #[cfg(feature = "flac")]
 pub mod flac;
 
@@ -13,7 +16,27 @@ pub mod mp3;
 #[cfg(feature = "opus")]
 pub mod opus;
 
+#[cfg(feature = "vorbis")]
+pub mod vorbis;
+
+#[cfg(feature = "wav")]
+pub mod wav;
+
 pub struct EncodeInfo {
     pub output_size: usize,
     pub input_consumed: usize,
 }
+
+/// Overrides of an encoder's default bitrate, sample rate and channel count.
+/// Unset values fall back to the encoder's defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EncoderSettings {
+    /// The constant bitrate, in kbps. Takes precedence over `vbr_quality`
+    pub bitrate: Option<u32>,
+    /// The variable bitrate quality, from 0 (best) to 9 (worst)
+    pub vbr_quality: Option<u8>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+    /// The bit depth of uncompressed output. Other encoders ignore it
+    pub bits_per_sample: Option<u8>,
+}
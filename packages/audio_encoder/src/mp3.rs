@@ -2,7 +2,7 @@
 
 use thiserror::Error;
 
-use crate::EncodeInfo;
+use crate::{EncodeInfo, EncoderSettings};
 
 #[derive(Debug, Error)]
 pub enum EncoderError {
@@ -12,6 +12,8 @@ pub enum EncoderError {
     Id3Tag(mp3lame_encoder::Id3TagError),
     #[error("Build error")]
     Build(mp3lame_encoder::BuildError),
+    #[error("Failed to create the LAME builder")]
+    CreateBuilder,
 }
 
 impl From<mp3lame_encoder::EncodeError> for EncoderError {
@@ -32,31 +34,39 @@ impl From<mp3lame_encoder::BuildError> for EncoderError {
     }
 }
 
-/// # Panics
-///
-/// * If the `mp3lame_encoder::Builder` fails to initialize.
-/// * If fails to set the number of channels
-/// * If fails to set the sample rate
-/// * If fails to set the bit rate
-/// * If fails to set the quality
-///
 /// # Errors
 ///
 /// * If the encoder fails to initialize
 pub fn encoder_mp3() -> Result<mp3lame_encoder::Encoder, EncoderError> {
-    use mp3lame_encoder::{Builder, Id3Tag};
-
-    let mut mp3_encoder = Builder::new().expect("Create LAME builder");
-    mp3_encoder.set_num_channels(2).expect("set channels");
-    mp3_encoder
-        .set_sample_rate(44_100)
-        .expect("set sample rate");
-    mp3_encoder
-        .set_brate(mp3lame_encoder::Bitrate::Kbps320)
-        .expect("set brate");
-    mp3_encoder
-        .set_quality(mp3lame_encoder::Quality::Best)
-        .expect("set quality");
+    encoder_mp3_with_settings(&EncoderSettings::default())
+}
+
+/// # Errors
+///
+/// * If the `mp3lame_encoder::Builder` fails to initialize
+/// * If LAME rejects the channel count, sample rate, bitrate or quality
+/// * If the encoder fails to initialize
+pub fn encoder_mp3_with_settings(
+    settings: &EncoderSettings,
+) -> Result<mp3lame_encoder::Encoder, EncoderError> {
+    use mp3lame_encoder::{Builder, Id3Tag, VbrMode};
+
+    let mut mp3_encoder = Builder::new().ok_or(EncoderError::CreateBuilder)?;
+    mp3_encoder.set_num_channels(settings.channels.unwrap_or(2))?;
+    mp3_encoder.set_sample_rate(settings.sample_rate.unwrap_or(44_100))?;
+    match (settings.bitrate, settings.vbr_quality) {
+        (Some(bitrate), _) => {
+            mp3_encoder.set_brate(mp3_bitrate(bitrate))?;
+        }
+        (None, Some(quality)) => {
+            mp3_encoder.set_vbr_mode(VbrMode::Mtrh)?;
+            mp3_encoder.set_vbr_quality(mp3_quality(quality))?;
+        }
+        (None, None) => {
+            mp3_encoder.set_brate(mp3lame_encoder::Bitrate::Kbps320)?;
+        }
+    }
+    mp3_encoder.set_quality(mp3lame_encoder::Quality::Best)?;
     mp3_encoder.set_id3_tag(Id3Tag {
         album_art: &[],
         title: b"My title",
@@ -69,6 +79,47 @@ pub fn encoder_mp3() -> Result<mp3lame_encoder::Encoder, EncoderError> {
     Ok(mp3_encoder)
 }
 
+/// The highest bitrate LAME supports that isn't above `bitrate` kbps
+const fn mp3_bitrate(bitrate: u32) -> mp3lame_encoder::Bitrate {
+    use mp3lame_encoder::Bitrate;
+
+    match bitrate {
+        0..=15 => Bitrate::Kbps8,
+        16..=23 => Bitrate::Kbps16,
+        24..=31 => Bitrate::Kbps24,
+        32..=39 => Bitrate::Kbps32,
+        40..=47 => Bitrate::Kbps40,
+        48..=63 => Bitrate::Kbps48,
+        64..=79 => Bitrate::Kbps64,
+        80..=95 => Bitrate::Kbps80,
+        96..=111 => Bitrate::Kbps96,
+        112..=127 => Bitrate::Kbps112,
+        128..=159 => Bitrate::Kbps128,
+        160..=191 => Bitrate::Kbps160,
+        192..=223 => Bitrate::Kbps192,
+        224..=255 => Bitrate::Kbps224,
+        256..=319 => Bitrate::Kbps256,
+        _ => Bitrate::Kbps320,
+    }
+}
+
+const fn mp3_quality(quality: u8) -> mp3lame_encoder::Quality {
+    use mp3lame_encoder::Quality;
+
+    match quality {
+        0 => Quality::Best,
+        1 => Quality::SecondBest,
+        2 => Quality::NearBest,
+        3 => Quality::VeryNice,
+        4 => Quality::Nice,
+        5 => Quality::Good,
+        6 => Quality::Decent,
+        7 => Quality::Ok,
+        8 => Quality::SecondWorst,
+        _ => Quality::Worst,
+    }
+}
+
 /// # Errors
 ///
 /// * If the encoder fails to encode the input bytes
@@ -102,3 +153,20 @@ pub fn encode_mp3(
         },
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use crate::EncoderSettings;
+
+    use super::{encoder_mp3_with_settings, EncoderError};
+
+    #[test]
+    fn encoder_mp3_with_settings_returns_error_for_invalid_channels() {
+        let result = encoder_mp3_with_settings(&EncoderSettings {
+            channels: Some(0),
+            ..Default::default()
+        });
+
+        assert!(matches!(result, Err(EncoderError::Build(_))));
+    }
+}
@@ -5,7 +5,7 @@ use std::fs::File;
 use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
 use thiserror::Error;
 
-use crate::EncodeInfo;
+use crate::{EncodeInfo, EncoderSettings};
 
 #[derive(Debug, Error)]
 pub enum EncoderError {
@@ -13,6 +13,8 @@ pub enum EncoderError {
     AudiopusEncoder(#[from] audiopus::Error),
     #[error("Encoder error")]
     OpusEncoder(::opus::Error),
+    #[error("Invalid encoder settings: {0:?}")]
+    InvalidSettings(EncoderSettings),
 }
 
 impl From<::opus::Error> for EncoderError {
@@ -95,8 +97,63 @@ pub fn encode_audiopus(samples: &[f32]) -> Result<(u32, Vec<u8>), EncoderError>
 ///
 /// * If the encoder fails to initialize
 pub fn encoder_opus() -> Result<::opus::Encoder, EncoderError> {
-    let encoder =
-        ::opus::Encoder::new(48000, ::opus::Channels::Stereo, ::opus::Application::Audio)?;
+    encoder_opus_with_settings(&EncoderSettings::default())
+}
+
+/// The sample rate, out of the ones Opus supports, that `sample_rate` is
+/// encoded at
+#[must_use]
+pub const fn opus_sample_rate(sample_rate: u32) -> u32 {
+    match sample_rate {
+        0..=8000 => 8000,
+        8001..=12000 => 12000,
+        12001..=16000 => 16000,
+        16001..=24000 => 24000,
+        _ => 48000,
+    }
+}
+
+/// # Errors
+///
+/// * If the `settings` bitrate overflows when converted to bits per second
+/// * If the encoder fails to initialize
+pub fn encoder_opus_with_settings(
+    settings: &EncoderSettings,
+) -> Result<::opus::Encoder, EncoderError> {
+    let channels = if settings.channels == Some(1) {
+        ::opus::Channels::Mono
+    } else {
+        ::opus::Channels::Stereo
+    };
+    let sample_rate = opus_sample_rate(settings.sample_rate.unwrap_or(48000));
+
+    let mut encoder = ::opus::Encoder::new(sample_rate, channels, ::opus::Application::Audio)?;
+
+    match (settings.bitrate, settings.vbr_quality) {
+        (Some(bitrate), _) => {
+            let bits = bitrate
+                .checked_mul(1000)
+                .and_then(|x| i32::try_from(x).ok())
+                .ok_or(EncoderError::InvalidSettings(*settings))?;
+
+            encoder.set_vbr(false)?;
+            encoder.set_bitrate(::opus::Bitrate::Bits(bits))?;
+        }
+        (None, Some(quality)) => {
+            // Opus has no quality scale, so target a bitrate per quality
+            const STEREO_KBPS: [i32; 10] = [256, 224, 192, 160, 128, 112, 96, 80, 64, 48];
+            let kbps = STEREO_KBPS[usize::from(quality.min(9))];
+            let kbps = if settings.channels == Some(1) {
+                kbps / 2
+            } else {
+                kbps
+            };
+
+            encoder.set_vbr(true)?;
+            encoder.set_bitrate(::opus::Bitrate::Bits(kbps * 1000))?;
+        }
+        (None, None) => {}
+    }
 
     Ok(encoder)
 }
@@ -205,6 +262,22 @@ pub const OPUS_STREAM_IDENTIFICATION_HEADER: [u8; 19] = [
     0x00, // Channel mapping: "normal"
 ];
 
+/// The identification header for a stream of `channels` channels that was
+/// encoded from audio at `sample_rate`
+#[must_use]
+pub const fn opus_stream_identification_header(channels: u8, sample_rate: u32) -> [u8; 19] {
+    let mut header = OPUS_STREAM_IDENTIFICATION_HEADER;
+    let rate = sample_rate.to_le_bytes();
+
+    header[9] = channels;
+    header[12] = rate[0];
+    header[13] = rate[1];
+    header[14] = rate[2];
+    header[15] = rate[3];
+
+    header
+}
+
 // Construct Opus Stream Header Packet data
 pub const OPUS_STREAM_COMMENTS_HEADER: [u8; 23] = [
     // Opus magic signature ("OpusHead")
@@ -300,3 +373,22 @@ impl std::io::Write for OpusWrite<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::EncoderSettings;
+
+    use super::{encoder_opus_with_settings, EncoderError};
+
+    #[test]
+    fn encoder_opus_with_settings_rejects_overflowing_bitrate() {
+        for bitrate in [u32::MAX, 2_200_000] {
+            let result = encoder_opus_with_settings(&EncoderSettings {
+                bitrate: Some(bitrate),
+                ..Default::default()
+            });
+
+            assert!(matches!(result, Err(EncoderError::InvalidSettings(_))));
+        }
+    }
+}
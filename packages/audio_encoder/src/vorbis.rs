@@ -0,0 +1,153 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::{
+    io::Write,
+    num::{NonZeroU32, NonZeroU8},
+    sync::{Arc, Mutex},
+};
+
+use thiserror::Error;
+use vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoder, VorbisEncoderBuilder};
+
+use crate::EncoderSettings;
+
+#[derive(Debug, Error)]
+pub enum EncoderError {
+    #[error(transparent)]
+    Vorbis(#[from] vorbis_rs::VorbisError),
+    #[error("Invalid encoder settings: {0:?}")]
+    InvalidSettings(EncoderSettings),
+}
+
+/// The Ogg pages written by the encoder, waiting to be taken
+#[derive(Clone, Default)]
+struct Sink(Arc<Mutex<Vec<u8>>>);
+
+impl Sink {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct Encoder {
+    encoder: Option<VorbisEncoder<Sink>>,
+    sink: Sink,
+    channels: usize,
+}
+
+/// # Errors
+///
+/// * If the encoder fails to initialize
+pub fn encoder_vorbis() -> Result<Encoder, EncoderError> {
+    encoder_vorbis_with_settings(&EncoderSettings::default())
+}
+
+/// Vorbis has no constant bitrate mode, so a `bitrate` is used as the average
+/// bitrate.
+///
+/// # Errors
+///
+/// * If the `settings` have a zero bitrate, sample rate or channel count, or
+///   a bitrate that overflows when converted to bits per second
+/// * If the encoder fails to initialize
+pub fn encoder_vorbis_with_settings(settings: &EncoderSettings) -> Result<Encoder, EncoderError> {
+    let invalid = || EncoderError::InvalidSettings(*settings);
+
+    let strategy = match (settings.bitrate, settings.vbr_quality) {
+        (Some(bitrate), _) => VorbisBitrateManagementStrategy::Abr {
+            average_bitrate: bitrate
+                .checked_mul(1000)
+                .and_then(NonZeroU32::new)
+                .ok_or_else(invalid)?,
+        },
+        (None, quality) => VorbisBitrateManagementStrategy::QualityVbr {
+            target_quality: vorbis_quality(quality),
+        },
+    };
+
+    let sample_rate =
+        NonZeroU32::new(settings.sample_rate.unwrap_or(44_100)).ok_or_else(invalid)?;
+    let channels = NonZeroU8::new(settings.channels.unwrap_or(2)).ok_or_else(invalid)?;
+    let sink = Sink::default();
+
+    let encoder = VorbisEncoderBuilder::new(sample_rate, channels, sink.clone())?
+        .bitrate_management_strategy(strategy)
+        .build()?;
+
+    Ok(Encoder {
+        encoder: Some(encoder),
+        sink,
+        channels: usize::from(channels.get()),
+    })
+}
+
+/// Maps a quality from 0 (best) to 9 (worst) to the libvorbis quality, from
+/// 1.0 (best) to 0.0. Defaults to 0.5, roughly 160kbps for stereo.
+fn vorbis_quality(quality: Option<u8>) -> f32 {
+    quality.map_or(0.5, |x| f32::from(9 - x.min(9)) / 9.0)
+}
+
+/// Encodes interleaved samples, returning the Ogg pages that were completed.
+/// The first pages hold the stream headers.
+///
+/// # Errors
+///
+/// * If the encoder fails to encode the input samples
+pub fn encode_vorbis(encoder: &mut Encoder, input: &[i16]) -> Result<Vec<u8>, EncoderError> {
+    if let Some(vorbis) = encoder.encoder.as_mut() {
+        let mut block = vec![Vec::with_capacity(input.len() / encoder.channels); encoder.channels];
+
+        for frame in input.chunks_exact(encoder.channels) {
+            for (channel, sample) in block.iter_mut().zip(frame) {
+                channel.push(f32::from(*sample) / 32768.0);
+            }
+        }
+
+        vorbis.encode_audio_block(&block)?;
+    }
+
+    Ok(encoder.sink.take())
+}
+
+/// Ends the stream, returning the remaining Ogg pages.
+///
+/// # Errors
+///
+/// * If the encoder fails to flush the last samples
+pub fn finish_vorbis(encoder: &mut Encoder) -> Result<Vec<u8>, EncoderError> {
+    if let Some(vorbis) = encoder.encoder.take() {
+        vorbis.finish()?;
+    }
+
+    Ok(encoder.sink.take())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EncoderSettings;
+
+    use super::{encoder_vorbis_with_settings, EncoderError};
+
+    #[test]
+    fn encoder_vorbis_with_settings_rejects_zero_and_overflowing_bitrate() {
+        for bitrate in [0, u32::MAX] {
+            let result = encoder_vorbis_with_settings(&EncoderSettings {
+                bitrate: Some(bitrate),
+                ..Default::default()
+            });
+
+            assert!(matches!(result, Err(EncoderError::InvalidSettings(_))));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+#![allow(clippy::module_name_repetitions)]
+
+use crate::EncoderSettings;
+
+/// The data size written to the header of a stream whose length isn't known
+/// up front
+pub const UNKNOWN_DATA_SIZE: u32 = u32::MAX;
+
+pub struct Encoder {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+}
+
+#[must_use]
+pub fn encoder_wav() -> Encoder {
+    encoder_wav_with_settings(&EncoderSettings::default())
+}
+
+/// WAV is uncompressed, so only the sample rate, channel count and bit depth
+/// of the `settings` are used. Bit depths are rounded up to 16, 24 or 32 bits.
+#[must_use]
+pub fn encoder_wav_with_settings(settings: &EncoderSettings) -> Encoder {
+    Encoder {
+        channels: u16::from(settings.channels.unwrap_or(2)),
+        sample_rate: settings.sample_rate.unwrap_or(44_100),
+        bits_per_sample: match settings.bits_per_sample {
+            None | Some(0..=16) => 16,
+            Some(17..=24) => 24,
+            Some(_) => 32,
+        },
+    }
+}
+
+/// The RIFF header of a PCM stream with `data_size` bytes of samples. Streams
+/// of unknown length use `UNKNOWN_DATA_SIZE`.
+#[must_use]
+pub fn wav_header(encoder: &Encoder, data_size: u32) -> [u8; 44] {
+    let block_align = encoder.channels * encoder.bits_per_sample / 8;
+    let byte_rate = encoder.sample_rate * u32::from(block_align);
+
+    let mut header = [0_u8; 44];
+    header[0..4].copy_from_slice(b"RIFF");
+    header[4..8].copy_from_slice(&data_size.saturating_add(36).to_le_bytes());
+    header[8..12].copy_from_slice(b"WAVE");
+    header[12..16].copy_from_slice(b"fmt ");
+    header[16..20].copy_from_slice(&16_u32.to_le_bytes());
+    // PCM
+    header[20..22].copy_from_slice(&1_u16.to_le_bytes());
+    header[22..24].copy_from_slice(&encoder.channels.to_le_bytes());
+    header[24..28].copy_from_slice(&encoder.sample_rate.to_le_bytes());
+    header[28..32].copy_from_slice(&byte_rate.to_le_bytes());
+    header[32..34].copy_from_slice(&block_align.to_le_bytes());
+    header[34..36].copy_from_slice(&encoder.bits_per_sample.to_le_bytes());
+    header[36..40].copy_from_slice(b"data");
+    header[40..44].copy_from_slice(&data_size.to_le_bytes());
+    header
+}
+
+/// Encodes interleaved full scale 32-bit samples as little-endian PCM of the
+/// `encoder` bit depth, keeping the most significant bytes of every sample.
+#[must_use]
+pub fn encode_wav(encoder: &Encoder, input: &[i32]) -> Vec<u8> {
+    let skip = 4 - usize::from(encoder.bits_per_sample / 8);
+
+    input
+        .iter()
+        .flat_map(|x| x.to_le_bytes().into_iter().skip(skip))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EncoderSettings;
+
+    use super::{encode_wav, encoder_wav_with_settings, wav_header, UNKNOWN_DATA_SIZE};
+
+    #[test]
+    fn wav_header_describes_the_pcm_stream() {
+        let encoder = encoder_wav_with_settings(&EncoderSettings {
+            sample_rate: Some(96_000),
+            channels: Some(2),
+            bits_per_sample: Some(24),
+            ..Default::default()
+        });
+
+        let header = wav_header(&encoder, 600);
+
+        assert_eq!(&header[0..4], b"RIFF");
+        assert_eq!(&header[4..8], &636_u32.to_le_bytes());
+        assert_eq!(&header[8..16], b"WAVEfmt ");
+        assert_eq!(&header[16..20], &16_u32.to_le_bytes());
+        assert_eq!(&header[20..22], &1_u16.to_le_bytes());
+        assert_eq!(&header[22..24], &2_u16.to_le_bytes());
+        assert_eq!(&header[24..28], &96_000_u32.to_le_bytes());
+        assert_eq!(&header[28..32], &576_000_u32.to_le_bytes());
+        assert_eq!(&header[32..34], &6_u16.to_le_bytes());
+        assert_eq!(&header[34..36], &24_u16.to_le_bytes());
+        assert_eq!(&header[36..40], b"data");
+        assert_eq!(&header[40..44], &600_u32.to_le_bytes());
+    }
+
+    #[test]
+    fn wav_header_of_unknown_length_saturates_the_riff_size() {
+        let header = wav_header(
+            &encoder_wav_with_settings(&EncoderSettings::default()),
+            UNKNOWN_DATA_SIZE,
+        );
+
+        assert_eq!(&header[4..8], &u32::MAX.to_le_bytes());
+        assert_eq!(&header[40..44], &u32::MAX.to_le_bytes());
+        assert_eq!(&header[22..24], &2_u16.to_le_bytes());
+        assert_eq!(&header[24..28], &44_100_u32.to_le_bytes());
+        assert_eq!(&header[34..36], &16_u16.to_le_bytes());
+    }
+
+    #[test]
+    fn encoder_wav_rounds_bit_depth_up() {
+        let bits = |bits_per_sample| {
+            encoder_wav_with_settings(&EncoderSettings {
+                bits_per_sample,
+                ..Default::default()
+            })
+            .bits_per_sample
+        };
+
+        assert_eq!(bits(None), 16);
+        assert_eq!(bits(Some(8)), 16);
+        assert_eq!(bits(Some(20)), 24);
+        assert_eq!(bits(Some(24)), 24);
+        assert_eq!(bits(Some(32)), 32);
+    }
+
+    #[test]
+    fn encode_wav_keeps_the_most_significant_bytes() {
+        let sample = 0x1234_5678_i32;
+        let encoder = |bits_per_sample| {
+            encoder_wav_with_settings(&EncoderSettings {
+                bits_per_sample: Some(bits_per_sample),
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(
+            encode_wav(&encoder(16), &[sample, -1]),
+            [0x34, 0x12, 0xff, 0xff]
+        );
+        assert_eq!(encode_wav(&encoder(24), &[sample]), [0x56, 0x34, 0x12]);
+        assert_eq!(
+            encode_wav(&encoder(32), &[sample]),
+            [0x78, 0x56, 0x34, 0x12]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use actix_web::{
     dev::{ServiceFactory, ServiceRequest},
+    error::ErrorNotFound,
     route,
     web::{self, Json},
     Result, Scope,
@@ -16,14 +17,16 @@ pub fn bind_services<
 >(
     scope: Scope<T>,
 ) -> Scope<T> {
-    scope.service(audio_outputs_endpoint)
+    scope
+        .service(audio_outputs_endpoint)
+        .service(update_audio_output_settings_endpoint)
 }
 
 #[cfg(feature = "openapi")]
 #[derive(utoipa::OpenApi)]
 #[openapi(
     tags((name = "Audio Output")),
-    paths(audio_outputs_endpoint),
+    paths(audio_outputs_endpoint, update_audio_output_settings_endpoint),
     components(schemas())
 )]
 pub struct Api;
@@ -77,3 +80,48 @@ pub async fn audio_outputs_endpoint(
         total,
     }))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAudioOutputSettings {
+    id: String,
+    dop: Option<bool>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Audio Output"],
+        patch,
+        path = "/audio-outputs/settings",
+        description = "Update the settings of an audio output",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("id" = String, Query, description = "The audio output ID"),
+            ("dop" = Option<bool>, Query, description = "Whether to send DSD to the output as DoP frames"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The updated audio output",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/audio-outputs/settings", method = "PATCH")]
+pub async fn update_audio_output_settings_endpoint(
+    query: web::Query<UpdateAudioOutputSettings>,
+) -> Result<Json<ApiAudioOutput>> {
+    let query = query.into_inner();
+    let output = crate::output_factories()
+        .await
+        .into_iter()
+        .find(|x| x.id == query.id)
+        .ok_or_else(|| ErrorNotFound(format!("Audio output not found: {}", query.id)))?;
+
+    if let Some(dop) = query.dop {
+        crate::set_dop_enabled(&output.id, dop);
+    }
+
+    Ok(Json(output.into()))
+}
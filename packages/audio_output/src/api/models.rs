@@ -8,14 +8,18 @@ pub struct ApiAudioOutput {
     pub id: String,
     pub name: String,
     pub spec: ApiSignalSpec,
+    pub dop: bool,
 }
 
 impl From<AudioOutputFactory> for ApiAudioOutput {
     fn from(value: AudioOutputFactory) -> Self {
+        let dop = value.is_dop_enabled();
+
         Self {
             id: value.id,
             name: value.name,
             spec: value.spec.into(),
+            dop,
         }
     }
 }
@@ -1,15 +1,19 @@
 #![allow(clippy::module_name_repetitions)]
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, Host, SampleFormat, SizedSample, StreamConfig};
-use rb::{RbConsumer, RbProducer, SpscRb, RB};
+use cpal::{Device, Host, SampleFormat, SizedSample, StreamConfig, SupportedStreamConfigRange};
+use rb::{RbConsumer, RbInspector as _, RbProducer, SpscRb, RB};
 use symphonia::core::audio::{
     AudioBuffer, Channels, Layout, RawSample, SampleBuffer, Signal as _, SignalSpec,
 };
 use symphonia::core::conv::{ConvertibleSample, IntoSample};
 use symphonia::core::units::Duration;
 
-use crate::{AudioOutputError, AudioOutputFactory, AudioWrite};
+use crate::{AudioOutputError, AudioOutputFactory, AudioWrite, SourceFormat};
+
+/// How long `drain` waits for the stream to consume any more of the buffered samples before
+/// giving up on it
+const DRAIN_STALL_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(5000);
 
 pub struct CpalAudioOutput {
     #[allow(unused)]
@@ -25,6 +29,10 @@ impl AudioWrite for CpalAudioOutput {
     fn flush(&mut self) -> Result<(), AudioOutputError> {
         self.write.flush()
     }
+
+    fn drain(&mut self) -> Result<(), AudioOutputError> {
+        self.write.drain()
+    }
 }
 
 trait AudioOutputSample:
@@ -52,18 +60,30 @@ impl CpalAudioOutput {
     ///
     /// * If the relevant `CpalAudioOutputImpl` fails to initialize
     pub fn new(device: cpal::Device, format: SampleFormat) -> Result<Self, AudioOutputError> {
+        let config = default_stream_config(&device)?;
+        Self::with_config(device, &config, format)
+    }
+
+    /// # Errors
+    ///
+    /// * If the relevant `CpalAudioOutputImpl` fails to initialize
+    pub fn with_config(
+        device: cpal::Device,
+        config: &StreamConfig,
+        format: SampleFormat,
+    ) -> Result<Self, AudioOutputError> {
         Ok(Self {
             write: match format {
-                cpal::SampleFormat::F32 => Box::new(CpalAudioOutputImpl::<f32>::new(&device)?),
-                cpal::SampleFormat::I16 => Box::new(CpalAudioOutputImpl::<i16>::new(&device)?),
-                cpal::SampleFormat::U16 => Box::new(CpalAudioOutputImpl::<u16>::new(&device)?),
-                cpal::SampleFormat::I8 => Box::new(CpalAudioOutputImpl::<i8>::new(&device)?),
-                cpal::SampleFormat::I32 => Box::new(CpalAudioOutputImpl::<i32>::new(&device)?),
-                cpal::SampleFormat::I64 => Box::new(CpalAudioOutputImpl::<i32>::new(&device)?),
-                cpal::SampleFormat::U8 => Box::new(CpalAudioOutputImpl::<u8>::new(&device)?),
-                cpal::SampleFormat::U32 => Box::new(CpalAudioOutputImpl::<u32>::new(&device)?),
-                cpal::SampleFormat::U64 => Box::new(CpalAudioOutputImpl::<u32>::new(&device)?),
-                cpal::SampleFormat::F64 => Box::new(CpalAudioOutputImpl::<f64>::new(&device)?),
+                SampleFormat::F32 => Box::new(CpalAudioOutputImpl::<f32>::new(&device, config)?),
+                SampleFormat::I16 => Box::new(CpalAudioOutputImpl::<i16>::new(&device, config)?),
+                SampleFormat::U16 => Box::new(CpalAudioOutputImpl::<u16>::new(&device, config)?),
+                SampleFormat::I8 => Box::new(CpalAudioOutputImpl::<i8>::new(&device, config)?),
+                SampleFormat::I32 => Box::new(CpalAudioOutputImpl::<i32>::new(&device, config)?),
+                SampleFormat::I64 => Box::new(CpalAudioOutputImpl::<i32>::new(&device, config)?),
+                SampleFormat::U8 => Box::new(CpalAudioOutputImpl::<u8>::new(&device, config)?),
+                SampleFormat::U32 => Box::new(CpalAudioOutputImpl::<u32>::new(&device, config)?),
+                SampleFormat::U64 => Box::new(CpalAudioOutputImpl::<u32>::new(&device, config)?),
+                SampleFormat::F64 => Box::new(CpalAudioOutputImpl::<f64>::new(&device, config)?),
                 _ => unreachable!(),
             },
             device,
@@ -71,6 +91,73 @@ impl CpalAudioOutput {
     }
 }
 
+/// The default config of the device, limited to stereo
+fn default_stream_config(device: &cpal::Device) -> Result<StreamConfig, AudioOutputError> {
+    let config = device
+        .default_output_config()
+        .map_err(|_e| AudioOutputError::UnsupportedOutputConfiguration)?
+        .config();
+
+    log::debug!("Got default config: {config:?}");
+
+    Ok(if config.channels <= 2 {
+        config
+    } else {
+        StreamConfig {
+            channels: 2,
+            sample_rate: config.sample_rate,
+            buffer_size: cpal::BufferSize::Default,
+        }
+    })
+}
+
+/// The config and sample format to open the device at to play the source format bit-perfect,
+/// if the device supports it.
+fn native_stream_config(
+    device: &cpal::Device,
+    format: SourceFormat,
+) -> Option<(StreamConfig, SampleFormat)> {
+    let configs = device.supported_output_configs().ok()?.collect::<Vec<_>>();
+
+    select_native_stream_config(&configs, format)
+}
+
+/// Picks the config and sample format out of the `configs` a device supports to play the
+/// source format bit-perfect. Every sample of the source has to be exactly representable in
+/// the sample format, which 32 bit integer samples aren't once they are decoded to `f32`. The
+/// narrowest such sample format is preferred.
+fn select_native_stream_config(
+    configs: &[SupportedStreamConfigRange],
+    format: SourceFormat,
+) -> Option<(StreamConfig, SampleFormat)> {
+    let channels = u16::try_from(format.spec.channels.count())
+        .ok()
+        .filter(|x| (1..=2).contains(x))?;
+    let sample_formats: &[SampleFormat] = match format.bits_per_sample {
+        Some(1..=16) => &[SampleFormat::I16, SampleFormat::I32, SampleFormat::F32],
+        Some(17..=24) => &[SampleFormat::I32, SampleFormat::F32],
+        Some(_) => &[],
+        None => &[SampleFormat::F32],
+    };
+
+    sample_formats.iter().find_map(|sample_format| {
+        configs
+            .iter()
+            .find(|x| {
+                x.channels() == channels
+                    && x.sample_format() == *sample_format
+                    && (x.min_sample_rate().0..=x.max_sample_rate().0).contains(&format.spec.rate)
+            })
+            .map(|x| {
+                let config = x
+                    .clone()
+                    .with_sample_rate(cpal::SampleRate(format.spec.rate))
+                    .config();
+                (config, *sample_format)
+            })
+    })
+}
+
 impl TryFrom<Device> for AudioOutputFactory {
     type Error = AudioOutputError;
 
@@ -98,42 +185,52 @@ impl TryFrom<Device> for AudioOutputFactory {
         };
 
         let id = format!("cpal:{name}");
+        let bits_per_sample = match config.sample_format() {
+            SampleFormat::I8 | SampleFormat::U8 => Some(8),
+            SampleFormat::I16 | SampleFormat::U16 => Some(16),
+            SampleFormat::I32 | SampleFormat::U32 => Some(32),
+            SampleFormat::I64 | SampleFormat::U64 => Some(64),
+            _ => None,
+        };
 
-        Ok(Self::new(id, name, spec, move || {
-            let format = config.sample_format();
-            Ok(Box::new(CpalAudioOutput::new(device.clone(), format)?))
+        Ok(Self::new(id, name, spec, {
+            let device = device.clone();
+            move || {
+                let format = config.sample_format();
+                Ok(Box::new(CpalAudioOutput::new(device.clone(), format)?))
+            }
+        })
+        .with_bits_per_sample(bits_per_sample)
+        .with_native_writer(move |format| {
+            let (config, sample_format) = native_stream_config(&device, format)?;
+            let device = device.clone();
+
+            Some(Box::new(move || {
+                let output: Box<dyn AudioWrite> = Box::new(CpalAudioOutput::with_config(
+                    device.clone(),
+                    &config,
+                    sample_format,
+                )?);
+                Ok(output)
+            }))
         }))
     }
 }
 
 struct CpalAudioOutputImpl<T: AudioOutputSample> {
     spec: SignalSpec,
+    ring_buf: SpscRb<T>,
     ring_buf_producer: rb::Producer<T>,
     sample_buf: Option<SampleBuffer<T>>,
     stream: cpal::Stream,
+    /// Whether the stream has been paused by `flush`, and so isn't consuming the ring buffer
+    paused: bool,
 }
 
 impl<T: AudioOutputSample> CpalAudioOutputImpl<T> {
-    pub fn new(device: &cpal::Device) -> Result<Self, AudioOutputError> {
-        let config = device
-            .default_output_config()
-            .map_err(|_e| AudioOutputError::UnsupportedOutputConfiguration)?
-            .config();
-
-        log::debug!("Got default config: {config:?}");
-
+    pub fn new(device: &cpal::Device, config: &StreamConfig) -> Result<Self, AudioOutputError> {
         let num_channels = config.channels as usize;
 
-        let config = if num_channels <= 2 {
-            config
-        } else {
-            StreamConfig {
-                channels: 2,
-                sample_rate: config.sample_rate,
-                buffer_size: cpal::BufferSize::Default,
-            }
-        };
-
         let spec = SignalSpec {
             rate: config.sample_rate.0,
             channels: if num_channels >= 2 {
@@ -151,7 +248,7 @@ impl<T: AudioOutputSample> CpalAudioOutputImpl<T> {
 
         let stream = device
             .build_output_stream(
-                &config,
+                config,
                 move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
                     // Write out as many samples as possible from the ring buffer to the audio
                     // output.
@@ -178,14 +275,24 @@ impl<T: AudioOutputSample> CpalAudioOutputImpl<T> {
 
         Ok(Self {
             spec,
+            ring_buf,
             ring_buf_producer,
             stream,
             sample_buf: None,
+            paused: false,
         })
     }
 
     fn init_sample_buf(&mut self, duration: Duration) -> &mut SampleBuffer<T> {
-        if self.sample_buf.is_none() {
+        // The buffer is re-created when a larger packet comes through, e.g. when the output is
+        // reused across tracks with different packet sizes
+        #[allow(clippy::cast_possible_truncation)]
+        let samples = duration as usize * self.spec.channels.count();
+        if self
+            .sample_buf
+            .as_ref()
+            .is_none_or(|x| x.capacity() < samples)
+        {
             let spec = self.spec;
             let sample_buf = SampleBuffer::<T>::new(duration, spec);
             self.sample_buf = Some(sample_buf);
@@ -233,7 +340,36 @@ impl<T: AudioOutputSample> AudioWrite for CpalAudioOutputImpl<T> {
         // depending on the number of samples it has.
 
         // Flush is best-effort, ignore the returned result.
-        let _ = self.stream.pause();
+        if self.stream.pause().is_ok() {
+            self.paused = true;
+        }
+
+        Ok(())
+    }
+
+    fn drain(&mut self) -> Result<(), AudioOutputError> {
+        // A paused stream doesn't play the buffered samples, so there is nothing to wait for
+        if self.paused {
+            return Ok(());
+        }
+
+        let mut remaining = self.ring_buf.count();
+        let mut last_progress = std::time::Instant::now();
+
+        while remaining > 0 {
+            // Only give up once the stream stops consuming samples, rather than after a fixed
+            // time, so a stream that is slow to start or briefly stalled still drains
+            if last_progress.elapsed() > DRAIN_STALL_TIMEOUT {
+                return Err(AudioOutputError::Interrupt);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+
+            let count = self.ring_buf.count();
+            if count < remaining {
+                last_progress = std::time::Instant::now();
+            }
+            remaining = count;
+        }
 
         Ok(())
     }
@@ -267,3 +403,93 @@ pub fn scan_available_outputs() -> impl Iterator<Item = AudioOutputFactory> {
         .flat_map(IntoIterator::into_iter)
         .filter_map(|device| device.try_into().ok())
 }
+
+#[cfg(test)]
+mod test {
+    use cpal::{SampleRate, SupportedBufferSize};
+
+    use super::*;
+
+    fn range(
+        channels: u16,
+        min: u32,
+        max: u32,
+        format: SampleFormat,
+    ) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(
+            channels,
+            SampleRate(min),
+            SampleRate(max),
+            SupportedBufferSize::Unknown,
+            format,
+        )
+    }
+
+    fn source(rate: u32, channels: Channels, bits_per_sample: Option<u32>) -> SourceFormat {
+        SourceFormat {
+            spec: SignalSpec::new(rate, channels),
+            bits_per_sample,
+        }
+    }
+
+    fn stereo() -> Channels {
+        Channels::FRONT_LEFT | Channels::FRONT_RIGHT
+    }
+
+    #[test]
+    fn picks_the_narrowest_sample_format_that_holds_the_source_samples() {
+        let configs = [
+            range(2, 44_100, 192_000, SampleFormat::F32),
+            range(2, 44_100, 192_000, SampleFormat::I32),
+            range(2, 44_100, 192_000, SampleFormat::I16),
+        ];
+
+        let (config, format) =
+            select_native_stream_config(&configs, source(96_000, stereo(), Some(16))).unwrap();
+        assert_eq!(format, SampleFormat::I16);
+        assert_eq!(config.sample_rate, SampleRate(96_000));
+        assert_eq!(config.channels, 2);
+
+        let (_, format) =
+            select_native_stream_config(&configs, source(96_000, stereo(), Some(24))).unwrap();
+        assert_eq!(format, SampleFormat::I32);
+
+        let (_, format) =
+            select_native_stream_config(&configs, source(96_000, stereo(), None)).unwrap();
+        assert_eq!(format, SampleFormat::F32);
+    }
+
+    #[test]
+    fn falls_back_to_a_wider_sample_format() {
+        let configs = [range(2, 44_100, 192_000, SampleFormat::F32)];
+
+        let (_, format) =
+            select_native_stream_config(&configs, source(44_100, stereo(), Some(16))).unwrap();
+        assert_eq!(format, SampleFormat::F32);
+        assert!(
+            select_native_stream_config(&configs, source(44_100, stereo(), Some(32))).is_none()
+        );
+    }
+
+    #[test]
+    fn requires_the_source_rate_and_channels() {
+        let configs = [range(2, 44_100, 48_000, SampleFormat::I32)];
+
+        assert!(
+            select_native_stream_config(&configs, source(96_000, stereo(), Some(24))).is_none()
+        );
+        assert!(select_native_stream_config(
+            &configs,
+            source(48_000, Channels::FRONT_LEFT, Some(24))
+        )
+        .is_none());
+        assert!(select_native_stream_config(
+            &configs,
+            source(48_000, stereo() | Channels::FRONT_CENTRE, Some(24))
+        )
+        .is_none());
+        assert!(
+            select_native_stream_config(&configs, source(48_000, stereo(), Some(24))).is_some()
+        );
+    }
+}
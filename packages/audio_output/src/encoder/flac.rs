@@ -6,10 +6,10 @@ use bytes::Bytes;
 use moosicbox_audio_decoder::{
     decode_file_path_str, AudioDecode, AudioDecodeError, AudioDecodeHandler,
 };
-use moosicbox_audio_encoder::flac::{encoder_flac, Encoder};
+use moosicbox_audio_encoder::flac::{encoder_flac, encoder_flac_with_settings, Encoder};
 use moosicbox_stream_utils::{ByteStream, ByteWriter};
 use symphonia::core::{
-    audio::{AudioBuffer, Channels, Signal, SignalSpec},
+    audio::{AudioBuffer, Signal, SignalSpec},
     formats::{Packet, Track},
     units::Duration,
 };
@@ -17,13 +17,14 @@ use symphonia::core::{
 use crate::{to_samples, AudioOutputError, AudioWrite};
 use moosicbox_resampler::Resampler;
 
-use super::AudioEncoder;
+use super::{channel_layout, remix, AudioEncoder, EncoderSettings};
 
 pub struct FlacEncoder {
     resampler: Option<RwLock<Resampler<i16>>>,
     input_rate: Option<u32>,
     resample_rate: Option<u32>,
     output_rate: usize,
+    channels: usize,
     duration: Option<Duration>,
     writer: Option<Box<dyn std::io::Write + Send + Sync>>,
     encoder: Encoder,
@@ -40,6 +41,7 @@ impl FlacEncoder {
             input_rate: None,
             resample_rate: None,
             output_rate: 44100,
+            channels: 2,
             duration: None,
             writer: None,
             encoder: encoder_flac().expect("Failed to create Flac encoder"),
@@ -55,12 +57,31 @@ impl FlacEncoder {
             input_rate: None,
             resample_rate: None,
             output_rate: 44100,
+            channels: 2,
             duration: None,
             writer: Some(Box::new(writer)),
             encoder: encoder_flac().expect("Failed to create Flac encoder"),
         }
     }
 
+    /// Recreates the encoder with the `settings`. Has to be called before the
+    /// encoder is opened.
+    ///
+    /// # Errors
+    ///
+    /// * If the `settings` are invalid for the flac encoder
+    pub fn with_settings(mut self, settings: &EncoderSettings) -> Result<Self, AudioOutputError> {
+        self.output_rate = settings
+            .sample_rate
+            .map_or(self.output_rate, |x| x as usize);
+        self.channels = settings
+            .channels
+            .map_or(self.channels, |x| usize::from(x.clamp(1, 2)));
+        self.encoder = encoder_flac_with_settings(settings)
+            .map_err(|e| AudioOutputError::Encoder(e.to_string()))?;
+        Ok(self)
+    }
+
     pub fn init_resampler(&mut self, spec: &SignalSpec, duration: Duration) -> &Self {
         self.input_rate.replace(spec.rate);
         self.duration.replace(duration);
@@ -85,7 +106,10 @@ impl FlacEncoder {
 
     #[must_use]
     pub fn open(mut self, spec: SignalSpec, duration: Duration) -> Self {
-        self.init_resampler(&spec, duration);
+        self.init_resampler(
+            &SignalSpec::new(spec.rate, channel_layout(self.channels)),
+            duration,
+        );
         self
     }
 
@@ -165,6 +189,7 @@ impl AudioEncoder for FlacEncoder {
     fn encode(&mut self, decoded: AudioBuffer<f32>) -> Result<Bytes, AudioOutputError> {
         log::debug!("FlacEncoder encode {} frames", decoded.frames());
 
+        let decoded = remix(decoded, self.channels);
         let decoded = self.resample_if_needed(&decoded)?;
 
         Ok(self.encode_output(&decoded))
@@ -173,7 +198,7 @@ impl AudioEncoder for FlacEncoder {
     fn spec(&self) -> SignalSpec {
         SignalSpec {
             rate: u32::try_from(self.output_rate).unwrap(),
-            channels: Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            channels: channel_layout(self.channels),
         }
     }
 }
@@ -2,17 +2,44 @@
 
 use bytes::Bytes;
 use symphonia::core::audio::{AudioBuffer, SignalSpec};
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+use symphonia::core::audio::{Channels, Signal as _};
 
 use crate::AudioOutputError;
 
 #[cfg(feature = "aac")]
 pub mod aac;
+#[cfg(feature = "alac")]
+pub mod alac;
 #[cfg(feature = "flac")]
 pub mod flac;
 #[cfg(feature = "mp3")]
 pub mod mp3;
 #[cfg(feature = "opus")]
 pub mod opus;
+#[cfg(feature = "vorbis")]
+pub mod vorbis;
+#[cfg(feature = "wav")]
+pub mod wav;
+
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+pub use moosicbox_audio_encoder::EncoderSettings;
 
 pub trait AudioEncoder: Send + Sync {
     /// # Errors
@@ -21,3 +48,229 @@ pub trait AudioEncoder: Send + Sync {
     fn encode(&mut self, decoded: AudioBuffer<f32>) -> Result<Bytes, AudioOutputError>;
     fn spec(&self) -> SignalSpec;
 }
+
+/// The layout of `channels` channels. Encoders output mono or stereo.
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+fn channel_layout(channels: usize) -> Channels {
+    if channels == 1 {
+        Channels::FRONT_LEFT
+    } else {
+        Channels::FRONT_LEFT | Channels::FRONT_RIGHT
+    }
+}
+
+/// -3 dB, the gain of a channel that is split between both sides of a stereo
+/// downmix
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+const DOWNMIX_GAIN: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// The gains of a source `channel` in the left and right channels of a stereo
+/// downmix. Centre and LFE channels are split between both sides.
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+const fn stereo_gains(channel: Channels) -> (f32, f32) {
+    match channel {
+        Channels::FRONT_LEFT => (1.0, 0.0),
+        Channels::FRONT_RIGHT => (0.0, 1.0),
+        Channels::FRONT_LEFT_CENTRE
+        | Channels::FRONT_LEFT_WIDE
+        | Channels::FRONT_LEFT_HIGH
+        | Channels::SIDE_LEFT
+        | Channels::REAR_LEFT
+        | Channels::REAR_LEFT_CENTRE
+        | Channels::TOP_FRONT_LEFT
+        | Channels::TOP_REAR_LEFT => (DOWNMIX_GAIN, 0.0),
+        Channels::FRONT_RIGHT_CENTRE
+        | Channels::FRONT_RIGHT_WIDE
+        | Channels::FRONT_RIGHT_HIGH
+        | Channels::SIDE_RIGHT
+        | Channels::REAR_RIGHT
+        | Channels::REAR_RIGHT_CENTRE
+        | Channels::TOP_FRONT_RIGHT
+        | Channels::TOP_REAR_RIGHT => (0.0, DOWNMIX_GAIN),
+        _ => (DOWNMIX_GAIN, DOWNMIX_GAIN),
+    }
+}
+
+/// Mixes the `decoded` audio to `channels` channels. Mono sources are copied
+/// to every channel. Other sources are downmixed to stereo by channel
+/// position, scaled so that the mix can't clip, and mono is the average of
+/// that stereo downmix.
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+fn remix(decoded: AudioBuffer<f32>, channels: usize) -> AudioBuffer<f32> {
+    let spec = *decoded.spec();
+    let source_channels = spec.channels.count();
+
+    if source_channels == channels {
+        return decoded;
+    }
+
+    let mut remixed = AudioBuffer::<f32>::new(
+        decoded.capacity() as u64,
+        SignalSpec::new(spec.rate, channel_layout(channels)),
+    );
+    remixed.render_reserved(Some(decoded.frames()));
+
+    if source_channels == 1 {
+        for ch in 0..channels {
+            remixed.chan_mut(ch).copy_from_slice(decoded.chan(0));
+        }
+
+        return remixed;
+    }
+
+    let gains = spec.channels.iter().map(stereo_gains).collect::<Vec<_>>();
+    let left_total = gains.iter().map(|(left, _)| left).sum::<f32>();
+    let right_total = gains.iter().map(|(_, right)| right).sum::<f32>();
+    let scale = 1.0 / left_total.max(right_total).max(1.0);
+
+    for (ch, (left, right)) in gains.into_iter().enumerate() {
+        let source = decoded.chan(ch);
+
+        if channels == 1 {
+            let gain = (left + right) * scale / 2.0;
+            for (dst, sample) in remixed.chan_mut(0).iter_mut().zip(source) {
+                *dst += sample * gain;
+            }
+        } else {
+            for (side, gain) in [(0, left * scale), (1, right * scale)] {
+                for (dst, sample) in remixed.chan_mut(side).iter_mut().zip(source) {
+                    *dst += sample * gain;
+                }
+            }
+        }
+    }
+
+    remixed
+}
+
+#[cfg(test)]
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+mod test {
+    use symphonia::core::audio::{AudioBuffer, Channels, Signal as _, SignalSpec};
+
+    use super::{remix, DOWNMIX_GAIN};
+
+    const SURROUND_5_1: Channels = Channels::FRONT_LEFT
+        .union(Channels::FRONT_RIGHT)
+        .union(Channels::FRONT_CENTRE)
+        .union(Channels::LFE1)
+        .union(Channels::REAR_LEFT)
+        .union(Channels::REAR_RIGHT);
+
+    fn buffer(channels: Channels, samples: &[f32]) -> AudioBuffer<f32> {
+        let mut buffer = AudioBuffer::new(1, SignalSpec::new(44_100, channels));
+        buffer.render_reserved(Some(1));
+
+        for (ch, sample) in samples.iter().enumerate() {
+            buffer.chan_mut(ch)[0] = *sample;
+        }
+
+        buffer
+    }
+
+    fn samples(buffer: &AudioBuffer<f32>) -> Vec<f32> {
+        (0..buffer.spec().channels.count())
+            .map(|ch| buffer.chan(ch)[0])
+            .collect()
+    }
+
+    fn assert_samples(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-6,
+                "expected {expected} but got {actual}"
+            );
+        }
+    }
+
+    #[test]
+    fn remix_copies_mono_to_both_sides() {
+        let remixed = remix(buffer(Channels::FRONT_LEFT, &[0.5]), 2);
+
+        assert_samples(&samples(&remixed), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn remix_averages_stereo_to_mono() {
+        let stereo = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+        let remixed = remix(buffer(stereo, &[0.5, 0.25]), 1);
+
+        assert_samples(&samples(&remixed), &[0.375]);
+    }
+
+    #[test]
+    fn remix_keeps_centre_and_lfe_in_stereo_downmix() {
+        let scale = 1.0 / 3.0f32.mul_add(DOWNMIX_GAIN, 1.0);
+
+        let centre = remix(buffer(SURROUND_5_1, &[0.0, 0.0, 1.0, 0.0, 0.0, 0.0]), 2);
+        assert_samples(
+            &samples(&centre),
+            &[DOWNMIX_GAIN * scale, DOWNMIX_GAIN * scale],
+        );
+
+        let lfe = remix(buffer(SURROUND_5_1, &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0]), 2);
+        assert_samples(
+            &samples(&lfe),
+            &[DOWNMIX_GAIN * scale, DOWNMIX_GAIN * scale],
+        );
+    }
+
+    #[test]
+    fn remix_keeps_rear_channels_on_their_side() {
+        let scale = 1.0 / 3.0f32.mul_add(DOWNMIX_GAIN, 1.0);
+
+        let remixed = remix(buffer(SURROUND_5_1, &[0.0, 0.0, 0.0, 0.0, 1.0, 0.0]), 2);
+
+        assert_samples(&samples(&remixed), &[DOWNMIX_GAIN * scale, 0.0]);
+    }
+
+    #[test]
+    fn remix_surround_downmix_does_not_clip() {
+        let remixed = remix(buffer(SURROUND_5_1, &[1.0; 6]), 2);
+        assert_samples(&samples(&remixed), &[1.0, 1.0]);
+
+        let remixed = remix(buffer(SURROUND_5_1, &[1.0; 6]), 1);
+        assert_samples(&samples(&remixed), &[1.0]);
+    }
+}
@@ -6,10 +6,10 @@ use bytes::Bytes;
 use moosicbox_audio_decoder::{
     decode_file_path_str, AudioDecode, AudioDecodeError, AudioDecodeHandler,
 };
-use moosicbox_audio_encoder::mp3::encoder_mp3;
+use moosicbox_audio_encoder::mp3::{encoder_mp3, encoder_mp3_with_settings};
 use moosicbox_stream_utils::{ByteStream, ByteWriter};
 use symphonia::core::{
-    audio::{AudioBuffer, Channels, Signal, SignalSpec},
+    audio::{AudioBuffer, Signal, SignalSpec},
     formats::{Packet, Track},
     units::Duration,
 };
@@ -17,13 +17,14 @@ use symphonia::core::{
 use crate::{to_samples, AudioOutputError, AudioWrite};
 use moosicbox_resampler::Resampler;
 
-use super::AudioEncoder;
+use super::{channel_layout, remix, AudioEncoder, EncoderSettings};
 
 pub struct Mp3Encoder {
     resampler: Option<RwLock<Resampler<i16>>>,
     input_rate: Option<u32>,
     resample_rate: Option<u32>,
     output_rate: usize,
+    channels: usize,
     duration: Option<Duration>,
     writer: Option<Box<dyn std::io::Write + Send + Sync>>,
     encoder: mp3lame_encoder::Encoder,
@@ -40,6 +41,7 @@ impl Mp3Encoder {
             input_rate: None,
             resample_rate: None,
             output_rate: 44100,
+            channels: 2,
             duration: None,
             writer: None,
             encoder: encoder_mp3().unwrap(),
@@ -55,12 +57,31 @@ impl Mp3Encoder {
             input_rate: None,
             resample_rate: None,
             output_rate: 44100,
+            channels: 2,
             duration: None,
             writer: Some(Box::new(writer)),
             encoder: encoder_mp3().unwrap(),
         }
     }
 
+    /// Recreates the encoder with the `settings`. Has to be called before the
+    /// encoder is opened.
+    ///
+    /// # Errors
+    ///
+    /// * If the `settings` are invalid for the mp3 encoder
+    pub fn with_settings(mut self, settings: &EncoderSettings) -> Result<Self, AudioOutputError> {
+        self.output_rate = settings
+            .sample_rate
+            .map_or(self.output_rate, |x| x as usize);
+        self.channels = settings
+            .channels
+            .map_or(self.channels, |x| usize::from(x.clamp(1, 2)));
+        self.encoder = encoder_mp3_with_settings(settings)
+            .map_err(|e| AudioOutputError::Encoder(e.to_string()))?;
+        Ok(self)
+    }
+
     pub fn init_resampler(&mut self, spec: &SignalSpec, duration: Duration) -> &Self {
         if self.resample_rate.is_none_or(|r| r != spec.rate)
             && self.output_rate != spec.rate as usize
@@ -84,7 +105,10 @@ impl Mp3Encoder {
 
     #[must_use]
     pub fn open(mut self, spec: SignalSpec, duration: Duration) -> Self {
-        self.init_resampler(&spec, duration);
+        self.init_resampler(
+            &SignalSpec::new(spec.rate, channel_layout(self.channels)),
+            duration,
+        );
         self
     }
 
@@ -163,6 +187,7 @@ impl AudioEncoder for Mp3Encoder {
     fn encode(&mut self, decoded: AudioBuffer<f32>) -> Result<Bytes, AudioOutputError> {
         log::debug!("Mp3Encoder encode {} frames", decoded.frames());
 
+        let decoded = remix(decoded, self.channels);
         let decoded = self.resample_if_needed(&decoded)?;
 
         Ok(self.encode_output(&decoded))
@@ -171,7 +196,7 @@ impl AudioEncoder for Mp3Encoder {
     fn spec(&self) -> SignalSpec {
         SignalSpec {
             rate: u32::try_from(self.output_rate).unwrap(),
-            channels: Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            channels: channel_layout(self.channels),
         }
     }
 }
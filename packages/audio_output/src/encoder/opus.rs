@@ -7,12 +7,13 @@ use moosicbox_audio_decoder::{
     decode_file_path_str, AudioDecode, AudioDecodeError, AudioDecodeHandler,
 };
 use moosicbox_audio_encoder::opus::{
-    encoder_opus, OPUS_STREAM_COMMENTS_HEADER, OPUS_STREAM_IDENTIFICATION_HEADER,
+    encoder_opus, encoder_opus_with_settings, opus_sample_rate, opus_stream_identification_header,
+    OPUS_STREAM_COMMENTS_HEADER,
 };
 use moosicbox_stream_utils::{ByteStream, ByteWriter};
 use ogg::{PacketWriteEndInfo, PacketWriter};
 use symphonia::core::{
-    audio::{AudioBuffer, Channels, Signal, SignalSpec},
+    audio::{AudioBuffer, Signal, SignalSpec},
     formats::{Packet, Track},
     units::Duration,
 };
@@ -20,10 +21,13 @@ use symphonia::core::{
 use crate::{to_samples, AudioOutputError, AudioWrite};
 use moosicbox_resampler::Resampler;
 
-use super::AudioEncoder;
+use super::{channel_layout, remix, AudioEncoder, EncoderSettings};
 
 const STEREO_20MS: usize = 48000 * 2 * 20 / 1000;
 
+/// Opus granule positions are always counted at 48kHz
+const GRANULE_RATE: usize = 48000;
+
 pub struct OpusEncoder<'a> {
     buf: [f32; STEREO_20MS],
     buf_len: usize,
@@ -37,6 +41,7 @@ pub struct OpusEncoder<'a> {
     input_rate: Option<u32>,
     resample_rate: Option<u32>,
     output_rate: usize,
+    channels: usize,
     duration: Option<Duration>,
     writer: Option<Box<dyn std::io::Write + Send + Sync>>,
     encoder: Mutex<opus::Encoder>,
@@ -63,6 +68,7 @@ impl OpusEncoder<'_> {
             input_rate: None,
             resample_rate: None,
             output_rate: 48000,
+            channels: 2,
             duration: None,
             writer: None,
             encoder: Mutex::new(encoder_opus().unwrap()),
@@ -75,6 +81,29 @@ impl OpusEncoder<'_> {
         x
     }
 
+    /// Recreates the encoder with the `settings`. Has to be called before the
+    /// encoder is opened.
+    ///
+    /// # Errors
+    ///
+    /// * If the `settings` are invalid for the opus encoder
+    pub fn with_settings(mut self, settings: &EncoderSettings) -> Result<Self, AudioOutputError> {
+        self.output_rate = opus_sample_rate(settings.sample_rate.unwrap_or(48000)) as usize;
+        self.channels = settings
+            .channels
+            .map_or(self.channels, |x| usize::from(x.clamp(1, 2)));
+        self.encoder = Mutex::new(
+            encoder_opus_with_settings(settings)
+                .map_err(|e| AudioOutputError::Encoder(e.to_string()))?,
+        );
+        Ok(self)
+    }
+
+    /// The number of samples in each 20ms frame
+    const fn frame_len(&self) -> usize {
+        self.output_rate * self.channels * 20 / 1000
+    }
+
     pub fn init_resampler(&mut self, spec: &SignalSpec, duration: Duration) -> &Self {
         if self.resample_rate.is_none_or(|r| r != spec.rate)
             && self.output_rate != spec.rate as usize
@@ -98,7 +127,10 @@ impl OpusEncoder<'_> {
 
     #[must_use]
     pub fn open(mut self, spec: SignalSpec, duration: Duration) -> Self {
-        self.init_resampler(&spec, duration);
+        self.init_resampler(
+            &SignalSpec::new(spec.rate, channel_layout(self.channels)),
+            duration,
+        );
         self
     }
 
@@ -134,7 +166,11 @@ impl OpusEncoder<'_> {
                 log::debug!("Writing OPUS identification header packet");
                 self.packet_writer
                     .write_packet(
-                        OPUS_STREAM_IDENTIFICATION_HEADER.to_vec(),
+                        opus_stream_identification_header(
+                            u8::try_from(self.channels).unwrap(),
+                            u32::try_from(self.output_rate).unwrap(),
+                        )
+                        .to_vec(),
                         self.serial,
                         PacketWriteEndInfo::EndPage,
                         self.absgp,
@@ -163,7 +199,8 @@ impl OpusEncoder<'_> {
                 )
                 .expect("Failed to write packet");
 
-            self.absgp += (info.input_consumed / 2) as u64;
+            self.absgp +=
+                (info.input_consumed / self.channels * (GRANULE_RATE / self.output_rate)) as u64;
 
             written.extend_from_slice(&self.write_new_packet_writer_contents());
 
@@ -209,19 +246,20 @@ impl OpusEncoder<'_> {
 
     fn write_samples(&mut self, decoded: Vec<f32>) -> Bytes {
         let samples = [self.buf[..self.buf_len].to_vec(), decoded].concat();
+        let frame_len = self.frame_len();
 
         self.buf_len = 0;
 
         let mut written = vec![];
 
-        for chunk in samples.chunks(STEREO_20MS) {
-            if chunk.len() < STEREO_20MS {
+        for chunk in samples.chunks(frame_len) {
+            if chunk.len() < frame_len {
                 self.buf_len = chunk.len();
                 self.buf[..self.buf_len].copy_from_slice(chunk);
             } else {
                 self.time += 20;
                 log::debug!("Encoding OPUS chunk...");
-                let bytes = self.encode_output(chunk, STEREO_20MS);
+                let bytes = self.encode_output(chunk, frame_len);
                 let byte_count = bytes.len();
                 log::debug!("Encoded OPUS chunk to {byte_count} bytes");
                 written.extend_from_slice(&bytes);
@@ -283,6 +321,7 @@ impl AudioEncoder for OpusEncoder<'_> {
     fn encode(&mut self, decoded: AudioBuffer<f32>) -> Result<Bytes, AudioOutputError> {
         log::debug!("OpusEncoder encode {} frames", decoded.frames());
 
+        let decoded = remix(decoded, self.channels);
         let decoded = self.resample_if_needed(&decoded)?;
 
         Ok(self.write_samples(decoded))
@@ -291,7 +330,7 @@ impl AudioEncoder for OpusEncoder<'_> {
     fn spec(&self) -> SignalSpec {
         SignalSpec {
             rate: u32::try_from(self.output_rate).unwrap(),
-            channels: Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            channels: channel_layout(self.channels),
         }
     }
 }
@@ -0,0 +1,343 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::sync::{Mutex, RwLock};
+
+use bytes::Bytes;
+use moosicbox_audio_decoder::{
+    decode_file_path_str, AudioDecode, AudioDecodeError, AudioDecodeHandler,
+};
+use moosicbox_audio_encoder::vorbis::{
+    encoder_vorbis, encoder_vorbis_with_settings, finish_vorbis, Encoder,
+};
+use moosicbox_stream_utils::{ByteStream, ByteWriter};
+use symphonia::core::{
+    audio::{AudioBuffer, Signal, SignalSpec},
+    formats::{Packet, Track},
+    units::Duration,
+};
+
+use crate::{to_samples, AudioOutputError, AudioWrite};
+use moosicbox_resampler::Resampler;
+
+use super::{channel_layout, remix, AudioEncoder, EncoderSettings};
+
+pub struct VorbisEncoder {
+    resampler: Option<RwLock<Resampler<i16>>>,
+    input_rate: Option<u32>,
+    resample_rate: Option<u32>,
+    output_rate: usize,
+    channels: usize,
+    duration: Option<Duration>,
+    writer: Option<Box<dyn std::io::Write + Send + Sync>>,
+    started: bool,
+    settings: EncoderSettings,
+    encoder: Mutex<Encoder>,
+}
+
+impl VorbisEncoder {
+    /// # Panics
+    ///
+    /// * If fails to get the vorbis encoder
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            resampler: None,
+            input_rate: None,
+            resample_rate: None,
+            output_rate: 44100,
+            channels: 2,
+            duration: None,
+            writer: None,
+            started: false,
+            settings: EncoderSettings::default(),
+            encoder: Mutex::new(encoder_vorbis().expect("Failed to create Vorbis encoder")),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// * If fails to get the vorbis encoder
+    pub fn with_writer<W: std::io::Write + Send + Sync + 'static>(writer: W) -> Self {
+        Self {
+            resampler: None,
+            input_rate: None,
+            resample_rate: None,
+            output_rate: 44100,
+            channels: 2,
+            duration: None,
+            writer: Some(Box::new(writer)),
+            started: false,
+            settings: EncoderSettings::default(),
+            encoder: Mutex::new(encoder_vorbis().expect("Failed to create Vorbis encoder")),
+        }
+    }
+
+    /// Recreates the encoder with the `settings`. Has to be called before the
+    /// encoder is opened.
+    ///
+    /// # Errors
+    ///
+    /// * If the `settings` are invalid for the vorbis encoder
+    pub fn with_settings(mut self, settings: &EncoderSettings) -> Result<Self, AudioOutputError> {
+        self.output_rate = settings
+            .sample_rate
+            .map_or(self.output_rate, |x| x as usize);
+        self.channels = settings
+            .channels
+            .map_or(self.channels, |x| usize::from(x.clamp(1, 2)));
+        self.encoder = Mutex::new(
+            encoder_vorbis_with_settings(settings)
+                .map_err(|e| AudioOutputError::Encoder(e.to_string()))?,
+        );
+        self.settings = *settings;
+        Ok(self)
+    }
+
+    /// Encodes at the `rate` of the source, unless the settings set the
+    /// sample rate. Has no effect once the encoder has started.
+    fn init_encoder(&mut self, rate: u32) -> Result<(), AudioOutputError> {
+        if self.started {
+            return Ok(());
+        }
+
+        self.started = true;
+
+        let sample_rate = self.settings.sample_rate.unwrap_or(rate);
+        self.output_rate = sample_rate as usize;
+        self.encoder = Mutex::new(
+            encoder_vorbis_with_settings(&EncoderSettings {
+                sample_rate: Some(sample_rate),
+                channels: u8::try_from(self.channels).ok(),
+                ..self.settings
+            })
+            .map_err(|e| AudioOutputError::Encoder(e.to_string()))?,
+        );
+
+        Ok(())
+    }
+
+    pub fn init_resampler(&mut self, spec: &SignalSpec, duration: Duration) -> &Self {
+        self.input_rate.replace(spec.rate);
+        self.duration.replace(duration);
+
+        if self.resample_rate.is_none_or(|r| r != spec.rate)
+            && self.output_rate != spec.rate as usize
+        {
+            log::debug!(
+                "Initializing resampler with rate={} duration={}",
+                spec.rate,
+                duration,
+            );
+            self.resample_rate.replace(spec.rate);
+            self.resampler.replace(RwLock::new(Resampler::new(
+                *spec,
+                self.output_rate,
+                duration,
+            )));
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn open(mut self, spec: SignalSpec, duration: Duration) -> Self {
+        self.output_rate = self.settings.sample_rate.unwrap_or(spec.rate) as usize;
+        self.init_resampler(
+            &SignalSpec::new(spec.rate, channel_layout(self.channels)),
+            duration,
+        );
+        self
+    }
+
+    /// Encodes the samples, returning the completed Ogg pages. The stream
+    /// headers are in the first pages.
+    fn encode_output(&mut self, buf: &[i16]) -> Result<Bytes, AudioOutputError> {
+        moosicbox_audio_encoder::vorbis::encode_vorbis(&mut self.encoder.lock().unwrap(), buf)
+            .map(Into::into)
+            .map_err(|e| AudioOutputError::Encoder(e.to_string()))
+    }
+
+    fn resample_if_needed(
+        &mut self,
+        decoded: &AudioBuffer<f32>,
+    ) -> Result<Vec<i16>, AudioOutputError> {
+        let spec = decoded.spec();
+        let duration = decoded.capacity() as u64;
+
+        self.init_resampler(spec, duration);
+
+        if let Some(resampler) = &self.resampler {
+            log::debug!(
+                "Resampling input_rate={:?} output_rate={} duration={:?}",
+                self.input_rate,
+                self.output_rate,
+                self.duration
+            );
+
+            let mut resampler = resampler.write().unwrap();
+
+            Ok(resampler
+                .resample(decoded)
+                .ok_or(AudioOutputError::StreamEnd)?
+                .to_vec())
+        } else {
+            log::debug!(
+                "Passing through audio frames={} duration={duration} rate={} channels={} channels_count={}",
+                decoded.frames(),
+                spec.rate,
+                spec.channels,
+                spec.channels.count(),
+            );
+            Ok(to_samples(decoded))
+        }
+    }
+}
+
+impl Default for VorbisEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioEncoder for VorbisEncoder {
+    fn encode(&mut self, decoded: AudioBuffer<f32>) -> Result<Bytes, AudioOutputError> {
+        log::debug!("VorbisEncoder encode {} frames", decoded.frames());
+
+        self.init_encoder(decoded.spec().rate)?;
+        let decoded = remix(decoded, self.channels);
+        let decoded = self.resample_if_needed(&decoded)?;
+
+        self.encode_output(&decoded)
+    }
+
+    fn spec(&self) -> SignalSpec {
+        SignalSpec {
+            rate: u32::try_from(self.output_rate).unwrap(),
+            channels: channel_layout(self.channels),
+        }
+    }
+}
+
+impl Drop for VorbisEncoder {
+    /// Writes the end of the stream
+    fn drop(&mut self) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+
+        let bytes = match finish_vorbis(&mut self.encoder.lock().unwrap()) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("Failed to finish the Vorbis stream: {e:?}");
+                return;
+            }
+        };
+
+        if let Err(e) = writer.write_all(&bytes) {
+            log::error!("Failed to write the end of the Vorbis stream: {e:?}");
+        }
+    }
+}
+
+impl AudioDecode for VorbisEncoder {
+    fn decoded(
+        &mut self,
+        decoded: AudioBuffer<f32>,
+        _packet: &Packet,
+        _track: &Track,
+    ) -> Result<(), AudioDecodeError> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+
+        let bytes = self.encode(decoded).map_err(|e| {
+            AudioDecodeError::IO(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to encode: {e:?}"),
+            ))
+        })?;
+
+        if let Some(writer) = self.writer.as_mut() {
+            let mut count = 0;
+            loop {
+                count += match writer.write(&bytes[count..]) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        log::error!("Failed to write: {e:?}");
+                        return Err(AudioDecodeError::StreamClosed);
+                    }
+                };
+                if count >= bytes.len() {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AudioWrite for VorbisEncoder {
+    fn write(&mut self, decoded: AudioBuffer<f32>) -> Result<usize, AudioOutputError> {
+        if self.writer.is_none() {
+            return Ok(0);
+        }
+
+        let bytes = self.encode(decoded)?;
+
+        if let Some(writer) = self.writer.as_mut() {
+            let mut count = 0;
+            loop {
+                count += match writer.write(&bytes[count..]) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        log::error!("Failed to write: {e:?}");
+                        return Err(AudioOutputError::StreamClosed);
+                    }
+                };
+                if count >= bytes.len() {
+                    break;
+                }
+            }
+        }
+
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> Result<(), AudioOutputError> {
+        Ok(())
+    }
+}
+
+#[must_use]
+pub fn encode_vorbis_stream(path: &str) -> ByteStream {
+    let writer = ByteWriter::default();
+    let stream = writer.stream();
+
+    encode_vorbis_spawn(path, writer);
+
+    stream
+}
+
+pub fn encode_vorbis_spawn<T: std::io::Write + Send + Sync + Clone + 'static>(
+    path: &str,
+    writer: T,
+) -> tokio::task::JoinHandle<()> {
+    let path = path.to_string();
+    moosicbox_task::spawn_blocking("audio_decoder: encode_vorbis", move || {
+        encode_vorbis(&path, writer);
+    })
+}
+
+pub fn encode_vorbis<T: std::io::Write + Send + Sync + Clone + 'static>(path: &str, writer: T) {
+    let mut audio_decode_handler =
+        AudioDecodeHandler::new().with_output(Box::new(move |spec, duration| {
+            Ok(Box::new(
+                VorbisEncoder::with_writer(writer.clone()).open(spec, duration),
+            ))
+        }));
+
+    if let Err(err) = decode_file_path_str(path, &mut audio_decode_handler, true, true, None, None)
+    {
+        log::error!("Failed to encode to vorbis: {err:?}");
+    }
+}
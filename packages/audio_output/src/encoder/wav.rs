@@ -0,0 +1,320 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::sync::RwLock;
+
+use bytes::Bytes;
+use moosicbox_audio_decoder::{
+    decode_file_path_str, AudioDecode, AudioDecodeError, AudioDecodeHandler,
+};
+use moosicbox_audio_encoder::wav::{
+    encoder_wav, encoder_wav_with_settings, wav_header, Encoder, UNKNOWN_DATA_SIZE,
+};
+use moosicbox_stream_utils::{ByteStream, ByteWriter};
+use symphonia::core::{
+    audio::{AudioBuffer, Signal, SignalSpec},
+    formats::{Packet, Track},
+    units::Duration,
+};
+
+use crate::{to_samples, AudioOutputError, AudioWrite};
+use moosicbox_resampler::Resampler;
+
+use super::{channel_layout, remix, AudioEncoder, EncoderSettings};
+
+pub struct WavEncoder {
+    resampler: Option<RwLock<Resampler<i32>>>,
+    input_rate: Option<u32>,
+    resample_rate: Option<u32>,
+    output_rate: usize,
+    channels: usize,
+    duration: Option<Duration>,
+    writer: Option<Box<dyn std::io::Write + Send + Sync>>,
+    header_written: bool,
+    settings: EncoderSettings,
+    encoder: Encoder,
+}
+
+impl WavEncoder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            resampler: None,
+            input_rate: None,
+            resample_rate: None,
+            output_rate: 44100,
+            channels: 2,
+            duration: None,
+            writer: None,
+            header_written: false,
+            settings: EncoderSettings::default(),
+            encoder: encoder_wav(),
+        }
+    }
+
+    pub fn with_writer<W: std::io::Write + Send + Sync + 'static>(writer: W) -> Self {
+        Self {
+            resampler: None,
+            input_rate: None,
+            resample_rate: None,
+            output_rate: 44100,
+            channels: 2,
+            duration: None,
+            writer: Some(Box::new(writer)),
+            header_written: false,
+            settings: EncoderSettings::default(),
+            encoder: encoder_wav(),
+        }
+    }
+
+    /// Recreates the encoder with the `settings`. Has to be called before the
+    /// encoder is opened.
+    #[must_use]
+    pub fn with_settings(mut self, settings: &EncoderSettings) -> Self {
+        self.output_rate = settings
+            .sample_rate
+            .map_or(self.output_rate, |x| x as usize);
+        self.channels = settings
+            .channels
+            .map_or(self.channels, |x| usize::from(x.clamp(1, 2)));
+        self.settings = *settings;
+        self.encoder = encoder_wav_with_settings(settings);
+        self
+    }
+
+    /// Encodes at the `rate` of the source, unless the settings set the
+    /// sample rate. Has no effect once the header has been written.
+    fn init_encoder(&mut self, rate: u32) {
+        if self.header_written {
+            return;
+        }
+
+        let sample_rate = self.settings.sample_rate.unwrap_or(rate);
+        self.output_rate = sample_rate as usize;
+        self.encoder = encoder_wav_with_settings(&EncoderSettings {
+            sample_rate: Some(sample_rate),
+            channels: u8::try_from(self.channels).ok(),
+            ..self.settings
+        });
+    }
+
+    pub fn init_resampler(&mut self, spec: &SignalSpec, duration: Duration) -> &Self {
+        self.input_rate.replace(spec.rate);
+        self.duration.replace(duration);
+
+        if self.resample_rate.is_none_or(|r| r != spec.rate)
+            && self.output_rate != spec.rate as usize
+        {
+            log::debug!(
+                "Initializing resampler with rate={} duration={}",
+                spec.rate,
+                duration,
+            );
+            self.resample_rate.replace(spec.rate);
+            self.resampler.replace(RwLock::new(Resampler::new(
+                *spec,
+                self.output_rate,
+                duration,
+            )));
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn open(mut self, spec: SignalSpec, duration: Duration) -> Self {
+        self.init_encoder(spec.rate);
+        self.init_resampler(
+            &SignalSpec::new(spec.rate, channel_layout(self.channels)),
+            duration,
+        );
+        self
+    }
+
+    /// Encodes the samples, preceded by the RIFF header in the first output.
+    /// The length of the stream isn't known up front, so the header declares
+    /// an unknown data size.
+    fn encode_output(&mut self, buf: &[i32]) -> Bytes {
+        let samples = moosicbox_audio_encoder::wav::encode_wav(&self.encoder, buf);
+
+        if self.header_written {
+            return samples.into();
+        }
+
+        self.header_written = true;
+
+        [&wav_header(&self.encoder, UNKNOWN_DATA_SIZE)[..], &samples]
+            .concat()
+            .into()
+    }
+
+    fn resample_if_needed(
+        &mut self,
+        decoded: &AudioBuffer<f32>,
+    ) -> Result<Vec<i32>, AudioOutputError> {
+        let spec = decoded.spec();
+        let duration = decoded.capacity() as u64;
+
+        self.init_resampler(spec, duration);
+
+        if let Some(resampler) = &self.resampler {
+            log::debug!(
+                "Resampling input_rate={:?} output_rate={} duration={:?}",
+                self.input_rate,
+                self.output_rate,
+                self.duration
+            );
+
+            let mut resampler = resampler.write().unwrap();
+
+            Ok(resampler
+                .resample(decoded)
+                .ok_or(AudioOutputError::StreamEnd)?
+                .to_vec())
+        } else {
+            log::debug!(
+                "Passing through audio frames={} duration={duration} rate={} channels={} channels_count={}",
+                decoded.frames(),
+                spec.rate,
+                spec.channels,
+                spec.channels.count(),
+            );
+            Ok(to_samples(decoded))
+        }
+    }
+}
+
+impl Default for WavEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioEncoder for WavEncoder {
+    fn encode(&mut self, decoded: AudioBuffer<f32>) -> Result<Bytes, AudioOutputError> {
+        log::debug!("WavEncoder encode {} frames", decoded.frames());
+
+        self.init_encoder(decoded.spec().rate);
+        let decoded = remix(decoded, self.channels);
+        let decoded = self.resample_if_needed(&decoded)?;
+
+        Ok(self.encode_output(&decoded))
+    }
+
+    fn spec(&self) -> SignalSpec {
+        SignalSpec {
+            rate: u32::try_from(self.output_rate).unwrap(),
+            channels: channel_layout(self.channels),
+        }
+    }
+}
+
+impl AudioDecode for WavEncoder {
+    fn decoded(
+        &mut self,
+        decoded: AudioBuffer<f32>,
+        _packet: &Packet,
+        track: &Track,
+    ) -> Result<(), AudioDecodeError> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+
+        // Keep the bit depth of the source unless the settings set one
+        if self.settings.bits_per_sample.is_none() {
+            self.settings.bits_per_sample = track
+                .codec_params
+                .bits_per_sample
+                .and_then(|x| u8::try_from(x).ok());
+        }
+
+        let bytes = self.encode(decoded).map_err(|e| {
+            AudioDecodeError::IO(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to encode: {e:?}"),
+            ))
+        })?;
+
+        if let Some(writer) = self.writer.as_mut() {
+            let mut count = 0;
+            loop {
+                count += match writer.write(&bytes[count..]) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        log::error!("Failed to write: {e:?}");
+                        return Err(AudioDecodeError::StreamClosed);
+                    }
+                };
+                if count >= bytes.len() {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AudioWrite for WavEncoder {
+    fn write(&mut self, decoded: AudioBuffer<f32>) -> Result<usize, AudioOutputError> {
+        if self.writer.is_none() {
+            return Ok(0);
+        }
+
+        let bytes = self.encode(decoded)?;
+
+        if let Some(writer) = self.writer.as_mut() {
+            let mut count = 0;
+            loop {
+                count += match writer.write(&bytes[count..]) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        log::error!("Failed to write: {e:?}");
+                        return Err(AudioOutputError::StreamClosed);
+                    }
+                };
+                if count >= bytes.len() {
+                    break;
+                }
+            }
+        }
+
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> Result<(), AudioOutputError> {
+        Ok(())
+    }
+}
+
+#[must_use]
+pub fn encode_wav_stream(path: &str) -> ByteStream {
+    let writer = ByteWriter::default();
+    let stream = writer.stream();
+
+    encode_wav_spawn(path, writer);
+
+    stream
+}
+
+pub fn encode_wav_spawn<T: std::io::Write + Send + Sync + Clone + 'static>(
+    path: &str,
+    writer: T,
+) -> tokio::task::JoinHandle<()> {
+    let path = path.to_string();
+    moosicbox_task::spawn_blocking("audio_decoder: encode_wav", move || {
+        encode_wav(&path, writer);
+    })
+}
+
+pub fn encode_wav<T: std::io::Write + Send + Sync + Clone + 'static>(path: &str, writer: T) {
+    let mut audio_decode_handler =
+        AudioDecodeHandler::new().with_output(Box::new(move |spec, duration| {
+            Ok(Box::new(
+                WavEncoder::with_writer(writer.clone()).open(spec, duration),
+            ))
+        }));
+
+    if let Err(err) = decode_file_path_str(path, &mut audio_decode_handler, true, true, None, None)
+    {
+        log::error!("Failed to encode to wav: {err:?}");
+    }
+}
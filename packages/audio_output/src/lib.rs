@@ -1,7 +1,10 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 
-use std::sync::{Arc, LazyLock};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, LazyLock},
+};
 
 use moosicbox_audio_decoder::{AudioDecode, AudioDecodeError};
 use moosicbox_resampler::{to_audio_buffer, Resampler};
@@ -25,12 +28,26 @@ pub mod pulseaudio;
 #[cfg(feature = "cpal")]
 pub mod cpal;
 
+/// The format of the samples a source was decoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceFormat {
+    pub spec: SignalSpec,
+    /// The bit depth of integer samples, or `None` for floating point samples
+    pub bits_per_sample: Option<u32>,
+}
+
 pub struct AudioOutput {
     pub id: String,
     pub name: String,
     pub spec: SignalSpec,
-    resampler: Option<Resampler<f32>>,
+    resampler: Option<(SignalSpec, Resampler<f32>)>,
     writer: Box<dyn AudioWrite>,
+    /// The `spec` the writer is opened at outside of bit-perfect playback
+    default_spec: SignalSpec,
+    get_writer: Option<Arc<std::sync::Mutex<GetWriter>>>,
+    get_native_writer: Option<Arc<std::sync::Mutex<GetNativeWriter>>>,
+    /// The source format the writer is currently opened at, during bit-perfect playback
+    native: Option<SourceFormat>,
 }
 
 impl AudioOutput {
@@ -42,14 +59,110 @@ impl AudioOutput {
             spec,
             resampler: None,
             writer,
+            default_spec: spec,
+            get_writer: None,
+            get_native_writer: None,
+            native: None,
+        }
+    }
+
+    /// The source format the output is opened at, if it is playing bit-perfect
+    #[must_use]
+    pub const fn source_format(&self) -> Option<SourceFormat> {
+        self.native
+    }
+
+    /// Re-opens the output at the native format of the source, so the samples reach the device
+    /// without being resampled, or back at its default `spec` with `None`. The output is only
+    /// re-opened when the format changes, after the audio already written to it has played.
+    ///
+    /// Falls back to the default `spec` if the output isn't opted into bit-perfect playback or
+    /// the device doesn't support the format.
+    ///
+    /// Returns whether the output is opened at the native format.
+    ///
+    /// # Errors
+    ///
+    /// * If the output fails to drain or re-open
+    ///
+    /// # Panics
+    ///
+    /// * If the writer `Mutex`es are poisoned
+    pub fn set_source_format(
+        &mut self,
+        format: Option<SourceFormat>,
+    ) -> Result<bool, AudioOutputError> {
+        if self.native == format {
+            return Ok(format.is_some());
+        }
+        let (Some(get_writer), Some(get_native_writer)) =
+            (self.get_writer.clone(), self.get_native_writer.clone())
+        else {
+            return Ok(false);
+        };
+
+        let native = format.and_then(|format| {
+            (get_native_writer.lock().unwrap())(format).map(|get_writer| (format, get_writer))
+        });
+
+        if native.is_none() && self.native.is_none() {
+            log::debug!("audio_output: set_source_format: Unsupported format {format:?}");
+            return Ok(false);
+        }
+
+        self.writer.drain()?;
+        // Devices may not allow opening another stream while the current one is still open
+        self.writer = Box::new(ClosedWriter);
+        self.resampler = None;
+
+        if let Some((format, get_native_writer)) = native {
+            match get_native_writer() {
+                Ok(writer) => {
+                    log::debug!("audio_output: set_source_format: Opened at {format:?}");
+                    self.writer = writer;
+                    self.spec = format.spec;
+                    self.native = Some(format);
+                    return Ok(true);
+                }
+                Err(e) => {
+                    log::warn!(
+                        "audio_output: set_source_format: Failed to open at {format:?}: {e:?}"
+                    );
+                }
+            }
         }
+
+        log::debug!("audio_output: set_source_format: Opened at the default spec");
+        self.writer = (get_writer.lock().unwrap())()?;
+        self.spec = self.default_spec;
+        self.native = None;
+
+        Ok(false)
     }
 
-    fn resample_if_needed(
+    /// Resamples the decoded audio to the `AudioOutput` `spec` rate, if needed. The resampler is
+    /// re-created whenever the decoded audio spec changes, so the same `AudioOutput` can be fed
+    /// consecutive tracks with different sample rates.
+    ///
+    /// # Errors
+    ///
+    /// * `AudioOutputError::StreamEnd` if the resampler has no samples to return
+    pub fn resample_if_needed(
         &mut self,
         decoded: AudioBuffer<f32>,
     ) -> Result<AudioBuffer<f32>, AudioOutputError> {
-        Ok(if let Some(resampler) = &mut self.resampler {
+        if self
+            .resampler
+            .as_ref()
+            .is_some_and(|(spec, _)| spec != decoded.spec())
+        {
+            log::debug!(
+                "audio_output: resample_if_needed: input spec changed, resetting resampler"
+            );
+            self.resampler = None;
+        }
+
+        Ok(if let Some((_, resampler)) = &mut self.resampler {
             // Resampling is required. The resampler will return interleaved samples in the
             // correct sample format.
             let Some(samples) = resampler.resample(&decoded) else {
@@ -66,16 +179,24 @@ impl AudioOutput {
                 decoded.capacity(),
                 duration,
             );
-            self.resampler.replace(Resampler::new(
+            self.resampler.replace((
                 *decoded.spec(),
-                self.spec.rate as usize,
-                duration as u64,
+                Resampler::new(*decoded.spec(), self.spec.rate as usize, duration as u64),
             ));
             self.resample_if_needed(decoded)?
         } else {
             decoded
         })
     }
+
+    /// Writes audio that has already been passed through [`AudioOutput::resample_if_needed`].
+    ///
+    /// # Errors
+    ///
+    /// * If the underlying writer fails to write the audio
+    pub fn write_resampled(&mut self, buf: AudioBuffer<f32>) -> Result<usize, AudioOutputError> {
+        self.writer.write(buf)
+    }
 }
 
 impl AudioWrite for AudioOutput {
@@ -95,6 +216,10 @@ impl AudioWrite for AudioOutput {
     fn flush(&mut self) -> Result<(), AudioOutputError> {
         self.writer.flush()
     }
+
+    fn drain(&mut self) -> Result<(), AudioOutputError> {
+        self.writer.drain()
+    }
 }
 
 impl AudioDecode for AudioOutput {
@@ -120,15 +245,80 @@ impl AudioDecode for AudioOutput {
     }
 }
 
+/// Stands in for the writer while the output is being re-opened.
+struct ClosedWriter;
+
+impl AudioWrite for ClosedWriter {
+    fn write(&mut self, _decoded: AudioBuffer<f32>) -> Result<usize, AudioOutputError> {
+        Err(AudioOutputError::StreamClosed)
+    }
+
+    fn flush(&mut self) -> Result<(), AudioOutputError> {
+        Ok(())
+    }
+}
+
 type InnerType = Box<dyn AudioWrite>;
 pub type GetWriter = Box<dyn Fn() -> Result<InnerType, AudioOutputError> + Send>;
+/// Returns how to open the output at the native format of a source, if the device supports it.
+pub type GetNativeWriter = Box<dyn Fn(SourceFormat) -> Option<GetWriter> + Send>;
+
+/// Reads a comma separated list of output ids from the `name` environment variable.
+fn output_ids_from_env(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .map(|x| {
+            x.split(',')
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The ids of the outputs connected to a DAC that plays DSD over PCM (`DoP`), from the comma
+/// separated `MOOSICBOX_DOP_OUTPUTS`.
+static DOP_OUTPUTS: LazyLock<Vec<String>> =
+    LazyLock::new(|| output_ids_from_env("MOOSICBOX_DOP_OUTPUTS"));
+
+/// The `DoP` setting of the outputs it has been changed for at runtime, taking precedence over
+/// `MOOSICBOX_DOP_OUTPUTS`.
+static DOP_SETTINGS: LazyLock<std::sync::RwLock<BTreeMap<String, bool>>> =
+    LazyLock::new(|| std::sync::RwLock::new(BTreeMap::new()));
+
+/// Enables or disables passing DSD through as `DoP` frames to the output with the `id`.
+///
+/// # Panics
+///
+/// * If the `DOP_SETTINGS` `RwLock` is poisoned
+pub fn set_dop_enabled(id: &str, enabled: bool) {
+    DOP_SETTINGS
+        .write()
+        .unwrap()
+        .insert(id.to_string(), enabled);
+}
+
+/// The ids of the outputs opted into bit-perfect playback, from the comma separated
+/// `MOOSICBOX_BIT_PERFECT_OUTPUTS`.
+static BIT_PERFECT_OUTPUTS: LazyLock<Vec<String>> =
+    LazyLock::new(|| output_ids_from_env("MOOSICBOX_BIT_PERFECT_OUTPUTS"));
 
 #[derive(Clone)]
 pub struct AudioOutputFactory {
     pub id: String,
     pub name: String,
     pub spec: SignalSpec,
+    /// Whether DSD can be passed through to the output as `DoP` frames, unless changed with
+    /// `set_dop_enabled`. The frames only reach the DAC intact at the output's `spec` rate.
+    pub dop: bool,
+    /// The bit depth of the integer samples the output is opened with, or `None` for floating
+    /// point or unknown samples.
+    pub bits_per_sample: Option<u32>,
+    /// Whether the output is opened at the native format of each track, with the samples left
+    /// untouched, when the device supports it.
+    pub bit_perfect: bool,
     get_writer: Arc<std::sync::Mutex<GetWriter>>,
+    get_native_writer: Option<Arc<std::sync::Mutex<GetNativeWriter>>>,
 }
 
 impl std::fmt::Debug for AudioOutputFactory {
@@ -137,6 +327,9 @@ impl std::fmt::Debug for AudioOutputFactory {
             .field("id", &self.id)
             .field("name", &self.name)
             .field("spec", &self.spec)
+            .field("dop", &self.dop)
+            .field("bits_per_sample", &self.bits_per_sample)
+            .field("bit_perfect", &self.bit_perfect)
             .field("get_writer", &"{{get_writer}}")
             .finish()
     }
@@ -150,23 +343,91 @@ impl AudioOutputFactory {
         writer: impl (Fn() -> Result<InnerType, AudioOutputError>) + Send + 'static,
     ) -> Self {
         Self {
+            dop: DOP_OUTPUTS.contains(&id),
+            bits_per_sample: None,
+            bit_perfect: BIT_PERFECT_OUTPUTS.contains(&id),
             id,
             name,
             spec,
             get_writer: Arc::new(std::sync::Mutex::new(Box::new(writer))),
+            get_native_writer: None,
         }
     }
 
     #[must_use]
     pub fn new_box(id: String, name: String, spec: SignalSpec, writer: GetWriter) -> Self {
         Self {
+            dop: DOP_OUTPUTS.contains(&id),
+            bits_per_sample: None,
+            bit_perfect: BIT_PERFECT_OUTPUTS.contains(&id),
             id,
             name,
             spec,
             get_writer: Arc::new(std::sync::Mutex::new(writer)),
+            get_native_writer: None,
         }
     }
 
+    #[must_use]
+    pub const fn with_dop(mut self, dop: bool) -> Self {
+        self.dop = dop;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bits_per_sample(mut self, bits_per_sample: Option<u32>) -> Self {
+        self.bits_per_sample = bits_per_sample;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bit_perfect(mut self, bit_perfect: bool) -> Self {
+        self.bit_perfect = bit_perfect;
+        self
+    }
+
+    /// Lets the output be opened at the native format of a source for bit-perfect playback.
+    #[must_use]
+    pub fn with_native_writer(
+        mut self,
+        get_native_writer: impl (Fn(SourceFormat) -> Option<GetWriter>) + Send + 'static,
+    ) -> Self {
+        self.get_native_writer = Some(Arc::new(std::sync::Mutex::new(Box::new(get_native_writer))));
+        self
+    }
+
+    /// Whether tracks are played bit-perfect on the output, when the device supports their
+    /// format.
+    #[must_use]
+    pub const fn supports_bit_perfect(&self) -> bool {
+        self.bit_perfect && self.get_native_writer.is_some()
+    }
+
+    /// Whether DSD is passed through to the output as `DoP` frames.
+    ///
+    /// # Panics
+    ///
+    /// * If the `DOP_SETTINGS` `RwLock` is poisoned
+    #[must_use]
+    pub fn is_dop_enabled(&self) -> bool {
+        DOP_SETTINGS
+            .read()
+            .unwrap()
+            .get(&self.id)
+            .copied()
+            .unwrap_or(self.dop)
+    }
+
+    /// Whether a DSD stream at `sample_rate` can be passed through to the output as `DoP`
+    /// frames, each of which carries 16 DSD bits per channel under an 8 bit marker. The
+    /// markers only survive a stream of integer samples of at least 24 bits.
+    #[must_use]
+    pub fn supports_dop(&self, sample_rate: u32) -> bool {
+        self.is_dop_enabled()
+            && self.bits_per_sample.is_some_and(|bits| bits >= 24)
+            && sample_rate / 16 == self.spec.rate
+    }
+
     /// # Errors
     ///
     /// * If fails to instantiate the `AudioOutput`
@@ -179,12 +440,18 @@ impl TryFrom<AudioOutputFactory> for AudioOutput {
     type Error = AudioOutputError;
 
     fn try_from(value: AudioOutputFactory) -> Result<Self, Self::Error> {
+        let writer = (value.get_writer.lock().unwrap())()?;
+
         Ok(Self {
             id: value.id,
             name: value.name,
             spec: value.spec,
             resampler: None,
-            writer: (value.get_writer.lock().unwrap())()?,
+            writer,
+            default_spec: value.spec,
+            get_native_writer: value.get_native_writer.filter(|_| value.bit_perfect),
+            get_writer: Some(value.get_writer),
+            native: None,
         })
     }
 }
@@ -199,6 +466,13 @@ impl TryFrom<&AudioOutputFactory> for AudioOutput {
             spec: value.spec,
             resampler: None,
             writer: (value.get_writer.lock().unwrap())()?,
+            default_spec: value.spec,
+            get_writer: Some(value.get_writer.clone()),
+            get_native_writer: value
+                .get_native_writer
+                .clone()
+                .filter(|_| value.bit_perfect),
+            native: None,
         })
     }
 }
@@ -213,6 +487,15 @@ pub trait AudioWrite {
     ///
     /// * If fails to flush the `AudioWrite`
     fn flush(&mut self) -> Result<(), AudioOutputError>;
+
+    /// Waits for the audio that has been written to finish playing.
+    ///
+    /// # Errors
+    ///
+    /// * If fails to drain the `AudioWrite`
+    fn drain(&mut self) -> Result<(), AudioOutputError> {
+        Ok(())
+    }
 }
 
 impl AudioDecode for Box<dyn AudioWrite> {
@@ -267,6 +550,8 @@ pub enum AudioOutputError {
     StreamEnd,
     #[error("InterruptError")]
     Interrupt,
+    #[error("Failed to create encoder: {0}")]
+    Encoder(String),
     #[error(transparent)]
     IO(#[from] std::io::Error),
     #[cfg(feature = "cpal")]
@@ -573,3 +858,196 @@ impl Default for AudioOutputScanner {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use symphonia::core::audio::Signal as _;
+
+    use super::*;
+
+    fn factory(id: &str) -> AudioOutputFactory {
+        AudioOutputFactory::new(
+            id.to_string(),
+            id.to_string(),
+            SignalSpec::new(176_400, Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+            || Err(AudioOutputError::NoOutputs),
+        )
+    }
+
+    #[test]
+    fn supports_dop_for_a_24_bit_stream_at_a_sixteenth_of_the_dsd_rate() {
+        let output = factory("dop-24")
+            .with_dop(true)
+            .with_bits_per_sample(Some(24));
+
+        assert!(output.supports_dop(2_822_400));
+        assert!(!output.supports_dop(5_644_800));
+    }
+
+    #[test]
+    fn doesnt_support_dop_for_a_16_bit_or_unknown_stream() {
+        let output = factory("dop-16")
+            .with_dop(true)
+            .with_bits_per_sample(Some(16));
+        assert!(!output.supports_dop(2_822_400));
+
+        let output = factory("dop-unknown").with_dop(true);
+        assert!(!output.supports_dop(2_822_400));
+    }
+
+    #[test]
+    fn doesnt_support_dop_when_disabled() {
+        let output = factory("dop-disabled").with_bits_per_sample(Some(32));
+
+        assert!(!output.supports_dop(2_822_400));
+    }
+
+    #[test]
+    fn dop_setting_overrides_the_output_default() {
+        let output = factory("dop-override")
+            .with_dop(true)
+            .with_bits_per_sample(Some(32));
+
+        set_dop_enabled("dop-override", false);
+        assert!(!output.is_dop_enabled());
+        assert!(!output.supports_dop(2_822_400));
+
+        set_dop_enabled("dop-override", true);
+        assert!(output.supports_dop(2_822_400));
+    }
+
+    /// Counts how often the default and native writers are opened and drained.
+    #[derive(Default)]
+    struct Counts {
+        default_opens: AtomicUsize,
+        native_opens: AtomicUsize,
+        drains: AtomicUsize,
+    }
+
+    struct CountingWriter(Arc<Counts>);
+
+    impl AudioWrite for CountingWriter {
+        fn write(&mut self, decoded: AudioBuffer<f32>) -> Result<usize, AudioOutputError> {
+            Ok(decoded.frames())
+        }
+
+        fn flush(&mut self) -> Result<(), AudioOutputError> {
+            Ok(())
+        }
+
+        fn drain(&mut self) -> Result<(), AudioOutputError> {
+            self.0.drains.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    const DEFAULT_RATE: u32 = 48_000;
+
+    fn stereo(rate: u32) -> SignalSpec {
+        SignalSpec::new(rate, Channels::FRONT_LEFT | Channels::FRONT_RIGHT)
+    }
+
+    fn source(rate: u32) -> SourceFormat {
+        SourceFormat {
+            spec: stereo(rate),
+            bits_per_sample: Some(24),
+        }
+    }
+
+    /// An output whose device supports the 96kHz native format, and fails to open at 88.2kHz.
+    fn output(bit_perfect: bool) -> (AudioOutput, Arc<Counts>) {
+        let counts = Arc::new(Counts::default());
+
+        let factory = AudioOutputFactory::new(
+            "bit-perfect".to_string(),
+            "bit-perfect".to_string(),
+            stereo(DEFAULT_RATE),
+            {
+                let counts = counts.clone();
+                move || {
+                    counts.default_opens.fetch_add(1, Ordering::SeqCst);
+                    Ok(Box::new(CountingWriter(counts.clone())))
+                }
+            },
+        )
+        .with_bit_perfect(bit_perfect)
+        .with_native_writer({
+            let counts = counts.clone();
+            move |format| {
+                let counts = counts.clone();
+                match format.spec.rate {
+                    96_000 => Some(Box::new(move || {
+                        counts.native_opens.fetch_add(1, Ordering::SeqCst);
+                        Ok(Box::new(CountingWriter(counts.clone())) as Box<dyn AudioWrite>)
+                    }) as GetWriter),
+                    88_200 => Some(Box::new(|| Err(AudioOutputError::OpenStream)) as GetWriter),
+                    _ => None,
+                }
+            }
+        });
+
+        (factory.try_into_output().unwrap(), counts)
+    }
+
+    #[test]
+    fn set_source_format_reopens_at_a_supported_native_format() {
+        let (mut output, counts) = output(true);
+
+        assert!(output.set_source_format(Some(source(96_000))).unwrap());
+        assert_eq!(output.spec.rate, 96_000);
+        assert_eq!(output.source_format(), Some(source(96_000)));
+        assert_eq!(counts.drains.load(Ordering::SeqCst), 1);
+        assert_eq!(counts.native_opens.load(Ordering::SeqCst), 1);
+
+        // The same format again leaves the output as it is
+        assert!(output.set_source_format(Some(source(96_000))).unwrap());
+        assert_eq!(counts.drains.load(Ordering::SeqCst), 1);
+        assert_eq!(counts.native_opens.load(Ordering::SeqCst), 1);
+
+        // Back to the default spec once bit-perfect playback ends
+        assert!(!output.set_source_format(None).unwrap());
+        assert_eq!(output.spec.rate, DEFAULT_RATE);
+        assert_eq!(output.source_format(), None);
+        assert_eq!(counts.drains.load(Ordering::SeqCst), 2);
+        assert_eq!(counts.default_opens.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn set_source_format_keeps_the_default_writer_for_an_unsupported_format() {
+        let (mut output, counts) = output(true);
+
+        assert!(!output.set_source_format(Some(source(192_000))).unwrap());
+        assert_eq!(output.spec.rate, DEFAULT_RATE);
+        assert_eq!(output.source_format(), None);
+        assert_eq!(counts.drains.load(Ordering::SeqCst), 0);
+        assert_eq!(counts.default_opens.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn set_source_format_falls_back_to_the_default_spec_when_the_native_format_fails_to_open() {
+        let (mut output, counts) = output(true);
+        assert!(output.set_source_format(Some(source(96_000))).unwrap());
+
+        assert!(!output.set_source_format(Some(source(88_200))).unwrap());
+        assert_eq!(output.spec.rate, DEFAULT_RATE);
+        assert_eq!(output.source_format(), None);
+        assert_eq!(counts.default_opens.load(Ordering::SeqCst), 2);
+
+        // The output still plays at the default spec
+        let mut buf = AudioBuffer::<f32>::new(16, stereo(DEFAULT_RATE));
+        buf.render_reserved(Some(16));
+        assert_eq!(output.write(buf).unwrap(), 16);
+    }
+
+    #[test]
+    fn set_source_format_is_a_no_op_unless_opted_into_bit_perfect_playback() {
+        let (mut output, counts) = output(false);
+
+        assert!(!output.set_source_format(Some(source(96_000))).unwrap());
+        assert_eq!(output.spec.rate, DEFAULT_RATE);
+        assert_eq!(counts.native_opens.load(Ordering::SeqCst), 0);
+        assert_eq!(counts.drains.load(Ordering::SeqCst), 0);
+    }
+}
@@ -67,7 +67,15 @@ impl PulseAudioOutput {
     }
 
     fn init_sample_buf(&mut self, duration: Duration) -> &mut RawSampleBuffer<f32> {
-        if self.sample_buf.is_none() {
+        // The buffer is re-created when a larger packet comes through, e.g. when the output is
+        // reused across tracks with different packet sizes
+        #[allow(clippy::cast_possible_truncation)]
+        let samples = duration as usize * self.spec.channels.count();
+        if self
+            .sample_buf
+            .as_ref()
+            .is_none_or(|x| x.capacity() < samples)
+        {
             let spec = self.spec;
             // An interleaved buffer is required to send data to PulseAudio. Use a SampleBuffer to
             // move data between Symphonia AudioBuffers and the byte buffers required by PulseAudio.
@@ -1,14 +1,18 @@
 use actix_web::{
     dev::{ServiceFactory, ServiceRequest},
-    error::ErrorInternalServerError,
+    error::{ErrorBadRequest, ErrorInternalServerError},
     route,
     web::{self, Json},
     Result, Scope,
 };
 use moosicbox_database::config::ConfigDatabase;
+use moosicbox_music_models::EncodingSettings;
 use serde::Deserialize;
 
-use crate::api::models::ApiProfile;
+use crate::{
+    api::models::{ApiProfile, ApiTranscodingProfile},
+    db::SetTranscodingProfile,
+};
 
 pub mod models;
 
@@ -20,6 +24,9 @@ pub fn bind_services<
     scope
         .service(get_profiles_endpoint)
         .service(create_profile_endpoint)
+        .service(get_transcoding_profiles_endpoint)
+        .service(upsert_transcoding_profile_endpoint)
+        .service(delete_transcoding_profile_endpoint)
 }
 
 #[cfg(feature = "openapi")]
@@ -29,6 +36,9 @@ pub fn bind_services<
     paths(
         get_profiles_endpoint,
         create_profile_endpoint,
+        get_transcoding_profiles_endpoint,
+        upsert_transcoding_profile_endpoint,
+        delete_transcoding_profile_endpoint,
     ),
     components(schemas())
 )]
@@ -98,3 +108,140 @@ pub async fn create_profile_endpoint(
             .into(),
     ))
 }
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Config"],
+        get,
+        path = "/transcoding-profiles",
+        description = "Get list of transcoding profiles",
+        params(),
+        responses(
+            (
+                status = 200,
+                description = "The list of transcoding profiles",
+                body = Vec<ApiTranscodingProfile>,
+            )
+        )
+    )
+)]
+#[route("/transcoding-profiles", method = "GET")]
+pub async fn get_transcoding_profiles_endpoint(
+    db: ConfigDatabase,
+) -> Result<Json<Vec<ApiTranscodingProfile>>> {
+    Ok(Json(
+        crate::db::get_transcoding_profiles(&db)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<ApiTranscodingProfile>>(),
+    ))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpsertTranscodingProfileQuery {
+    name: String,
+    format: String,
+    bitrate: Option<u32>,
+    vbr_quality: Option<u8>,
+    sample_rate: Option<u32>,
+    channels: Option<u8>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Config"],
+        post,
+        path = "/transcoding-profiles",
+        description = "Create or update a transcoding profile",
+        params(
+            ("name" = String, Query, description = "The name of the profile"),
+            ("format" = String, Query, description = "The audio format to transcode to"),
+            ("bitrate" = Option<u32>, Query, description = "The constant bitrate, in kbps"),
+            ("vbrQuality" = Option<u8>, Query, description = "The variable bitrate quality, from 0 (best) to 9 (worst)"),
+            ("sampleRate" = Option<u32>, Query, description = "The sample rate to transcode to"),
+            ("channels" = Option<u8>, Query, description = "The number of channels to transcode to"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The transcoding profile",
+                body = ApiTranscodingProfile,
+            )
+        )
+    )
+)]
+#[route("/transcoding-profiles", method = "POST")]
+pub async fn upsert_transcoding_profile_endpoint(
+    query: web::Query<UpsertTranscodingProfileQuery>,
+    db: ConfigDatabase,
+) -> Result<Json<ApiTranscodingProfile>> {
+    let query = query.into_inner();
+
+    EncodingSettings {
+        bitrate: query.bitrate,
+        vbr_quality: query.vbr_quality,
+        sample_rate: query.sample_rate,
+        channels: query.channels,
+    }
+    .validate()
+    .map_err(ErrorBadRequest)?;
+
+    Ok(Json(
+        crate::db::upsert_transcoding_profile(
+            &db,
+            SetTranscodingProfile {
+                name: query.name,
+                format: query.format.to_uppercase(),
+                bitrate: query.bitrate,
+                vbr_quality: query.vbr_quality,
+                sample_rate: query.sample_rate,
+                channels: query.channels,
+            },
+        )
+        .await
+        .map_err(ErrorInternalServerError)?
+        .into(),
+    ))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteTranscodingProfileQuery {
+    name: String,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Config"],
+        delete,
+        path = "/transcoding-profiles",
+        description = "Delete a transcoding profile",
+        params(
+            ("name" = String, Query, description = "The name of the profile"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The deleted transcoding profiles",
+                body = Vec<ApiTranscodingProfile>,
+            )
+        )
+    )
+)]
+#[route("/transcoding-profiles", method = "DELETE")]
+pub async fn delete_transcoding_profile_endpoint(
+    query: web::Query<DeleteTranscodingProfileQuery>,
+    db: ConfigDatabase,
+) -> Result<Json<Vec<ApiTranscodingProfile>>> {
+    Ok(Json(
+        crate::db::delete_transcoding_profile(&db, &query.name)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<ApiTranscodingProfile>>(),
+    ))
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::db::models::Profile;
+use crate::db::models::{Profile, TranscodingProfile};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -13,3 +13,32 @@ impl From<Profile> for ApiProfile {
         Self { name: value.name }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiTranscodingProfile {
+    pub name: String,
+    pub format: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bitrate: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vbr_quality: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u8>,
+}
+
+impl From<TranscodingProfile> for ApiTranscodingProfile {
+    fn from(value: TranscodingProfile) -> Self {
+        Self {
+            name: value.name,
+            format: value.format,
+            bitrate: value.bitrate,
+            vbr_quality: value.vbr_quality,
+            sample_rate: value.sample_rate,
+            channels: value.channels,
+        }
+    }
+}
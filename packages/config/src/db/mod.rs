@@ -87,3 +87,78 @@ pub(crate) async fn get_profiles(
 ) -> Result<Vec<models::Profile>, DatabaseFetchError> {
     Ok(db.select("profiles").execute(db).await?.to_value_type()?)
 }
+
+#[derive(Debug, Clone)]
+pub struct SetTranscodingProfile {
+    pub name: String,
+    pub format: String,
+    pub bitrate: Option<u32>,
+    pub vbr_quality: Option<u8>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_transcoding_profiles(
+    db: &ConfigDatabase,
+) -> Result<Vec<models::TranscodingProfile>, DatabaseFetchError> {
+    Ok(db
+        .select("transcoding_profiles")
+        .execute(db)
+        .await?
+        .to_value_type()?)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_transcoding_profile(
+    db: &ConfigDatabase,
+    name: &str,
+) -> Result<Option<models::TranscodingProfile>, DatabaseFetchError> {
+    Ok(db
+        .select("transcoding_profiles")
+        .where_eq("name", name)
+        .execute_first(db)
+        .await?
+        .map(|x| x.to_value_type())
+        .transpose()?)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn upsert_transcoding_profile(
+    db: &ConfigDatabase,
+    profile: SetTranscodingProfile,
+) -> Result<models::TranscodingProfile, DatabaseFetchError> {
+    Ok(db
+        .upsert("transcoding_profiles")
+        .where_eq("name", profile.name.clone())
+        .value("name", profile.name)
+        .value("format", profile.format)
+        .value("bitrate", profile.bitrate)
+        .value("vbr_quality", profile.vbr_quality)
+        .value("sample_rate", profile.sample_rate)
+        .value("channels", profile.channels)
+        .execute_first(db)
+        .await?
+        .to_value_type()?)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn delete_transcoding_profile(
+    db: &ConfigDatabase,
+    name: &str,
+) -> Result<Vec<models::TranscodingProfile>, DatabaseFetchError> {
+    Ok(db
+        .delete("transcoding_profiles")
+        .where_eq("name", name)
+        .execute(db)
+        .await?
+        .to_value_type()?)
+}
@@ -13,3 +13,27 @@ impl ToValueType<Profile> for &moosicbox_database::Row {
         })
     }
 }
+
+pub struct TranscodingProfile {
+    pub id: u64,
+    pub name: String,
+    pub format: String,
+    pub bitrate: Option<u32>,
+    pub vbr_quality: Option<u8>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+}
+
+impl ToValueType<TranscodingProfile> for &moosicbox_database::Row {
+    fn to_value_type(self) -> Result<TranscodingProfile, ParseError> {
+        Ok(TranscodingProfile {
+            id: self.to_value("id")?,
+            name: self.to_value("name")?,
+            format: self.to_value("format")?,
+            bitrate: self.to_value("bitrate")?,
+            vbr_quality: self.to_value("vbr_quality")?,
+            sample_rate: self.to_value("sample_rate")?,
+            channels: self.to_value("channels")?,
+        })
+    }
+}
@@ -17,8 +17,8 @@ use std::{num::TryFromIntError, sync::Arc};
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use query::{
-    DeleteStatement, InsertStatement, SelectQuery, UpdateStatement, UpsertMultiStatement,
-    UpsertStatement,
+    DeleteStatement, InsertStatement, SelectQuery, TransactionStatement, UpdateStatement,
+    UpsertMultiStatement, UpsertStatement,
 };
 use thiserror::Error;
 
@@ -315,6 +315,18 @@ pub trait Database: Send + Sync + std::fmt::Debug {
         statement: &DeleteStatement<'_>,
     ) -> Result<Option<Row>, DatabaseError>;
 
+    /// Executes the `statements` in order in a single transaction, so either all of them take
+    /// effect or none of them do.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if any of the statements failed to execute, in which case the
+    /// transaction is rolled back.
+    async fn exec_transaction(
+        &self,
+        statements: &[TransactionStatement<'_>],
+    ) -> Result<(), DatabaseError>;
+
     /// # Errors
     ///
     /// Will return `Err` if the close failed to trigger.
@@ -11,12 +11,16 @@ use thiserror::Error;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     pin,
+    sync::RwLock,
     task::JoinHandle,
 };
-use tokio_postgres::{types::IsNull, Client, Row, RowStream};
+use tokio_postgres::{types::IsNull, Client, GenericClient, Row, RowStream};
 
 use crate::{
-    query::{BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection},
+    query::{
+        BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection,
+        TransactionStatement,
+    },
     Database, DatabaseError, DatabaseValue, DeleteStatement, InsertStatement, SelectQuery,
     UpdateStatement, UpsertMultiStatement, UpsertStatement,
 };
@@ -120,6 +124,12 @@ impl<T: Expression + ?Sized> ToSql for T {
                     )
                 }
             }
+            ExpressionType::Like(value) => format!(
+                "({} LIKE {} ESCAPE '{}')",
+                value.left.to_sql(index),
+                value.right.to_sql(index),
+                crate::query::LIKE_ESCAPE
+            ),
             ExpressionType::Join(value) => format!(
                 "{} JOIN {} ON {}",
                 if value.left { "LEFT" } else { "" },
@@ -206,12 +216,11 @@ impl<T: Expression + ?Sized> ToSql for T {
                     }
                 });
 
-                let limit = value
-                    .limit
-                    .map_or_else(String::new, |limit| format!("LIMIT {limit}"));
+                let group_by = build_group_by_clause(value.group_by);
+                let limit = build_limit_clause(value.limit, value.offset);
 
                 format!(
-                    "SELECT {} {} FROM {} {} {} {} {}",
+                    "SELECT {} {} FROM {} {} {} {} {} {}",
                     if value.distinct { "DISTINCT" } else { "" },
                     value
                         .columns
@@ -222,6 +231,7 @@ impl<T: Expression + ?Sized> ToSql for T {
                     value.table_name,
                     joins,
                     where_clause,
+                    group_by,
                     sort_clause,
                     limit
                 )
@@ -246,7 +256,8 @@ impl<T: Expression + ?Sized> ToSql for T {
 
 #[allow(clippy::module_name_repetitions)]
 pub struct PostgresDatabase {
-    client: Client,
+    /// Locked for writing while a transaction runs, so no other statements run inside of it
+    client: RwLock<Client>,
     handle: JoinHandle<std::result::Result<(), tokio_postgres::Error>>,
 }
 
@@ -258,7 +269,10 @@ impl PostgresDatabase {
     ) -> Self {
         let handle = moosicbox_task::spawn("Postgres database connection", connection);
 
-        Self { client, handle }
+        Self {
+            client: RwLock::new(client),
+            handle,
+        }
     }
 }
 
@@ -305,14 +319,16 @@ impl From<PostgresDatabaseError> for DatabaseError {
 impl Database for PostgresDatabase {
     async fn query(&self, query: &SelectQuery<'_>) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(select(
-            &self.client,
+            &*self.client.read().await,
             query.table_name,
             query.distinct,
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
             query.limit,
+            query.offset,
         )
         .await?)
     }
@@ -322,12 +338,13 @@ impl Database for PostgresDatabase {
         query: &SelectQuery<'_>,
     ) -> Result<Option<crate::Row>, DatabaseError> {
         Ok(find_row(
-            &self.client,
+            &*self.client.read().await,
             query.table_name,
             query.distinct,
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
         )
         .await?)
@@ -338,7 +355,7 @@ impl Database for PostgresDatabase {
         statement: &DeleteStatement<'_>,
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(delete(
-            &self.client,
+            &*self.client.read().await,
             statement.table_name,
             statement.filters.as_deref(),
             statement.limit,
@@ -351,7 +368,7 @@ impl Database for PostgresDatabase {
         statement: &DeleteStatement<'_>,
     ) -> Result<Option<crate::Row>, DatabaseError> {
         Ok(delete(
-            &self.client,
+            &*self.client.read().await,
             statement.table_name,
             statement.filters.as_deref(),
             Some(1),
@@ -365,7 +382,12 @@ impl Database for PostgresDatabase {
         &self,
         statement: &InsertStatement<'_>,
     ) -> Result<crate::Row, DatabaseError> {
-        Ok(insert_and_get_row(&self.client, statement.table_name, &statement.values).await?)
+        Ok(insert_and_get_row(
+            &*self.client.read().await,
+            statement.table_name,
+            &statement.values,
+        )
+        .await?)
     }
 
     async fn exec_update(
@@ -373,7 +395,7 @@ impl Database for PostgresDatabase {
         statement: &UpdateStatement<'_>,
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(update_and_get_rows(
-            &self.client,
+            &*self.client.read().await,
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -387,7 +409,7 @@ impl Database for PostgresDatabase {
         statement: &UpdateStatement<'_>,
     ) -> Result<Option<crate::Row>, DatabaseError> {
         Ok(update_and_get_row(
-            &self.client,
+            &*self.client.read().await,
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -401,7 +423,7 @@ impl Database for PostgresDatabase {
         statement: &UpsertStatement<'_>,
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(upsert(
-            &self.client,
+            &*self.client.read().await,
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -415,7 +437,7 @@ impl Database for PostgresDatabase {
         statement: &UpsertStatement<'_>,
     ) -> Result<crate::Row, DatabaseError> {
         Ok(upsert_and_get_row(
-            &self.client,
+            &*self.client.read().await,
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -430,7 +452,7 @@ impl Database for PostgresDatabase {
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         let rows = {
             upsert_multi(
-                &self.client,
+                &*self.client.read().await,
                 statement.table_name,
                 statement
                     .unique
@@ -443,12 +465,80 @@ impl Database for PostgresDatabase {
         Ok(rows)
     }
 
+    async fn exec_transaction(
+        &self,
+        statements: &[TransactionStatement<'_>],
+    ) -> Result<(), DatabaseError> {
+        let mut client = self.client.write().await;
+        // Rolls back when dropped before the commit, including when this future is dropped
+        let transaction = client
+            .transaction()
+            .await
+            .map_err(PostgresDatabaseError::Postgres)?;
+
+        for statement in statements {
+            exec_statement(&transaction, statement).await?;
+        }
+
+        transaction
+            .commit()
+            .await
+            .map_err(PostgresDatabaseError::Postgres)?;
+
+        Ok(())
+    }
+
     fn trigger_close(&self) -> Result<(), DatabaseError> {
         self.handle.abort();
         Ok(())
     }
 }
 
+/// Executes a statement of a transaction
+async fn exec_statement(
+    client: &impl GenericClient,
+    statement: &TransactionStatement<'_>,
+) -> Result<(), PostgresDatabaseError> {
+    match statement {
+        TransactionStatement::Insert(statement) => {
+            insert_and_get_row(client, statement.table_name, &statement.values).await?;
+        }
+        TransactionStatement::Update(statement) => {
+            update_and_get_rows(
+                client,
+                statement.table_name,
+                &statement.values,
+                statement.filters.as_deref(),
+                statement.limit,
+            )
+            .await?;
+        }
+        TransactionStatement::Delete(statement) => {
+            delete(
+                client,
+                statement.table_name,
+                statement.filters.as_deref(),
+                statement.limit,
+            )
+            .await?;
+        }
+        TransactionStatement::UpsertMulti(statement) => {
+            upsert_multi(
+                client,
+                statement.table_name,
+                statement
+                    .unique
+                    .as_ref()
+                    .ok_or(PostgresDatabaseError::MissingUnique)?,
+                &statement.values,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
 fn column_value(row: &Row, index: &str) -> Result<DatabaseValue, PostgresDatabaseError> {
     let column_type = row
         .columns()
@@ -475,7 +565,7 @@ fn from_row(column_names: &[String], row: &Row) -> Result<crate::Row, PostgresDa
 }
 
 async fn update_and_get_row(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -556,7 +646,7 @@ async fn update_and_get_row(
 }
 
 async fn update_and_get_rows(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -677,6 +767,32 @@ fn build_sort_clause(sorts: Option<&[Sort]>, index: &AtomicU16) -> String {
     })
 }
 
+fn build_limit_clause(limit: Option<usize>, offset: Option<usize>) -> String {
+    match (limit, offset) {
+        (Some(limit), Some(offset)) => format!("LIMIT {limit} OFFSET {offset}"),
+        (Some(limit), None) => format!("LIMIT {limit}"),
+        (None, Some(offset)) => format!("LIMIT ALL OFFSET {offset}"),
+        (None, None) => String::new(),
+    }
+}
+
+fn build_group_by_clause(group_by: Option<&[&str]>) -> String {
+    group_by.map_or_else(String::new, |columns| {
+        if columns.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "GROUP BY {}",
+                columns
+                    .iter()
+                    .map(|x| format_identifier(x))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    })
+}
+
 fn build_sort_props(sorts: &[Sort], index: &AtomicU16) -> Vec<String> {
     sorts.iter().map(|sort| sort.to_sql(index)).collect()
 }
@@ -822,18 +938,20 @@ fn bexprs_to_params_opt(values: Option<&[Box<dyn BooleanExpression>]>) -> Vec<Pg
 
 #[allow(clippy::too_many_arguments)]
 async fn select(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     distinct: bool,
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join<'_>]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
     limit: Option<usize>,
+    offset: Option<usize>,
 ) -> Result<Vec<crate::Row>, PostgresDatabaseError> {
     let index = AtomicU16::new(0);
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} {}",
+        "SELECT {} {} FROM {table_name} {} {} {} {} {}",
         if distinct { "DISTINCT" } else { "" },
         columns
             .iter()
@@ -842,8 +960,9 @@ async fn select(
             .join(", "),
         build_join_clauses(joins),
         build_where_clause(filters, &index),
+        build_group_by_clause(group_by),
         build_sort_clause(sort, &index),
-        limit.map_or_else(String::new, |limit| format!("LIMIT {limit}"))
+        build_limit_clause(limit, offset)
     );
 
     log::trace!(
@@ -865,7 +984,7 @@ async fn select(
 }
 
 async fn delete(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     filters: Option<&[Box<dyn BooleanExpression>]>,
     limit: Option<usize>,
@@ -895,18 +1014,20 @@ async fn delete(
     to_rows(&column_names, rows).await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn find_row(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     distinct: bool,
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join<'_>]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
 ) -> Result<Option<crate::Row>, PostgresDatabaseError> {
     let index = AtomicU16::new(0);
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} LIMIT 1",
+        "SELECT {} {} FROM {table_name} {} {} {} {} LIMIT 1",
         if distinct { "DISTINCT" } else { "" },
         columns
             .iter()
@@ -915,6 +1036,7 @@ async fn find_row(
             .join(", "),
         build_join_clauses(joins),
         build_where_clause(filters, &index),
+        build_group_by_clause(group_by),
         build_sort_clause(sort, &index),
     );
 
@@ -940,7 +1062,7 @@ async fn find_row(
 }
 
 async fn insert_and_get_row(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
 ) -> Result<crate::Row, PostgresDatabaseError> {
@@ -989,7 +1111,7 @@ async fn insert_and_get_row(
 ///
 /// Will return `Err` if the update multi execution failed.
 pub async fn update_multi(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     values: &[Vec<(&str, Box<dyn Expression>)>],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -1036,7 +1158,7 @@ pub async fn update_multi(
 }
 
 async fn update_chunk(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     values: &[Vec<(&str, Box<dyn Expression>)>],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -1149,7 +1271,7 @@ async fn update_chunk(
 ///
 /// Will return `Err` if the upsert multi execution failed.
 pub async fn upsert_multi(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     unique: &[Box<dyn Expression>],
     values: &[Vec<(&str, Box<dyn Expression>)>],
@@ -1184,7 +1306,7 @@ pub async fn upsert_multi(
 }
 
 async fn upsert_chunk(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     unique: &[Box<dyn Expression>],
     values: &[Vec<(&str, Box<dyn Expression>)>],
@@ -1271,7 +1393,7 @@ async fn upsert_chunk(
 }
 
 async fn upsert(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -1287,7 +1409,7 @@ async fn upsert(
 }
 
 async fn upsert_and_get_row(
-    client: &Client,
+    client: &impl GenericClient,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
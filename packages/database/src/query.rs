@@ -41,6 +41,7 @@ pub enum ExpressionType<'a> {
     Or(&'a Or),
     And(&'a And),
     Gte(&'a Gte),
+    Like(&'a Like),
     Lte(&'a Lte),
     Join(&'a Join<'a>),
     Sort(&'a Sort),
@@ -313,6 +314,43 @@ impl Expression for Gte {
     }
 }
 
+/// A `LIKE` comparison whose `right` pattern uses `!` as its escape character, so a literal
+/// value can be matched with [`escape_like`].
+#[derive(Debug)]
+pub struct Like {
+    pub left: Identifier,
+    pub right: Box<dyn Expression>,
+}
+
+impl BooleanExpression for Like {}
+impl Expression for Like {
+    fn expression_type(&self) -> ExpressionType {
+        ExpressionType::Like(self)
+    }
+
+    fn values(&self) -> Option<Vec<&DatabaseValue>> {
+        self.right.values()
+    }
+}
+
+/// The escape character of [`Like`] patterns.
+pub const LIKE_ESCAPE: char = '!';
+
+/// Escapes the `LIKE` wildcards in `value` so it matches literally in a [`Like`] pattern.
+#[must_use]
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if matches!(c, '%' | '_') || c == LIKE_ESCAPE {
+            escaped.push(LIKE_ESCAPE);
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
 #[derive(Debug)]
 pub struct Lt {
     pub left: Identifier,
@@ -455,6 +493,17 @@ where
     }
 }
 
+pub fn where_like<L, R>(left: L, right: R) -> Like
+where
+    L: Into<Identifier>,
+    R: Into<Box<dyn Expression>>,
+{
+    Like {
+        left: left.into(),
+        right: right.into(),
+    }
+}
+
 pub fn where_lt<L, R>(left: L, right: R) -> Lt
 where
     L: Into<Identifier>,
@@ -697,6 +746,15 @@ where
         self.filter(Box::new(where_gte(left, right)))
     }
 
+    #[must_use]
+    fn where_like<L, R>(self, left: L, right: R) -> Self
+    where
+        L: Into<Identifier>,
+        R: Into<Box<dyn Expression>>,
+    {
+        self.filter(Box::new(where_like(left, right)))
+    }
+
     #[must_use]
     fn where_lt<L, R>(self, left: L, right: R) -> Self
     where
@@ -730,8 +788,10 @@ pub struct SelectQuery<'a> {
     pub columns: &'a [&'a str],
     pub filters: Option<Vec<Box<dyn BooleanExpression>>>,
     pub joins: Option<Vec<Join<'a>>>,
+    pub group_by: Option<&'a [&'a str]>,
     pub sorts: Option<Vec<Sort>>,
     pub limit: Option<usize>,
+    pub offset: Option<usize>,
 }
 
 impl List for SelectQuery<'_> {}
@@ -787,8 +847,10 @@ pub fn select(table_name: &str) -> SelectQuery<'_> {
         columns: &["*"],
         filters: None,
         joins: None,
+        group_by: None,
         sorts: None,
         limit: None,
+        offset: None,
     }
 }
 
@@ -860,6 +922,12 @@ impl<'a> SelectQuery<'a> {
         self
     }
 
+    #[must_use]
+    pub const fn group_by(mut self, columns: &'a [&'a str]) -> Self {
+        self.group_by = Some(columns);
+        self
+    }
+
     #[must_use]
     pub fn sorts(mut self, sorts: Vec<Sort>) -> Self {
         for sort in sorts {
@@ -891,6 +959,12 @@ impl<'a> SelectQuery<'a> {
         self
     }
 
+    #[must_use]
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset.replace(offset);
+        self
+    }
+
     /// # Errors
     ///
     /// Will return `Err` if the select query execution failed.
@@ -1247,3 +1321,36 @@ impl<'a> DeleteStatement<'a> {
         db.into().exec_delete_first(self).await
     }
 }
+
+/// A statement executed along with others in a single transaction, see
+/// [`Database::exec_transaction`].
+pub enum TransactionStatement<'a> {
+    Insert(InsertStatement<'a>),
+    Update(UpdateStatement<'a>),
+    Delete(DeleteStatement<'a>),
+    UpsertMulti(UpsertMultiStatement<'a>),
+}
+
+impl<'a> From<InsertStatement<'a>> for TransactionStatement<'a> {
+    fn from(value: InsertStatement<'a>) -> Self {
+        Self::Insert(value)
+    }
+}
+
+impl<'a> From<UpdateStatement<'a>> for TransactionStatement<'a> {
+    fn from(value: UpdateStatement<'a>) -> Self {
+        Self::Update(value)
+    }
+}
+
+impl<'a> From<DeleteStatement<'a>> for TransactionStatement<'a> {
+    fn from(value: DeleteStatement<'a>) -> Self {
+        Self::Delete(value)
+    }
+}
+
+impl<'a> From<UpsertMultiStatement<'a>> for TransactionStatement<'a> {
+    fn from(value: UpsertMultiStatement<'a>) -> Self {
+        Self::UpsertMulti(value)
+    }
+}
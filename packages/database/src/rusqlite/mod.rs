@@ -6,7 +6,10 @@ use thiserror::Error;
 use tokio::sync::Mutex;
 
 use crate::{
-    query::{BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection},
+    query::{
+        BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection,
+        TransactionStatement,
+    },
     Database, DatabaseError, DatabaseValue, DeleteStatement, InsertStatement, SelectQuery,
     UpdateStatement, UpsertMultiStatement, UpsertStatement,
 };
@@ -90,6 +93,12 @@ impl<T: Expression + ?Sized> ToSql for T {
                     format!("({} <= {})", value.left.to_sql(), value.right.to_sql())
                 }
             }
+            ExpressionType::Like(value) => format!(
+                "({} LIKE {} ESCAPE '{}')",
+                value.left.to_sql(),
+                value.right.to_sql(),
+                crate::query::LIKE_ESCAPE
+            ),
             ExpressionType::Join(value) => format!(
                 "{} JOIN {} ON {}",
                 if value.left { "LEFT" } else { "" },
@@ -163,17 +172,17 @@ impl<T: Expression + ?Sized> ToSql for T {
                     }
                 });
 
-                let limit = value
-                    .limit
-                    .map_or_else(String::new, |limit| format!("LIMIT {limit}"));
+                let group_by = build_group_by_clause(value.group_by);
+                let limit = build_limit_clause(value.limit, value.offset);
 
                 format!(
-                    "SELECT {} {} FROM {} {} {} {} {}",
+                    "SELECT {} {} FROM {} {} {} {} {} {}",
                     if value.distinct { "DISTINCT" } else { "" },
                     value.columns.join(", "),
                     value.table_name,
                     joins,
                     where_clause,
+                    group_by,
                     sort_clause,
                     limit
                 )
@@ -226,8 +235,10 @@ impl Database for RusqliteDatabase {
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
             query.limit,
+            query.offset,
         )?)
     }
 
@@ -242,6 +253,7 @@ impl Database for RusqliteDatabase {
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
         )?)
     }
@@ -349,6 +361,68 @@ impl Database for RusqliteDatabase {
             &statement.values,
         )?)
     }
+
+    async fn exec_transaction(
+        &self,
+        statements: &[TransactionStatement<'_>],
+    ) -> Result<(), DatabaseError> {
+        let mut connection = self.connection.lock().await;
+        let transaction = connection
+            .transaction()
+            .map_err(RusqliteDatabaseError::Rusqlite)?;
+
+        for statement in statements {
+            exec_statement(&transaction, statement)?;
+        }
+
+        transaction
+            .commit()
+            .map_err(RusqliteDatabaseError::Rusqlite)?;
+
+        Ok(())
+    }
+}
+
+/// Executes a statement of a transaction, which is rolled back if this fails.
+fn exec_statement(
+    connection: &Connection,
+    statement: &TransactionStatement<'_>,
+) -> Result<(), RusqliteDatabaseError> {
+    match statement {
+        TransactionStatement::Insert(statement) => {
+            insert_and_get_row(connection, statement.table_name, &statement.values)?;
+        }
+        TransactionStatement::Update(statement) => {
+            update_and_get_rows(
+                connection,
+                statement.table_name,
+                &statement.values,
+                statement.filters.as_deref(),
+                statement.limit,
+            )?;
+        }
+        TransactionStatement::Delete(statement) => {
+            delete(
+                connection,
+                statement.table_name,
+                statement.filters.as_deref(),
+                statement.limit,
+            )?;
+        }
+        TransactionStatement::UpsertMulti(statement) => {
+            upsert_multi(
+                connection,
+                statement.table_name,
+                statement
+                    .unique
+                    .as_ref()
+                    .ok_or(RusqliteDatabaseError::MissingUnique)?,
+                &statement.values,
+            )?;
+        }
+    }
+
+    Ok(())
 }
 
 impl From<Value> for DatabaseValue {
@@ -541,6 +615,25 @@ fn build_sort_clause(sorts: Option<&[Sort]>) -> String {
     })
 }
 
+fn build_limit_clause(limit: Option<usize>, offset: Option<usize>) -> String {
+    match (limit, offset) {
+        (Some(limit), Some(offset)) => format!("LIMIT {limit} OFFSET {offset}"),
+        (Some(limit), None) => format!("LIMIT {limit}"),
+        (None, Some(offset)) => format!("LIMIT -1 OFFSET {offset}"),
+        (None, None) => String::new(),
+    }
+}
+
+fn build_group_by_clause(group_by: Option<&[&str]>) -> String {
+    group_by.map_or_else(String::new, |columns| {
+        if columns.is_empty() {
+            String::new()
+        } else {
+            format!("GROUP BY {}", columns.join(", "))
+        }
+    })
+}
+
 fn build_sort_props(sorts: &[Sort]) -> Vec<String> {
     sorts.iter().map(Sort::to_sql).collect()
 }
@@ -771,17 +864,20 @@ fn select(
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join<'_>]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
     limit: Option<usize>,
+    offset: Option<usize>,
 ) -> Result<Vec<crate::Row>, RusqliteDatabaseError> {
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} {}",
+        "SELECT {} {} FROM {table_name} {} {} {} {} {}",
         if distinct { "DISTINCT" } else { "" },
         columns.join(", "),
         build_join_clauses(joins),
         build_where_clause(filters),
+        build_group_by_clause(group_by),
         build_sort_clause(sort),
-        limit.map_or_else(String::new, |limit| format!("LIMIT {limit}"))
+        build_limit_clause(limit, offset)
     );
 
     log::trace!(
@@ -855,6 +951,7 @@ fn delete(
     to_rows(&column_names, statement.raw_query())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn find_row(
     connection: &Connection,
     table_name: &str,
@@ -862,14 +959,16 @@ fn find_row(
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
 ) -> Result<Option<crate::Row>, RusqliteDatabaseError> {
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} LIMIT 1",
+        "SELECT {} {} FROM {table_name} {} {} {} {} LIMIT 1",
         if distinct { "DISTINCT" } else { "" },
         columns.join(", "),
         build_join_clauses(joins),
         build_where_clause(filters),
+        build_group_by_clause(group_by),
         build_sort_clause(sort),
     );
 
@@ -1309,3 +1408,105 @@ impl Expression for RusqliteDatabaseValue {
         ExpressionType::DatabaseValue(self)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+    use tokio::sync::Mutex;
+
+    use crate::{
+        query::{delete, escape_like, insert, update, FilterableQuery as _, SortDirection},
+        Database, DatabaseValue,
+    };
+
+    use super::RusqliteDatabase;
+
+    fn db() -> RusqliteDatabase {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE items (id INTEGER PRIMARY KEY NOT NULL, name TEXT NOT NULL UNIQUE);
+                INSERT INTO items (id, name) VALUES (1, 'a'), (2, 'b');",
+            )
+            .unwrap();
+
+        RusqliteDatabase::new(Arc::new(Mutex::new(connection)))
+    }
+
+    async fn names(db: &dyn Database) -> Vec<String> {
+        db.select("items")
+            .sort("id", SortDirection::Asc)
+            .execute(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|row| row.get("name")?.as_str().map(ToString::to_string))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn exec_transaction_applies_all_statements() {
+        let db = db();
+        let db: &dyn Database = &db;
+
+        db.exec_transaction(&[
+            insert("items").value("id", 3).value("name", "c").into(),
+            update("items").value("name", "d").where_eq("id", 1).into(),
+            delete("items").where_eq("id", 2).into(),
+        ])
+        .await
+        .unwrap();
+
+        assert_eq!(names(db).await, vec!["d".to_string(), "c".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn exec_transaction_rolls_back_when_a_statement_fails() {
+        let db = db();
+        let db: &dyn Database = &db;
+
+        let result = db
+            .exec_transaction(&[
+                delete("items").where_eq("id", 1).into(),
+                insert("items").value("id", 3).value("name", "c").into(),
+                insert("items").value("id", 4).value("name", "b").into(),
+            ])
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(names(db).await, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn where_like_matches_escaped_wildcards_literally() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE items (id INTEGER PRIMARY KEY NOT NULL, name TEXT NOT NULL);
+                INSERT INTO items (id, name) VALUES
+                    (1, '/music/50%_off/a.flac'),
+                    (2, '/music/50x_off/b.flac'),
+                    (3, '/music/a_b!/c.flac'),
+                    (4, '/music/axb!/d.flac');",
+            )
+            .unwrap();
+        let db = RusqliteDatabase::new(Arc::new(Mutex::new(connection)));
+        let db: &dyn Database = &db;
+
+        for (prefix, expected) in [("/music/50%_off/", 1), ("/music/a_b!/", 3)] {
+            let rows = db
+                .select("items")
+                .where_like("name", format!("{}%", escape_like(prefix)))
+                .execute(db)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                rows.iter().map(crate::Row::id).collect::<Vec<_>>(),
+                vec![Some(DatabaseValue::Number(expected))],
+            );
+        }
+    }
+}
@@ -5,13 +5,16 @@ use futures::{Stream, StreamExt};
 use sqlx::{
     mysql::{MySqlArguments, MySqlRow, MySqlValueRef},
     query::Query,
-    Column, Executor, MySql, MySqlPool, Row, Statement, TypeInfo, Value, ValueRef,
+    Column, Executor, MySql, MySqlConnection, MySqlPool, Row, Statement, TypeInfo, Value, ValueRef,
 };
 use thiserror::Error;
 use tokio::sync::Mutex;
 
 use crate::{
-    query::{BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection},
+    query::{
+        BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection,
+        TransactionStatement,
+    },
     Database, DatabaseError, DatabaseValue, DeleteStatement, InsertStatement, SelectQuery,
     UpdateStatement, UpsertMultiStatement, UpsertStatement,
 };
@@ -87,6 +90,12 @@ impl<T: Expression + ?Sized> ToSql for T {
                     format!("({} <= {})", value.left.to_sql(), value.right.to_sql())
                 }
             }
+            ExpressionType::Like(value) => format!(
+                "({} LIKE {} ESCAPE '{}')",
+                value.left.to_sql(),
+                value.right.to_sql(),
+                crate::query::LIKE_ESCAPE
+            ),
             ExpressionType::Join(value) => format!(
                 "{} JOIN {} ON {}",
                 if value.left { "LEFT" } else { "" },
@@ -160,17 +169,17 @@ impl<T: Expression + ?Sized> ToSql for T {
                     }
                 });
 
-                let limit = value
-                    .limit
-                    .map_or_else(String::new, |limit| format!("LIMIT {limit}"));
+                let group_by = build_group_by_clause(value.group_by);
+                let limit = build_limit_clause(value.limit, value.offset);
 
                 format!(
-                    "SELECT {} {} FROM {} {} {} {} {}",
+                    "SELECT {} {} FROM {} {} {} {} {} {}",
                     if value.distinct { "DISTINCT" } else { "" },
                     value.columns.join(", "),
                     value.table_name,
                     joins,
                     where_clause,
+                    group_by,
                     sort_clause,
                     limit
                 )
@@ -231,14 +240,22 @@ impl From<SqlxDatabaseError> for DatabaseError {
 impl Database for MySqlSqlxDatabase {
     async fn query(&self, query: &SelectQuery<'_>) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(select(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             query.table_name,
             query.distinct,
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
             query.limit,
+            query.offset,
         )
         .await?)
     }
@@ -248,12 +265,19 @@ impl Database for MySqlSqlxDatabase {
         query: &SelectQuery<'_>,
     ) -> Result<Option<crate::Row>, DatabaseError> {
         Ok(find_row(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             query.table_name,
             query.distinct,
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
         )
         .await?)
@@ -264,7 +288,13 @@ impl Database for MySqlSqlxDatabase {
         statement: &DeleteStatement<'_>,
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(delete(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             statement.table_name,
             statement.filters.as_deref(),
             statement.limit,
@@ -277,7 +307,13 @@ impl Database for MySqlSqlxDatabase {
         statement: &DeleteStatement<'_>,
     ) -> Result<Option<crate::Row>, DatabaseError> {
         Ok(delete(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             statement.table_name,
             statement.filters.as_deref(),
             Some(1),
@@ -292,7 +328,13 @@ impl Database for MySqlSqlxDatabase {
         statement: &InsertStatement<'_>,
     ) -> Result<crate::Row, DatabaseError> {
         Ok(insert_and_get_row(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             statement.table_name,
             &statement.values,
         )
@@ -304,7 +346,13 @@ impl Database for MySqlSqlxDatabase {
         statement: &UpdateStatement<'_>,
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(update_and_get_rows(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -318,7 +366,13 @@ impl Database for MySqlSqlxDatabase {
         statement: &UpdateStatement<'_>,
     ) -> Result<Option<crate::Row>, DatabaseError> {
         Ok(update_and_get_row(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -332,7 +386,13 @@ impl Database for MySqlSqlxDatabase {
         statement: &UpsertStatement<'_>,
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         Ok(upsert(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -346,7 +406,13 @@ impl Database for MySqlSqlxDatabase {
         statement: &UpsertStatement<'_>,
     ) -> Result<crate::Row, DatabaseError> {
         Ok(upsert_and_get_row(
-            &*self.connection.lock().await,
+            self.connection
+                .lock()
+                .await
+                .acquire()
+                .await
+                .map_err(SqlxDatabaseError::Sqlx)?
+                .as_mut(),
             statement.table_name,
             &statement.values,
             statement.filters.as_deref(),
@@ -361,7 +427,13 @@ impl Database for MySqlSqlxDatabase {
     ) -> Result<Vec<crate::Row>, DatabaseError> {
         let rows = {
             upsert_multi(
-                &*self.connection.lock().await,
+                self.connection
+                    .lock()
+                    .await
+                    .acquire()
+                    .await
+                    .map_err(SqlxDatabaseError::Sqlx)?
+                    .as_mut(),
                 statement.table_name,
                 statement
                     .unique
@@ -373,6 +445,75 @@ impl Database for MySqlSqlxDatabase {
         };
         Ok(rows)
     }
+
+    async fn exec_transaction(
+        &self,
+        statements: &[TransactionStatement<'_>],
+    ) -> Result<(), DatabaseError> {
+        let mut transaction = self
+            .connection
+            .lock()
+            .await
+            .begin()
+            .await
+            .map_err(SqlxDatabaseError::Sqlx)?;
+
+        for statement in statements {
+            exec_statement(&mut transaction, statement).await?;
+        }
+
+        transaction
+            .commit()
+            .await
+            .map_err(SqlxDatabaseError::Sqlx)?;
+
+        Ok(())
+    }
+}
+
+/// Executes a statement of a transaction, which is rolled back if this fails.
+async fn exec_statement(
+    connection: &mut MySqlConnection,
+    statement: &TransactionStatement<'_>,
+) -> Result<(), SqlxDatabaseError> {
+    match statement {
+        TransactionStatement::Insert(statement) => {
+            insert_and_get_row(connection, statement.table_name, &statement.values).await?;
+        }
+        TransactionStatement::Update(statement) => {
+            update_and_get_rows(
+                connection,
+                statement.table_name,
+                &statement.values,
+                statement.filters.as_deref(),
+                statement.limit,
+            )
+            .await?;
+        }
+        TransactionStatement::Delete(statement) => {
+            delete(
+                connection,
+                statement.table_name,
+                statement.filters.as_deref(),
+                statement.limit,
+            )
+            .await?;
+        }
+        TransactionStatement::UpsertMulti(statement) => {
+            upsert_multi(
+                connection,
+                statement.table_name,
+                statement
+                    .unique
+                    .as_ref()
+                    .ok_or(SqlxDatabaseError::MissingUnique)?,
+                &statement.values,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
 }
 
 fn column_value(value: &MySqlValueRef<'_>) -> Result<DatabaseValue, sqlx::Error> {
@@ -411,7 +552,7 @@ fn from_row(column_names: &[String], row: &MySqlRow) -> Result<crate::Row, SqlxD
 }
 
 async fn update_and_get_row(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -473,7 +614,7 @@ async fn update_and_get_row(
 }
 
 async fn update_and_get_rows(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -575,6 +716,25 @@ fn build_sort_clause(sorts: Option<&[Sort]>) -> String {
     })
 }
 
+fn build_limit_clause(limit: Option<usize>, offset: Option<usize>) -> String {
+    match (limit, offset) {
+        (Some(limit), Some(offset)) => format!("LIMIT {limit} OFFSET {offset}"),
+        (Some(limit), None) => format!("LIMIT {limit}"),
+        (None, Some(offset)) => format!("LIMIT 18446744073709551615 OFFSET {offset}"),
+        (None, None) => String::new(),
+    }
+}
+
+fn build_group_by_clause(group_by: Option<&[&str]>) -> String {
+    group_by.map_or_else(String::new, |columns| {
+        if columns.is_empty() {
+            String::new()
+        } else {
+            format!("GROUP BY {}", columns.join(", "))
+        }
+    })
+}
+
 fn build_sort_props(sorts: &[Sort]) -> Vec<String> {
     sorts.iter().map(Sort::to_sql).collect()
 }
@@ -747,23 +907,26 @@ fn bexprs_to_values_opt(
 
 #[allow(clippy::too_many_arguments)]
 async fn select(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     distinct: bool,
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join<'_>]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
     limit: Option<usize>,
+    offset: Option<usize>,
 ) -> Result<Vec<crate::Row>, SqlxDatabaseError> {
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} {}",
+        "SELECT {} {} FROM {table_name} {} {} {} {} {}",
         if distinct { "DISTINCT" } else { "" },
         columns.join(", "),
         build_join_clauses(joins),
         build_where_clause(filters),
+        build_group_by_clause(group_by),
         build_sort_clause(sort),
-        limit.map_or_else(String::new, |limit| format!("LIMIT {limit}"))
+        build_limit_clause(limit, offset)
     );
 
     log::trace!(
@@ -785,7 +948,7 @@ async fn select(
 }
 
 async fn delete(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     filters: Option<&[Box<dyn BooleanExpression>]>,
     limit: Option<usize>,
@@ -814,21 +977,24 @@ async fn delete(
     to_rows(&column_names, query.fetch(connection)).await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn find_row(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     distinct: bool,
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join<'_>]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
 ) -> Result<Option<crate::Row>, SqlxDatabaseError> {
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} LIMIT 1",
+        "SELECT {} {} FROM {table_name} {} {} {} {} LIMIT 1",
         if distinct { "DISTINCT" } else { "" },
         columns.join(", "),
         build_join_clauses(joins),
         build_where_clause(filters),
+        build_group_by_clause(group_by),
         build_sort_clause(sort),
     );
 
@@ -858,7 +1024,7 @@ async fn find_row(
 }
 
 async fn insert_and_get_row(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
 ) -> Result<crate::Row, SqlxDatabaseError> {
@@ -905,7 +1071,7 @@ async fn insert_and_get_row(
 ///
 /// Will return `Err` if the update multi execution failed.
 pub async fn update_multi(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     values: &[Vec<(&str, Box<dyn Expression>)>],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -953,7 +1119,7 @@ pub async fn update_multi(
 }
 
 async fn update_chunk(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     values: &[Vec<(&str, Box<dyn Expression>)>],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -1048,7 +1214,7 @@ async fn update_chunk(
 ///
 /// Will return `Err` if the upsert multi execution failed.
 pub async fn upsert_multi(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     unique: &[Box<dyn Expression>],
     values: &[Vec<(&str, Box<dyn Expression>)>],
@@ -1084,7 +1250,7 @@ pub async fn upsert_multi(
 }
 
 async fn upsert_chunk(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     unique: &[Box<dyn Expression>],
     values: &[Vec<(&str, Box<dyn Expression>)>],
@@ -1158,7 +1324,7 @@ async fn upsert_chunk(
 }
 
 async fn upsert(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
@@ -1175,7 +1341,7 @@ async fn upsert(
 
 #[allow(unused)]
 async fn upsert_and_get_row(
-    connection: &MySqlPool,
+    connection: &mut MySqlConnection,
     table_name: &str,
     values: &[(&str, Box<dyn Expression>)],
     filters: Option<&[Box<dyn BooleanExpression>]>,
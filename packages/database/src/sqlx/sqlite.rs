@@ -10,14 +10,17 @@ use sqlx::{
     pool::PoolConnection,
     query::Query,
     sqlite::{SqliteArguments, SqliteRow, SqliteValueRef},
-    Column, Executor, Row, Sqlite, SqliteConnection, SqlitePool, Statement, TypeInfo, Value,
-    ValueRef,
+    Column, Connection as _, Executor, Row, Sqlite, SqliteConnection, SqlitePool, Statement,
+    TypeInfo, Value, ValueRef,
 };
 use thiserror::Error;
 use tokio::sync::Mutex;
 
 use crate::{
-    query::{BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection},
+    query::{
+        BooleanExpression, Expression, ExpressionType, Join, Sort, SortDirection,
+        TransactionStatement,
+    },
     Database, DatabaseError, DatabaseValue, DeleteStatement, InsertStatement, SelectQuery,
     UpdateStatement, UpsertMultiStatement, UpsertStatement,
 };
@@ -121,6 +124,12 @@ impl<T: Expression + ?Sized> ToSql for T {
                     )
                 }
             }
+            ExpressionType::Like(value) => format!(
+                "({} LIKE {} ESCAPE '{}')",
+                value.left.to_sql(index),
+                value.right.to_sql(index),
+                crate::query::LIKE_ESCAPE
+            ),
             ExpressionType::Join(value) => format!(
                 "{} JOIN {} ON {}",
                 if value.left { "LEFT" } else { "" },
@@ -206,12 +215,11 @@ impl<T: Expression + ?Sized> ToSql for T {
                     }
                 });
 
-                let limit = value
-                    .limit
-                    .map_or_else(String::new, |limit| format!("LIMIT {limit}"));
+                let group_by = build_group_by_clause(value.group_by);
+                let limit = build_limit_clause(value.limit, value.offset);
 
                 format!(
-                    "SELECT {} {} FROM {} {} {} {} {}",
+                    "SELECT {} {} FROM {} {} {} {} {} {}",
                     if value.distinct { "DISTINCT" } else { "" },
                     value
                         .columns
@@ -222,6 +230,7 @@ impl<T: Expression + ?Sized> ToSql for T {
                     value.table_name,
                     joins,
                     where_clause,
+                    group_by,
                     sort_clause,
                     limit
                 )
@@ -312,8 +321,10 @@ impl Database for SqliteSqlxDatabase {
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
             query.limit,
+            query.offset,
         )
         .await?)
     }
@@ -329,6 +340,7 @@ impl Database for SqliteSqlxDatabase {
             query.columns,
             query.filters.as_deref(),
             query.joins.as_deref(),
+            query.group_by,
             query.sorts.as_deref(),
         )
         .await?)
@@ -448,6 +460,75 @@ impl Database for SqliteSqlxDatabase {
         };
         Ok(rows)
     }
+
+    async fn exec_transaction(
+        &self,
+        statements: &[TransactionStatement<'_>],
+    ) -> Result<(), DatabaseError> {
+        let connection = self.get_connection().await?;
+        let mut connection = connection.lock().await;
+        let mut transaction = connection
+            .as_mut()
+            .begin()
+            .await
+            .map_err(SqlxDatabaseError::Sqlx)?;
+
+        for statement in statements {
+            exec_statement(&mut transaction, statement).await?;
+        }
+
+        transaction
+            .commit()
+            .await
+            .map_err(SqlxDatabaseError::Sqlx)?;
+
+        Ok(())
+    }
+}
+
+/// Executes a statement of a transaction, which is rolled back if this fails.
+async fn exec_statement(
+    connection: &mut SqliteConnection,
+    statement: &TransactionStatement<'_>,
+) -> Result<(), SqlxDatabaseError> {
+    match statement {
+        TransactionStatement::Insert(statement) => {
+            insert_and_get_row(connection, statement.table_name, &statement.values).await?;
+        }
+        TransactionStatement::Update(statement) => {
+            update_and_get_rows(
+                connection,
+                statement.table_name,
+                &statement.values,
+                statement.filters.as_deref(),
+                statement.limit,
+            )
+            .await?;
+        }
+        TransactionStatement::Delete(statement) => {
+            delete(
+                connection,
+                statement.table_name,
+                statement.filters.as_deref(),
+                statement.limit,
+            )
+            .await?;
+        }
+        TransactionStatement::UpsertMulti(statement) => {
+            upsert_multi(
+                connection,
+                statement.table_name,
+                statement
+                    .unique
+                    .as_ref()
+                    .ok_or(SqlxDatabaseError::MissingUnique)?,
+                &statement.values,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
 }
 
 /// # Errors
@@ -690,6 +771,32 @@ fn build_sort_clause(sorts: Option<&[Sort]>, index: &AtomicU16) -> String {
     })
 }
 
+fn build_limit_clause(limit: Option<usize>, offset: Option<usize>) -> String {
+    match (limit, offset) {
+        (Some(limit), Some(offset)) => format!("LIMIT {limit} OFFSET {offset}"),
+        (Some(limit), None) => format!("LIMIT {limit}"),
+        (None, Some(offset)) => format!("LIMIT -1 OFFSET {offset}"),
+        (None, None) => String::new(),
+    }
+}
+
+fn build_group_by_clause(group_by: Option<&[&str]>) -> String {
+    group_by.map_or_else(String::new, |columns| {
+        if columns.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "GROUP BY {}",
+                columns
+                    .iter()
+                    .map(|x| format_identifier(x))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    })
+}
+
 fn build_sort_props(sorts: &[Sort], index: &AtomicU16) -> Vec<String> {
     sorts.iter().map(|sort| sort.to_sql(index)).collect()
 }
@@ -887,12 +994,14 @@ async fn select(
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join<'_>]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
     limit: Option<usize>,
+    offset: Option<usize>,
 ) -> Result<Vec<crate::Row>, SqlxDatabaseError> {
     let index = AtomicU16::new(0);
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} {}",
+        "SELECT {} {} FROM {table_name} {} {} {} {} {}",
         if distinct { "DISTINCT" } else { "" },
         columns
             .iter()
@@ -901,8 +1010,9 @@ async fn select(
             .join(", "),
         build_join_clauses(joins),
         build_where_clause(filters, &index),
+        build_group_by_clause(group_by),
         build_sort_clause(sort, &index),
-        limit.map_or_else(String::new, |limit| format!("LIMIT {limit}"))
+        build_limit_clause(limit, offset)
     );
 
     log::trace!(
@@ -954,6 +1064,7 @@ async fn delete(
     to_rows(&column_names, query.fetch(connection)).await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn find_row(
     connection: &mut SqliteConnection,
     table_name: &str,
@@ -961,11 +1072,12 @@ async fn find_row(
     columns: &[&str],
     filters: Option<&[Box<dyn BooleanExpression>]>,
     joins: Option<&[Join<'_>]>,
+    group_by: Option<&[&str]>,
     sort: Option<&[Sort]>,
 ) -> Result<Option<crate::Row>, SqlxDatabaseError> {
     let index = AtomicU16::new(0);
     let query = format!(
-        "SELECT {} {} FROM {table_name} {} {} {} LIMIT 1",
+        "SELECT {} {} FROM {table_name} {} {} {} {} LIMIT 1",
         if distinct { "DISTINCT" } else { "" },
         columns
             .iter()
@@ -974,6 +1086,7 @@ async fn find_row(
             .join(", "),
         build_join_clauses(joins),
         build_where_clause(filters, &index),
+        build_group_by_clause(group_by),
         build_sort_clause(sort, &index),
     );
 
@@ -23,7 +23,7 @@ use actix_web::{
     web::{self, Json},
     Result, Scope,
 };
-use moosicbox_database::profiles::LibraryDatabase;
+use moosicbox_database::profiles::{api::ProfileName, LibraryDatabase};
 use moosicbox_music_api::{models::TrackAudioQuality, MusicApis, SourceToMusicApi as _};
 use moosicbox_paging::Page;
 use regex::{Captures, Regex};
@@ -74,11 +74,16 @@ pub async fn add_progress_listener_to_download_queue(listener: ProgressListenerR
 }
 
 async fn get_default_download_queue(
+    profile: String,
     db: LibraryDatabase,
     music_apis: MusicApis,
 ) -> Arc<RwLock<DownloadQueue>> {
     let queue = { DOWNLOAD_QUEUE.read().await.clone() };
 
+    if !queue.has_profile() {
+        let mut queue = DOWNLOAD_QUEUE.write().await;
+        *queue = queue.clone().with_profile(profile);
+    }
     if !queue.has_database() {
         let mut queue = DOWNLOAD_QUEUE.write().await;
         *queue = queue.clone().with_database(db.clone());
@@ -166,6 +171,7 @@ pub struct DownloadQuery {
 #[allow(clippy::future_not_send)]
 pub async fn download_endpoint(
     query: web::Query<DownloadQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     music_apis: MusicApis,
 ) -> Result<Json<Value>> {
@@ -189,7 +195,7 @@ pub async fn download_endpoint(
 
     let tasks = create_download_tasks(&db, tasks).await?;
 
-    let queue = get_default_download_queue(db.clone(), music_apis).await;
+    let queue = get_default_download_queue(profile.into(), db.clone(), music_apis).await;
     let mut download_queue = queue.write().await;
 
     download_queue.add_tasks_to_queue(tasks).await;
@@ -228,6 +234,7 @@ pub struct RetryDownloadQuery {
 #[route("/retry-download", method = "POST")]
 pub async fn retry_download_endpoint(
     query: web::Query<RetryDownloadQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     music_apis: MusicApis,
 ) -> Result<Json<Value>> {
@@ -240,6 +247,7 @@ pub async fn retry_download_endpoint(
         .ok_or_else(|| ErrorNotFound(format!("Task not found with ID {}", query.task_id)))?;
 
     let mut download_queue = DownloadQueue::new()
+        .with_profile(profile.into())
         .with_database(db.clone())
         .with_downloader(Box::new(MoosicboxDownloader::new(db, music_apis)));
     download_queue.add_tasks_to_queue(vec![task]).await;
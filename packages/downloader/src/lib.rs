@@ -461,7 +461,7 @@ pub async fn create_download_tasks(
 }
 
 fn get_filename_for_track(track: &Track) -> String {
-    let extension = "flac";
+    let extension = track.format.map_or("flac", audio_format_extension);
 
     format!(
         "{}_{}.{extension}",
@@ -470,6 +470,27 @@ fn get_filename_for_track(track: &Track) -> String {
     )
 }
 
+const fn audio_format_extension(format: AudioFormat) -> &'static str {
+    #[allow(clippy::match_same_arms)]
+    match format {
+        #[cfg(feature = "aac")]
+        AudioFormat::Aac => "m4a",
+        #[cfg(feature = "alac")]
+        AudioFormat::Alac => "m4a",
+        #[cfg(feature = "flac")]
+        AudioFormat::Flac => "flac",
+        #[cfg(feature = "mp3")]
+        AudioFormat::Mp3 => "mp3",
+        #[cfg(feature = "opus")]
+        AudioFormat::Opus => "opus",
+        #[cfg(feature = "vorbis")]
+        AudioFormat::Vorbis => "ogg",
+        #[cfg(feature = "wav")]
+        AudioFormat::Wav => "wav",
+        AudioFormat::Source => "flac",
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum DownloadTrackError {
     #[error(transparent)]
@@ -700,7 +721,7 @@ async fn download_track_inner(
         api,
         &track.id,
         source,
-        AudioFormat::Source,
+        AudioFormat::Source.into(),
         false,
         start,
         None,
@@ -55,6 +55,8 @@ pub enum ProcessDownloadQueueError {
     IO(#[from] std::io::Error),
     #[error("No database")]
     NoDatabase,
+    #[error("No profile")]
+    NoProfile,
     #[error("No downloader")]
     NoDownloader,
 }
@@ -130,6 +132,7 @@ pub type ProgressListenerRef =
 #[derive(Clone)]
 pub struct DownloadQueue {
     progress_listeners: Vec<Arc<ProgressListenerRef>>,
+    profile: Option<String>,
     database: Option<LibraryDatabase>,
     downloader: Option<Arc<Box<dyn Downloader + Send + Sync>>>,
     state: Arc<RwLock<DownloadQueueState>>,
@@ -143,6 +146,7 @@ impl DownloadQueue {
     pub fn new() -> Self {
         Self {
             progress_listeners: vec![],
+            profile: None,
             database: None,
             downloader: None,
             state: Arc::new(RwLock::new(DownloadQueueState::new())),
@@ -151,6 +155,19 @@ impl DownloadQueue {
         }
     }
 
+    #[must_use]
+    pub const fn has_profile(&self) -> bool {
+        self.profile.is_some()
+    }
+
+    /// The profile whose search index is updated when downloaded files are
+    /// scanned
+    #[must_use]
+    pub fn with_profile(mut self, profile: String) -> Self {
+        self.profile.replace(profile);
+        self
+    }
+
     #[must_use]
     pub const fn has_database(&self) -> bool {
         self.database.is_some()
@@ -400,13 +417,18 @@ impl DownloadQueue {
         let path = PathBuf::from_str(&task.file_path).unwrap();
 
         let scanner = if self.scan {
+            let profile = self
+                .profile
+                .clone()
+                .ok_or(ProcessDownloadQueueError::NoProfile)?;
             let scan_paths = moosicbox_scan::get_scan_paths(&database.clone()).await?;
 
             if scan_paths.iter().any(|x| path.starts_with(x)) {
-                Some(moosicbox_scan::Scanner::new(
-                    moosicbox_scan::event::ScanTask::Local {
+                Some((
+                    profile,
+                    moosicbox_scan::Scanner::new(moosicbox_scan::event::ScanTask::Local {
                         paths: vec![path.parent().unwrap().to_str().unwrap().to_string()],
-                    },
+                    }),
                 ))
             } else {
                 None
@@ -433,7 +455,7 @@ impl DownloadQueue {
                     )
                     .await?;
 
-                if let Some(scanner) = scanner {
+                if let Some((profile, scanner)) = scanner {
                     let metadata = tokio::fs::File::open(&path).await?.metadata().await?;
 
                     moosicbox_scan::local::scan_items(
@@ -442,6 +464,7 @@ impl DownloadQueue {
                             metadata,
                             track: Some(track),
                         }],
+                        &profile,
                         &database,
                         CancellationToken::new(),
                         scanner.clone(),
@@ -458,7 +481,7 @@ impl DownloadQueue {
                     .download_album_cover(&task.file_path, album_id, *source, on_progress)
                     .await?;
 
-                if let Some(scanner) = scanner {
+                if let Some((profile, scanner)) = scanner {
                     let metadata = tokio::fs::File::open(&path).await?.metadata().await?;
 
                     moosicbox_scan::local::scan_items(
@@ -467,6 +490,7 @@ impl DownloadQueue {
                             metadata,
                             album: Some(album),
                         }],
+                        &profile,
                         &database,
                         CancellationToken::new(),
                         scanner.clone(),
@@ -483,7 +507,7 @@ impl DownloadQueue {
                     .download_artist_cover(&task.file_path, album_id, *source, on_progress)
                     .await?;
 
-                if let Some(scanner) = scanner {
+                if let Some((profile, scanner)) = scanner {
                     let metadata = tokio::fs::File::open(&path).await?.metadata().await?;
 
                     moosicbox_scan::local::scan_items(
@@ -492,6 +516,7 @@ impl DownloadQueue {
                             metadata,
                             artist: Some(artist),
                         }],
+                        &profile,
                         &database,
                         CancellationToken::new(),
                         scanner.clone(),
@@ -664,6 +689,13 @@ mod tests {
         ) -> Result<Vec<Row>, DatabaseError> {
             Ok(vec![Row { columns: vec![] }])
         }
+
+        async fn exec_transaction(
+            &self,
+            _statements: &[TransactionStatement<'_>],
+        ) -> Result<(), DatabaseError> {
+            Ok(())
+        }
     }
 
     fn new_queue() -> DownloadQueue {
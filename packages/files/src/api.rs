@@ -9,14 +9,14 @@ use actix_web::{
 };
 use bytes::{Bytes, BytesMut};
 use futures::{StreamExt, TryStreamExt as _};
-use moosicbox_database::profiles::LibraryDatabase;
+use moosicbox_database::{config::ConfigDatabase, profiles::LibraryDatabase};
 use moosicbox_music_api::{
     models::{ImageCoverSize, TrackAudioQuality, TrackSource},
     MusicApis, SourceToMusicApi as _,
 };
 use moosicbox_music_models::{
     id::{parse_id_ranges, parse_integer_ranges_to_ids, Id, IdType, ParseIdsError},
-    ApiSource, AudioFormat,
+    ApiSource, AudioFormat, EncodingSettings, PlaybackQuality,
 };
 use moosicbox_parsing_utils::integer_range::ParseIntegersError;
 use serde::Deserialize;
@@ -27,6 +27,10 @@ use tokio_util::codec::{BytesCodec, FramedRead};
 use crate::files::{
     album::{get_album_cover, AlbumCoverError},
     artist::{get_artist_cover, ArtistCoverError},
+    hls::{
+        get_segment_bytes, master_playlist, media_playlist, packed_audio_timestamp_tag, renditions,
+        HlsError, HlsRendition, DEFAULT_SEGMENT_DURATION,
+    },
     track::{
         audio_format_to_content_type, get_or_init_track_visualization, get_silence_bytes,
         get_track_bytes, get_track_id_source, get_track_info, get_tracks_info,
@@ -43,6 +47,9 @@ pub fn bind_services<
     scope
         .service(get_silence_endpoint)
         .service(track_endpoint)
+        .service(track_hls_playlist_endpoint)
+        .service(track_hls_media_playlist_endpoint)
+        .service(track_hls_segment_endpoint)
         .service(track_visualization_endpoint)
         .service(track_info_endpoint)
         .service(tracks_info_endpoint)
@@ -61,6 +68,9 @@ pub fn bind_services<
         track_visualization_endpoint,
         get_silence_endpoint,
         track_endpoint,
+        track_hls_playlist_endpoint,
+        track_hls_media_playlist_endpoint,
+        track_hls_segment_endpoint,
         track_info_endpoint,
         tracks_info_endpoint,
         artist_cover_endpoint,
@@ -71,6 +81,8 @@ pub fn bind_services<
     components(schemas(
         GetTrackVisualizationQuery,
         GetTrackQuery,
+        GetTrackHlsQuery,
+        GetTrackHlsMediaQuery,
         GetTrackInfoQuery,
         GetTracksInfoQuery,
         ArtistCoverQuery,
@@ -259,6 +271,39 @@ pub struct GetTrackQuery {
     pub format: Option<AudioFormat>,
     pub quality: Option<TrackAudioQuality>,
     pub source: Option<ApiSource>,
+    pub profile: Option<String>,
+    pub bitrate: Option<u32>,
+    pub vbr_quality: Option<u8>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+}
+
+/// The `PlaybackQuality` of the transcoding profile named `name`
+async fn get_transcoding_profile_quality(
+    db: &ConfigDatabase,
+    name: &str,
+) -> Result<PlaybackQuality> {
+    let profile = moosicbox_config::db::get_transcoding_profile(db, name)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound(format!("Transcoding profile not found: {name}")))?;
+
+    let format = AudioFormat::from_str(&profile.format).map_err(|_| {
+        ErrorBadRequest(format!(
+            "Invalid transcoding profile format: {}",
+            profile.format
+        ))
+    })?;
+
+    Ok(PlaybackQuality {
+        format,
+        settings: EncodingSettings {
+            bitrate: profile.bitrate,
+            vbr_quality: profile.vbr_quality,
+            sample_rate: profile.sample_rate,
+            channels: profile.channels,
+        },
+    })
 }
 
 #[cfg_attr(
@@ -273,6 +318,11 @@ pub struct GetTrackQuery {
             ("format" = Option<AudioFormat>, Query, description = "The track format to return"),
             ("quality" = Option<TrackAudioQuality>, Query, description = "The quality to return"),
             ("source" = Option<ApiSource>, Query, description = "The track API source"),
+            ("profile" = Option<String>, Query, description = "The transcoding profile to encode the track with"),
+            ("bitrate" = Option<u32>, Query, description = "The constant bitrate to encode the track at, in kbps"),
+            ("vbrQuality" = Option<u8>, Query, description = "The variable bitrate quality to encode the track at, from 0 (best) to 9 (worst)"),
+            ("sampleRate" = Option<u32>, Query, description = "The sample rate to encode the track at"),
+            ("channels" = Option<u8>, Query, description = "The number of channels to encode the track with"),
         ),
         responses(
             (
@@ -288,9 +338,33 @@ pub async fn track_endpoint(
     req: HttpRequest,
     query: web::Query<GetTrackQuery>,
     music_apis: MusicApis,
+    db: ConfigDatabase,
 ) -> Result<HttpResponse> {
     let method = req.method();
 
+    // The query's format and settings take precedence over the profile's
+    let profile = match &query.profile {
+        Some(name) => Some(get_transcoding_profile_quality(&db, name).await?),
+        None => None,
+    };
+    let quality = PlaybackQuality {
+        format: query
+            .format
+            .or_else(|| profile.map(|x| x.format))
+            .unwrap_or_default(),
+        settings: profile
+            .map(|x| x.settings)
+            .unwrap_or_default()
+            .merge(EncodingSettings {
+                bitrate: query.bitrate,
+                vbr_quality: query.vbr_quality,
+                sample_rate: query.sample_rate,
+                channels: query.channels,
+            }),
+    };
+
+    quality.settings.validate().map_err(ErrorBadRequest)?;
+
     let source = get_track_id_source(
         music_apis.clone(),
         &query.track_id.into(),
@@ -306,14 +380,9 @@ pub async fn track_endpoint(
         query.source
     );
 
-    let content_type = query
-        .format
-        .as_ref()
-        .and_then(audio_format_to_content_type)
+    let content_type = audio_format_to_content_type(&quality.format)
         .or_else(|| track_source_to_content_type(&source));
 
-    let format = query.format.unwrap_or_default();
-
     #[cfg(feature = "track-range")]
     let range = req
         .headers()
@@ -356,7 +425,8 @@ pub async fn track_endpoint(
                 #[cfg(not(feature = "flac"))]
                 {
                     moosicbox_assert::die_or_warn!(
-                        "No valid CONTENT_TYPE available for audio format {format:?}"
+                        "No valid CONTENT_TYPE available for audio format {:?}",
+                        quality.format
                     );
                 }
             }
@@ -374,7 +444,7 @@ pub async fn track_endpoint(
             .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?,
         &Id::Number(query.track_id),
         source,
-        format,
+        quality,
         true,
         range.as_ref().and_then(|r| r.start.map(|x| x as u64)),
         range.as_ref().and_then(|r| r.end.map(|x| x as u64)),
@@ -450,6 +520,255 @@ pub async fn track_endpoint(
     }
 }
 
+const HLS_PLAYLIST_CONTENT_TYPE: &str = "application/vnd.apple.mpegurl";
+
+impl From<HlsError> for actix_web::Error {
+    fn from(err: HlsError) -> Self {
+        match err {
+            HlsError::UnsupportedSource
+            | HlsError::UnsupportedFormat(_)
+            | HlsError::UnsupportedRendition(..)
+            | HlsError::InvalidSegmentDuration(_) => ErrorBadRequest(err),
+            HlsError::SegmentOutOfRange(_) => ErrorNotFound(err),
+            HlsError::GetTrackBytes(err) => err.into(),
+            HlsError::IO(_) => ErrorInternalServerError(err),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetTrackHlsQuery {
+    pub track_id: u64,
+    pub quality: Option<TrackAudioQuality>,
+    pub source: Option<ApiSource>,
+    pub segment_duration: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetTrackHlsMediaQuery {
+    pub track_id: u64,
+    pub format: AudioFormat,
+    pub bitrate: Option<u32>,
+    pub quality: Option<TrackAudioQuality>,
+    pub source: Option<ApiSource>,
+    pub segment_duration: Option<u32>,
+}
+
+/// Gets the duration and source of the track to stream over HLS
+async fn get_hls_track(
+    music_apis: MusicApis,
+    track_id: u64,
+    source: Option<ApiSource>,
+    quality: Option<TrackAudioQuality>,
+) -> Result<(f64, TrackSource)> {
+    let track_id = Id::Number(track_id);
+    let source = source.unwrap_or(ApiSource::Library);
+
+    let track = music_apis
+        .get(source)
+        .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?
+        .track(&track_id)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound(format!("Track not found: {track_id}")))?;
+
+    let track_source = get_track_id_source(music_apis, &track_id, source, quality).await?;
+
+    if !matches!(track_source, TrackSource::LocalFilePath { .. }) {
+        return Err(HlsError::UnsupportedSource.into());
+    }
+
+    Ok((track.duration, track_source))
+}
+
+/// Builds the URI of another HLS resource relative to the current one,
+/// keeping the query params of the current request (e.g. the profile) and
+/// replacing any that are in `params`.
+fn hls_uri(req: &HttpRequest, path: &str, params: &[(&str, String)]) -> String {
+    let query = req
+        .query_string()
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .filter(|param| {
+            let key = param.split_once('=').map_or(*param, |(key, _)| key);
+            !params.iter().any(|(x, _)| *x == key)
+        })
+        .map(ToString::to_string)
+        .chain(params.iter().map(|(key, value)| format!("{key}={value}")))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{path}?{query}")
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Files"],
+        get,
+        path = "/track/hls/playlist.m3u8",
+        description = "Get the HLS master playlist for the track, listing a rendition per streamable format and bitrate",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("trackId" = u64, Query, description = "The track ID"),
+            ("quality" = Option<TrackAudioQuality>, Query, description = "The quality of the source to transcode"),
+            ("source" = Option<ApiSource>, Query, description = "The track API source"),
+            ("segmentDuration" = Option<u32>, Query, description = "The duration of each segment in seconds"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "HLS master playlist",
+            )
+        )
+    )
+)]
+#[route("/track/hls/playlist.m3u8", method = "GET")]
+#[allow(clippy::future_not_send)]
+pub async fn track_hls_playlist_endpoint(
+    req: HttpRequest,
+    query: web::Query<GetTrackHlsQuery>,
+    music_apis: MusicApis,
+) -> Result<HttpResponse> {
+    get_hls_track(music_apis, query.track_id, query.source, query.quality).await?;
+
+    let playlist = master_playlist(&renditions(), |rendition| {
+        hls_uri(
+            &req,
+            "media.m3u8",
+            &[
+                ("format", rendition.format.to_string()),
+                ("bitrate", rendition.bitrate.to_string()),
+            ],
+        )
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(HLS_PLAYLIST_CONTENT_TYPE)
+        .body(playlist))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Files"],
+        get,
+        path = "/track/hls/media.m3u8",
+        description = "Get the HLS media playlist of the track's segments in the given format",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("trackId" = u64, Query, description = "The track ID"),
+            ("format" = AudioFormat, Query, description = "The format to transcode the segments to"),
+            ("bitrate" = Option<u32>, Query, description = "The bitrate of the rendition in kbps, defaulting to the highest one of the format"),
+            ("quality" = Option<TrackAudioQuality>, Query, description = "The quality of the source to transcode"),
+            ("source" = Option<ApiSource>, Query, description = "The track API source"),
+            ("segmentDuration" = Option<u32>, Query, description = "The duration of each segment in seconds"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "HLS media playlist",
+            )
+        )
+    )
+)]
+#[route("/track/hls/media.m3u8", method = "GET")]
+#[allow(clippy::future_not_send)]
+pub async fn track_hls_media_playlist_endpoint(
+    req: HttpRequest,
+    query: web::Query<GetTrackHlsMediaQuery>,
+    music_apis: MusicApis,
+) -> Result<HttpResponse> {
+    let rendition = HlsRendition::find(query.format, query.bitrate)?;
+
+    let (duration, _) =
+        get_hls_track(music_apis, query.track_id, query.source, query.quality).await?;
+
+    let playlist = media_playlist(
+        duration,
+        query.segment_duration.unwrap_or(DEFAULT_SEGMENT_DURATION),
+        |index| {
+            hls_uri(
+                &req,
+                &format!("segments/{index}.{}", rendition.extension),
+                &[],
+            )
+        },
+    )?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(HLS_PLAYLIST_CONTENT_TYPE)
+        .body(playlist))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Files"],
+        get,
+        path = "/track/hls/segments/{index}.{extension}",
+        description = "Get a segment of the track transcoded to the given format",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("index" = u64, Path, description = "The index of the segment"),
+            ("extension" = String, Path, description = "The file extension of the format"),
+            ("trackId" = u64, Query, description = "The track ID"),
+            ("format" = AudioFormat, Query, description = "The format to transcode the segment to"),
+            ("bitrate" = Option<u32>, Query, description = "The bitrate of the rendition in kbps, defaulting to the highest one of the format"),
+            ("quality" = Option<TrackAudioQuality>, Query, description = "The quality of the source to transcode"),
+            ("source" = Option<ApiSource>, Query, description = "The track API source"),
+            ("segmentDuration" = Option<u32>, Query, description = "The duration of each segment in seconds"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Segment audio bytes",
+            )
+        )
+    )
+)]
+#[route("/track/hls/segments/{index}.{extension}", method = "GET")]
+#[allow(clippy::future_not_send)]
+pub async fn track_hls_segment_endpoint(
+    path: web::Path<(u64, String)>,
+    query: web::Query<GetTrackHlsMediaQuery>,
+    music_apis: MusicApis,
+) -> Result<HttpResponse> {
+    let (index, extension) = path.into_inner();
+
+    let rendition = HlsRendition::find(query.format, query.bitrate)?;
+
+    if extension != rendition.extension {
+        return Err(ErrorBadRequest(format!(
+            "Invalid extension '{extension}' for format {}",
+            query.format
+        )));
+    }
+
+    let (duration, source) =
+        get_hls_track(music_apis, query.track_id, query.source, query.quality).await?;
+
+    let segment_duration = query.segment_duration.unwrap_or(DEFAULT_SEGMENT_DURATION);
+
+    log::debug!(
+        "GET /track/hls/segments track_id={} index={index} format={} bitrate={} segment_duration={segment_duration}",
+        query.track_id,
+        query.format,
+        rendition.bitrate,
+    );
+
+    let segment = get_segment_bytes(&source, duration, segment_duration, index, &rendition).await?;
+
+    let mut body = packed_audio_timestamp_tag(segment.start).to_vec();
+    body.extend_from_slice(&segment.bytes);
+
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CONTENT_ENCODING, "identity"))
+        .content_type(rendition.content_type)
+        .body(body))
+}
+
 #[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -2,10 +2,12 @@ use std::str::FromStr as _;
 
 pub mod album;
 pub mod artist;
+pub mod hls;
 pub mod track;
 
 mod track_bytes_media_source;
 pub mod track_pool;
+pub mod transcode_cache;
 
 pub(crate) fn filename_from_path_str(path: &str) -> Option<String> {
     std::path::PathBuf::from_str(path).ok().and_then(|p| {
@@ -0,0 +1,897 @@
+//! HTTP Live Streaming of tracks.
+//!
+//! A track is transcoded once, as a whole, and split into segments at the
+//! boundaries of its encoded frames. Encoding each segment separately would
+//! add the encoder delay to the start of every segment, leaving audible gaps
+//! between them. The whole-track transcodes of local files are kept in the
+//! transcode cache along with an index of their frames, so once a rendition
+//! has been encoded each segment is read straight from its byte range. Until
+//! then a segment is returned as soon as its frames have been encoded.
+//!
+//! Every format that can be served as HLS packed audio is offered at a ladder
+//! of bitrates in the master playlist so that clients can switch between them
+//! as their bandwidth changes.
+
+use std::{
+    io::SeekFrom,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use futures::StreamExt as _;
+use moosicbox_music_api::models::TrackSource;
+use moosicbox_music_models::{AudioFormat, EncodingSettings, PlaybackQuality};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt as _, AsyncSeekExt as _};
+
+use super::{
+    track::{get_audio_bytes, GetTrackBytesError},
+    track_pool::track_key,
+    transcode_cache,
+};
+
+/// The duration of each segment, in seconds
+pub const DEFAULT_SEGMENT_DURATION: u32 = 6;
+
+#[derive(Debug, Error)]
+pub enum HlsError {
+    #[error("HLS is only supported for local tracks")]
+    UnsupportedSource,
+    #[error("Unsupported HLS format: {0}")]
+    UnsupportedFormat(AudioFormat),
+    #[error("Unsupported HLS rendition: {0} at {1} kbps")]
+    UnsupportedRendition(AudioFormat, u32),
+    #[error("Invalid segment duration: {0}")]
+    InvalidSegmentDuration(u32),
+    #[error("Segment out of range: {0}")]
+    SegmentOutOfRange(u64),
+    #[error(transparent)]
+    GetTrackBytes(#[from] GetTrackBytesError),
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+}
+
+/// The bitrates, in kbps, that each format is offered at
+const BITRATE_LADDER: &[(AudioFormat, u32)] = &[
+    #[cfg(feature = "aac")]
+    (AudioFormat::Aac, 64),
+    #[cfg(feature = "aac")]
+    (AudioFormat::Aac, 128),
+    #[cfg(feature = "aac")]
+    (AudioFormat::Aac, 256),
+    #[cfg(feature = "mp3")]
+    (AudioFormat::Mp3, 128),
+    #[cfg(feature = "mp3")]
+    (AudioFormat::Mp3, 320),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HlsRendition {
+    pub format: AudioFormat,
+    /// The constant bitrate the segments are encoded at, in kbps
+    pub bitrate: u32,
+    /// The RFC 6381 codec of the segments
+    pub codecs: &'static str,
+    pub extension: &'static str,
+    pub content_type: &'static str,
+}
+
+impl HlsRendition {
+    #[must_use]
+    pub const fn new(format: AudioFormat, bitrate: u32) -> Option<Self> {
+        match format {
+            #[cfg(feature = "aac")]
+            AudioFormat::Aac => Some(Self {
+                format,
+                bitrate,
+                codecs: "mp4a.40.2",
+                extension: "aac",
+                content_type: "audio/aac",
+            }),
+            #[cfg(feature = "mp3")]
+            AudioFormat::Mp3 => Some(Self {
+                format,
+                bitrate,
+                codecs: "mp4a.40.34",
+                extension: "mp3",
+                content_type: "audio/mpeg",
+            }),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// The rendition of `format` in the bitrate ladder at `bitrate`, or the
+    /// highest bitrate of the format if no `bitrate` is given.
+    ///
+    /// # Errors
+    ///
+    /// * If the `format` can't be served over HLS
+    /// * If the `bitrate` isn't in the ladder of the `format`
+    pub fn find(format: AudioFormat, bitrate: Option<u32>) -> Result<Self, HlsError> {
+        let renditions = renditions()
+            .into_iter()
+            .filter(|x| x.format == format)
+            .collect::<Vec<_>>();
+
+        let rendition = match bitrate {
+            Some(bitrate) => renditions
+                .into_iter()
+                .find(|x| x.bitrate == bitrate)
+                .ok_or(HlsError::UnsupportedRendition(format, bitrate))?,
+            None => renditions
+                .into_iter()
+                .max_by_key(|x| x.bitrate)
+                .ok_or(HlsError::UnsupportedFormat(format))?,
+        };
+
+        Ok(rendition)
+    }
+
+    /// The peak bitrate in bits per second
+    #[must_use]
+    pub const fn bandwidth(&self) -> u32 {
+        self.bitrate.saturating_mul(1000)
+    }
+
+    #[must_use]
+    pub fn quality(&self) -> PlaybackQuality {
+        PlaybackQuality {
+            format: self.format,
+            settings: EncodingSettings {
+                bitrate: Some(self.bitrate),
+                ..EncodingSettings::default()
+            },
+        }
+    }
+}
+
+/// The renditions offered in the master playlist, in ascending bandwidth
+#[must_use]
+pub fn renditions() -> Vec<HlsRendition> {
+    let mut renditions = BITRATE_LADDER
+        .iter()
+        .filter_map(|(format, bitrate)| HlsRendition::new(*format, *bitrate))
+        .collect::<Vec<_>>();
+
+    renditions.sort_by_key(HlsRendition::bandwidth);
+
+    renditions
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn segment_count(duration: f64, segment_duration: u32) -> u64 {
+    ((duration / f64::from(segment_duration)).ceil() as u64).max(1)
+}
+
+#[must_use]
+pub fn master_playlist(
+    renditions: &[HlsRendition],
+    media_uri: impl Fn(&HlsRendition) -> String,
+) -> String {
+    let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-INDEPENDENT-SEGMENTS\n");
+
+    for rendition in renditions {
+        playlist.push_str(&format!(
+            "#EXT-X-STREAM-INF:BANDWIDTH={},CODECS=\"{}\"\n{}\n",
+            rendition.bandwidth(),
+            rendition.codecs,
+            media_uri(rendition),
+        ));
+    }
+
+    playlist
+}
+
+/// # Errors
+///
+/// * If the `segment_duration` is zero
+#[allow(clippy::cast_precision_loss)]
+pub fn media_playlist(
+    duration: f64,
+    segment_duration: u32,
+    segment_uri: impl Fn(u64) -> String,
+) -> Result<String, HlsError> {
+    if segment_duration == 0 {
+        return Err(HlsError::InvalidSegmentDuration(segment_duration));
+    }
+
+    let mut playlist = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{segment_duration}\n#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-PLAYLIST-TYPE:VOD\n"
+    );
+
+    for index in 0..segment_count(duration, segment_duration) {
+        let start = (index * u64::from(segment_duration)) as f64;
+        let length = (duration - start).clamp(0.0, f64::from(segment_duration));
+
+        playlist.push_str(&format!("#EXTINF:{length:.3},\n{}\n", segment_uri(index)));
+    }
+
+    playlist.push_str("#EXT-X-ENDLIST\n");
+
+    Ok(playlist)
+}
+
+/// An encoded frame in a stream of ADTS AAC or MPEG audio frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Frame {
+    offset: usize,
+    len: usize,
+    samples: u32,
+    sample_rate: u32,
+}
+
+const ADTS_SAMPLE_RATES: [u32; 13] = [
+    96_000, 88_200, 64_000, 48_000, 44_100, 32_000, 24_000, 22_050, 16_000, 12_000, 11_025, 8_000,
+    7_350,
+];
+
+/// The length, samples and sample rate of the ADTS frame at the start of
+/// `bytes`
+fn adts_frame(bytes: &[u8]) -> Option<(usize, u32, u32)> {
+    let header = bytes.get(..7)?;
+
+    if header[0] != 0xff || header[1] & 0xf6 != 0xf0 {
+        return None;
+    }
+
+    let sample_rate = *ADTS_SAMPLE_RATES.get(usize::from((header[2] >> 2) & 0x0f))?;
+    let len = (usize::from(header[3] & 0x03) << 11)
+        | (usize::from(header[4]) << 3)
+        | usize::from(header[5] >> 5);
+    let blocks = u32::from(header[6] & 0x03) + 1;
+
+    (len >= 7).then_some((len, blocks * 1024, sample_rate))
+}
+
+const MPEG1_LAYER3_BITRATES: [u32; 15] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+];
+const MPEG2_LAYER3_BITRATES: [u32; 15] =
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// The length, samples and sample rate of the MPEG audio layer III frame at
+/// the start of `bytes`
+fn mp3_frame(bytes: &[u8]) -> Option<(usize, u32, u32)> {
+    let header = bytes.get(..4)?;
+
+    // Sync word and layer III
+    if header[0] != 0xff || header[1] & 0xe0 != 0xe0 || (header[1] >> 1) & 0x03 != 0x01 {
+        return None;
+    }
+
+    let version = (header[1] >> 3) & 0x03;
+    let (bitrates, base_sample_rates, samples): (_, [u32; 3], u32) = match version {
+        // MPEG 1
+        0b11 => (&MPEG1_LAYER3_BITRATES, [44_100, 48_000, 32_000], 1152),
+        // MPEG 2
+        0b10 => (&MPEG2_LAYER3_BITRATES, [22_050, 24_000, 16_000], 576),
+        // MPEG 2.5
+        0b00 => (&MPEG2_LAYER3_BITRATES, [11_025, 12_000, 8_000], 576),
+        _ => return None,
+    };
+
+    let bitrate = *bitrates.get(usize::from(header[2] >> 4))?;
+    let sample_rate = *base_sample_rates.get(usize::from((header[2] >> 2) & 0x03))?;
+    let padding = u32::from((header[2] >> 1) & 0x01);
+
+    if bitrate == 0 {
+        return None;
+    }
+
+    let len = samples / 8 * bitrate * 1000 / sample_rate + padding;
+
+    Some((usize::try_from(len).ok()?, samples, sample_rate))
+}
+
+/// The size of the ID3v2 tag at the start of `bytes`, if there is one
+fn id3_tag_len(bytes: &[u8]) -> usize {
+    match bytes.get(..10) {
+        Some(header) if header.starts_with(b"ID3") => {
+            let size = header[6..10]
+                .iter()
+                .fold(0, |size, byte| (size << 7) | usize::from(byte & 0x7f));
+            let footer = if header[5] & 0x10 == 0 { 0 } else { 10 };
+
+            10 + size + footer
+        }
+        _ => 0,
+    }
+}
+
+/// The most bytes a frame header takes up
+const MAX_FRAME_HEADER_LEN: usize = 7;
+
+/// Parses the frames of an encoded track as its bytes arrive, skipping any tag
+/// or junk between them
+struct FrameParser {
+    parse: Option<fn(&[u8]) -> Option<(usize, u32, u32)>>,
+    /// Where the next frame is looked for. `None` until the start of the track
+    /// has been checked for a tag.
+    offset: Option<usize>,
+    frames: Vec<Frame>,
+    /// The time at the end of the parsed frames, in seconds
+    duration: f64,
+}
+
+impl FrameParser {
+    fn new(format: AudioFormat) -> Self {
+        let parse: Option<fn(&[u8]) -> Option<(usize, u32, u32)>> = match format {
+            #[cfg(feature = "aac")]
+            AudioFormat::Aac => Some(adts_frame),
+            #[cfg(feature = "mp3")]
+            AudioFormat::Mp3 => Some(mp3_frame),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        };
+
+        Self {
+            parse,
+            offset: None,
+            frames: vec![],
+            duration: 0.0,
+        }
+    }
+
+    /// Parses the frames in the `bytes` received so far, past the ones that
+    /// have already been parsed. Unless this is the end of the track, stops
+    /// at a frame that hasn't fully arrived yet.
+    fn parse(&mut self, bytes: &[u8], end: bool) {
+        let Some(parse) = self.parse else {
+            return;
+        };
+        let mut offset = match self.offset {
+            Some(offset) => offset,
+            None if !end && bytes.len() < 10 => return,
+            None => id3_tag_len(bytes),
+        };
+
+        while offset < bytes.len() {
+            match parse(&bytes[offset..]) {
+                Some((len, samples, sample_rate)) if offset + len <= bytes.len() => {
+                    self.frames.push(Frame {
+                        offset,
+                        len,
+                        samples,
+                        sample_rate,
+                    });
+                    self.duration += f64::from(samples) / f64::from(sample_rate);
+                    offset += len;
+                }
+                Some(_) if !end => break,
+                None if !end && bytes.len() - offset < MAX_FRAME_HEADER_LEN => break,
+                _ => offset += 1,
+            }
+        }
+
+        self.offset = Some(offset);
+    }
+}
+
+/// The frames of the encoded track, skipping any tag or junk between them
+fn frames(format: AudioFormat, bytes: &[u8]) -> Vec<Frame> {
+    let mut parser = FrameParser::new(format);
+    parser.parse(bytes, true);
+    parser.frames
+}
+
+/// The size of each frame in a frame index
+const FRAME_INDEX_ENTRY_LEN: usize = 20;
+
+/// Serializes the frames of a transcode cache entry of `len` bytes
+fn encode_frame_index(len: u64, frames: &[Frame]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + frames.len() * FRAME_INDEX_ENTRY_LEN);
+    bytes.extend_from_slice(&len.to_le_bytes());
+
+    for frame in frames {
+        bytes.extend_from_slice(&(frame.offset as u64).to_le_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        bytes.extend_from_slice(&(frame.len as u32).to_le_bytes());
+        bytes.extend_from_slice(&frame.samples.to_le_bytes());
+        bytes.extend_from_slice(&frame.sample_rate.to_le_bytes());
+    }
+
+    bytes
+}
+
+/// Deserializes a frame index. `None` if it's malformed or was built for an
+/// entry of a different length than `len`.
+fn decode_frame_index(bytes: &[u8], len: u64) -> Option<Vec<Frame>> {
+    let (header, frames) = bytes.split_first_chunk::<8>()?;
+
+    if u64::from_le_bytes(*header) != len || frames.len() % FRAME_INDEX_ENTRY_LEN != 0 {
+        return None;
+    }
+
+    frames
+        .chunks_exact(FRAME_INDEX_ENTRY_LEN)
+        .map(|frame| {
+            let u32_at = |at: usize| frame[at..at + 4].try_into().ok().map(u32::from_le_bytes);
+
+            Some(Frame {
+                offset: usize::try_from(u64::from_le_bytes(frame[..8].try_into().ok()?)).ok()?,
+                len: usize::try_from(u32_at(8)?).ok()?,
+                samples: u32_at(12)?,
+                sample_rate: u32_at(16)?,
+            })
+        })
+        .collect()
+}
+
+/// The frames of the transcode cache entry at `path`, read from its frame
+/// index, which is built the first time the entry is used for a segment.
+async fn cached_frames(path: &Path, format: AudioFormat) -> Result<Vec<Frame>, HlsError> {
+    let len = tokio::fs::metadata(path).await?.len();
+    let index_path = transcode_cache::index_path(path);
+
+    if let Some(frames) = tokio::fs::read(&index_path)
+        .await
+        .ok()
+        .and_then(|bytes| decode_frame_index(&bytes, len))
+    {
+        return Ok(frames);
+    }
+
+    log::debug!("hls: building frame index of {path:?}");
+    let frames = frames(format, &tokio::fs::read(path).await?);
+
+    if let Err(e) = tokio::fs::write(&index_path, encode_frame_index(len, &frames)).await {
+        log::warn!("hls: failed to save frame index {index_path:?}: {e:?}");
+    }
+
+    Ok(frames)
+}
+
+/// The byte range of segment `index` of the frames and the time of its first
+/// sample, in seconds. A segment holds the frames that start within its
+/// duration, and the last one also holds every frame after it in case the
+/// stored duration of the track is a little short.
+#[allow(clippy::cast_precision_loss)]
+fn segment_range(
+    frames: &[Frame],
+    segment_duration: u32,
+    index: u64,
+    last: bool,
+) -> (Range<usize>, f64) {
+    let segment_start = (index * u64::from(segment_duration)) as f64;
+    let segment_end = segment_start + f64::from(segment_duration);
+
+    let mut time = 0.0;
+    let mut start = None;
+    let mut range = None::<Range<usize>>;
+
+    for frame in frames {
+        if time >= segment_start && (last || time < segment_end) {
+            start.get_or_insert(time);
+            range = Some(
+                range.map_or(frame.offset..frame.offset + frame.len, |range| {
+                    range.start..frame.offset + frame.len
+                }),
+            );
+        }
+
+        time += f64::from(frame.samples) / f64::from(frame.sample_rate);
+    }
+
+    (range.unwrap_or_default(), start.unwrap_or(segment_start))
+}
+
+/// A segment of the track and the time of its first sample, in seconds
+#[derive(Debug, Clone)]
+pub struct HlsSegment {
+    pub start: f64,
+    pub bytes: Bytes,
+}
+
+/// Gets segment `index` of the track transcoded to the rendition.
+///
+/// # Errors
+///
+/// * If the track isn't a local file
+/// * If the `segment_duration` is zero
+/// * If the segment is past the end of the track
+/// * If failed to get or read the audio bytes
+pub async fn get_segment_bytes(
+    source: &TrackSource,
+    duration: f64,
+    segment_duration: u32,
+    index: u64,
+    rendition: &HlsRendition,
+) -> Result<HlsSegment, HlsError> {
+    if segment_duration == 0 {
+        return Err(HlsError::InvalidSegmentDuration(segment_duration));
+    }
+    let count = segment_count(duration, segment_duration);
+    if index >= count {
+        return Err(HlsError::SegmentOutOfRange(index));
+    }
+    let TrackSource::LocalFilePath { path, .. } = source else {
+        return Err(HlsError::UnsupportedSource);
+    };
+    let last = index + 1 == count;
+    let quality = rendition.quality();
+
+    if let Some(cached) = transcode_cache::get(path, &track_key(source, quality)).await {
+        return get_cached_segment_bytes(cached, rendition.format, segment_duration, index, last)
+            .await;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let segment_end = ((index + 1) * u64::from(segment_duration)) as f64;
+    let mut stream = get_audio_bytes(source.clone(), quality, None, None, None)
+        .await?
+        .stream;
+    let mut bytes = vec![];
+    let mut parser = FrameParser::new(rendition.format);
+
+    // Only waits for the encode to reach the end of the segment
+    loop {
+        if let Some(chunk) = stream.next().await {
+            bytes.extend_from_slice(&chunk?);
+            parser.parse(&bytes, false);
+
+            if !last && parser.duration >= segment_end {
+                break;
+            }
+        } else {
+            parser.parse(&bytes, true);
+            break;
+        }
+    }
+
+    // Keeps reading the rest of the transcode so that it finishes and is
+    // saved to the transcode cache for the following segments
+    moosicbox_task::spawn("files: hls finish transcode", async move {
+        while stream.next().await.is_some() {}
+    });
+
+    let (range, start) = segment_range(&parser.frames, segment_duration, index, last);
+
+    Ok(HlsSegment {
+        start,
+        bytes: Bytes::from(bytes).slice(range),
+    })
+}
+
+/// Reads segment `index` out of the transcode cache entry at `path`.
+async fn get_cached_segment_bytes(
+    path: PathBuf,
+    format: AudioFormat,
+    segment_duration: u32,
+    index: u64,
+    last: bool,
+) -> Result<HlsSegment, HlsError> {
+    let frames = cached_frames(&path, format).await?;
+    let (range, start) = segment_range(&frames, segment_duration, index, last);
+
+    let mut file = tokio::fs::File::open(&path).await?;
+    file.seek(SeekFrom::Start(range.start as u64)).await?;
+    let mut bytes = vec![0; range.len()];
+    file.read_exact(&mut bytes).await?;
+
+    Ok(HlsSegment {
+        start,
+        bytes: bytes.into(),
+    })
+}
+
+/// The ID3 tag that HLS packed audio segments start with, holding the
+/// timestamp of the first sample of the segment so that clients can line up
+/// segments from different renditions.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn packed_audio_timestamp_tag(seconds: f64) -> Bytes {
+    const OWNER: &[u8] = b"com.apple.streaming.transportStreamTimestamp\0";
+
+    // 33-bit MPEG-2 timestamp with a 90kHz clock
+    let timestamp = ((seconds * 90_000.0).round() as u64) & 0x1_FFFF_FFFF;
+
+    let mut frame = Vec::with_capacity(OWNER.len() + 8);
+    frame.extend_from_slice(OWNER);
+    frame.extend_from_slice(&timestamp.to_be_bytes());
+
+    let mut tag = Vec::with_capacity(20 + frame.len());
+    tag.extend_from_slice(b"ID3\x04\x00\x00");
+    tag.extend_from_slice(&syncsafe(10 + frame.len()));
+    tag.extend_from_slice(b"PRIV");
+    tag.extend_from_slice(&syncsafe(frame.len()));
+    tag.extend_from_slice(&[0, 0]);
+    tag.extend_from_slice(&frame);
+
+    tag.into()
+}
+
+/// An ID3v2.4 size, which uses 7 bits per byte
+#[allow(clippy::cast_possible_truncation)]
+const fn syncsafe(size: usize) -> [u8; 4] {
+    [
+        ((size >> 21) & 0x7f) as u8,
+        ((size >> 14) & 0x7f) as u8,
+        ((size >> 7) & 0x7f) as u8,
+        (size & 0x7f) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frame(offset: usize) -> Frame {
+        Frame {
+            offset,
+            len: 10,
+            samples: 1,
+            sample_rate: 1,
+        }
+    }
+
+    /// 13 one second frames of 10 bytes each
+    fn one_second_frames() -> Vec<Frame> {
+        (0..13).map(|i| frame(i * 10)).collect()
+    }
+
+    /// An ADTS frame of `len` bytes at 44.1kHz
+    fn adts(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        bytes[..7].copy_from_slice(&[
+            0xff,
+            0xf1,
+            0x50,
+            0x80 | u8::try_from(len >> 11).unwrap(),
+            u8::try_from((len >> 3) & 0xff).unwrap(),
+            u8::try_from((len & 0x07) << 5).unwrap() | 0x1f,
+            0xfc,
+        ]);
+        bytes
+    }
+
+    #[test]
+    fn media_playlist_splits_duration_into_segments() {
+        let playlist = media_playlist(13.5, 6, |index| format!("{index}.aac")).unwrap();
+
+        assert_eq!(
+            playlist,
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:6\n#EXT-X-MEDIA-SEQUENCE:0\n\
+            #EXT-X-PLAYLIST-TYPE:VOD\n\
+            #EXTINF:6.000,\n0.aac\n\
+            #EXTINF:6.000,\n1.aac\n\
+            #EXTINF:1.500,\n2.aac\n\
+            #EXT-X-ENDLIST\n"
+        );
+    }
+
+    #[test]
+    fn media_playlist_has_one_segment_for_empty_track() {
+        let playlist = media_playlist(0.0, 6, |index| format!("{index}.aac")).unwrap();
+
+        assert_eq!(playlist.matches("#EXTINF:0.000,\n0.aac\n").count(), 1);
+    }
+
+    #[test]
+    fn media_playlist_rejects_zero_segment_duration() {
+        assert!(matches!(
+            media_playlist(10.0, 0, |index| index.to_string()),
+            Err(HlsError::InvalidSegmentDuration(0))
+        ));
+    }
+
+    #[test]
+    fn segment_count_rounds_up() {
+        assert_eq!(segment_count(12.0, 6), 2);
+        assert_eq!(segment_count(12.1, 6), 3);
+        assert_eq!(segment_count(0.0, 6), 1);
+    }
+
+    #[test]
+    fn segment_range_holds_frames_starting_within_segment() {
+        let frames = one_second_frames();
+
+        assert_eq!(segment_range(&frames, 6, 0, false), (0..60, 0.0));
+        assert_eq!(segment_range(&frames, 6, 1, false), (60..120, 6.0));
+        assert_eq!(segment_range(&frames, 6, 2, true), (120..130, 12.0));
+    }
+
+    #[test]
+    fn segment_range_last_segment_holds_remaining_frames() {
+        let frames = one_second_frames();
+
+        assert_eq!(segment_range(&frames, 6, 1, true), (60..130, 6.0));
+    }
+
+    #[test]
+    fn segment_range_starts_at_first_frame_in_segment() {
+        // Frames of 1.5 seconds, so segments start at the first frame
+        // boundary within them rather than at a multiple of 5 seconds
+        let frames = (0..10)
+            .map(|i| Frame {
+                offset: i * 10,
+                len: 10,
+                samples: 3,
+                sample_rate: 2,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(segment_range(&frames, 5, 1, false), (40..70, 6.0));
+        assert_eq!(segment_range(&frames, 5, 2, true), (70..100, 10.5));
+    }
+
+    #[test]
+    fn segment_range_is_empty_past_the_frames() {
+        assert_eq!(
+            segment_range(&one_second_frames(), 6, 3, true),
+            (0..0, 18.0)
+        );
+    }
+
+    #[test]
+    fn adts_frame_parses_header() {
+        assert_eq!(adts_frame(&adts(371)), Some((371, 1024, 44_100)));
+        assert_eq!(adts_frame(&[0xff, 0xf1, 0x50]), None);
+        assert_eq!(adts_frame(&[0; 7]), None);
+    }
+
+    #[test]
+    fn mp3_frame_parses_header() {
+        // MPEG 1 layer III, 128kbps, 44.1kHz, no padding
+        assert_eq!(
+            mp3_frame(&[0xff, 0xfb, 0x90, 0x64]),
+            Some((417, 1152, 44_100))
+        );
+        // With padding
+        assert_eq!(
+            mp3_frame(&[0xff, 0xfb, 0x92, 0x64]),
+            Some((418, 1152, 44_100))
+        );
+        // MPEG 2 layer III, 64kbps, 22.05kHz
+        assert_eq!(
+            mp3_frame(&[0xff, 0xf3, 0x80, 0x64]),
+            Some((208, 576, 22_050))
+        );
+        // Free bitrate
+        assert_eq!(mp3_frame(&[0xff, 0xfb, 0x00, 0x64]), None);
+        // Layer II
+        assert_eq!(mp3_frame(&[0xff, 0xfd, 0x90, 0x64]), None);
+    }
+
+    #[test]
+    fn id3_tag_len_reads_syncsafe_size() {
+        assert_eq!(id3_tag_len(b"ID3\x04\x00\x00\x00\x00\x02\x01"), 10 + 257);
+        assert_eq!(id3_tag_len(b"ID3\x04\x00\x10\x00\x00\x00\x01"), 10 + 1 + 10);
+        assert_eq!(id3_tag_len(&[0xff, 0xf1, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+    }
+
+    #[cfg(feature = "aac")]
+    #[test]
+    fn frame_parser_waits_for_frames_that_have_not_fully_arrived() {
+        let mut bytes = adts(20);
+        bytes.extend(adts(30));
+        let mut parser = FrameParser::new(AudioFormat::Aac);
+
+        parser.parse(&bytes[..5], false);
+        assert!(parser.frames.is_empty());
+
+        parser.parse(&bytes[..25], false);
+        assert_eq!(
+            parser.frames.iter().map(|x| x.offset).collect::<Vec<_>>(),
+            vec![0]
+        );
+
+        parser.parse(&bytes, false);
+        assert_eq!(parser.frames, frames(AudioFormat::Aac, &bytes));
+        assert!((parser.duration - 2048.0 / 44_100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn frame_index_round_trips() {
+        let frames = one_second_frames();
+        let index = encode_frame_index(130, &frames);
+
+        assert_eq!(decode_frame_index(&index, 130), Some(frames));
+        assert_eq!(decode_frame_index(&index, 131), None);
+        assert_eq!(decode_frame_index(&index[..index.len() - 1], 130), None);
+        assert_eq!(decode_frame_index(&[], 130), None);
+    }
+
+    #[cfg(feature = "aac")]
+    #[test]
+    fn frames_skips_tags_and_junk() {
+        let mut bytes = b"ID3\x04\x00\x00\x00\x00\x00\x02\x00\x00".to_vec();
+        bytes.extend(adts(20));
+        bytes.extend([0x00, 0x01]);
+        bytes.extend(adts(30));
+        // Truncated frame
+        bytes.extend(&adts(30)[..10]);
+
+        let frames = frames(AudioFormat::Aac, &bytes);
+
+        assert_eq!(
+            frames,
+            vec![
+                Frame {
+                    offset: 12,
+                    len: 20,
+                    samples: 1024,
+                    sample_rate: 44_100,
+                },
+                Frame {
+                    offset: 34,
+                    len: 30,
+                    samples: 1024,
+                    sample_rate: 44_100,
+                },
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "aac", feature = "mp3"))]
+    #[test]
+    fn renditions_are_a_bitrate_ladder_in_ascending_bandwidth() {
+        let renditions = renditions();
+
+        assert_eq!(
+            renditions
+                .iter()
+                .map(|x| (x.format, x.bandwidth()))
+                .collect::<Vec<_>>(),
+            vec![
+                (AudioFormat::Aac, 64_000),
+                (AudioFormat::Aac, 128_000),
+                (AudioFormat::Mp3, 128_000),
+                (AudioFormat::Aac, 256_000),
+                (AudioFormat::Mp3, 320_000),
+            ]
+        );
+    }
+
+    #[cfg(feature = "aac")]
+    #[test]
+    fn find_rendition_defaults_to_highest_bitrate() {
+        assert_eq!(
+            HlsRendition::find(AudioFormat::Aac, None).unwrap().bitrate,
+            256
+        );
+        assert_eq!(
+            HlsRendition::find(AudioFormat::Aac, Some(64))
+                .unwrap()
+                .bitrate,
+            64
+        );
+        assert!(matches!(
+            HlsRendition::find(AudioFormat::Aac, Some(100)),
+            Err(HlsError::UnsupportedRendition(AudioFormat::Aac, 100))
+        ));
+    }
+
+    #[cfg(feature = "aac")]
+    #[test]
+    fn master_playlist_lists_renditions() {
+        let renditions = [
+            HlsRendition::new(AudioFormat::Aac, 64).unwrap(),
+            HlsRendition::new(AudioFormat::Aac, 256).unwrap(),
+        ];
+
+        let playlist =
+            master_playlist(&renditions, |x| format!("media.m3u8?bitrate={}", x.bitrate));
+
+        assert_eq!(
+            playlist,
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-INDEPENDENT-SEGMENTS\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=64000,CODECS=\"mp4a.40.2\"\nmedia.m3u8?bitrate=64\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=256000,CODECS=\"mp4a.40.2\"\nmedia.m3u8?bitrate=256\n"
+        );
+    }
+
+    #[test]
+    fn packed_audio_timestamp_tag_holds_90khz_timestamp() {
+        let tag = packed_audio_timestamp_tag(1.0);
+
+        assert_eq!(&tag[..4], b"ID3\x04");
+        assert_eq!(id3_tag_len(&tag), tag.len());
+        assert_eq!(&tag[tag.len() - 8..], &90_000_u64.to_be_bytes());
+    }
+}
@@ -11,7 +11,7 @@ use futures::prelude::*;
 use futures_core::Stream;
 use moosicbox_audio_decoder::{
     decode_file_path_str_async, decode_media_source_async,
-    media_sources::remote_bytestream::RemoteByteStreamMediaSource, DecodeError,
+    media_sources::remote_bytestream::RemoteByteStreamMediaSource, AudioDecodeError, DecodeError,
 };
 use moosicbox_audio_output::{AudioOutputError, AudioWrite, Channels, SignalSpec};
 use moosicbox_music_api::{
@@ -33,15 +33,17 @@ use symphonia::core::{
     util::clamp::clamp_i16,
 };
 use thiserror::Error;
-use tokio::io::AsyncSeekExt;
+use tokio::io::{AsyncReadExt as _, AsyncSeekExt};
 use tokio_util::{
     codec::{BytesCodec, FramedRead},
     sync::CancellationToken,
 };
 
 use crate::files::{
-    filename_from_path_str, track_bytes_media_source::TrackBytesMediaSource,
-    track_pool::get_or_fetch_track,
+    filename_from_path_str,
+    track_bytes_media_source::TrackBytesMediaSource,
+    track_pool::{get_or_fetch_track, track_key},
+    transcode_cache::{self, CacheWriter},
 };
 
 use super::track_pool::service::CommanderError;
@@ -57,12 +59,18 @@ pub fn audio_format_to_content_type(format: &AudioFormat) -> Option<String> {
     match format {
         #[cfg(feature = "aac")]
         AudioFormat::Aac => Some("audio/m4a".into()),
+        #[cfg(feature = "alac")]
+        AudioFormat::Alac => Some("audio/mp4".into()),
         #[cfg(feature = "flac")]
         AudioFormat::Flac => Some("audio/flac".into()),
         #[cfg(feature = "mp3")]
         AudioFormat::Mp3 => Some("audio/mp3".into()),
         #[cfg(feature = "opus")]
         AudioFormat::Opus => Some("audio/opus".into()),
+        #[cfg(feature = "vorbis")]
+        AudioFormat::Vorbis => Some("audio/ogg".into()),
+        #[cfg(feature = "wav")]
+        AudioFormat::Wav => Some("audio/wav".into()),
         AudioFormat::Source => None,
     }
 }
@@ -228,15 +236,17 @@ pub async fn get_track_bytes(
     api: &dyn MusicApi,
     track_id: &Id,
     source: TrackSource,
-    format: AudioFormat,
+    quality: PlaybackQuality,
     try_to_get_size: bool,
     start: Option<u64>,
     end: Option<u64>,
 ) -> Result<TrackBytes, GetTrackBytesError> {
-    log::debug!("get_track_bytes: Getting track bytes track_id={track_id} format={format:?} try_to_get_size={try_to_get_size} start={start:?} end={end:?}");
+    log::debug!("get_track_bytes: Getting track bytes track_id={track_id} quality={quality:?} try_to_get_size={try_to_get_size} start={start:?} end={end:?}");
 
-    let size = if try_to_get_size {
-        match get_or_init_track_size(api, track_id, &source, PlaybackQuality { format }).await {
+    // The stored size is for the whole file, not the part of it that is the
+    // track, and sizes are only stored for the default encoding settings
+    let size = if try_to_get_size && !source.has_offsets() && quality.settings.is_default() {
+        match get_or_init_track_size(api, track_id, &source, quality).await {
             Ok(size) => Some(size),
             Err(err) => match err {
                 TrackInfoError::UnsupportedFormat(_) | TrackInfoError::UnsupportedSource(_) => None,
@@ -263,20 +273,12 @@ pub async fn get_track_bytes(
 
     log::debug!("get_track_bytes: Got track from api: track={track:?}");
 
-    let format = match format {
-        #[cfg(feature = "flac")]
-        AudioFormat::Flac => {
-            if track.format != Some(AudioFormat::Flac) {
-                return Err(GetTrackBytesError::UnsupportedFormat);
-            }
-            format
-        }
-        AudioFormat::Source => format,
-        #[allow(unreachable_patterns)]
-        _ => format,
-    };
+    #[cfg(feature = "flac")]
+    if quality.format == AudioFormat::Flac && track.format != Some(AudioFormat::Flac) {
+        return Err(GetTrackBytesError::UnsupportedFormat);
+    }
 
-    get_audio_bytes(source, format, size, start, end).await
+    get_audio_bytes(source, quality, size, start, end).await
 }
 
 #[derive(Debug, Error)]
@@ -316,6 +318,11 @@ pub fn get_silence_bytes(
                 use moosicbox_audio_output::encoder::aac::AacEncoder;
                 Box::new(AacEncoder::with_writer(writer).open(spec, duration))
             }
+            #[cfg(feature = "alac")]
+            AudioFormat::Alac => {
+                use moosicbox_audio_output::encoder::alac::AlacEncoder;
+                Box::new(AlacEncoder::with_writer(writer).open(spec, duration))
+            }
             #[cfg(feature = "flac")]
             AudioFormat::Flac => {
                 use moosicbox_audio_output::encoder::flac::FlacEncoder;
@@ -331,10 +338,28 @@ pub fn get_silence_bytes(
                 use moosicbox_audio_output::encoder::opus::OpusEncoder;
                 Box::new(OpusEncoder::with_writer(writer).open(spec, duration))
             }
+            #[cfg(feature = "vorbis")]
+            AudioFormat::Vorbis => {
+                use moosicbox_audio_output::encoder::vorbis::VorbisEncoder;
+                Box::new(VorbisEncoder::with_writer(writer).open(spec, duration))
+            }
+            #[cfg(feature = "wav")]
+            AudioFormat::Wav => {
+                use moosicbox_audio_output::encoder::wav::WavEncoder;
+                Box::new(WavEncoder::with_writer(writer).open(spec, duration))
+            }
             AudioFormat::Source => return Err::<(), _>(GetSilenceBytesError::InvalidSource),
         };
 
-        #[cfg(any(feature = "aac", feature = "flac", feature = "mp3", feature = "opus"))]
+        #[cfg(any(
+            feature = "aac",
+            feature = "alac",
+            feature = "flac",
+            feature = "mp3",
+            feature = "opus",
+            feature = "vorbis",
+            feature = "wav"
+        ))]
         {
             let mut buffer = AudioBuffer::<f32>::new(duration, spec);
             buffer.render_silence(None);
@@ -355,6 +380,27 @@ pub fn get_silence_bytes(
     })
 }
 
+#[cfg(any(
+    feature = "aac",
+    feature = "alac",
+    feature = "flac",
+    feature = "mp3",
+    feature = "opus",
+    feature = "vorbis",
+    feature = "wav"
+))]
+const fn encoder_settings(
+    settings: moosicbox_music_models::EncodingSettings,
+) -> moosicbox_audio_output::encoder::EncoderSettings {
+    moosicbox_audio_output::encoder::EncoderSettings {
+        bitrate: settings.bitrate,
+        vbr_quality: settings.vbr_quality,
+        sample_rate: settings.sample_rate,
+        channels: settings.channels,
+        bits_per_sample: None,
+    }
+}
+
 /// # Errors
 ///
 /// * If the track cover was not found
@@ -365,24 +411,80 @@ pub fn get_silence_bytes(
 #[allow(clippy::too_many_lines)]
 pub async fn get_audio_bytes(
     source: TrackSource,
-    format: AudioFormat,
+    quality: PlaybackQuality,
     size: Option<u64>,
     start: Option<u64>,
     end: Option<u64>,
 ) -> Result<TrackBytes, GetTrackBytesError> {
-    log::debug!("Getting audio bytes format={format:?} size={size:?} start={start:?} end={end:?}");
+    log::debug!(
+        "Getting audio bytes quality={quality:?} size={size:?} start={start:?} end={end:?}"
+    );
 
-    get_or_fetch_track(&source, format, size, start, end, {
+    // A track that is only part of its file has to be cut out of the file by
+    // decoding it, so it is re-encoded in the source format
+    let quality = if source.has_offsets() && quality.format == AudioFormat::Source {
+        PlaybackQuality {
+            format: source.format(),
+            ..quality
+        }
+    } else {
+        quality
+    };
+    let format = quality.format;
+    #[cfg(any(
+        feature = "aac",
+        feature = "alac",
+        feature = "flac",
+        feature = "mp3",
+        feature = "opus",
+        feature = "vorbis",
+        feature = "wav"
+    ))]
+    let settings = encoder_settings(quality.settings);
+    let key = track_key(&source, quality);
+
+    get_or_fetch_track(&source, quality, size, start, end, {
         let source = source.clone();
         move |start, end, size| {
             let source = source.clone();
+            let key = key.clone();
             Box::pin(async move {
                 log::debug!("get_audio_bytes: cache miss; eagerly fetching audio bytes");
                 let writer = ByteWriter::default();
                 let writer_id = writer.id;
                 #[allow(unused)]
                 let stream = writer.stream();
-                let same_format = format == AudioFormat::Source || source.format() == format;
+                let same_format = !source.has_offsets()
+                    && (format == AudioFormat::Source
+                        || (source.format() == format && quality.settings.is_default()));
+
+                // Transcodes of local files are kept on disk so that they
+                // don't need to be encoded again
+                let cache_source = match &source {
+                    TrackSource::LocalFilePath { path, .. }
+                        if !same_format
+                            && format != AudioFormat::Source
+                            && transcode_cache::is_enabled() =>
+                    {
+                        Some(path.clone())
+                    }
+                    _ => None,
+                };
+
+                if let Some(path) = &cache_source {
+                    if let Some(cached) = transcode_cache::get(path, &key).await {
+                        let mut track_bytes = request_audio_bytes_from_file(
+                            cached.to_string_lossy().to_string(),
+                            format,
+                            None,
+                            start,
+                            end,
+                        )
+                        .await?;
+                        track_bytes.filename = filename_from_path_str(path);
+                        return Ok(track_bytes);
+                    }
+                }
 
                 let track_bytes = if same_format {
                     match source {
@@ -394,9 +496,18 @@ pub async fn get_audio_bytes(
                         }
                     }
                 } else {
+                    let end_offset = match source {
+                        TrackSource::LocalFilePath { end_offset, .. } => end_offset,
+                        TrackSource::RemoteUrl { .. } => None,
+                    };
+                    let writer = match &cache_source {
+                        Some(path) => CacheWriter::new(writer, path, &key),
+                        None => CacheWriter::from(writer),
+                    };
+                    let cache_writer = writer.clone();
                     let get_handler = move || {
                         #[allow(unreachable_code)]
-                        Ok(match format {
+                        let handler: moosicbox_audio_decoder::AudioDecodeHandler = match format {
                             #[cfg(feature = "aac")]
                             AudioFormat::Aac => {
                                 use moosicbox_audio_output::encoder::aac::AacEncoder;
@@ -404,6 +515,21 @@ pub async fn get_audio_bytes(
                                     Box::new(move |spec, duration| {
                                         Ok(Box::new(
                                             AacEncoder::with_writer(writer.clone())
+                                                .with_settings(&settings)
+                                                .map_err(|e| AudioDecodeError::Other(Box::new(e)))?
+                                                .open(spec, duration),
+                                        ))
+                                    }),
+                                )
+                            }
+                            #[cfg(feature = "alac")]
+                            AudioFormat::Alac => {
+                                use moosicbox_audio_output::encoder::alac::AlacEncoder;
+                                moosicbox_audio_decoder::AudioDecodeHandler::new().with_output(
+                                    Box::new(move |spec, duration| {
+                                        Ok(Box::new(
+                                            AlacEncoder::with_writer(writer.clone())
+                                                .with_settings(&settings)
                                                 .open(spec, duration),
                                         ))
                                     }),
@@ -416,6 +542,8 @@ pub async fn get_audio_bytes(
                                     Box::new(move |spec, duration| {
                                         Ok(Box::new(
                                             FlacEncoder::with_writer(writer.clone())
+                                                .with_settings(&settings)
+                                                .map_err(|e| AudioDecodeError::Other(Box::new(e)))?
                                                 .open(spec, duration),
                                         ))
                                     }),
@@ -428,6 +556,8 @@ pub async fn get_audio_bytes(
                                     Box::new(move |spec, duration| {
                                         Ok(Box::new(
                                             Mp3Encoder::with_writer(writer.clone())
+                                                .with_settings(&settings)
+                                                .map_err(|e| AudioDecodeError::Other(Box::new(e)))?
                                                 .open(spec, duration),
                                         ))
                                     }),
@@ -440,6 +570,35 @@ pub async fn get_audio_bytes(
                                     Box::new(move |spec, duration| {
                                         Ok(Box::new(
                                             OpusEncoder::with_writer(writer.clone())
+                                                .with_settings(&settings)
+                                                .map_err(|e| AudioDecodeError::Other(Box::new(e)))?
+                                                .open(spec, duration),
+                                        ))
+                                    }),
+                                )
+                            }
+                            #[cfg(feature = "vorbis")]
+                            AudioFormat::Vorbis => {
+                                use moosicbox_audio_output::encoder::vorbis::VorbisEncoder;
+                                moosicbox_audio_decoder::AudioDecodeHandler::new().with_output(
+                                    Box::new(move |spec, duration| {
+                                        Ok(Box::new(
+                                            VorbisEncoder::with_writer(writer.clone())
+                                                .with_settings(&settings)
+                                                .map_err(|e| AudioDecodeError::Other(Box::new(e)))?
+                                                .open(spec, duration),
+                                        ))
+                                    }),
+                                )
+                            }
+                            #[cfg(feature = "wav")]
+                            AudioFormat::Wav => {
+                                use moosicbox_audio_output::encoder::wav::WavEncoder;
+                                moosicbox_audio_decoder::AudioDecodeHandler::new().with_output(
+                                    Box::new(move |spec, duration| {
+                                        Ok(Box::new(
+                                            WavEncoder::with_writer(writer.clone())
+                                                .with_settings(&settings)
                                                 .open(spec, duration),
                                         ))
                                     }),
@@ -448,26 +607,57 @@ pub async fn get_audio_bytes(
                             AudioFormat::Source => {
                                 return Err(moosicbox_audio_decoder::DecodeError::InvalidSource)
                             }
+                        };
+
+                        Ok(match end_offset {
+                            Some(end_offset) => handler.with_end_time(end_offset),
+                            None => handler,
                         })
                     };
 
                     match source {
-                        TrackSource::LocalFilePath { ref path, .. } => {
-                            if let Err(err) = decode_file_path_str_async(
-                                path,
-                                get_handler,
-                                true,
-                                true,
-                                None,
-                                None,
-                            )
-                            .await
-                            {
-                                log::error!(
-                                    "Failed to encode to {format} (source={}): {err:?}",
-                                    source.format()
-                                );
-                            }
+                        TrackSource::LocalFilePath {
+                            ref path,
+                            start_offset,
+                            ..
+                        } => {
+                            let path = path.clone();
+                            let source_format = source.format();
+                            let cache = cache_source.is_some();
+
+                            // The encoded bytes are streamed as they are written, so the
+                            // caller doesn't have to wait for the whole track to be encoded
+                            moosicbox_task::spawn("files: encode local file", async move {
+                                let result = decode_file_path_str_async(
+                                    &path,
+                                    get_handler,
+                                    true,
+                                    true,
+                                    None,
+                                    start_offset,
+                                )
+                                .await;
+
+                                if let Err(err) = &result {
+                                    log::error!(
+                                        "Failed to encode to {format} (source={source_format}): {err:?}",
+                                    );
+                                }
+
+                                if cache {
+                                    let succeeded = result.is_ok();
+                                    moosicbox_task::spawn_blocking(
+                                        "files: transcode_cache save",
+                                        move || {
+                                            if succeeded {
+                                                cache_writer.commit();
+                                            } else {
+                                                cache_writer.discard();
+                                            }
+                                        },
+                                    );
+                                }
+                            });
                         }
                         TrackSource::RemoteUrl { ref url, .. } => {
                             let source_format = source.format();
@@ -582,8 +772,11 @@ async fn request_audio_bytes_from_file(
         "request_audio_bytes_from_file calculated size={size} original_size={original_size}"
     );
 
-    let framed_read =
-        FramedRead::with_capacity(file, BytesCodec::new(), usize::try_from(size).unwrap());
+    let framed_read = FramedRead::with_capacity(
+        file.take(size),
+        BytesCodec::new(),
+        usize::try_from(size).unwrap(),
+    );
 
     Ok(TrackBytes {
         id: new_byte_writer_id(),
@@ -807,7 +1000,7 @@ pub async fn get_or_init_track_visualization(
     let viz = Arc::new(RwLock::new(vec![]));
     let inner_viz = viz.clone();
 
-    let bytes = get_audio_bytes(source.clone(), source.format(), None, None, None)
+    let bytes = get_audio_bytes(source.clone(), source.format().into(), None, None, None)
         .await
         .map_err(Box::new)?;
 
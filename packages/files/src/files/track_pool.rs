@@ -14,7 +14,7 @@ use futures::StreamExt;
 use futures_core::Future;
 use lazy_static::lazy_static;
 use moosicbox_music_api::models::TrackSource;
-use moosicbox_music_models::AudioFormat;
+use moosicbox_music_models::{AudioFormat, PlaybackQuality};
 use moosicbox_stream_utils::{stalled_monitor::StalledReadMonitor, ByteWriter};
 use strum_macros::AsRefStr;
 use thiserror::Error;
@@ -66,7 +66,7 @@ pub enum Command {
     FetchTrackBytes {
         tx: Sender<TrackBytes>,
         source: TrackSource,
-        output_format: AudioFormat,
+        quality: PlaybackQuality,
         size: Option<u64>,
         start: Option<u64>,
         end: Option<u64>,
@@ -104,13 +104,13 @@ impl Context {
     async fn fetch_track_bytes(
         &mut self,
         source: TrackSource,
-        output_format: AudioFormat,
+        quality: PlaybackQuality,
         size: Option<u64>,
         start: Option<u64>,
         end: Option<u64>,
         fetch: FetchTrackBytesFunc,
     ) -> Result<TrackBytes, GetTrackBytesError> {
-        let key = track_key(&source, output_format);
+        let key = track_key(&source, quality);
         log::debug!("get_or_fetch_track key={key}");
 
         let semaphore = self
@@ -233,7 +233,7 @@ impl service::Processor for service::Service {
             Command::FetchTrackBytes {
                 tx,
                 source,
-                output_format,
+                quality,
                 size,
                 start,
                 end,
@@ -242,7 +242,7 @@ impl service::Processor for service::Service {
                 tx.send_async(
                     ctx.write()
                         .await
-                        .fetch_track_bytes(source, output_format, size, start, end, fetch)
+                        .fetch_track_bytes(source, quality, size, start, end, fetch)
                         .await?,
                 )
                 .await?;
@@ -407,22 +407,42 @@ impl TrackBytesSource {
 }
 
 #[must_use]
-pub fn track_key(source: &TrackSource, output_format: AudioFormat) -> String {
+pub fn track_key(source: &TrackSource, quality: PlaybackQuality) -> String {
+    let output_format = if quality.settings.is_default() {
+        quality.format.to_string()
+    } else {
+        format!("{}[{}]", quality.format, quality.settings)
+    };
+
     match source {
         TrackSource::LocalFilePath {
             format,
             path,
             track_id,
             source,
+            start_offset,
+            end_offset,
         } => {
-            format!(
+            let key = format!(
                 "local:{source}:{format}:{id}:{output_format}",
                 id = track_id
                     .as_ref()
                     .map(|x| format!("id:{x}"))
                     .as_deref()
                     .unwrap_or(path)
-            )
+            );
+
+            // The tracks of a CUE sheet are different parts of the same
+            // file, so they are different outputs
+            if start_offset.is_some() || end_offset.is_some() {
+                format!(
+                    "{key}:{start}-{end}",
+                    start = start_offset.unwrap_or(0.0),
+                    end = end_offset.map_or_else(String::new, |x| x.to_string()),
+                )
+            } else {
+                key
+            }
         }
         TrackSource::RemoteUrl {
             format,
@@ -450,7 +470,7 @@ pub fn track_key(source: &TrackSource, output_format: AudioFormat) -> String {
 /// * If the `AudioFormat` is invalid
 pub async fn get_or_fetch_track(
     source: &TrackSource,
-    output_format: AudioFormat,
+    quality: PlaybackQuality,
     size: Option<u64>,
     start: Option<u64>,
     end: Option<u64>,
@@ -479,7 +499,7 @@ pub async fn get_or_fetch_track(
         .send_command_async(Command::FetchTrackBytes {
             tx,
             source: source.clone(),
-            output_format,
+            quality,
             size,
             start,
             end,
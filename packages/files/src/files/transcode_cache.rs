@@ -0,0 +1,558 @@
+//! On-disk cache of transcoded tracks.
+//!
+//! Entries are stored at `{cache dir}/transcodes/{source}/{key}`, where
+//! `{source}` is a hash of the path of the file the entry was transcoded from,
+//! so that every entry of a file can be dropped when the file changes, and
+//! `{key}` is a hash of the `track_pool::track_key` of the entry. Whenever the
+//! entries grow past the size limit, the least recently used ones are evicted.
+//! The size and last use of every entry are kept in an index that is loaded
+//! from the cache directory once, so that saving an entry doesn't have to walk
+//! the whole directory. An entry may have an index of its encoded frames next
+//! to it, which is dropped along with the entry.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex},
+    time::SystemTime,
+};
+
+use moosicbox_stream_utils::ByteWriter;
+
+/// The default size limit of the cache, in megabytes
+pub const DEFAULT_MAX_SIZE_MB: u64 = 2048;
+
+/// The size limit of the cache, in bytes. Set by
+/// `MOOSICBOX_TRANSCODE_CACHE_MAX_SIZE_MB`, where `0` disables the cache.
+static MAX_SIZE: LazyLock<u64> = LazyLock::new(|| {
+    std::env::var("MOOSICBOX_TRANSCODE_CACHE_MAX_SIZE_MB")
+        .ok()
+        .and_then(|x| x.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_SIZE_MB)
+        .saturating_mul(1024 * 1024)
+});
+
+/// The index of the entries in the cache directory. `None` until it's first
+/// needed.
+static INDEX: LazyLock<Mutex<Option<CacheIndex>>> = LazyLock::new(|| Mutex::new(None));
+
+#[must_use]
+pub fn is_enabled() -> bool {
+    *MAX_SIZE > 0
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    moosicbox_config::get_cache_dir_path().map(|x| x.join("transcodes"))
+}
+
+fn source_dir_in(dir: &Path, source_path: &str) -> PathBuf {
+    dir.join(format!("{:x}", md5::compute(source_path)))
+}
+
+fn entry_path_in(dir: &Path, source_path: &str, key: &str) -> PathBuf {
+    source_dir_in(dir, source_path).join(format!("{:x}", md5::compute(key)))
+}
+
+fn source_dir(source_path: &str) -> Option<PathBuf> {
+    cache_dir().map(|x| source_dir_in(&x, source_path))
+}
+
+fn entry_path(source_path: &str, key: &str) -> Option<PathBuf> {
+    cache_dir().map(|x| entry_path_in(&x, source_path, key))
+}
+
+/// The path of the frame index of the entry at `path`.
+#[must_use]
+pub fn index_path(path: &Path) -> PathBuf {
+    path.with_extension("frames")
+}
+
+/// The path of the cached entry for `key`, if there is one. Marks the entry
+/// as the most recently used.
+pub async fn get(source_path: &str, key: &str) -> Option<PathBuf> {
+    if !is_enabled() {
+        return None;
+    }
+
+    let path = entry_path(source_path, key)?;
+
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        log::trace!("transcode_cache: miss key={key}");
+        return None;
+    }
+
+    log::debug!("transcode_cache: hit key={key} path={path:?}");
+
+    let touched = path.clone();
+    let _ = moosicbox_task::spawn_blocking("files: transcode_cache touch", move || {
+        let now = SystemTime::now();
+
+        if let Err(e) = File::options()
+            .write(true)
+            .open(&touched)
+            .and_then(|file| file.set_modified(now))
+        {
+            log::warn!("transcode_cache: failed to touch {touched:?}: {e:?}");
+        }
+
+        if let Some(index) = INDEX.lock().unwrap().as_mut() {
+            index.touch(&touched, now);
+        }
+    })
+    .await;
+
+    Some(path)
+}
+
+/// Drops every entry transcoded from the file at `source_path`.
+///
+/// # Errors
+///
+/// * If the entries fail to be removed
+pub fn invalidate(source_path: &str) -> std::io::Result<()> {
+    let Some(dir) = source_dir(source_path) else {
+        return Ok(());
+    };
+
+    if dir.is_dir() {
+        log::debug!("transcode_cache: invalidating entries of source_path={source_path}");
+        std::fs::remove_dir_all(&dir)?;
+    }
+
+    if let Some(index) = INDEX.lock().unwrap().as_mut() {
+        index.remove_source(&dir);
+    }
+
+    Ok(())
+}
+
+/// Adds the entry at `path` to the index, loading the index from `dir` if it
+/// hasn't been yet, and evicts the least recently used entries if the cache
+/// has grown past `max_size` bytes.
+fn record(dir: &Path, path: PathBuf, len: u64, max_size: u64) -> std::io::Result<()> {
+    let mut index = INDEX.lock().unwrap();
+
+    let index = match &mut *index {
+        Some(index) => index,
+        empty => empty.insert(CacheIndex::load(dir)?),
+    };
+
+    index.insert(path, len, SystemTime::now());
+    index.evict(max_size)
+}
+
+/// A writer that passes the transcoded bytes through to a `ByteWriter`, and
+/// also writes them to a temporary file that becomes the cache entry once
+/// the transcode has finished successfully.
+#[derive(Clone)]
+pub struct CacheWriter {
+    writer: ByteWriter,
+    file: Arc<Mutex<Option<File>>>,
+    temp_path: Option<PathBuf>,
+    path: Option<PathBuf>,
+}
+
+impl CacheWriter {
+    #[must_use]
+    pub fn new(writer: ByteWriter, source_path: &str, key: &str) -> Self {
+        Self::with_path(writer, entry_path(source_path, key))
+    }
+
+    fn with_path(writer: ByteWriter, path: Option<PathBuf>) -> Self {
+        let temp_path = path
+            .as_ref()
+            .map(|x| x.with_extension(format!("{}.tmp", writer.id)));
+
+        let file = temp_path.as_ref().and_then(|temp_path| {
+            temp_path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| File::create(temp_path))
+                .map_err(|e| log::warn!("transcode_cache: failed to create {temp_path:?}: {e:?}"))
+                .ok()
+        });
+
+        Self {
+            writer,
+            file: Arc::new(Mutex::new(file)),
+            temp_path,
+            path,
+        }
+    }
+
+    /// Moves the transcoded file into the cache and evicts the least recently
+    /// used entries if the cache has grown past its size limit.
+    ///
+    /// # Panics
+    ///
+    /// * If the file or index `Mutex` is poisoned
+    pub fn commit(&self) {
+        let Some(len) = self.save() else {
+            return;
+        };
+        let (Some(dir), Some(path)) = (cache_dir(), &self.path) else {
+            return;
+        };
+
+        if let Err(e) = record(&dir, path.clone(), len, *MAX_SIZE) {
+            log::warn!("transcode_cache: failed to evict entries: {e:?}");
+        }
+    }
+
+    /// Moves the transcoded file to the entry path, returning its size.
+    fn save(&self) -> Option<u64> {
+        let Some(mut file) = self.file.lock().unwrap().take() else {
+            self.discard();
+            return None;
+        };
+        let (Some(temp_path), Some(path)) = (&self.temp_path, &self.path) else {
+            return None;
+        };
+
+        match file
+            .flush()
+            .and_then(|()| file.metadata())
+            .and_then(|metadata| std::fs::rename(temp_path, path).map(|()| metadata.len()))
+        {
+            Ok(len) => {
+                log::debug!("transcode_cache: saved {path:?} ({len} bytes)");
+                Some(len)
+            }
+            Err(e) => {
+                log::warn!("transcode_cache: failed to save {path:?}: {e:?}");
+                self.discard();
+                None
+            }
+        }
+    }
+
+    /// Drops the partially transcoded file.
+    ///
+    /// # Panics
+    ///
+    /// * If the file `Mutex` is poisoned
+    pub fn discard(&self) {
+        self.file.lock().unwrap().take();
+
+        if let Some(temp_path) = &self.temp_path {
+            let _ = std::fs::remove_file(temp_path);
+        }
+    }
+}
+
+impl From<ByteWriter> for CacheWriter {
+    /// A `CacheWriter` that only passes the bytes through, without caching
+    fn from(writer: ByteWriter) -> Self {
+        Self {
+            writer,
+            file: Arc::new(Mutex::new(None)),
+            temp_path: None,
+            path: None,
+        }
+    }
+}
+
+impl Write for CacheWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut file = self.file.lock().unwrap();
+
+        if let Some(writer) = file.as_mut() {
+            if let Err(e) = writer.write_all(buf) {
+                log::warn!(
+                    "transcode_cache: failed to write to {:?}: {e:?}",
+                    self.temp_path
+                );
+                file.take();
+            }
+        }
+
+        drop(file);
+
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CacheEntry {
+    len: u64,
+    used: SystemTime,
+}
+
+/// The size and last use of every entry in the cache.
+#[derive(Debug, Default)]
+struct CacheIndex {
+    entries: BTreeMap<PathBuf, CacheEntry>,
+    size: u64,
+}
+
+impl CacheIndex {
+    /// Walks `dir` for the entries that are already in the cache. A missing
+    /// `dir` is an empty cache.
+    fn load(dir: &Path) -> std::io::Result<Self> {
+        let mut index = Self::default();
+
+        let source_dirs = match std::fs::read_dir(dir) {
+            Ok(source_dirs) => source_dirs,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(index),
+            Err(e) => return Err(e),
+        };
+
+        for source_dir in source_dirs {
+            let source_dir = source_dir?.path();
+
+            if !source_dir.is_dir() {
+                continue;
+            }
+
+            for entry in std::fs::read_dir(&source_dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+
+                if metadata.is_file() && entry.path().extension().is_none() {
+                    index.insert(
+                        entry.path(),
+                        metadata.len(),
+                        metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    );
+                }
+            }
+        }
+
+        log::debug!(
+            "transcode_cache: loaded {} entries ({} bytes)",
+            index.entries.len(),
+            index.size
+        );
+
+        Ok(index)
+    }
+
+    fn insert(&mut self, path: PathBuf, len: u64, used: SystemTime) {
+        if let Some(previous) = self.entries.insert(path, CacheEntry { len, used }) {
+            self.size -= previous.len;
+        }
+        self.size += len;
+    }
+
+    fn touch(&mut self, path: &Path, used: SystemTime) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.used = used;
+        }
+    }
+
+    /// Drops the entries of the `source_dir` that has been removed.
+    fn remove_source(&mut self, source_dir: &Path) {
+        self.entries.retain(|path, entry| {
+            if path.starts_with(source_dir) {
+                self.size -= entry.len;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Removes the least recently used entries until the entries take up no
+    /// more than `max_size` bytes.
+    fn evict(&mut self, max_size: u64) -> std::io::Result<()> {
+        if self.size <= max_size {
+            return Ok(());
+        }
+
+        let mut entries = self
+            .entries
+            .iter()
+            .map(|(path, entry)| (path.clone(), *entry))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| entry.used);
+
+        for (path, entry) in entries {
+            if self.size <= max_size {
+                break;
+            }
+
+            log::debug!("transcode_cache: evicting {path:?} ({} bytes)", entry.len);
+
+            for path in [&path, &index_path(&path)] {
+                match std::fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            self.entries.remove(&path);
+            self.size -= entry.len;
+
+            if let Some(parent) = path.parent() {
+                // Only succeeds once the source has no entries left
+                let _ = std::fs::remove_dir(parent);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "moosicbox_transcode_cache_{name}_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_entry(path: &Path, len: usize, modified: SystemTime) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = File::create(path).unwrap();
+        file.set_len(len as u64).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn entry_path_is_stable_per_key_and_grouped_by_source() {
+        let dir = Path::new("/cache");
+
+        let path = entry_path_in(dir, "/music/track.flac", "1:MP3");
+
+        assert_eq!(path, entry_path_in(dir, "/music/track.flac", "1:MP3"));
+        assert_ne!(path, entry_path_in(dir, "/music/track.flac", "1:AAC"));
+        assert_ne!(path, entry_path_in(dir, "/music/other.flac", "1:MP3"));
+        assert_eq!(
+            path.parent().unwrap(),
+            source_dir_in(dir, "/music/track.flac")
+        );
+        assert_eq!(
+            entry_path_in(dir, "/music/track.flac", "1:AAC").parent(),
+            path.parent()
+        );
+    }
+
+    #[test]
+    fn save_moves_the_written_bytes_to_the_entry_path() {
+        let dir = temp_dir("save");
+        let path = entry_path_in(&dir, "/music/track.flac", "1:MP3");
+        let mut writer = CacheWriter::with_path(ByteWriter::default(), Some(path.clone()));
+        let temp_path = writer.temp_path.clone().unwrap();
+
+        writer.write_all(b"transcoded").unwrap();
+
+        assert_eq!(writer.save(), Some(10));
+        assert_eq!(std::fs::read(&path).unwrap(), b"transcoded");
+        assert!(!temp_path.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn discard_drops_the_partial_entry() {
+        let dir = temp_dir("discard");
+        let path = entry_path_in(&dir, "/music/track.flac", "1:MP3");
+        let mut writer = CacheWriter::with_path(ByteWriter::default(), Some(path.clone()));
+        let temp_path = writer.temp_path.clone().unwrap();
+
+        writer.write_all(b"partial").unwrap();
+        writer.discard();
+
+        assert_eq!(writer.save(), None);
+        assert!(!temp_path.exists());
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_indexes_entries_and_skips_temp_files() {
+        let dir = temp_dir("load");
+        let now = SystemTime::now();
+        write_entry(&entry_path_in(&dir, "a", "1"), 10, now);
+        write_entry(&entry_path_in(&dir, "b", "1"), 20, now);
+        write_entry(
+            &entry_path_in(&dir, "b", "2").with_extension("1.tmp"),
+            40,
+            now,
+        );
+
+        let index = CacheIndex::load(&dir).unwrap();
+
+        assert_eq!(index.entries.len(), 2);
+        assert_eq!(index.size, 30);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_of_missing_dir_is_empty() {
+        let dir = temp_dir("missing").join("transcodes");
+
+        let index = CacheIndex::load(&dir).unwrap();
+
+        assert!(index.entries.is_empty());
+        assert_eq!(index.size, 0);
+    }
+
+    #[test]
+    fn evict_removes_least_recently_used_entries() {
+        let dir = temp_dir("evict");
+        let now = SystemTime::now();
+        let oldest = entry_path_in(&dir, "a", "1");
+        let older = entry_path_in(&dir, "b", "1");
+        let newest = entry_path_in(&dir, "b", "2");
+        write_entry(&oldest, 10, now - Duration::from_secs(30));
+        write_entry(&older, 10, now - Duration::from_secs(20));
+        write_entry(&newest, 10, now - Duration::from_secs(10));
+
+        let mut index = CacheIndex::load(&dir).unwrap();
+        index.touch(&oldest, now);
+        index.evict(20).unwrap();
+
+        assert_eq!(index.size, 20);
+        assert!(oldest.exists());
+        assert!(!older.exists());
+        assert!(newest.exists());
+
+        std::fs::write(index_path(&newest), b"frames").unwrap();
+        index.evict(10).unwrap();
+
+        assert_eq!(index.size, 10);
+        assert!(!index_path(&newest).exists());
+        assert!(oldest.exists());
+        assert!(!newest.exists());
+        assert!(!newest.parent().unwrap().exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn insert_and_remove_source_keep_the_size_in_sync() {
+        let dir = Path::new("/cache");
+        let now = SystemTime::now();
+        let mut index = CacheIndex::default();
+
+        index.insert(entry_path_in(dir, "a", "1"), 10, now);
+        index.insert(entry_path_in(dir, "a", "2"), 20, now);
+        index.insert(entry_path_in(dir, "b", "1"), 40, now);
+        index.insert(entry_path_in(dir, "b", "1"), 50, now);
+
+        assert_eq!(index.size, 80);
+
+        index.remove_source(&source_dir_in(dir, "a"));
+
+        assert_eq!(index.entries.len(), 1);
+        assert_eq!(index.size, 50);
+    }
+}
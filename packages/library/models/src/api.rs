@@ -1,10 +1,15 @@
 use moosicbox_music_models::{
     api::{ApiAlbum, ApiAlbumVersionQuality, ApiArtist, ApiTrack},
-    Album, AlbumSource, ApiSource, ApiSources, Artist, AudioFormat, Track, TrackApiSource,
+    id::Id,
+    Album, AlbumSource, ApiSource, ApiSources, Artist, AudioFormat, ReplayGain, Track,
+    TrackApiSource,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{LibraryAlbum, LibraryAlbumType, LibraryArtist, LibraryTrack};
+use crate::{
+    LibraryAlbum, LibraryAlbumPlayCount, LibraryAlbumType, LibraryArtist, LibraryArtistPlayCount,
+    LibraryPlay, LibraryTrack, LibraryTrackPlayCount,
+};
 
 impl From<LibraryArtist> for ApiArtist {
     fn from(value: LibraryArtist) -> Self {
@@ -42,6 +47,8 @@ pub struct ApiLibraryAlbum {
     pub source: AlbumSource,
     pub blur: bool,
     pub versions: Vec<ApiAlbumVersionQuality>,
+    #[serde(default)]
+    pub genres: Vec<String>,
     pub album_sources: ApiSources,
     pub artist_sources: ApiSources,
 }
@@ -89,6 +96,7 @@ impl From<ApiLibraryAlbum> for Album {
             directory: None,
             blur: value.blur,
             versions: vec![],
+            upc: None,
             album_source: value.source,
             api_source: ApiSource::Library,
             album_sources: value.album_sources,
@@ -111,6 +119,7 @@ impl From<LibraryAlbum> for ApiLibraryAlbum {
             source: value.source,
             blur: value.blur,
             versions: value.versions.into_iter().map(Into::into).collect(),
+            genres: value.genres,
             album_sources: value.album_sources,
             artist_sources: value.artist_sources,
         }
@@ -148,6 +157,18 @@ pub struct ApiLibraryTrack {
     pub overall_bitrate: Option<u32>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
+    pub replay_gain: Option<ReplayGain>,
+    pub play_count: u32,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    pub composer: Option<String>,
+    /// The artist(s) credited on the track, if they differ from the album artist
+    pub track_artist: Option<String>,
+    pub isrc: Option<String>,
+    pub start_offset: Option<f64>,
+    pub end_offset: Option<f64>,
     pub source: TrackApiSource,
     pub api_source: ApiSource,
 }
@@ -182,6 +203,17 @@ impl From<ApiLibraryTrack> for LibraryTrack {
             overall_bitrate: value.overall_bitrate,
             sample_rate: value.sample_rate,
             channels: value.channels,
+            replay_gain: value.replay_gain,
+            play_count: value.play_count,
+            disc_number: value.disc_number,
+            disc_total: value.disc_total,
+            genres: value.genres,
+            composer: value.composer,
+            track_artist: value.track_artist,
+            isrc: value.isrc,
+            musicbrainz_recording_id: None,
+            start_offset: value.start_offset,
+            end_offset: value.end_offset,
             source: value.source,
             api_source: value.api_source,
             qobuz_id: None,
@@ -215,9 +247,117 @@ impl From<ApiLibraryTrack> for Track {
             overall_bitrate: value.overall_bitrate,
             sample_rate: value.sample_rate,
             channels: value.channels,
+            replay_gain: value.replay_gain,
+            play_count: Some(value.play_count),
+            disc_number: value.disc_number,
+            disc_total: value.disc_total,
+            genres: value.genres,
+            composer: value.composer,
+            track_artist: value.track_artist,
+            isrc: value.isrc,
+            start_offset: value.start_offset,
+            end_offset: value.end_offset,
             track_source: value.source,
             api_source: ApiSource::Library,
             sources: ApiSources::default().with_source(ApiSource::Library, value.track_id.into()),
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiLibraryPlay {
+    pub play_id: u64,
+    pub profile: String,
+    pub session_id: u64,
+    pub audio_zone_id: Option<u64>,
+    pub api_source: ApiSource,
+    pub played_at: u64,
+    pub track: ApiTrack,
+}
+
+impl From<LibraryPlay> for ApiLibraryPlay {
+    fn from(value: LibraryPlay) -> Self {
+        Self {
+            play_id: value.id,
+            profile: value.profile,
+            session_id: value.session_id,
+            audio_zone_id: value.audio_zone_id,
+            api_source: value.api_source,
+            played_at: value.played_at,
+            track: value.track.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiLibraryTrackPlayCount {
+    pub track: ApiTrack,
+    pub play_count: u32,
+    pub last_played_at: u64,
+}
+
+impl From<LibraryTrackPlayCount> for ApiLibraryTrackPlayCount {
+    fn from(value: LibraryTrackPlayCount) -> Self {
+        Self {
+            track: value.track.into(),
+            play_count: value.play_count,
+            last_played_at: value.last_played_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiLibraryAlbumPlayCount {
+    pub album_id: Id,
+    pub title: String,
+    pub artist: String,
+    pub artist_id: Id,
+    pub contains_cover: bool,
+    pub api_source: ApiSource,
+    pub play_count: u32,
+    pub last_played_at: u64,
+}
+
+impl From<LibraryAlbumPlayCount> for ApiLibraryAlbumPlayCount {
+    fn from(value: LibraryAlbumPlayCount) -> Self {
+        Self {
+            album_id: value.album_id,
+            title: value.title,
+            artist: value.artist,
+            artist_id: value.artist_id,
+            contains_cover: value.artwork.is_some(),
+            api_source: value.api_source,
+            play_count: value.play_count,
+            last_played_at: value.last_played_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiLibraryArtistPlayCount {
+    pub artist_id: Id,
+    pub title: String,
+    pub api_source: ApiSource,
+    pub play_count: u32,
+    pub last_played_at: u64,
+}
+
+impl From<LibraryArtistPlayCount> for ApiLibraryArtistPlayCount {
+    fn from(value: LibraryArtistPlayCount) -> Self {
+        Self {
+            artist_id: value.artist_id,
+            title: value.title,
+            api_source: value.api_source,
+            play_count: value.play_count,
+            last_played_at: value.last_played_at,
+        }
+    }
+}
@@ -10,10 +10,14 @@ use moosicbox_json_utils::{
     MissingValue, ParseError, ToValueType,
 };
 use moosicbox_music_models::{
-    AlbumSource, AlbumVersionQuality, ApiSource, ApiSources, AudioFormat, TrackApiSource,
+    split_genres, AlbumSource, AlbumVersionQuality, ApiSource, ApiSources, AudioFormat, ReplayGain,
+    TrackApiSource,
 };
 
-use crate::{sort_album_versions, LibraryAlbum, LibraryAlbumType, LibraryArtist, LibraryTrack};
+use crate::{
+    sort_album_versions, LibraryAlbum, LibraryAlbumType, LibraryArtist, LibraryPlaylist,
+    LibraryTrack,
+};
 
 impl AsId for LibraryTrack {
     fn as_id(&self) -> DatabaseValue {
@@ -36,6 +40,7 @@ impl ToValueType<LibraryArtist> for &moosicbox_database::Row {
             tidal_id: self.to_value("tidal_id")?,
             qobuz_id: self.to_value("qobuz_id")?,
             yt_id: self.to_value("yt_id")?,
+            musicbrainz_artist_id: self.to_value("musicbrainz_artist_id").unwrap_or_default(),
         })
     }
 }
@@ -49,6 +54,7 @@ impl AsModelResult<LibraryArtist, ParseError> for &moosicbox_database::Row {
             tidal_id: self.to_value("tidal_id")?,
             qobuz_id: self.to_value("qobuz_id")?,
             yt_id: self.to_value("yt_id")?,
+            musicbrainz_artist_id: self.to_value("musicbrainz_artist_id").unwrap_or_default(),
         })
     }
 }
@@ -120,6 +126,14 @@ impl ToValueType<LibraryAlbum> for &moosicbox_database::Row {
             source: AlbumSource::Local,
             blur: self.to_value("blur")?,
             versions: vec![],
+            genres: self
+                .to_value::<Option<String>>("genres")
+                .unwrap_or_default()
+                .as_deref()
+                .map(split_genres)
+                .unwrap_or_default(),
+            musicbrainz_release_id: self.to_value("musicbrainz_release_id").unwrap_or_default(),
+            upc: self.to_value("upc").unwrap_or_default(),
             album_sources: {
                 #[allow(unused_mut)]
                 let mut sources = ApiSources::default().with_source(ApiSource::Library, id.into());
@@ -199,6 +213,14 @@ impl AsModelResult<LibraryAlbum, ParseError> for &moosicbox_database::Row {
             source: AlbumSource::Local,
             blur: self.to_value("blur")?,
             versions: vec![],
+            genres: self
+                .to_value::<Option<String>>("genres")
+                .unwrap_or_default()
+                .as_deref()
+                .map(split_genres)
+                .unwrap_or_default(),
+            musicbrainz_release_id: self.to_value("musicbrainz_release_id").unwrap_or_default(),
+            upc: self.to_value("upc").unwrap_or_default(),
             album_sources: {
                 #[allow(unused_mut)]
                 let mut sources = ApiSources::default().with_source(ApiSource::Library, id.into());
@@ -410,6 +432,13 @@ impl AsModelQuery<LibraryAlbum> for &moosicbox_database::Row {
             source: AlbumSource::Local,
             blur: self.to_value("blur")?,
             versions: get_album_version_qualities(&db.into(), id).await?,
+            genres: self
+                .to_value::<Option<String>>("genres")?
+                .as_deref()
+                .map(split_genres)
+                .unwrap_or_default(),
+            musicbrainz_release_id: self.to_value("musicbrainz_release_id").unwrap_or_default(),
+            upc: self.to_value("upc").unwrap_or_default(),
             album_sources: {
                 #[allow(unused_mut)]
                 let mut sources = ApiSources::default().with_source(ApiSource::Library, id.into());
@@ -459,6 +488,25 @@ impl AsId for LibraryAlbum {
     }
 }
 
+impl MissingValue<LibraryPlaylist> for &moosicbox_database::Row {}
+impl ToValueType<LibraryPlaylist> for &moosicbox_database::Row {
+    fn to_value_type(self) -> Result<LibraryPlaylist, ParseError> {
+        Ok(LibraryPlaylist {
+            id: self.to_value("id")?,
+            name: self.to_value("name")?,
+            created: self.to_value("created")?,
+            updated: self.to_value("updated")?,
+            track_count: self.to_value("track_count")?,
+        })
+    }
+}
+
+impl AsId for LibraryPlaylist {
+    fn as_id(&self) -> DatabaseValue {
+        DatabaseValue::Number(self.id.try_into().unwrap())
+    }
+}
+
 impl AsModel<LibraryTrack> for &moosicbox_database::Row {
     fn as_model(&self) -> LibraryTrack {
         AsModelResult::as_model(self).unwrap()
@@ -497,6 +545,30 @@ impl ToValueType<LibraryTrack> for &moosicbox_database::Row {
             overall_bitrate: self.to_value("overall_bitrate").unwrap_or_default(),
             sample_rate: self.to_value("sample_rate").unwrap_or_default(),
             channels: self.to_value("channels").unwrap_or_default(),
+            replay_gain: ReplayGain {
+                track_gain: self.to_value("replay_gain_track_gain").unwrap_or_default(),
+                track_peak: self.to_value("replay_gain_track_peak").unwrap_or_default(),
+                album_gain: self.to_value("replay_gain_album_gain").unwrap_or_default(),
+                album_peak: self.to_value("replay_gain_album_peak").unwrap_or_default(),
+            }
+            .or_none(),
+            play_count: self.to_value("play_count").unwrap_or_default(),
+            disc_number: self.to_value("disc_number")?,
+            disc_total: self.to_value("disc_total")?,
+            genres: self
+                .to_value::<Option<String>>("genres")
+                .unwrap_or_default()
+                .as_deref()
+                .map(split_genres)
+                .unwrap_or_default(),
+            composer: self.to_value("composer").unwrap_or_default(),
+            track_artist: self.to_value("track_artist").unwrap_or_default(),
+            isrc: self.to_value("isrc").unwrap_or_default(),
+            musicbrainz_recording_id: self
+                .to_value("musicbrainz_recording_id")
+                .unwrap_or_default(),
+            start_offset: self.to_value("start_offset").unwrap_or_default(),
+            end_offset: self.to_value("end_offset").unwrap_or_default(),
             source: TrackApiSource::from_str(&self.to_value::<String>("source")?)
                 .expect("Missing source"),
             api_source: ApiSource::Library,
@@ -539,6 +611,30 @@ impl AsModelResult<LibraryTrack, ParseError> for &moosicbox_database::Row {
             overall_bitrate: self.to_value("overall_bitrate").unwrap_or_default(),
             sample_rate: self.to_value("sample_rate").unwrap_or_default(),
             channels: self.to_value("channels").unwrap_or_default(),
+            replay_gain: ReplayGain {
+                track_gain: self.to_value("replay_gain_track_gain").unwrap_or_default(),
+                track_peak: self.to_value("replay_gain_track_peak").unwrap_or_default(),
+                album_gain: self.to_value("replay_gain_album_gain").unwrap_or_default(),
+                album_peak: self.to_value("replay_gain_album_peak").unwrap_or_default(),
+            }
+            .or_none(),
+            play_count: self.to_value("play_count").unwrap_or_default(),
+            disc_number: self.to_value("disc_number")?,
+            disc_total: self.to_value("disc_total")?,
+            genres: self
+                .to_value::<Option<String>>("genres")
+                .unwrap_or_default()
+                .as_deref()
+                .map(split_genres)
+                .unwrap_or_default(),
+            composer: self.to_value("composer").unwrap_or_default(),
+            track_artist: self.to_value("track_artist").unwrap_or_default(),
+            isrc: self.to_value("isrc").unwrap_or_default(),
+            musicbrainz_recording_id: self
+                .to_value("musicbrainz_recording_id")
+                .unwrap_or_default(),
+            start_offset: self.to_value("start_offset").unwrap_or_default(),
+            end_offset: self.to_value("end_offset").unwrap_or_default(),
             source: TrackApiSource::from_str(&self.to_value::<String>("source")?)
                 .expect("Missing source"),
             api_source: ApiSource::Library,
@@ -11,8 +11,9 @@ use std::{path::PathBuf, str::FromStr as _};
 
 use moosicbox_json_utils::{ParseError, ToValueType};
 use moosicbox_music_models::{
-    id::TryFromIdError, Album, AlbumSource, AlbumType, AlbumVersionQuality, ApiSource, ApiSources,
-    Artist, AudioFormat, Track, TrackApiSource,
+    id::{Id, TryFromIdError},
+    Album, AlbumSource, AlbumType, AlbumVersionQuality, ApiSource, ApiSources, Artist, AudioFormat,
+    Playlist, ReplayGain, Track, TrackApiSource,
 };
 use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, EnumString};
@@ -25,6 +26,7 @@ pub struct LibraryArtist {
     pub tidal_id: Option<u64>,
     pub qobuz_id: Option<u64>,
     pub yt_id: Option<u64>,
+    pub musicbrainz_artist_id: Option<String>,
 }
 
 impl From<LibraryArtist> for Artist {
@@ -132,6 +134,9 @@ pub struct LibraryAlbum {
     pub source: AlbumSource,
     pub blur: bool,
     pub versions: Vec<AlbumVersionQuality>,
+    pub genres: Vec<String>,
+    pub musicbrainz_release_id: Option<String>,
+    pub upc: Option<String>,
     pub album_sources: ApiSources,
     pub artist_sources: ApiSources,
 }
@@ -150,6 +155,7 @@ impl From<LibraryAlbum> for Album {
             directory: value.directory,
             blur: value.blur,
             versions: value.versions,
+            upc: value.upc,
             album_source: value.source,
             api_source: ApiSource::Library,
             artist_sources: value.artist_sources,
@@ -174,6 +180,9 @@ impl TryFrom<Album> for LibraryAlbum {
             directory: value.directory,
             blur: value.blur,
             versions: value.versions,
+            genres: vec![],
+            musicbrainz_release_id: None,
+            upc: value.upc,
             source: AlbumSource::Local,
             album_sources: value.album_sources,
             artist_sources: value.artist_sources,
@@ -232,6 +241,20 @@ pub struct LibraryTrack {
     pub overall_bitrate: Option<u32>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
+    pub replay_gain: Option<ReplayGain>,
+    pub play_count: u32,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    pub genres: Vec<String>,
+    pub composer: Option<String>,
+    /// The artist(s) credited on the track, if they differ from the album artist
+    pub track_artist: Option<String>,
+    pub isrc: Option<String>,
+    pub musicbrainz_recording_id: Option<String>,
+    /// Where the track starts in its file, in seconds, when the file holds several tracks
+    pub start_offset: Option<f64>,
+    /// Where the track ends in its file, in seconds. `None` is the end of the file
+    pub end_offset: Option<f64>,
     pub source: TrackApiSource,
     pub api_source: ApiSource,
     pub qobuz_id: Option<u64>,
@@ -276,6 +299,16 @@ impl From<LibraryTrack> for Track {
             overall_bitrate: value.overall_bitrate,
             sample_rate: value.sample_rate,
             channels: value.channels,
+            replay_gain: value.replay_gain,
+            play_count: Some(value.play_count),
+            disc_number: value.disc_number,
+            disc_total: value.disc_total,
+            genres: value.genres,
+            composer: value.composer,
+            track_artist: value.track_artist,
+            isrc: value.isrc,
+            start_offset: value.start_offset,
+            end_offset: value.end_offset,
             track_source: value.source,
             api_source: ApiSource::Library,
             sources: {
@@ -300,3 +333,101 @@ impl From<LibraryTrack> for Track {
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct LibraryPlaylist {
+    pub id: u64,
+    pub name: String,
+    pub created: String,
+    pub updated: String,
+    pub track_count: Option<u32>,
+}
+
+impl From<LibraryPlaylist> for Playlist {
+    fn from(value: LibraryPlaylist) -> Self {
+        Self {
+            id: value.id.into(),
+            title: value.name,
+            description: None,
+            artwork: None,
+            track_count: value.track_count,
+            api_source: ApiSource::Library,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiLibraryPlaylist {
+    pub playlist_id: u64,
+    pub name: String,
+    pub created: String,
+    pub updated: String,
+    pub track_count: Option<u32>,
+}
+
+impl From<LibraryPlaylist> for ApiLibraryPlaylist {
+    fn from(value: LibraryPlaylist) -> Self {
+        Self {
+            playlist_id: value.id,
+            name: value.name,
+            created: value.created,
+            updated: value.updated,
+            track_count: value.track_count,
+        }
+    }
+}
+
+impl From<ApiLibraryPlaylist> for LibraryPlaylist {
+    fn from(value: ApiLibraryPlaylist) -> Self {
+        Self {
+            id: value.playlist_id,
+            name: value.name,
+            created: value.created,
+            updated: value.updated,
+            track_count: value.track_count,
+        }
+    }
+}
+
+/// A track that was listened to for long enough to be recorded in the play history.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LibraryPlay {
+    pub id: u64,
+    pub profile: String,
+    pub session_id: u64,
+    pub audio_zone_id: Option<u64>,
+    pub api_source: ApiSource,
+    /// Seconds since the unix epoch
+    pub played_at: u64,
+    pub track: Track,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LibraryTrackPlayCount {
+    pub track: Track,
+    pub play_count: u32,
+    pub last_played_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct LibraryAlbumPlayCount {
+    pub album_id: Id,
+    pub title: String,
+    pub artist: String,
+    pub artist_id: Id,
+    pub artwork: Option<String>,
+    pub api_source: ApiSource,
+    pub play_count: u32,
+    pub last_played_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct LibraryArtistPlayCount {
+    pub artist_id: Id,
+    pub title: String,
+    pub api_source: ApiSource,
+    pub play_count: u32,
+    pub last_played_at: u64,
+}
@@ -7,9 +7,14 @@ use actix_web::{
     web::{self, Json},
     Result, Scope,
 };
-use moosicbox_database::profiles::LibraryDatabase;
-use moosicbox_music_api::models::AlbumsRequest;
-use moosicbox_music_models::{api::ApiAlbum, id::parse_integer_ranges_to_ids, AlbumSort};
+use moosicbox_database::profiles::{api::ProfileName, LibraryDatabase};
+use moosicbox_music_api::{models::AlbumsRequest, MusicApis, SourceToMusicApi as _};
+use moosicbox_music_models::{
+    api::ApiAlbum,
+    id::{parse_integer_ranges_to_ids, Id, IdType},
+    lyrics::Lyrics,
+    AlbumSort, ApiSource,
+};
 use moosicbox_paging::{Page, PagingRequest};
 use moosicbox_search::api::models::ApiSearchResultsResponse;
 use serde::{Deserialize, Serialize};
@@ -17,18 +22,30 @@ use serde_json::Value;
 use strum_macros::{AsRefStr, EnumString};
 
 use crate::{
-    add_favorite_album, add_favorite_artist, add_favorite_track, album, album_tracks, artist,
-    artist_albums, favorite_albums, favorite_artists, favorite_tracks, reindex_global_search_index,
-    remove_favorite_album, remove_favorite_artist, remove_favorite_track, search, track,
-    track_file_url, LibraryAddFavoriteAlbumError, LibraryAddFavoriteArtistError,
-    LibraryAddFavoriteTrackError, LibraryAlbumError, LibraryAlbumOrder, LibraryAlbumOrderDirection,
-    LibraryAlbumTracksError, LibraryAlbumType, LibraryArtist, LibraryArtistAlbumsError,
-    LibraryArtistError, LibraryArtistOrder, LibraryArtistOrderDirection, LibraryAudioQuality,
-    LibraryFavoriteAlbumsError, LibraryFavoriteArtistsError, LibraryFavoriteTracksError,
+    add_favorite_album, add_favorite_artist, add_favorite_track, add_playlist_tracks, album,
+    album_tracks, artist, artist_albums, create_playlist, delete_playlist, favorite_albums,
+    favorite_artists, favorite_tracks,
+    models::{
+        api::{
+            ApiLibraryAlbumPlayCount, ApiLibraryArtistPlayCount, ApiLibraryPlay,
+            ApiLibraryTrackPlayCount,
+        },
+        ApiLibraryPlaylist,
+    },
+    most_played_albums, most_played_artists, most_played_tracks, move_playlist_track, playlist,
+    playlist_tracks, playlists, recently_played, reindex_global_search_index,
+    remove_favorite_album, remove_favorite_artist, remove_favorite_track, remove_playlist_tracks,
+    rename_playlist, search, track, track_file_url, track_lyrics, LibraryAddFavoriteAlbumError,
+    LibraryAddFavoriteArtistError, LibraryAddFavoriteTrackError, LibraryAlbumError,
+    LibraryAlbumOrder, LibraryAlbumOrderDirection, LibraryAlbumTracksError, LibraryAlbumType,
+    LibraryArtist, LibraryArtistAlbumsError, LibraryArtistError, LibraryArtistOrder,
+    LibraryArtistOrderDirection, LibraryAudioQuality, LibraryFavoriteAlbumsError,
+    LibraryFavoriteArtistsError, LibraryFavoriteTracksError, LibraryPlayHistoryError,
+    LibraryPlaylistError, LibraryPlaylistTracksError, LibraryPlaylistsError,
     LibraryRemoveFavoriteAlbumError, LibraryRemoveFavoriteArtistError,
     LibraryRemoveFavoriteTrackError, LibrarySearchError, LibraryTrack, LibraryTrackError,
-    LibraryTrackFileUrlError, LibraryTrackOrder, LibraryTrackOrderDirection, ReindexError,
-    SearchType,
+    LibraryTrackFileUrlError, LibraryTrackLyricsError, LibraryTrackOrder,
+    LibraryTrackOrderDirection, LibraryUpdatePlaylistTracksError, ReindexError, SearchType,
 };
 
 pub fn bind_services<
@@ -52,8 +69,22 @@ pub fn bind_services<
         .service(album_endpoint)
         .service(artist_endpoint)
         .service(track_endpoint)
+        .service(track_lyrics_endpoint)
         .service(search_endpoint)
         .service(reindex_endpoint)
+        .service(playlists_endpoint)
+        .service(playlist_endpoint)
+        .service(create_playlist_endpoint)
+        .service(rename_playlist_endpoint)
+        .service(delete_playlist_endpoint)
+        .service(playlist_tracks_endpoint)
+        .service(add_playlist_tracks_endpoint)
+        .service(remove_playlist_tracks_endpoint)
+        .service(move_playlist_track_endpoint)
+        .service(recently_played_endpoint)
+        .service(most_played_tracks_endpoint)
+        .service(most_played_albums_endpoint)
+        .service(most_played_artists_endpoint)
 }
 
 #[cfg(feature = "openapi")]
@@ -76,8 +107,22 @@ pub fn bind_services<
         album_endpoint,
         artist_endpoint,
         track_endpoint,
+        track_lyrics_endpoint,
         search_endpoint,
         reindex_endpoint,
+        playlists_endpoint,
+        playlist_endpoint,
+        create_playlist_endpoint,
+        rename_playlist_endpoint,
+        delete_playlist_endpoint,
+        playlist_tracks_endpoint,
+        add_playlist_tracks_endpoint,
+        remove_playlist_tracks_endpoint,
+        move_playlist_track_endpoint,
+        recently_played_endpoint,
+        most_played_tracks_endpoint,
+        most_played_albums_endpoint,
+        most_played_artists_endpoint,
     ),
     components(schemas(
         LibraryTrackQuery,
@@ -88,6 +133,13 @@ pub fn bind_services<
         ApiLibraryArtist,
         ApiLibraryAlbum,
         ApiLibraryTrack,
+        ApiLibraryPlaylist,
+        ApiLibraryPlay,
+        ApiLibraryTrackPlayCount,
+        ApiLibraryAlbumPlayCount,
+        ApiLibraryArtistPlayCount,
+        Lyrics,
+        moosicbox_music_models::lyrics::LyricsLine,
         ApiSearchResultsResponse,
         moosicbox_search::api::models::ApiGlobalSearchResult,
         moosicbox_search::api::models::ApiGlobalArtistSearchResult,
@@ -965,6 +1017,65 @@ pub async fn track_endpoint(
     Ok(Json(track.into()))
 }
 
+impl From<LibraryTrackLyricsError> for actix_web::Error {
+    fn from(err: LibraryTrackLyricsError) -> Self {
+        log::error!("{err:?}");
+        match err {
+            LibraryTrackLyricsError::NotFound => ErrorNotFound("Track not found"),
+            LibraryTrackLyricsError::DatabaseFetch(_) | LibraryTrackLyricsError::TrackLyrics(_) => {
+                ErrorInternalServerError(err.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryTrackLyricsQuery {
+    track_id: String,
+    source: Option<ApiSource>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/tracks/lyrics",
+        description = "Get the lyrics of a track. Synced lyrics have the start time of each line, in seconds, so the current line can be followed along with the playback progress",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("trackId" = String, Query, description = "The track ID"),
+            ("source" = Option<ApiSource>, Query, description = "API source to fetch the lyrics from"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The track's lyrics",
+                body = Lyrics,
+            )
+        )
+    )
+)]
+#[route("/tracks/lyrics", method = "GET")]
+pub async fn track_lyrics_endpoint(
+    query: web::Query<LibraryTrackLyricsQuery>,
+    db: LibraryDatabase,
+    music_apis: MusicApis,
+) -> Result<Json<Lyrics>> {
+    let source = query.source.unwrap_or(ApiSource::Library);
+    let track_id =
+        Id::try_from_str(&query.track_id, source, IdType::Track).map_err(ErrorBadRequest)?;
+    let api = music_apis
+        .get(source)
+        .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?;
+
+    let lyrics = track_lyrics(&db, &**api, &track_id)
+        .await?
+        .ok_or_else(|| ErrorNotFound("Lyrics not found"))?;
+
+    Ok(Json(lyrics))
+}
+
 impl From<LibrarySearchError> for actix_web::Error {
     fn from(err: LibrarySearchError) -> Self {
         ErrorInternalServerError(err.to_string())
@@ -1005,10 +1116,10 @@ pub struct LibrarySearchQuery {
 #[route("/search", method = "GET")]
 pub async fn search_endpoint(
     query: web::Query<LibrarySearchQuery>,
-    db: LibraryDatabase,
+    profile: ProfileName,
 ) -> Result<Json<ApiSearchResultsResponse>> {
     let results = search(
-        &db,
+        &profile.0,
         &query.query,
         query.offset,
         query.limit,
@@ -1018,7 +1129,7 @@ pub async fn search_endpoint(
             .map(|x| x.into_iter().map(Into::into).collect::<Vec<_>>()),
     )?;
 
-    Ok(Json(results.into()))
+    Ok(Json(results))
 }
 
 impl From<ReindexError> for actix_web::Error {
@@ -1057,9 +1168,575 @@ pub struct ReindexQuery {}
 #[route("/reindex", method = "POST")]
 pub async fn reindex_endpoint(
     _query: web::Query<ReindexQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
 ) -> Result<Json<Value>> {
-    reindex_global_search_index(&db).await?;
+    reindex_global_search_index(&profile.0, &db).await?;
 
     Ok(Json(serde_json::json!({"success": true})))
 }
+
+impl From<LibraryPlaylistsError> for actix_web::Error {
+    fn from(err: LibraryPlaylistsError) -> Self {
+        log::error!("{err:?}");
+        ErrorInternalServerError(err.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryPlaylistsQuery {
+    offset: Option<u32>,
+    limit: Option<u32>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/playlists",
+        description = "List the saved playlists",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Page of playlist metadata",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/playlists", method = "GET")]
+pub async fn playlists_endpoint(
+    query: web::Query<LibraryPlaylistsQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Page<ApiLibraryPlaylist>>> {
+    Ok(Json(
+        playlists(&db, query.offset, query.limit)
+            .await?
+            .map(Into::into)
+            .into(),
+    ))
+}
+
+impl From<LibraryPlaylistError> for actix_web::Error {
+    fn from(err: LibraryPlaylistError) -> Self {
+        log::error!("{err:?}");
+        match err {
+            LibraryPlaylistError::NotFound => ErrorNotFound("Playlist not found"),
+            LibraryPlaylistError::DatabaseFetch(_) => ErrorInternalServerError(err.to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryPlaylistQuery {
+    playlist_id: u64,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/playlist",
+        description = "Get the playlist metadata for a playlistId",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = u64, Query, description = "The playlist ID"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Playlist metadata information",
+                body = ApiLibraryPlaylist,
+            )
+        )
+    )
+)]
+#[route("/playlist", method = "GET")]
+pub async fn playlist_endpoint(
+    query: web::Query<LibraryPlaylistQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<ApiLibraryPlaylist>> {
+    let playlist = playlist(&db, query.playlist_id)
+        .await?
+        .ok_or_else(|| ErrorNotFound("Playlist not found"))?;
+
+    Ok(Json(playlist.into()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryCreatePlaylistQuery {
+    name: String,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        post,
+        path = "/playlist",
+        description = "Create a new, empty playlist",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("name" = String, Query, description = "The name of the playlist"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The created playlist",
+                body = ApiLibraryPlaylist,
+            )
+        )
+    )
+)]
+#[route("/playlist", method = "POST")]
+pub async fn create_playlist_endpoint(
+    query: web::Query<LibraryCreatePlaylistQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<ApiLibraryPlaylist>> {
+    Ok(Json(create_playlist(&db, &query.name).await?.into()))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRenamePlaylistQuery {
+    playlist_id: u64,
+    name: String,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        patch,
+        path = "/playlist",
+        description = "Rename a playlist",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = u64, Query, description = "The playlist ID"),
+            ("name" = String, Query, description = "The new name of the playlist"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The updated playlist",
+                body = ApiLibraryPlaylist,
+            )
+        )
+    )
+)]
+#[route("/playlist", method = "PATCH")]
+pub async fn rename_playlist_endpoint(
+    query: web::Query<LibraryRenamePlaylistQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<ApiLibraryPlaylist>> {
+    Ok(Json(
+        rename_playlist(&db, query.playlist_id, &query.name)
+            .await?
+            .into(),
+    ))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        delete,
+        path = "/playlist",
+        description = "Delete a playlist and all of its tracks",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = u64, Query, description = "The playlist ID"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Success message",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/playlist", method = "DELETE")]
+pub async fn delete_playlist_endpoint(
+    query: web::Query<LibraryPlaylistQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Value>> {
+    delete_playlist(&db, query.playlist_id).await?;
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+impl From<LibraryPlaylistTracksError> for actix_web::Error {
+    fn from(err: LibraryPlaylistTracksError) -> Self {
+        log::error!("{err:?}");
+        match err {
+            LibraryPlaylistTracksError::NotFound => ErrorNotFound("Playlist not found"),
+            LibraryPlaylistTracksError::DatabaseFetch(_) => {
+                ErrorInternalServerError(err.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryPlaylistTracksQuery {
+    playlist_id: u64,
+    offset: Option<u32>,
+    limit: Option<u32>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/playlist/tracks",
+        description = "Get the list of playlist track metadata for a playlistId",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = u64, Query, description = "The playlist ID"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Page of playlist track metadata",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/playlist/tracks", method = "GET")]
+pub async fn playlist_tracks_endpoint(
+    query: web::Query<LibraryPlaylistTracksQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Page<moosicbox_music_models::api::ApiTrack>>> {
+    Ok(Json(
+        playlist_tracks(&db, query.playlist_id, query.offset, query.limit)
+            .await?
+            .map(Into::into)
+            .into(),
+    ))
+}
+
+impl From<LibraryUpdatePlaylistTracksError> for actix_web::Error {
+    fn from(err: LibraryUpdatePlaylistTracksError) -> Self {
+        log::error!("{err:?}");
+        match err {
+            LibraryUpdatePlaylistTracksError::NotFound => ErrorNotFound("Playlist not found"),
+            LibraryUpdatePlaylistTracksError::TrackNotFound(track_id) => {
+                ErrorNotFound(format!("Track not found: {track_id}"))
+            }
+            LibraryUpdatePlaylistTracksError::PositionOutOfBounds(position) => {
+                ErrorBadRequest(format!("Position out of bounds: {position}"))
+            }
+            LibraryUpdatePlaylistTracksError::DatabaseFetch(_) => {
+                ErrorInternalServerError(err.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryAddPlaylistTracksQuery {
+    playlist_id: u64,
+    track_ids: String,
+    source: Option<ApiSource>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        post,
+        path = "/playlist/tracks",
+        description = "Append tracks from the given source to the end of a playlist",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = u64, Query, description = "The playlist ID"),
+            ("trackIds" = String, Query, description = "A comma-separated list of track IDs"),
+            ("source" = Option<ApiSource>, Query, description = "API source to fetch the tracks from"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Success message",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/playlist/tracks", method = "POST")]
+pub async fn add_playlist_tracks_endpoint(
+    query: web::Query<LibraryAddPlaylistTracksQuery>,
+    db: LibraryDatabase,
+    music_apis: MusicApis,
+) -> Result<Json<Value>> {
+    let track_ids = parse_integer_ranges_to_ids(query.track_ids.as_str())
+        .map_err(|e| ErrorBadRequest(format!("Invalid track id values: {e:?}")))?;
+
+    let tracks = music_apis
+        .get(query.source.unwrap_or(ApiSource::Library))
+        .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?
+        .tracks(Some(track_ids.as_ref()), None, None, None, None)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("Failed to get tracks: {e:?}")))?
+        .with_rest_of_items_in_batches()
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("Failed to get tracks: {e:?}")))?;
+
+    add_playlist_tracks(&db, query.playlist_id, tracks).await?;
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRemovePlaylistTracksQuery {
+    playlist_id: u64,
+    positions: String,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        delete,
+        path = "/playlist/tracks",
+        description = "Remove the tracks at the given positions from a playlist",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = u64, Query, description = "The playlist ID"),
+            ("positions" = String, Query, description = "A comma-separated list of track positions"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Success message",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/playlist/tracks", method = "DELETE")]
+pub async fn remove_playlist_tracks_endpoint(
+    query: web::Query<LibraryRemovePlaylistTracksQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Value>> {
+    let positions = query
+        .positions
+        .split(',')
+        .map(|x| x.trim().parse::<u32>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| ErrorBadRequest(format!("Invalid positions: {e:?}")))?;
+
+    remove_playlist_tracks(&db, query.playlist_id, &positions).await?;
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryMovePlaylistTrackQuery {
+    playlist_id: u64,
+    from_position: u32,
+    to_position: u32,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        post,
+        path = "/playlist/tracks/move",
+        description = "Move a track within a playlist to a new position",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = u64, Query, description = "The playlist ID"),
+            ("fromPosition" = u32, Query, description = "The current position of the track"),
+            ("toPosition" = u32, Query, description = "The position to move the track to"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Success message",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/playlist/tracks/move", method = "POST")]
+pub async fn move_playlist_track_endpoint(
+    query: web::Query<LibraryMovePlaylistTrackQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Value>> {
+    move_playlist_track(
+        &db,
+        query.playlist_id,
+        query.from_position,
+        query.to_position,
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+impl From<LibraryPlayHistoryError> for actix_web::Error {
+    fn from(err: LibraryPlayHistoryError) -> Self {
+        log::error!("{err:?}");
+        ErrorInternalServerError(err.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryPlayHistoryQuery {
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/history/recent",
+        description = "List the recently played tracks, most recent first",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("since" = Option<u64>, Query, description = "Only include plays at or after this unix timestamp (in seconds)"),
+            ("until" = Option<u64>, Query, description = "Only include plays before this unix timestamp (in seconds)"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Page of plays",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/history/recent", method = "GET")]
+pub async fn recently_played_endpoint(
+    query: web::Query<LibraryPlayHistoryQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Page<ApiLibraryPlay>>> {
+    Ok(Json(
+        recently_played(&db, query.since, query.until, query.offset, query.limit)
+            .await?
+            .map(Into::into)
+            .into(),
+    ))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/history/most-played/tracks",
+        description = "List the most played tracks within a time window",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("since" = Option<u64>, Query, description = "Only include plays at or after this unix timestamp (in seconds)"),
+            ("until" = Option<u64>, Query, description = "Only include plays before this unix timestamp (in seconds)"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Page of tracks and their play counts",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/history/most-played/tracks", method = "GET")]
+pub async fn most_played_tracks_endpoint(
+    query: web::Query<LibraryPlayHistoryQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Page<ApiLibraryTrackPlayCount>>> {
+    Ok(Json(
+        most_played_tracks(&db, query.since, query.until, query.offset, query.limit)
+            .await?
+            .map(Into::into)
+            .into(),
+    ))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/history/most-played/albums",
+        description = "List the most played albums within a time window",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("since" = Option<u64>, Query, description = "Only include plays at or after this unix timestamp (in seconds)"),
+            ("until" = Option<u64>, Query, description = "Only include plays before this unix timestamp (in seconds)"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Page of albums and their play counts",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/history/most-played/albums", method = "GET")]
+pub async fn most_played_albums_endpoint(
+    query: web::Query<LibraryPlayHistoryQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Page<ApiLibraryAlbumPlayCount>>> {
+    Ok(Json(
+        most_played_albums(&db, query.since, query.until, query.offset, query.limit)
+            .await?
+            .map(Into::into)
+            .into(),
+    ))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Library"],
+        get,
+        path = "/history/most-played/artists",
+        description = "List the most played artists within a time window",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("since" = Option<u64>, Query, description = "Only include plays at or after this unix timestamp (in seconds)"),
+            ("until" = Option<u64>, Query, description = "Only include plays before this unix timestamp (in seconds)"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Page of artists and their play counts",
+                body = Value,
+            )
+        )
+    )
+)]
+#[route("/history/most-played/artists", method = "GET")]
+pub async fn most_played_artists_endpoint(
+    query: web::Query<LibraryPlayHistoryQuery>,
+    db: LibraryDatabase,
+) -> Result<Json<Page<ApiLibraryArtistPlayCount>>> {
+    Ok(Json(
+        most_played_artists(&db, query.since, query.until, query.offset, query.limit)
+            .await?
+            .map(Into::into)
+            .into(),
+    ))
+}
@@ -1,25 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use moosicbox_database::{
     boxed,
     profiles::LibraryDatabase,
     query::{
-        coalesce, identifier, literal, where_in, where_not_eq, FilterableQuery, SortDirection,
+        coalesce, delete, identifier, insert, literal, update, where_gte, where_in, where_lt,
+        where_not_eq, BooleanExpression, FilterableQuery, SortDirection, TransactionStatement,
     },
     DatabaseError, DatabaseValue,
 };
 use moosicbox_json_utils::{
-    database::{AsModelResultMapped as _, DatabaseFetchError},
-    ToValueType,
+    database::{AsModelResultMapped as _, DatabaseFetchError, ToValue as _},
+    ParseError, ToValueType,
+};
+use moosicbox_music_models::{
+    api::ApiTrack, id::Id, join_genres, ApiSource, AudioFormat, PlaybackQuality, TrackApiSource,
+    TrackSize,
 };
-use moosicbox_music_models::{id::Id, AudioFormat, PlaybackQuality, TrackApiSource, TrackSize};
 use thiserror::Error;
 
 pub mod models;
 
 use crate::{
     db::models::LibraryConfig,
-    models::{LibraryAlbum, LibraryArtist, LibraryTrack},
+    models::{LibraryAlbum, LibraryArtist, LibraryPlay, LibraryPlaylist, LibraryTrack},
 };
 
 /// # Errors
@@ -289,6 +293,218 @@ pub async fn get_album(
         .to_value_type()?)
 }
 
+/// The identifiers of a release that [`find_matching_albums`] looks up library albums by.
+#[derive(Debug, Clone, Default)]
+pub struct AlbumMatchIds {
+    pub musicbrainz_release_id: Option<String>,
+    pub upc: Option<String>,
+    /// The ISRCs of the release's tracks, or empty if any of its tracks has none
+    pub isrcs: Vec<String>,
+}
+
+/// Finds the album in the library that is the same release as each of the `releases`. The
+/// MusicBrainz release id is tried first, then the UPC, and finally the album whose tracks have
+/// exactly the release's ISRCs, so a standard and a deluxe edition are not taken for each other.
+///
+/// The lookups for all the `releases` are done in a fixed number of queries.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn find_matching_albums(
+    db: &LibraryDatabase,
+    releases: &[AlbumMatchIds],
+) -> Result<Vec<Option<LibraryAlbum>>, DatabaseFetchError> {
+    let musicbrainz_release_ids = releases
+        .iter()
+        .filter_map(|x| x.musicbrainz_release_id.clone())
+        .collect::<BTreeSet<_>>();
+    let upcs = releases
+        .iter()
+        .filter_map(|x| x.upc.clone())
+        .collect::<BTreeSet<_>>();
+    let isrcs = releases
+        .iter()
+        .flat_map(|x| x.isrcs.iter().cloned())
+        .collect::<BTreeSet<_>>();
+
+    let mut candidate_album_ids = BTreeSet::new();
+
+    if !isrcs.is_empty() {
+        for row in db
+            .select("tracks")
+            .distinct()
+            .columns(&["album_id"])
+            .where_in("isrc", isrcs.into_iter().collect::<Vec<_>>())
+            .execute(db)
+            .await?
+        {
+            candidate_album_ids.insert(row.to_value::<u64>("album_id")?);
+        }
+    }
+
+    // The distinct ISRCs of each candidate album's tracks, or `None` if any track has none
+    let mut album_isrcs: HashMap<u64, Option<BTreeSet<String>>> = HashMap::new();
+
+    if !candidate_album_ids.is_empty() {
+        for row in db
+            .select("tracks")
+            .columns(&["album_id", "isrc"])
+            .where_in(
+                "album_id",
+                candidate_album_ids.iter().copied().collect::<Vec<_>>(),
+            )
+            .execute(db)
+            .await?
+        {
+            let album_id: u64 = row.to_value("album_id")?;
+            let isrc: Option<String> = row.to_value("isrc")?;
+            let entry = album_isrcs
+                .entry(album_id)
+                .or_insert_with(|| Some(BTreeSet::new()));
+            match isrc {
+                Some(isrc) => {
+                    if let Some(isrcs) = entry {
+                        isrcs.insert(isrc);
+                    }
+                }
+                None => *entry = None,
+            }
+        }
+    }
+
+    let mut conditions: Vec<Box<dyn BooleanExpression>> = vec![];
+    if !musicbrainz_release_ids.is_empty() {
+        conditions.push(Box::new(where_in(
+            "albums.musicbrainz_release_id",
+            musicbrainz_release_ids.into_iter().collect::<Vec<_>>(),
+        )));
+    }
+    if !upcs.is_empty() {
+        conditions.push(Box::new(where_in(
+            "albums.upc",
+            upcs.into_iter().collect::<Vec<_>>(),
+        )));
+    }
+    if !candidate_album_ids.is_empty() {
+        conditions.push(Box::new(where_in(
+            "albums.id",
+            candidate_album_ids.into_iter().collect::<Vec<_>>(),
+        )));
+    }
+
+    if conditions.is_empty() {
+        return Ok(releases.iter().map(|_| None).collect());
+    }
+
+    let albums: Vec<LibraryAlbum> = db
+        .select("albums")
+        .columns(&[
+            "albums.*",
+            "artists.title as artist",
+            "artists.tidal_id as tidal_artist_id",
+            "artists.qobuz_id as qobuz_artist_id",
+        ])
+        .join("artists", "artists.id = albums.artist_id")
+        .where_or(conditions)
+        .sort("albums.id", SortDirection::Asc)
+        .execute(db)
+        .await?
+        .to_value_type()?;
+
+    Ok(releases
+        .iter()
+        .map(|release| {
+            let by_musicbrainz_release_id = || {
+                release.musicbrainz_release_id.as_ref().and_then(|id| {
+                    albums
+                        .iter()
+                        .find(|x| x.musicbrainz_release_id.as_ref() == Some(id))
+                })
+            };
+            let by_upc = || {
+                release
+                    .upc
+                    .as_ref()
+                    .and_then(|upc| albums.iter().find(|x| x.upc.as_ref() == Some(upc)))
+            };
+            let by_isrcs = || {
+                if release.isrcs.is_empty() {
+                    return None;
+                }
+                let isrcs = release.isrcs.iter().cloned().collect::<BTreeSet<_>>();
+                albums.iter().find(|x| {
+                    album_isrcs
+                        .get(&x.id)
+                        .is_some_and(|x| x.as_ref() == Some(&isrcs))
+                })
+            };
+
+            by_musicbrainz_release_id()
+                .or_else(by_upc)
+                .or_else(by_isrcs)
+                .cloned()
+        })
+        .collect())
+}
+
+/// Finds the album in the library that is the same release as the `release`. See
+/// [`find_matching_albums`].
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn find_matching_album(
+    db: &LibraryDatabase,
+    release: AlbumMatchIds,
+) -> Result<Option<LibraryAlbum>, DatabaseFetchError> {
+    Ok(find_matching_albums(db, &[release])
+        .await?
+        .into_iter()
+        .next()
+        .flatten())
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_albums_by_titles(
+    db: &LibraryDatabase,
+    titles: &[String],
+) -> Result<Vec<LibraryAlbum>, DatabaseFetchError> {
+    if titles.is_empty() {
+        return Ok(vec![]);
+    }
+
+    Ok(db
+        .select("albums")
+        .where_in("title", titles.to_vec())
+        .execute(db)
+        .await?
+        .to_value_type()?)
+}
+
+/// Changes the artist and title that the album is upserted on.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn set_album_artist_and_title(
+    db: &LibraryDatabase,
+    album_id: u64,
+    artist_id: u64,
+    title: &str,
+) -> Result<(), DatabaseError> {
+    db.update("albums")
+        .where_eq("id", album_id)
+        .value("artist_id", artist_id)
+        .value("title", title)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
 /// # Errors
 ///
 /// * If there was a database error
@@ -324,6 +540,8 @@ pub async fn get_album_tracks(
             "track_sizes",
             "tracks.id=track_sizes.track_id AND track_sizes.format=tracks.format",
         )
+        // Tracks without a disc number are treated as being on the first disc
+        .sort("COALESCE(tracks.disc_number, 1)", SortDirection::Asc)
         .sort("number", SortDirection::Asc)
         .execute(db)
         .await?
@@ -564,6 +782,17 @@ pub async fn delete_tracks(
         return Ok(vec![]);
     }
 
+    db.delete("track_lyrics")
+        .where_eq("api_source", ApiSource::Library.as_ref())
+        .filter_if_some(ids.map(|ids| {
+            where_in(
+                "track_id",
+                ids.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            )
+        }))
+        .execute(db)
+        .await?;
+
     Ok(db
         .delete("tracks")
         .filter_if_some(ids.map(|ids| where_in("id", ids.clone())))
@@ -604,6 +833,178 @@ pub async fn delete_track_sizes_by_track_id(
         .to_value_type()?)
 }
 
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_tracks_by_files(
+    db: &LibraryDatabase,
+    files: &[String],
+) -> Result<Vec<LibraryTrack>, DatabaseFetchError> {
+    let mut tracks = vec![];
+
+    for chunk in files.chunks(1000) {
+        let mut chunk_tracks: Vec<LibraryTrack> = db
+            .select("tracks")
+            .where_in("file", chunk.to_vec())
+            .execute(db)
+            .await?
+            .to_value_type()?;
+        tracks.append(&mut chunk_tracks);
+    }
+
+    Ok(tracks)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn update_track_file(
+    db: &LibraryDatabase,
+    from: &str,
+    to: &str,
+) -> Result<(), DatabaseFetchError> {
+    db.update("tracks")
+        .where_eq("file", from)
+        .value("file", to)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Points the albums in the `from` directory at the `to` directory that their tracks moved to,
+/// along with any artwork stored in it.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn move_album_directory(
+    db: &LibraryDatabase,
+    from: &str,
+    to: &str,
+) -> Result<(), DatabaseFetchError> {
+    let albums: Vec<LibraryAlbum> = db
+        .select("albums")
+        .where_eq("directory", from)
+        .execute(db)
+        .await?
+        .to_value_type()?;
+
+    for album in albums {
+        let artwork = album.artwork.map(|artwork| {
+            std::path::Path::new(&artwork)
+                .strip_prefix(from)
+                .ok()
+                .and_then(|rest| {
+                    std::path::Path::new(to)
+                        .join(rest)
+                        .to_str()
+                        .map(ToString::to_string)
+                })
+                .unwrap_or(artwork)
+        });
+
+        db.update("albums")
+            .where_eq("id", album.id)
+            .value("directory", to)
+            .value("artwork", artwork)
+            .execute(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Deletes the albums in `ids` that no longer have any tracks, returning the
+/// `(album_id, artist_id)` pairs of the albums that were deleted.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn delete_empty_albums(
+    db: &LibraryDatabase,
+    ids: &[u64],
+) -> Result<Vec<(u64, u64)>, DatabaseFetchError> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let non_empty: Vec<u64> = db
+        .select("tracks")
+        .distinct()
+        .columns(&["album_id"])
+        .where_in("album_id", ids.to_vec())
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| row.to_value("album_id"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let empty = ids
+        .iter()
+        .filter(|id| !non_empty.contains(id))
+        .copied()
+        .collect::<Vec<_>>();
+
+    if empty.is_empty() {
+        return Ok(vec![]);
+    }
+
+    Ok(db
+        .delete("albums")
+        .where_in("id", empty)
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| Ok((row.to_value("id")?, row.to_value("artist_id")?)))
+        .collect::<Result<Vec<_>, ParseError>>()?)
+}
+
+/// Deletes the artists in `ids` that no longer have any albums, returning the
+/// ids of the artists that were deleted.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn delete_empty_artists(
+    db: &LibraryDatabase,
+    ids: &[u64],
+) -> Result<Vec<u64>, DatabaseFetchError> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let non_empty: Vec<u64> = db
+        .select("albums")
+        .distinct()
+        .columns(&["artist_id"])
+        .where_in("artist_id", ids.to_vec())
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| row.to_value("artist_id"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let empty = ids
+        .iter()
+        .filter(|id| !non_empty.contains(id))
+        .copied()
+        .collect::<Vec<_>>();
+
+    if empty.is_empty() {
+        return Ok(vec![]);
+    }
+
+    Ok(db
+        .delete("artists")
+        .where_in("id", empty)
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| row.to_value("id"))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
 /// # Errors
 ///
 /// * If there was a database error
@@ -826,6 +1227,62 @@ pub async fn add_tracks(
                 ),
             ];
 
+            let replay_gain = insert.track.replay_gain.unwrap_or_default();
+            values.extend([
+                (
+                    "replay_gain_track_gain",
+                    DatabaseValue::RealOpt(replay_gain.track_gain),
+                ),
+                (
+                    "replay_gain_track_peak",
+                    DatabaseValue::RealOpt(replay_gain.track_peak),
+                ),
+                (
+                    "replay_gain_album_gain",
+                    DatabaseValue::RealOpt(replay_gain.album_gain),
+                ),
+                (
+                    "replay_gain_album_peak",
+                    DatabaseValue::RealOpt(replay_gain.album_peak),
+                ),
+            ]);
+
+            values.extend([
+                (
+                    "disc_number",
+                    DatabaseValue::NumberOpt(insert.track.disc_number.map(i64::from)),
+                ),
+                (
+                    "disc_total",
+                    DatabaseValue::NumberOpt(insert.track.disc_total.map(i64::from)),
+                ),
+                (
+                    "genres",
+                    DatabaseValue::StringOpt(join_genres(&insert.track.genres)),
+                ),
+                (
+                    "composer",
+                    DatabaseValue::StringOpt(insert.track.composer.clone()),
+                ),
+                (
+                    "track_artist",
+                    DatabaseValue::StringOpt(insert.track.track_artist.clone()),
+                ),
+                ("isrc", DatabaseValue::StringOpt(insert.track.isrc.clone())),
+                (
+                    "musicbrainz_recording_id",
+                    DatabaseValue::StringOpt(insert.track.musicbrainz_recording_id.clone()),
+                ),
+                (
+                    "start_offset",
+                    DatabaseValue::RealOpt(insert.track.start_offset),
+                ),
+                (
+                    "end_offset",
+                    DatabaseValue::RealOpt(insert.track.end_offset),
+                ),
+            ]);
+
             if let Some(file) = &insert.file {
                 values.push(("file", DatabaseValue::String(file.clone())));
             }
@@ -868,3 +1325,751 @@ pub async fn add_tracks(
         .await?
         .to_value_type()?)
 }
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_playlists(
+    db: &LibraryDatabase,
+    offset: u32,
+    limit: u32,
+) -> Result<Vec<LibraryPlaylist>, DatabaseFetchError> {
+    Ok(db
+        .select("playlists")
+        .columns(&[
+            "playlists.*",
+            "(SELECT COUNT(*) FROM playlist_tracks WHERE playlist_tracks.playlist_id = playlists.id) AS track_count",
+        ])
+        .sort("id", SortDirection::Asc)
+        .offset(offset as usize)
+        .limit(limit as usize)
+        .execute(db)
+        .await?
+        .to_value_type()?)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_playlist_count(db: &LibraryDatabase) -> Result<u32, DatabaseFetchError> {
+    Ok(db
+        .select("playlists")
+        .columns(&["COUNT(*) AS count"])
+        .execute_first(db)
+        .await?
+        .map(|row| row.to_value("count"))
+        .transpose()?
+        .unwrap_or(0))
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_playlist(
+    db: &LibraryDatabase,
+    id: u64,
+) -> Result<Option<LibraryPlaylist>, DatabaseFetchError> {
+    Ok(db
+        .select("playlists")
+        .columns(&[
+            "playlists.*",
+            "(SELECT COUNT(*) FROM playlist_tracks WHERE playlist_tracks.playlist_id = playlists.id) AS track_count",
+        ])
+        .where_eq("id", id)
+        .execute_first(db)
+        .await?
+        .as_ref()
+        .to_value_type()?)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn create_playlist(
+    db: &LibraryDatabase,
+    name: &str,
+) -> Result<LibraryPlaylist, DatabaseFetchError> {
+    let playlist: LibraryPlaylist = db
+        .insert("playlists")
+        .value("name", name)
+        .execute(db)
+        .await?
+        .to_value_type()?;
+
+    Ok(LibraryPlaylist {
+        track_count: Some(0),
+        ..playlist
+    })
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn rename_playlist(
+    db: &LibraryDatabase,
+    id: u64,
+    name: &str,
+) -> Result<Option<LibraryPlaylist>, DatabaseFetchError> {
+    if db
+        .update("playlists")
+        .where_eq("id", id)
+        .value("name", name)
+        .value("updated", DatabaseValue::Now)
+        .execute_first(db)
+        .await?
+        .is_none()
+    {
+        return Ok(None);
+    }
+
+    get_playlist(db, id).await
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn delete_playlist(
+    db: &LibraryDatabase,
+    id: u64,
+) -> Result<Option<LibraryPlaylist>, DatabaseFetchError> {
+    let Some(playlist) = get_playlist(db, id).await? else {
+        return Ok(None);
+    };
+
+    db.exec_transaction(&[
+        delete("playlist_tracks").where_eq("playlist_id", id).into(),
+        delete("playlists").where_eq("id", id).into(),
+    ])
+    .await?;
+
+    Ok(Some(playlist))
+}
+
+/// # Errors
+///
+/// * If there was a database error
+/// * If a stored track failed to deserialize
+pub async fn get_playlist_tracks(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    offset: u32,
+    limit: u32,
+) -> Result<Vec<ApiTrack>, DatabaseFetchError> {
+    db.select("playlist_tracks")
+        .where_eq("playlist_id", playlist_id)
+        .sort("position", SortDirection::Asc)
+        .offset(offset as usize)
+        .limit(limit as usize)
+        .execute(db)
+        .await?
+        .into_iter()
+        .filter_map(|x| x.get("data"))
+        .filter_map(|x| {
+            x.as_str().map(serde_json::from_str).map(|x| {
+                x.map_err(|e| DatabaseFetchError::Parse(ParseError::Parse(format!("data: {e:?}"))))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_playlist_track_count(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+) -> Result<u32, DatabaseFetchError> {
+    Ok(db
+        .select("playlist_tracks")
+        .columns(&["COUNT(*) AS count"])
+        .where_eq("playlist_id", playlist_id)
+        .execute_first(db)
+        .await?
+        .map(|row| row.to_value("count"))
+        .transpose()?
+        .unwrap_or(0))
+}
+
+async fn get_playlist_track_positions(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+) -> Result<Vec<(u64, u32)>, DatabaseFetchError> {
+    Ok(db
+        .select("playlist_tracks")
+        .columns(&["id", "position"])
+        .where_eq("playlist_id", playlist_id)
+        .sort("position", SortDirection::Asc)
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| Ok((row.to_value("id")?, row.to_value("position")?)))
+        .collect::<Result<Vec<_>, ParseError>>()?)
+}
+
+/// Builds the statements that rewrite the `position` of each `playlist_tracks`
+/// row so that they are sequential, in the order of the given row ids.
+fn playlist_track_position_statements(
+    playlist_id: u64,
+    existing: &[(u64, u32)],
+    ordered_ids: &[u64],
+) -> Vec<TransactionStatement<'static>> {
+    let mut statements = ordered_ids
+        .iter()
+        .enumerate()
+        .filter_map(|(position, id)| {
+            #[allow(clippy::cast_possible_truncation)]
+            let position = position as u32;
+
+            if existing.iter().any(|(existing_id, existing_position)| {
+                existing_id == id && *existing_position == position
+            }) {
+                return None;
+            }
+
+            Some(
+                update("playlist_tracks")
+                    .where_eq("id", *id)
+                    .value("position", position)
+                    .into(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    statements.push(
+        update("playlists")
+            .where_eq("id", playlist_id)
+            .value("updated", DatabaseValue::Now)
+            .into(),
+    );
+
+    statements
+}
+
+/// # Errors
+///
+/// * If there was a database error
+/// * If a track failed to serialize
+pub async fn add_playlist_tracks(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    tracks: &[ApiTrack],
+) -> Result<(), DatabaseFetchError> {
+    let existing = get_playlist_track_positions(db, playlist_id).await?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let start = existing.len() as u32;
+
+    let mut statements = tracks
+        .iter()
+        .enumerate()
+        .map(|(i, track)| {
+            #[allow(clippy::cast_possible_truncation)]
+            let position = start + i as u32;
+
+            Ok(insert("playlist_tracks")
+                .value("playlist_id", playlist_id)
+                .value("position", position)
+                .value("track_id", track.track_id.to_string())
+                .value("api_source", track.api_source.to_string())
+                .value(
+                    "data",
+                    serde_json::to_string(track).map_err(|e| {
+                        DatabaseFetchError::Parse(ParseError::Parse(format!("data: {e:?}")))
+                    })?,
+                )
+                .into())
+        })
+        .collect::<Result<Vec<TransactionStatement>, DatabaseFetchError>>()?;
+
+    statements.push(
+        update("playlists")
+            .where_eq("id", playlist_id)
+            .value("updated", DatabaseValue::Now)
+            .into(),
+    );
+
+    db.exec_transaction(&statements).await?;
+
+    Ok(())
+}
+
+/// Deletes the given `playlist_tracks` rows and renumbers the remaining ones in
+/// a single transaction.
+async fn delete_playlist_track_rows(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    existing: &[(u64, u32)],
+    deleted_ids: &[u64],
+) -> Result<(), DatabaseFetchError> {
+    if deleted_ids.is_empty() {
+        return Ok(());
+    }
+
+    let ids = existing
+        .iter()
+        .map(|(id, _)| *id)
+        .filter(|id| !deleted_ids.contains(id))
+        .collect::<Vec<_>>();
+
+    let mut statements: Vec<TransactionStatement> = vec![delete("playlist_tracks")
+        .where_eq("playlist_id", playlist_id)
+        .where_in("id", deleted_ids.to_vec())
+        .into()];
+
+    statements.extend(playlist_track_position_statements(
+        playlist_id,
+        existing,
+        &ids,
+    ));
+
+    db.exec_transaction(&statements).await?;
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn remove_playlist_tracks(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    positions: &[u32],
+) -> Result<(), DatabaseFetchError> {
+    if positions.is_empty() {
+        return Ok(());
+    }
+
+    let existing = get_playlist_track_positions(db, playlist_id).await?;
+    let deleted_ids = existing
+        .iter()
+        .filter(|(_, position)| positions.contains(position))
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+
+    delete_playlist_track_rows(db, playlist_id, &existing, &deleted_ids).await
+}
+
+/// Removes every occurrence of the given track from the playlist.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn remove_playlist_track_id(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    track_id: &Id,
+    api_source: ApiSource,
+) -> Result<(), DatabaseFetchError> {
+    let existing = get_playlist_track_positions(db, playlist_id).await?;
+    let deleted_ids = db
+        .select("playlist_tracks")
+        .columns(&["id"])
+        .where_eq("playlist_id", playlist_id)
+        .where_eq("track_id", track_id.to_string())
+        .where_eq("api_source", api_source.to_string())
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| row.to_value("id"))
+        .collect::<Result<Vec<u64>, _>>()?;
+
+    delete_playlist_track_rows(db, playlist_id, &existing, &deleted_ids).await
+}
+
+/// # Errors
+///
+/// * If there was a database error
+/// * If either position is out of bounds
+pub async fn move_playlist_track(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    from_position: u32,
+    to_position: u32,
+) -> Result<(), DatabaseFetchError> {
+    let existing = get_playlist_track_positions(db, playlist_id).await?;
+    let mut ids = existing.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+
+    let from = from_position as usize;
+    let to = to_position as usize;
+
+    if from >= ids.len() || to >= ids.len() {
+        return Err(DatabaseFetchError::InvalidRequest);
+    }
+
+    let id = ids.remove(from);
+    ids.insert(to, id);
+
+    db.exec_transaction(&playlist_track_position_statements(
+        playlist_id,
+        &existing,
+        &ids,
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Records a play of the track in the play history and increments the library
+/// `play_count` of the track.
+///
+/// # Errors
+///
+/// * If there was a database error
+/// * If the track failed to serialize
+pub async fn add_play(
+    db: &LibraryDatabase,
+    profile: &str,
+    session_id: u64,
+    audio_zone_id: Option<u64>,
+    track: &ApiTrack,
+    played_at: u64,
+) -> Result<(), DatabaseFetchError> {
+    db.insert("play_history")
+        .value("track_id", track.track_id.to_string())
+        .value("api_source", track.api_source.to_string())
+        .value("album_id", track.album_id.to_string())
+        .value("artist_id", track.artist_id.to_string())
+        .value("profile", profile)
+        .value("session_id", session_id)
+        .value("audio_zone_id", audio_zone_id)
+        .value("played_at", played_at)
+        .value(
+            "data",
+            serde_json::to_string(track).map_err(|e| {
+                DatabaseFetchError::Parse(ParseError::Parse(format!("data: {e:?}")))
+            })?,
+        )
+        .execute(db)
+        .await?;
+
+    if track.api_source == ApiSource::Library {
+        db.update("tracks")
+            .where_eq("id", &track.track_id)
+            .value("play_count", literal("play_count + 1"))
+            .execute(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn play_from_row(row: &moosicbox_database::Row) -> Result<LibraryPlay, ParseError> {
+    let track: ApiTrack = serde_json::from_str(&row.to_value::<String>("data")?)
+        .map_err(|e| ParseError::Parse(format!("data: {e:?}")))?;
+
+    Ok(LibraryPlay {
+        id: row.to_value("id")?,
+        profile: row.to_value("profile")?,
+        session_id: row.to_value("session_id")?,
+        audio_zone_id: row.to_value("audio_zone_id")?,
+        api_source: track.api_source,
+        played_at: row.to_value("played_at")?,
+        track: track.into(),
+    })
+}
+
+/// Fetches a page of the plays in the play history, most recent first, that
+/// were played at or after `since` and before `until` (in seconds since the
+/// unix epoch).
+///
+/// # Errors
+///
+/// * If there was a database error
+/// * If a stored track failed to deserialize
+pub async fn get_plays(
+    db: &LibraryDatabase,
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: u32,
+    limit: u32,
+) -> Result<Vec<LibraryPlay>, DatabaseFetchError> {
+    db.select("play_history")
+        .filter_if_some(since.map(|x| where_gte("played_at", x)))
+        .filter_if_some(until.map(|x| where_lt("played_at", x)))
+        .sort("played_at", SortDirection::Desc)
+        .sort("id", SortDirection::Desc)
+        .offset(offset as usize)
+        .limit(limit as usize)
+        .execute(db)
+        .await?
+        .iter()
+        .map(play_from_row)
+        .collect::<Result<Vec<_>, ParseError>>()
+        .map_err(DatabaseFetchError::Parse)
+}
+
+/// The number of plays in the play history that were played at or after
+/// `since` and before `until` (in seconds since the unix epoch).
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_play_count(
+    db: &LibraryDatabase,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> Result<u32, DatabaseFetchError> {
+    Ok(db
+        .select("play_history")
+        .columns(&["COUNT(*) AS count"])
+        .filter_if_some(since.map(|x| where_gte("played_at", x)))
+        .filter_if_some(until.map(|x| where_lt("played_at", x)))
+        .execute_first(db)
+        .await?
+        .map(|row| row.to_value("count"))
+        .transpose()?
+        .unwrap_or(0))
+}
+
+/// What the plays in the play history are counted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayGroup {
+    Track,
+    Album,
+    Artist,
+}
+
+impl PlayGroup {
+    const fn group_by(self) -> &'static [&'static str] {
+        match self {
+            Self::Track => &["api_source", "track_id"],
+            Self::Album => &["api_source", "album_id"],
+            Self::Artist => &["api_source", "artist_id"],
+        }
+    }
+
+    const fn columns(self) -> &'static [&'static str] {
+        match self {
+            Self::Track => &[
+                "api_source",
+                "track_id",
+                "COUNT(*) AS play_count",
+                "MAX(played_at) AS last_played_at",
+                "MAX(id) AS last_play_id",
+            ],
+            Self::Album => &[
+                "api_source",
+                "album_id",
+                "COUNT(*) AS play_count",
+                "MAX(played_at) AS last_played_at",
+                "MAX(id) AS last_play_id",
+            ],
+            Self::Artist => &[
+                "api_source",
+                "artist_id",
+                "COUNT(*) AS play_count",
+                "MAX(played_at) AS last_played_at",
+                "MAX(id) AS last_play_id",
+            ],
+        }
+    }
+}
+
+/// The number of times a [`PlayGroup`] was played, along with its most recent
+/// play.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayCount {
+    pub last_play: LibraryPlay,
+    pub play_count: u32,
+    pub last_played_at: u64,
+}
+
+/// Fetches a page of the play counts of the plays in the play history that
+/// were played at or after `since` and before `until` (in seconds since the
+/// unix epoch), grouped by the `group`.
+///
+/// The groups are ordered by their play count, with ties ordered by the group
+/// that was played most recently.
+///
+/// # Errors
+///
+/// * If there was a database error
+/// * If a stored track failed to deserialize
+pub async fn get_play_counts(
+    db: &LibraryDatabase,
+    group: PlayGroup,
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: u32,
+    limit: u32,
+) -> Result<Vec<PlayCount>, DatabaseFetchError> {
+    let counts = db
+        .select("play_history")
+        .columns(group.columns())
+        .filter_if_some(since.map(|x| where_gte("played_at", x)))
+        .filter_if_some(until.map(|x| where_lt("played_at", x)))
+        .group_by(group.group_by())
+        // Sorted by the aggregates rather than their aliases, since not every database
+        // resolves an alias inside of a sort expression
+        .sort("COUNT(*)", SortDirection::Desc)
+        .sort("MAX(played_at)", SortDirection::Desc)
+        .sort("MAX(id)", SortDirection::Desc)
+        .offset(offset as usize)
+        .limit(limit as usize)
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok((
+                row.to_value::<u64>("last_play_id")?,
+                row.to_value::<u32>("play_count")?,
+                row.to_value::<u64>("last_played_at")?,
+            ))
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    if counts.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut last_plays = db
+        .select("play_history")
+        .where_in(
+            "id",
+            counts.iter().map(|(id, _, _)| *id).collect::<Vec<_>>(),
+        )
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| play_from_row(row).map(|play| (play.id, play)))
+        .collect::<Result<HashMap<_, _>, ParseError>>()?;
+
+    Ok(counts
+        .into_iter()
+        .filter_map(|(id, play_count, last_played_at)| {
+            last_plays.remove(&id).map(|last_play| PlayCount {
+                last_play,
+                play_count,
+                last_played_at,
+            })
+        })
+        .collect())
+}
+
+/// The number of distinct groups in the plays in the play history that were
+/// played at or after `since` and before `until` (in seconds since the unix
+/// epoch).
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_play_group_count(
+    db: &LibraryDatabase,
+    group: PlayGroup,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> Result<u32, DatabaseFetchError> {
+    let groups = db
+        .select("play_history")
+        .columns(group.group_by())
+        .filter_if_some(since.map(|x| where_gte("played_at", x)))
+        .filter_if_some(until.map(|x| where_lt("played_at", x)))
+        .group_by(group.group_by())
+        .execute(db)
+        .await?
+        .len();
+
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(groups as u32)
+}
+
+/// Fetches the stored lyrics, plain text or LRC, of a track from `api_source`.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn get_track_lyrics(
+    db: &LibraryDatabase,
+    track_id: &Id,
+    api_source: ApiSource,
+) -> Result<Option<String>, DatabaseFetchError> {
+    Ok(db
+        .select("track_lyrics")
+        .columns(&["lyrics"])
+        .where_eq("track_id", track_id.to_string())
+        .where_eq("api_source", api_source.as_ref())
+        .execute_first(db)
+        .await?
+        .map(|row| row.to_value::<String>("lyrics"))
+        .transpose()?)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn set_track_lyrics(
+    db: &LibraryDatabase,
+    track_id: &Id,
+    api_source: ApiSource,
+    lyrics: &str,
+) -> Result<(), DatabaseFetchError> {
+    set_tracks_lyrics(
+        db,
+        &[SetTrackLyrics {
+            track_id: track_id.clone(),
+            api_source,
+            lyrics: Some(lyrics.to_string()),
+        }],
+    )
+    .await
+}
+
+pub struct SetTrackLyrics {
+    pub track_id: Id,
+    pub api_source: ApiSource,
+    /// The lyrics, plain text or LRC, or `None` to delete the stored lyrics
+    pub lyrics: Option<String>,
+}
+
+/// Stores the lyrics of many tracks at once, deleting the stored lyrics of
+/// the tracks that no longer have any.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn set_tracks_lyrics(
+    db: &LibraryDatabase,
+    values: &[SetTrackLyrics],
+) -> Result<(), DatabaseFetchError> {
+    let mut deleted: BTreeMap<ApiSource, Vec<String>> = BTreeMap::new();
+    let mut upserted = vec![];
+
+    for value in values {
+        if let Some(lyrics) = &value.lyrics {
+            upserted.push(vec![
+                (
+                    "track_id",
+                    DatabaseValue::String(value.track_id.to_string()),
+                ),
+                (
+                    "api_source",
+                    DatabaseValue::String(value.api_source.as_ref().to_string()),
+                ),
+                ("lyrics", DatabaseValue::String(lyrics.clone())),
+            ]);
+        } else {
+            deleted
+                .entry(value.api_source)
+                .or_default()
+                .push(value.track_id.to_string());
+        }
+    }
+
+    for (api_source, track_ids) in deleted {
+        db.delete("track_lyrics")
+            .where_eq("api_source", api_source.as_ref())
+            .where_in("track_id", track_ids)
+            .execute(db)
+            .await?;
+    }
+
+    if !upserted.is_empty() {
+        db.upsert_multi("track_lyrics")
+            .unique(boxed![identifier("track_id"), identifier("api_source")])
+            .values(upserted)
+            .execute(db)
+            .await?;
+    }
+
+    Ok(())
+}
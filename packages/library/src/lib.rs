@@ -4,11 +4,16 @@
 use std::{
     cmp::Ordering,
     fs::File,
+    future::Future,
+    pin::Pin,
     sync::{Arc, LazyLock},
 };
 
 use db::{get_artist_by_album_id, SetTrackSize};
-use models::{LibraryAlbum, LibraryArtist, LibraryTrack};
+use models::{
+    LibraryAlbum, LibraryAlbumPlayCount, LibraryArtist, LibraryArtistPlayCount, LibraryPlay,
+    LibraryPlaylist, LibraryTrack, LibraryTrackPlayCount,
+};
 
 use async_recursion::async_recursion;
 use async_trait::async_trait;
@@ -23,17 +28,20 @@ use moosicbox_music_api::{
         ImageCoverSize, ImageCoverSource, TrackAudioQuality, TrackOrder, TrackOrderDirection,
         TrackSource,
     },
-    AddAlbumError, AddArtistError, AddTrackError, AlbumError, AlbumsError, ArtistAlbumsError,
-    ArtistError, ArtistsError, MusicApi, RemoveAlbumError, RemoveArtistError, RemoveTrackError,
-    TrackError, TrackOrId, TracksError,
+    AddAlbumError, AddArtistError, AddPlaylistTrackError, AddTrackError, AlbumError, AlbumsError,
+    ArtistAlbumsError, ArtistError, ArtistsError, MusicApi, PlaylistError, PlaylistsError,
+    RemoveAlbumError, RemoveArtistError, RemovePlaylistTrackError, RemoveTrackError, SearchError,
+    TrackError, TrackLyricsError, TrackOrId, TracksError,
 };
 use moosicbox_music_models::{
-    id::Id, Album, AlbumSort, AlbumType, ApiSource, Artist, AudioFormat, PlaybackQuality, Track,
+    id::Id, lyrics::Lyrics, Album, AlbumSort, AlbumType, ApiSource, Artist, AudioFormat,
+    PlaybackQuality, Playlist, Track,
 };
 use moosicbox_paging::{Page, PagingRequest, PagingResponse, PagingResult};
 use moosicbox_search::{
-    api::models::ApiGlobalSearchResult, data::AsDataValues as _, populate_global_search_index,
-    PopulateIndexError, RecreateIndexError,
+    api::models::{ApiGlobalSearchResult, ApiSearchResultsResponse},
+    data::AsDataValues as _,
+    populate_global_search_index, PopulateIndexError, RecreateIndexError, SearchIndexError,
 };
 use regex::{Captures, Regex};
 use serde::{Deserialize, Serialize};
@@ -233,6 +241,13 @@ pub fn filter_albums<'a>(
                     .is_none_or(|t| album.album_type == t)
             })
         })
+        .filter(|album| {
+            request.filters.as_ref().is_none_or(|x| {
+                x.genre
+                    .as_ref()
+                    .is_none_or(|s| album.genres.iter().any(|genre| genre.to_lowercase() == *s))
+            })
+        })
         .filter(|album| {
             request.filters.as_ref().is_none_or(|x| {
                 x.name
@@ -306,12 +321,28 @@ pub fn sort_albums<'a>(
         }),
         Some(AlbumSort::DateAddedAsc) => albums.sort_by(|a, b| a.date_added.cmp(&b.date_added)),
         Some(AlbumSort::DateAddedDesc) => albums.sort_by(|b, a| a.date_added.cmp(&b.date_added)),
+        Some(AlbumSort::GenreAsc) => {
+            albums.sort_by(|a, b| match (first_genre(a), first_genre(b)) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (a, b) => a.is_none().cmp(&b.is_none()),
+            })
+        }
+        Some(AlbumSort::GenreDesc) => {
+            albums.sort_by(|a, b| match (first_genre(a), first_genre(b)) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (a, b) => a.is_none().cmp(&b.is_none()),
+            })
+        }
         None => (),
     }
 
     albums
 }
 
+fn first_genre(album: &LibraryAlbum) -> Option<String> {
+    album.genres.first().map(|x| x.to_lowercase())
+}
+
 #[derive(Debug, Error)]
 pub enum LibraryFavoriteAlbumsError {
     #[error("No user ID available")]
@@ -783,6 +814,561 @@ pub async fn track(
     Ok(db::get_track(db, track_id).await?)
 }
 
+#[derive(Debug, Error)]
+pub enum LibraryTrackLyricsError {
+    #[error("Track not found")]
+    NotFound,
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+    #[error(transparent)]
+    TrackLyrics(#[from] TrackLyricsError),
+}
+
+/// Fetches the lyrics of a track from `api`. The lyrics of library tracks are
+/// read during scanning, while the lyrics of tracks from other sources are
+/// fetched from the source the first time they are requested and stored. A
+/// source having no lyrics for a track is stored as empty lyrics so that it
+/// isn't asked again.
+///
+/// # Errors
+///
+/// * If the track was not found
+/// * If there was a database error
+/// * If the lyrics failed to fetch from the source
+pub async fn track_lyrics(
+    db: &LibraryDatabase,
+    api: &dyn MusicApi,
+    track_id: &Id,
+) -> Result<Option<Lyrics>, LibraryTrackLyricsError> {
+    let source = api.source();
+
+    if let Some(lyrics) = db::get_track_lyrics(db, track_id, source).await? {
+        return Ok(Some(lyrics)
+            .filter(|x| !x.is_empty())
+            .map(|x| Lyrics::parse(&x)));
+    }
+
+    if source == ApiSource::Library {
+        if db::get_track(db, track_id).await?.is_none() {
+            return Err(LibraryTrackLyricsError::NotFound);
+        }
+        return Ok(None);
+    }
+
+    let lyrics = match api.track_lyrics(track_id).await {
+        Ok(lyrics) => lyrics,
+        Err(TrackLyricsError::NotFound) => return Err(LibraryTrackLyricsError::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+
+    db::set_track_lyrics(
+        db,
+        track_id,
+        source,
+        &lyrics.as_ref().map(ToString::to_string).unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(lyrics)
+}
+
+#[derive(Debug, Error)]
+pub enum LibraryPlaylistsError {
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+}
+
+#[async_recursion]
+pub async fn playlists(
+    db: &LibraryDatabase,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> PagingResult<LibraryPlaylist, LibraryPlaylistsError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let items = db::get_playlists(db, offset, limit).await?;
+    let total = db::get_playlist_count(db).await?;
+
+    let db = db.to_owned();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            let db = db.clone();
+
+            Box::pin(async move { playlists(&db, Some(offset), Some(limit)).await })
+        }))),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum LibraryPlaylistError {
+    #[error("Not found")]
+    NotFound,
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn playlist(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+) -> Result<Option<LibraryPlaylist>, LibraryPlaylistError> {
+    Ok(db::get_playlist(db, playlist_id).await?)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn create_playlist(
+    db: &LibraryDatabase,
+    name: &str,
+) -> Result<LibraryPlaylist, LibraryPlaylistError> {
+    Ok(db::create_playlist(db, name).await?)
+}
+
+/// # Errors
+///
+/// * If the playlist was not found
+/// * If there was a database error
+pub async fn rename_playlist(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    name: &str,
+) -> Result<LibraryPlaylist, LibraryPlaylistError> {
+    db::rename_playlist(db, playlist_id, name)
+        .await?
+        .ok_or(LibraryPlaylistError::NotFound)
+}
+
+/// # Errors
+///
+/// * If the playlist was not found
+/// * If there was a database error
+pub async fn delete_playlist(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+) -> Result<LibraryPlaylist, LibraryPlaylistError> {
+    db::delete_playlist(db, playlist_id)
+        .await?
+        .ok_or(LibraryPlaylistError::NotFound)
+}
+
+#[derive(Debug, Error)]
+pub enum LibraryPlaylistTracksError {
+    #[error("Not found")]
+    NotFound,
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+}
+
+#[async_recursion]
+pub async fn playlist_tracks(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> PagingResult<Track, LibraryPlaylistTracksError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    if db::get_playlist(db, playlist_id).await?.is_none() {
+        return Err(LibraryPlaylistTracksError::NotFound);
+    }
+
+    let tracks = db::get_playlist_tracks(db, playlist_id, offset, limit).await?;
+    log::trace!("Received playlist tracks response: {tracks:?}");
+
+    let total = db::get_playlist_track_count(db, playlist_id).await?;
+    let items = tracks.into_iter().map(Into::into).collect::<Vec<_>>();
+
+    let db = db.to_owned();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            let db = db.clone();
+
+            Box::pin(
+                async move { playlist_tracks(&db, playlist_id, Some(offset), Some(limit)).await },
+            )
+        }))),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum LibraryUpdatePlaylistTracksError {
+    #[error("Not found")]
+    NotFound,
+    #[error("Track not found: {0}")]
+    TrackNotFound(Id),
+    #[error("Position out of bounds: {0}")]
+    PositionOutOfBounds(u32),
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+}
+
+/// Appends the given tracks to the end of the playlist. The tracks may come
+/// from any `ApiSource`.
+///
+/// # Errors
+///
+/// * If the playlist was not found
+/// * If there was a database error
+pub async fn add_playlist_tracks(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    tracks: Vec<Track>,
+) -> Result<(), LibraryUpdatePlaylistTracksError> {
+    if db::get_playlist(db, playlist_id).await?.is_none() {
+        return Err(LibraryUpdatePlaylistTracksError::NotFound);
+    }
+
+    let tracks = tracks.into_iter().map(Into::into).collect::<Vec<_>>();
+
+    Ok(db::add_playlist_tracks(db, playlist_id, &tracks).await?)
+}
+
+/// Removes the tracks at the given positions from the playlist, shifting the
+/// remaining tracks up to fill the gaps.
+///
+/// # Errors
+///
+/// * If the playlist was not found
+/// * If there was a database error
+pub async fn remove_playlist_tracks(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    positions: &[u32],
+) -> Result<(), LibraryUpdatePlaylistTracksError> {
+    if db::get_playlist(db, playlist_id).await?.is_none() {
+        return Err(LibraryUpdatePlaylistTracksError::NotFound);
+    }
+
+    Ok(db::remove_playlist_tracks(db, playlist_id, positions).await?)
+}
+
+/// Removes every occurrence of the given track from the playlist.
+///
+/// # Errors
+///
+/// * If the playlist was not found
+/// * If there was a database error
+pub async fn remove_playlist_track_id(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    track_id: &Id,
+    source: ApiSource,
+) -> Result<(), LibraryUpdatePlaylistTracksError> {
+    if db::get_playlist(db, playlist_id).await?.is_none() {
+        return Err(LibraryUpdatePlaylistTracksError::NotFound);
+    }
+
+    Ok(db::remove_playlist_track_id(db, playlist_id, track_id, source).await?)
+}
+
+/// # Errors
+///
+/// * If the playlist was not found
+/// * If either position is out of bounds
+/// * If there was a database error
+pub async fn move_playlist_track(
+    db: &LibraryDatabase,
+    playlist_id: u64,
+    from_position: u32,
+    to_position: u32,
+) -> Result<(), LibraryUpdatePlaylistTracksError> {
+    if db::get_playlist(db, playlist_id).await?.is_none() {
+        return Err(LibraryUpdatePlaylistTracksError::NotFound);
+    }
+
+    match db::move_playlist_track(db, playlist_id, from_position, to_position).await {
+        Ok(()) => Ok(()),
+        Err(DatabaseFetchError::InvalidRequest) => {
+            Err(LibraryUpdatePlaylistTracksError::PositionOutOfBounds(
+                std::cmp::max(from_position, to_position),
+            ))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LibraryPlayHistoryError {
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+}
+
+/// Records a play of the track in the play history.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn add_play(
+    db: &LibraryDatabase,
+    profile: &str,
+    session_id: u64,
+    audio_zone_id: Option<u64>,
+    track: Track,
+    played_at: u64,
+) -> Result<(), LibraryPlayHistoryError> {
+    Ok(db::add_play(
+        db,
+        profile,
+        session_id,
+        audio_zone_id,
+        &track.into(),
+        played_at,
+    )
+    .await?)
+}
+
+type FuturePlayHistoryPage<T> =
+    Pin<Box<dyn Future<Output = PagingResult<T, LibraryPlayHistoryError>> + Send>>;
+
+fn play_history_page<T: Send + 'static>(
+    items: Vec<T>,
+    offset: u32,
+    limit: u32,
+    total: u32,
+    fetch: impl FnMut(u32, u32) -> FuturePlayHistoryPage<T> + Send + 'static,
+) -> PagingResponse<T, LibraryPlayHistoryError> {
+    PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(fetch))),
+    }
+}
+
+/// The plays played at or after `since` and before `until` (in seconds since
+/// the unix epoch), most recent first.
+#[async_recursion]
+pub async fn recently_played(
+    db: &LibraryDatabase,
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> PagingResult<LibraryPlay, LibraryPlayHistoryError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let plays = db::get_plays(db, since, until, offset, limit).await?;
+    let total = db::get_play_count(db, since, until).await?;
+    let db = db.to_owned();
+
+    Ok(play_history_page(
+        plays,
+        offset,
+        limit,
+        total,
+        move |offset, limit| {
+            let db = db.clone();
+
+            Box::pin(
+                async move { recently_played(&db, since, until, Some(offset), Some(limit)).await },
+            )
+        },
+    ))
+}
+
+#[must_use]
+pub fn track_play_count(count: db::PlayCount) -> LibraryTrackPlayCount {
+    LibraryTrackPlayCount {
+        track: count.last_play.track,
+        play_count: count.play_count,
+        last_played_at: count.last_played_at,
+    }
+}
+
+#[must_use]
+pub fn album_play_count(count: db::PlayCount) -> LibraryAlbumPlayCount {
+    let play = count.last_play;
+
+    LibraryAlbumPlayCount {
+        album_id: play.track.album_id,
+        title: play.track.album,
+        artist: play.track.artist,
+        artist_id: play.track.artist_id,
+        artwork: play.track.artwork,
+        api_source: play.api_source,
+        play_count: count.play_count,
+        last_played_at: count.last_played_at,
+    }
+}
+
+#[must_use]
+pub fn artist_play_count(count: db::PlayCount) -> LibraryArtistPlayCount {
+    let play = count.last_play;
+
+    LibraryArtistPlayCount {
+        artist_id: play.track.artist_id,
+        title: play.track.artist,
+        api_source: play.api_source,
+        play_count: count.play_count,
+        last_played_at: count.last_played_at,
+    }
+}
+
+/// A page of the play counts of the plays played at or after `since` and
+/// before `until` (in seconds since the unix epoch), grouped by the `group`
+/// and most played first.
+async fn play_counts<T: Send + 'static>(
+    db: &LibraryDatabase,
+    group: db::PlayGroup,
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: u32,
+    limit: u32,
+    to_item: fn(db::PlayCount) -> T,
+) -> Result<(Vec<T>, u32), LibraryPlayHistoryError> {
+    let items = db::get_play_counts(db, group, since, until, offset, limit)
+        .await?
+        .into_iter()
+        .map(to_item)
+        .collect();
+    let total = db::get_play_group_count(db, group, since, until).await?;
+
+    Ok((items, total))
+}
+
+/// The most played tracks of the plays played at or after `since` and before
+/// `until` (in seconds since the unix epoch).
+#[async_recursion]
+pub async fn most_played_tracks(
+    db: &LibraryDatabase,
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> PagingResult<LibraryTrackPlayCount, LibraryPlayHistoryError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let (tracks, total) = play_counts(
+        db,
+        db::PlayGroup::Track,
+        since,
+        until,
+        offset,
+        limit,
+        track_play_count,
+    )
+    .await?;
+    let db = db.to_owned();
+
+    Ok(play_history_page(
+        tracks,
+        offset,
+        limit,
+        total,
+        move |offset, limit| {
+            let db = db.clone();
+
+            Box::pin(async move {
+                most_played_tracks(&db, since, until, Some(offset), Some(limit)).await
+            })
+        },
+    ))
+}
+
+/// The most played albums of the plays played at or after `since` and before
+/// `until` (in seconds since the unix epoch).
+#[async_recursion]
+pub async fn most_played_albums(
+    db: &LibraryDatabase,
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> PagingResult<LibraryAlbumPlayCount, LibraryPlayHistoryError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let (albums, total) = play_counts(
+        db,
+        db::PlayGroup::Album,
+        since,
+        until,
+        offset,
+        limit,
+        album_play_count,
+    )
+    .await?;
+    let db = db.to_owned();
+
+    Ok(play_history_page(
+        albums,
+        offset,
+        limit,
+        total,
+        move |offset, limit| {
+            let db = db.clone();
+
+            Box::pin(async move {
+                most_played_albums(&db, since, until, Some(offset), Some(limit)).await
+            })
+        },
+    ))
+}
+
+/// The most played artists of the plays played at or after `since` and before
+/// `until` (in seconds since the unix epoch).
+#[async_recursion]
+pub async fn most_played_artists(
+    db: &LibraryDatabase,
+    since: Option<u64>,
+    until: Option<u64>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> PagingResult<LibraryArtistPlayCount, LibraryPlayHistoryError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let (artists, total) = play_counts(
+        db,
+        db::PlayGroup::Artist,
+        since,
+        until,
+        offset,
+        limit,
+        artist_play_count,
+    )
+    .await?;
+    let db = db.to_owned();
+
+    Ok(play_history_page(
+        artists,
+        offset,
+        limit,
+        total,
+        move |offset, limit| {
+            let db = db.clone();
+
+            Box::pin(async move {
+                most_played_artists(&db, since, until, Some(offset), Some(limit)).await
+            })
+        },
+    ))
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, EnumString, AsRefStr)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
@@ -824,24 +1410,49 @@ pub enum LibrarySearchType {
 #[derive(Debug, Error)]
 pub enum LibrarySearchError {
     #[error(transparent)]
-    DatabaseFetch(#[from] DatabaseFetchError),
+    SearchIndex(#[from] SearchIndexError),
+}
+
+impl LibrarySearchType {
+    #[must_use]
+    pub const fn matches(self, result: &ApiGlobalSearchResult) -> bool {
+        matches!(
+            (self, result),
+            (Self::Artists, ApiGlobalSearchResult::Artist(_))
+                | (Self::Albums, ApiGlobalSearchResult::Album(_))
+                | (Self::Tracks, ApiGlobalSearchResult::Track(_))
+        )
+    }
 }
 
+/// Searches the profile's global search index, keeping only the results of
+/// the given `types`, if any.
+///
+/// # Panics
+///
+/// * If any `RwLock`s are poisoned
+///
 /// # Errors
 ///
-/// * If there was a database error
-#[allow(clippy::too_many_arguments)]
+/// * If failed to search the global search index
 pub fn search(
-    _db: &LibraryDatabase,
-    _query: &str,
-    _offset: Option<usize>,
-    _limit: Option<usize>,
-    _types: &Option<Vec<LibrarySearchType>>,
-) -> Result<Vec<ApiGlobalSearchResult>, LibrarySearchError> {
-    let items = vec![];
-    log::trace!("Received search response: {items:?}");
+    profile: &str,
+    query: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    types: &Option<Vec<LibrarySearchType>>,
+) -> Result<ApiSearchResultsResponse, LibrarySearchError> {
+    let mut response = moosicbox_search::api::global_search(profile, query, offset, limit)?;
+
+    if let Some(types) = types {
+        response
+            .results
+            .retain(|result| types.iter().any(|x| x.matches(result)));
+    }
+
+    log::trace!("Received search response: {:?}", response.results);
 
-    Ok(items)
+    Ok(response)
 }
 
 #[derive(Debug, Serialize, Deserialize, EnumString, AsRefStr, PartialEq, Eq, Clone, Copy)]
@@ -1015,6 +1626,16 @@ impl From<LibraryTrackError> for TrackError {
     }
 }
 
+impl From<LibraryTrackLyricsError> for TrackLyricsError {
+    fn from(err: LibraryTrackLyricsError) -> Self {
+        match err {
+            LibraryTrackLyricsError::NotFound => Self::NotFound,
+            LibraryTrackLyricsError::TrackLyrics(err) => err,
+            LibraryTrackLyricsError::DatabaseFetch(_) => Self::Other(Box::new(err)),
+        }
+    }
+}
+
 impl From<LibraryAddFavoriteTrackError> for AddTrackError {
     fn from(err: LibraryAddFavoriteTrackError) -> Self {
         Self::Other(Box::new(err))
@@ -1027,6 +1648,42 @@ impl From<LibraryRemoveFavoriteTrackError> for RemoveTrackError {
     }
 }
 
+impl From<LibraryPlaylistsError> for PlaylistsError {
+    fn from(err: LibraryPlaylistsError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<LibraryPlaylistError> for PlaylistError {
+    fn from(err: LibraryPlaylistError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<LibraryPlaylistTracksError> for TracksError {
+    fn from(err: LibraryPlaylistTracksError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<LibraryUpdatePlaylistTracksError> for AddPlaylistTrackError {
+    fn from(err: LibraryUpdatePlaylistTracksError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<LibraryUpdatePlaylistTracksError> for RemovePlaylistTrackError {
+    fn from(err: LibraryUpdatePlaylistTracksError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<LibrarySearchError> for SearchError {
+    fn from(err: LibrarySearchError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum TrackSizeError {
     #[error("Unsupported audio format: {0:?}")]
@@ -1037,6 +1694,7 @@ pub enum TrackSizeError {
 
 #[derive(Clone)]
 pub struct LibraryMusicApi {
+    profile: String,
     db: LibraryDatabase,
 }
 
@@ -1052,16 +1710,13 @@ impl From<LibraryMusicApi> for LibraryDatabase {
     }
 }
 
-impl From<LibraryDatabase> for LibraryMusicApi {
-    fn from(value: LibraryDatabase) -> Self {
-        Self { db: value }
-    }
-}
-
 impl LibraryMusicApi {
     #[must_use]
-    pub const fn new(db: LibraryDatabase) -> Self {
-        Self { db }
+    pub fn new(profile: &str, db: LibraryDatabase) -> Self {
+        Self {
+            profile: profile.to_string(),
+            db,
+        }
     }
 
     /// # Errors
@@ -1413,6 +2068,8 @@ impl MusicApi for LibraryMusicApi {
             format: track.format.unwrap_or(AudioFormat::Source),
             track_id: Some(track.id.clone()),
             source: track.track_source,
+            start_offset: track.start_offset,
+            end_offset: track.end_offset,
         }))
     }
 
@@ -1444,6 +2101,14 @@ impl MusicApi for LibraryMusicApi {
                         .map_err(|e| TrackError::Other(Box::new(e)))?;
                     writer.bytes_written()
                 }
+                #[cfg(feature = "alac")]
+                AudioFormat::Alac => {
+                    let writer = moosicbox_stream_utils::ByteWriter::default();
+                    moosicbox_audio_output::encoder::alac::encode_alac_spawn(path, writer.clone())
+                        .await
+                        .map_err(|e| TrackError::Other(Box::new(e)))?;
+                    writer.bytes_written()
+                }
                 #[cfg(feature = "flac")]
                 AudioFormat::Flac => {
                     return Err(TrackError::Other(Box::new(
@@ -1466,6 +2131,25 @@ impl MusicApi for LibraryMusicApi {
                         .map_err(|e| TrackError::Other(Box::new(e)))?;
                     writer.bytes_written()
                 }
+                #[cfg(feature = "vorbis")]
+                AudioFormat::Vorbis => {
+                    let writer = moosicbox_stream_utils::ByteWriter::default();
+                    moosicbox_audio_output::encoder::vorbis::encode_vorbis_spawn(
+                        path,
+                        writer.clone(),
+                    )
+                    .await
+                    .map_err(|e| TrackError::Other(Box::new(e)))?;
+                    writer.bytes_written()
+                }
+                #[cfg(feature = "wav")]
+                AudioFormat::Wav => {
+                    let writer = moosicbox_stream_utils::ByteWriter::default();
+                    moosicbox_audio_output::encoder::wav::encode_wav_spawn(path, writer.clone())
+                        .await
+                        .map_err(|e| TrackError::Other(Box::new(e)))?;
+                    writer.bytes_written()
+                }
                 AudioFormat::Source => File::open(path).unwrap().metadata().unwrap().len(),
                 #[allow(unreachable_patterns)]
                 _ => {
@@ -1505,6 +2189,84 @@ impl MusicApi for LibraryMusicApi {
 
         Ok(Some(bytes))
     }
+
+    async fn track_lyrics(&self, track_id: &Id) -> Result<Option<Lyrics>, TrackLyricsError> {
+        Ok(track_lyrics(&self.db, self, track_id).await?)
+    }
+
+    async fn playlists(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Playlist, PlaylistsError> {
+        Ok(playlists(&self.db, offset, limit).await?.inner_into())
+    }
+
+    async fn playlist(&self, playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError> {
+        let playlist_id: u64 = playlist_id
+            .try_into()
+            .map_err(|e| PlaylistError::Other(Box::new(e)))?;
+
+        Ok(playlist(&self.db, playlist_id).await?.map(Into::into))
+    }
+
+    async fn playlist_tracks(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Track, TracksError> {
+        let playlist_id: u64 = playlist_id
+            .try_into()
+            .map_err(|e| TracksError::Other(Box::new(e)))?;
+
+        Ok(playlist_tracks(&self.db, playlist_id, offset, limit).await?)
+    }
+
+    async fn add_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), AddPlaylistTrackError> {
+        let playlist_id: u64 = playlist_id
+            .try_into()
+            .map_err(|e| AddPlaylistTrackError::Other(Box::new(e)))?;
+
+        let track = self
+            .track(track_id)
+            .await
+            .map_err(|e| AddPlaylistTrackError::Other(Box::new(e)))?
+            .ok_or_else(|| LibraryUpdatePlaylistTracksError::TrackNotFound(track_id.to_owned()))?;
+
+        Ok(add_playlist_tracks(&self.db, playlist_id, vec![track]).await?)
+    }
+
+    async fn remove_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), RemovePlaylistTrackError> {
+        let playlist_id: u64 = playlist_id
+            .try_into()
+            .map_err(|e| RemovePlaylistTrackError::Other(Box::new(e)))?;
+
+        Ok(remove_playlist_track_id(&self.db, playlist_id, track_id, ApiSource::Library).await?)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<ApiSearchResultsResponse, SearchError> {
+        Ok(search(
+            &self.profile,
+            query,
+            offset.map(|x| x as usize),
+            limit.map(|x| x as usize),
+            &None,
+        )?)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -1526,10 +2288,13 @@ pub enum ReindexError {
 /// * If there was a database error
 /// * If failed to recreate the index
 /// * If failed to populate the index
-pub async fn reindex_global_search_index(db: &LibraryDatabase) -> Result<(), ReindexError> {
+pub async fn reindex_global_search_index(
+    profile: &str,
+    db: &LibraryDatabase,
+) -> Result<(), ReindexError> {
     let reindex_start = std::time::SystemTime::now();
 
-    moosicbox_search::data::recreate_global_search_index().await?;
+    moosicbox_search::data::recreate_global_search_index(profile).await?;
 
     let artists = db::get_artists(db)
         .await?
@@ -1538,7 +2303,7 @@ pub async fn reindex_global_search_index(db: &LibraryDatabase) -> Result<(), Rei
         .map(|artist: Artist| artist.as_data_values())
         .collect::<Vec<_>>();
 
-    populate_global_search_index(&artists, false).await?;
+    populate_global_search_index(profile, &artists, false).await?;
 
     let albums = db::get_albums(db)
         .await?
@@ -1547,7 +2312,7 @@ pub async fn reindex_global_search_index(db: &LibraryDatabase) -> Result<(), Rei
         .map(|album: Album| album.as_data_values())
         .collect::<Vec<_>>();
 
-    populate_global_search_index(&albums, false).await?;
+    populate_global_search_index(profile, &albums, false).await?;
 
     let tracks = db::get_tracks(db, None)
         .await?
@@ -1556,7 +2321,7 @@ pub async fn reindex_global_search_index(db: &LibraryDatabase) -> Result<(), Rei
         .map(|track: Track| track.as_data_values())
         .collect::<Vec<_>>();
 
-    populate_global_search_index(&tracks, false).await?;
+    populate_global_search_index(profile, &tracks, false).await?;
 
     let reindex_end = std::time::SystemTime::now();
     log::info!(
@@ -1572,8 +2337,9 @@ pub async fn reindex_global_search_index(db: &LibraryDatabase) -> Result<(), Rei
 
 #[cfg(test)]
 mod test {
+    use moosicbox_json_utils::database::ToValue as _;
     use moosicbox_music_api::models::AlbumFilters;
-    use moosicbox_music_models::AlbumSource;
+    use moosicbox_music_models::{api::ApiTrack, AlbumSource};
 
     use super::*;
 
@@ -1700,6 +2466,87 @@ mod test {
         assert_eq!(result, vec![test]);
     }
 
+    #[test]
+    fn filter_albums_filters_albums_of_genre_that_dont_match() {
+        let jazz = LibraryAlbum {
+            id: 0,
+            title: "jazz".to_string(),
+            genres: vec!["Jazz".to_string(), "Bebop".to_string()],
+            source: AlbumSource::Local,
+            ..Default::default()
+        };
+        let rock = LibraryAlbum {
+            id: 1,
+            title: "rock".to_string(),
+            genres: vec!["Rock".to_string()],
+            source: AlbumSource::Local,
+            ..Default::default()
+        };
+        let untagged = LibraryAlbum {
+            id: 2,
+            title: "untagged".to_string(),
+            source: AlbumSource::Local,
+            ..Default::default()
+        };
+        let albums = vec![jazz.clone(), rock, untagged];
+        let result = filter_albums(
+            &albums,
+            &AlbumsRequest {
+                sources: None,
+                sort: None,
+                filters: Some(AlbumFilters {
+                    genre: Some("bebop".to_string()),
+                    ..Default::default()
+                }),
+                page: Some(PagingRequest {
+                    offset: 0,
+                    limit: 10,
+                }),
+            },
+        )
+        .cloned()
+        .collect::<Vec<_>>();
+        assert_eq!(result, vec![jazz]);
+    }
+
+    #[test]
+    fn sort_albums_sorts_albums_by_genre_with_untagged_albums_last() {
+        let jazz = LibraryAlbum {
+            id: 0,
+            genres: vec!["jazz".to_string()],
+            ..Default::default()
+        };
+        let blues = LibraryAlbum {
+            id: 1,
+            genres: vec!["Blues".to_string()],
+            ..Default::default()
+        };
+        let untagged = LibraryAlbum {
+            id: 2,
+            ..Default::default()
+        };
+        let request = |sort| AlbumsRequest {
+            sources: None,
+            sort: Some(sort),
+            filters: None,
+            page: None,
+        };
+
+        let asc = request(AlbumSort::GenreAsc);
+        let result = sort_albums(vec![&untagged, &jazz, &blues], &asc)
+            .into_iter()
+            .map(|x| x.id)
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![1, 0, 2]);
+
+        let desc = request(AlbumSort::GenreDesc);
+        let result = sort_albums(vec![&untagged, &blues, &jazz], &desc)
+            .into_iter()
+            .map(|x| x.id)
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![0, 1, 2]);
+    }
+
     #[test]
     fn filter_albums_filters_albums_of_name_that_dont_match_and_searches_multiple_words() {
         let bob = LibraryAlbum {
@@ -2075,4 +2922,553 @@ mod test {
         .collect::<Vec<_>>();
         assert_eq!(result, vec![bob, test]);
     }
+
+    fn play_history_db() -> LibraryDatabase {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch("CREATE TABLE tracks (id INTEGER PRIMARY KEY NOT NULL);")
+            .unwrap();
+        connection
+            .execute_batch(include_str!(
+                "../../schema/migrations/server/library/sqlite/2024-10-05-120000_create_play_history/up.sql"
+            ))
+            .unwrap();
+        let database: Box<dyn moosicbox_database::Database> =
+            Box::new(moosicbox_database::rusqlite::RusqliteDatabase::new(
+                std::sync::Arc::new(tokio::sync::Mutex::new(connection)),
+            ));
+
+        std::sync::Arc::new(database).into()
+    }
+
+    async fn add_plays(db: &LibraryDatabase, plays: &[(u64, u64, u64, u64)]) {
+        for (track_id, album_id, artist_id, played_at) in plays {
+            let track = ApiTrack {
+                track_id: Id::Number(*track_id),
+                album_id: Id::Number(*album_id),
+                artist_id: Id::Number(*artist_id),
+                title: format!("track {track_id}"),
+                ..Default::default()
+            };
+            db::add_play(db, "master", 1, None, &track, *played_at)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn recently_played_pages_in_the_query_and_counts_the_total() {
+        let db = play_history_db();
+        add_plays(&db, &[(1, 1, 1, 10), (2, 1, 1, 20), (3, 1, 1, 30)]).await;
+
+        let page = recently_played(&db, None, None, Some(1), Some(1))
+            .await
+            .unwrap();
+        let played_at = page.items().iter().map(|x| x.played_at).collect::<Vec<_>>();
+        assert_eq!(played_at, vec![20]);
+        assert_eq!(page.total(), Some(3));
+
+        let page = recently_played(&db, Some(20), Some(30), None, None)
+            .await
+            .unwrap();
+        let played_at = page.items().iter().map(|x| x.played_at).collect::<Vec<_>>();
+        assert_eq!(played_at, vec![20]);
+        assert_eq!(page.total(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn most_played_tracks_orders_tracks_by_play_count_then_last_played() {
+        let db = play_history_db();
+        add_plays(
+            &db,
+            &[
+                (3, 2, 1, 10),
+                (2, 1, 1, 20),
+                (3, 2, 1, 30),
+                (2, 1, 1, 40),
+                (1, 1, 1, 50),
+            ],
+        )
+        .await;
+
+        let page = most_played_tracks(&db, None, None, None, None)
+            .await
+            .unwrap();
+        let result = page
+            .items()
+            .iter()
+            .map(|x| (x.track.id.clone(), x.play_count, x.last_played_at))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            vec![
+                (Id::Number(2), 2, 40),
+                (Id::Number(3), 2, 30),
+                (Id::Number(1), 1, 50)
+            ]
+        );
+        assert_eq!(page.total(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn most_played_tracks_pages_the_groups_in_the_query() {
+        let db = play_history_db();
+        add_plays(
+            &db,
+            &[(1, 1, 1, 10), (1, 1, 1, 20), (2, 1, 1, 30), (3, 1, 1, 40)],
+        )
+        .await;
+
+        let page = most_played_tracks(&db, None, None, Some(1), Some(1))
+            .await
+            .unwrap();
+        let ids = page
+            .items()
+            .iter()
+            .map(|x| x.track.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![Id::Number(3)]);
+        assert_eq!(page.total(), Some(3));
+        assert!(page.has_more());
+    }
+
+    #[tokio::test]
+    async fn most_played_tracks_counts_the_same_track_id_from_different_sources_separately() {
+        let db = play_history_db();
+        add_plays(&db, &[(1, 1, 1, 20)]).await;
+        let tidal = ApiTrack {
+            track_id: Id::Number(1),
+            api_source: ApiSource::Tidal,
+            ..Default::default()
+        };
+        db::add_play(&db, "master", 1, None, &tidal, 10)
+            .await
+            .unwrap();
+
+        let page = most_played_tracks(&db, None, None, None, None)
+            .await
+            .unwrap();
+        let result = page
+            .items()
+            .iter()
+            .map(|x| (x.track.api_source, x.play_count))
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![(ApiSource::Library, 1), (ApiSource::Tidal, 1)]);
+    }
+
+    #[tokio::test]
+    async fn most_played_albums_groups_plays_by_album() {
+        let db = play_history_db();
+        add_plays(&db, &[(1, 1, 1, 30), (2, 2, 1, 20), (3, 2, 1, 10)]).await;
+
+        let page = most_played_albums(&db, None, None, None, None)
+            .await
+            .unwrap();
+        let result = page
+            .items()
+            .iter()
+            .map(|x| (x.album_id.clone(), x.play_count, x.last_played_at))
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![(Id::Number(2), 2, 20), (Id::Number(1), 1, 30)]);
+        assert_eq!(page.total(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn most_played_artists_groups_plays_by_artist() {
+        let db = play_history_db();
+        add_plays(&db, &[(1, 1, 1, 30), (2, 2, 2, 20), (3, 3, 1, 10)]).await;
+
+        let page = most_played_artists(&db, None, Some(30), None, None)
+            .await
+            .unwrap();
+        let result = page
+            .items()
+            .iter()
+            .map(|x| (x.artist_id.clone(), x.play_count, x.last_played_at))
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![(Id::Number(2), 1, 20), (Id::Number(1), 1, 10)]);
+        assert_eq!(page.total(), Some(2));
+    }
+
+    fn playlist_db() -> LibraryDatabase {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(include_str!(
+                "../../schema/migrations/server/library/sqlite/2024-10-01-180000_create_playlists/up.sql"
+            ))
+            .unwrap();
+        let database: Box<dyn moosicbox_database::Database> =
+            Box::new(moosicbox_database::rusqlite::RusqliteDatabase::new(
+                std::sync::Arc::new(tokio::sync::Mutex::new(connection)),
+            ));
+
+        std::sync::Arc::new(database).into()
+    }
+
+    #[tokio::test]
+    async fn playlists_pages_in_the_query_and_counts_the_total() {
+        let db = playlist_db();
+        for name in ["a", "b", "c", "d", "e"] {
+            create_playlist(&db, name).await.unwrap();
+        }
+
+        let page = playlists(&db, Some(1), Some(2)).await.unwrap();
+        let names = page
+            .items()
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["b", "c"]);
+        assert_eq!(page.total(), Some(5));
+
+        let page = playlists(&db, Some(4), Some(10)).await.unwrap();
+        assert_eq!(page.items().len(), 1);
+        assert_eq!(page.total(), Some(5));
+        assert!(!page.has_more());
+    }
+
+    #[tokio::test]
+    async fn playlist_tracks_pages_in_the_query_and_counts_the_total() {
+        let db = playlist_db();
+        let playlist = create_playlist(&db, "mix").await.unwrap();
+        let other = create_playlist(&db, "other").await.unwrap();
+        let tracks = (1..=4)
+            .map(|id| ApiTrack {
+                track_id: Id::Number(id),
+                title: format!("track {id}"),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        db::add_playlist_tracks(&db, playlist.id, &tracks)
+            .await
+            .unwrap();
+        db::add_playlist_tracks(&db, other.id, &tracks[..1])
+            .await
+            .unwrap();
+
+        let page = playlist_tracks(&db, playlist.id, Some(2), Some(5))
+            .await
+            .unwrap();
+        let titles = page
+            .items()
+            .iter()
+            .map(|x| x.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["track 3", "track 4"]);
+        assert_eq!(page.total(), Some(4));
+    }
+
+    #[tokio::test]
+    async fn playlist_tracks_returns_not_found_for_unknown_playlist() {
+        let db = playlist_db();
+        assert!(matches!(
+            playlist_tracks(&db, 42, None, None).await,
+            Err(LibraryPlaylistTracksError::NotFound)
+        ));
+    }
+
+    async fn playlist_with_tracks(db: &LibraryDatabase, ids: &[u64]) -> u64 {
+        let playlist = create_playlist(db, "mix").await.unwrap();
+        let tracks = ids
+            .iter()
+            .map(|id| ApiTrack {
+                track_id: Id::Number(*id),
+                title: format!("track {id}"),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        db::add_playlist_tracks(db, playlist.id, &tracks)
+            .await
+            .unwrap();
+
+        playlist.id
+    }
+
+    async fn playlist_titles(db: &LibraryDatabase, playlist_id: u64) -> Vec<String> {
+        db::get_playlist_tracks(db, playlist_id, 0, 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|x| x.title)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn remove_playlist_tracks_renumbers_the_remaining_tracks() {
+        let db = playlist_db();
+        let playlist_id = playlist_with_tracks(&db, &[1, 2, 3, 4]).await;
+
+        db::remove_playlist_tracks(&db, playlist_id, &[0, 2])
+            .await
+            .unwrap();
+        assert_eq!(
+            playlist_titles(&db, playlist_id).await,
+            vec!["track 2", "track 4"]
+        );
+
+        db::move_playlist_track(&db, playlist_id, 1, 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            playlist_titles(&db, playlist_id).await,
+            vec!["track 4", "track 2"]
+        );
+
+        db::add_playlist_tracks(
+            &db,
+            playlist_id,
+            &[ApiTrack {
+                track_id: Id::Number(5),
+                title: "track 5".to_string(),
+                ..Default::default()
+            }],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            playlist_titles(&db, playlist_id).await,
+            vec!["track 4", "track 2", "track 5"]
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_playlist_track_id_removes_every_occurrence() {
+        let db = playlist_db();
+        let playlist_id = playlist_with_tracks(&db, &[1, 2, 1, 3]).await;
+
+        db::remove_playlist_track_id(&db, playlist_id, &Id::Number(1), ApiSource::Library)
+            .await
+            .unwrap();
+        assert_eq!(
+            playlist_titles(&db, playlist_id).await,
+            vec!["track 2", "track 3"]
+        );
+
+        db::move_playlist_track(&db, playlist_id, 0, 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            playlist_titles(&db, playlist_id).await,
+            vec!["track 3", "track 2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn move_playlist_track_rejects_out_of_bounds_positions() {
+        let db = playlist_db();
+        let playlist_id = playlist_with_tracks(&db, &[1, 2]).await;
+
+        assert!(matches!(
+            db::move_playlist_track(&db, playlist_id, 0, 2).await,
+            Err(DatabaseFetchError::InvalidRequest)
+        ));
+        assert_eq!(
+            playlist_titles(&db, playlist_id).await,
+            vec!["track 1", "track 2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_playlist_deletes_its_tracks() {
+        let db = playlist_db();
+        let playlist_id = playlist_with_tracks(&db, &[1, 2]).await;
+
+        let deleted = db::delete_playlist(&db, playlist_id).await.unwrap();
+        assert_eq!(deleted.map(|x| x.id), Some(playlist_id));
+        assert!(db::get_playlist(&db, playlist_id).await.unwrap().is_none());
+        assert!(playlist_titles(&db, playlist_id).await.is_empty());
+        assert!(db::delete_playlist(&db, playlist_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn playlists_count_their_tracks() {
+        let db = playlist_db();
+        let empty = create_playlist(&db, "empty").await.unwrap();
+        assert_eq!(empty.track_count, Some(0));
+        let playlist_id = playlist_with_tracks(&db, &[1, 2, 1]).await;
+
+        let counts = playlists(&db, None, None)
+            .await
+            .unwrap()
+            .items()
+            .iter()
+            .map(|x| (x.id, x.track_count))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![(empty.id, Some(0)), (playlist_id, Some(3))]);
+
+        let renamed = db::rename_playlist(&db, playlist_id, "renamed")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Playlist::from(renamed).track_count, Some(3));
+    }
+
+    fn album_db() -> LibraryDatabase {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "
+                CREATE TABLE artists (
+                    id INTEGER PRIMARY KEY NOT NULL,
+                    title TEXT NOT NULL,
+                    tidal_id TEXT,
+                    qobuz_id TEXT
+                );
+                CREATE TABLE albums (
+                    id INTEGER PRIMARY KEY NOT NULL,
+                    artist_id INTEGER NOT NULL,
+                    title TEXT NOT NULL,
+                    date_released TEXT,
+                    date_added TEXT,
+                    artwork TEXT,
+                    directory TEXT,
+                    blur INTEGER NOT NULL DEFAULT 0,
+                    tidal_id TEXT,
+                    qobuz_id TEXT,
+                    genres TEXT,
+                    musicbrainz_release_id TEXT,
+                    upc TEXT,
+                    UNIQUE(artist_id, title)
+                );
+                CREATE TABLE tracks (
+                    id INTEGER PRIMARY KEY NOT NULL,
+                    album_id INTEGER NOT NULL,
+                    title TEXT NOT NULL,
+                    isrc TEXT
+                );
+                INSERT INTO artists (id, title) VALUES (1, 'artist');
+                ",
+            )
+            .unwrap();
+        let database: Box<dyn moosicbox_database::Database> =
+            Box::new(moosicbox_database::rusqlite::RusqliteDatabase::new(
+                std::sync::Arc::new(tokio::sync::Mutex::new(connection)),
+            ));
+
+        std::sync::Arc::new(database).into()
+    }
+
+    async fn add_album(
+        db: &LibraryDatabase,
+        title: &str,
+        musicbrainz_release_id: Option<&str>,
+        upc: Option<&str>,
+        isrcs: &[Option<&str>],
+    ) -> u64 {
+        let album = db
+            .insert("albums")
+            .value("artist_id", 1)
+            .value("title", title)
+            .value("musicbrainz_release_id", musicbrainz_release_id)
+            .value("upc", upc)
+            .execute(db)
+            .await
+            .unwrap();
+        let album_id: u64 = album.to_value("id").unwrap();
+
+        for (i, isrc) in isrcs.iter().enumerate() {
+            db.insert("tracks")
+                .value("album_id", album_id)
+                .value("title", format!("track {i}"))
+                .value("isrc", *isrc)
+                .execute(db)
+                .await
+                .unwrap();
+        }
+
+        album_id
+    }
+
+    fn release(
+        musicbrainz_release_id: Option<&str>,
+        upc: Option<&str>,
+        isrcs: &[&str],
+    ) -> db::AlbumMatchIds {
+        db::AlbumMatchIds {
+            musicbrainz_release_id: musicbrainz_release_id.map(ToString::to_string),
+            upc: upc.map(ToString::to_string),
+            isrcs: isrcs.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn find_matching_album_prefers_the_musicbrainz_release_id_over_the_upc() {
+        let db = album_db();
+        let by_upc = add_album(&db, "by upc", None, Some("upc-1"), &[]).await;
+        let by_mbid = add_album(&db, "by mbid", Some("mbid-1"), None, &[]).await;
+
+        let album = db::find_matching_album(&db, release(Some("mbid-1"), Some("upc-1"), &[]))
+            .await
+            .unwrap();
+        assert_eq!(album.map(|x| x.id), Some(by_mbid));
+
+        let album = db::find_matching_album(&db, release(Some("mbid-2"), Some("upc-1"), &[]))
+            .await
+            .unwrap();
+        assert_eq!(album.map(|x| x.id), Some(by_upc));
+    }
+
+    #[tokio::test]
+    async fn find_matching_album_requires_the_same_track_isrcs() {
+        let db = album_db();
+        let standard = add_album(&db, "standard", None, None, &[Some("a"), Some("b")]).await;
+        add_album(
+            &db,
+            "deluxe",
+            None,
+            None,
+            &[Some("a"), Some("b"), Some("c")],
+        )
+        .await;
+
+        let album = db::find_matching_album(&db, release(None, None, &["b", "a"]))
+            .await
+            .unwrap();
+        assert_eq!(album.map(|x| x.id), Some(standard));
+
+        let album = db::find_matching_album(&db, release(None, None, &["a"]))
+            .await
+            .unwrap();
+        assert_eq!(album, None);
+    }
+
+    #[tokio::test]
+    async fn find_matching_album_skips_albums_with_tracks_without_an_isrc() {
+        let db = album_db();
+        add_album(&db, "album", None, None, &[Some("a"), None]).await;
+
+        let album = db::find_matching_album(&db, release(None, None, &["a"]))
+            .await
+            .unwrap();
+        assert_eq!(album, None);
+    }
+
+    #[tokio::test]
+    async fn find_matching_albums_matches_each_release() {
+        let db = album_db();
+        let first = add_album(&db, "first", Some("mbid-1"), None, &[]).await;
+        let second = add_album(&db, "second", None, None, &[Some("a")]).await;
+
+        let albums = db::find_matching_albums(
+            &db,
+            &[
+                release(None, None, &["a"]),
+                release(None, Some("upc-unknown"), &[]),
+                release(Some("mbid-1"), None, &[]),
+                release(None, None, &[]),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            albums
+                .iter()
+                .map(|x| x.as_ref().map(|x| x.id))
+                .collect::<Vec<_>>(),
+            vec![Some(second), None, Some(first), None]
+        );
+    }
 }
@@ -21,7 +21,7 @@ impl LibraryMusicApiProfiles {
         self.profiles
             .write()
             .unwrap()
-            .push((profile, LibraryMusicApi { db }));
+            .push((profile.clone(), LibraryMusicApi { profile, db }));
     }
 
     /// # Panics
@@ -8,7 +8,7 @@ use actix_web::{
     web::{self, Json},
     Result, Scope,
 };
-use moosicbox_database::profiles::LibraryDatabase;
+use moosicbox_database::profiles::{api::ProfileName, LibraryDatabase};
 use moosicbox_library::{
     db::{get_album_tracks, get_tracks},
     LibraryMusicApi,
@@ -19,7 +19,7 @@ use moosicbox_music_api::{
     MusicApis, SourceToMusicApi as _,
 };
 use moosicbox_music_models::{
-    api::{ApiAlbum, ApiArtist, ApiTrack},
+    api::{ApiAlbum, ApiArtist, ApiPlaylist, ApiTrack},
     id::{parse_integer_ranges_to_ids, Id, IdType, ParseIntegersError},
     AlbumSort, AlbumSource, AlbumType, ApiSource, ArtistSort,
 };
@@ -53,6 +53,11 @@ pub fn bind_services<
         .service(get_album_tracks_endpoint)
         .service(get_album_versions_endpoint)
         .service(get_artist_albums_endpoint)
+        .service(get_playlists_endpoint)
+        .service(get_playlist_endpoint)
+        .service(get_playlist_tracks_endpoint)
+        .service(add_playlist_track_endpoint)
+        .service(remove_playlist_track_endpoint)
 }
 
 #[cfg(feature = "openapi")]
@@ -71,10 +76,16 @@ pub fn bind_services<
         add_album_endpoint,
         remove_album_endpoint,
         refavorite_album_endpoint,
+        get_playlists_endpoint,
+        get_playlist_endpoint,
+        get_playlist_tracks_endpoint,
+        add_playlist_track_endpoint,
+        remove_playlist_track_endpoint,
     ),
     components(schemas(
         ApiAlbum,
         ApiArtist,
+        ApiPlaylist,
         ApiTrack,
         ApiAlbumVersion,
         moosicbox_music_models::TrackApiSource,
@@ -195,6 +206,7 @@ pub struct GetAlbumsQuery {
     name: Option<String>,
     artist: Option<String>,
     search: Option<String>,
+    genre: Option<String>,
     artist_id: Option<String>,
     tidal_artist_id: Option<u64>,
     qobuz_artist_id: Option<u64>,
@@ -276,6 +288,7 @@ pub async fn get_albums_endpoint(
             artist: query.artist.clone().map(|s| s.to_lowercase()),
             search: query.search.clone().map(|s| s.to_lowercase()),
             album_type: query.album_type,
+            genre: query.genre.clone().map(|s| s.to_lowercase()),
             artist_id: query
                 .artist_id
                 .as_ref()
@@ -661,6 +674,7 @@ pub struct AddAlbumQuery {
 #[allow(clippy::future_not_send)]
 pub async fn add_album_endpoint(
     query: web::Query<AddAlbumQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     library_api: LibraryMusicApi,
     music_apis: MusicApis,
@@ -671,6 +685,7 @@ pub async fn add_album_endpoint(
                 .get(query.source)
                 .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?,
             &library_api,
+            &profile.0,
             &db,
             &album_id_for_source(&query.album_id, query.source)?,
         )
@@ -711,6 +726,7 @@ pub struct RemoveAlbumQuery {
 #[allow(clippy::future_not_send)]
 pub async fn remove_album_endpoint(
     query: web::Query<RemoveAlbumQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     library_api: LibraryMusicApi,
     music_apis: MusicApis,
@@ -721,6 +737,7 @@ pub async fn remove_album_endpoint(
                 .get(query.source)
                 .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?,
             &library_api,
+            &profile.0,
             &db,
             &album_id_for_source(&query.album_id, query.source)?,
         )
@@ -761,6 +778,7 @@ pub struct ReFavoriteAlbumQuery {
 #[allow(clippy::future_not_send)]
 pub async fn refavorite_album_endpoint(
     query: web::Query<ReFavoriteAlbumQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     library_api: LibraryMusicApi,
     music_apis: MusicApis,
@@ -771,6 +789,7 @@ pub async fn refavorite_album_endpoint(
                 .get(query.source)
                 .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?,
             &library_api,
+            &profile.0,
             &db,
             &album_id_for_source(&query.album_id, query.source)?,
         )
@@ -779,3 +798,247 @@ pub async fn refavorite_album_endpoint(
         .into(),
     ))
 }
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPlaylistsQuery {
+    source: Option<ApiSource>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Menu"],
+        get,
+        path = "/playlists",
+        description = "Get the playlists for the specified API source",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("source" = Option<ApiSource>, Query, description = "API Source to fetch the playlists from"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The page of playlists",
+                body = Value,
+            )
+        )
+    )
+)]
+#[get("/playlists")]
+pub async fn get_playlists_endpoint(
+    query: web::Query<GetPlaylistsQuery>,
+    music_apis: MusicApis,
+) -> Result<Json<Page<ApiPlaylist>>> {
+    let source = query.source.unwrap_or(ApiSource::Library);
+    let api = music_apis.get(source).map_err(ErrorBadRequest)?;
+
+    Ok(Json(
+        api.playlists(query.offset, query.limit)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("Failed to fetch playlists: {e}")))?
+            .map(Into::into)
+            .into(),
+    ))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPlaylistQuery {
+    playlist_id: String,
+    source: Option<ApiSource>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Menu"],
+        get,
+        path = "/playlist",
+        description = "Get the playlist for the specified API source",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = String, Query, description = "Playlist ID to fetch"),
+            ("source" = Option<ApiSource>, Query, description = "Playlist source to retrieve"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The matching playlist",
+                body = ApiPlaylist,
+            )
+        )
+    )
+)]
+#[get("/playlist")]
+pub async fn get_playlist_endpoint(
+    query: web::Query<GetPlaylistQuery>,
+    music_apis: MusicApis,
+) -> Result<Json<ApiPlaylist>> {
+    let source = query.source.unwrap_or(ApiSource::Library);
+    let id =
+        Id::try_from_str(&query.playlist_id, source, IdType::Playlist).map_err(ErrorBadRequest)?;
+    let api = music_apis.get(source).map_err(ErrorBadRequest)?;
+
+    Ok(Json(
+        api.playlist(&id)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("Failed to fetch playlist: {e}")))?
+            .ok_or_else(|| ErrorNotFound("Playlist not found"))?
+            .into(),
+    ))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPlaylistTracksQuery {
+    playlist_id: String,
+    source: Option<ApiSource>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Menu"],
+        get,
+        path = "/playlist/tracks",
+        description = "Get the tracks for the specified playlist",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = String, Query, description = "Playlist ID to fetch the tracks for"),
+            ("source" = Option<ApiSource>, Query, description = "Playlist source to retrieve"),
+            ("offset" = Option<u32>, Query, description = "Page offset"),
+            ("limit" = Option<u32>, Query, description = "Page limit"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The page of playlist tracks",
+                body = Value,
+            )
+        )
+    )
+)]
+#[get("/playlist/tracks")]
+pub async fn get_playlist_tracks_endpoint(
+    query: web::Query<GetPlaylistTracksQuery>,
+    music_apis: MusicApis,
+) -> Result<Json<Page<ApiTrack>>> {
+    let source = query.source.unwrap_or(ApiSource::Library);
+    let id =
+        Id::try_from_str(&query.playlist_id, source, IdType::Playlist).map_err(ErrorBadRequest)?;
+    let api = music_apis.get(source).map_err(ErrorBadRequest)?;
+
+    Ok(Json(
+        api.playlist_tracks(&id, query.offset, query.limit)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("Failed to fetch playlist tracks: {e}")))?
+            .map(Into::into)
+            .into(),
+    ))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePlaylistTrackQuery {
+    playlist_id: String,
+    track_id: String,
+    source: ApiSource,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Menu"],
+        post,
+        path = "/playlist/tracks",
+        description = "Add the track to the playlist on the given API source",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = String, Query, description = "Playlist ID to add the track to"),
+            ("trackId" = String, Query, description = "Track ID to add"),
+            ("source" = ApiSource, Query, description = "The API source the playlist exists in"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The updated playlist",
+                body = ApiPlaylist,
+            )
+        )
+    )
+)]
+#[post("/playlist/tracks")]
+pub async fn add_playlist_track_endpoint(
+    query: web::Query<UpdatePlaylistTrackQuery>,
+    music_apis: MusicApis,
+) -> Result<Json<ApiPlaylist>> {
+    let playlist_id = Id::try_from_str(&query.playlist_id, query.source, IdType::Playlist)
+        .map_err(ErrorBadRequest)?;
+    let track_id =
+        Id::try_from_str(&query.track_id, query.source, IdType::Track).map_err(ErrorBadRequest)?;
+    let api = music_apis
+        .get(query.source)
+        .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?;
+
+    api.add_playlist_track(&playlist_id, &track_id)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("Failed to add playlist track: {e:?}")))?;
+
+    Ok(Json(
+        api.playlist(&playlist_id)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("Failed to fetch playlist: {e}")))?
+            .ok_or_else(|| ErrorNotFound("Playlist not found"))?
+            .into(),
+    ))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Menu"],
+        delete,
+        path = "/playlist/tracks",
+        description = "Remove the track from the playlist on the given API source",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("playlistId" = String, Query, description = "Playlist ID to remove the track from"),
+            ("trackId" = String, Query, description = "Track ID to remove"),
+            ("source" = ApiSource, Query, description = "The API source the playlist exists in"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The updated playlist",
+                body = ApiPlaylist,
+            )
+        )
+    )
+)]
+#[delete("/playlist/tracks")]
+pub async fn remove_playlist_track_endpoint(
+    query: web::Query<UpdatePlaylistTrackQuery>,
+    music_apis: MusicApis,
+) -> Result<Json<ApiPlaylist>> {
+    let playlist_id = Id::try_from_str(&query.playlist_id, query.source, IdType::Playlist)
+        .map_err(ErrorBadRequest)?;
+    let track_id =
+        Id::try_from_str(&query.track_id, query.source, IdType::Track).map_err(ErrorBadRequest)?;
+    let api = music_apis
+        .get(query.source)
+        .map_err(|e| ErrorBadRequest(format!("Invalid source: {e:?}")))?;
+
+    api.remove_playlist_track(&playlist_id, &track_id)
+        .await
+        .map_err(|e| ErrorInternalServerError(format!("Failed to remove playlist track: {e:?}")))?;
+
+    Ok(Json(
+        api.playlist(&playlist_id)
+            .await
+            .map_err(|e| ErrorInternalServerError(format!("Failed to fetch playlist: {e}")))?
+            .ok_or_else(|| ErrorNotFound("Playlist not found"))?
+            .into(),
+    ))
+}
@@ -214,6 +214,7 @@ pub enum AddAlbumError {
 pub async fn add_album(
     api: &dyn MusicApi,
     library_api: &LibraryMusicApi,
+    profile: &str,
     db: &LibraryDatabase,
     album_id: &Id,
 ) -> Result<LibraryAlbum, AddAlbumError> {
@@ -249,6 +250,7 @@ pub async fn add_album(
     moosicbox_library::cache::clear_cache();
 
     moosicbox_search::populate_global_search_index(
+        profile,
         &results
             .artists
             .clone()
@@ -271,6 +273,7 @@ pub async fn add_album(
     }
 
     moosicbox_search::populate_global_search_index(
+        profile,
         &albums
             .clone()
             .into_iter()
@@ -302,6 +305,7 @@ pub async fn add_album(
     drop(output);
 
     moosicbox_search::populate_global_search_index(
+        profile,
         &tracks
             .iter()
             .map(AsDataValues::as_data_values)
@@ -351,6 +355,7 @@ pub enum RemoveAlbumError {
 pub async fn remove_album(
     api: &dyn MusicApi,
     library_api: &LibraryMusicApi,
+    profile: &str,
     db: &LibraryDatabase,
     album_id: &Id,
 ) -> Result<LibraryAlbum, RemoveAlbumError> {
@@ -463,6 +468,7 @@ pub async fn remove_album(
     moosicbox_library::cache::clear_cache();
 
     moosicbox_search::delete_from_global_search_index(
+        profile,
         &target_tracks
             .iter()
             .map(AsDeleteTerm::as_delete_term)
@@ -497,7 +503,7 @@ pub async fn remove_album(
     {
         let album: Album = album.clone().into();
 
-        moosicbox_search::delete_from_global_search_index(&[album.as_delete_term()])?;
+        moosicbox_search::delete_from_global_search_index(profile, &[album.as_delete_term()])?;
     }
 
     Ok(album)
@@ -537,6 +543,7 @@ pub enum ReFavoriteAlbumError {
 pub async fn refavorite_album(
     api: &dyn MusicApi,
     library_api: &LibraryMusicApi,
+    profile: &str,
     db: &LibraryDatabase,
     album_id: &Id,
 ) -> Result<LibraryAlbum, ReFavoriteAlbumError> {
@@ -601,8 +608,8 @@ pub async fn refavorite_album(
 
     log::debug!("Re-favoriting with ids album_id={album_id} new_album_id={new_album_id:?}");
 
-    remove_album(api, library_api, db, album_id).await?;
-    let album = add_album(api, library_api, db, &new_album_id).await?;
+    remove_album(api, library_api, profile, db, album_id).await?;
+    let album = add_album(api, library_api, profile, db, &new_album_id).await?;
 
     Ok(album)
 }
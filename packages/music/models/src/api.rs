@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     id::Id, Album, AlbumSource, AlbumType, AlbumVersionQuality, ApiSource, ApiSources, Artist,
-    AudioFormat, Track, TrackApiSource,
+    AudioFormat, Playlist, ReplayGain, Track, TrackApiSource,
 };
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -44,6 +44,44 @@ impl From<ApiArtist> for Artist {
     }
 }
 
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiPlaylist {
+    pub playlist_id: Id,
+    pub title: String,
+    pub description: Option<String>,
+    pub artwork: Option<String>,
+    pub track_count: Option<u32>,
+    pub api_source: ApiSource,
+}
+
+impl From<Playlist> for ApiPlaylist {
+    fn from(value: Playlist) -> Self {
+        Self {
+            playlist_id: value.id,
+            title: value.title,
+            description: value.description,
+            artwork: value.artwork,
+            track_count: value.track_count,
+            api_source: value.api_source,
+        }
+    }
+}
+
+impl From<ApiPlaylist> for Playlist {
+    fn from(value: ApiPlaylist) -> Self {
+        Self {
+            id: value.playlist_id,
+            title: value.title,
+            description: value.description,
+            artwork: value.artwork,
+            track_count: value.track_count,
+            api_source: value.api_source,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "camelCase")]
@@ -102,6 +140,18 @@ pub struct ApiTrack {
     pub overall_bitrate: Option<u32>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
+    pub replay_gain: Option<ReplayGain>,
+    pub play_count: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    pub composer: Option<String>,
+    /// The artist(s) credited on the track, if they differ from the album artist
+    pub track_artist: Option<String>,
+    pub isrc: Option<String>,
+    pub start_offset: Option<f64>,
+    pub end_offset: Option<f64>,
     pub track_source: TrackApiSource,
     pub api_source: ApiSource,
     pub sources: ApiSources,
@@ -129,6 +179,16 @@ impl From<Track> for ApiTrack {
             overall_bitrate: value.overall_bitrate,
             sample_rate: value.sample_rate,
             channels: value.channels,
+            replay_gain: value.replay_gain,
+            play_count: value.play_count,
+            disc_number: value.disc_number,
+            disc_total: value.disc_total,
+            genres: value.genres,
+            composer: value.composer,
+            track_artist: value.track_artist,
+            isrc: value.isrc,
+            start_offset: value.start_offset,
+            end_offset: value.end_offset,
             track_source: value.track_source,
             api_source: value.api_source,
             sources: value.sources,
@@ -164,6 +224,16 @@ impl From<ApiTrack> for Track {
             overall_bitrate: value.overall_bitrate,
             sample_rate: value.sample_rate,
             channels: value.channels,
+            replay_gain: value.replay_gain,
+            play_count: value.play_count,
+            disc_number: value.disc_number,
+            disc_total: value.disc_total,
+            genres: value.genres,
+            composer: value.composer,
+            track_artist: value.track_artist,
+            isrc: value.isrc,
+            start_offset: value.start_offset,
+            end_offset: value.end_offset,
             track_source: value.track_source,
             api_source: value.api_source,
             sources: value.sources,
@@ -18,6 +18,7 @@ pub enum IdType {
     Artist,
     Album,
     Track,
+    Playlist,
 }
 
 impl std::fmt::Display for IdType {
@@ -26,6 +27,7 @@ impl std::fmt::Display for IdType {
             Self::Artist => "artist",
             Self::Album => "album",
             Self::Track => "track",
+            Self::Playlist => "playlist",
         })
     }
 }
@@ -109,6 +111,15 @@ impl Id {
                 #[cfg(feature = "yt")]
                 ApiSource::Yt => Self::String(value.to_owned()),
             },
+            IdType::Playlist => match source {
+                ApiSource::Library => Self::Number(value.parse::<u64>()?),
+                #[cfg(feature = "tidal")]
+                ApiSource::Tidal => Self::String(value.to_owned()),
+                #[cfg(feature = "qobuz")]
+                ApiSource::Qobuz => Self::Number(value.parse::<u64>()?),
+                #[cfg(feature = "yt")]
+                ApiSource::Yt => Self::String(value.to_owned()),
+            },
         })
     }
 
@@ -125,6 +136,15 @@ impl Id {
                 #[cfg(feature = "yt")]
                 ApiSource::Yt => Self::String(String::new()),
             },
+            IdType::Playlist => match source {
+                ApiSource::Library => Self::Number(0),
+                #[cfg(feature = "tidal")]
+                ApiSource::Tidal => Self::String(String::new()),
+                #[cfg(feature = "qobuz")]
+                ApiSource::Qobuz => Self::Number(0),
+                #[cfg(feature = "yt")]
+                ApiSource::Yt => Self::String(String::new()),
+            },
             IdType::Track | IdType::Artist => match source {
                 ApiSource::Library => Self::Number(0),
                 #[cfg(feature = "tidal")]
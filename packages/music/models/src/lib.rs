@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use strum::{AsRefStr, EnumString};
 
 pub mod id;
+pub mod lyrics;
 
 #[cfg(feature = "api")]
 pub mod api;
@@ -45,7 +46,19 @@ impl FromStr for ArtistSort {
 }
 
 #[derive(
-    Default, Copy, Debug, Serialize, Deserialize, EnumString, AsRefStr, Eq, PartialEq, Clone, Hash,
+    Default,
+    Copy,
+    Debug,
+    Serialize,
+    Deserialize,
+    EnumString,
+    AsRefStr,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Clone,
+    Hash,
 )]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
@@ -202,6 +215,67 @@ impl ToValueType<TrackApiSource> for &serde_json::Value {
     }
 }
 
+/// `ReplayGain` loudness normalization values for a track, in dB for the gains and as a linear
+/// sample amplitude for the peaks.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReplayGain {
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+}
+
+impl ReplayGain {
+    /// Returns `None` if none of the gain or peak values are set.
+    #[must_use]
+    pub const fn or_none(self) -> Option<Self> {
+        if self.track_gain.is_none()
+            && self.track_peak.is_none()
+            && self.album_gain.is_none()
+            && self.album_peak.is_none()
+        {
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+/// The separator used between the genres of a track or album when they are stored as a single
+/// value.
+pub const GENRE_SEPARATOR: &str = "; ";
+
+/// Splits a genre tag value into its individual genres. Handles the `;` and null separated lists
+/// that taggers commonly write multiple genres as.
+#[must_use]
+pub fn split_genres(value: &str) -> Vec<String> {
+    let mut genres: Vec<String> = vec![];
+
+    for genre in value
+        .split([';', '\0'])
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+    {
+        if !genres.iter().any(|x| x.eq_ignore_ascii_case(genre)) {
+            genres.push(genre.to_string());
+        }
+    }
+
+    genres
+}
+
+/// Joins the genres into a single value that can be split again with [`split_genres`].
+#[must_use]
+pub fn join_genres(genres: &[String]) -> Option<String> {
+    if genres.is_empty() {
+        None
+    } else {
+        Some(genres.join(GENRE_SEPARATOR))
+    }
+}
+
 #[derive(Default, Debug, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Track {
@@ -226,6 +300,20 @@ pub struct Track {
     pub overall_bitrate: Option<u32>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
+    pub replay_gain: Option<ReplayGain>,
+    pub play_count: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    pub composer: Option<String>,
+    /// The artist(s) credited on the track, if they differ from the album artist
+    pub track_artist: Option<String>,
+    pub isrc: Option<String>,
+    /// Where the track starts in its file, in seconds, when the file holds several tracks
+    pub start_offset: Option<f64>,
+    /// Where the track ends in its file, in seconds. `None` is the end of the file
+    pub end_offset: Option<f64>,
     pub track_source: TrackApiSource,
     pub api_source: ApiSource,
     pub sources: ApiSources,
@@ -255,6 +343,17 @@ struct TrackInner {
     overall_bitrate: Option<u32>,
     sample_rate: Option<u32>,
     channels: Option<u8>,
+    replay_gain: Option<ReplayGain>,
+    play_count: Option<u32>,
+    disc_number: Option<u32>,
+    disc_total: Option<u32>,
+    #[serde(default)]
+    genres: Vec<String>,
+    composer: Option<String>,
+    track_artist: Option<String>,
+    isrc: Option<String>,
+    start_offset: Option<f64>,
+    end_offset: Option<f64>,
     source: TrackApiSource,
     qobuz_id: Option<u64>,
     tidal_id: Option<u64>,
@@ -294,6 +393,16 @@ impl From<TrackInner> for Track {
             overall_bitrate: value.overall_bitrate,
             sample_rate: value.sample_rate,
             channels: value.channels,
+            replay_gain: value.replay_gain,
+            play_count: value.play_count,
+            disc_number: value.disc_number,
+            disc_total: value.disc_total,
+            genres: value.genres,
+            composer: value.composer,
+            track_artist: value.track_artist,
+            isrc: value.isrc,
+            start_offset: value.start_offset,
+            end_offset: value.end_offset,
             track_source: value.source,
             api_source: ApiSource::Library,
             sources: {
@@ -440,6 +549,8 @@ pub struct Album {
     pub directory: Option<String>,
     pub blur: bool,
     pub versions: Vec<AlbumVersionQuality>,
+    /// The Universal Product Code (barcode) of the release
+    pub upc: Option<String>,
     pub album_source: AlbumSource,
     pub api_source: ApiSource,
     pub artist_sources: ApiSources,
@@ -466,6 +577,7 @@ impl From<Track> for Album {
             artwork: value.artwork,
             blur: value.blur,
             versions: vec![],
+            upc: None,
             album_source: value.track_source.into(),
             api_source: value.api_source,
             artist_sources: value.sources.clone(),
@@ -474,6 +586,17 @@ impl From<Track> for Album {
     }
 }
 
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Playlist {
+    pub id: Id,
+    pub title: String,
+    pub description: Option<String>,
+    pub artwork: Option<String>,
+    pub track_count: Option<u32>,
+    pub api_source: ApiSource,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Default, AsRefStr)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum AlbumSource {
@@ -542,6 +665,8 @@ pub enum AlbumSort {
     ReleaseDateDesc,
     DateAddedAsc,
     DateAddedDesc,
+    GenreAsc,
+    GenreDesc,
 }
 
 impl std::fmt::Display for AlbumSort {
@@ -555,6 +680,8 @@ impl std::fmt::Display for AlbumSort {
             Self::ReleaseDateDesc => f.write_str("release-date-desc"),
             Self::DateAddedAsc => f.write_str("date-added"),
             Self::DateAddedDesc => f.write_str("date-added-desc"),
+            Self::GenreAsc => f.write_str("genre"),
+            Self::GenreDesc => f.write_str("genre-desc"),
         }
     }
 }
@@ -572,6 +699,8 @@ impl FromStr for AlbumSort {
             "release-date-desc" => Ok(Self::ReleaseDateDesc),
             "date-added-asc" | "date-added" => Ok(Self::DateAddedAsc),
             "date-added-desc" => Ok(Self::DateAddedDesc),
+            "genre-asc" | "genre" => Ok(Self::GenreAsc),
+            "genre-desc" => Ok(Self::GenreDesc),
             _ => Err(()),
         }
     }
@@ -586,12 +715,21 @@ impl FromStr for AlbumSort {
 pub enum AudioFormat {
     #[cfg(feature = "aac")]
     Aac,
+    /// Apple Lossless in MP4
+    #[cfg(feature = "alac")]
+    Alac,
     #[cfg(feature = "flac")]
     Flac,
     #[cfg(feature = "mp3")]
     Mp3,
     #[cfg(feature = "opus")]
     Opus,
+    /// Ogg Vorbis
+    #[cfg(feature = "vorbis")]
+    Vorbis,
+    /// 16-bit PCM in a RIFF/WAVE container
+    #[cfg(feature = "wav")]
+    Wav,
     #[default]
     Source,
 }
@@ -612,16 +750,125 @@ pub fn from_extension_to_audio_format(extension: &str) -> Option<AudioFormat> {
         "mp3" => AudioFormat::Mp3,
         #[cfg(feature = "opus")]
         "opus" => AudioFormat::Opus,
+        #[cfg(feature = "vorbis")]
+        "ogg" | "oga" => AudioFormat::Vorbis,
+        #[cfg(feature = "wav")]
+        "wav" => AudioFormat::Wav,
         #[cfg(feature = "aac")]
         "m4a" | "mp4" => AudioFormat::Aac,
         _ => return None,
     })
 }
 
+/// Overrides of the encoder defaults for a transcoded format
+#[derive(Copy, Clone, Default, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EncodingSettings {
+    /// The constant bitrate, in kbps. Takes precedence over `vbr_quality`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bitrate: Option<u32>,
+    /// The variable bitrate quality, from 0 (best) to 9 (worst)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vbr_quality: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u8>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum InvalidEncodingSettingsError {
+    #[error("Invalid bitrate {0}. Expected a value from 8 to 512 kbps")]
+    Bitrate(u32),
+    #[error("Invalid vbrQuality {0}. Expected a value from 0 to 9")]
+    VbrQuality(u8),
+    #[error("Invalid sampleRate {0}. Expected a value from 8000 to 192000")]
+    SampleRate(u32),
+    #[error("Invalid channels {0}. Expected 1 or 2")]
+    Channels(u8),
+}
+
+impl EncodingSettings {
+    pub const BITRATE_RANGE: std::ops::RangeInclusive<u32> = 8..=512;
+    pub const VBR_QUALITY_RANGE: std::ops::RangeInclusive<u8> = 0..=9;
+    pub const SAMPLE_RATE_RANGE: std::ops::RangeInclusive<u32> = 8_000..=192_000;
+    pub const CHANNELS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
+
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks that every value that is set is within the range the encoders
+    /// support
+    ///
+    /// # Errors
+    ///
+    /// * If any of the values is out of range
+    pub fn validate(&self) -> Result<(), InvalidEncodingSettingsError> {
+        if let Some(bitrate) = self.bitrate.filter(|x| !Self::BITRATE_RANGE.contains(x)) {
+            return Err(InvalidEncodingSettingsError::Bitrate(bitrate));
+        }
+        if let Some(quality) = self
+            .vbr_quality
+            .filter(|x| !Self::VBR_QUALITY_RANGE.contains(x))
+        {
+            return Err(InvalidEncodingSettingsError::VbrQuality(quality));
+        }
+        if let Some(rate) = self
+            .sample_rate
+            .filter(|x| !Self::SAMPLE_RATE_RANGE.contains(x))
+        {
+            return Err(InvalidEncodingSettingsError::SampleRate(rate));
+        }
+        if let Some(channels) = self.channels.filter(|x| !Self::CHANNELS_RANGE.contains(x)) {
+            return Err(InvalidEncodingSettingsError::Channels(channels));
+        }
+
+        Ok(())
+    }
+
+    /// Uses the values of `other` that are set over the ones of `self`
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            bitrate: other.bitrate.or(self.bitrate),
+            vbr_quality: other.vbr_quality.or(self.vbr_quality),
+            sample_rate: other.sample_rate.or(self.sample_rate),
+            channels: other.channels.or(self.channels),
+        }
+    }
+}
+
+impl std::fmt::Display for EncodingSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values = [
+            self.bitrate.map(|x| format!("bitrate={x}")),
+            self.vbr_quality.map(|x| format!("vbrQuality={x}")),
+            self.sample_rate.map(|x| format!("sampleRate={x}")),
+            self.channels.map(|x| format!("channels={x}")),
+        ];
+
+        f.write_str(&values.into_iter().flatten().collect::<Vec<_>>().join(","))
+    }
+}
+
 #[derive(Copy, Clone, Default, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaybackQuality {
     pub format: AudioFormat,
+    #[serde(default, skip_serializing_if = "EncodingSettings::is_default")]
+    pub settings: EncodingSettings,
+}
+
+impl From<AudioFormat> for PlaybackQuality {
+    fn from(format: AudioFormat) -> Self {
+        Self {
+            format,
+            settings: EncodingSettings::default(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
@@ -631,3 +878,95 @@ pub struct TrackSize {
     pub bytes: Option<u64>,
     pub format: String,
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::{EncodingSettings, InvalidEncodingSettingsError};
+
+    #[test_log::test]
+    fn encoding_settings_validate_accepts_defaults_and_bounds() {
+        assert_eq!(EncodingSettings::default().validate(), Ok(()));
+        assert_eq!(
+            EncodingSettings {
+                bitrate: Some(8),
+                vbr_quality: Some(0),
+                sample_rate: Some(8_000),
+                channels: Some(1),
+            }
+            .validate(),
+            Ok(())
+        );
+        assert_eq!(
+            EncodingSettings {
+                bitrate: Some(512),
+                vbr_quality: Some(9),
+                sample_rate: Some(192_000),
+                channels: Some(2),
+            }
+            .validate(),
+            Ok(())
+        );
+    }
+
+    #[test_log::test]
+    fn encoding_settings_validate_rejects_out_of_range_values() {
+        assert_eq!(
+            EncodingSettings {
+                bitrate: Some(u32::MAX),
+                ..Default::default()
+            }
+            .validate(),
+            Err(InvalidEncodingSettingsError::Bitrate(u32::MAX))
+        );
+        assert_eq!(
+            EncodingSettings {
+                bitrate: Some(0),
+                ..Default::default()
+            }
+            .validate(),
+            Err(InvalidEncodingSettingsError::Bitrate(0))
+        );
+        assert_eq!(
+            EncodingSettings {
+                vbr_quality: Some(10),
+                ..Default::default()
+            }
+            .validate(),
+            Err(InvalidEncodingSettingsError::VbrQuality(10))
+        );
+        assert_eq!(
+            EncodingSettings {
+                sample_rate: Some(0),
+                ..Default::default()
+            }
+            .validate(),
+            Err(InvalidEncodingSettingsError::SampleRate(0))
+        );
+        assert_eq!(
+            EncodingSettings {
+                sample_rate: Some(384_000),
+                ..Default::default()
+            }
+            .validate(),
+            Err(InvalidEncodingSettingsError::SampleRate(384_000))
+        );
+        assert_eq!(
+            EncodingSettings {
+                channels: Some(0),
+                ..Default::default()
+            }
+            .validate(),
+            Err(InvalidEncodingSettingsError::Channels(0))
+        );
+        assert_eq!(
+            EncodingSettings {
+                channels: Some(6),
+                ..Default::default()
+            }
+            .validate(),
+            Err(InvalidEncodingSettingsError::Channels(6))
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Track lyrics, either plain text or synced to the track with LRC timestamps.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LyricsLine {
+    /// When the line starts, in seconds from the start of the track. Only
+    /// present for synced lyrics.
+    pub start: Option<f64>,
+    pub text: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Lyrics {
+    /// Whether every line has a start time
+    pub synced: bool,
+    pub lines: Vec<LyricsLine>,
+}
+
+impl Lyrics {
+    /// Parses LRC lyrics, falling back to plain text lyrics with one
+    /// unsynced line per line of `text` if there are no LRC timestamps.
+    ///
+    /// A line may have several timestamps, for repeated lines, and the
+    /// `[offset:]` tag shifts every timestamp by the given milliseconds.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut offset = 0.0;
+        let mut lines = vec![];
+
+        for line in text.lines() {
+            let (starts, tags, text) = parse_lrc_line(line);
+
+            for (key, value) in tags {
+                if key.eq_ignore_ascii_case("offset") {
+                    if let Ok(value) = value.trim().parse::<f64>() {
+                        offset = value / 1000.0;
+                    }
+                }
+            }
+
+            for start in starts {
+                lines.push((start, text.to_string()));
+            }
+        }
+
+        if lines.is_empty() {
+            return Self::plain(text);
+        }
+
+        lines.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            synced: true,
+            lines: lines
+                .into_iter()
+                .map(|(start, text)| LyricsLine {
+                    start: Some((start - offset).max(0.0)),
+                    text,
+                })
+                .collect(),
+        }
+    }
+
+    /// Unsynced lyrics with one line per line of `text`
+    #[must_use]
+    pub fn plain(text: &str) -> Self {
+        Self {
+            synced: false,
+            lines: text
+                .trim()
+                .lines()
+                .map(|line| LyricsLine {
+                    start: None,
+                    text: line.trim().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// The index of the line being sung `position` seconds into the track.
+    /// Always `None` for unsynced lyrics.
+    #[must_use]
+    pub fn line_at(&self, position: f64) -> Option<usize> {
+        if !self.synced {
+            return None;
+        }
+
+        self.lines
+            .iter()
+            .rposition(|line| line.start.is_some_and(|start| start <= position))
+    }
+}
+
+/// Writes synced lyrics as LRC and unsynced lyrics as plain text, so they
+/// [`Lyrics::parse`] back to the same lyrics.
+impl std::fmt::Display for Lyrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            if let Some(start) = line.start {
+                let centiseconds = (start * 100.0).round();
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let centiseconds = centiseconds as u64;
+                write!(
+                    f,
+                    "[{:02}:{:02}.{:02}]",
+                    centiseconds / 6000,
+                    centiseconds / 100 % 60,
+                    centiseconds % 100
+                )?;
+            }
+            writeln!(f, "{}", line.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a line into its timestamps, its `[key:value]` tags and its text.
+/// Lines without any leading brackets have no timestamps.
+fn parse_lrc_line(line: &str) -> (Vec<f64>, Vec<(&str, &str)>, &str) {
+    let mut starts = vec![];
+    let mut tags = vec![];
+    let mut rest = line.trim_start();
+
+    while let Some(inner) = rest.strip_prefix('[') {
+        let Some(end) = inner.find(']') else {
+            break;
+        };
+        let tag = &inner[..end];
+
+        if let Some(start) = parse_timestamp(tag) {
+            starts.push(start);
+        } else if let Some((key, value)) = tag.split_once(':') {
+            tags.push((key, value));
+        } else {
+            break;
+        }
+
+        rest = &inner[end + 1..];
+    }
+
+    (starts, tags, rest.trim())
+}
+
+/// Parses a `mm:ss`, `mm:ss.xx` or `mm:ss:xx` timestamp into seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let (minutes, seconds) = value.split_once(':')?;
+    let minutes = minutes.trim().parse::<u32>().ok()?;
+    let seconds = seconds.trim().replacen(':', ".", 1);
+
+    if !seconds.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let seconds = seconds.parse::<f64>().ok()?;
+
+    Some(f64::from(minutes).mul_add(60.0, seconds))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::lyrics::{Lyrics, LyricsLine};
+
+    fn synced(lines: &[(f64, &str)]) -> Lyrics {
+        Lyrics {
+            synced: true,
+            lines: lines
+                .iter()
+                .map(|(start, text)| LyricsLine {
+                    start: Some(*start),
+                    text: (*text).to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test_log::test]
+    fn parse_repeats_lines_with_multiple_timestamps() {
+        let lyrics = Lyrics::parse("[00:12.00][01:30.50]Chorus\n[00:20.00]Verse\n");
+
+        assert_eq!(
+            lyrics,
+            synced(&[(12.0, "Chorus"), (20.0, "Verse"), (90.5, "Chorus")])
+        );
+    }
+
+    #[test_log::test]
+    fn parse_shifts_timestamps_by_the_offset() {
+        let lyrics = Lyrics::parse("[offset:+500]\n[00:00.20]Intro\n[00:01.00]A\n[00:02.00]B");
+
+        assert_eq!(lyrics, synced(&[(0.0, "Intro"), (0.5, "A"), (1.5, "B")]));
+    }
+
+    #[test_log::test]
+    fn parse_skips_metadata_and_malformed_tags() {
+        let lyrics = Lyrics::parse(
+            "[ti:Song]\n[ar:Artist]\n[00:05.00]Good\n[00:1a]Bad\n[00:07.00 Unclosed\n[00:09:50]Colon",
+        );
+
+        assert_eq!(lyrics, synced(&[(5.0, "Good"), (9.5, "Colon")]));
+    }
+
+    #[test_log::test]
+    fn parse_falls_back_to_plain_lyrics_without_timestamps() {
+        let lyrics = Lyrics::parse("[not a tag] First\n  Second  \n");
+
+        assert_eq!(
+            lyrics,
+            Lyrics {
+                synced: false,
+                lines: vec![
+                    LyricsLine {
+                        start: None,
+                        text: "[not a tag] First".to_string(),
+                    },
+                    LyricsLine {
+                        start: None,
+                        text: "Second".to_string(),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test_log::test]
+    fn display_round_trips_synced_lyrics() {
+        let lyrics = synced(&[(5.0, "Good"), (65.25, "Later")]);
+
+        assert_eq!(lyrics.to_string(), "[00:05.00]Good\n[01:05.25]Later\n");
+        assert_eq!(Lyrics::parse(&lyrics.to_string()), lyrics);
+    }
+
+    #[test_log::test]
+    fn line_at_finds_the_current_line() {
+        let lyrics = synced(&[(5.0, "Good"), (9.5, "Colon")]);
+
+        assert_eq!(lyrics.line_at(1.0), None);
+        assert_eq!(lyrics.line_at(5.0), Some(0));
+        assert_eq!(lyrics.line_at(12.0), Some(1));
+        assert_eq!(Lyrics::plain("Plain").line_at(12.0), None);
+    }
+}
@@ -28,6 +28,7 @@ pub struct AlbumFilters {
     pub artist: Option<String>,
     pub search: Option<String>,
     pub album_type: Option<AlbumType>,
+    pub genre: Option<String>,
     pub artist_id: Option<Id>,
     pub tidal_artist_id: Option<Id>,
     pub qobuz_artist_id: Option<Id>,
@@ -85,6 +86,10 @@ pub enum TrackSource {
         format: AudioFormat,
         track_id: Option<Id>,
         source: TrackApiSource,
+        /// Where the track starts in the file, in seconds, when the file holds several tracks
+        start_offset: Option<f64>,
+        /// Where the track ends in the file, in seconds. `None` is the end of the file
+        end_offset: Option<f64>,
     },
     RemoteUrl {
         url: String,
@@ -102,6 +107,17 @@ impl TrackSource {
         }
     }
 
+    /// Whether the track is only part of its file, so the file can't be served
+    /// as-is.
+    #[must_use]
+    pub const fn has_offsets(&self) -> bool {
+        matches!(
+            self,
+            Self::LocalFilePath { start_offset, end_offset, .. }
+                if start_offset.is_some() || end_offset.is_some()
+        )
+    }
+
     #[must_use]
     pub const fn track_id(&self) -> Option<&Id> {
         match self {
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use actix_web::{
+    dev::{ServiceFactory, ServiceRequest},
+    get,
+    web::{self, Json},
+    Result, Scope,
+};
+use serde::Deserialize;
+
+pub mod models;
+
+use crate::{
+    search::{federated_search, DEFAULT_SOURCE_TIMEOUT},
+    MusicApis,
+};
+use models::ApiFederatedSearchResultsResponse;
+
+pub fn bind_services<
+    T: ServiceFactory<ServiceRequest, Config = (), Error = actix_web::Error, InitError = ()>,
+>(
+    scope: Scope<T>,
+) -> Scope<T> {
+    scope.service(federated_search_endpoint)
+}
+
+#[cfg(feature = "openapi")]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    tags((name = "Music API")),
+    paths(federated_search_endpoint),
+    components(schemas(
+        models::ApiFederatedSearchResult,
+        models::ApiFederatedSearchSource,
+        models::ApiFederatedSearchResultsResponse,
+        moosicbox_search::api::models::ApiGlobalSearchResult,
+        moosicbox_search::api::models::ApiGlobalArtistSearchResult,
+        moosicbox_search::api::models::ApiGlobalAlbumSearchResult,
+        moosicbox_search::api::models::ApiGlobalTrackSearchResult,
+    ))
+)]
+pub struct Api;
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FederatedSearchQuery {
+    query: String,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    timeout_ms: Option<u64>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Music API"],
+        get,
+        path = "/search",
+        description = "Search every music API for artists/albums/tracks that match the query",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("query" = String, Query, description = "The search query"),
+            ("offset" = Option<u32>, Query, description = "Page offset for each music API"),
+            ("limit" = Option<u32>, Query, description = "Page limit for each music API"),
+            ("timeoutMs" = Option<u64>, Query, description = "How long to wait for each music API to respond, in milliseconds"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The de-duplicated matches from every music API",
+                body = ApiFederatedSearchResultsResponse,
+            )
+        )
+    )
+)]
+#[get("/search")]
+pub async fn federated_search_endpoint(
+    query: web::Query<FederatedSearchQuery>,
+    music_apis: MusicApis,
+) -> Result<Json<ApiFederatedSearchResultsResponse>> {
+    let timeout = query
+        .timeout_ms
+        .map_or(DEFAULT_SOURCE_TIMEOUT, Duration::from_millis);
+
+    let results = federated_search(
+        &music_apis,
+        &query.query,
+        query.offset,
+        query.limit,
+        timeout,
+    )
+    .await;
+
+    Ok(Json(results.into()))
+}
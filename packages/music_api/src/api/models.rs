@@ -0,0 +1,59 @@
+use moosicbox_music_models::{id::Id, ApiSource};
+use moosicbox_search::api::models::ApiGlobalSearchResult;
+use serde::Serialize;
+
+use crate::search::{FederatedSearchResult, FederatedSearchResults, FederatedSearchSource};
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiFederatedSearchSource {
+    pub source: ApiSource,
+    pub id: Id,
+}
+
+impl From<FederatedSearchSource> for ApiFederatedSearchSource {
+    fn from(value: FederatedSearchSource) -> Self {
+        Self {
+            source: value.source,
+            id: value.id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiFederatedSearchResult {
+    pub source: ApiSource,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub other_sources: Vec<ApiFederatedSearchSource>,
+    pub result: ApiGlobalSearchResult,
+}
+
+impl From<FederatedSearchResult> for ApiFederatedSearchResult {
+    fn from(value: FederatedSearchResult) -> Self {
+        Self {
+            source: value.source,
+            other_sources: value.other_sources.into_iter().map(Into::into).collect(),
+            result: value.result,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiFederatedSearchResultsResponse {
+    pub results: Vec<ApiFederatedSearchResult>,
+    pub failed_sources: Vec<ApiSource>,
+}
+
+impl From<FederatedSearchResults> for ApiFederatedSearchResultsResponse {
+    fn from(value: FederatedSearchResults) -> Self {
+        Self {
+            results: value.results.into_iter().map(Into::into).collect(),
+            failed_sources: value.failed_sources,
+        }
+    }
+}
@@ -12,12 +12,19 @@ use models::{
 };
 use moosicbox_menu_models::AlbumVersion;
 pub use moosicbox_music_api_models as models;
-use moosicbox_music_models::{id::Id, Album, AlbumType, ApiSource, Artist, PlaybackQuality, Track};
-use moosicbox_paging::PagingResult;
+use moosicbox_music_models::{
+    id::Id, lyrics::Lyrics, Album, AlbumType, ApiSource, Artist, PlaybackQuality, Playlist, Track,
+};
+use moosicbox_paging::{PagingResponse, PagingResult};
+use moosicbox_search::api::models::ApiSearchResultsResponse;
 use thiserror::Error;
 use tokio::sync::{Mutex, RwLock};
 
+#[cfg(feature = "api")]
+pub mod api;
+
 pub mod profiles;
+pub mod search;
 
 #[derive(Clone)]
 pub struct MusicApis<S: ::std::hash::BuildHasher + Clone = std::hash::RandomState>(
@@ -48,6 +55,12 @@ impl<S: ::std::hash::BuildHasher + Clone> From<Arc<HashMap<ApiSource, Arc<Box<dy
     }
 }
 
+impl<S: ::std::hash::BuildHasher + Clone> MusicApis<S> {
+    pub fn iter(&self) -> impl Iterator<Item = (&ApiSource, &Arc<Box<dyn MusicApi>>)> {
+        self.0.iter()
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum MusicApisError {
     #[error("Music API for source not found: {0}")]
@@ -144,6 +157,14 @@ pub enum TrackError {
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
 
+#[derive(Debug, Error)]
+pub enum TrackLyricsError {
+    #[error("Track not found")]
+    NotFound,
+    #[error(transparent)]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
 #[derive(Debug, Error)]
 pub enum AddTrackError {
     #[error(transparent)]
@@ -156,6 +177,38 @@ pub enum RemoveTrackError {
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
 
+#[derive(Debug, Error)]
+pub enum PlaylistsError {
+    #[error(transparent)]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[derive(Debug, Error)]
+pub enum PlaylistError {
+    #[error(transparent)]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[derive(Debug, Error)]
+pub enum AddPlaylistTrackError {
+    #[error(transparent)]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[derive(Debug, Error)]
+pub enum RemovePlaylistTrackError {
+    #[error(transparent)]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("Searching is not supported by this music API")]
+    Unsupported,
+    #[error(transparent)]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
 pub enum TrackOrId {
     Track(Box<Track>),
     Id(Id),
@@ -313,14 +366,57 @@ pub trait MusicApi: Send + Sync {
         source: &TrackSource,
         quality: PlaybackQuality,
     ) -> Result<Option<u64>, TrackError>;
+
+    async fn track_lyrics(&self, _track_id: &Id) -> Result<Option<Lyrics>, TrackLyricsError> {
+        Ok(None)
+    }
+
+    async fn playlists(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Playlist, PlaylistsError>;
+
+    async fn playlist(&self, playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError>;
+
+    async fn playlist_tracks(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Track, TracksError>;
+
+    async fn add_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), AddPlaylistTrackError>;
+
+    async fn remove_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), RemovePlaylistTrackError>;
+
+    async fn search(
+        &self,
+        query: &str,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<ApiSearchResultsResponse, SearchError>;
 }
 
+/// The cached `playlist_tracks` pages of a playlist, keyed by their `(offset, limit)`.
+type PlaylistTracksPages = HashMap<(Option<u32>, Option<u32>), PagingResponse<Track, TracksError>>;
+
 pub struct CachedMusicApi<T: MusicApi> {
     inner: T,
     cascade_delete: bool,
     artists: Arc<RwLock<HashMap<Id, Option<Artist>>>>,
     albums: Arc<RwLock<HashMap<Id, Option<Album>>>>,
     tracks: Arc<RwLock<HashMap<Id, Option<Track>>>>,
+    playlists: Arc<RwLock<HashMap<Id, Option<Playlist>>>>,
+    playlist_tracks: Arc<RwLock<HashMap<Id, PlaylistTracksPages>>>,
 }
 
 impl<T: MusicApi> CachedMusicApi<T> {
@@ -331,6 +427,8 @@ impl<T: MusicApi> CachedMusicApi<T> {
             artists: Arc::new(RwLock::new(HashMap::new())),
             albums: Arc::new(RwLock::new(HashMap::new())),
             tracks: Arc::new(RwLock::new(HashMap::new())),
+            playlists: Arc::new(RwLock::new(HashMap::new())),
+            playlist_tracks: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -348,6 +446,8 @@ impl<T: MusicApi> CachedMusicApi<T> {
         self.artists.write().await.clear();
         self.albums.write().await.clear();
         self.tracks.write().await.clear();
+        self.playlists.write().await.clear();
+        self.playlist_tracks.write().await.clear();
     }
 
     #[inline]
@@ -365,6 +465,26 @@ impl<T: MusicApi> CachedMusicApi<T> {
         self.tracks.read().await.get(track_id).cloned()
     }
 
+    #[inline]
+    async fn get_playlist_from_cache(&self, playlist_id: &Id) -> Option<Option<Playlist>> {
+        self.playlists.read().await.get(playlist_id).cloned()
+    }
+
+    #[inline]
+    async fn get_playlist_tracks_from_cache(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Option<PagingResponse<Track, TracksError>> {
+        self.playlist_tracks
+            .read()
+            .await
+            .get(playlist_id)
+            .and_then(|pages| pages.get(&(offset, limit)))
+            .cloned()
+    }
+
     pub async fn cache_empty_artists(&self, ids: &[&Id]) {
         Self::cache_empty_values(&self.artists, ids).await;
     }
@@ -377,6 +497,10 @@ impl<T: MusicApi> CachedMusicApi<T> {
         Self::cache_empty_values(&self.tracks, ids).await;
     }
 
+    pub async fn cache_empty_playlists(&self, ids: &[&Id]) {
+        Self::cache_empty_values(&self.playlists, ids).await;
+    }
+
     async fn cache_empty_values<E: Send + Sync>(
         cache: &RwLock<HashMap<Id, Option<E>>>,
         ids: &[&Id],
@@ -420,6 +544,20 @@ impl<T: MusicApi> CachedMusicApi<T> {
         }
     }
 
+    pub async fn cache_playlists(&self, playlists: &[Playlist]) {
+        Self::cache_playlists_inner(&self.playlists, playlists).await;
+    }
+
+    async fn cache_playlists_inner(
+        cache: &RwLock<HashMap<Id, Option<Playlist>>>,
+        playlists: &[Playlist],
+    ) {
+        let mut cache = cache.write().await;
+        for playlist in playlists {
+            cache.insert(playlist.id.clone(), Some(playlist.to_owned()));
+        }
+    }
+
     pub async fn remove_cache_artist_ids(&self, ids: &[&Id]) {
         Self::remove_cache_ids(&mut *self.artists.write().await, ids);
 
@@ -478,7 +616,15 @@ impl<T: MusicApi> CachedMusicApi<T> {
         Self::remove_cache_ids(&mut *self.tracks.write().await, ids);
     }
 
-    fn remove_cache_ids<E>(cache: &mut HashMap<Id, Option<E>>, ids: &[&Id]) {
+    pub async fn remove_cache_playlist_ids(&self, ids: &[&Id]) {
+        Self::remove_cache_ids(&mut *self.playlists.write().await, ids);
+    }
+
+    pub async fn remove_cache_playlist_tracks_ids(&self, ids: &[&Id]) {
+        Self::remove_cache_ids(&mut *self.playlist_tracks.write().await, ids);
+    }
+
+    fn remove_cache_ids<V>(cache: &mut HashMap<Id, V>, ids: &[&Id]) {
         for id in ids {
             cache.remove(*id);
         }
@@ -818,6 +964,142 @@ impl<T: MusicApi> MusicApi for CachedMusicApi<T> {
     ) -> Result<Option<u64>, TrackError> {
         self.inner.track_size(track, source, quality).await
     }
+
+    async fn track_lyrics(&self, track_id: &Id) -> Result<Option<Lyrics>, TrackLyricsError> {
+        self.inner.track_lyrics(track_id).await
+    }
+
+    async fn playlists(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Playlist, PlaylistsError> {
+        let mut playlists = self.inner.playlists(offset, limit).await?;
+
+        self.cache_playlists(&playlists).await;
+
+        let cache = self.playlists.clone();
+        let fetch = playlists.fetch;
+
+        playlists.fetch = Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            let cache = cache.clone();
+            let fetch = fetch.clone();
+
+            Box::pin(async move {
+                let playlists = (fetch.lock().await)(offset, limit).await;
+
+                if let Ok(playlists) = &playlists {
+                    Self::cache_playlists_inner(&cache, playlists).await;
+                }
+
+                playlists
+            })
+        })));
+
+        Ok(playlists)
+    }
+
+    async fn playlist(&self, playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError> {
+        if let Some(playlist) = self.get_playlist_from_cache(playlist_id).await {
+            return Ok(playlist);
+        }
+
+        let playlists = self
+            .inner
+            .playlist(playlist_id)
+            .await?
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        if playlists.is_empty() {
+            self.cache_empty_playlists(&[playlist_id]).await;
+        } else {
+            self.cache_playlists(&playlists).await;
+        }
+
+        Ok(playlists.into_iter().next())
+    }
+
+    async fn playlist_tracks(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Track, TracksError> {
+        if let Some(tracks) = self
+            .get_playlist_tracks_from_cache(playlist_id, offset, limit)
+            .await
+        {
+            return Ok(tracks);
+        }
+
+        let mut tracks = self
+            .inner
+            .playlist_tracks(playlist_id, offset, limit)
+            .await?;
+
+        self.cache_tracks(&tracks).await;
+
+        let cache = self.tracks.clone();
+        let fetch = tracks.fetch;
+
+        tracks.fetch = Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            let cache = cache.clone();
+            let fetch = fetch.clone();
+
+            Box::pin(async move {
+                let tracks = (fetch.lock().await)(offset, limit).await;
+
+                if let Ok(tracks) = &tracks {
+                    Self::cache_tracks_inner(&cache, tracks).await;
+                }
+
+                tracks
+            })
+        })));
+
+        self.playlist_tracks
+            .write()
+            .await
+            .entry(playlist_id.clone())
+            .or_default()
+            .insert((offset, limit), tracks.clone());
+
+        Ok(tracks)
+    }
+
+    async fn add_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), AddPlaylistTrackError> {
+        self.remove_cache_playlist_ids(&[playlist_id]).await;
+        self.remove_cache_playlist_tracks_ids(&[playlist_id]).await;
+
+        self.inner.add_playlist_track(playlist_id, track_id).await
+    }
+
+    async fn remove_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), RemovePlaylistTrackError> {
+        self.remove_cache_playlist_ids(&[playlist_id]).await;
+        self.remove_cache_playlist_tracks_ids(&[playlist_id]).await;
+
+        self.inner
+            .remove_playlist_track(playlist_id, track_id)
+            .await
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<ApiSearchResultsResponse, SearchError> {
+        self.inner.search(query, offset, limit).await
+    }
 }
 
 #[cfg(test)]
@@ -828,17 +1110,24 @@ mod test {
         AlbumOrder, AlbumOrderDirection, AlbumsRequest, ArtistOrder, ArtistOrderDirection,
         TrackAudioQuality, TrackOrder, TrackOrderDirection, TrackSource,
     };
-    use moosicbox_paging::PagingResponse;
+    use moosicbox_paging::Page;
+    use moosicbox_search::api::models::ApiGlobalSearchResult;
     use pretty_assertions::assert_eq;
 
     use crate::*;
 
-    pub struct TestMusicApi {}
+    #[derive(Default)]
+    pub struct TestMusicApi {
+        pub source: ApiSource,
+        pub search_results: Vec<ApiGlobalSearchResult>,
+        pub fail_search: bool,
+        pub playlist_tracks: RwLock<Vec<Track>>,
+    }
 
     #[async_trait]
     impl MusicApi for TestMusicApi {
         fn source(&self) -> ApiSource {
-            ApiSource::Library
+            self.source
         }
 
         async fn artists(
@@ -951,11 +1240,77 @@ mod test {
         ) -> Result<Option<u64>, TrackError> {
             Ok(None)
         }
+
+        async fn playlists(
+            &self,
+            _offset: Option<u32>,
+            _limit: Option<u32>,
+        ) -> PagingResult<Playlist, PlaylistsError> {
+            Ok(PagingResponse::empty())
+        }
+
+        async fn playlist(&self, _playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError> {
+            Ok(None)
+        }
+
+        async fn playlist_tracks(
+            &self,
+            _playlist_id: &Id,
+            _offset: Option<u32>,
+            _limit: Option<u32>,
+        ) -> PagingResult<Track, TracksError> {
+            let items = self.playlist_tracks.read().await.clone();
+            let total = u32::try_from(items.len()).unwrap();
+
+            Ok(PagingResponse::new(
+                Page::WithTotal {
+                    items,
+                    offset: 0,
+                    limit: total,
+                    total,
+                },
+                |_offset, _limit| Box::pin(async move { Ok(PagingResponse::empty()) }),
+            ))
+        }
+
+        async fn add_playlist_track(
+            &self,
+            _playlist_id: &Id,
+            track_id: &Id,
+        ) -> Result<(), AddPlaylistTrackError> {
+            self.playlist_tracks.write().await.push(Track {
+                id: track_id.clone(),
+                ..Default::default()
+            });
+
+            Ok(())
+        }
+
+        async fn remove_playlist_track(
+            &self,
+            _playlist_id: &Id,
+            _track_id: &Id,
+        ) -> Result<(), RemovePlaylistTrackError> {
+            Ok(())
+        }
+
+        async fn search(
+            &self,
+            _query: &str,
+            _offset: Option<u32>,
+            _limit: Option<u32>,
+        ) -> Result<ApiSearchResultsResponse, SearchError> {
+            if self.fail_search {
+                return Err(SearchError::Other("Search failed".into()));
+            }
+
+            Ok(self.search_results.clone().into())
+        }
     }
 
     #[test_log::test(tokio::test)]
     async fn doesnt_cache_nothing_for_artists() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let one = api.artist(&1.into()).await.unwrap();
 
@@ -964,7 +1319,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn can_cache_single_artist_by_id() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let artist = Artist {
             id: 1.into(),
@@ -981,7 +1336,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn doesnt_return_artist_from_cache_if_doesnt_exist() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let artist = Artist {
             id: 1.into(),
@@ -998,7 +1353,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn can_cache_two_artists_by_id_and_recall_each() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let artist1 = Artist {
             id: 1.into(),
@@ -1023,7 +1378,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn doesnt_cache_nothing_for_albums() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let one = api.album(&1.into()).await.unwrap();
 
@@ -1032,7 +1387,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn can_cache_single_album_by_id() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let album = Album {
             id: 1.into(),
@@ -1049,7 +1404,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn doesnt_return_album_from_cache_if_doesnt_exist() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let album = Album {
             id: 1.into(),
@@ -1066,7 +1421,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn can_cache_two_albums_by_id_and_recall_each() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let album1 = Album {
             id: 1.into(),
@@ -1091,7 +1446,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn doesnt_cache_nothing_for_tracks() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let one = api.track(&1.into()).await.unwrap();
 
@@ -1100,7 +1455,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn can_cache_single_track_by_id() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let track = Track {
             id: 1.into(),
@@ -1117,7 +1472,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn doesnt_return_track_from_cache_if_doesnt_exist() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let track = Track {
             id: 1.into(),
@@ -1134,7 +1489,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn can_cache_two_tracks_by_id_and_recall_each() {
-        let api = CachedMusicApi::new(TestMusicApi {});
+        let api = CachedMusicApi::new(TestMusicApi::default());
 
         let track1 = Track {
             id: 1.into(),
@@ -1159,7 +1514,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn doesnt_cascade_delete_albums_from_artists_if_cascade_delete_disabled() {
-        let api = CachedMusicApi::new(TestMusicApi {}).with_cascade_delete(false);
+        let api = CachedMusicApi::new(TestMusicApi::default()).with_cascade_delete(false);
 
         let artist = Artist {
             id: 5.into(),
@@ -1188,7 +1543,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn doesnt_cascade_delete_albums_and_tracks_from_artists_if_cascade_delete_disabled() {
-        let api = CachedMusicApi::new(TestMusicApi {}).with_cascade_delete(false);
+        let api = CachedMusicApi::new(TestMusicApi::default()).with_cascade_delete(false);
 
         let artist = Artist {
             id: 5.into(),
@@ -1228,7 +1583,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn cascade_deletes_albums_from_artists_if_cascade_delete_enabled() {
-        let api = CachedMusicApi::new(TestMusicApi {}).with_cascade_delete(true);
+        let api = CachedMusicApi::new(TestMusicApi::default()).with_cascade_delete(true);
 
         let artist = Artist {
             id: 5.into(),
@@ -1257,7 +1612,7 @@ mod test {
 
     #[test_log::test(tokio::test)]
     async fn cascade_deletes_albums_and_tracks_from_artists_if_cascade_delete_enabled() {
-        let api = CachedMusicApi::new(TestMusicApi {}).with_cascade_delete(true);
+        let api = CachedMusicApi::new(TestMusicApi::default()).with_cascade_delete(true);
 
         let artist = Artist {
             id: 5.into(),
@@ -1294,4 +1649,80 @@ mod test {
         let track = api.track(&track.id).await.unwrap();
         assert_eq!(track, None);
     }
+
+    #[test_log::test(tokio::test)]
+    async fn doesnt_cache_nothing_for_playlists() {
+        let api = CachedMusicApi::new(TestMusicApi::default());
+
+        let one = api.playlist(&1.into()).await.unwrap();
+
+        assert_eq!(one, None);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn can_cache_single_playlist_by_id() {
+        let api = CachedMusicApi::new(TestMusicApi::default());
+
+        let playlist = Playlist {
+            id: 1.into(),
+            title: "bob".into(),
+            ..Default::default()
+        };
+
+        api.cache_playlists(&[playlist.clone()]).await;
+
+        let one = api.playlist(&playlist.id).await.unwrap();
+
+        assert_eq!(one, Some(playlist));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn removes_playlist_from_cache_when_adding_playlist_track() {
+        let api = CachedMusicApi::new(TestMusicApi::default());
+
+        let playlist = Playlist {
+            id: 1.into(),
+            title: "bob".into(),
+            ..Default::default()
+        };
+
+        api.cache_playlists(&[playlist.clone()]).await;
+
+        api.add_playlist_track(&playlist.id, &3.into())
+            .await
+            .unwrap();
+
+        let one = api.playlist(&playlist.id).await.unwrap();
+
+        assert_eq!(one, None);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn removes_playlist_tracks_pages_from_cache_when_adding_playlist_track() {
+        let api = CachedMusicApi::new(TestMusicApi::default());
+        let playlist_id = Id::Number(1);
+
+        api.add_playlist_track(&playlist_id, &3.into())
+            .await
+            .unwrap();
+        let tracks = api.playlist_tracks(&playlist_id, None, None).await.unwrap();
+        assert_eq!(tracks.items().len(), 1);
+
+        api.inner.playlist_tracks.write().await.clear();
+        let tracks = api.playlist_tracks(&playlist_id, None, None).await.unwrap();
+        assert_eq!(tracks.items().len(), 1);
+
+        api.add_playlist_track(&playlist_id, &4.into())
+            .await
+            .unwrap();
+        let tracks = api.playlist_tracks(&playlist_id, None, None).await.unwrap();
+        assert_eq!(
+            tracks
+                .items()
+                .iter()
+                .map(|x| x.id.clone())
+                .collect::<Vec<_>>(),
+            vec![Id::Number(4)]
+        );
+    }
 }
@@ -0,0 +1,309 @@
+//! Federated search across every `MusicApi` registered in a `MusicApis`.
+
+use std::{collections::HashMap, time::Duration};
+
+use moosicbox_music_models::{id::Id, ApiSource};
+use moosicbox_search::{api::models::ApiGlobalSearchResult, folded_terms};
+
+use crate::MusicApis;
+
+/// How long each source gets to respond before its results are dropped
+pub const DEFAULT_SOURCE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A source that returned an artist, album or track, and its id there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FederatedSearchSource {
+    pub source: ApiSource,
+    pub id: Id,
+}
+
+#[derive(Debug, Clone)]
+pub struct FederatedSearchResult {
+    pub source: ApiSource,
+    /// The other sources that returned the same artist, album or track, with
+    /// their ids for it
+    pub other_sources: Vec<FederatedSearchSource>,
+    pub result: ApiGlobalSearchResult,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FederatedSearchResults {
+    pub results: Vec<FederatedSearchResult>,
+    /// The sources that failed or timed out
+    pub failed_sources: Vec<ApiSource>,
+}
+
+/// Searches every `MusicApi` concurrently, giving each source `timeout` to
+/// respond, and merges the results. The results are interleaved by rank so
+/// that the top hits of every source come first, and hits for the same
+/// artist, album or track are only included once, labeled with the first
+/// source that returned them and the ids of the other sources.
+pub async fn federated_search<S: ::std::hash::BuildHasher + Clone>(
+    music_apis: &MusicApis<S>,
+    query: &str,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    timeout: Duration,
+) -> FederatedSearchResults {
+    let searches = music_apis.iter().map(|(source, api)| async move {
+        let response = tokio::time::timeout(timeout, api.search(query, offset, limit)).await;
+        (*source, response)
+    });
+
+    let mut responses = vec![];
+    let mut failed_sources = vec![];
+
+    for (source, response) in futures::future::join_all(searches).await {
+        match response {
+            Ok(Ok(response)) => responses.push((source, response.results)),
+            Ok(Err(e)) => {
+                log::warn!("Failed to search {source}: {e:?}");
+                failed_sources.push(source);
+            }
+            Err(_) => {
+                log::warn!("Search of {source} timed out after {timeout:?}");
+                failed_sources.push(source);
+            }
+        }
+    }
+
+    responses.sort_by_key(|(source, _)| *source);
+    failed_sources.sort();
+
+    FederatedSearchResults {
+        results: merge_results(responses),
+        failed_sources,
+    }
+}
+
+fn merge_results(
+    responses: Vec<(ApiSource, Vec<ApiGlobalSearchResult>)>,
+) -> Vec<FederatedSearchResult> {
+    let mut sources = responses
+        .into_iter()
+        .map(|(source, results)| (source, results.into_iter()))
+        .collect::<Vec<_>>();
+
+    let mut results: Vec<FederatedSearchResult> = vec![];
+    let mut indices = HashMap::new();
+
+    loop {
+        let mut exhausted = true;
+
+        for (source, source_results) in &mut sources {
+            let Some(result) = source_results.next() else {
+                continue;
+            };
+            exhausted = false;
+
+            let key = identity_key(&result);
+
+            if let Some(index) = indices.get(&key) {
+                let existing = &mut results[*index];
+
+                if existing.source != *source
+                    && !existing.other_sources.iter().any(|x| x.source == *source)
+                {
+                    existing.other_sources.push(FederatedSearchSource {
+                        source: *source,
+                        id: result_id(&result).clone(),
+                    });
+                }
+            } else {
+                indices.insert(key, results.len());
+                results.push(FederatedSearchResult {
+                    source: *source,
+                    other_sources: vec![],
+                    result,
+                });
+            }
+        }
+
+        if exhausted {
+            break;
+        }
+    }
+
+    results
+}
+
+const fn result_id(result: &ApiGlobalSearchResult) -> &Id {
+    match result {
+        ApiGlobalSearchResult::Artist(artist) => &artist.artist_id,
+        ApiGlobalSearchResult::Album(album) => &album.album_id,
+        ApiGlobalSearchResult::Track(track) => &track.track_id,
+    }
+}
+
+fn identity(value: &str) -> String {
+    folded_terms(value).join(" ")
+}
+
+/// The key identifying the same artist, album or track across sources,
+/// ignoring case, accents and punctuation
+fn identity_key(result: &ApiGlobalSearchResult) -> String {
+    match result {
+        ApiGlobalSearchResult::Artist(artist) => format!("artist|{}", identity(&artist.title)),
+        ApiGlobalSearchResult::Album(album) => format!(
+            "album|{}|{}",
+            identity(&album.title),
+            identity(&album.artist)
+        ),
+        ApiGlobalSearchResult::Track(track) => format!(
+            "track|{}|{}|{}",
+            identity(&track.title),
+            identity(&track.album),
+            identity(&track.artist)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use moosicbox_search::api::models::{ApiGlobalAlbumSearchResult, ApiGlobalArtistSearchResult};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{test::TestMusicApi, MusicApi};
+
+    fn artist(id: u64, title: &str) -> ApiGlobalSearchResult {
+        ApiGlobalSearchResult::Artist(ApiGlobalArtistSearchResult {
+            artist_id: id.into(),
+            title: title.into(),
+            contains_cover: false,
+            blur: false,
+        })
+    }
+
+    fn album(id: u64, title: &str, artist: &str) -> ApiGlobalSearchResult {
+        ApiGlobalSearchResult::Album(ApiGlobalAlbumSearchResult {
+            artist_id: 1.into(),
+            artist: artist.into(),
+            album_id: id.into(),
+            title: title.into(),
+            contains_cover: false,
+            blur: false,
+            date_released: None,
+            date_added: None,
+            versions: vec![],
+        })
+    }
+
+    type Summary = (ApiSource, Vec<(ApiSource, Id)>, String);
+
+    fn summary(results: &[FederatedSearchResult]) -> Vec<Summary> {
+        results
+            .iter()
+            .map(|x| {
+                (
+                    x.source,
+                    x.other_sources
+                        .iter()
+                        .map(|other| (other.source, other.id.clone()))
+                        .collect(),
+                    identity_key(&x.result),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merge_results_interleaves_sources_by_rank() {
+        let results = merge_results(vec![
+            (
+                ApiSource::Library,
+                vec![artist(1, "Radiohead"), album(2, "Kid A", "Radiohead")],
+            ),
+            (ApiSource::Tidal, vec![artist(3, "Portishead")]),
+        ]);
+
+        assert_eq!(
+            summary(&results),
+            vec![
+                (ApiSource::Library, vec![], "artist|radiohead".into()),
+                (ApiSource::Tidal, vec![], "artist|portishead".into()),
+                (ApiSource::Library, vec![], "album|kid a|radiohead".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_results_deduplicates_by_identity() {
+        let results = merge_results(vec![
+            (
+                ApiSource::Library,
+                vec![artist(1, "Björk"), album(2, "Homogenic", "Björk")],
+            ),
+            (
+                ApiSource::Tidal,
+                vec![album(3, "HOMOGENIC", "Bjork"), artist(4, "bjork")],
+            ),
+            (ApiSource::Qobuz, vec![artist(5, "Björk!")]),
+        ]);
+
+        assert_eq!(
+            summary(&results),
+            vec![
+                (
+                    ApiSource::Library,
+                    vec![(ApiSource::Qobuz, 5.into()), (ApiSource::Tidal, 4.into())],
+                    "artist|bjork".into()
+                ),
+                (
+                    ApiSource::Tidal,
+                    vec![(ApiSource::Library, 2.into())],
+                    "album|homogenic|bjork".into()
+                ),
+            ]
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn federated_search_reports_failing_sources() {
+        let apis: HashMap<ApiSource, Arc<Box<dyn MusicApi>>> = HashMap::from([
+            (
+                ApiSource::Library,
+                Arc::new(Box::new(TestMusicApi {
+                    source: ApiSource::Library,
+                    search_results: vec![artist(1, "Björk")],
+                    ..Default::default()
+                }) as Box<dyn MusicApi>),
+            ),
+            (
+                ApiSource::Tidal,
+                Arc::new(Box::new(TestMusicApi {
+                    source: ApiSource::Tidal,
+                    search_results: vec![artist(2, "bjork"), album(3, "Homogenic", "Björk")],
+                    ..Default::default()
+                }) as Box<dyn MusicApi>),
+            ),
+            (
+                ApiSource::Qobuz,
+                Arc::new(Box::new(TestMusicApi {
+                    source: ApiSource::Qobuz,
+                    fail_search: true,
+                    ..Default::default()
+                }) as Box<dyn MusicApi>),
+            ),
+        ]);
+        let music_apis: MusicApis = Arc::new(apis).into();
+
+        let results =
+            federated_search(&music_apis, "bjork", None, None, DEFAULT_SOURCE_TIMEOUT).await;
+
+        assert_eq!(results.failed_sources, vec![ApiSource::Qobuz]);
+        assert_eq!(
+            summary(&results.results),
+            vec![
+                (
+                    ApiSource::Library,
+                    vec![(ApiSource::Tidal, 2.into())],
+                    "artist|bjork".into()
+                ),
+                (ApiSource::Tidal, vec![], "album|homogenic|bjork".into()),
+            ]
+        );
+    }
+}
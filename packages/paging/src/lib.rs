@@ -7,7 +7,7 @@ use futures::Future;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Page<T> {
     WithTotal {
         items: Vec<T>,
@@ -275,6 +275,15 @@ pub struct PagingResponse<T, E> {
     pub fetch: Arc<Mutex<FetchPagingResponse<T, E>>>,
 }
 
+impl<T: Clone, E> Clone for PagingResponse<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            page: self.page.clone(),
+            fetch: self.fetch.clone(),
+        }
+    }
+}
+
 impl<T: std::fmt::Debug, E> std::fmt::Debug for PagingResponse<T, E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("PagingResponse")
@@ -1,23 +1,29 @@
 #![allow(clippy::future_not_send)]
 
 use actix_web::{
+    delete,
     dev::{ServiceFactory, ServiceRequest},
     error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound},
     get, post,
     web::{self, Json},
     Result, Scope,
 };
-use moosicbox_database::profiles::api::ProfileName;
+use moosicbox_database::{
+    config::ConfigDatabase,
+    profiles::{api::ProfileName, LibraryDatabase},
+};
 use moosicbox_music_api::{MusicApi, MusicApis, SourceToMusicApi as _};
 use moosicbox_music_models::{
     id::{parse_integer_ranges_to_ids, Id, IdType, ParseIntegersError},
     ApiSource, AudioFormat, PlaybackQuality, Track,
 };
-use moosicbox_session::models::PlaybackTarget;
+use moosicbox_session::models::{PlaybackTarget, RepeatMode, ReplayGainMode};
 use serde::Deserialize;
 
 use crate::{
-    ApiPlaybackStatus, PlaybackHandler, PlaybackStatus, PlayerError, DEFAULT_PLAYBACK_RETRY_OPTIONS,
+    dsp::{DspPreset, DspSettings, DspTarget, SetDspPreset},
+    ApiPlaybackStatus, PlaybackHandler, PlaybackStatus, PlayerError,
+    DEFAULT_PLAYBACK_RETRY_OPTIONS,
 };
 
 pub fn bind_services<
@@ -29,6 +35,7 @@ pub fn bind_services<
         .service(play_track_endpoint)
         .service(play_tracks_endpoint)
         .service(play_album_endpoint)
+        .service(play_playlist_endpoint)
         .service(pause_playback_endpoint)
         .service(resume_playback_endpoint)
         .service(update_playback_endpoint)
@@ -37,6 +44,12 @@ pub fn bind_services<
         .service(stop_track_endpoint)
         .service(seek_track_endpoint)
         .service(player_status_endpoint)
+        .service(dsp_presets_endpoint)
+        .service(set_dsp_preset_endpoint)
+        .service(delete_dsp_preset_endpoint)
+        .service(dsp_endpoint)
+        .service(set_dsp_endpoint)
+        .service(clear_dsp_endpoint)
 }
 
 #[cfg(feature = "openapi")]
@@ -45,6 +58,7 @@ pub fn bind_services<
     tags((name = "Library")),
     paths(
         play_album_endpoint,
+        play_playlist_endpoint,
         play_track_endpoint,
         play_tracks_endpoint,
         stop_track_endpoint,
@@ -55,11 +69,26 @@ pub fn bind_services<
         resume_playback_endpoint,
         previous_track_endpoint,
         player_status_endpoint,
+        dsp_presets_endpoint,
+        set_dsp_preset_endpoint,
+        delete_dsp_preset_endpoint,
+        dsp_endpoint,
+        set_dsp_endpoint,
+        clear_dsp_endpoint,
     ),
     components(schemas(
         crate::ApiPlayback,
         ApiPlaybackStatus,
         PlaybackStatus,
+        RepeatMode,
+        DspPreset,
+        DspSettings,
+        DspTarget,
+        SetDspPreset,
+        crate::dsp::EqBand,
+        crate::dsp::EqFilterType,
+        crate::dsp::LimiterSettings,
+        ReplayGainMode,
     ))
 )]
 pub struct Api;
@@ -84,6 +113,9 @@ impl From<PlayerError> for actix_web::Error {
             PlayerError::AlbumFetchFailed(album_id) => {
                 ErrorInternalServerError(format!("Failed to fetch album: {album_id}"))
             }
+            PlayerError::PlaylistFetchFailed(playlist_id) => {
+                ErrorInternalServerError(format!("Failed to fetch playlist: {playlist_id}"))
+            }
             PlayerError::NoPlayersPlaying => ErrorBadRequest(err),
             PlayerError::PositionOutOfBounds(position) => {
                 ErrorBadRequest(format!("Position out of bounds: {position}"))
@@ -298,9 +330,73 @@ pub async fn play_album_endpoint(
             query.position,
             query.seek,
             query.volume,
-            PlaybackQuality {
-                format: query.format.unwrap_or_default(),
-            },
+            query.format.unwrap_or_default().into(),
+            query
+                .audio_zone_id
+                .map(|audio_zone_id| PlaybackTarget::AudioZone { audio_zone_id }),
+            Some(DEFAULT_PLAYBACK_RETRY_OPTIONS),
+        )
+        .await?;
+
+    Ok(Json(PlaybackStatus { success: true }))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayPlaylistQuery {
+    pub session_id: u64,
+    pub playlist_id: u64,
+    pub position: Option<u16>,
+    pub seek: Option<f64>,
+    pub volume: Option<f64>,
+    pub host: Option<String>,
+    pub format: Option<AudioFormat>,
+    pub audio_zone_id: Option<u64>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Player"],
+        post,
+        path = "/play/playlist",
+        description = "Play the given saved playlist for the specified host or local player",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("sessionId" = u64, Query, description = "Session ID to play the playlist on"),
+            ("playlistId" = u64, Query, description = "Playlist ID to play"),
+            ("position" = Option<u16>, Query, description = "Position in the playlist to play from"),
+            ("seek" = Option<f64>, Query, description = "Seek position to begin playback from"),
+            ("volume" = Option<f64>, Query, description = "Volume level to play at"),
+            ("host" = Option<String>, Query, description = "Remote host to fetch track audio from"),
+            ("format" = Option<AudioFormat>, Query, description = "Audio format to play the tracks in"),
+            ("audioZoneId" = Option<u64>, Query, description = "Audio zone ID to play from"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Success message",
+                body = Value,
+            )
+        )
+    )
+)]
+#[post("/play/playlist")]
+pub async fn play_playlist_endpoint(
+    query: web::Query<PlayPlaylistQuery>,
+    profile: ProfileName,
+    db: LibraryDatabase,
+) -> Result<Json<PlaybackStatus>> {
+    get_player(query.host.as_deref())
+        .await?
+        .play_playlist(
+            &db,
+            query.session_id,
+            profile.into(),
+            query.playlist_id,
+            query.position,
+            query.seek,
+            query.volume,
+            query.format.unwrap_or_default().into(),
             query
                 .audio_zone_id
                 .map(|audio_zone_id| PlaybackTarget::AudioZone { audio_zone_id }),
@@ -375,9 +471,7 @@ pub async fn play_track_endpoint(
             track_id,
             query.seek,
             query.volume,
-            PlaybackQuality {
-                format: query.format.unwrap_or_default(),
-            },
+            query.format.unwrap_or_default().into(),
             query
                 .audio_zone_id
                 .map(|audio_zone_id| PlaybackTarget::AudioZone { audio_zone_id }),
@@ -452,9 +546,7 @@ pub async fn play_tracks_endpoint(
             query.position,
             query.seek,
             query.volume,
-            PlaybackQuality {
-                format: query.format.unwrap_or_default(),
-            },
+            query.format.unwrap_or_default().into(),
             query
                 .audio_zone_id
                 .map(|audio_zone_id| PlaybackTarget::AudioZone { audio_zone_id }),
@@ -552,6 +644,10 @@ pub struct UpdatePlaybackQuery {
     pub host: Option<String>,
     pub track_ids: Option<String>,
     pub format: Option<AudioFormat>,
+    pub shuffle: Option<bool>,
+    pub repeat_mode: Option<RepeatMode>,
+    pub crossfade: Option<f64>,
+    pub replay_gain_mode: Option<ReplayGainMode>,
     pub session_id: Option<u64>,
     pub audio_zone_id: Option<u64>,
     pub source: Option<ApiSource>,
@@ -574,6 +670,10 @@ pub struct UpdatePlaybackQuery {
             ("host" = Option<String>, Query, description = "Remote host to fetch track audio from"),
             ("trackIds" = String, Query, description = "Comma-separated list of track IDs to update the playback with"),
             ("format" = Option<AudioFormat>, Query, description = "Update the 'format' status on the playback"),
+            ("shuffle" = Option<bool>, Query, description = "Update the 'shuffle' status on the playback"),
+            ("repeatMode" = Option<RepeatMode>, Query, description = "Update the 'repeatMode' status on the playback"),
+            ("crossfade" = Option<f64>, Query, description = "Update the crossfade duration, in seconds, between the playback tracks"),
+            ("replayGainMode" = Option<ReplayGainMode>, Query, description = "Update the ReplayGain mode used to normalize the loudness of the playback tracks"),
             ("sessionId" = Option<u64>, Query, description = "Session ID to update the playback for"),
             ("audioZoneId" = Option<u64>, Query, description = "Audio zone ID to update the playback for"),
             ("source" = Option<ApiSource>, Query, description = "Update the 'source' status on the playback"),
@@ -618,7 +718,11 @@ pub async fn update_playback_endpoint(
             query.seek,
             query.volume,
             track_ids,
-            query.format.map(|format| PlaybackQuality { format }),
+            query.format.map(PlaybackQuality::from),
+            query.shuffle,
+            query.repeat_mode,
+            query.crossfade,
+            query.replay_gain_mode,
             query.session_id,
             Some(profile.into()),
             query
@@ -820,3 +924,235 @@ pub async fn player_status_endpoint(
             .player_status()?,
     ))
 }
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DspPresetsQuery {
+    pub audio_zone_id: Option<u64>,
+    pub player_id: Option<u64>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Player"],
+        get,
+        path = "/dsp-presets",
+        description = "Get the DSP presets, optionally only those of an audio zone or player",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("audioZoneId" = Option<u64>, Query, description = "Audio zone to get the DSP presets of"),
+            ("playerId" = Option<u64>, Query, description = "Player to get the DSP presets of"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The DSP presets",
+                body = Vec<DspPreset>,
+            )
+        )
+    )
+)]
+#[get("/dsp-presets")]
+pub async fn dsp_presets_endpoint(
+    query: web::Query<DspPresetsQuery>,
+    db: ConfigDatabase,
+) -> Result<Json<Vec<DspPreset>>> {
+    let target = match (query.audio_zone_id, query.player_id) {
+        (Some(_), Some(_)) => {
+            return Err(ErrorBadRequest(
+                "Only one of audioZoneId and playerId can be specified",
+            ))
+        }
+        (Some(audio_zone_id), None) => Some(DspTarget::AudioZone { audio_zone_id }),
+        (None, Some(player_id)) => Some(DspTarget::Player { player_id }),
+        (None, None) => None,
+    };
+
+    Ok(Json(
+        crate::dsp::db::get_dsp_presets(&db, target)
+            .await
+            .map_err(ErrorInternalServerError)?,
+    ))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Player"],
+        post,
+        path = "/dsp-presets",
+        description = "Create or update the DSP preset with the given target and name, applying it if it is active",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+        ),
+        request_body = SetDspPreset,
+        responses(
+            (
+                status = 200,
+                description = "The DSP preset that was saved",
+                body = DspPreset,
+            )
+        )
+    )
+)]
+#[post("/dsp-presets")]
+pub async fn set_dsp_preset_endpoint(
+    preset: Json<SetDspPreset>,
+    db: ConfigDatabase,
+) -> Result<Json<DspPreset>> {
+    preset
+        .settings
+        .validate(crate::dsp::PRESET_SAMPLE_RATE)
+        .map_err(ErrorBadRequest)?;
+
+    let preset = crate::dsp::db::upsert_dsp_preset(&db, &preset)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    crate::dsp::apply_dsp_presets(&db)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(Json(preset))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteDspPresetQuery {
+    pub id: u64,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Player"],
+        delete,
+        path = "/dsp-presets",
+        description = "Delete a DSP preset",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("id" = u64, Query, description = "ID of the DSP preset to delete"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The DSP preset that was deleted",
+                body = DspPreset,
+            )
+        )
+    )
+)]
+#[delete("/dsp-presets")]
+pub async fn delete_dsp_preset_endpoint(
+    query: web::Query<DeleteDspPresetQuery>,
+    db: ConfigDatabase,
+) -> Result<Json<DspPreset>> {
+    let preset = crate::dsp::db::delete_dsp_preset(&db, query.id)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound("DSP preset not found"))?;
+
+    if preset.active {
+        crate::dsp::apply_dsp_presets(&db)
+            .await
+            .map_err(ErrorInternalServerError)?;
+    }
+
+    Ok(Json(preset))
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DspQuery {
+    pub audio_output_id: String,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Player"],
+        get,
+        path = "/dsp",
+        description = "Get the DSP settings currently applied to an audio output",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("audioOutputId" = String, Query, description = "Audio output to get the DSP settings of"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The DSP settings, or null if the audio output has none",
+                body = Option<DspSettings>,
+            )
+        )
+    )
+)]
+#[get("/dsp")]
+#[allow(clippy::unused_async)]
+pub async fn dsp_endpoint(query: web::Query<DspQuery>) -> Result<Json<Option<DspSettings>>> {
+    Ok(Json(crate::dsp::output_dsp(&query.audio_output_id)))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Player"],
+        post,
+        path = "/dsp",
+        description = "Adjust the DSP settings of an audio output live, without saving them to a preset. They apply to the current playback without restarting it, and take precedence over the audio output's active preset until they are cleared.",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("audioOutputId" = String, Query, description = "Audio output to set the DSP settings of"),
+        ),
+        request_body = DspSettings,
+        responses(
+            (
+                status = 200,
+                description = "Success message",
+                body = PlaybackStatus,
+            )
+        )
+    )
+)]
+#[post("/dsp")]
+pub async fn set_dsp_endpoint(
+    query: web::Query<DspQuery>,
+    settings: Json<DspSettings>,
+) -> Result<Json<PlaybackStatus>> {
+    let output = moosicbox_audio_output::output_factories()
+        .await
+        .into_iter()
+        .find(|x| x.id == query.audio_output_id)
+        .ok_or_else(|| ErrorNotFound("Audio output not found"))?;
+
+    settings
+        .validate(output.spec.rate)
+        .map_err(ErrorBadRequest)?;
+
+    crate::dsp::set_output_dsp(&query.audio_output_id, Some(settings.into_inner()));
+
+    Ok(Json(PlaybackStatus { success: true }))
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Player"],
+        delete,
+        path = "/dsp",
+        description = "Clear the DSP settings adjusted live on an audio output, going back to the audio output's active preset",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("audioOutputId" = String, Query, description = "Audio output to clear the DSP settings of"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "Success message",
+                body = PlaybackStatus,
+            )
+        )
+    )
+)]
+#[delete("/dsp")]
+#[allow(clippy::unused_async)]
+pub async fn clear_dsp_endpoint(query: web::Query<DspQuery>) -> Result<Json<PlaybackStatus>> {
+    crate::dsp::set_output_dsp(&query.audio_output_id, None);
+
+    Ok(Json(PlaybackStatus { success: true }))
+}
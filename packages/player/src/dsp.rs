@@ -0,0 +1,930 @@
+//! Digital signal processing applied to the decoded samples before they
+//! reach the audio output: a preamp, a multi-band parametric equalizer and a
+//! peak limiter.
+//!
+//! The settings are kept per audio output, and the [`DspProcessor`] of a
+//! playing track picks up changes to them on its next buffer, so they are
+//! adjustable without restarting playback. Settings adjusted live take
+//! precedence over the active presets until they are cleared.
+
+#![allow(clippy::module_name_repetitions)]
+
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock, RwLock,
+    },
+};
+
+use moosicbox_database::config::ConfigDatabase;
+use moosicbox_json_utils::database::DatabaseFetchError;
+use serde::{Deserialize, Serialize};
+use symphonia::core::audio::{AudioBuffer, Signal as _};
+use thiserror::Error;
+
+pub mod db;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum EqFilterType {
+    Peaking,
+    LowShelf,
+    HighShelf,
+    LowPass,
+    HighPass,
+}
+
+const fn default_q() -> f64 {
+    std::f64::consts::FRAC_1_SQRT_2
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EqBand {
+    pub filter_type: EqFilterType,
+    /// The center, corner or cutoff frequency, in Hz
+    pub frequency: f64,
+    /// The gain, in dB. Ignored by the low-pass and high-pass filters.
+    #[serde(default)]
+    pub gain: f64,
+    #[serde(default = "default_q")]
+    pub q: f64,
+}
+
+const fn default_release() -> f64 {
+    100.0
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LimiterSettings {
+    /// The level the output is kept under, in dBFS
+    #[serde(default)]
+    pub threshold: f64,
+    /// How long the gain takes to recover after a peak, in milliseconds
+    #[serde(default = "default_release")]
+    pub release: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DspSettings {
+    /// The gain applied before the equalizer, in dB
+    #[serde(default)]
+    pub preamp: f64,
+    #[serde(default)]
+    pub bands: Vec<EqBand>,
+    #[serde(default)]
+    pub limiter: Option<LimiterSettings>,
+}
+
+impl DspSettings {
+    /// Whether the settings leave the samples untouched
+    #[must_use]
+    pub fn is_bypassed(&self) -> bool {
+        self.preamp.abs() < f64::EPSILON
+            && self.limiter.is_none()
+            && self
+                .bands
+                .iter()
+                .all(|band| band.gain.abs() < f64::EPSILON && !band.filter_type.ignores_gain())
+    }
+}
+
+impl EqFilterType {
+    const fn ignores_gain(self) -> bool {
+        matches!(self, Self::LowPass | Self::HighPass)
+    }
+}
+
+/// The most a band or the preamp can boost or cut, in dB
+pub const MAX_GAIN: f64 = 24.0;
+
+/// The sample rate presets are validated against, as the audio outputs they
+/// end up applied to aren't known up front. Band frequencies have to be
+/// under its Nyquist frequency.
+pub const PRESET_SAMPLE_RATE: u32 = 44_100;
+
+#[derive(Debug, Error)]
+pub enum InvalidEqBand {
+    #[error("Gain must be between -{MAX_GAIN}dB and {MAX_GAIN}dB, got {0}")]
+    Gain(f64),
+    #[error("Q must be greater than 0, got {0}")]
+    Q(f64),
+    #[error(
+        "Frequency must be between 0Hz and the Nyquist frequency {nyquist}Hz, got {frequency}"
+    )]
+    Frequency { frequency: f64, nyquist: f64 },
+}
+
+#[derive(Debug, Error)]
+pub enum InvalidDspSettings {
+    #[error("Preamp must be between -{MAX_GAIN}dB and {MAX_GAIN}dB, got {0}")]
+    Preamp(f64),
+    #[error("Band {index}: {error}")]
+    Band { index: usize, error: InvalidEqBand },
+    #[error("Limiter threshold must be at most 0dBFS, got {0}")]
+    LimiterThreshold(f64),
+    #[error("Limiter release must be greater than 0ms, got {0}")]
+    LimiterRelease(f64),
+}
+
+fn is_valid_gain(gain: f64) -> bool {
+    gain.is_finite() && gain.abs() <= MAX_GAIN
+}
+
+impl EqBand {
+    /// # Errors
+    ///
+    /// * If the band can't be applied to samples at the given `sample_rate`
+    pub fn validate(&self, sample_rate: u32) -> Result<(), InvalidEqBand> {
+        let nyquist = f64::from(sample_rate) / 2.0;
+
+        if !is_valid_gain(self.gain) {
+            return Err(InvalidEqBand::Gain(self.gain));
+        }
+        if !(self.q.is_finite() && self.q > 0.0) {
+            return Err(InvalidEqBand::Q(self.q));
+        }
+        if !(self.frequency.is_finite() && self.frequency > 0.0 && self.frequency < nyquist) {
+            return Err(InvalidEqBand::Frequency {
+                frequency: self.frequency,
+                nyquist,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl LimiterSettings {
+    /// # Errors
+    ///
+    /// * If the threshold is above 0dBFS or the release isn't positive
+    pub fn validate(&self) -> Result<(), InvalidDspSettings> {
+        if !(self.threshold.is_finite() && self.threshold <= 0.0) {
+            return Err(InvalidDspSettings::LimiterThreshold(self.threshold));
+        }
+        if !(self.release.is_finite() && self.release > 0.0) {
+            return Err(InvalidDspSettings::LimiterRelease(self.release));
+        }
+
+        Ok(())
+    }
+}
+
+impl DspSettings {
+    /// # Errors
+    ///
+    /// * If any of the settings can't be applied to samples at the given
+    ///   `sample_rate`
+    pub fn validate(&self, sample_rate: u32) -> Result<(), InvalidDspSettings> {
+        if !is_valid_gain(self.preamp) {
+            return Err(InvalidDspSettings::Preamp(self.preamp));
+        }
+
+        for (index, band) in self.bands.iter().enumerate() {
+            band.validate(sample_rate)
+                .map_err(|error| InvalidDspSettings::Band { index, error })?;
+        }
+
+        if let Some(limiter) = &self.limiter {
+            limiter.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What a DSP preset applies to. A player's own active preset takes
+/// precedence over the active preset of its audio zone.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(tag = "type")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum DspTarget {
+    #[serde(rename_all = "camelCase")]
+    AudioZone { audio_zone_id: u64 },
+    #[serde(rename_all = "camelCase")]
+    Player { player_id: u64 },
+}
+
+impl DspTarget {
+    #[must_use]
+    pub const fn id(&self) -> u64 {
+        match self {
+            Self::AudioZone { audio_zone_id } => *audio_zone_id,
+            Self::Player { player_id } => *player_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DspPreset {
+    pub id: u64,
+    pub name: String,
+    pub target: DspTarget,
+    /// Whether this is the preset applied to the target
+    pub active: bool,
+    pub settings: DspSettings,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetDspPreset {
+    pub name: String,
+    pub target: DspTarget,
+    #[serde(default)]
+    pub active: bool,
+    pub settings: DspSettings,
+}
+
+#[derive(Default)]
+struct OutputDsp {
+    /// The settings of the active presets, by audio output id
+    presets: HashMap<String, Arc<DspSettings>>,
+    /// The settings adjusted live, by audio output id. They take precedence
+    /// over the `presets` until they are cleared.
+    live: HashMap<String, Arc<DspSettings>>,
+}
+
+impl OutputDsp {
+    fn get(&self, audio_output_id: &str) -> Option<&Arc<DspSettings>> {
+        self.live
+            .get(audio_output_id)
+            .or_else(|| self.presets.get(audio_output_id))
+            .filter(|x| !x.is_bypassed())
+    }
+}
+
+static OUTPUT_DSP: LazyLock<RwLock<OutputDsp>> =
+    LazyLock::new(|| RwLock::new(OutputDsp::default()));
+
+/// Bumped on every change to `OUTPUT_DSP`, so processors only look their
+/// settings up again when something changed.
+static DSP_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// The DSP settings applied to the audio output with the given id
+///
+/// # Panics
+///
+/// * If the `OUTPUT_DSP` `RwLock` is poisoned
+#[must_use]
+pub fn output_dsp(audio_output_id: &str) -> Option<DspSettings> {
+    OUTPUT_DSP
+        .read()
+        .unwrap()
+        .get(audio_output_id)
+        .map(|x| x.as_ref().clone())
+}
+
+/// Adjusts the DSP settings applied to the audio output with the given id
+/// live, taking effect on the next buffer of anything playing to it. The
+/// settings are kept over the output's active preset until they are cleared
+/// by passing `None`.
+///
+/// # Panics
+///
+/// * If the `OUTPUT_DSP` `RwLock` is poisoned
+pub fn set_output_dsp(audio_output_id: &str, settings: Option<DspSettings>) {
+    let mut binding = OUTPUT_DSP.write().unwrap();
+
+    match settings {
+        Some(settings) => {
+            binding
+                .live
+                .insert(audio_output_id.to_string(), Arc::new(settings));
+        }
+        None => {
+            binding.live.remove(audio_output_id);
+        }
+    }
+
+    drop(binding);
+    DSP_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Resolves the active DSP preset of every player, by its own active preset
+/// or else its audio zone's, and applies them to the players' audio outputs.
+///
+/// # Errors
+///
+/// * If there was a database error
+///
+/// # Panics
+///
+/// * If the `OUTPUT_DSP` `RwLock` is poisoned
+pub async fn apply_dsp_presets(db: &ConfigDatabase) -> Result<(), DatabaseFetchError> {
+    let presets = db::get_active_dsp_presets(db).await?;
+    let players = db::get_player_audio_outputs(db).await?;
+    let zone_players = db::get_audio_zone_players(db).await?;
+
+    let mut outputs = HashMap::new();
+
+    for preset in &presets {
+        if let DspTarget::AudioZone { audio_zone_id } = preset.target {
+            for (_, player_id) in zone_players.iter().filter(|(x, _)| *x == audio_zone_id) {
+                if let Some((_, audio_output_id)) = players.iter().find(|(x, _)| x == player_id) {
+                    outputs.insert(audio_output_id.clone(), preset.settings.clone());
+                }
+            }
+        }
+    }
+    for preset in &presets {
+        if let DspTarget::Player { player_id } = preset.target {
+            if let Some((_, audio_output_id)) = players.iter().find(|(x, _)| *x == player_id) {
+                outputs.insert(audio_output_id.clone(), preset.settings.clone());
+            }
+        }
+    }
+
+    log::debug!(
+        "apply_dsp_presets: Applying DSP to {} outputs",
+        outputs.len()
+    );
+
+    OUTPUT_DSP.write().unwrap().presets = outputs
+        .into_iter()
+        .map(|(audio_output_id, settings)| (audio_output_id, Arc::new(settings)))
+        .collect();
+    DSP_GENERATION.fetch_add(1, Ordering::SeqCst);
+
+    Ok(())
+}
+
+/// Normalized biquad coefficients, from the Audio EQ Cookbook
+#[derive(Debug, Clone, Copy)]
+struct Coefficients {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+}
+
+impl Coefficients {
+    /// `None` if the band can't be applied at the `sample_rate`, e.g. its
+    /// frequency is above the Nyquist frequency of a low sample rate track
+    fn new(band: &EqBand, sample_rate: u32) -> Option<Self> {
+        if let Err(e) = band.validate(sample_rate) {
+            log::debug!("Skipping EQ band {band:?} at {sample_rate}Hz: {e}");
+            return None;
+        }
+
+        let sample_rate = f64::from(sample_rate);
+
+        let w0 = 2.0 * PI * band.frequency / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * band.q);
+        let a = 10_f64.powf(band.gain / 40.0);
+
+        let (b0, b1, b2, a0, a1, a2) = match band.filter_type {
+            EqFilterType::Peaking => (
+                alpha.mul_add(a, 1.0),
+                -2.0 * cos,
+                alpha.mul_add(-a, 1.0),
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            EqFilterType::LowShelf => {
+                let sq = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a - 1.0).mul_add(-cos, a + 1.0) + sq),
+                    2.0 * a * (a + 1.0).mul_add(-cos, a - 1.0),
+                    a * ((a - 1.0).mul_add(-cos, a + 1.0) - sq),
+                    (a - 1.0).mul_add(cos, a + 1.0) + sq,
+                    -2.0 * (a + 1.0).mul_add(cos, a - 1.0),
+                    (a - 1.0).mul_add(cos, a + 1.0) - sq,
+                )
+            }
+            EqFilterType::HighShelf => {
+                let sq = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a - 1.0).mul_add(cos, a + 1.0) + sq),
+                    -2.0 * a * (a + 1.0).mul_add(cos, a - 1.0),
+                    a * ((a - 1.0).mul_add(cos, a + 1.0) - sq),
+                    (a - 1.0).mul_add(-cos, a + 1.0) + sq,
+                    2.0 * (a + 1.0).mul_add(-cos, a - 1.0),
+                    (a - 1.0).mul_add(-cos, a + 1.0) - sq,
+                )
+            }
+            EqFilterType::LowPass => (
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            EqFilterType::HighPass => (
+                (1.0 + cos) / 2.0,
+                -(1.0 + cos),
+                (1.0 + cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+        };
+
+        Some(Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        })
+    }
+}
+
+/// The state of a biquad on one channel, in transposed direct form II
+#[derive(Debug, Clone, Copy, Default)]
+struct BiquadState {
+    z1: f64,
+    z2: f64,
+}
+
+impl BiquadState {
+    fn process(&mut self, c: &Coefficients, x: f64) -> f64 {
+        let y = c.b0.mul_add(x, self.z1);
+        self.z1 = c.a1.mul_add(-y, c.b1.mul_add(x, self.z2));
+        self.z2 = c.b2.mul_add(x, -c.a2 * y);
+        y
+    }
+}
+
+/// A peak limiter, keeping the level of the samples under its threshold
+#[derive(Debug, Clone, Copy)]
+struct Limiter {
+    /// The linear level the samples are kept under
+    threshold: f64,
+    /// How much of the gain reduction is kept from one frame to the next
+    /// while recovering
+    release: f64,
+    /// The gain currently applied
+    gain: f64,
+}
+
+impl Limiter {
+    fn new(settings: &LimiterSettings, sample_rate: u32) -> Self {
+        Self {
+            threshold: 10_f64.powf(settings.threshold / 20.0),
+            release: (-1.0 / (settings.release / 1000.0 * f64::from(sample_rate))).exp(),
+            gain: 1.0,
+        }
+    }
+
+    /// The gain to apply to a frame with the given peak. Peaks over the
+    /// threshold are reduced right away, and the gain recovers exponentially
+    /// over the release time afterwards.
+    fn gain(&mut self, peak: f64) -> f64 {
+        let target = if peak > self.threshold {
+            self.threshold / peak
+        } else {
+            1.0
+        };
+
+        self.gain = if target < self.gain {
+            target
+        } else {
+            (self.gain - target).mul_add(self.release, target)
+        };
+
+        self.gain
+    }
+}
+
+/// Applies the DSP settings of an audio output to the buffers of a track.
+pub struct DspProcessor {
+    audio_output_id: String,
+    settings: Option<Arc<DspSettings>>,
+    /// The `DSP_GENERATION`, sample rate and channel count the filters were
+    /// set up for
+    configured: Option<(u64, u32, usize)>,
+    preamp: f64,
+    coefficients: Vec<Coefficients>,
+    /// The state of each filter on each channel
+    states: Vec<Vec<BiquadState>>,
+    limiter: Option<Limiter>,
+}
+
+impl DspProcessor {
+    #[must_use]
+    pub fn new(audio_output_id: impl Into<String>) -> Self {
+        Self {
+            audio_output_id: audio_output_id.into(),
+            settings: None,
+            configured: None,
+            preamp: 1.0,
+            coefficients: vec![],
+            states: vec![],
+            limiter: None,
+        }
+    }
+
+    /// Sets the filters up for the current settings of the audio output, and
+    /// the rate and channels of the samples. The filter states and limiter
+    /// gain are kept when only the settings change, so live adjustments don't
+    /// click.
+    fn configure(&mut self, generation: u64, sample_rate: u32, channels: usize) {
+        if self.configured.is_none_or(|(x, _, _)| x != generation) {
+            self.settings = OUTPUT_DSP
+                .read()
+                .unwrap()
+                .get(&self.audio_output_id)
+                .cloned();
+        }
+
+        let Some(settings) = &self.settings else {
+            self.coefficients.clear();
+            self.limiter = None;
+            self.configured = Some((generation, sample_rate, channels));
+            return;
+        };
+
+        self.preamp = if is_valid_gain(settings.preamp) {
+            10_f64.powf(settings.preamp / 20.0)
+        } else {
+            1.0
+        };
+        self.coefficients = settings
+            .bands
+            .iter()
+            .filter_map(|band| Coefficients::new(band, sample_rate))
+            .collect();
+
+        let layout_changed = self
+            .configured
+            .is_none_or(|(_, rate, count)| rate != sample_rate || count != channels);
+
+        if layout_changed || self.states.first().map(Vec::len) != Some(self.coefficients.len()) {
+            self.states = vec![vec![BiquadState::default(); self.coefficients.len()]; channels];
+        }
+
+        let gain = self.limiter.map_or(1.0, |x| x.gain);
+        self.limiter = settings
+            .limiter
+            .filter(|x| x.validate().is_ok())
+            .map(|x| Limiter {
+                gain,
+                ..Limiter::new(&x, sample_rate)
+            });
+
+        self.configured = Some((generation, sample_rate, channels));
+    }
+
+    /// Applies the preamp and equalizer to the buffer. The limiter is applied
+    /// separately by [`Self::limit`], after the volume.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+        let generation = DSP_GENERATION.load(Ordering::SeqCst);
+        let sample_rate = buf.spec().rate;
+        let channels = buf.spec().channels.count();
+
+        if self.configured != Some((generation, sample_rate, channels)) {
+            self.configure(generation, sample_rate, channels);
+        }
+
+        if self.settings.is_none() {
+            return;
+        }
+
+        let mut planes = buf.planes_mut();
+
+        for (plane, states) in planes.planes().iter_mut().zip(&mut self.states) {
+            for sample in plane.iter_mut() {
+                let mut x = f64::from(*sample) * self.preamp;
+
+                for (c, state) in self.coefficients.iter().zip(states.iter_mut()) {
+                    x = state.process(c, x);
+                }
+
+                *sample = x as f32;
+            }
+        }
+    }
+
+    /// Applies the limiter to the buffer. This is the last stage, so that
+    /// nothing applied after it pushes the samples back over the threshold.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn limit(&mut self, buf: &mut AudioBuffer<f32>) {
+        let Some(limiter) = &mut self.limiter else {
+            return;
+        };
+
+        let frames = buf.frames();
+        let mut planes = buf.planes_mut();
+        let planes = planes.planes();
+
+        for frame in 0..frames {
+            let peak = planes
+                .iter()
+                .map(|plane| f64::from(plane[frame].abs()))
+                .fold(0.0, f64::max);
+
+            let gain = limiter.gain(peak);
+
+            for plane in planes.iter_mut() {
+                plane[frame] = (f64::from(plane[frame]) * gain) as f32;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use symphonia::core::audio::{Channels, Signal as _, SignalSpec};
+
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn band(filter_type: EqFilterType, frequency: f64, gain: f64) -> EqBand {
+        EqBand {
+            filter_type,
+            frequency,
+            gain,
+            q: default_q(),
+        }
+    }
+
+    fn db_to_linear(db: f64) -> f64 {
+        10_f64.powf(db / 20.0)
+    }
+
+    /// The magnitude of the filter's frequency response at `frequency`
+    fn magnitude(c: &Coefficients, frequency: f64) -> f64 {
+        let w = 2.0 * PI * frequency / f64::from(SAMPLE_RATE);
+        let (sin1, cos1) = w.sin_cos();
+        let (sin2, cos2) = (2.0 * w).sin_cos();
+
+        let num = (
+            c.b2.mul_add(cos2, c.b1.mul_add(cos1, c.b0)),
+            -c.b2.mul_add(sin2, c.b1 * sin1),
+        );
+        let den = (
+            c.a2.mul_add(cos2, c.a1.mul_add(cos1, 1.0)),
+            -c.a2.mul_add(sin2, c.a1 * sin1),
+        );
+
+        num.0.hypot(num.1) / den.0.hypot(den.1)
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {expected} ± {tolerance}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn peaking_band_without_gain_is_flat() {
+        let c = Coefficients::new(&band(EqFilterType::Peaking, 1_000.0, 0.0), SAMPLE_RATE).unwrap();
+
+        for frequency in [20.0, 1_000.0, 10_000.0, 20_000.0] {
+            assert_close(magnitude(&c, frequency), 1.0, 1e-9);
+        }
+    }
+
+    #[test]
+    fn peaking_band_applies_its_gain_at_the_center_frequency() {
+        let c = Coefficients::new(&band(EqFilterType::Peaking, 1_000.0, 6.0), SAMPLE_RATE).unwrap();
+
+        assert_close(magnitude(&c, 1_000.0), db_to_linear(6.0), 1e-9);
+        assert_close(magnitude(&c, 20.0), 1.0, 0.01);
+        assert_close(magnitude(&c, 20_000.0), 1.0, 0.01);
+    }
+
+    #[test]
+    fn shelf_bands_apply_their_gain_past_the_corner_frequency() {
+        let low =
+            Coefficients::new(&band(EqFilterType::LowShelf, 200.0, -6.0), SAMPLE_RATE).unwrap();
+        assert_close(magnitude(&low, 0.0), db_to_linear(-6.0), 1e-9);
+        assert_close(magnitude(&low, 20_000.0), 1.0, 0.01);
+
+        let high =
+            Coefficients::new(&band(EqFilterType::HighShelf, 5_000.0, 4.0), SAMPLE_RATE).unwrap();
+        assert_close(magnitude(&high, 24_000.0), db_to_linear(4.0), 1e-9);
+        assert_close(magnitude(&high, 20.0), 1.0, 0.01);
+    }
+
+    #[test]
+    fn pass_bands_ignore_their_gain() {
+        let low =
+            Coefficients::new(&band(EqFilterType::LowPass, 1_000.0, 12.0), SAMPLE_RATE).unwrap();
+        assert_close(magnitude(&low, 0.0), 1.0, 1e-9);
+        assert_close(
+            magnitude(&low, 1_000.0),
+            std::f64::consts::FRAC_1_SQRT_2,
+            1e-9,
+        );
+        assert_close(magnitude(&low, 24_000.0), 0.0, 1e-9);
+
+        let high =
+            Coefficients::new(&band(EqFilterType::HighPass, 1_000.0, 12.0), SAMPLE_RATE).unwrap();
+        assert_close(magnitude(&high, 0.0), 0.0, 1e-9);
+        assert_close(
+            magnitude(&high, 1_000.0),
+            std::f64::consts::FRAC_1_SQRT_2,
+            1e-9,
+        );
+        assert_close(magnitude(&high, 24_000.0), 1.0, 1e-9);
+    }
+
+    #[test]
+    fn bands_at_or_above_the_nyquist_frequency_are_skipped() {
+        let band = band(EqFilterType::Peaking, 30_000.0, 3.0);
+
+        assert!(Coefficients::new(&band, 44_100).is_none());
+        assert!(Coefficients::new(&band, 96_000).is_some());
+    }
+
+    #[test]
+    fn validate_rejects_settings_that_cannot_be_applied() {
+        let valid = DspSettings {
+            preamp: -3.0,
+            bands: vec![band(EqFilterType::Peaking, 1_000.0, 3.0)],
+            limiter: Some(LimiterSettings {
+                threshold: -1.0,
+                release: default_release(),
+            }),
+        };
+        assert!(valid.validate(SAMPLE_RATE).is_ok());
+
+        let with_band = |band: EqBand| DspSettings {
+            bands: vec![band],
+            ..valid.clone()
+        };
+
+        assert!(matches!(
+            DspSettings {
+                preamp: f64::NAN,
+                ..valid.clone()
+            }
+            .validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::Preamp(_))
+        ));
+        assert!(matches!(
+            DspSettings {
+                preamp: MAX_GAIN + 1.0,
+                ..valid.clone()
+            }
+            .validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::Preamp(_))
+        ));
+        assert!(matches!(
+            with_band(band(EqFilterType::Peaking, 1_000.0, f64::INFINITY)).validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::Band {
+                index: 0,
+                error: InvalidEqBand::Gain(_)
+            })
+        ));
+        assert!(matches!(
+            with_band(EqBand {
+                q: 0.0,
+                ..band(EqFilterType::Peaking, 1_000.0, 3.0)
+            })
+            .validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::Band {
+                error: InvalidEqBand::Q(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            with_band(band(EqFilterType::Peaking, 24_000.0, 3.0)).validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::Band {
+                error: InvalidEqBand::Frequency { .. },
+                ..
+            })
+        ));
+        assert!(matches!(
+            with_band(band(EqFilterType::Peaking, f64::NAN, 3.0)).validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::Band {
+                error: InvalidEqBand::Frequency { .. },
+                ..
+            })
+        ));
+        assert!(matches!(
+            DspSettings {
+                limiter: Some(LimiterSettings {
+                    threshold: 1.0,
+                    release: default_release(),
+                }),
+                ..valid.clone()
+            }
+            .validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::LimiterThreshold(_))
+        ));
+        assert!(matches!(
+            DspSettings {
+                limiter: Some(LimiterSettings {
+                    threshold: -1.0,
+                    release: 0.0,
+                }),
+                ..valid
+            }
+            .validate(SAMPLE_RATE),
+            Err(InvalidDspSettings::LimiterRelease(_))
+        ));
+    }
+
+    #[test]
+    fn limiter_reduces_peaks_right_away_and_recovers_over_the_release_time() {
+        let mut limiter = Limiter::new(
+            &LimiterSettings {
+                threshold: -6.0,
+                release: 10.0,
+            },
+            SAMPLE_RATE,
+        );
+        let threshold = db_to_linear(-6.0);
+
+        assert_close(limiter.gain(1.0), threshold, 1e-12);
+        assert_close(limiter.gain(0.5 * threshold), threshold, 2e-3);
+
+        // After the release time, the gain has recovered by 1 - 1/e of the way
+        let release_frames = SAMPLE_RATE / 100;
+        let mut gain = 0.0;
+        for _ in 1..release_frames {
+            let next = limiter.gain(0.0);
+            assert!(next >= gain);
+            gain = next;
+        }
+        assert_close(
+            gain,
+            (1.0 - threshold).mul_add(1.0 - (-1_f64).exp(), threshold),
+            1e-3,
+        );
+    }
+
+    #[test]
+    fn processor_keeps_the_limited_samples_under_the_threshold() {
+        let audio_output_id = "processor_keeps_the_limited_samples_under_the_threshold";
+        set_output_dsp(
+            audio_output_id,
+            Some(DspSettings {
+                preamp: 6.0,
+                bands: vec![],
+                limiter: Some(LimiterSettings {
+                    threshold: -3.0,
+                    release: default_release(),
+                }),
+            }),
+        );
+
+        let spec = SignalSpec::new(SAMPLE_RATE, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+        let mut buf = AudioBuffer::<f32>::new(256, spec);
+        buf.render_reserved(Some(256));
+        for channel in 0..2 {
+            buf.chan_mut(channel).fill(0.9);
+        }
+
+        let mut dsp = DspProcessor::new(audio_output_id);
+        dsp.process(&mut buf);
+        assert_close(f64::from(buf.chan(0)[0]), 0.9 * db_to_linear(6.0), 1e-6);
+
+        dsp.limit(&mut buf);
+        for channel in 0..2 {
+            for sample in buf.chan(channel) {
+                assert!(f64::from(*sample) <= db_to_linear(-3.0) + 1e-6);
+            }
+        }
+
+        set_output_dsp(audio_output_id, None);
+    }
+
+    #[test]
+    fn live_settings_take_precedence_over_the_presets_until_cleared() {
+        let audio_output_id = "live_settings_take_precedence_over_the_presets_until_cleared";
+        let preset = DspSettings {
+            preamp: -3.0,
+            ..Default::default()
+        };
+        let live = DspSettings {
+            preamp: 2.0,
+            ..Default::default()
+        };
+
+        OUTPUT_DSP
+            .write()
+            .unwrap()
+            .presets
+            .insert(audio_output_id.to_string(), Arc::new(preset.clone()));
+        set_output_dsp(audio_output_id, Some(live.clone()));
+        assert_eq!(output_dsp(audio_output_id), Some(live));
+
+        set_output_dsp(audio_output_id, None);
+        assert_eq!(output_dsp(audio_output_id), Some(preset));
+
+        OUTPUT_DSP.write().unwrap().presets.remove(audio_output_id);
+    }
+}
@@ -0,0 +1,174 @@
+use moosicbox_database::{
+    config::ConfigDatabase,
+    query::{where_eq, FilterableQuery as _},
+    Row,
+};
+use moosicbox_json_utils::{
+    database::{DatabaseFetchError, ToValue as _},
+    ParseError, ToValueType,
+};
+
+use super::{DspPreset, DspTarget, SetDspPreset};
+
+const AUDIO_ZONE_TARGET: &str = "AUDIO_ZONE";
+const PLAYER_TARGET: &str = "PLAYER";
+
+const fn target_type(target: &DspTarget) -> &'static str {
+    match target {
+        DspTarget::AudioZone { .. } => AUDIO_ZONE_TARGET,
+        DspTarget::Player { .. } => PLAYER_TARGET,
+    }
+}
+
+impl ToValueType<DspPreset> for &Row {
+    fn to_value_type(self) -> Result<DspPreset, ParseError> {
+        let target_id = self.to_value("target_id")?;
+        let target_type: String = self.to_value("target_type")?;
+        let target = match target_type.as_str() {
+            AUDIO_ZONE_TARGET => DspTarget::AudioZone {
+                audio_zone_id: target_id,
+            },
+            PLAYER_TARGET => DspTarget::Player {
+                player_id: target_id,
+            },
+            _ => {
+                return Err(ParseError::Parse(format!(
+                    "target_type: Invalid value '{target_type}'"
+                )))
+            }
+        };
+        let settings: String = self.to_value("settings")?;
+
+        Ok(DspPreset {
+            id: self.to_value("id")?,
+            name: self.to_value("name")?,
+            target,
+            active: self.to_value("active")?,
+            settings: serde_json::from_str(&settings)
+                .map_err(|e| ParseError::Parse(format!("settings: {e:?}")))?,
+        })
+    }
+}
+
+/// # Errors
+///
+/// * If there is a database error
+pub async fn get_dsp_presets(
+    db: &ConfigDatabase,
+    target: Option<DspTarget>,
+) -> Result<Vec<DspPreset>, DatabaseFetchError> {
+    Ok(db
+        .select("dsp_presets")
+        .filter_if_some(target.map(|x| where_eq("target_type", target_type(&x))))
+        .filter_if_some(target.map(|x| where_eq("target_id", x.id())))
+        .execute(db)
+        .await?
+        .to_value_type()?)
+}
+
+/// # Errors
+///
+/// * If there is a database error
+pub async fn get_active_dsp_presets(
+    db: &ConfigDatabase,
+) -> Result<Vec<DspPreset>, DatabaseFetchError> {
+    Ok(db
+        .select("dsp_presets")
+        .where_eq("active", true)
+        .execute(db)
+        .await?
+        .to_value_type()?)
+}
+
+/// Creates or updates the preset with the target and name of `preset`. An
+/// active preset deactivates the other presets of its target.
+///
+/// # Errors
+///
+/// * If there is a database error
+pub async fn upsert_dsp_preset(
+    db: &ConfigDatabase,
+    preset: &SetDspPreset,
+) -> Result<DspPreset, DatabaseFetchError> {
+    let target_type = target_type(&preset.target);
+    let target_id = preset.target.id();
+
+    if preset.active {
+        db.update("dsp_presets")
+            .where_eq("target_type", target_type)
+            .where_eq("target_id", target_id)
+            .where_not_eq("name", preset.name.as_str())
+            .value("active", false)
+            .execute(db)
+            .await?;
+    }
+
+    let settings = serde_json::to_string(&preset.settings)
+        .map_err(|e| DatabaseFetchError::Parse(ParseError::Parse(format!("settings: {e:?}"))))?;
+
+    Ok(db
+        .upsert("dsp_presets")
+        .where_eq("target_type", target_type)
+        .where_eq("target_id", target_id)
+        .where_eq("name", preset.name.as_str())
+        .value("target_type", target_type)
+        .value("target_id", target_id)
+        .value("name", preset.name.as_str())
+        .value("active", preset.active)
+        .value("settings", settings)
+        .execute_first(db)
+        .await?
+        .to_value_type()?)
+}
+
+/// # Errors
+///
+/// * If there is a database error
+pub async fn delete_dsp_preset(
+    db: &ConfigDatabase,
+    id: u64,
+) -> Result<Option<DspPreset>, DatabaseFetchError> {
+    Ok(db
+        .delete("dsp_presets")
+        .where_eq("id", id)
+        .execute_first(db)
+        .await?
+        .map(|x| x.to_value_type())
+        .transpose()?)
+}
+
+/// The id and audio output id of every player
+///
+/// # Errors
+///
+/// * If there is a database error
+pub async fn get_player_audio_outputs(
+    db: &ConfigDatabase,
+) -> Result<Vec<(u64, String)>, DatabaseFetchError> {
+    db.select("players")
+        .columns(&["id", "audio_output_id"])
+        .execute(db)
+        .await?
+        .iter()
+        .map(|x| Ok::<_, DatabaseFetchError>((x.to_value("id")?, x.to_value("audio_output_id")?)))
+        .collect()
+}
+
+/// The audio zone id and player id of every player in an audio zone
+///
+/// # Errors
+///
+/// * If there is a database error
+pub async fn get_audio_zone_players(
+    db: &ConfigDatabase,
+) -> Result<Vec<(u64, u64)>, DatabaseFetchError> {
+    db.select("audio_zone_players")
+        .columns(&["audio_zone_id", "player_id"])
+        .execute(db)
+        .await?
+        .iter()
+        .map(|x| {
+            Ok::<_, DatabaseFetchError>((x.to_value("audio_zone_id")?, x.to_value("player_id")?))
+        })
+        .collect()
+}
@@ -0,0 +1,588 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::{
+    f32::consts::FRAC_PI_2,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use flume::{Receiver, Sender};
+use moosicbox_audio_decoder::{AudioDecode, AudioDecodeError};
+use moosicbox_audio_output::{AudioOutput, AudioOutputError, AudioOutputFactory, SourceFormat};
+use symphonia::core::{
+    audio::{AudioBuffer, Signal as _, SignalSpec},
+    formats::{Packet, Track},
+    units::Time,
+};
+use tokio_util::sync::CancellationToken;
+
+/// The number of decoded packets that can be queued for the output before the decoder blocks.
+/// Keeps the decoder from running far ahead of the audio that is actually being heard.
+const OUTPUT_QUEUE_SIZE: usize = 2;
+
+enum OutputCommand {
+    StartTrack {
+        /// The format to open the output at for bit-perfect playback
+        format: Option<SourceFormat>,
+        /// Receives whether the output was opened at the `format`
+        opened: Sender<bool>,
+    },
+    Write {
+        decoded: AudioBuffer<f32>,
+        hold: bool,
+    },
+    Finish,
+}
+
+/// Planar samples held back from the `AudioOutput`.
+struct Samples {
+    spec: SignalSpec,
+    channels: Vec<Vec<f32>>,
+}
+
+impl Samples {
+    fn new(spec: SignalSpec) -> Self {
+        Self {
+            spec,
+            channels: vec![vec![]; spec.channels.count()],
+        }
+    }
+
+    fn frames(&self) -> usize {
+        self.channels.first().map_or(0, Vec::len)
+    }
+
+    fn push(&mut self, buf: &AudioBuffer<f32>) {
+        for (c, samples) in self.channels.iter_mut().enumerate() {
+            samples.extend_from_slice(buf.chan(c));
+        }
+    }
+
+    /// Drops the first `frames` frames.
+    fn skip(&mut self, frames: usize) {
+        for samples in &mut self.channels {
+            samples.drain(..frames.min(samples.len()));
+        }
+    }
+
+    fn into_buffer(self) -> AudioBuffer<f32> {
+        let frames = self.frames();
+        let mut buf = AudioBuffer::new(frames as u64, self.spec);
+        buf.render_reserved(Some(frames));
+
+        for (c, samples) in self.channels.iter().enumerate() {
+            buf.chan_mut(c).copy_from_slice(samples);
+        }
+
+        buf
+    }
+}
+
+/// Writes the decoded audio of consecutive tracks to a single `AudioOutput`, crossfading the
+/// held back end of a track into the start of the next one.
+struct TrackMixer {
+    output: AudioOutput,
+    /// The end of the current track, held back to crossfade into the next track
+    tail: Option<Samples>,
+    /// The end of the previous track and how many of its frames have been mixed into the
+    /// current track so far
+    fade_out: Option<(Samples, usize)>,
+    /// The format the current track asked the output to be opened at
+    format: Option<SourceFormat>,
+    /// Whether the output is currently playing bit-perfect
+    bit_perfect: Arc<AtomicBool>,
+}
+
+impl TrackMixer {
+    const fn new(output: AudioOutput, bit_perfect: Arc<AtomicBool>) -> Self {
+        Self {
+            output,
+            tail: None,
+            fade_out: None,
+            format: None,
+            bit_perfect,
+        }
+    }
+
+    /// Re-opens the output at the `format` of the current track, or back at its default spec,
+    /// after writing out whatever is left of the previous track.
+    fn set_source_format(&mut self, format: Option<SourceFormat>) -> Result<(), AudioOutputError> {
+        self.finish()?;
+
+        if let Some((mut fade_out, offset)) = self.fade_out.take() {
+            fade_out.skip(offset);
+            self.output.write_resampled(fade_out.into_buffer())?;
+        }
+
+        self.format = format;
+        let bit_perfect = self.output.set_source_format(format)?;
+        self.bit_perfect.store(bit_perfect, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Starts the next track, re-opening the output at its `format` if that changed, and returns
+    /// whether the output plays it bit-perfect.
+    fn start_track(&mut self, format: Option<SourceFormat>) -> Result<bool, AudioOutputError> {
+        self.fade_out = self.tail.take().map(|tail| (tail, 0));
+
+        if format != self.format {
+            self.set_source_format(format)?;
+        }
+
+        Ok(self.bit_perfect.load(Ordering::SeqCst))
+    }
+
+    fn write(&mut self, decoded: AudioBuffer<f32>, hold: bool) -> Result<(), AudioOutputError> {
+        let mut buf = match self.output.resample_if_needed(decoded) {
+            Ok(buf) => buf,
+            Err(AudioOutputError::StreamEnd) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        self.mix_fade_out(&mut buf);
+
+        if hold {
+            self.tail
+                .get_or_insert_with(|| Samples::new(*buf.spec()))
+                .push(&buf);
+        } else {
+            self.output.write_resampled(buf)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), AudioOutputError> {
+        if let Some(tail) = self.tail.take() {
+            log::debug!(
+                "TrackMixer: No track to crossfade into, writing {} held back frames",
+                tail.frames()
+            );
+            self.output.write_resampled(tail.into_buffer())?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn mix_fade_out(&mut self, buf: &mut AudioBuffer<f32>) {
+        let Some((fade_out, offset)) = &mut self.fade_out else {
+            return;
+        };
+
+        let len = fade_out.frames();
+        let frames = buf.frames().min(len - *offset);
+
+        if let Some(last_channel) = fade_out.channels.len().checked_sub(1) {
+            for c in 0..buf.spec().channels.count() {
+                let samples = &fade_out.channels[c.min(last_channel)][*offset..];
+
+                for (i, (sample, out)) in buf.chan_mut(c).iter_mut().zip(samples).enumerate() {
+                    // Equal power crossfade, so the overall loudness doesn't dip halfway through
+                    let (fade_in_gain, fade_out_gain) =
+                        ((*offset + i) as f32 / len as f32 * FRAC_PI_2).sin_cos();
+                    *sample = sample.mul_add(fade_in_gain, out * fade_out_gain);
+                }
+            }
+        }
+
+        *offset += frames;
+
+        if *offset >= len {
+            self.fade_out = None;
+        }
+    }
+}
+
+fn run_output(
+    mut mixer: TrackMixer,
+    receiver: &Receiver<OutputCommand>,
+    abort: &CancellationToken,
+) {
+    while let Ok(command) = receiver.recv() {
+        if abort.is_cancelled() {
+            log::debug!("run_output: Playback cancelled, closing output");
+            break;
+        }
+
+        let result = match command {
+            OutputCommand::StartTrack { format, opened } => {
+                mixer.start_track(format).map(|bit_perfect| {
+                    let _ = opened.send(bit_perfect);
+                })
+            }
+            OutputCommand::Write { decoded, hold } => mixer.write(decoded, hold),
+            OutputCommand::Finish => mixer.finish(),
+        };
+
+        if let Err(e) = result {
+            log::error!("run_output: Failed to write to output: {e:?}");
+            break;
+        }
+    }
+
+    log::debug!("run_output: Output closed");
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn time_secs(time: Time) -> f64 {
+    time.seconds as f64 + time.frac
+}
+
+/// The seconds of audio left in the `track` from the start of the `packet`, if the length of
+/// the track is known.
+fn remaining_secs(packet: &Packet, track: &Track) -> Option<f64> {
+    let time_base = track.codec_params.time_base?;
+    let end = track.codec_params.start_ts + track.codec_params.n_frames?;
+
+    Some(time_secs(time_base.calc_time(end)) - time_secs(time_base.calc_time(packet.ts())))
+}
+
+/// An `AudioOutput` that is kept open across the consecutive tracks of a `Playback`, so each
+/// track is fed into the same output stream instead of tearing it down and opening a new one
+/// in between tracks.
+///
+/// The `AudioOutput` lives on its own blocking task, since the output streams can't be moved
+/// between the threads that the tracks are decoded on. The output is closed once the `abort`
+/// token is cancelled or every handle to it has been dropped.
+#[derive(Clone)]
+pub struct GaplessOutput {
+    sender: Sender<OutputCommand>,
+    abort: CancellationToken,
+    bit_perfect: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for GaplessOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GaplessOutput")
+            .field("abort", &self.abort)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GaplessOutput {
+    /// # Errors
+    ///
+    /// * If the `AudioOutput` failed to open
+    pub async fn open(
+        factory: AudioOutputFactory,
+        abort: CancellationToken,
+    ) -> Result<Self, AudioOutputError> {
+        let (sender, receiver) = flume::bounded(OUTPUT_QUEUE_SIZE);
+        let (opened_tx, opened_rx) = flume::bounded(1);
+        let bit_perfect = Arc::new(AtomicBool::new(false));
+
+        moosicbox_task::spawn_blocking("player: Gapless output", {
+            let abort = abort.clone();
+            let bit_perfect = bit_perfect.clone();
+            move || match factory.try_into_output() {
+                Ok(output) => {
+                    let _ = opened_tx.send(Ok(()));
+                    run_output(TrackMixer::new(output, bit_perfect), &receiver, &abort);
+                }
+                Err(e) => {
+                    let _ = opened_tx.send(Err(e));
+                }
+            }
+        });
+
+        opened_rx
+            .recv_async()
+            .await
+            .map_err(|_| AudioOutputError::StreamClosed)??;
+
+        Ok(Self {
+            sender,
+            abort,
+            bit_perfect,
+        })
+    }
+
+    /// Whether the output can still be written to by the `Playback` that opened it.
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        !self.abort.is_cancelled() && !self.sender.is_disconnected()
+    }
+
+    /// Whether the output is playing the current track bit-perfect, at the track's native
+    /// format.
+    #[must_use]
+    pub fn is_bit_perfect(&self) -> bool {
+        self.is_open() && self.bit_perfect.load(Ordering::SeqCst)
+    }
+
+    /// Starts writing the next track to the output, waiting for the audio of the previous track
+    /// to be written first.
+    ///
+    /// With a `crossfade` duration, the last `crossfade` seconds of the track are held back and
+    /// mixed into the start of the next track instead of being written directly.
+    ///
+    /// With a native `format`, the output is re-opened at it for bit-perfect playback, if the
+    /// output supports it. [`GaplessTrackOutput::is_bit_perfect`] tells whether it did, in which
+    /// case the track has to reach the output untouched and isn't crossfaded.
+    ///
+    /// # Errors
+    ///
+    /// * If the output has been closed or failed to re-open
+    pub fn start_track(
+        &self,
+        crossfade: Option<f64>,
+        format: Option<SourceFormat>,
+    ) -> Result<GaplessTrackOutput, AudioDecodeError> {
+        let (opened_tx, opened_rx) = flume::bounded(1);
+
+        self.sender
+            .send(OutputCommand::StartTrack {
+                format,
+                opened: opened_tx,
+            })
+            .map_err(|_| AudioDecodeError::StreamClosed)?;
+
+        let bit_perfect = opened_rx
+            .recv()
+            .map_err(|_| AudioDecodeError::StreamClosed)?;
+
+        Ok(GaplessTrackOutput {
+            sender: self.sender.clone(),
+            crossfade: crossfade.filter(|x| *x > 0.0 && !bit_perfect),
+            bit_perfect,
+        })
+    }
+
+    /// Writes the audio held back for a crossfade when there is no next track to crossfade into.
+    ///
+    /// # Errors
+    ///
+    /// * If the output has been closed
+    pub async fn finish(&self) -> Result<(), AudioOutputError> {
+        self.sender
+            .send_async(OutputCommand::Finish)
+            .await
+            .map_err(|_| AudioOutputError::StreamClosed)
+    }
+}
+
+/// The `AudioDecode` output for a single track written to a [`GaplessOutput`].
+pub struct GaplessTrackOutput {
+    sender: Sender<OutputCommand>,
+    crossfade: Option<f64>,
+    bit_perfect: bool,
+}
+
+impl GaplessTrackOutput {
+    /// Whether the output was opened at the native format of the track.
+    #[must_use]
+    pub const fn is_bit_perfect(&self) -> bool {
+        self.bit_perfect
+    }
+}
+
+impl AudioDecode for GaplessTrackOutput {
+    fn decoded(
+        &mut self,
+        decoded: AudioBuffer<f32>,
+        packet: &Packet,
+        track: &Track,
+    ) -> Result<(), AudioDecodeError> {
+        let hold = self.crossfade.is_some_and(|crossfade| {
+            remaining_secs(packet, track).is_some_and(|remaining| remaining <= crossfade)
+        });
+
+        self.sender
+            .send(OutputCommand::Write { decoded, hold })
+            .map_err(|_| AudioDecodeError::StreamClosed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use moosicbox_audio_output::AudioWrite;
+    use symphonia::core::{audio::Layout, codecs::CodecParameters, units::TimeBase};
+
+    use super::*;
+
+    /// Records every buffer written to the output, as planar samples.
+    struct RecordingWriter(Arc<Mutex<Vec<Vec<Vec<f32>>>>>);
+
+    impl AudioWrite for RecordingWriter {
+        fn write(&mut self, decoded: AudioBuffer<f32>) -> Result<usize, AudioOutputError> {
+            let channels = (0..decoded.spec().channels.count())
+                .map(|c| decoded.chan(c).to_vec())
+                .collect();
+            self.0.lock().unwrap().push(channels);
+            Ok(decoded.frames())
+        }
+
+        fn flush(&mut self) -> Result<(), AudioOutputError> {
+            Ok(())
+        }
+    }
+
+    fn spec() -> SignalSpec {
+        SignalSpec::new_with_layout(4, Layout::Mono)
+    }
+
+    fn mixer() -> (TrackMixer, Arc<Mutex<Vec<Vec<Vec<f32>>>>>) {
+        let written = Arc::new(Mutex::new(vec![]));
+        let output = AudioOutput::new(
+            "test".into(),
+            "test".into(),
+            spec(),
+            Box::new(RecordingWriter(written.clone())),
+        );
+
+        (
+            TrackMixer::new(output, Arc::new(AtomicBool::new(false))),
+            written,
+        )
+    }
+
+    fn buffer(samples: &[f32]) -> AudioBuffer<f32> {
+        let mut buf = AudioBuffer::new(samples.len() as u64, spec());
+        buf.render_reserved(Some(samples.len()));
+        buf.chan_mut(0).copy_from_slice(samples);
+        buf
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn samples_push_skip_and_into_buffer_keep_the_frames_in_order() {
+        let mut samples = Samples::new(spec());
+        samples.push(&buffer(&[1.0, 2.0]));
+        samples.push(&buffer(&[3.0, 4.0]));
+        assert_eq!(samples.frames(), 4);
+
+        samples.skip(1);
+        let buf = samples.into_buffer();
+
+        assert_eq!(buf.frames(), 3);
+        assert_eq!(buf.chan(0), &[2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn write_without_hold_goes_straight_to_the_output() {
+        let (mut mixer, written) = mixer();
+
+        mixer.write(buffer(&[0.5, 0.5]), false).unwrap();
+
+        assert_eq!(*written.lock().unwrap(), vec![vec![vec![0.5, 0.5]]]);
+        assert!(mixer.tail.is_none());
+    }
+
+    #[test]
+    fn held_frames_are_written_on_finish() {
+        let (mut mixer, written) = mixer();
+
+        mixer.write(buffer(&[0.1, 0.2]), true).unwrap();
+        mixer.write(buffer(&[0.3]), true).unwrap();
+        assert!(written.lock().unwrap().is_empty());
+
+        mixer.finish().unwrap();
+
+        assert_eq!(*written.lock().unwrap(), vec![vec![vec![0.1, 0.2, 0.3]]]);
+        assert!(mixer.tail.is_none());
+    }
+
+    #[test]
+    fn start_track_moves_the_held_tail_into_the_fade_out() {
+        let (mut mixer, _written) = mixer();
+
+        mixer.write(buffer(&[1.0, 1.0]), true).unwrap();
+        mixer.start_track(None).unwrap();
+
+        assert!(mixer.tail.is_none());
+        let (fade_out, offset) = mixer.fade_out.as_ref().unwrap();
+        assert_eq!(fade_out.frames(), 2);
+        assert_eq!(*offset, 0);
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn crossfade_uses_equal_power_gains_across_the_fade_out() {
+        let (mut mixer, written) = mixer();
+
+        mixer.write(buffer(&[1.0; 4]), true).unwrap();
+        mixer.start_track(None).unwrap();
+        mixer.write(buffer(&[1.0; 4]), false).unwrap();
+
+        let expected = (0..4)
+            .map(|i| {
+                let (fade_in, fade_out) = (i as f32 / 4.0 * FRAC_PI_2).sin_cos();
+                fade_in + fade_out
+            })
+            .collect::<Vec<_>>();
+
+        let written = written.lock().unwrap();
+        assert_eq!(written.len(), 1);
+        assert_close(&written[0][0], &expected);
+        // The first frame is all fade out, since the fade in starts from silence
+        assert_close(&written[0][0][..1], &[1.0]);
+        assert!(mixer.fade_out.is_none());
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn crossfade_continues_across_buffers_and_stops_after_the_fade_out() {
+        let (mut mixer, written) = mixer();
+
+        mixer.write(buffer(&[1.0; 4]), true).unwrap();
+        mixer.start_track(None).unwrap();
+
+        mixer.write(buffer(&[0.0; 2]), false).unwrap();
+        assert_eq!(mixer.fade_out.as_ref().map(|(_, offset)| *offset), Some(2));
+
+        mixer.write(buffer(&[0.0; 4]), false).unwrap();
+        assert!(mixer.fade_out.is_none());
+
+        mixer.write(buffer(&[0.0; 2]), false).unwrap();
+
+        let gain = |i: usize| (i as f32 / 4.0 * FRAC_PI_2).cos();
+        let written = written.lock().unwrap();
+        assert_close(&written[0][0], &[gain(0), gain(1)]);
+        assert_close(&written[1][0], &[gain(2), gain(3), 0.0, 0.0]);
+        assert_close(&written[2][0], &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn remaining_secs_is_measured_from_the_start_of_the_packet() {
+        let track = Track::new(
+            0,
+            CodecParameters::new()
+                .with_time_base(TimeBase::new(1, 4))
+                .with_n_frames(40)
+                .clone(),
+        );
+
+        let at = |ts| remaining_secs(&Packet::new_from_slice(0, ts, 4, &[]), &track);
+
+        assert_eq!(at(0), Some(10.0));
+        assert_eq!(at(32), Some(2.0));
+        assert_eq!(at(40), Some(0.0));
+    }
+
+    #[test]
+    fn remaining_secs_is_none_for_an_unknown_track_length() {
+        let track = Track::new(
+            0,
+            CodecParameters::new()
+                .with_time_base(TimeBase::new(1, 4))
+                .clone(),
+        );
+
+        assert_eq!(
+            remaining_secs(&Packet::new_from_slice(0, 0, 4, &[]), &track),
+            None
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    sync::{LazyLock, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use moosicbox_database::profiles::PROFILES;
+use moosicbox_session::models::PlaybackTarget;
+
+use crate::Playback;
+
+/// The maximum progress, in seconds, between two consecutive progress updates for the
+/// threshold to count as being played through rather than seeked past.
+const MAX_PROGRESS_STEP: f64 = 5.0;
+
+/// How much of a track has to be played for it to be recorded in the play history. A track
+/// counts as played once either threshold is reached, whichever comes first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayThreshold {
+    /// The percentage (0-100) of the track duration
+    pub percent: f64,
+    /// The number of seconds
+    pub secs: f64,
+}
+
+impl Default for PlayThreshold {
+    fn default() -> Self {
+        Self {
+            percent: 50.0,
+            secs: 240.0,
+        }
+    }
+}
+
+impl PlayThreshold {
+    /// The number of seconds of a track with the given `duration` that have to be played.
+    #[must_use]
+    pub fn secs_for(&self, duration: f64) -> f64 {
+        (duration * self.percent / 100.0).min(self.secs)
+    }
+
+    /// Whether the progress of a track with the given `duration` went from `previous` to
+    /// `current` seconds by playing through the threshold, rather than by seeking past it.
+    #[must_use]
+    pub fn played_through(&self, duration: f64, previous: f64, current: f64) -> bool {
+        let threshold = self.secs_for(duration);
+
+        previous < threshold && current >= threshold && current - previous <= MAX_PROGRESS_STEP
+    }
+}
+
+static PLAY_THRESHOLD: LazyLock<RwLock<PlayThreshold>> = LazyLock::new(|| {
+    let default = PlayThreshold::default();
+
+    RwLock::new(PlayThreshold {
+        percent: std::env::var("PLAY_HISTORY_THRESHOLD_PERCENT")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(default.percent),
+        secs: std::env::var("PLAY_HISTORY_THRESHOLD_SECS")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or(default.secs),
+    })
+});
+
+/// # Panics
+///
+/// * If the `PLAY_THRESHOLD` `RwLock` is poisoned
+#[must_use]
+pub fn play_threshold() -> PlayThreshold {
+    *PLAY_THRESHOLD.read().unwrap()
+}
+
+/// # Panics
+///
+/// * If the `PLAY_THRESHOLD` `RwLock` is poisoned
+pub fn set_play_threshold(threshold: PlayThreshold) {
+    *PLAY_THRESHOLD.write().unwrap() = threshold;
+}
+
+/// Records the current track of the `Playback` in the play history of its profile if the
+/// progress update from `previous` to `current` played through the [`PlayThreshold`].
+pub(crate) fn record_if_played(current: &Playback, previous: &Playback) {
+    if current.id != previous.id || current.position != previous.position {
+        return;
+    }
+
+    let Some(track) = current.tracks.get(current.position as usize) else {
+        return;
+    };
+
+    if previous
+        .tracks
+        .get(previous.position as usize)
+        .is_none_or(|x| x.id != track.id || x.api_source != track.api_source)
+    {
+        return;
+    }
+
+    if !play_threshold().played_through(track.duration, previous.progress, current.progress) {
+        return;
+    }
+
+    let Some(db) = PROFILES.get(&current.profile) else {
+        log::debug!(
+            "record_if_played: No database for profile '{}'",
+            current.profile
+        );
+        return;
+    };
+
+    let audio_zone_id = match &current.playback_target {
+        Some(PlaybackTarget::AudioZone { audio_zone_id }) => Some(*audio_zone_id),
+        _ => None,
+    };
+    let played_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+
+    log::debug!(
+        "record_if_played: Recording play of track_id={} api_source={:?} session_id={}",
+        track.id,
+        track.api_source,
+        current.session_id,
+    );
+
+    let profile = current.profile.clone();
+    let session_id = current.session_id;
+    let track = track.clone();
+
+    moosicbox_task::spawn("player: Record play", async move {
+        if let Err(e) =
+            moosicbox_library::add_play(&db, &profile, session_id, audio_zone_id, track, played_at)
+                .await
+        {
+            log::error!("Failed to record play: {e:?}");
+        }
+    });
+}
@@ -17,19 +17,22 @@ use local_ip_address::local_ip;
 use moosicbox_audio_decoder::media_sources::{
     bytestream_source::ByteStreamSource, remote_bytestream::RemoteByteStreamMediaSource,
 };
-use moosicbox_audio_output::AudioOutputFactory;
+use moosicbox_audio_output::{AudioOutputError, AudioOutputFactory};
 use moosicbox_database::profiles::LibraryDatabase;
 use moosicbox_json_utils::{database::DatabaseFetchError, ParseError};
 use moosicbox_music_api::MusicApi;
 use moosicbox_music_models::{id::Id, ApiSource, AudioFormat, PlaybackQuality, Track};
 use moosicbox_session::{
     get_session_playlist,
-    models::{ApiSession, PlaybackTarget, Session, UpdateSession, UpdateSessionPlaylist},
+    models::{
+        ApiSession, PlaybackTarget, QueueDelta, RepeatMode, ReplayGainMode, Session, UpdateSession,
+        UpdateSessionPlaylist,
+    },
 };
 use moosicbox_stream_utils::{
     remote_bytestream::RemoteByteStream, stalled_monitor::StalledReadMonitor,
 };
-use rand::{rng, Rng as _};
+use rand::{rng, seq::SliceRandom as _, Rng as _};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -49,6 +52,9 @@ pub mod api;
 #[cfg(feature = "local")]
 pub mod local;
 
+pub mod dsp;
+pub mod gapless;
+pub mod history;
 pub mod signal_chain;
 pub mod symphonia;
 pub mod symphonia_unsync;
@@ -86,10 +92,14 @@ pub enum PlayerError {
     UnsupportedFormat(AudioFormat),
     #[error(transparent)]
     PlaybackError(#[from] PlaybackError),
+    #[error(transparent)]
+    AudioOutput(#[from] AudioOutputError),
     #[error("Track fetch failed: {0}")]
     TrackFetchFailed(String),
     #[error("Album fetch failed: {0}")]
     AlbumFetchFailed(Id),
+    #[error("Playlist fetch failed: {0}")]
+    PlaylistFetchFailed(u64),
     #[error("Track not found: {0}")]
     TrackNotFound(Id),
     #[error("Track not locally stored: {0}")]
@@ -144,6 +154,16 @@ pub struct Playback {
     pub quality: PlaybackQuality,
     pub progress: f64,
     pub volume: Arc<AtomicF64>,
+    pub shuffle: bool,
+    pub repeat_mode: RepeatMode,
+    /// The original track order while `shuffle` is enabled, used to restore the order when
+    /// shuffle is disabled again.
+    pub unshuffled_tracks: Option<Vec<Track>>,
+    /// The duration, in seconds, to crossfade between consecutive tracks. `None` or `0` plays
+    /// the tracks back to back without any gap.
+    pub crossfade: Option<f64>,
+    /// Which of the stored ReplayGain values the tracks are normalized with
+    pub replay_gain_mode: ReplayGainMode,
     pub playback_target: Option<PlaybackTarget>,
     pub abort: CancellationToken,
 }
@@ -168,10 +188,109 @@ impl Playback {
             quality,
             progress: 0.0,
             volume: Arc::new(volume),
+            shuffle: false,
+            repeat_mode: RepeatMode::Off,
+            unshuffled_tracks: None,
+            crossfade: None,
+            replay_gain_mode: ReplayGainMode::Off,
             playback_target,
             abort: CancellationToken::new(),
         }
     }
+
+    /// Enables or disables shuffle on this `Playback`.
+    ///
+    /// Enabling shuffle randomizes the order of the upcoming tracks, leaving the tracks that have
+    /// already been played and the current track in place. Disabling shuffle restores the
+    /// original track order and moves the position to the current track's original index.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        if shuffle == self.shuffle {
+            return;
+        }
+
+        self.shuffle = shuffle;
+
+        if shuffle {
+            self.unshuffled_tracks = Some(self.tracks.clone());
+            let upcoming = (self.position as usize + 1).min(self.tracks.len());
+            self.tracks[upcoming..].shuffle(&mut rng());
+        } else if let Some(tracks) = self.unshuffled_tracks.take() {
+            if let Some(position) = positions_in(&self.tracks, &tracks)
+                .and_then(|positions| positions.get(self.position as usize).copied())
+            {
+                self.position = position;
+            }
+            self.tracks = tracks;
+        }
+    }
+
+    /// The index of each of the `tracks` in the `unshuffled_tracks`, so that the original order
+    /// can be persisted alongside the shuffled tracks. `None` if the `Playback` isn't shuffled or
+    /// the `tracks` aren't a reordering of the `unshuffled_tracks`.
+    #[must_use]
+    pub fn unshuffled_positions(&self) -> Option<Vec<u16>> {
+        positions_in(&self.tracks, self.unshuffled_tracks.as_ref()?)
+    }
+
+    /// The position of the track that should be played after the current track finishes,
+    /// taking the `repeat_mode` into account.
+    #[must_use]
+    pub fn next_position(&self) -> Option<u16> {
+        let next = self.position + 1;
+
+        match self.repeat_mode {
+            RepeatMode::One => Some(self.position),
+            _ if (next as usize) < self.tracks.len() => Some(next),
+            RepeatMode::All if !self.tracks.is_empty() => Some(0),
+            RepeatMode::All | RepeatMode::Off => None,
+        }
+    }
+}
+
+/// The index of each of the `tracks` in `order`. `None` if the `tracks` aren't a reordering of
+/// `order`.
+fn positions_in(tracks: &[Track], order: &[Track]) -> Option<Vec<u16>> {
+    if order.len() != tracks.len() {
+        return None;
+    }
+
+    let mut indices = HashMap::<_, Vec<usize>>::new();
+    for (index, track) in order.iter().enumerate().rev() {
+        indices
+            .entry((&track.id, &track.api_source))
+            .or_default()
+            .push(index);
+    }
+
+    tracks
+        .iter()
+        .map(|track| {
+            indices
+                .get_mut(&(&track.id, &track.api_source))
+                .and_then(Vec::pop)
+                .and_then(|index| u16::try_from(index).ok())
+        })
+        .collect()
+}
+
+/// Puts the `tracks` back in their original order, given the index of each of them in that order.
+/// `None` if the `positions` don't describe a reordering of the `tracks`.
+fn unshuffle_tracks(tracks: &[Track], positions: &[u16]) -> Option<Vec<Track>> {
+    if tracks.len() != positions.len() {
+        return None;
+    }
+
+    let mut unshuffled = vec![None; tracks.len()];
+
+    for (track, position) in tracks.iter().zip(positions) {
+        let slot = unshuffled.get_mut(*position as usize)?;
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some(track.clone());
+    }
+
+    unshuffled.into_iter().collect()
 }
 
 #[derive(Serialize)]
@@ -182,6 +301,10 @@ pub struct ApiPlayback {
     pub playing: bool,
     pub position: u16,
     pub seek: f64,
+    pub shuffle: bool,
+    pub repeat_mode: RepeatMode,
+    pub crossfade: Option<f64>,
+    pub replay_gain_mode: ReplayGainMode,
 }
 
 impl From<Playback> for ApiPlayback {
@@ -191,6 +314,10 @@ impl From<Playback> for ApiPlayback {
             playing: value.playing,
             position: value.position,
             seek: value.progress,
+            shuffle: value.shuffle,
+            repeat_mode: value.repeat_mode,
+            crossfade: value.crossfade,
+            replay_gain_mode: value.replay_gain_mode,
         }
     }
 }
@@ -200,6 +327,9 @@ impl From<Playback> for ApiPlayback {
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ApiPlaybackStatus {
     pub active_playbacks: Option<ApiPlayback>,
+    /// Whether the current track is reaching the output bit-perfect, at its native sample rate
+    /// and bit depth and without any software volume or DSP applied
+    pub bit_perfect: bool,
 }
 
 #[derive(Serialize, Debug, Clone, Copy)]
@@ -301,6 +431,18 @@ pub async fn get_track_url(
                 if quality.format != AudioFormat::Source {
                     serializer.append_pair("format", quality.format.as_ref());
                 }
+                if let Some(bitrate) = quality.settings.bitrate {
+                    serializer.append_pair("bitrate", &bitrate.to_string());
+                }
+                if let Some(vbr_quality) = quality.settings.vbr_quality {
+                    serializer.append_pair("vbrQuality", &vbr_quality.to_string());
+                }
+                if let Some(sample_rate) = quality.settings.sample_rate {
+                    serializer.append_pair("sampleRate", &sample_rate.to_string());
+                }
+                if let Some(channels) = quality.settings.channels {
+                    serializer.append_pair("channels", &channels.to_string());
+                }
             }
             #[cfg(feature = "tidal")]
             ApiSource::Tidal => {
@@ -491,6 +633,8 @@ impl PlaybackHandler {
         session: ApiSession,
     ) -> Result<(), PlayerError> {
         let session_id = session.session_id;
+        let session_shuffle = session.shuffle;
+        let unshuffled_positions = session.playlist.unshuffled_positions;
         if let Err(err) = self
             .update_playback(
                 false,
@@ -509,6 +653,10 @@ impl PlaybackHandler {
                         .collect::<Vec<_>>(),
                 ),
                 None,
+                None,
+                Some(session.repeat_mode),
+                session.crossfade,
+                Some(session.replay_gain_mode),
                 Some(session.session_id),
                 Some(profile),
                 session.playback_target,
@@ -523,6 +671,8 @@ impl PlaybackHandler {
             });
         }
 
+        self.restore_shuffle(session_shuffle, unshuffled_positions.as_deref());
+
         Ok(())
     }
 
@@ -546,6 +696,8 @@ impl PlaybackHandler {
             )
         );
         let session_id = init.session_id;
+        let session_shuffle = session.shuffle;
+        let unshuffled_positions = session.playlist.unshuffled_positions;
         if let Err(err) = self
             .update_playback(
                 false,
@@ -564,6 +716,10 @@ impl PlaybackHandler {
                         .collect::<Vec<_>>(),
                 ),
                 None,
+                None,
+                Some(init.repeat_mode.unwrap_or(session.repeat_mode)),
+                init.crossfade.or(session.crossfade),
+                Some(init.replay_gain_mode.unwrap_or(session.replay_gain_mode)),
                 Some(session.id),
                 Some(profile),
                 session.playback_target,
@@ -578,9 +734,31 @@ impl PlaybackHandler {
             });
         }
 
+        self.restore_shuffle(
+            init.shuffle.unwrap_or(session_shuffle),
+            unshuffled_positions.as_deref(),
+        );
+
         Ok(())
     }
 
+    /// Marks the restored session tracks as shuffled. The session tracks are persisted in their
+    /// shuffled order, along with the index of each track in the original order so that it can be
+    /// restored when shuffle is disabled. Sessions persisted without those indices fall back to
+    /// keeping the shuffled order.
+    fn restore_shuffle(&self, shuffle: bool, unshuffled_positions: Option<&[u16]>) {
+        if let Some(playback) = self.playback.write().unwrap().as_mut() {
+            if shuffle && !playback.shuffle {
+                playback.shuffle = true;
+                playback.unshuffled_tracks = Some(
+                    unshuffled_positions
+                        .and_then(|positions| unshuffle_tracks(&playback.tracks, positions))
+                        .unwrap_or_else(|| playback.tracks.clone()),
+                );
+            }
+        }
+    }
+
     /// # Errors
     ///
     /// * If failed to fetch the album tracks
@@ -628,6 +806,53 @@ impl PlaybackHandler {
         .await
     }
 
+    /// # Errors
+    ///
+    /// * If failed to fetch the playlist tracks
+    /// * If failed to play the tracks
+    #[allow(clippy::too_many_arguments)]
+    pub async fn play_playlist(
+        &mut self,
+        db: &LibraryDatabase,
+        session_id: u64,
+        profile: String,
+        playlist_id: u64,
+        position: Option<u16>,
+        seek: Option<f64>,
+        volume: Option<f64>,
+        quality: PlaybackQuality,
+        playback_target: Option<PlaybackTarget>,
+        retry_options: Option<PlaybackRetryOptions>,
+    ) -> Result<(), PlayerError> {
+        let tracks = {
+            moosicbox_library::playlist_tracks(db, playlist_id, None, None)
+                .await
+                .map_err(|e| {
+                    log::error!("Failed to fetch playlist tracks: {e:?}");
+                    PlayerError::PlaylistFetchFailed(playlist_id)
+                })?
+                .with_rest_of_items_in_batches()
+                .await
+                .map_err(|e| {
+                    log::error!("Failed to fetch playlist tracks: {e:?}");
+                    PlayerError::PlaylistFetchFailed(playlist_id)
+                })?
+        };
+
+        self.play_tracks(
+            session_id,
+            profile,
+            tracks,
+            position,
+            seek,
+            volume,
+            quality,
+            playback_target,
+            retry_options,
+        )
+        .await
+    }
+
     /// # Errors
     ///
     /// * If failed to play the track
@@ -678,15 +903,15 @@ impl PlaybackHandler {
         playback_target: Option<PlaybackTarget>,
         retry_options: Option<PlaybackRetryOptions>,
     ) -> Result<(), PlayerError> {
-        let playback = { self.playback.read().unwrap().clone() };
+        let existing = { self.playback.read().unwrap().clone() };
 
-        if let Some(playback) = playback {
+        if let Some(playback) = &existing {
             log::debug!("Stopping existing playback {}", playback.id);
             self.stop(retry_options).await?;
         }
 
         {
-            let playback = Playback::new(
+            let mut playback = Playback::new(
                 tracks,
                 position,
                 AtomicF64::new(volume.unwrap_or(1.0)),
@@ -696,6 +921,13 @@ impl PlaybackHandler {
                 playback_target,
             );
 
+            if let Some(existing) = existing {
+                playback.repeat_mode = existing.repeat_mode;
+                playback.crossfade = existing.crossfade;
+                playback.replay_gain_mode = existing.replay_gain_mode;
+                playback.set_shuffle(existing.shuffle);
+            }
+
             self.playback.write().unwrap().replace(playback);
         }
 
@@ -797,13 +1029,36 @@ impl PlaybackHandler {
                     break;
                 }
 
-                if ((playback.position + 1) as usize) >= playback.tracks.len() {
+                if let Some(current) = player.playback.read().unwrap().as_ref() {
+                    if current.id == playback.id {
+                        playback.repeat_mode = current.repeat_mode;
+                        playback.crossfade = current.crossfade;
+                        playback.replay_gain_mode = current.replay_gain_mode;
+
+                        let tracks_changed = current
+                            .tracks
+                            .iter()
+                            .map(|x| &x.id)
+                            .ne(playback.tracks.iter().map(|x| &x.id));
+
+                        if current.shuffle != playback.shuffle || tracks_changed {
+                            playback.shuffle = current.shuffle;
+                            playback.tracks.clone_from(&current.tracks);
+                            playback
+                                .unshuffled_tracks
+                                .clone_from(&current.unshuffled_tracks);
+                            playback.position = current.position;
+                        }
+                    }
+                }
+
+                let Some(next_position) = playback.next_position() else {
                     log::debug!("Playback position at end of tracks. Breaking");
                     break;
-                }
+                };
 
                 let old = playback.clone();
-                playback.position += 1;
+                playback.position = next_position;
                 playback.progress = 0.0;
                 player.playback.write().unwrap().replace(playback.clone());
                 trigger_playback_event(&playback, &old);
@@ -920,16 +1175,20 @@ impl PlaybackHandler {
                 .ok_or(PlayerError::NoPlayersPlaying)?
         };
 
-        if playback.position + 1 >= u16::try_from(playback.tracks.len()).unwrap() {
+        let position = if playback.position + 1 < u16::try_from(playback.tracks.len()).unwrap() {
+            playback.position + 1
+        } else if playback.repeat_mode == RepeatMode::All {
+            0
+        } else {
             return Err(PlayerError::PositionOutOfBounds(playback.position + 1));
-        }
+        };
 
         self.update_playback(
             true,
             Some(true),
             None,
             None,
-            Some(playback.position + 1),
+            Some(position),
             seek,
             None,
             None,
@@ -937,6 +1196,10 @@ impl PlaybackHandler {
             None,
             None,
             None,
+            None,
+            None,
+            None,
+            None,
             true,
             retry_options,
         )
@@ -964,16 +1227,20 @@ impl PlaybackHandler {
                 .ok_or(PlayerError::NoPlayersPlaying)?
         };
 
-        if playback.position == 0 {
+        let position = if playback.position > 0 {
+            playback.position - 1
+        } else if playback.repeat_mode == RepeatMode::All && !playback.tracks.is_empty() {
+            u16::try_from(playback.tracks.len() - 1).unwrap()
+        } else {
             return Err(PlayerError::PositionOutOfBounds(0));
-        }
+        };
 
         self.update_playback(
             true,
             Some(true),
             None,
             None,
-            Some(playback.position - 1),
+            Some(position),
             seek,
             None,
             None,
@@ -981,6 +1248,10 @@ impl PlaybackHandler {
             None,
             None,
             None,
+            None,
+            None,
+            None,
+            None,
             true,
             retry_options,
         )
@@ -1015,6 +1286,10 @@ impl PlaybackHandler {
         volume: Option<f64>,
         tracks: Option<Vec<Track>>,
         quality: Option<PlaybackQuality>,
+        shuffle: Option<bool>,
+        repeat_mode: Option<RepeatMode>,
+        crossfade: Option<f64>,
+        replay_gain_mode: Option<ReplayGainMode>,
         session_id: Option<u64>,
         profile: Option<String>,
         playback_target: Option<PlaybackTarget>,
@@ -1033,6 +1308,10 @@ impl PlaybackHandler {
             volume={volume:?}\n\t\
             tracks={tracks:?}\n\t\
             quality={quality:?}\n\t\
+            shuffle={shuffle:?}\n\t\
+            repeat_mode={repeat_mode:?}\n\t\
+            crossfade={crossfade:?}\n\t\
+            replay_gain_mode={replay_gain_mode:?}\n\t\
             session_id={session_id:?}\n\t\
             profile={profile:?}\n\t\
             playback_target={playback_target:?}\n\t\
@@ -1079,7 +1358,7 @@ impl PlaybackHandler {
         let should_resume = same_track && !original.playing && playing && seek.is_none();
         let should_pause = same_track && original.playing && !playing;
 
-        let playback = Playback {
+        let mut playback = Playback {
             id: original.id,
             session_id,
             profile,
@@ -1094,6 +1373,23 @@ impl PlaybackHandler {
                 seek.unwrap_or(original.progress)
             },
             volume: original.volume.clone(),
+            shuffle: original.shuffle,
+            repeat_mode: repeat_mode.unwrap_or(original.repeat_mode),
+            crossfade: crossfade.or(original.crossfade),
+            replay_gain_mode: replay_gain_mode.unwrap_or(original.replay_gain_mode),
+            unshuffled_tracks: if let Some(tracks) = &tracks {
+                // Keep the original order when the new tracks are only a reordering of it, e.g.
+                // after a queue edit has already been applied to the unshuffled tracks
+                original.shuffle.then(|| {
+                    original
+                        .unshuffled_tracks
+                        .clone()
+                        .filter(|unshuffled| positions_in(tracks, unshuffled).is_some())
+                        .unwrap_or_else(|| tracks.clone())
+                })
+            } else {
+                original.unshuffled_tracks.clone()
+            },
             abort: if original.abort.is_cancelled() {
                 CancellationToken::new()
             } else {
@@ -1101,6 +1397,10 @@ impl PlaybackHandler {
             },
         };
 
+        if let Some(shuffle) = shuffle {
+            playback.set_shuffle(shuffle);
+        }
+
         if let Some(volume) = volume {
             playback
                 .volume
@@ -1159,6 +1459,76 @@ impl PlaybackHandler {
         Ok(())
     }
 
+    /// Applies a session queue delta to the tracks of the current `Playback`,
+    /// restarting playback if the current track was removed while playing.
+    ///
+    /// The session already has the edit applied, so this doesn't trigger a
+    /// playback event.
+    ///
+    /// # Panics
+    ///
+    /// * If the `playback` `RwLock` is poisoned
+    ///
+    /// # Errors
+    ///
+    /// * If failed to update the playback
+    pub async fn edit_queue(
+        &mut self,
+        delta: &QueueDelta,
+        retry_options: Option<PlaybackRetryOptions>,
+    ) -> Result<(), PlayerError> {
+        let (tracks, position, same_track) = {
+            let mut binding = self.playback.write().unwrap();
+            let Some(playback) = binding.as_mut() else {
+                log::debug!("edit_queue: No playback to edit");
+                return Ok(());
+            };
+            let unshuffled_positions = playback.unshuffled_positions();
+            let mut tracks = playback.tracks.clone();
+            let position = delta.apply(&mut tracks, playback.position);
+            let same_track = tracks.get(position as usize).map(|x| &x.id)
+                == playback
+                    .tracks
+                    .get(playback.position as usize)
+                    .map(|x| &x.id);
+
+            // Apply the delta to the original order too, so that `update_playback` keeps it
+            // rather than treating the edited, shuffled tracks as the original order
+            if let Some(unshuffled_tracks) = unshuffled_positions.and_then(|mut positions| {
+                delta.apply_unshuffled(&mut positions);
+                unshuffle_tracks(&tracks, &positions)
+            }) {
+                playback.unshuffled_tracks = Some(unshuffled_tracks);
+            }
+            drop(binding);
+            (tracks, position, same_track)
+        };
+
+        log::debug!("edit_queue: delta={delta:?} position={position} same_track={same_track}");
+
+        self.update_playback(
+            true,
+            None,
+            None,
+            None,
+            Some(position),
+            (!same_track).then_some(0.0),
+            None,
+            Some(tracks),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+            retry_options,
+        )
+        .await
+    }
+
     /// # Errors
     ///
     /// * If failed to pause the current `Playback`
@@ -1279,6 +1649,8 @@ pub fn on_playback_event(listener: PlaybackEventCallback) {
 
 #[cfg_attr(feature = "profiling", profiling::function)]
 pub fn trigger_playback_event(current: &Playback, previous: &Playback) {
+    history::record_if_played(current, previous);
+
     let Some(playback_target) = current.playback_target.clone() else {
         return;
     };
@@ -1320,6 +1692,30 @@ pub fn trigger_playback_event(current: &Playback, previous: &Playback) {
         has_change = true;
         Some(current.quality)
     };
+    let shuffle = if current.shuffle == previous.shuffle {
+        None
+    } else {
+        has_change = true;
+        Some(current.shuffle)
+    };
+    let repeat_mode = if current.repeat_mode == previous.repeat_mode {
+        None
+    } else {
+        has_change = true;
+        Some(current.repeat_mode)
+    };
+    let crossfade = if current.crossfade == previous.crossfade {
+        None
+    } else {
+        has_change = true;
+        current.crossfade
+    };
+    let replay_gain_mode = if current.replay_gain_mode == previous.replay_gain_mode {
+        None
+    } else {
+        has_change = true;
+        Some(current.replay_gain_mode)
+    };
     let tracks = current
         .tracks
         .iter()
@@ -1332,15 +1728,17 @@ pub fn trigger_playback_event(current: &Playback, previous: &Playback) {
         .cloned()
         .map(Into::into)
         .collect::<Vec<_>>();
-    let playlist = if tracks == prev_tracks {
-        None
-    } else {
-        has_change = true;
-        Some(UpdateSessionPlaylist {
-            session_playlist_id: 0,
-            tracks,
-        })
-    };
+    let playlist =
+        if tracks == prev_tracks && current.unshuffled_tracks == previous.unshuffled_tracks {
+            None
+        } else {
+            has_change = true;
+            Some(UpdateSessionPlaylist {
+                session_playlist_id: 0,
+                tracks,
+                unshuffled_positions: current.unshuffled_positions(),
+            })
+        };
 
     if !has_change {
         return;
@@ -1354,6 +1752,10 @@ pub fn trigger_playback_event(current: &Playback, previous: &Playback) {
         seek={seek:?}\n\t\
         quality={quality:?}\n\t\
         volume={volume:?}\n\t\
+        shuffle={shuffle:?}\n\t\
+        repeat_mode={repeat_mode:?}\n\t\
+        crossfade={crossfade:?}\n\t\
+        replay_gain_mode={replay_gain_mode:?}\n\t\
         playback_target={playback_target:?}\n\t\
         playlist={playlist:?}\
         "
@@ -1373,6 +1775,10 @@ pub fn trigger_playback_event(current: &Playback, previous: &Playback) {
         volume,
         playlist,
         quality,
+        shuffle,
+        repeat_mode,
+        crossfade,
+        replay_gain_mode,
     };
 
     send_playback_event(&update, current);
@@ -1401,7 +1807,10 @@ async fn track_to_playable_file(
     let same_source = match quality.format {
         AudioFormat::Source => true,
         #[allow(unreachable_patterns)]
-        _ => track.format.is_none_or(|format| format == quality.format),
+        _ => {
+            quality.settings.is_default()
+                && track.format.is_none_or(|format| format == quality.format)
+        }
     };
 
     let source: Box<dyn MediaSource> = if same_source {
@@ -1409,6 +1818,22 @@ async fn track_to_playable_file(
     } else {
         #[allow(unused_mut)]
         let mut signal_chain = SignalChain::new();
+        #[cfg(any(
+            feature = "aac",
+            feature = "alac",
+            feature = "flac",
+            feature = "mp3",
+            feature = "opus",
+            feature = "vorbis",
+            feature = "wav"
+        ))]
+        let settings = moosicbox_audio_output::encoder::EncoderSettings {
+            bitrate: quality.settings.bitrate,
+            vbr_quality: quality.settings.vbr_quality,
+            sample_rate: quality.settings.sample_rate,
+            channels: quality.settings.channels,
+            bits_per_sample: track.bit_depth,
+        };
 
         match quality.format {
             #[cfg(feature = "aac")]
@@ -1417,8 +1842,19 @@ async fn track_to_playable_file(
                 log::debug!("Encoding playback with AacEncoder");
                 let mut hint = Hint::new();
                 hint.with_extension("m4a");
+                let encoder = AacEncoder::new().with_settings(&settings)?;
+                signal_chain = signal_chain
+                    .add_encoder_step(move || Box::new(encoder))
+                    .with_hint(hint);
+            }
+            #[cfg(feature = "alac")]
+            AudioFormat::Alac => {
+                use moosicbox_audio_output::encoder::alac::AlacEncoder;
+                log::debug!("Encoding playback with AlacEncoder");
+                let mut hint = Hint::new();
+                hint.with_extension("mp4");
                 signal_chain = signal_chain
-                    .add_encoder_step(|| Box::new(AacEncoder::new()))
+                    .add_encoder_step(move || Box::new(AlacEncoder::new().with_settings(&settings)))
                     .with_hint(hint);
             }
             #[cfg(feature = "flac")]
@@ -1427,8 +1863,9 @@ async fn track_to_playable_file(
                 log::debug!("Encoding playback with FlacEncoder");
                 let mut hint = Hint::new();
                 hint.with_extension("flac");
+                let encoder = FlacEncoder::new().with_settings(&settings)?;
                 signal_chain = signal_chain
-                    .add_encoder_step(|| Box::new(FlacEncoder::new()))
+                    .add_encoder_step(move || Box::new(encoder))
                     .with_hint(hint);
             }
             #[cfg(feature = "mp3")]
@@ -1437,8 +1874,9 @@ async fn track_to_playable_file(
                 log::debug!("Encoding playback with Mp3Encoder");
                 let mut hint = Hint::new();
                 hint.with_extension("mp3");
+                let encoder = Mp3Encoder::new().with_settings(&settings)?;
                 signal_chain = signal_chain
-                    .add_encoder_step(|| Box::new(Mp3Encoder::new()))
+                    .add_encoder_step(move || Box::new(encoder))
                     .with_hint(hint);
             }
             #[cfg(feature = "opus")]
@@ -1447,8 +1885,30 @@ async fn track_to_playable_file(
                 log::debug!("Encoding playback with OpusEncoder");
                 let mut hint = Hint::new();
                 hint.with_extension("opus");
+                let encoder = OpusEncoder::new().with_settings(&settings)?;
+                signal_chain = signal_chain
+                    .add_encoder_step(move || Box::new(encoder))
+                    .with_hint(hint);
+            }
+            #[cfg(feature = "vorbis")]
+            AudioFormat::Vorbis => {
+                use moosicbox_audio_output::encoder::vorbis::VorbisEncoder;
+                log::debug!("Encoding playback with VorbisEncoder");
+                let mut hint = Hint::new();
+                hint.with_extension("ogg");
+                let encoder = VorbisEncoder::new().with_settings(&settings)?;
+                signal_chain = signal_chain
+                    .add_encoder_step(move || Box::new(encoder))
+                    .with_hint(hint);
+            }
+            #[cfg(feature = "wav")]
+            AudioFormat::Wav => {
+                use moosicbox_audio_output::encoder::wav::WavEncoder;
+                log::debug!("Encoding playback with WavEncoder");
+                let mut hint = Hint::new();
+                hint.with_extension("wav");
                 signal_chain = signal_chain
-                    .add_encoder_step(|| Box::new(OpusEncoder::new()))
+                    .add_encoder_step(move || Box::new(WavEncoder::new().with_settings(&settings)))
                     .with_hint(hint);
             }
             #[allow(unreachable_patterns)]
@@ -1642,3 +2102,155 @@ pub fn send_playback_event(update: &UpdateSession, playback: &Playback) {
         listener(update, playback);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn track(id: u64) -> Track {
+        Track {
+            id: Id::Number(id),
+            ..Default::default()
+        }
+    }
+
+    fn playback(ids: &[u64], position: u16) -> Playback {
+        Playback::new(
+            ids.iter().copied().map(track).collect(),
+            Some(position),
+            AtomicF64::new(1.0),
+            PlaybackQuality::default(),
+            1,
+            "profile".to_string(),
+            None,
+        )
+    }
+
+    fn ids(tracks: &[Track]) -> Vec<Id> {
+        tracks.iter().map(|x| x.id.clone()).collect()
+    }
+
+    #[test]
+    fn set_shuffle_only_shuffles_the_upcoming_tracks() {
+        let mut playback = playback(&[1, 2, 3, 4, 5, 6, 7, 8], 2);
+
+        playback.set_shuffle(true);
+
+        assert!(playback.shuffle);
+        assert_eq!(playback.position, 2);
+        assert_eq!(
+            ids(&playback.tracks[..3]),
+            ids(&[track(1), track(2), track(3)])
+        );
+        let mut upcoming = ids(&playback.tracks[3..]);
+        upcoming.sort_by_key(|x| x.as_number().unwrap());
+        assert_eq!(
+            upcoming,
+            ids(&[track(4), track(5), track(6), track(7), track(8)])
+        );
+        assert_eq!(
+            ids(playback.unshuffled_tracks.as_ref().unwrap()),
+            ids(&(1..=8).map(track).collect::<Vec<_>>())
+        );
+    }
+
+    #[test]
+    fn set_shuffle_off_restores_the_original_order_at_the_current_track() {
+        let mut playback = playback(&[1, 2, 3, 4], 0);
+        playback.shuffle = true;
+        playback.unshuffled_tracks = Some(playback.tracks.clone());
+        playback.tracks = vec![track(1), track(4), track(2), track(3)];
+        playback.position = 1;
+
+        playback.set_shuffle(false);
+
+        assert!(!playback.shuffle);
+        assert_eq!(playback.unshuffled_tracks, None);
+        assert_eq!(
+            ids(&playback.tracks),
+            ids(&[track(1), track(2), track(3), track(4)])
+        );
+        assert_eq!(playback.position, 3);
+    }
+
+    #[test]
+    fn set_shuffle_off_restores_the_position_of_a_duplicated_current_track() {
+        let mut playback = playback(&[1, 2, 3, 2], 0);
+        playback.shuffle = true;
+        playback.unshuffled_tracks = Some(playback.tracks.clone());
+        playback.tracks = vec![track(1), track(3), track(2), track(2)];
+        playback.position = 3;
+
+        playback.set_shuffle(false);
+
+        assert_eq!(
+            ids(&playback.tracks),
+            ids(&[track(1), track(2), track(3), track(2)])
+        );
+        assert_eq!(playback.position, 3);
+    }
+
+    #[test]
+    fn next_position_follows_the_repeat_mode() {
+        let mut playback = playback(&[1, 2, 3], 1);
+
+        assert_eq!(playback.next_position(), Some(2));
+        playback.position = 2;
+        assert_eq!(playback.next_position(), None);
+
+        playback.repeat_mode = RepeatMode::All;
+        assert_eq!(playback.next_position(), Some(0));
+        playback.position = 1;
+        assert_eq!(playback.next_position(), Some(2));
+
+        playback.repeat_mode = RepeatMode::One;
+        assert_eq!(playback.next_position(), Some(1));
+
+        let mut empty = self::playback(&[], 0);
+        assert_eq!(empty.next_position(), None);
+        empty.repeat_mode = RepeatMode::All;
+        assert_eq!(empty.next_position(), None);
+    }
+
+    #[test]
+    fn unshuffled_positions_round_trip_through_unshuffle_tracks() {
+        let mut playback = playback(&[1, 2, 3, 2, 5], 0);
+        playback.shuffle = true;
+        playback.unshuffled_tracks = Some(playback.tracks.clone());
+        playback.tracks = vec![track(1), track(5), track(2), track(3), track(2)];
+
+        let positions = playback.unshuffled_positions().unwrap();
+        assert_eq!(positions, vec![0, 4, 1, 2, 3]);
+        assert_eq!(
+            unshuffle_tracks(&playback.tracks, &positions),
+            playback.unshuffled_tracks
+        );
+    }
+
+    #[test]
+    fn unshuffled_positions_is_none_when_not_shuffled() {
+        assert_eq!(playback(&[1, 2, 3], 0).unshuffled_positions(), None);
+    }
+
+    #[test]
+    fn unshuffled_positions_is_none_when_the_tracks_differ() {
+        let mut playback = playback(&[1, 2, 3], 0);
+        playback.shuffle = true;
+        playback.unshuffled_tracks = Some(vec![track(1), track(2), track(4)]);
+
+        assert_eq!(playback.unshuffled_positions(), None);
+    }
+
+    #[test]
+    fn unshuffle_tracks_rejects_positions_that_are_not_a_reordering() {
+        let tracks = vec![track(1), track(2), track(3)];
+
+        assert_eq!(unshuffle_tracks(&tracks, &[0, 1]), None);
+        assert_eq!(unshuffle_tracks(&tracks, &[0, 1, 1]), None);
+        assert_eq!(unshuffle_tracks(&tracks, &[0, 1, 3]), None);
+        assert_eq!(
+            unshuffle_tracks(&tracks, &[2, 0, 1]).map(|x| ids(&x)),
+            Some(ids(&[track(2), track(3), track(1)]))
+        );
+    }
+}
@@ -4,20 +4,46 @@ use std::sync::{atomic::AtomicBool, Arc, Mutex, RwLock};
 
 use async_trait::async_trait;
 use flume::Receiver;
-use moosicbox_audio_decoder::{AudioDecodeError, AudioDecodeHandler};
-use moosicbox_audio_output::{AudioOutput, AudioOutputFactory};
-use moosicbox_music_models::TrackApiSource;
+use moosicbox_audio_decoder::AudioDecodeHandler;
+use moosicbox_audio_output::{AudioOutputFactory, SourceFormat};
+use moosicbox_music_models::{
+    id::Id, ApiSource, AudioFormat, PlaybackQuality, Track, TrackApiSource,
+};
 use moosicbox_session::models::UpdateSession;
 use rand::{rng, Rng as _};
 use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
-    send_playback_event, symphonia::play_media_source_async, track_or_id_to_playable,
-    trigger_playback_event, volume_mixer::mix_volume, ApiPlaybackStatus, Playback, PlaybackHandler,
-    PlaybackType, Player, PlayerError, PlayerSource,
+    gapless::GaplessOutput,
+    send_playback_event,
+    signal_chain::{DspStage, SignalProcessor as _},
+    symphonia::{decode_ahead, play_decoded_ahead_async, play_media_source_async, DecodedAhead},
+    track_or_id_to_playable, trigger_playback_event,
+    volume_mixer::replay_gain_factor,
+    ApiPlaybackStatus, PlayableTrack, Playback, PlaybackHandler, PlaybackType, Player, PlayerError,
+    PlayerSource,
 };
 
+/// How many decoded buffers the next track is decoded ahead of playing it.
+const DECODE_AHEAD_BUFFERS: usize = 64;
+
+/// The next track of the `Playback`, decoded ahead of time while the current track is playing.
+struct PreloadedTrack {
+    track_id: Id,
+    api_source: ApiSource,
+    quality: PlaybackQuality,
+    dop: bool,
+    abort: CancellationToken,
+    decoded: flume::Receiver<DecodedAhead>,
+}
+
+/// Where the audio of the track that is about to be played comes from.
+enum TrackSource {
+    DecodedAhead(flume::Receiver<DecodedAhead>),
+    Playable(PlayableTrack),
+}
+
 #[derive(Clone)]
 pub struct LocalPlayer {
     pub id: u64,
@@ -27,6 +53,8 @@ pub struct LocalPlayer {
     pub receiver: Arc<tokio::sync::RwLock<Option<Receiver<()>>>>,
     pub playback: Arc<RwLock<Option<Playback>>>,
     pub playback_handler: Arc<RwLock<Option<PlaybackHandler>>>,
+    gapless_output: Arc<Mutex<Option<GaplessOutput>>>,
+    preloaded: Arc<tokio::sync::Mutex<Option<PreloadedTrack>>>,
 }
 
 impl std::fmt::Debug for LocalPlayer {
@@ -38,6 +66,7 @@ impl std::fmt::Debug for LocalPlayer {
             .field("output", &self.output)
             .field("receiver", &self.receiver)
             .field("playback", &self.playback)
+            .field("gapless_output", &self.gapless_output)
             .finish_non_exhaustive()
     }
 }
@@ -76,34 +105,71 @@ impl Player for LocalPlayer {
             playback.abort,
         );
 
-        #[allow(clippy::match_wildcard_for_single_variants)]
-        let playback_type = match track.track_source {
-            TrackApiSource::Local => self.playback_type,
-            #[allow(unreachable_patterns)]
-            _ => PlaybackType::Stream,
+        let dop = self.is_dop_playback(track, playback.quality);
+        log::debug!("trigger_play: dop={dop}");
+
+        // The preloaded track is decoded from its start, so it can't be used to seek into it
+        let decoded_ahead = self
+            .take_preloaded(track, &playback, dop)
+            .await
+            .filter(|_| seek.is_none());
+
+        let (start_offset, end_offset) = self.track_offsets(track);
+        let seek = seek
+            .map(|seek| seek + start_offset.unwrap_or_default())
+            .or(start_offset);
+
+        let track_source = if let Some(decoded) = decoded_ahead {
+            log::debug!("trigger_play: Using track_id={track_id} decoded ahead");
+            TrackSource::DecodedAhead(decoded)
+        } else {
+            TrackSource::Playable(
+                track_or_id_to_playable(
+                    self.track_playback_type(track),
+                    track,
+                    playback.quality,
+                    &self.source,
+                    playback.abort.clone(),
+                )
+                .await?,
+            )
         };
 
-        let playable_track = track_or_id_to_playable(
-            playback_type,
-            track,
-            playback.quality,
-            &self.source,
-            playback.abort.clone(),
-        )
-        .await?;
-        let mss =
-            MediaSourceStream::new(playable_track.source, MediaSourceStreamOptions::default());
+        self.preload_next_track(&playback);
+
+        let replay_gain = track
+            .replay_gain
+            .map_or(1.0, |x| replay_gain_factor(&x, playback.replay_gain_mode));
+        log::debug!("trigger_play: replay_gain={replay_gain}");
+
+        let wants_bit_perfect = !dop && self.is_bit_perfect_playback(playback.quality);
+        let bits_per_sample = track.bit_depth.map(u32::from);
+        log::debug!("trigger_play: wants_bit_perfect={wants_bit_perfect}");
+
+        // Whether the output was actually re-opened at the track's native format, which is only
+        // known once the track starts. Volume and DSP still apply when the output falls back to
+        // its default format.
+        let bit_perfect = Arc::new(AtomicBool::new(false));
 
         let active_playback = self.playback.clone();
         let sent_playback_start_event = AtomicBool::new(false);
 
-        if self.output.is_none() {
-            return Err(PlayerError::NoAudioOutputs);
-        }
+        let gapless_output = self.gapless_output(&playback.abort).await?;
 
-        let open_func = self.output.clone().unwrap();
+        // Only hold back the end of the track for a crossfade if there is a track to fade into
+        let crossfade = playback
+            .crossfade
+            .filter(|_| !dop && playback.next_position().is_some());
+
+        let audio_output_id = self
+            .output
+            .as_ref()
+            .map(|output| output.lock().unwrap().id.clone());
 
         let get_handler = move || {
+            let mut dsp = DspStage::new(audio_output_id, playback.volume.clone(), replay_gain);
+            let track_bit_perfect = bit_perfect.clone();
+
             #[allow(unused_mut)]
             let mut audio_decode_handler = AudioDecodeHandler::new()
                 .with_filter(Box::new({
@@ -112,7 +178,9 @@ impl Player for LocalPlayer {
                         if let Some(tb) = track.codec_params.time_base {
                             let ts = packet.ts();
                             let t = tb.calc_time(ts);
-                            let secs = f64::from(u32::try_from(t.seconds).unwrap()) + t.frac;
+                            let secs = (f64::from(u32::try_from(t.seconds).unwrap()) + t.frac
+                                - start_offset.unwrap_or_default())
+                            .max(0.0);
 
                             let mut binding = active_playback.write().unwrap();
                             if let Some(playback) = binding.as_mut() {
@@ -141,6 +209,10 @@ impl Player for LocalPlayer {
                                             volume: None,
                                             playlist: None,
                                             quality: None,
+                                            shuffle: None,
+                                            repeat_mode: None,
+                                            crossfade: None,
+                                            replay_gain_mode: None,
                                         };
                                         send_playback_event(&update, playback);
                                     }
@@ -155,21 +227,30 @@ impl Player for LocalPlayer {
                     }
                 }))
                 .with_filter(Box::new(move |decoded, _packet, _track| {
-                    mix_volume(
-                        decoded,
-                        playback.volume.load(std::sync::atomic::Ordering::SeqCst),
-                    );
+                    // DoP frames and bit-perfect playback have to reach the DAC untouched
+                    if !dop && !bit_perfect.load(std::sync::atomic::Ordering::SeqCst) {
+                        dsp.process(decoded);
+                    }
                     Ok(())
                 }))
-                .with_output(Box::new(move |_spec, _duration| {
-                    let output: AudioOutput = (open_func.lock().unwrap())
-                        .try_into_output()
-                        .map_err(|e| AudioDecodeError::Other(Box::new(e)))?;
-
+                .with_dop(dop)
+                .with_output(Box::new(move |spec, _duration| {
+                    let format = wants_bit_perfect.then_some(SourceFormat {
+                        spec,
+                        bits_per_sample,
+                    });
+                    let output = gapless_output.start_track(crossfade, format)?;
+                    log::debug!("trigger_play: bit_perfect={}", output.is_bit_perfect());
+                    track_bit_perfect
+                        .store(output.is_bit_perfect(), std::sync::atomic::Ordering::SeqCst);
                     Ok(Box::new(output))
                 }))
                 .with_cancellation_token(playback.abort);
 
+            if let Some(end_offset) = end_offset {
+                audio_decode_handler = audio_decode_handler.with_end_time(end_offset);
+            }
+
             moosicbox_assert::assert_or_err!(
                 audio_decode_handler.contains_outputs_to_open(),
                 crate::symphonia::PlaybackError::NoAudioOutputs,
@@ -179,22 +260,51 @@ impl Player for LocalPlayer {
             Ok(audio_decode_handler)
         };
 
-        let response = play_media_source_async(
-            mss,
-            &playable_track.hint,
-            get_handler,
-            true,
-            true,
-            None,
-            seek,
-        )
-        .await;
+        let response = match track_source {
+            TrackSource::DecodedAhead(decoded) => {
+                play_decoded_ahead_async(decoded, get_handler).await
+            }
+            TrackSource::Playable(playable_track) => {
+                let mss = MediaSourceStream::new(
+                    playable_track.source,
+                    MediaSourceStreamOptions::default(),
+                );
+
+                play_media_source_async(
+                    mss,
+                    &playable_track.hint,
+                    get_handler,
+                    true,
+                    true,
+                    None,
+                    seek,
+                )
+                .await
+            }
+        };
 
         if let Err(e) = response {
             log::error!("Failed to play playback: {e:?}");
+            self.gapless_output.lock().unwrap().take();
             return Err(e.into());
         }
 
+        let has_next_track = self
+            .playback
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|x| x.next_position().is_some());
+
+        if !has_next_track {
+            let gapless_output = self.gapless_output.lock().unwrap().take();
+            if let Some(gapless_output) = gapless_output {
+                if let Err(e) = gapless_output.finish().await {
+                    log::debug!("Gapless output already closed: {e:?}");
+                }
+            }
+        }
+
         log::info!("Finished playback for track_id={}", track_id);
 
         Ok(())
@@ -234,6 +344,7 @@ impl Player for LocalPlayer {
             }
         }
 
+        self.close_gapless_output();
         self.playback.write().unwrap().as_mut().unwrap().abort = CancellationToken::new();
 
         Ok(())
@@ -263,6 +374,7 @@ impl Player for LocalPlayer {
             log::trace!("Playback successfully paused");
         }
 
+        self.close_gapless_output();
         self.playback.write().unwrap().as_mut().unwrap().abort = CancellationToken::new();
 
         Ok(())
@@ -313,6 +425,12 @@ impl Player for LocalPlayer {
                 .unwrap()
                 .clone()
                 .map(Into::into),
+            bit_perfect: self
+                .gapless_output
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_some_and(GaplessOutput::is_bit_perfect),
         })
     }
 
@@ -340,6 +458,8 @@ impl LocalPlayer {
             playback: Arc::new(RwLock::new(None)),
             receiver: Arc::new(tokio::sync::RwLock::new(None)),
             playback_handler: Arc::new(RwLock::new(None)),
+            gapless_output: Arc::new(Mutex::new(None)),
+            preloaded: Arc::new(tokio::sync::Mutex::new(None)),
         })
     }
 
@@ -348,4 +468,168 @@ impl LocalPlayer {
         self.output.replace(Arc::new(Mutex::new(output)));
         self
     }
+
+    fn track_playback_type(&self, track: &Track) -> PlaybackType {
+        #[allow(clippy::match_wildcard_for_single_variants)]
+        match track.track_source {
+            TrackApiSource::Local => self.playback_type,
+            #[allow(unreachable_patterns)]
+            _ => PlaybackType::Stream,
+        }
+    }
+
+    /// Returns the `GaplessOutput` that is still open for the `Playback`, opening a new one if
+    /// the previous `Playback` has been stopped.
+    async fn gapless_output(
+        &self,
+        abort: &CancellationToken,
+    ) -> Result<GaplessOutput, PlayerError> {
+        let existing = self.gapless_output.lock().unwrap().clone();
+
+        if let Some(existing) = existing.filter(GaplessOutput::is_open) {
+            return Ok(existing);
+        }
+
+        let factory = self
+            .output
+            .as_ref()
+            .ok_or(PlayerError::NoAudioOutputs)?
+            .lock()
+            .unwrap()
+            .clone();
+
+        log::debug!("Opening gapless output for {}", factory.id);
+        let gapless_output = GaplessOutput::open(factory, abort.clone()).await?;
+        self.gapless_output
+            .lock()
+            .unwrap()
+            .replace(gapless_output.clone());
+
+        Ok(gapless_output)
+    }
+
+    /// Whether the `track` is DSD that is passed through to the output as `DoP` frames instead
+    /// of being decoded to PCM.
+    fn is_dop_playback(&self, track: &Track, quality: PlaybackQuality) -> bool {
+        quality.format == AudioFormat::Source
+            && track.bit_depth == Some(1)
+            && track.sample_rate.is_some_and(|sample_rate| {
+                self.output
+                    .as_ref()
+                    .is_some_and(|output| output.lock().unwrap().supports_dop(sample_rate))
+            })
+    }
+
+    /// Whether tracks at the `quality` are played bit-perfect, at their native format and
+    /// without any volume or DSP applied, when the output supports their format.
+    fn is_bit_perfect_playback(&self, quality: PlaybackQuality) -> bool {
+        quality.format == AudioFormat::Source
+            && self
+                .output
+                .as_ref()
+                .is_some_and(|output| output.lock().unwrap().supports_bit_perfect())
+    }
+
+    fn close_gapless_output(&self) {
+        self.gapless_output.lock().unwrap().take();
+
+        // A preload that is still in progress is discarded once it is taken, since its abort
+        // token will have been cancelled
+        if let Ok(mut preloaded) = self.preloaded.try_lock() {
+            preloaded.take();
+        }
+    }
+
+    /// Where the `track` starts and ends in its file. A track that is only part of its file is cut
+    /// out of the file while playing it. Streamed tracks are already cut by the server.
+    fn track_offsets(&self, track: &Track) -> (Option<f64>, Option<f64>) {
+        if matches!(
+            (self.track_playback_type(track), track.api_source),
+            (
+                PlaybackType::File | PlaybackType::Default,
+                ApiSource::Library
+            )
+        ) {
+            (track.start_offset, track.end_offset)
+        } else {
+            (None, None)
+        }
+    }
+
+    /// Takes the preloaded track if it is the `track` that is about to be played.
+    async fn take_preloaded(
+        &self,
+        track: &Track,
+        playback: &Playback,
+        dop: bool,
+    ) -> Option<flume::Receiver<DecodedAhead>> {
+        let preloaded = self.preloaded.lock().await.take()?;
+
+        (preloaded.track_id == track.id
+            && preloaded.api_source == track.api_source
+            && preloaded.quality == playback.quality
+            && preloaded.dop == dop
+            && !preloaded.abort.is_cancelled())
+        .then_some(preloaded.decoded)
+    }
+
+    /// Opens the track that will play after the current one in the background and starts
+    /// decoding it, so its audio is ready as soon as the current track finishes.
+    fn preload_next_track(&self, playback: &Playback) {
+        let Some(track) = playback
+            .next_position()
+            .and_then(|position| playback.tracks.get(position as usize))
+            .cloned()
+        else {
+            return;
+        };
+
+        let preloaded = self.preloaded.clone();
+        let playback_type = self.track_playback_type(&track);
+        let (start_offset, end_offset) = self.track_offsets(&track);
+        let source = self.source.clone();
+        let quality = playback.quality;
+        let dop = self.is_dop_playback(&track, quality);
+        let abort = playback.abort.clone();
+
+        moosicbox_task::spawn("player: local player preload", async move {
+            // Hold the lock while opening the track so the next `trigger_play` waits for it
+            let mut preloaded = preloaded.lock().await;
+            preloaded.take();
+
+            log::debug!("Preloading next track_id={}", track.id);
+
+            match track_or_id_to_playable(playback_type, &track, quality, &source, abort.clone())
+                .await
+            {
+                Ok(playable_track) => {
+                    let mss = MediaSourceStream::new(
+                        playable_track.source,
+                        MediaSourceStreamOptions::default(),
+                    );
+                    let decoded = decode_ahead(
+                        mss,
+                        &playable_track.hint,
+                        start_offset,
+                        end_offset,
+                        dop,
+                        DECODE_AHEAD_BUFFERS,
+                        abort.clone(),
+                    );
+
+                    preloaded.replace(PreloadedTrack {
+                        track_id: track.id.clone(),
+                        api_source: track.api_source,
+                        quality,
+                        dop,
+                        abort,
+                        decoded,
+                    });
+                }
+                Err(e) => {
+                    log::debug!("Failed to preload track_id={}: {e:?}", track.id);
+                }
+            }
+        });
+    }
 }
@@ -1,5 +1,8 @@
 #![allow(clippy::module_name_repetitions)]
 
+use std::sync::{atomic::Ordering, Arc};
+
+use atomic_float::AtomicF64;
 use flume::Receiver;
 use moosicbox_audio_decoder::{AudioDecodeError, AudioDecodeHandler};
 use moosicbox_audio_output::encoder::AudioEncoder;
@@ -11,7 +14,11 @@ use symphonia::core::{
 };
 use thiserror::Error;
 
-use super::symphonia_unsync::{play_media_source, PlaybackError};
+use super::{
+    dsp::DspProcessor,
+    symphonia_unsync::{play_media_source, PlaybackError},
+    volume_mixer::mix_volume,
+};
 
 #[derive(Debug, Error)]
 pub enum SignalChainError {
@@ -21,6 +28,43 @@ pub enum SignalChainError {
     Empty,
 }
 
+/// A stage that processes the decoded samples in place, before they are resampled and encoded.
+pub trait SignalProcessor: Send {
+    fn process(&mut self, buf: &mut AudioBuffer<f32>);
+}
+
+/// The DSP stage of an audio output: its preamp and equalizer, then the volume and
+/// ReplayGain, then its limiter.
+pub struct DspStage {
+    dsp: Option<DspProcessor>,
+    volume: Arc<AtomicF64>,
+    replay_gain: f64,
+}
+
+impl DspStage {
+    /// Without an `audio_output_id` only the volume and ReplayGain are applied.
+    #[must_use]
+    pub fn new(audio_output_id: Option<String>, volume: Arc<AtomicF64>, replay_gain: f64) -> Self {
+        Self {
+            dsp: audio_output_id.map(DspProcessor::new),
+            volume,
+            replay_gain,
+        }
+    }
+}
+
+impl SignalProcessor for DspStage {
+    fn process(&mut self, buf: &mut AudioBuffer<f32>) {
+        if let Some(dsp) = &mut self.dsp {
+            dsp.process(buf);
+        }
+        mix_volume(buf, self.volume.load(Ordering::SeqCst) * self.replay_gain);
+        if let Some(dsp) = &mut self.dsp {
+            dsp.limit(buf);
+        }
+    }
+}
+
 type CreateAudioDecodeStream = Box<dyn (FnOnce() -> AudioDecodeHandler) + Send + 'static>;
 type CreateAudioEncoder = Box<dyn (FnOnce() -> Box<dyn AudioEncoder>) + Send + 'static>;
 
@@ -65,6 +109,14 @@ impl SignalChain {
         self
     }
 
+    #[must_use]
+    pub fn with_processor<P: SignalProcessor + 'static>(mut self, processor: P) -> Self {
+        if let Some(step) = self.steps.pop() {
+            self.steps.push(step.with_processor(processor));
+        }
+        self
+    }
+
     #[must_use]
     pub fn with_verify(mut self, verify: bool) -> Self {
         if let Some(step) = self.steps.pop() {
@@ -144,6 +196,7 @@ pub struct SignalChainStep {
     audio_output_handler: Option<CreateAudioDecodeStream>,
     encoder: Option<CreateAudioEncoder>,
     resampler: Option<Resampler<f32>>,
+    processors: Vec<Box<dyn SignalProcessor>>,
     enable_gapless: bool,
     verify: bool,
     seek: Option<f64>,
@@ -157,6 +210,7 @@ impl SignalChainStep {
             audio_output_handler: None,
             encoder: None,
             resampler: None,
+            processors: vec![],
             enable_gapless: true,
             verify: true,
             seek: None,
@@ -193,6 +247,12 @@ impl SignalChainStep {
         self
     }
 
+    #[must_use]
+    pub fn with_processor<P: SignalProcessor + 'static>(mut self, processor: P) -> Self {
+        self.processors.push(Box::new(processor));
+        self
+    }
+
     #[must_use]
     pub const fn with_verify(mut self, verify: bool) -> Self {
         self.verify = verify;
@@ -229,6 +289,7 @@ impl SignalChainStep {
         Ok(SignalChainStepProcessor {
             encoder,
             resampler: self.resampler,
+            processors: self.processors,
             receiver,
             overflow: vec![],
         })
@@ -244,6 +305,7 @@ impl Default for SignalChainStep {
 pub struct SignalChainStepProcessor {
     encoder: Option<Box<dyn AudioEncoder>>,
     resampler: Option<Resampler<f32>>,
+    processors: Vec<Box<dyn SignalProcessor>>,
     receiver: Receiver<AudioBuffer<f32>>,
     overflow: Vec<u8>,
 }
@@ -273,12 +335,16 @@ impl std::io::Read for SignalChainStepProcessor {
 
         let bytes = loop {
             log::debug!("Waiting for samples from receiver...");
-            let audio = self
+            let mut audio = self
                 .receiver
                 .recv_timeout(std::time::Duration::from_millis(1000))
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::TimedOut, e))?;
             log::debug!("Received {} frames from receiver", audio.frames());
 
+            for processor in &mut self.processors {
+                processor.process(&mut audio);
+            }
+
             let audio = if let Some(resampler) = &mut self.resampler {
                 let channels = audio.spec().channels.count();
 
@@ -341,3 +407,33 @@ pub enum SignalChainProcessorError {
     #[error(transparent)]
     AudioDecode(#[from] AudioDecodeError),
 }
+
+#[cfg(test)]
+mod test {
+    use symphonia::core::audio::{Channels, SignalSpec};
+
+    use super::*;
+
+    #[test]
+    fn dsp_stage_without_an_output_applies_the_volume_and_replay_gain() {
+        let mut buf = AudioBuffer::<f32>::new(
+            4,
+            SignalSpec::new(48_000, Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+        );
+        buf.render_reserved(Some(4));
+        buf.chan_mut(0).fill(0.5);
+        buf.chan_mut(1).fill(-0.25);
+
+        let volume = Arc::new(AtomicF64::new(0.5));
+        let mut stage = DspStage::new(None, volume.clone(), 0.5);
+        stage.process(&mut buf);
+
+        assert_eq!(buf.chan(0), &[0.125; 4]);
+        assert_eq!(buf.chan(1), &[-0.0625; 4]);
+
+        volume.store(1.0, Ordering::SeqCst);
+        stage.process(&mut buf);
+
+        assert_eq!(buf.chan(0), &[0.0625; 4]);
+    }
+}
@@ -1,15 +1,19 @@
 use std::{fs::File, path::Path};
 
-use moosicbox_audio_decoder::{decode, AudioDecodeHandler, DecodeError};
+use moosicbox_audio_decoder::{
+    decode, AudioDecode, AudioDecodeError, AudioDecodeHandler, DecodeError,
+};
 use symphonia::core::{
+    audio::{AudioBuffer, Signal as _},
     codecs::DecoderOptions,
-    formats::FormatOptions,
+    formats::{FormatOptions, Packet, Track},
     io::{MediaSourceStream, MediaSourceStreamOptions},
     meta::MetadataOptions,
     probe::Hint,
 };
 use thiserror::Error;
 use tokio::task::JoinError;
+use tokio_util::sync::CancellationToken;
 
 impl From<std::io::Error> for PlaybackError {
     fn from(err: std::io::Error) -> Self {
@@ -146,7 +150,7 @@ fn play_media_source(
     let metadata_opts = MetadataOptions::default();
 
     // Probe the media source stream for metadata and get the format reader.
-    match symphonia::default::get_probe().format(
+    match moosicbox_audio_decoder::get_probe().format(
         hint,
         media_source_stream,
         &format_opts,
@@ -175,3 +179,169 @@ fn play_media_source(
         }
     }
 }
+
+/// A message from a track that is being decoded ahead of playing it.
+pub enum DecodedAhead {
+    /// The track that the following buffers are decoded from. Sent once, before the first
+    /// buffer.
+    Track(Box<Track>),
+    Buffer(AudioBuffer<f32>, Packet),
+    /// Decoding the track failed after the buffers before it.
+    Failed(PlaybackError),
+}
+
+/// Sends the decoded buffers of a track that is decoded ahead to the receiver of
+/// `decode_ahead`.
+struct DecodeAheadOutput {
+    sender: flume::Sender<DecodedAhead>,
+    sent_track: bool,
+}
+
+impl AudioDecode for DecodeAheadOutput {
+    fn decoded(
+        &mut self,
+        decoded: AudioBuffer<f32>,
+        packet: &Packet,
+        track: &Track,
+    ) -> Result<(), AudioDecodeError> {
+        // The receiver has been dropped once the decoded track is no longer going to be played
+        if !self.sent_track {
+            self.sender
+                .send(DecodedAhead::Track(Box::new(track.clone())))
+                .map_err(|_| AudioDecodeError::Interrupt)?;
+            self.sent_track = true;
+        }
+
+        self.sender
+            .send(DecodedAhead::Buffer(decoded, packet.clone()))
+            .map_err(|_| AudioDecodeError::Interrupt)
+    }
+}
+
+/// Starts decoding the `media_source_stream` in the background, before it is played. The decode
+/// stays at most `buffers` decoded buffers ahead of what has been received, and stops once the
+/// returned receiver is dropped.
+#[allow(clippy::too_many_arguments)]
+pub fn decode_ahead(
+    media_source_stream: MediaSourceStream,
+    hint: &Hint,
+    seek: Option<f64>,
+    end_time: Option<f64>,
+    dop: bool,
+    buffers: usize,
+    cancellation_token: CancellationToken,
+) -> flume::Receiver<DecodedAhead> {
+    let (sender, receiver) = flume::bounded(buffers);
+    let hint = hint.clone();
+
+    moosicbox_task::spawn_blocking("player: Decode ahead", move || {
+        let output_sender = sender.clone();
+        let mut handler = AudioDecodeHandler::new()
+            .with_dop(dop)
+            .with_output(Box::new(move |_spec, _duration| {
+                Ok(Box::new(DecodeAheadOutput {
+                    sender: output_sender.clone(),
+                    sent_track: false,
+                }))
+            }))
+            .with_cancellation_token(cancellation_token);
+
+        if let Some(end_time) = end_time {
+            handler = handler.with_end_time(end_time);
+        }
+
+        match play_media_source(
+            media_source_stream,
+            &hint,
+            &mut handler,
+            true,
+            true,
+            None,
+            seek,
+        ) {
+            Ok(_)
+            | Err(PlaybackError::Decode(DecodeError::AudioDecode(AudioDecodeError::Interrupt))) => {
+            }
+            Err(e) => {
+                log::debug!("decode_ahead: Failed to decode: {e:?}");
+                // The receiver may have already been dropped
+                let _ = sender.send(DecodedAhead::Failed(e));
+            }
+        }
+    });
+
+    receiver
+}
+
+/// Plays a track that was decoded ahead with `decode_ahead`, passing its decoded buffers through
+/// the `AudioDecodeHandler`.
+///
+/// # Errors
+///
+/// * If the track failed to decode
+/// * If the decoded audio failed to write to the outputs
+/// * If the tokio task failed to join
+pub async fn play_decoded_ahead_async(
+    receiver: flume::Receiver<DecodedAhead>,
+    get_audio_output_handler: impl FnOnce() -> GetAudioDecodeHandlerRet + Send + 'static,
+) -> Result<i32, PlaybackError> {
+    moosicbox_task::spawn_blocking("player: Play decoded ahead", move || {
+        let mut handler = get_audio_output_handler()?;
+        play_decoded_ahead(&receiver, &mut handler)
+    })
+    .await?
+}
+
+fn play_decoded_ahead(
+    receiver: &flume::Receiver<DecodedAhead>,
+    audio_decode_handler: &mut AudioDecodeHandler,
+) -> Result<i32, PlaybackError> {
+    let is_cancelled = |handler: &AudioDecodeHandler| {
+        handler
+            .cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    };
+
+    let mut track = None;
+
+    // The decode ahead ends, dropping its sender, when the track has been fully decoded
+    while let Ok(message) = receiver.recv() {
+        if is_cancelled(audio_decode_handler) {
+            return Ok(2);
+        }
+
+        match message {
+            DecodedAhead::Track(decoded_track) => {
+                track.replace(decoded_track);
+            }
+            DecodedAhead::Buffer(decoded, packet) => {
+                let track = track.as_ref().ok_or(PlaybackError::InvalidSource)?;
+
+                if audio_decode_handler.contains_outputs_to_open() {
+                    let spec = *decoded.spec();
+                    let duration = decoded.capacity() as u64;
+
+                    audio_decode_handler
+                        .try_open(spec, duration)
+                        .map_err(DecodeError::from)?;
+                }
+
+                match audio_decode_handler.write(decoded, &packet, track) {
+                    Ok(()) => {}
+                    Err(AudioDecodeError::StreamEnd) => break,
+                    Err(e) => return Err(DecodeError::from(e).into()),
+                }
+            }
+            DecodedAhead::Failed(e) => return Err(e),
+        }
+    }
+
+    if is_cancelled(audio_decode_handler) {
+        return Ok(2);
+    }
+
+    audio_decode_handler.flush().map_err(DecodeError::from)?;
+
+    Ok(0)
+}
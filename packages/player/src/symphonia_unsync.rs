@@ -43,7 +43,7 @@ pub fn play_media_source(
     let metadata_opts = MetadataOptions::default();
 
     // Probe the media source stream for metadata and get the format reader.
-    match symphonia::default::get_probe().format(
+    match moosicbox_audio_decoder::get_probe().format(
         hint,
         media_source_stream,
         &format_opts,
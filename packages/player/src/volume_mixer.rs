@@ -1,9 +1,41 @@
+use moosicbox_music_models::ReplayGain;
+pub use moosicbox_session::models::ReplayGainMode;
 use symphonia::core::{
     audio::{AudioBuffer, Signal},
     conv::{FromSample, IntoSample},
     sample::Sample,
 };
 
+/// The linear gain to apply to a track's samples for the given `ReplayGainMode`.
+///
+/// Falls back to the track gain and peak in album mode (and vice versa) when the preferred gain
+/// is missing, so the peak always comes from the same scope as the gain, and limits the gain so
+/// that the stored peak doesn't clip.
+#[must_use]
+pub fn replay_gain_factor(replay_gain: &ReplayGain, mode: ReplayGainMode) -> f64 {
+    let track = (replay_gain.track_gain, replay_gain.track_peak);
+    let album = (replay_gain.album_gain, replay_gain.album_peak);
+
+    let (gain, peak) = match mode {
+        ReplayGainMode::Off => return 1.0,
+        ReplayGainMode::Track if track.0.is_some() => track,
+        ReplayGainMode::Album if album.0.is_some() => album,
+        ReplayGainMode::Track => album,
+        ReplayGainMode::Album => track,
+    };
+
+    let Some(gain) = gain else {
+        return 1.0;
+    };
+
+    let factor = 10_f64.powf(gain / 20.0);
+
+    match peak {
+        Some(peak) if peak > 0.0 => factor.min(1.0 / peak),
+        _ => factor,
+    }
+}
+
 pub fn mix_volume<S>(input: &mut AudioBuffer<S>, volume: f64)
 where
     S: Sample + FromSample<f32> + IntoSample<f32>,
@@ -19,3 +51,137 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    const GAIN: ReplayGain = ReplayGain {
+        track_gain: Some(-6.0),
+        track_peak: Some(0.5),
+        album_gain: Some(-3.0),
+        album_peak: Some(0.8),
+    };
+
+    #[test]
+    fn replay_gain_factor_is_unity_when_off() {
+        assert_close(replay_gain_factor(&GAIN, ReplayGainMode::Off), 1.0);
+    }
+
+    #[test]
+    fn replay_gain_factor_uses_the_track_gain_in_track_mode() {
+        assert_close(
+            replay_gain_factor(&GAIN, ReplayGainMode::Track),
+            10_f64.powf(-6.0 / 20.0),
+        );
+    }
+
+    #[test]
+    fn replay_gain_factor_uses_the_album_gain_in_album_mode() {
+        assert_close(
+            replay_gain_factor(&GAIN, ReplayGainMode::Album),
+            10_f64.powf(-3.0 / 20.0),
+        );
+    }
+
+    #[test]
+    fn replay_gain_factor_falls_back_to_the_other_gain_when_missing() {
+        let track_only = ReplayGain {
+            album_gain: None,
+            album_peak: None,
+            ..GAIN
+        };
+        let album_only = ReplayGain {
+            track_gain: None,
+            track_peak: None,
+            ..GAIN
+        };
+
+        assert_close(
+            replay_gain_factor(&track_only, ReplayGainMode::Album),
+            10_f64.powf(-6.0 / 20.0),
+        );
+        assert_close(
+            replay_gain_factor(&album_only, ReplayGainMode::Track),
+            10_f64.powf(-3.0 / 20.0),
+        );
+    }
+
+    #[test]
+    fn replay_gain_factor_takes_the_fallback_peak_from_the_same_scope_as_the_gain() {
+        // The album peak alone would limit the album gain, but not the track gain it falls
+        // back to
+        let track_gain_with_album_peak = ReplayGain {
+            track_gain: Some(6.0),
+            track_peak: Some(0.25),
+            album_gain: None,
+            album_peak: Some(0.9),
+        };
+        assert_close(
+            replay_gain_factor(&track_gain_with_album_peak, ReplayGainMode::Album),
+            10_f64.powf(6.0 / 20.0),
+        );
+
+        // The track gain falls back to the album gain, which is limited by the album peak
+        // rather than the track peak
+        let album_gain_with_track_peak = ReplayGain {
+            track_gain: None,
+            track_peak: Some(0.9),
+            album_gain: Some(12.0),
+            album_peak: Some(0.5),
+        };
+        assert_close(
+            replay_gain_factor(&album_gain_with_track_peak, ReplayGainMode::Track),
+            2.0,
+        );
+    }
+
+    #[test]
+    fn replay_gain_factor_is_unity_without_any_gain() {
+        let peaks_only = ReplayGain {
+            track_gain: None,
+            album_gain: None,
+            ..GAIN
+        };
+
+        assert_close(
+            replay_gain_factor(&ReplayGain::default(), ReplayGainMode::Track),
+            1.0,
+        );
+        assert_close(replay_gain_factor(&peaks_only, ReplayGainMode::Album), 1.0);
+    }
+
+    #[test]
+    fn replay_gain_factor_limits_positive_gain_to_avoid_clipping() {
+        let loud = ReplayGain {
+            track_gain: Some(12.0),
+            track_peak: Some(0.5),
+            album_gain: None,
+            album_peak: None,
+        };
+
+        assert_close(replay_gain_factor(&loud, ReplayGainMode::Track), 2.0);
+    }
+
+    #[test]
+    fn replay_gain_factor_ignores_a_non_positive_peak() {
+        let no_peak = ReplayGain {
+            track_gain: Some(6.0),
+            track_peak: Some(0.0),
+            album_gain: None,
+            album_peak: None,
+        };
+
+        assert_close(
+            replay_gain_factor(&no_peak, ReplayGainMode::Track),
+            10_f64.powf(6.0 / 20.0),
+        );
+    }
+}
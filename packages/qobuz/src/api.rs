@@ -271,6 +271,16 @@ impl From<ApiQobuzTrack> for moosicbox_music_models::api::ApiTrack {
             overall_bitrate: None,
             sample_rate: None,
             channels: None,
+            replay_gain: None,
+            play_count: None,
+            disc_number: None,
+            disc_total: None,
+            genres: vec![],
+            composer: None,
+            track_artist: None,
+            isrc: None,
+            start_offset: None,
+            end_offset: None,
             track_source: TrackApiSource::Qobuz,
             api_source: ApiSource::Qobuz,
             sources: ApiSources::default().with_source(ApiSource::Qobuz, value.id.into()),
@@ -9,7 +9,9 @@ pub mod db;
 pub mod models;
 
 use itertools::Itertools;
-use models::{QobuzAlbum, QobuzArtist, QobuzRelease, QobuzSearchResults, QobuzTrack};
+use models::{
+    QobuzAlbum, QobuzArtist, QobuzPlaylist, QobuzRelease, QobuzSearchResults, QobuzTrack,
+};
 #[cfg(feature = "db")]
 use moosicbox_database::profiles::LibraryDatabase;
 #[cfg(feature = "db")]
@@ -20,10 +22,11 @@ use moosicbox_json_utils::database::DatabaseFetchError;
 use moosicbox_files::get_content_length;
 use moosicbox_menu_models::AlbumVersion;
 use moosicbox_music_models::{
-    id::Id, Album, AlbumType, ApiSource, Artist, AudioFormat, PlaybackQuality, Track,
+    id::Id, Album, AlbumType, ApiSource, Artist, AudioFormat, PlaybackQuality, Playlist, Track,
     TrackApiSource,
 };
 use moosicbox_paging::{Page, PagingResponse, PagingResult};
+use moosicbox_search::api::models::ApiSearchResultsResponse;
 use reqwest::StatusCode;
 use std::{
     collections::HashMap,
@@ -44,8 +47,9 @@ use moosicbox_music_api::{
         ImageCoverSize, ImageCoverSource, TrackAudioQuality, TrackOrder, TrackOrderDirection,
         TrackSource,
     },
-    AddAlbumError, AddArtistError, AddTrackError, AlbumError, AlbumsError, ArtistAlbumsError,
-    ArtistError, ArtistsError, MusicApi, RemoveAlbumError, RemoveArtistError, RemoveTrackError,
+    AddAlbumError, AddArtistError, AddPlaylistTrackError, AddTrackError, AlbumError, AlbumsError,
+    ArtistAlbumsError, ArtistError, ArtistsError, MusicApi, PlaylistError, PlaylistsError,
+    RemoveAlbumError, RemoveArtistError, RemovePlaylistTrackError, RemoveTrackError, SearchError,
     TrackError, TrackOrId, TracksError,
 };
 use serde::{Deserialize, Serialize};
@@ -434,6 +438,10 @@ enum QobuzApiEndpoint {
     Favorites,
     AddFavorites,
     RemoveFavorites,
+    UserPlaylists,
+    Playlist,
+    AddPlaylistTracks,
+    RemovePlaylistTracks,
     Search,
 }
 
@@ -453,6 +461,10 @@ impl ToUrl for QobuzApiEndpoint {
             Self::Favorites => format!("{QOBUZ_API_BASE_URL}/favorite/getUserFavorites"),
             Self::AddFavorites => format!("{QOBUZ_API_BASE_URL}/favorite/create"),
             Self::RemoveFavorites => format!("{QOBUZ_API_BASE_URL}/favorite/delete"),
+            Self::UserPlaylists => format!("{QOBUZ_API_BASE_URL}/playlist/getUserPlaylists"),
+            Self::Playlist => format!("{QOBUZ_API_BASE_URL}/playlist/get"),
+            Self::AddPlaylistTracks => format!("{QOBUZ_API_BASE_URL}/playlist/addTracks"),
+            Self::RemovePlaylistTracks => format!("{QOBUZ_API_BASE_URL}/playlist/deleteTracks"),
             Self::Search => format!("{QOBUZ_API_BASE_URL}/catalog/search"),
         }
     }
@@ -1626,6 +1638,359 @@ pub async fn remove_favorite_track(
     Ok(())
 }
 
+#[derive(Debug, Error)]
+pub enum QobuzUserPlaylistsError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+#[allow(clippy::too_many_arguments)]
+#[async_recursion]
+pub async fn user_playlists(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    access_token: Option<String>,
+    app_id: Option<String>,
+) -> PagingResult<QobuzPlaylist, QobuzUserPlaylistsError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let url = qobuz_api_endpoint!(
+        UserPlaylists,
+        &[],
+        &[
+            ("offset", &offset.to_string()),
+            ("limit", &limit.to_string()),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        app_id.clone(),
+        access_token.clone(),
+    )
+    .await?;
+
+    log::trace!("Received user playlists response: {value:?}");
+
+    let items: Vec<QobuzPlaylist> = value.to_nested_value(&["playlists", "items"])?;
+    let total = value.to_nested_value(&["playlists", "total"])?;
+
+    #[cfg(feature = "db")]
+    let db = db.clone();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            #[cfg(feature = "db")]
+            let db = db.clone();
+            let access_token = access_token.clone();
+            let app_id = app_id.clone();
+
+            Box::pin(async move {
+                user_playlists(
+                    #[cfg(feature = "db")]
+                    &db,
+                    Some(offset),
+                    Some(limit),
+                    access_token,
+                    app_id,
+                )
+                .await
+            })
+        }))),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum QobuzPlaylistError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("Not found")]
+    NotFound,
+}
+
+/// # Errors
+///
+/// * If the API request failed
+/// * If there is a database error while saving the configuration
+/// * If there is no access token available
+/// * If there is no app ID available
+/// * If failed to fetch the Qobuz login source
+/// * If failed to fetch the Qobuz app bundle
+/// * If failed to fetch the Qobuz app secrets
+/// * If failed to fetch the Qobuz app ID
+/// * If failed to parse the JSON response
+pub async fn playlist(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    access_token: Option<String>,
+    app_id: Option<String>,
+) -> Result<QobuzPlaylist, QobuzPlaylistError> {
+    let url = qobuz_api_endpoint!(
+        Playlist,
+        &[],
+        &[("playlist_id", &playlist_id.to_string()), ("limit", "0")]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        app_id,
+        access_token,
+    )
+    .await
+    .map_err(|e| match e {
+        AuthenticatedRequestError::RequestFailed(404, _) => QobuzPlaylistError::NotFound,
+        _ => e.into(),
+    })?;
+
+    log::trace!("Received playlist response: {value:?}");
+
+    Ok(value.to_value_type()?)
+}
+
+#[derive(Debug, Error)]
+pub enum QobuzPlaylistTracksError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+#[allow(clippy::too_many_arguments)]
+#[async_recursion]
+pub async fn playlist_tracks(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    access_token: Option<String>,
+    app_id: Option<String>,
+) -> PagingResult<QobuzTrack, QobuzPlaylistTracksError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let url = qobuz_api_endpoint!(
+        Playlist,
+        &[],
+        &[
+            ("playlist_id", &playlist_id.to_string()),
+            ("extra", "tracks"),
+            ("offset", &offset.to_string()),
+            ("limit", &limit.to_string()),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        app_id.clone(),
+        access_token.clone(),
+    )
+    .await?;
+
+    log::trace!("Received playlist tracks response: {value:?}");
+
+    let items: Vec<QobuzTrack> = value.to_nested_value(&["tracks", "items"])?;
+    let total = value.to_nested_value(&["tracks", "total"])?;
+
+    #[cfg(feature = "db")]
+    let db = db.clone();
+    let playlist_id = playlist_id.clone();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            #[cfg(feature = "db")]
+            let db = db.clone();
+            let playlist_id = playlist_id.clone();
+            let access_token = access_token.clone();
+            let app_id = app_id.clone();
+
+            Box::pin(async move {
+                playlist_tracks(
+                    #[cfg(feature = "db")]
+                    &db,
+                    &playlist_id,
+                    Some(offset),
+                    Some(limit),
+                    access_token,
+                    app_id,
+                )
+                .await
+            })
+        }))),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum QobuzAddPlaylistTrackError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+}
+
+/// # Errors
+///
+/// * If the API request failed
+/// * If there is a database error while saving the configuration
+/// * If there is no access token available
+/// * If there is no app ID available
+/// * If failed to fetch the Qobuz login source
+/// * If failed to fetch the Qobuz app bundle
+/// * If failed to fetch the Qobuz app secrets
+/// * If failed to fetch the Qobuz app ID
+/// * If failed to parse the JSON response
+pub async fn add_playlist_track(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    track_id: &Id,
+    access_token: Option<String>,
+    app_id: Option<String>,
+) -> Result<(), QobuzAddPlaylistTrackError> {
+    let url = qobuz_api_endpoint!(
+        AddPlaylistTracks,
+        &[],
+        &[
+            ("playlist_id", &playlist_id.to_string()),
+            ("track_ids", &track_id.to_string()),
+            ("no_duplicate", "false"),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        app_id,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received add playlist track response: {value:?}");
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum QobuzRemovePlaylistTrackError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// Removes every occurrence of the given track from the playlist.
+///
+/// # Errors
+///
+/// * If the API request failed
+/// * If there is a database error while saving the configuration
+/// * If there is no access token available
+/// * If there is no app ID available
+/// * If failed to fetch the Qobuz login source
+/// * If failed to fetch the Qobuz app bundle
+/// * If failed to fetch the Qobuz app secrets
+/// * If failed to fetch the Qobuz app ID
+/// * If failed to parse the JSON response
+pub async fn remove_playlist_track(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    track_id: &Id,
+    access_token: Option<String>,
+    app_id: Option<String>,
+) -> Result<(), QobuzRemovePlaylistTrackError> {
+    let track_id = track_id.to_string();
+    let mut playlist_track_ids = vec![];
+    let mut offset = 0;
+    let limit = 500;
+
+    loop {
+        let url = qobuz_api_endpoint!(
+            Playlist,
+            &[],
+            &[
+                ("playlist_id", &playlist_id.to_string()),
+                ("extra", "tracks"),
+                ("offset", &offset.to_string()),
+                ("limit", &limit.to_string()),
+            ]
+        );
+
+        let value = authenticated_request(
+            #[cfg(feature = "db")]
+            db,
+            &url,
+            app_id.clone(),
+            access_token.clone(),
+        )
+        .await?;
+
+        let items = value.to_nested_value::<Vec<&Value>>(&["tracks", "items"])?;
+        let total: u32 = value.to_nested_value(&["tracks", "total"])?;
+
+        for item in &items {
+            let id: u64 = item.to_value("id")?;
+
+            if id.to_string() == track_id {
+                let playlist_track_id: u64 = item.to_value("playlist_track_id")?;
+                playlist_track_ids.push(playlist_track_id.to_string());
+            }
+        }
+
+        offset += limit;
+
+        if items.is_empty() || offset >= total {
+            break;
+        }
+    }
+
+    if playlist_track_ids.is_empty() {
+        return Ok(());
+    }
+
+    let url = qobuz_api_endpoint!(
+        RemovePlaylistTracks,
+        &[],
+        &[
+            ("playlist_id", &playlist_id.to_string()),
+            ("playlist_track_ids", &playlist_track_ids.join(",")),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        app_id,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received remove playlist track response: {value:?}");
+
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, EnumString, AsRefStr, PartialEq, Eq, Clone, Copy)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
@@ -2091,6 +2456,42 @@ impl From<QobuzRemoveFavoriteTrackError> for RemoveTrackError {
     }
 }
 
+impl From<QobuzUserPlaylistsError> for PlaylistsError {
+    fn from(err: QobuzUserPlaylistsError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<QobuzPlaylistError> for PlaylistError {
+    fn from(err: QobuzPlaylistError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<QobuzPlaylistTracksError> for TracksError {
+    fn from(err: QobuzPlaylistTracksError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<QobuzAddPlaylistTrackError> for AddPlaylistTrackError {
+    fn from(err: QobuzAddPlaylistTrackError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<QobuzRemovePlaylistTrackError> for RemovePlaylistTrackError {
+    fn from(err: QobuzRemovePlaylistTrackError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<QobuzSearchError> for SearchError {
+    fn from(err: QobuzSearchError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
 pub struct QobuzMusicApi {
     #[cfg(feature = "db")]
     db: LibraryDatabase,
@@ -2551,6 +2952,111 @@ impl MusicApi for QobuzMusicApi {
             .await
             .map_err(|e| TrackError::Other(Box::new(e)))?)
     }
+
+    async fn playlists(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Playlist, PlaylistsError> {
+        Ok(user_playlists(
+            #[cfg(feature = "db")]
+            &self.db,
+            offset,
+            limit,
+            None,
+            None,
+        )
+        .await?
+        .inner_into())
+    }
+
+    async fn playlist(&self, playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError> {
+        Ok(
+            match playlist(
+                #[cfg(feature = "db")]
+                &self.db,
+                playlist_id,
+                None,
+                None,
+            )
+            .await
+            {
+                Ok(playlist) => Some(playlist.into()),
+                Err(QobuzPlaylistError::NotFound) => None,
+                Err(e) => return Err(e.into()),
+            },
+        )
+    }
+
+    async fn playlist_tracks(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Track, TracksError> {
+        Ok(playlist_tracks(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            offset,
+            limit,
+            None,
+            None,
+        )
+        .await?
+        .inner_into())
+    }
+
+    async fn add_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), AddPlaylistTrackError> {
+        Ok(add_playlist_track(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            track_id,
+            None,
+            None,
+        )
+        .await?)
+    }
+
+    async fn remove_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), RemovePlaylistTrackError> {
+        Ok(remove_playlist_track(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            track_id,
+            None,
+            None,
+        )
+        .await?)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<ApiSearchResultsResponse, SearchError> {
+        Ok(search(
+            #[cfg(feature = "db")]
+            &self.db,
+            query,
+            offset.map(|x| x as usize),
+            limit.map(|x| x as usize),
+            None,
+            None,
+        )
+        .await?
+        .into())
+    }
 }
 
 #[cfg(test)]
@@ -10,7 +10,7 @@ use moosicbox_music_api::models::ImageCoverSize;
 use moosicbox_music_models::{
     api::{ApiAlbum, ApiArtist},
     id::TryFromIdError,
-    Album, AlbumSource, ApiSource, ApiSources, Artist, Track, TrackApiSource,
+    Album, AlbumSource, ApiSource, ApiSources, Artist, Playlist, Track, TrackApiSource,
 };
 use moosicbox_search::api::models::{
     ApiGlobalAlbumSearchResult, ApiGlobalArtistSearchResult, ApiGlobalSearchResult,
@@ -215,6 +215,7 @@ pub struct QobuzAlbum {
     pub genre: QobuzGenre,
     pub maximum_channel_count: u16,
     pub maximum_sampling_rate: f32,
+    pub upc: Option<String>,
 }
 
 impl From<QobuzAlbum> for Album {
@@ -232,6 +233,7 @@ impl From<QobuzAlbum> for Album {
             directory: None,
             blur: false,
             versions: vec![],
+            upc: value.upc.filter(|x| !x.is_empty()),
             album_source: AlbumSource::Qobuz,
             api_source: ApiSource::Qobuz,
             artist_sources: ApiSources::default()
@@ -367,6 +369,7 @@ impl AsModelResult<QobuzAlbum, ParseError> for Value {
             maximum_sampling_rate: self
                 .to_value("maximum_sampling_rate")
                 .or_else(|_| self.to_nested_value(&["audio_info", "maximum_sampling_rate"]))?,
+            upc: self.to_value("upc")?,
         })
     }
 }
@@ -477,6 +480,14 @@ impl From<QobuzTrack> for Track {
             overall_bitrate: None,
             sample_rate: None,
             channels: None,
+            replay_gain: None,
+            play_count: None,
+            disc_number: None,
+            disc_total: None,
+            genres: vec![],
+            composer: None,
+            track_artist: None,
+            isrc: Some(value.isrc).filter(|x| !x.is_empty()),
             track_source: TrackApiSource::Qobuz,
             api_source: ApiSource::Qobuz,
             sources: ApiSources::default().with_source(ApiSource::Qobuz, value.id.into()),
@@ -580,6 +591,60 @@ impl AsModelResult<QobuzTrack, ParseError> for Value {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct QobuzPlaylist {
+    pub id: u64,
+    pub name: String,
+    pub description: Option<String>,
+    pub tracks_count: u32,
+    pub duration: u32,
+    pub images: Vec<String>,
+}
+
+impl From<QobuzPlaylist> for Playlist {
+    fn from(value: QobuzPlaylist) -> Self {
+        let artwork = value.cover_url();
+
+        Self {
+            id: value.id.into(),
+            title: value.name,
+            description: value.description.filter(|x| !x.is_empty()),
+            artwork,
+            track_count: Some(value.tracks_count),
+            api_source: ApiSource::Qobuz,
+        }
+    }
+}
+
+impl QobuzPlaylist {
+    #[must_use]
+    pub fn cover_url(&self) -> Option<String> {
+        self.images.first().cloned()
+    }
+}
+
+impl ToValueType<QobuzPlaylist> for &Value {
+    fn to_value_type(self) -> Result<QobuzPlaylist, ParseError> {
+        self.as_model()
+    }
+}
+
+impl AsModelResult<QobuzPlaylist, ParseError> for Value {
+    fn as_model(&self) -> Result<QobuzPlaylist, ParseError> {
+        let images: Option<Vec<String>> = self.to_value("images300")?;
+
+        Ok(QobuzPlaylist {
+            id: self.to_value("id")?,
+            name: self.to_value("name")?,
+            description: self.to_value("description")?,
+            tracks_count: self.to_value("tracks_count")?,
+            duration: self.to_value("duration")?,
+            images: images.unwrap_or_default(),
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct QobuzArtist {
@@ -670,6 +735,7 @@ impl From<QobuzRelease> for QobuzAlbum {
             qobuz_id: 0,
             popularity: 0,
             genre: QobuzGenre::default(),
+            upc: None,
         }
     }
 }
@@ -758,6 +824,7 @@ impl From<QobuzSearchResults> for ApiSearchResultsResponse {
         Self {
             position,
             results: [artists, albums, tracks].concat(),
+            facets: None,
         }
     }
 }
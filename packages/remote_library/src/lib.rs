@@ -11,16 +11,19 @@ use moosicbox_music_api::{
         ImageCoverSize, ImageCoverSource, TrackAudioQuality, TrackOrder, TrackOrderDirection,
         TrackSource,
     },
-    AddAlbumError, AddArtistError, AddTrackError, AlbumError, AlbumsError, ArtistAlbumsError,
-    ArtistError, ArtistsError, MusicApi, RemoveAlbumError, RemoveArtistError, RemoveTrackError,
-    TrackError, TrackOrId, TracksError,
+    AddAlbumError, AddArtistError, AddPlaylistTrackError, AddTrackError, AlbumError, AlbumsError,
+    ArtistAlbumsError, ArtistError, ArtistsError, MusicApi, PlaylistError, PlaylistsError,
+    RemoveAlbumError, RemoveArtistError, RemovePlaylistTrackError, RemoveTrackError, SearchError,
+    TrackError, TrackLyricsError, TrackOrId, TracksError,
 };
 use moosicbox_music_models::{
-    api::{ApiAlbum, ApiTrack},
+    api::{ApiAlbum, ApiPlaylist, ApiTrack},
     id::Id,
-    Album, AlbumType, ApiSource, Artist, PlaybackQuality, Track,
+    lyrics::Lyrics,
+    Album, AlbumType, ApiSource, Artist, PlaybackQuality, Playlist, Track,
 };
 use moosicbox_paging::{Page, PagingResponse, PagingResult};
+use moosicbox_search::api::models::ApiSearchResultsResponse;
 use reqwest::Client;
 use thiserror::Error;
 
@@ -385,4 +388,243 @@ impl MusicApi for RemoteLibraryMusicApi {
     ) -> Result<Option<u64>, TrackError> {
         unimplemented!("Fetching track size is not implemented")
     }
+
+    async fn track_lyrics(&self, track_id: &Id) -> Result<Option<Lyrics>, TrackLyricsError> {
+        let request = self
+            .client
+            .request(
+                reqwest::Method::GET,
+                format!(
+                    "{host}/library/tracks/lyrics?trackId={track_id}&source={source}",
+                    host = self.host,
+                    source = self.api_source
+                ),
+            )
+            .header("moosicbox-profile", &self.profile);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| TrackLyricsError::Other(Box::new(e)))?;
+
+        if !response.status().is_success() {
+            if response.status() == 404 {
+                return Ok(None);
+            }
+            return Err(TrackLyricsError::Other(Box::new(
+                RequestError::Unsuccessful(format!("Status {}", response.status())),
+            )));
+        }
+
+        let value = response
+            .json()
+            .await
+            .map_err(|e| TrackLyricsError::Other(Box::new(e)))?;
+
+        Ok(Some(value))
+    }
+
+    async fn playlists(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Playlist, PlaylistsError> {
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(100);
+
+        let request = self
+            .client
+            .request(
+                reqwest::Method::GET,
+                format!(
+                    "{host}/menu/playlists?source={source}&offset={offset}&limit={limit}",
+                    host = self.host,
+                    source = self.api_source
+                ),
+            )
+            .header("moosicbox-profile", &self.profile);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| PlaylistsError::Other(Box::new(e)))?;
+
+        if !response.status().is_success() {
+            return Err(PlaylistsError::Other(Box::new(RequestError::Unsuccessful(
+                format!("Status {}", response.status()),
+            ))));
+        }
+
+        let page: Page<ApiPlaylist> = response
+            .json()
+            .await
+            .map_err(|e| PlaylistsError::Other(Box::new(e)))?;
+
+        Ok(PagingResponse::new(page.map(Into::into), {
+            let api = self.clone();
+
+            move |offset, limit| {
+                let api = api.clone();
+                Box::pin(async move { api.playlists(Some(offset), Some(limit)).await })
+            }
+        }))
+    }
+
+    async fn playlist(&self, playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError> {
+        let request = self
+            .client
+            .request(
+                reqwest::Method::GET,
+                format!(
+                    "{host}/menu/playlist?playlistId={playlist_id}&source={source}",
+                    host = self.host,
+                    source = self.api_source
+                ),
+            )
+            .header("moosicbox-profile", &self.profile);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| PlaylistError::Other(Box::new(e)))?;
+
+        if !response.status().is_success() {
+            if response.status() == 404 {
+                return Ok(None);
+            }
+            return Err(PlaylistError::Other(Box::new(RequestError::Unsuccessful(
+                format!("Status {}", response.status()),
+            ))));
+        }
+
+        let value: ApiPlaylist = response
+            .json()
+            .await
+            .map_err(|e| PlaylistError::Other(Box::new(e)))?;
+
+        Ok(Some(value.into()))
+    }
+
+    async fn playlist_tracks(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Track, TracksError> {
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(100);
+
+        let request = self
+            .client
+            .request(
+                reqwest::Method::GET,
+                format!(
+                    "{host}/menu/playlist/tracks?playlistId={playlist_id}&source={source}&offset={offset}&limit={limit}",
+                    host = self.host,
+                    source = self.api_source
+                ),
+            )
+            .header("moosicbox-profile", &self.profile);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| TracksError::Other(Box::new(e)))?;
+
+        if !response.status().is_success() {
+            return Err(TracksError::Other(Box::new(RequestError::Unsuccessful(
+                format!("Status {}", response.status()),
+            ))));
+        }
+
+        let page: Page<ApiTrack> = response
+            .json()
+            .await
+            .map_err(|e| TracksError::Other(Box::new(e)))?;
+
+        Ok(PagingResponse::new(page.map(Into::into), {
+            let api = self.clone();
+            let playlist_id = playlist_id.clone();
+
+            move |offset, limit| {
+                let api = api.clone();
+                let playlist_id = playlist_id.clone();
+                Box::pin(async move {
+                    api.playlist_tracks(&playlist_id, Some(offset), Some(limit))
+                        .await
+                })
+            }
+        }))
+    }
+
+    async fn add_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), AddPlaylistTrackError> {
+        let request = self
+            .client
+            .request(
+                reqwest::Method::POST,
+                format!(
+                    "{host}/menu/playlist/tracks?playlistId={playlist_id}&trackId={track_id}&source={source}",
+                    host = self.host,
+                    source = self.api_source
+                ),
+            )
+            .header("moosicbox-profile", &self.profile);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AddPlaylistTrackError::Other(Box::new(e)))?;
+
+        if !response.status().is_success() {
+            return Err(AddPlaylistTrackError::Other(Box::new(
+                RequestError::Unsuccessful(format!("Status {}", response.status())),
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn remove_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), RemovePlaylistTrackError> {
+        let request = self
+            .client
+            .request(
+                reqwest::Method::DELETE,
+                format!(
+                    "{host}/menu/playlist/tracks?playlistId={playlist_id}&trackId={track_id}&source={source}",
+                    host = self.host,
+                    source = self.api_source
+                ),
+            )
+            .header("moosicbox-profile", &self.profile);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| RemovePlaylistTrackError::Other(Box::new(e)))?;
+
+        if !response.status().is_success() {
+            return Err(RemovePlaylistTrackError::Other(Box::new(
+                RequestError::Unsuccessful(format!("Status {}", response.status())),
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        _query: &str,
+        _offset: Option<u32>,
+        _limit: Option<u32>,
+    ) -> Result<ApiSearchResultsResponse, SearchError> {
+        Err(SearchError::Unsupported)
+    }
 }
@@ -9,7 +9,7 @@ use actix_web::{
     Result, Scope,
 };
 use moosicbox_auth::NonTunnelRequestAuthorized;
-use moosicbox_database::profiles::LibraryDatabase;
+use moosicbox_database::profiles::{api::ProfileName, LibraryDatabase};
 use moosicbox_music_api::MusicApis;
 use serde::Deserialize;
 use serde_json::Value;
@@ -87,6 +87,7 @@ pub struct ScanQuery {
 #[post("/run-scan")]
 pub async fn run_scan_endpoint(
     query: web::Query<ScanQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     music_apis: MusicApis,
     _: NonTunnelRequestAuthorized,
@@ -106,7 +107,7 @@ pub async fn run_scan_endpoint(
         })
         .transpose()?;
 
-    run_scan(origins, &db, music_apis)
+    run_scan(origins, &profile.0, &db, music_apis)
         .await
         .map_err(|e| ErrorInternalServerError(format!("Failed to scan: {e:?}")))?;
 
@@ -135,6 +136,7 @@ pub async fn run_scan_endpoint(
 #[post("/start-scan")]
 pub async fn start_scan_endpoint(
     query: web::Query<ScanQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     music_apis: MusicApis,
     _: NonTunnelRequestAuthorized,
@@ -155,10 +157,12 @@ pub async fn start_scan_endpoint(
         .transpose()?;
 
     moosicbox_task::spawn("scan", async move {
-        run_scan(origins, &db, music_apis).await.map_err(|e| {
-            moosicbox_assert::die_or_error!("Scan error: {e:?}");
-            e
-        })?;
+        run_scan(origins, &profile.0, &db, music_apis)
+            .await
+            .map_err(|e| {
+                moosicbox_assert::die_or_error!("Scan error: {e:?}");
+                e
+            })?;
 
         Ok::<_, ScanError>(())
     });
@@ -196,6 +200,7 @@ pub struct ScanPathQuery {
 #[post("/run-scan-path")]
 pub async fn run_scan_path_endpoint(
     query: web::Query<ScanPathQuery>,
+    profile: ProfileName,
     db: LibraryDatabase,
     music_apis: MusicApis,
     _: NonTunnelRequestAuthorized,
@@ -205,13 +210,19 @@ pub async fn run_scan_path_endpoint(
     });
 
     scanner
-        .scan(music_apis, &db)
+        .scan(music_apis, &profile.0, &db)
         .await
         .map_err(|e| ErrorInternalServerError(format!("Failed to scan: {e:?}")))?;
 
-    crate::local::scan(&query.path, &db, crate::CANCELLATION_TOKEN.clone(), scanner)
-        .await
-        .map_err(|e| ErrorInternalServerError(format!("Failed to scan: {e:?}")))?;
+    crate::local::scan(
+        &query.path,
+        &profile.0,
+        &db,
+        crate::CANCELLATION_TOKEN.clone(),
+        scanner,
+    )
+    .await
+    .map_err(|e| ErrorInternalServerError(format!("Failed to scan: {e:?}")))?;
 
     Ok(Json(serde_json::json!({"success": true})))
 }
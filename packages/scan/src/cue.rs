@@ -0,0 +1,475 @@
+//! Parsing for CUE sheets, which describe how a single audio file (e.g. a
+//! whole-album rip) is split into tracks.
+
+use std::path::Path;
+
+/// CUE sheet timestamps are `mm:ss:ff` where there are 75 frames per second
+const FRAMES_PER_SECOND: f64 = 75.0;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CueSheet {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub date: Option<String>,
+    pub genre: Option<String>,
+    pub files: Vec<CueFile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CueFile {
+    pub name: String,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CueTrack {
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub isrc: Option<String>,
+    /// Where the track starts in its file, in seconds
+    pub start: f64,
+}
+
+/// A track that is still being parsed. Tracks are only kept once they have a
+/// start index.
+struct PendingTrack {
+    track: CueTrack,
+    start: Option<f64>,
+}
+
+impl CueSheet {
+    /// Parses the contents of a CUE sheet. Unknown and malformed commands are
+    /// ignored, as are tracks that have no start index.
+    #[must_use]
+    pub fn parse(contents: &str) -> Self {
+        let mut sheet = Self::default();
+        let mut pending: Option<PendingTrack> = None;
+
+        for line in contents.trim_start_matches('\u{feff}').lines() {
+            let line = line.trim();
+            let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+
+            match command.to_uppercase().as_str() {
+                "REM" => {
+                    let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    let value = parse_value(value);
+                    match key.to_uppercase().as_str() {
+                        "DATE" => sheet.date = value,
+                        "GENRE" => sheet.genre = value,
+                        _ => {}
+                    }
+                }
+                "TITLE" => match pending.as_mut() {
+                    Some(pending) => pending.track.title = parse_value(rest),
+                    None => sheet.title = parse_value(rest),
+                },
+                "PERFORMER" => match pending.as_mut() {
+                    Some(pending) => pending.track.performer = parse_value(rest),
+                    None => sheet.performer = parse_value(rest),
+                },
+                "ISRC" => {
+                    if let Some(pending) = pending.as_mut() {
+                        pending.track.isrc = parse_value(rest);
+                    }
+                }
+                "FILE" => {
+                    sheet.finish_track(pending.take());
+                    if let Some(name) = parse_file_name(rest) {
+                        sheet.files.push(CueFile {
+                            name,
+                            tracks: vec![],
+                        });
+                    }
+                }
+                "TRACK" => {
+                    sheet.finish_track(pending.take());
+                    let mut parts = rest.split_whitespace();
+                    let number = parts.next().and_then(|x| x.parse::<u32>().ok());
+                    let is_audio = parts
+                        .next()
+                        .is_some_and(|x| x.eq_ignore_ascii_case("AUDIO"));
+
+                    if let (Some(number), true) = (number, is_audio) {
+                        pending = Some(PendingTrack {
+                            track: CueTrack {
+                                number,
+                                ..Default::default()
+                            },
+                            start: None,
+                        });
+                    }
+                }
+                "INDEX" => {
+                    if let Some(pending) = pending.as_mut() {
+                        let mut parts = rest.split_whitespace();
+                        let index = parts.next().and_then(|x| x.parse::<u32>().ok());
+                        let time = parts.next().and_then(parse_timestamp);
+
+                        // Index 1 is where the track starts. Index 0 is the
+                        // pregap, which is only used if there is no index 1.
+                        match (index, time) {
+                            (Some(1), Some(time)) => pending.start = Some(time),
+                            (Some(0), Some(time)) if pending.start.is_none() => {
+                                pending.start = Some(time);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        sheet.finish_track(pending);
+
+        sheet
+    }
+
+    fn finish_track(&mut self, pending: Option<PendingTrack>) {
+        let Some(PendingTrack {
+            mut track,
+            start: Some(start),
+        }) = pending
+        else {
+            return;
+        };
+        let Some(file) = self.files.last_mut() else {
+            return;
+        };
+
+        track.start = start;
+        file.tracks.push(track);
+    }
+
+    /// Reads and parses the CUE sheet at `path`. Sheets that aren't valid
+    /// UTF-8 are read lossily.
+    ///
+    /// # Errors
+    ///
+    /// * If the file could not be read
+    pub fn read(path: &Path) -> Result<Self, std::io::Error> {
+        let bytes = std::fs::read(path)?;
+
+        Ok(Self::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// Finds the CUE sheet next to `path` that splits it into several tracks,
+    /// along with the sheet's entry for the file.
+    ///
+    /// The file is matched by name, falling back to the file stem since rips
+    /// are often re-encoded after the sheet was written (e.g. `.wav` to
+    /// `.flac`).
+    #[must_use]
+    pub fn find(path: &Path) -> Option<(Self, CueFile)> {
+        let file_name = path.file_name()?.to_str()?;
+        let file_stem = path.file_stem()?.to_str()?;
+        let entries = std::fs::read_dir(path.parent()?).ok()?;
+
+        let mut cue_paths = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|x| {
+                x.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+            })
+            .collect::<Vec<_>>();
+
+        cue_paths.sort();
+
+        for cue_path in cue_paths {
+            let mut sheet = match Self::read(&cue_path) {
+                Ok(sheet) => sheet,
+                Err(e) => {
+                    log::warn!("Failed to read CUE sheet {cue_path:?}: {e:?}");
+                    continue;
+                }
+            };
+
+            let index = sheet
+                .files
+                .iter()
+                .position(|file| cue_file_name(file).eq_ignore_ascii_case(file_name))
+                .or_else(|| {
+                    sheet.files.iter().position(|file| {
+                        Path::new(cue_file_name(file))
+                            .file_stem()
+                            .and_then(|x| x.to_str())
+                            .is_some_and(|x| x.eq_ignore_ascii_case(file_stem))
+                    })
+                });
+
+            if let Some(index) = index {
+                if sheet.files[index].tracks.len() > 1 {
+                    let file = sheet.files.swap_remove(index);
+                    return Some((sheet, file));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// The file name of a `FILE` entry, without any directories the sheet was
+/// written with
+fn cue_file_name(file: &CueFile) -> &str {
+    file.name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(file.name.as_str())
+}
+
+/// Parses a command value, which is either quoted or the rest of the line.
+///
+/// A quoted value runs until the last quote on the line, so that quotes inside
+/// of it are kept whether or not they are escaped with a backslash.
+fn parse_value(value: &str) -> Option<String> {
+    let value = value.trim();
+
+    let value = value.strip_prefix('"').map_or_else(
+        || value.to_string(),
+        |quoted| {
+            quoted
+                .rfind('"')
+                .map_or(quoted, |end| &quoted[..end])
+                .replace("\\\"", "\"")
+        },
+    );
+
+    Some(value.trim().to_string()).filter(|x| !x.is_empty())
+}
+
+/// Parses the value of a `FILE` command, which is the file name followed by
+/// the file type (e.g. `"album.flac" WAVE`)
+fn parse_file_name(value: &str) -> Option<String> {
+    let value = value.trim();
+
+    if value.starts_with('"') {
+        return parse_value(value);
+    }
+
+    parse_value(
+        value
+            .rsplit_once(char::is_whitespace)
+            .map_or(value, |(name, _)| name),
+    )
+}
+
+/// Parses a `mm:ss:ff` timestamp into seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let mut parts = value.split(':').map(|x| x.parse::<u32>().ok());
+    let minutes = parts.next()??;
+    let seconds = parts.next()??;
+    let frames = parts.next()??;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(f64::from(minutes) * 60.0 + f64::from(seconds) + f64::from(frames) / FRAMES_PER_SECOND)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn parse_timestamp_converts_frames_to_seconds() {
+        assert_close(parse_timestamp("00:00:00").unwrap(), 0.0);
+        assert_close(parse_timestamp("00:00:75").unwrap(), 1.0);
+        assert_close(parse_timestamp("00:01:15").unwrap(), 1.2);
+        assert_close(parse_timestamp("03:25:60").unwrap(), 205.8);
+        assert_close(parse_timestamp("120:00:00").unwrap(), 7200.0);
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_malformed_timestamps() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("01:02"), None);
+        assert_eq!(parse_timestamp("01:02:03:04"), None);
+        assert_eq!(parse_timestamp("01:xx:03"), None);
+        assert_eq!(parse_timestamp("-1:02:03"), None);
+    }
+
+    #[test]
+    fn parse_reads_the_sheet_and_track_metadata() {
+        let sheet = CueSheet::parse(
+            "\u{feff}REM GENRE \"Rock\"
+REM DATE 1999
+PERFORMER \"The Band\"
+TITLE \"The Album\"
+FILE \"The Album.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"First\"
+    PERFORMER \"Guest\"
+    ISRC USABC9900001
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE \"Second\"
+    INDEX 00 04:00:00
+    INDEX 01 04:02:37
+",
+        );
+
+        assert_eq!(sheet.title.as_deref(), Some("The Album"));
+        assert_eq!(sheet.performer.as_deref(), Some("The Band"));
+        assert_eq!(sheet.date.as_deref(), Some("1999"));
+        assert_eq!(sheet.genre.as_deref(), Some("Rock"));
+        assert_eq!(sheet.files.len(), 1);
+
+        let file = &sheet.files[0];
+        assert_eq!(file.name, "The Album.flac");
+        assert_eq!(file.tracks.len(), 2);
+        assert_eq!(file.tracks[0].title.as_deref(), Some("First"));
+        assert_eq!(file.tracks[0].performer.as_deref(), Some("Guest"));
+        assert_eq!(file.tracks[0].isrc.as_deref(), Some("USABC9900001"));
+        assert_close(file.tracks[0].start, 0.0);
+        assert_eq!(file.tracks[1].number, 2);
+        assert_eq!(file.tracks[1].performer, None);
+        // Index 1 wins over the pregap
+        assert_close(file.tracks[1].start, 242.0 + 37.0 / 75.0);
+    }
+
+    #[test]
+    fn parse_uses_the_pregap_when_there_is_no_index_1() {
+        let sheet = CueSheet::parse(
+            "FILE \"a.wav\" WAVE
+  TRACK 01 AUDIO
+    INDEX 00 00:01:00
+",
+        );
+
+        assert_close(sheet.files[0].tracks[0].start, 1.0);
+    }
+
+    #[test]
+    fn parse_drops_tracks_without_an_index_and_non_audio_tracks() {
+        let sheet = CueSheet::parse(
+            "FILE \"a.bin\" BINARY
+  TRACK 01 MODE1/2352
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE \"No index\"
+  TRACK 03 AUDIO
+    INDEX 01 00:10:00
+",
+        );
+
+        let numbers = sheet.files[0]
+            .tracks
+            .iter()
+            .map(|x| x.number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec![3]);
+    }
+
+    #[test]
+    fn parse_assigns_tracks_to_the_file_they_follow() {
+        let sheet = CueSheet::parse(
+            "FILE \"disc 1.flac\" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 03:00:00
+FILE \"disc 2.flac\" WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+",
+        );
+
+        let files = sheet
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.name.as_str(),
+                    file.tracks.iter().map(|x| x.number).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec![("disc 1.flac", vec![1, 2]), ("disc 2.flac", vec![3])]
+        );
+    }
+
+    #[test]
+    fn parse_keeps_quotes_inside_of_quoted_values() {
+        let sheet = CueSheet::parse(
+            r#"FILE "album.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Say \"Hello\""
+    PERFORMER "The "Quoted" Band"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE Unquoted Title
+    INDEX 01 01:00:00
+"#,
+        );
+
+        let tracks = &sheet.files[0].tracks;
+        assert_eq!(tracks[0].title.as_deref(), Some("Say \"Hello\""));
+        assert_eq!(tracks[0].performer.as_deref(), Some("The \"Quoted\" Band"));
+        assert_eq!(tracks[1].title.as_deref(), Some("Unquoted Title"));
+    }
+
+    #[test]
+    fn parse_reads_unquoted_file_names_without_the_file_type() {
+        let sheet = CueSheet::parse("FILE album name.flac WAVE\n");
+
+        assert_eq!(sheet.files[0].name, "album name.flac");
+    }
+
+    #[test]
+    fn cue_file_name_strips_directories() {
+        let file = |name: &str| CueFile {
+            name: name.to_string(),
+            tracks: vec![],
+        };
+
+        assert_eq!(cue_file_name(&file("C:\\rips\\album.wav")), "album.wav");
+        assert_eq!(cue_file_name(&file("rips/album.wav")), "album.wav");
+        assert_eq!(cue_file_name(&file("album.wav")), "album.wav");
+    }
+
+    #[test]
+    fn find_matches_the_sheet_by_file_name_or_stem() {
+        let directory =
+            std::env::temp_dir().join(format!("moosicbox_scan_cue_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let sheet = "FILE \"album.wav\" WAVE
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 01 03:00:00
+FILE \"single.wav\" WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+";
+        std::fs::write(directory.join("album.cue"), sheet).unwrap();
+
+        // Re-encoded after the sheet was written
+        let (_, file) = CueSheet::find(&directory.join("album.flac")).unwrap();
+        assert_eq!(file.name, "album.wav");
+        assert_eq!(file.tracks.len(), 2);
+
+        let (_, file) = CueSheet::find(&directory.join("ALBUM.wav")).unwrap();
+        assert_eq!(file.name, "album.wav");
+
+        // A file with a single track isn't split
+        assert!(CueSheet::find(&directory.join("single.wav")).is_none());
+        assert!(CueSheet::find(&directory.join("other.flac")).is_none());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,9 +1,15 @@
+#[cfg(feature = "local")]
+use moosicbox_database::{
+    boxed,
+    query::{escape_like, identifier},
+    DatabaseValue,
+};
 use moosicbox_database::{profiles::LibraryDatabase, query::FilterableQuery};
 use moosicbox_json_utils::{database::DatabaseFetchError, ToValueType};
 
 use crate::ScanOrigin;
 
-use self::models::ScanLocation;
+use self::models::{ScanFile, ScanLocation};
 
 pub mod models;
 
@@ -110,3 +116,172 @@ pub async fn get_scan_locations_for_origin(
         .await?
         .to_value_type()?)
 }
+
+/// Get the files that were recorded by previous local scans under the given
+/// directory.
+///
+/// # Errors
+///
+/// * If a database error occurs
+#[cfg(feature = "local")]
+pub async fn get_scan_files(
+    db: &LibraryDatabase,
+    directory: &str,
+) -> Result<Vec<ScanFile>, DatabaseFetchError> {
+    let separator = std::path::MAIN_SEPARATOR;
+    let directory = directory.trim_end_matches(separator);
+    let prefix = format!("{directory}{separator}");
+
+    let files: Vec<ScanFile> = db
+        .select("scan_files")
+        .where_like("path", format!("{}%", escape_like(&prefix)))
+        .execute(db)
+        .await?
+        .to_value_type()?;
+
+    // `LIKE` is case insensitive on some databases
+    Ok(files
+        .into_iter()
+        .filter(|file| file.path.starts_with(&prefix))
+        .collect())
+}
+
+/// # Errors
+///
+/// * If a database error occurs
+#[cfg(feature = "local")]
+pub async fn add_scan_files(
+    db: &LibraryDatabase,
+    files: &[(String, u64, u64)],
+) -> Result<(), DatabaseFetchError> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    db.upsert_multi("scan_files")
+        .unique(boxed![identifier("path")])
+        .values(
+            files
+                .iter()
+                .map(|(path, size, modified)| {
+                    vec![
+                        ("path", DatabaseValue::String(path.clone())),
+                        ("size", DatabaseValue::UNumber(*size)),
+                        ("modified", DatabaseValue::UNumber(*modified)),
+                    ]
+                })
+                .collect(),
+        )
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// * If a database error occurs
+#[cfg(feature = "local")]
+pub async fn move_scan_file(
+    db: &LibraryDatabase,
+    from: &str,
+    to: &str,
+) -> Result<(), DatabaseFetchError> {
+    db.update("scan_files")
+        .where_eq("path", from)
+        .value("path", to)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// * If a database error occurs
+#[cfg(feature = "local")]
+pub async fn delete_scan_files(
+    db: &LibraryDatabase,
+    paths: &[String],
+) -> Result<(), DatabaseFetchError> {
+    for chunk in paths.chunks(1000) {
+        db.delete("scan_files")
+            .where_in("path", chunk.to_vec())
+            .execute(db)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "local"))]
+mod test {
+    use super::*;
+
+    fn scan_files_db() -> LibraryDatabase {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(include_str!(
+                "../../../schema/migrations/server/library/sqlite/2024-10-09-120000_create_scan_files/up.sql"
+            ))
+            .unwrap();
+        let database: Box<dyn moosicbox_database::Database> =
+            Box::new(moosicbox_database::rusqlite::RusqliteDatabase::new(
+                std::sync::Arc::new(tokio::sync::Mutex::new(connection)),
+            ));
+
+        std::sync::Arc::new(database).into()
+    }
+
+    fn path(parts: &[&str]) -> String {
+        parts.join(std::path::MAIN_SEPARATOR_STR)
+    }
+
+    #[tokio::test]
+    async fn get_scan_files_only_returns_files_under_the_directory() {
+        let db = scan_files_db();
+        let files = [
+            path(&["", "music", "a_b", "1.flac"]),
+            path(&["", "music", "a_b", "disc 2", "1.flac"]),
+            path(&["", "music", "axb", "1.flac"]),
+            path(&["", "music", "a_b2", "1.flac"]),
+            path(&["", "Music", "a_b", "1.flac"]),
+            path(&["", "music", "a%b", "1.flac"]),
+        ];
+        add_scan_files(
+            &db,
+            &files
+                .iter()
+                .map(|path| (path.clone(), 1, 1))
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+
+        let mut paths = get_scan_files(&db, &path(&["", "music", "a_b"]))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(paths, vec![files[0].clone(), files[1].clone()]);
+    }
+
+    #[tokio::test]
+    async fn get_scan_files_matches_a_directory_with_a_trailing_separator() {
+        let db = scan_files_db();
+        let file = path(&["", "music", "a%b", "1.flac"]);
+        add_scan_files(&db, &[(file.clone(), 1, 1)]).await.unwrap();
+
+        let files = get_scan_files(&db, &path(&["", "music", "a%b", ""]))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            files.into_iter().map(|x| x.path).collect::<Vec<_>>(),
+            vec![file]
+        );
+    }
+}
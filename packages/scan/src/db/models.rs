@@ -34,6 +34,38 @@ impl ToValueType<ScanOrigin> for DatabaseValue {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanFile {
+    pub id: u64,
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+    pub created: String,
+    pub updated: String,
+}
+
+impl MissingValue<ScanFile> for &moosicbox_database::Row {}
+impl ToValueType<ScanFile> for &moosicbox_database::Row {
+    fn to_value_type(self) -> Result<ScanFile, ParseError> {
+        Ok(ScanFile {
+            id: self.to_value("id")?,
+            path: self.to_value("path")?,
+            size: self.to_value("size")?,
+            modified: self.to_value("modified")?,
+            created: self.to_value("created")?,
+            updated: self.to_value("updated")?,
+        })
+    }
+}
+
+impl AsId for ScanFile {
+    fn as_id(&self) -> DatabaseValue {
+        #[allow(clippy::cast_possible_wrap)]
+        DatabaseValue::Number(self.id as i64)
+    }
+}
+
 impl MissingValue<ScanLocation> for &moosicbox_database::Row {}
 impl ToValueType<ScanLocation> for &moosicbox_database::Row {
     fn to_value_type(self) -> Result<ScanLocation, ParseError> {
@@ -41,6 +41,9 @@ pub enum ApiProgressEvent {
     Finished {
         scanned: usize,
         total: usize,
+        added: usize,
+        updated: usize,
+        removed: usize,
         task: ApiScanTask,
     },
     #[serde(rename_all = "camelCase")]
@@ -63,10 +66,16 @@ impl From<ProgressEvent> for Option<ApiProgressEvent> {
             ProgressEvent::ScanFinished {
                 scanned,
                 total,
+                added,
+                updated,
+                removed,
                 task,
             } => Some(ApiProgressEvent::Finished {
                 scanned,
                 total,
+                added,
+                updated,
+                removed,
                 task: task.into(),
             }),
             ProgressEvent::ScanCountUpdated {
@@ -98,6 +107,9 @@ pub enum ProgressEvent {
         task: ScanTask,
         scanned: usize,
         total: usize,
+        added: usize,
+        updated: usize,
+        removed: usize,
     },
     ScanCountUpdated {
         task: ScanTask,
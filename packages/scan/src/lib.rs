@@ -20,7 +20,13 @@ use tokio_util::sync::CancellationToken;
 #[cfg(feature = "api")]
 pub mod api;
 #[cfg(feature = "local")]
+pub mod cue;
+#[cfg(feature = "local")]
 pub mod local;
+#[cfg(feature = "loudness-analysis")]
+pub mod loudness;
+#[cfg(feature = "watcher")]
+pub mod watcher;
 
 pub mod db;
 pub mod event;
@@ -157,6 +163,9 @@ pub enum ScanError {
 pub struct Scanner {
     scanned: Arc<AtomicUsize>,
     total: Arc<AtomicUsize>,
+    added: Arc<AtomicUsize>,
+    updated: Arc<AtomicUsize>,
+    removed: Arc<AtomicUsize>,
     task: Arc<ScanTask>,
 }
 
@@ -203,6 +212,9 @@ impl Scanner {
         Self {
             scanned: Arc::new(AtomicUsize::new(0)),
             total: Arc::new(AtomicUsize::new(0)),
+            added: Arc::new(AtomicUsize::new(0)),
+            updated: Arc::new(AtomicUsize::new(0)),
+            removed: Arc::new(AtomicUsize::new(0)),
             task: Arc::new(task),
         }
     }
@@ -233,11 +245,15 @@ impl Scanner {
 
     #[allow(unused)]
     async fn on_scanned_track(&self) {
+        self.on_scanned_tracks(1).await;
+    }
+
+    async fn on_scanned_tracks(&self, count: usize) {
         let total = self.total.load(std::sync::atomic::Ordering::SeqCst);
         let scanned = self
             .scanned
-            .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
-            + 1;
+            .fetch_add(count, std::sync::atomic::Ordering::SeqCst)
+            + count;
         let event = ProgressEvent::ItemScanned {
             scanned,
             total,
@@ -251,14 +267,30 @@ impl Scanner {
         }
     }
 
+    #[cfg(feature = "local")]
+    fn on_files_changed(&self, added: usize, updated: usize, removed: usize) {
+        self.added
+            .fetch_add(added, std::sync::atomic::Ordering::SeqCst);
+        self.updated
+            .fetch_add(updated, std::sync::atomic::Ordering::SeqCst);
+        self.removed
+            .fetch_add(removed, std::sync::atomic::Ordering::SeqCst);
+    }
+
     #[allow(unused)]
     pub async fn on_scan_finished(&self) {
         let scanned = self.scanned.load(std::sync::atomic::Ordering::SeqCst);
         let total = self.total.load(std::sync::atomic::Ordering::SeqCst);
+        let added = self.added.load(std::sync::atomic::Ordering::SeqCst);
+        let updated = self.updated.load(std::sync::atomic::Ordering::SeqCst);
+        let removed = self.removed.load(std::sync::atomic::Ordering::SeqCst);
 
         let event = ProgressEvent::ScanFinished {
             scanned,
             total,
+            added,
+            updated,
+            removed,
             task: self.task.deref().clone(),
         };
 
@@ -274,15 +306,23 @@ impl Scanner {
     /// * If the scan fails
     /// * If a tokio task fails to join
     #[allow(clippy::uninhabited_references)]
-    pub async fn scan(&self, music_apis: MusicApis, db: &LibraryDatabase) -> Result<(), ScanError> {
+    pub async fn scan(
+        &self,
+        music_apis: MusicApis,
+        profile: &str,
+        db: &LibraryDatabase,
+    ) -> Result<(), ScanError> {
         self.scanned.store(0, std::sync::atomic::Ordering::SeqCst);
         self.total.store(0, std::sync::atomic::Ordering::SeqCst);
+        self.added.store(0, std::sync::atomic::Ordering::SeqCst);
+        self.updated.store(0, std::sync::atomic::Ordering::SeqCst);
+        self.removed.store(0, std::sync::atomic::Ordering::SeqCst);
 
         match &*self.task {
             #[cfg(feature = "local")]
-            ScanTask::Local { paths } => self.scan_local(db, paths).await?,
+            ScanTask::Local { paths } => self.scan_local(profile, db, paths).await?,
             ScanTask::Api { origin } => {
-                self.scan_music_api(&**music_apis.get((*origin).into())?, db)
+                self.scan_music_api(&**music_apis.get((*origin).into())?, profile, db)
                     .await?;
             }
         }
@@ -299,16 +339,18 @@ impl Scanner {
     #[cfg(feature = "local")]
     pub async fn scan_local(
         &self,
+        profile: &str,
         db: &LibraryDatabase,
         paths: &[String],
     ) -> Result<(), local::ScanError> {
         let handles = paths.iter().map(|path| {
+            let profile = profile.to_owned();
             let db = db.clone();
             let scanner = self.clone();
             let path = path.to_owned();
 
             moosicbox_task::spawn(&format!("scan_local: scan '{path}'"), async move {
-                local::scan(&path, &db, CANCELLATION_TOKEN.clone(), scanner).await
+                local::scan(&path, &profile, &db, CANCELLATION_TOKEN.clone(), scanner).await
             })
         });
 
@@ -326,6 +368,7 @@ impl Scanner {
     pub async fn scan_music_api(
         &self,
         api: &dyn MusicApi,
+        profile: &str,
         db: &LibraryDatabase,
     ) -> Result<(), music_api::ScanError> {
         let enabled_origins = get_enabled_scan_origins(db).await?;
@@ -342,7 +385,7 @@ impl Scanner {
             return Ok(());
         }
 
-        music_api::scan(api, db, CANCELLATION_TOKEN.clone(), Some(scanner)).await?;
+        music_api::scan(api, profile, db, CANCELLATION_TOKEN.clone(), Some(scanner)).await?;
 
         Ok(())
     }
@@ -398,6 +441,7 @@ pub async fn disable_scan_origin(
 /// * If the scan fails
 pub async fn run_scan(
     origins: Option<Vec<ScanOrigin>>,
+    profile: &str,
     db: &LibraryDatabase,
     music_apis: MusicApis,
 ) -> Result<(), ScanError> {
@@ -409,7 +453,7 @@ pub async fn run_scan(
     for origin in origins {
         Scanner::from_origin(db, origin)
             .await?
-            .scan(music_apis.clone(), db)
+            .scan(music_apis.clone(), profile, db)
             .await?;
     }
 
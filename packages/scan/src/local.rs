@@ -2,17 +2,28 @@ use async_recursion::async_recursion;
 use futures::Future;
 use moosicbox_audiotags::Tag;
 use moosicbox_database::profiles::LibraryDatabase;
-use moosicbox_files::{sanitize_filename, search_for_cover};
+use moosicbox_files::{files::transcode_cache, sanitize_filename, search_for_cover};
 use moosicbox_json_utils::database::DatabaseFetchError;
-use moosicbox_lofty::{AudioFile, ParseOptions};
-use moosicbox_music_models::{Album, ApiSource, Artist, AudioFormat, Track, TrackApiSource};
+use moosicbox_library::{
+    db::{
+        delete_empty_albums, delete_empty_artists, delete_track_sizes_by_track_id, delete_tracks,
+        get_tracks_by_files, move_album_directory, update_track_file,
+    },
+    models::LibraryTrack,
+};
+use moosicbox_lofty::{AudioFile, ItemKey, ParseOptions, TaggedFile, TaggedFileExt as _};
+use moosicbox_music_models::{
+    split_genres, Album, ApiSource, Artist, AudioFormat, ReplayGain, Track, TrackApiSource,
+};
 use regex::Regex;
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs::Metadata,
     num::ParseIntError,
     path::{Path, PathBuf},
     pin::Pin,
     sync::{Arc, LazyLock},
+    time::UNIX_EPOCH,
 };
 use thiserror::Error;
 use tokio::{
@@ -23,6 +34,8 @@ use tokio::{
 use tokio_util::sync::CancellationToken;
 
 use crate::{
+    cue::{CueFile, CueSheet},
+    db::{add_scan_files, delete_scan_files, get_scan_files, move_scan_file},
     output::{ScanOutput, UpdateDatabaseError},
     Scanner, CACHE_DIR,
 };
@@ -43,6 +56,9 @@ pub enum ScanError {
     UpdateDatabase(#[from] UpdateDatabaseError),
     #[error(transparent)]
     Lofty(#[from] moosicbox_lofty::LoftyError),
+    #[cfg(feature = "dsd")]
+    #[error(transparent)]
+    Dsd(#[from] symphonia::core::errors::Error),
 }
 
 /// # Errors
@@ -50,6 +66,7 @@ pub enum ScanError {
 /// * If the scan fails
 pub async fn scan(
     directory: &str,
+    profile: &str,
     db: &LibraryDatabase,
     token: CancellationToken,
     scanner: Scanner,
@@ -61,7 +78,326 @@ pub async fn scan(
     )
     .await?;
 
-    scan_items(items, db, token, scanner).await
+    let known = get_scan_files(db, directory)
+        .await?
+        .into_iter()
+        .map(|file| (file.path, (file.size, file.modified)))
+        .collect::<HashMap<_, _>>();
+
+    let mut cue_fingerprints = HashMap::new();
+    let found = items
+        .iter()
+        .filter_map(|item| match item {
+            ScanItem::Track { path, metadata, .. } => path.to_str().map(|path_str| {
+                (
+                    path_str.to_string(),
+                    track_fingerprint(path, metadata, &mut cue_fingerprints),
+                )
+            }),
+            ScanItem::AlbumCover { .. } | ScanItem::ArtistCover { .. } => None,
+        })
+        .collect::<Vec<_>>();
+
+    let changes = diff_scan_files(&known, &found);
+
+    let rescan = changes
+        .added
+        .iter()
+        .chain(&changes.updated)
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+    let to_scan = items
+        .into_iter()
+        .filter(|item| match item {
+            ScanItem::Track { path, .. } => path.to_str().map_or(true, |x| rescan.contains(x)),
+            ScanItem::AlbumCover { .. } | ScanItem::ArtistCover { .. } => true,
+        })
+        .collect::<Vec<_>>();
+
+    log::debug!(
+        "scan: directory={directory} unchanged={} added={} updated={} moved={} removed={}",
+        changes.unchanged,
+        changes.added.len(),
+        changes.updated.len(),
+        changes.moved.len(),
+        changes.removed.len()
+    );
+
+    if changes.unchanged + changes.moved.len() > 0 {
+        scanner
+            .on_scanned_tracks(changes.unchanged + changes.moved.len())
+            .await;
+    }
+
+    for path in &changes.updated {
+        invalidate_transcodes(path);
+    }
+
+    for (from, to) in &changes.moved {
+        log::debug!("scan: file moved from={from} to={to}");
+        update_track_file(db, from, to).await?;
+        move_scan_file(db, from, to).await?;
+        invalidate_transcodes(from);
+    }
+
+    for (from, to) in moved_directories(&changes.moved, &found) {
+        log::debug!("scan: album directory moved from={from} to={to}");
+        move_album_directory(db, &from, &to).await?;
+    }
+
+    for path in &changes.removed {
+        invalidate_transcodes(path);
+    }
+
+    let removed_count = remove_files(db, &changes.removed).await?;
+
+    if to_scan.is_empty() {
+        if !changes.moved.is_empty() || removed_count > 0 {
+            ScanOutput::new()
+                .reindex_global_search_index(profile, db)
+                .await?;
+        }
+    } else {
+        scan_items(to_scan, profile, db, token, scanner.clone()).await?;
+    }
+
+    scanner.on_files_changed(
+        changes.added.len(),
+        changes.updated.len() + changes.moved.len(),
+        removed_count,
+    );
+
+    Ok(())
+}
+
+/// How the track files found by a scan compare with the files recorded by the previous scans.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ScanChanges {
+    unchanged: usize,
+    added: Vec<String>,
+    updated: Vec<String>,
+    /// The `(from, to)` paths of the files that moved
+    moved: Vec<(String, String)>,
+    removed: Vec<String>,
+}
+
+/// Compares the `found` track files with the `known` files from the previous scans, both with
+/// their `(size, modified)` fingerprints.
+fn diff_scan_files(
+    known: &HashMap<String, (u64, u64)>,
+    found: &[(String, (u64, u64))],
+) -> ScanChanges {
+    let mut changes = ScanChanges::default();
+    let mut new_files = vec![];
+
+    for (path, fingerprint) in found {
+        match known.get(path) {
+            Some(known) if known == fingerprint => changes.unchanged += 1,
+            Some(_) => changes.updated.push(path.clone()),
+            None => new_files.push((path, *fingerprint)),
+        }
+    }
+
+    let found_paths = found
+        .iter()
+        .map(|(path, _)| path.as_str())
+        .collect::<HashSet<_>>();
+    let mut vanished = known
+        .iter()
+        .filter(|(path, _)| !found_paths.contains(path.as_str()))
+        .collect::<Vec<_>>();
+    vanished.sort_by(|a, b| a.0.cmp(b.0));
+
+    // A new file is treated as a moved file when its size and modification
+    // time match exactly one vanished file, and no other new file.
+    let mut fingerprint_counts: HashMap<(u64, u64), (usize, usize)> = HashMap::new();
+    for (_, fingerprint) in &vanished {
+        fingerprint_counts.entry(**fingerprint).or_default().0 += 1;
+    }
+    for (_, fingerprint) in &new_files {
+        fingerprint_counts.entry(*fingerprint).or_default().1 += 1;
+    }
+
+    for (path, fingerprint) in new_files {
+        if fingerprint_counts.get(&fingerprint) == Some(&(1, 1)) {
+            if let Some((from, _)) = vanished.iter().find(|(_, x)| **x == fingerprint) {
+                changes.moved.push(((*from).clone(), path.clone()));
+                continue;
+            }
+        }
+
+        changes.added.push(path.clone());
+    }
+
+    changes.removed = vanished
+        .iter()
+        .filter(|(path, _)| !changes.moved.iter().any(|(from, _)| from == *path))
+        .map(|(path, _)| (*path).clone())
+        .collect();
+
+    changes
+}
+
+/// The `(from, to)` directories that all the `found` track files moved out of into a single
+/// other directory, so that the albums in them can follow.
+fn moved_directories(
+    moved: &[(String, String)],
+    found: &[(String, (u64, u64))],
+) -> Vec<(String, String)> {
+    let mut directories: BTreeMap<&Path, BTreeSet<&Path>> = BTreeMap::new();
+
+    for (from, to) in moved {
+        let (Some(from), Some(to)) = (Path::new(from).parent(), Path::new(to).parent()) else {
+            continue;
+        };
+        if from != to {
+            directories.entry(from).or_default().insert(to);
+        }
+    }
+
+    directories
+        .into_iter()
+        .filter(|(from, _)| {
+            !found
+                .iter()
+                .any(|(path, _)| Path::new(path).parent() == Some(*from))
+        })
+        .filter_map(|(from, to)| {
+            let mut to = to.into_iter();
+            match (to.next(), to.next()) {
+                (Some(to), None) => Some((from.to_str()?.to_string(), to.to_str()?.to_string())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Drops the cached transcodes of a file that changed, moved or was removed.
+fn invalidate_transcodes(path: &str) {
+    if let Err(e) = transcode_cache::invalidate(path) {
+        log::warn!("scan: failed to invalidate transcodes of {path}: {e:?}");
+    }
+}
+
+/// The size and modification time (in unix milliseconds) of a file, used to
+/// tell whether it has changed since it was last scanned.
+fn file_fingerprint(metadata: &Metadata) -> (u64, u64) {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| {
+            u64::try_from(modified.as_millis()).unwrap_or(u64::MAX)
+        });
+
+    (metadata.len(), modified)
+}
+
+/// The fingerprint of a track file combined with its `.lrc` sidecar and the
+/// `.cue` sheets in its directory, so that adding, editing or removing a
+/// sidecar rescans the track. The sizes are summed and the most recent
+/// modification time is kept.
+///
+/// The `.cue` sheet fingerprints are cached per directory in `cue_fingerprints`.
+fn track_fingerprint(
+    path: &Path,
+    metadata: &Metadata,
+    cue_fingerprints: &mut HashMap<PathBuf, Vec<(u64, u64)>>,
+) -> (u64, u64) {
+    let lrc = std::fs::metadata(path.with_extension("lrc"))
+        .ok()
+        .filter(Metadata::is_file)
+        .map(|metadata| file_fingerprint(&metadata));
+    let cues = path.parent().map_or(&[][..], |directory| {
+        cue_fingerprints
+            .entry(directory.to_path_buf())
+            .or_insert_with(|| directory_cue_fingerprints(directory))
+    });
+
+    std::iter::once(file_fingerprint(metadata))
+        .chain(lrc)
+        .chain(cues.iter().copied())
+        .fold((0, 0), |(size, modified), (file_size, file_modified)| {
+            (size.saturating_add(file_size), modified.max(file_modified))
+        })
+}
+
+fn directory_cue_fingerprints(directory: &Path) -> Vec<(u64, u64)> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+        })
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(Metadata::is_file)
+        .map(|metadata| file_fingerprint(&metadata))
+        .collect()
+}
+
+/// Deletes the tracks for the given files that no longer exist on disk, along
+/// with any albums and artists that are left empty. Returns the number of
+/// tracks that were removed.
+async fn remove_files(db: &LibraryDatabase, files: &[String]) -> Result<usize, ScanError> {
+    if files.is_empty() {
+        return Ok(0);
+    }
+
+    let track_ids = get_tracks_by_files(db, files)
+        .await?
+        .into_iter()
+        .map(|track| track.id)
+        .collect::<Vec<_>>();
+
+    let (tracks, deleted_albums) = delete_tracks_and_empty_parents(db, track_ids).await?;
+
+    delete_scan_files(db, files).await?;
+
+    moosicbox_library::cache::clear_cache();
+
+    log::debug!(
+        "Removed {} tracks and {deleted_albums} albums for {} missing files",
+        tracks.len(),
+        files.len()
+    );
+
+    Ok(tracks.len())
+}
+
+/// Deletes the tracks along with any albums and artists that are left empty. Returns the
+/// deleted tracks and the number of albums that were deleted.
+async fn delete_tracks_and_empty_parents(
+    db: &LibraryDatabase,
+    track_ids: Vec<u64>,
+) -> Result<(Vec<LibraryTrack>, usize), ScanError> {
+    delete_track_sizes_by_track_id(db, Some(&track_ids)).await?;
+    let tracks = delete_tracks(db, Some(&track_ids)).await?;
+
+    let mut album_ids = tracks
+        .iter()
+        .map(|track| track.album_id)
+        .collect::<Vec<_>>();
+    album_ids.sort_unstable();
+    album_ids.dedup();
+
+    let deleted_albums = delete_empty_albums(db, &album_ids).await?;
+
+    let mut artist_ids = deleted_albums
+        .iter()
+        .map(|(_, artist_id)| *artist_id)
+        .collect::<Vec<_>>();
+    artist_ids.sort_unstable();
+    artist_ids.dedup();
+
+    delete_empty_artists(db, &artist_ids).await?;
+
+    Ok((tracks, deleted_albums.len()))
 }
 
 /// # Errors
@@ -70,6 +406,7 @@ pub async fn scan(
 /// * If a tokio task failed to join
 pub async fn scan_items(
     items: Vec<ScanItem>,
+    profile: &str,
     db: &LibraryDatabase,
     _token: CancellationToken,
     scanner: Scanner,
@@ -85,6 +422,18 @@ pub async fn scan_items(
         }
     }
 
+    let mut cue_fingerprints = HashMap::new();
+    let scan_files = items
+        .iter()
+        .filter_map(|item| match item {
+            ScanItem::Track { path, metadata, .. } => path.to_str().map(|path_str| {
+                let (size, modified) = track_fingerprint(path, metadata, &mut cue_fingerprints);
+                (path_str.to_string(), size, modified)
+            }),
+            ScanItem::AlbumCover { .. } | ScanItem::ArtistCover { .. } => None,
+        })
+        .collect::<Vec<_>>();
+
     let output = Arc::new(RwLock::new(ScanOutput::new()));
 
     let handles = items.into_iter().map({
@@ -120,8 +469,30 @@ pub async fn scan_items(
     log::info!("Finished initial scan");
 
     let output = output.read().await;
-    output.update_database(db).await?;
-    output.reindex_global_search_index(db).await?;
+    let results = output.update_database(db).await?;
+
+    // Tracks whose tags changed are inserted as new rows, so drop the rows that
+    // the rescanned files no longer produce.
+    let files = scan_files
+        .iter()
+        .map(|(path, _, _)| path.clone())
+        .collect::<Vec<_>>();
+    let stale_track_ids = get_tracks_by_files(db, &files)
+        .await?
+        .into_iter()
+        .map(|track| track.id)
+        .filter(|id| !results.scanned_track_ids.contains(id))
+        .collect::<Vec<_>>();
+
+    if !stale_track_ids.is_empty() {
+        log::debug!("Removing {} stale tracks", stale_track_ids.len());
+        delete_tracks_and_empty_parents(db, stale_track_ids).await?;
+        moosicbox_library::cache::clear_cache();
+    }
+
+    add_scan_files(db, &scan_files).await?;
+
+    output.reindex_global_search_index(profile, db).await?;
     drop(output);
 
     log::info!("Finished total scan");
@@ -157,6 +528,149 @@ fn extract_track_name(track_filestem: &str) -> Option<String> {
     }
 }
 
+fn parse_replay_gain_value(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let value = value
+        .strip_suffix("dB")
+        .or_else(|| value.strip_suffix("db"))
+        .or_else(|| value.strip_suffix("DB"))
+        .unwrap_or(value);
+
+    value.trim().parse().ok()
+}
+
+fn read_replay_gain(tagged_file: Option<&TaggedFile>) -> Option<ReplayGain> {
+    let tagged_file = tagged_file?;
+    let get = |key: &ItemKey| {
+        tagged_file
+            .tags()
+            .iter()
+            .find_map(|tag| tag.get_string(key))
+            .and_then(parse_replay_gain_value)
+    };
+
+    ReplayGain {
+        track_gain: get(&ItemKey::ReplayGainTrackGain),
+        track_peak: get(&ItemKey::ReplayGainTrackPeak),
+        album_gain: get(&ItemKey::ReplayGainAlbumGain),
+        album_peak: get(&ItemKey::ReplayGainAlbumPeak),
+    }
+    .or_none()
+}
+
+fn read_tag_string(tagged_file: Option<&TaggedFile>, key: &ItemKey) -> Option<String> {
+    tagged_file?
+        .tags()
+        .iter()
+        .find_map(|tag| tag.get_string(key))
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Reads the lyrics of the file at `path`, preferring a sidecar `.lrc` file
+/// with the same name, which is usually synced, over the embedded lyrics.
+fn read_lyrics(path: &Path, tagged_file: Option<&TaggedFile>) -> Option<String> {
+    let lrc = path.with_extension("lrc");
+
+    if lrc.is_file() {
+        match std::fs::read_to_string(&lrc) {
+            Ok(lyrics) if !lyrics.trim().is_empty() => return Some(lyrics),
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to read lyrics: path={lrc:?} ({e:?})"),
+        }
+    }
+
+    read_tag_string(tagged_file, &ItemKey::Lyrics)
+}
+
+/// Reads the first MusicBrainz id of the `key`, since multi-valued ids (e.g. for collaborations)
+/// can be joined into a single tag value.
+fn read_musicbrainz_id(tagged_file: Option<&TaggedFile>, key: &ItemKey) -> Option<String> {
+    read_tag_string(tagged_file, key).and_then(|ids| {
+        ids.split(['/', ';', ',', '\0'])
+            .map(str::trim)
+            .find(|x| !x.is_empty())
+            .map(ToString::to_string)
+    })
+}
+
+/// One track to add for a scanned file. Files are normally a single track, but
+/// a CUE sheet can split a file into several.
+struct TrackSplit {
+    number: u32,
+    title: String,
+    duration: f64,
+    bytes: u64,
+    track_artist: Option<String>,
+    isrc: Option<String>,
+    musicbrainz_recording_id: Option<String>,
+    replay_gain: Option<ReplayGain>,
+    start_offset: Option<f64>,
+    end_offset: Option<f64>,
+    lyrics: Option<String>,
+}
+
+/// Splits a file into the tracks listed in its CUE sheet. Each track runs until
+/// the next one starts, and the last track runs to the end of the file.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn split_cue_tracks(
+    file: &CueFile,
+    duration: f64,
+    bytes: u64,
+    album_artist: &str,
+    replay_gain: Option<ReplayGain>,
+) -> Vec<TrackSplit> {
+    // The file's own gain is for the whole album, so it can only be used as
+    // the album gain
+    let replay_gain = replay_gain
+        .map(|x| ReplayGain {
+            track_gain: None,
+            track_peak: None,
+            album_gain: x.album_gain.or(x.track_gain),
+            album_peak: x.album_peak.or(x.track_peak),
+        })
+        .filter(|x| x.album_gain.is_some() || x.album_peak.is_some());
+
+    file.tracks
+        .iter()
+        .enumerate()
+        .map(|(i, track)| {
+            let end_offset = file.tracks.get(i + 1).map(|next| next.start);
+            let track_duration = (end_offset.unwrap_or(duration) - track.start).max(0.0);
+            let track_bytes = if duration > 0.0 {
+                (bytes as f64 * track_duration / duration).round() as u64
+            } else {
+                0
+            };
+
+            TrackSplit {
+                number: track.number,
+                title: track
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| format!("Track {}", track.number)),
+                duration: track_duration,
+                bytes: track_bytes,
+                track_artist: track
+                    .performer
+                    .clone()
+                    .filter(|performer| performer != album_artist),
+                isrc: track.isrc.clone(),
+                musicbrainz_recording_id: None,
+                replay_gain,
+                start_offset: Some(track.start),
+                end_offset,
+                lyrics: None,
+            }
+        })
+        .collect()
+}
+
 #[allow(clippy::too_many_lines)]
 fn scan_track(
     path: PathBuf,
@@ -170,10 +684,7 @@ fn scan_track(
             tag,
             path_album,
             format,
-            bytes,
-            title,
-            number,
-            duration,
+            splits,
             album,
             album_artist,
             date_released,
@@ -182,6 +693,13 @@ fn scan_track(
             sample_rate,
             bit_depth,
             channels,
+            disc_number,
+            disc_total,
+            genres,
+            composer,
+            musicbrainz_release_id,
+            musicbrainz_artist_id,
+            upc,
         ) = moosicbox_task::spawn_blocking("scan: scan_track", move || {
             log::debug!("scan_track: path={path:?}");
             let extension = path
@@ -196,16 +714,35 @@ fn scan_track(
                 }
                 _ => None,
             };
-            let moosicbox_lofty_tag = moosicbox_lofty::Probe::open(&path)
-                .expect("ERROR: Bad path provided!")
-                .options(ParseOptions::new().read_picture(false))
-                .read()
-                .map_err(|e| {
-                    moosicbox_assert::die_or_error!(
-                        "Failed to read lofty tags: path={path:?} ({e:?})"
-                    );
-                    e
-                })?;
+
+            #[cfg(feature = "dsd")]
+            let dsd_properties = if moosicbox_audio_decoder::dsd::is_dsd_path(&path) {
+                Some(moosicbox_audio_decoder::dsd::read_properties(&path)?)
+            } else {
+                None
+            };
+            #[cfg(feature = "dsd")]
+            let is_dsd = dsd_properties.is_some();
+            #[cfg(not(feature = "dsd"))]
+            let is_dsd = false;
+
+            // lofty can't read DSD files, so their properties come from their headers instead
+            let moosicbox_lofty_tag = if is_dsd {
+                None
+            } else {
+                Some(
+                    moosicbox_lofty::Probe::open(&path)
+                        .expect("ERROR: Bad path provided!")
+                        .options(ParseOptions::new().read_picture(false))
+                        .read()
+                        .map_err(|e| {
+                            moosicbox_assert::die_or_error!(
+                                "Failed to read lofty tags: path={path:?} ({e:?})"
+                            );
+                            e
+                        })?,
+                )
+            };
 
             let duration = if std::path::Path::new(path.clone().to_str().unwrap())
                 .extension()
@@ -221,6 +758,8 @@ fn scan_track(
                     None => 10.0,
                 }
             };
+            #[cfg(feature = "dsd")]
+            let duration = dsd_properties.map_or(duration, |x| x.duration());
 
             let tag = tag.and_then(Result::ok);
 
@@ -240,6 +779,7 @@ fn scan_track(
                 .to_string();
 
             let track_filestem = path.file_stem().unwrap().to_str().unwrap().to_string();
+            let cue = CueSheet::find(&path);
 
             let format = match extension.as_str() {
                 #[cfg(feature = "aac")]
@@ -267,11 +807,16 @@ fn scan_track(
             let album = tag
                 .as_ref()
                 .and_then(|tag| tag.album_title())
+                .or_else(|| cue.as_ref().and_then(|(sheet, _)| sheet.title.as_deref()))
                 .unwrap_or(&album_dir_name)
                 .to_string();
             let artist_name = tag
                 .as_ref()
                 .and_then(|tag| tag.artist().or_else(|| tag.album_artist()))
+                .or_else(|| {
+                    cue.as_ref()
+                        .and_then(|(sheet, _)| sheet.performer.as_deref())
+                })
                 .unwrap_or(&artist_dir_name)
                 .to_string();
             let album_artist = tag
@@ -282,13 +827,102 @@ fn scan_track(
             let date_released = tag
                 .as_ref()
                 .and_then(|tag| tag.date())
-                .map(|date| date.to_string());
+                .map(|date| date.to_string())
+                .or_else(|| cue.as_ref().and_then(|(sheet, _)| sheet.date.clone()));
+            let disc_number = tag
+                .as_ref()
+                .and_then(|tag| tag.disc_number())
+                .map(u32::from);
+            let disc_total = tag
+                .as_ref()
+                .and_then(|tag| tag.total_discs())
+                .map(u32::from);
+            let genres = tag
+                .as_ref()
+                .and_then(|tag| tag.genre())
+                .or_else(|| cue.as_ref().and_then(|(sheet, _)| sheet.genre.as_deref()))
+                .map(split_genres)
+                .unwrap_or_default();
+            let composer = tag
+                .as_ref()
+                .and_then(|tag| tag.composer())
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(ToString::to_string);
+            let track_artist = tag
+                .as_ref()
+                .and_then(|tag| tag.artist())
+                .filter(|artist| *artist != album_artist)
+                .map(ToString::to_string);
+            let isrc = read_tag_string(moosicbox_lofty_tag.as_ref(), &ItemKey::Isrc);
+            let musicbrainz_recording_id = read_musicbrainz_id(
+                moosicbox_lofty_tag.as_ref(),
+                &ItemKey::MusicBrainzRecordingId,
+            );
+            let musicbrainz_release_id =
+                read_musicbrainz_id(moosicbox_lofty_tag.as_ref(), &ItemKey::MusicBrainzReleaseId);
+            let musicbrainz_artist_id = read_musicbrainz_id(
+                moosicbox_lofty_tag.as_ref(),
+                &ItemKey::MusicBrainzReleaseArtistId,
+            )
+            .or_else(|| {
+                // The track artist id is only the album artist's when the track doesn't
+                // credit someone else
+                if track_artist.is_none() {
+                    read_musicbrainz_id(moosicbox_lofty_tag.as_ref(), &ItemKey::MusicBrainzArtistId)
+                } else {
+                    None
+                }
+            });
+            let upc = read_tag_string(moosicbox_lofty_tag.as_ref(), &ItemKey::Barcode);
+            let lyrics = read_lyrics(&path, moosicbox_lofty_tag.as_ref());
+
+            let properties = moosicbox_lofty_tag.as_ref().map(AudioFile::properties);
+            let audio_bitrate = properties.and_then(|x| x.audio_bitrate());
+            let overall_bitrate = properties.and_then(|x| x.overall_bitrate());
+            let sample_rate = properties.and_then(|x| x.sample_rate());
+            let bit_depth = properties.and_then(|x| x.bit_depth());
+            let channels = properties.and_then(|x| x.channels());
+
+            #[cfg(feature = "dsd")]
+            let (audio_bitrate, overall_bitrate, sample_rate, bit_depth, channels) = dsd_properties
+                .map_or(
+                    (
+                        audio_bitrate,
+                        overall_bitrate,
+                        sample_rate,
+                        bit_depth,
+                        channels,
+                    ),
+                    |x| {
+                        (
+                            Some(x.bitrate()),
+                            Some(x.bitrate()),
+                            Some(x.sample_rate),
+                            Some(1),
+                            Some(x.channels),
+                        )
+                    },
+                );
+            let replay_gain = read_replay_gain(moosicbox_lofty_tag.as_ref());
 
-            let audio_bitrate = moosicbox_lofty_tag.properties().audio_bitrate();
-            let overall_bitrate = moosicbox_lofty_tag.properties().overall_bitrate();
-            let sample_rate = moosicbox_lofty_tag.properties().sample_rate();
-            let bit_depth = moosicbox_lofty_tag.properties().bit_depth();
-            let channels = moosicbox_lofty_tag.properties().channels();
+            #[cfg(feature = "loudness-analysis")]
+            let replay_gain = if replay_gain.is_some_and(|x| x.track_gain.is_some()) {
+                replay_gain
+            } else {
+                match crate::loudness::analyze_track(path.to_str().unwrap()) {
+                    Ok(Some(analyzed)) => Some(ReplayGain {
+                        album_gain: replay_gain.and_then(|x| x.album_gain),
+                        album_peak: replay_gain.and_then(|x| x.album_peak),
+                        ..analyzed
+                    }),
+                    Ok(None) => replay_gain,
+                    Err(e) => {
+                        log::warn!("Failed to analyze loudness: path={path:?} ({e:?})");
+                        replay_gain
+                    }
+                }
+            };
 
             log::debug!("====== {} ======", path.clone().to_str().unwrap());
             log::debug!("title: {}", title);
@@ -301,17 +935,49 @@ fn scan_track(
             log::debug!("sample_rate: {:?}", sample_rate);
             log::debug!("bit_depth: {:?}", bit_depth);
             log::debug!("channels: {:?}", channels);
+            log::debug!("replay_gain: {:?}", replay_gain);
             log::debug!("album title: {}", album);
             log::debug!("artist directory name: {}", artist_dir_name);
             log::debug!("album directory name: {}", album_dir_name);
             log::debug!("artist: {}", artist_name);
             log::debug!("album_artist: {}", album_artist);
             log::debug!("date_released: {:?}", date_released);
+            log::debug!("disc_number: {:?}", disc_number);
+            log::debug!("disc_total: {:?}", disc_total);
+            log::debug!("genres: {:?}", genres);
+            log::debug!("composer: {:?}", composer);
+            log::debug!("track_artist: {:?}", track_artist);
+            log::debug!("isrc: {:?}", isrc);
+            log::debug!("musicbrainz_recording_id: {:?}", musicbrainz_recording_id);
+            log::debug!("musicbrainz_release_id: {:?}", musicbrainz_release_id);
+            log::debug!("musicbrainz_artist_id: {:?}", musicbrainz_artist_id);
+            log::debug!("upc: {:?}", upc);
+            log::debug!("lyrics: {:?}", lyrics.as_ref().map(|x| x.lines().count()));
             log::debug!(
                 "contains cover: {:?}",
                 tag.as_ref().is_some_and(|tag| tag.album_cover().is_some())
             );
 
+            let splits = match cue {
+                Some((_, file)) => {
+                    log::debug!("Splitting {path:?} into {} CUE tracks", file.tracks.len());
+                    split_cue_tracks(&file, duration, bytes, &album_artist, replay_gain)
+                }
+                None => vec![TrackSplit {
+                    number: u32::try_from(number).unwrap(),
+                    title,
+                    duration,
+                    bytes,
+                    track_artist,
+                    isrc,
+                    musicbrainz_recording_id,
+                    replay_gain,
+                    start_offset: None,
+                    end_offset: None,
+                    lyrics,
+                }],
+            };
+
             let album_artist = match MULTI_ARTIST_PATTERN.find(album_artist.as_str()) {
                 Some(comma) => album_artist[..=comma.start()].to_string(),
                 None => album_artist,
@@ -322,10 +988,7 @@ fn scan_track(
                 tag,
                 path_album,
                 format,
-                bytes,
-                title,
-                number,
-                duration,
+                splits,
                 album,
                 album_artist,
                 date_released,
@@ -334,6 +997,13 @@ fn scan_track(
                 sample_rate,
                 bit_depth,
                 channels,
+                disc_number,
+                disc_total,
+                genres,
+                composer,
+                musicbrainz_release_id,
+                musicbrainz_artist_id,
+                upc,
             ))
         })
         .await??;
@@ -353,6 +1023,9 @@ fn scan_track(
             .add_artist(&album_artist, &None, ApiSource::Library)
             .await;
         let mut artist = artist.write().await;
+        if artist.musicbrainz_artist_id.is_none() {
+            artist.musicbrainz_artist_id = musicbrainz_artist_id;
+        }
         let album = artist
             .add_album(
                 &album,
@@ -363,6 +1036,12 @@ fn scan_track(
             )
             .await;
         let mut album = album.write().await;
+        if album.musicbrainz_release_id.is_none() {
+            album.musicbrainz_release_id = musicbrainz_release_id;
+        }
+        if album.upc.is_none() {
+            album.upc = upc;
+        }
         let save_path = CACHE_DIR
             .join("local")
             .join(sanitize_filename(&artist.name))
@@ -414,24 +1093,39 @@ fn scan_track(
 
         drop(artist);
 
-        let _ = album
-            .add_track(
-                &Some(path.to_str().unwrap()),
-                u32::try_from(number).unwrap(),
-                &title,
-                duration,
-                &Some(bytes),
-                format,
-                &bit_depth,
-                &audio_bitrate,
-                &overall_bitrate,
-                &sample_rate,
-                &channels,
-                TrackApiSource::Local,
-                &None,
-                ApiSource::Library,
-            )
-            .await;
+        for split in splits {
+            let track = album
+                .add_track(
+                    &Some(path.to_str().unwrap()),
+                    split.number,
+                    &split.title,
+                    split.duration,
+                    &Some(split.bytes),
+                    format,
+                    &bit_depth,
+                    &audio_bitrate,
+                    &overall_bitrate,
+                    &sample_rate,
+                    &channels,
+                    TrackApiSource::Local,
+                    &None,
+                    ApiSource::Library,
+                )
+                .await;
+
+            let mut track = track.write().await;
+            track.replay_gain = split.replay_gain;
+            track.disc_number = disc_number;
+            track.disc_total = disc_total;
+            track.genres.clone_from(&genres);
+            track.composer.clone_from(&composer);
+            track.track_artist = split.track_artist;
+            track.isrc = split.isrc;
+            track.musicbrainz_recording_id = split.musicbrainz_recording_id;
+            track.start_offset = split.start_offset;
+            track.end_offset = split.end_offset;
+            track.lyrics = split.lyrics;
+        }
 
         drop(album);
         drop(output);
@@ -510,8 +1204,12 @@ fn scan_artist_cover(
     })
 }
 
-static MUSIC_FILE_PATTERN: LazyLock<Regex> =
+#[cfg(not(feature = "dsd"))]
+pub(crate) static MUSIC_FILE_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r".+\.(flac|m4a|mp3|opus)").unwrap());
+#[cfg(feature = "dsd")]
+pub(crate) static MUSIC_FILE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r".+\.(flac|m4a|mp3|opus|dsf|dff)").unwrap());
 static MULTI_ARTIST_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\S,\S").unwrap());
 
 pub enum ScanItem {
@@ -582,3 +1280,377 @@ async fn scan_dir(
 
     Ok(handles)
 }
+
+#[cfg(test)]
+mod test {
+    use crate::cue::CueTrack;
+
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn cue_file(tracks: &[(u32, f64)]) -> CueFile {
+        CueFile {
+            name: "album.flac".to_string(),
+            tracks: tracks
+                .iter()
+                .map(|(number, start)| CueTrack {
+                    number: *number,
+                    start: *start,
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn split_cue_tracks_runs_each_track_until_the_next_one_starts() {
+        let splits = split_cue_tracks(
+            &cue_file(&[(1, 0.0), (2, 60.0), (3, 150.5)]),
+            200.0,
+            2000,
+            "Artist",
+            None,
+        );
+
+        let offsets = splits
+            .iter()
+            .map(|x| (x.number, x.start_offset, x.end_offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            vec![
+                (1, Some(0.0), Some(60.0)),
+                (2, Some(60.0), Some(150.5)),
+                (3, Some(150.5), None),
+            ]
+        );
+        assert_close(splits[0].duration, 60.0);
+        assert_close(splits[1].duration, 90.5);
+        // The last track runs to the end of the file
+        assert_close(splits[2].duration, 49.5);
+    }
+
+    #[test]
+    fn split_cue_tracks_splits_the_bytes_by_duration() {
+        let splits = split_cue_tracks(&cue_file(&[(1, 0.0), (2, 50.0)]), 200.0, 1000, "", None);
+
+        assert_eq!(
+            splits.iter().map(|x| x.bytes).collect::<Vec<_>>(),
+            vec![250, 750]
+        );
+    }
+
+    #[test]
+    fn split_cue_tracks_handles_an_unknown_or_short_file_duration() {
+        let splits = split_cue_tracks(&cue_file(&[(1, 0.0), (2, 50.0)]), 0.0, 1000, "", None);
+
+        assert_eq!(splits[0].bytes, 0);
+        assert_close(splits[0].duration, 50.0);
+        // Never negative, even when the file is shorter than the sheet says
+        assert_close(splits[1].duration, 0.0);
+    }
+
+    #[test]
+    fn split_cue_tracks_names_untitled_tracks_and_drops_the_album_artist() {
+        let mut file = cue_file(&[(1, 0.0), (2, 10.0)]);
+        file.tracks[0].title = Some("Intro".to_string());
+        file.tracks[0].performer = Some("Artist".to_string());
+        file.tracks[1].performer = Some("Guest".to_string());
+
+        let splits = split_cue_tracks(&file, 20.0, 0, "Artist", None);
+
+        assert_eq!(splits[0].title, "Intro");
+        assert_eq!(splits[0].track_artist, None);
+        assert_eq!(splits[1].title, "Track 2");
+        assert_eq!(splits[1].track_artist.as_deref(), Some("Guest"));
+    }
+
+    #[test]
+    fn split_cue_tracks_only_uses_the_file_gain_as_the_album_gain() {
+        let replay_gain = ReplayGain {
+            track_gain: Some(-6.0),
+            track_peak: Some(0.9),
+            album_gain: None,
+            album_peak: None,
+        };
+
+        let splits = split_cue_tracks(
+            &cue_file(&[(1, 0.0), (2, 10.0)]),
+            20.0,
+            0,
+            "",
+            Some(replay_gain),
+        );
+
+        for split in &splits {
+            let gain = split.replay_gain.unwrap();
+            assert_eq!(gain.track_gain, None);
+            assert_eq!(gain.track_peak, None);
+            assert_eq!(gain.album_gain, Some(-6.0));
+            assert_eq!(gain.album_peak, Some(0.9));
+        }
+    }
+
+    fn tagged_file(values: &[(ItemKey, &str)]) -> TaggedFile {
+        let mut tag = moosicbox_lofty::Tag::new(moosicbox_lofty::TagType::Id3v2);
+        for (key, value) in values {
+            assert!(tag.insert_text(key.clone(), (*value).to_string()));
+        }
+
+        TaggedFile::new(
+            moosicbox_lofty::FileType::Mpeg,
+            moosicbox_lofty::FileProperties::default(),
+            vec![tag],
+        )
+    }
+
+    #[test]
+    fn read_musicbrainz_id_reads_a_single_id() {
+        let file = tagged_file(&[(ItemKey::MusicBrainzReleaseId, "  release-1  ")]);
+
+        assert_eq!(
+            read_musicbrainz_id(Some(&file), &ItemKey::MusicBrainzReleaseId).as_deref(),
+            Some("release-1")
+        );
+    }
+
+    #[test]
+    fn read_musicbrainz_id_reads_the_first_of_joined_ids() {
+        for value in [
+            "artist-1/artist-2",
+            "artist-1; artist-2",
+            " ,artist-1,artist-2",
+        ] {
+            let file = tagged_file(&[(ItemKey::MusicBrainzArtistId, value)]);
+
+            assert_eq!(
+                read_musicbrainz_id(Some(&file), &ItemKey::MusicBrainzArtistId).as_deref(),
+                Some("artist-1"),
+                "value={value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_musicbrainz_id_is_none_without_the_tag() {
+        let file = tagged_file(&[(ItemKey::MusicBrainzReleaseId, "release-1")]);
+
+        assert_eq!(
+            read_musicbrainz_id(Some(&file), &ItemKey::MusicBrainzRecordingId),
+            None
+        );
+        assert_eq!(
+            read_musicbrainz_id(None, &ItemKey::MusicBrainzReleaseId),
+            None
+        );
+    }
+
+    #[test]
+    fn read_musicbrainz_id_is_none_for_only_separators() {
+        let file = tagged_file(&[(ItemKey::MusicBrainzReleaseId, " / ; ")]);
+
+        assert_eq!(
+            read_musicbrainz_id(Some(&file), &ItemKey::MusicBrainzReleaseId),
+            None
+        );
+    }
+
+    fn known(files: &[(&str, (u64, u64))]) -> HashMap<String, (u64, u64)> {
+        files
+            .iter()
+            .map(|(path, fingerprint)| ((*path).to_string(), *fingerprint))
+            .collect()
+    }
+
+    fn found(files: &[(&str, (u64, u64))]) -> Vec<(String, (u64, u64))> {
+        files
+            .iter()
+            .map(|(path, fingerprint)| ((*path).to_string(), *fingerprint))
+            .collect()
+    }
+
+    #[test]
+    fn diff_scan_files_detects_unchanged_updated_and_added_files() {
+        let changes = diff_scan_files(
+            &known(&[("/music/a.flac", (1, 10)), ("/music/b.flac", (2, 20))]),
+            &found(&[
+                ("/music/a.flac", (1, 10)),
+                ("/music/b.flac", (2, 21)),
+                ("/music/c.flac", (3, 30)),
+            ]),
+        );
+
+        assert_eq!(
+            changes,
+            ScanChanges {
+                unchanged: 1,
+                added: vec!["/music/c.flac".to_string()],
+                updated: vec!["/music/b.flac".to_string()],
+                moved: vec![],
+                removed: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn diff_scan_files_detects_moved_and_vanished_files() {
+        let changes = diff_scan_files(
+            &known(&[("/music/a.flac", (1, 10)), ("/music/b.flac", (2, 20))]),
+            &found(&[("/music/new/a.flac", (1, 10))]),
+        );
+
+        assert_eq!(
+            changes.moved,
+            vec![("/music/a.flac".to_string(), "/music/new/a.flac".to_string())]
+        );
+        assert_eq!(changes.removed, vec!["/music/b.flac".to_string()]);
+        assert!(changes.added.is_empty());
+    }
+
+    #[test]
+    fn diff_scan_files_does_not_guess_moves_for_ambiguous_fingerprints() {
+        let changes = diff_scan_files(
+            &known(&[("/music/a.flac", (1, 10)), ("/music/b.flac", (1, 10))]),
+            &found(&[("/music/new/a.flac", (1, 10))]),
+        );
+
+        assert!(changes.moved.is_empty());
+        assert_eq!(changes.added, vec!["/music/new/a.flac".to_string()]);
+        assert_eq!(
+            changes.removed,
+            vec!["/music/a.flac".to_string(), "/music/b.flac".to_string()]
+        );
+    }
+
+    #[test]
+    fn moved_directories_follows_a_directory_whose_files_all_moved() {
+        let moved = vec![
+            (
+                "/music/old/1.flac".to_string(),
+                "/music/new/1.flac".to_string(),
+            ),
+            (
+                "/music/old/2.flac".to_string(),
+                "/music/new/2.flac".to_string(),
+            ),
+        ];
+        let found = found(&[("/music/new/1.flac", (1, 1)), ("/music/new/2.flac", (2, 2))]);
+
+        assert_eq!(
+            moved_directories(&moved, &found),
+            vec![("/music/old".to_string(), "/music/new".to_string())]
+        );
+    }
+
+    #[test]
+    fn moved_directories_skips_directories_that_still_have_files_or_split_up() {
+        let moved = vec![
+            (
+                "/music/kept/1.flac".to_string(),
+                "/music/new/1.flac".to_string(),
+            ),
+            (
+                "/music/split/1.flac".to_string(),
+                "/music/x/1.flac".to_string(),
+            ),
+            (
+                "/music/split/2.flac".to_string(),
+                "/music/y/2.flac".to_string(),
+            ),
+            (
+                "/music/same/1.flac".to_string(),
+                "/music/same/one.flac".to_string(),
+            ),
+        ];
+        let found = found(&[
+            ("/music/kept/2.flac", (2, 2)),
+            ("/music/new/1.flac", (1, 1)),
+            ("/music/x/1.flac", (3, 3)),
+            ("/music/y/2.flac", (4, 4)),
+            ("/music/same/one.flac", (5, 5)),
+        ]);
+
+        assert!(moved_directories(&moved, &found).is_empty());
+    }
+
+    #[test]
+    fn file_fingerprint_is_the_size_and_modification_time_in_millis() {
+        let path = std::env::temp_dir().join(format!(
+            "moosicbox_scan_fingerprint_{}.flac",
+            std::process::id()
+        ));
+        std::fs::write(&path, [0_u8; 42]).unwrap();
+        let modified = UNIX_EPOCH + std::time::Duration::from_millis(1_700_000_000_123);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let fingerprint = file_fingerprint(&std::fs::metadata(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(fingerprint, (42, 1_700_000_000_123));
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("moosicbox_scan_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(path: &Path, len: usize, modified_millis: u64) {
+        std::fs::write(path, vec![0_u8; len]).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + std::time::Duration::from_millis(modified_millis))
+            .unwrap();
+    }
+
+    #[test]
+    fn track_fingerprint_includes_the_cue_sheets_in_the_directory() {
+        let dir = temp_dir("cue_fingerprint");
+        let track = dir.join("album.flac");
+        write_file(&track, 100, 1_000);
+        let metadata = std::fs::metadata(&track).unwrap();
+
+        let without_cue = track_fingerprint(&track, &metadata, &mut HashMap::new());
+
+        write_file(&dir.join("album.cue"), 10, 2_000);
+        let with_cue = track_fingerprint(&track, &metadata, &mut HashMap::new());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(without_cue, (100, 1_000));
+        assert_eq!(with_cue, (110, 2_000));
+    }
+
+    #[test]
+    fn track_fingerprint_includes_the_lrc_sidecar() {
+        let dir = temp_dir("lrc_fingerprint");
+        let track = dir.join("01 Track.flac");
+        write_file(&track, 100, 1_000);
+        write_file(&dir.join("02 Other.lrc"), 5, 5_000);
+        let metadata = std::fs::metadata(&track).unwrap();
+
+        let without_lrc = track_fingerprint(&track, &metadata, &mut HashMap::new());
+
+        write_file(&dir.join("01 Track.lrc"), 20, 3_000);
+        let with_lrc = track_fingerprint(&track, &metadata, &mut HashMap::new());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(without_lrc, (100, 1_000));
+        assert_eq!(with_lrc, (120, 3_000));
+    }
+}
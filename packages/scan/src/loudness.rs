@@ -0,0 +1,224 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::sync::{Arc, Mutex};
+
+use moosicbox_audio_decoder::{
+    decode_file_path_str, AudioDecode, AudioDecodeError, AudioDecodeHandler, DecodeError,
+};
+use moosicbox_music_models::ReplayGain;
+use symphonia::core::{
+    audio::{AudioBuffer, Signal as _},
+    formats::{Packet, Track},
+};
+
+/// The `ReplayGain` 2.0 reference loudness in LUFS
+pub const REFERENCE_LOUDNESS: f64 = -18.0;
+
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0].mul_add(x, self.z[0]);
+        self.z[0] = self.a[1].mul_add(-y, self.b[1].mul_add(x, self.z[1]));
+        self.z[1] = self.a[2].mul_add(-y, self.b[2] * x);
+        y
+    }
+}
+
+/// The ITU-R BS.1770 K-weighting filter (a high shelf followed by a high pass) with the
+/// coefficients recalculated for the given sample rate.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(sample_rate);
+
+    let f0 = 1_681.974_450_955_533;
+    let gain = 3.999_843_853_973_347;
+    let q = 0.707_175_236_955_419_6;
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let vh = 10_f64.powf(gain / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    let f0 = 38.135_470_876_024_44;
+    let q = 0.500_327_037_323_877_3;
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+fn block_loudness(mean_square: f64) -> f64 {
+    10.0_f64.mul_add(mean_square.log10(), -0.691)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Measures the EBU R128 integrated loudness and the sample peak of decoded audio.
+///
+/// Surround channel weighting is not applied; every channel contributes equally to the
+/// measured loudness.
+#[derive(Debug)]
+pub struct LoudnessAnalyzer {
+    filters: Vec<[Biquad; 2]>,
+    frames_per_step: usize,
+    step_frames: usize,
+    step_energy: f64,
+    steps: Vec<f64>,
+    peak: f64,
+}
+
+impl LoudnessAnalyzer {
+    #[must_use]
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            filters: vec![k_weighting(sample_rate); channels],
+            frames_per_step: (sample_rate as usize / 10).max(1),
+            step_frames: 0,
+            step_energy: 0.0,
+            steps: vec![],
+            peak: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, decoded: &AudioBuffer<f32>) {
+        let channels = decoded.spec().channels.count().min(self.filters.len());
+
+        for frame in 0..decoded.frames() {
+            for (c, [shelf, high_pass]) in self.filters.iter_mut().enumerate().take(channels) {
+                let x = f64::from(decoded.chan(c)[frame]);
+                self.peak = self.peak.max(x.abs());
+                let y = high_pass.process(shelf.process(x));
+                self.step_energy += y * y;
+            }
+
+            self.step_frames += 1;
+
+            if self.step_frames == self.frames_per_step {
+                #[allow(clippy::cast_precision_loss)]
+                self.steps
+                    .push(self.step_energy / self.frames_per_step as f64);
+                self.step_energy = 0.0;
+                self.step_frames = 0;
+            }
+        }
+    }
+
+    /// The gated integrated loudness in LUFS, or `None` if the audio is too short or silent.
+    #[must_use]
+    pub fn integrated_loudness(&self) -> Option<f64> {
+        // 400ms gating blocks with 75% overlap, built from the 100ms steps
+        let blocks = self
+            .steps
+            .windows(4)
+            .map(mean)
+            .filter(|x| block_loudness(*x) > ABSOLUTE_GATE)
+            .collect::<Vec<_>>();
+
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let relative_gate = block_loudness(mean(&blocks)) + RELATIVE_GATE;
+        let blocks = blocks
+            .into_iter()
+            .filter(|x| block_loudness(*x) > relative_gate)
+            .collect::<Vec<_>>();
+
+        if blocks.is_empty() {
+            return None;
+        }
+
+        Some(block_loudness(mean(&blocks)))
+    }
+
+    #[must_use]
+    pub const fn peak(&self) -> f64 {
+        self.peak
+    }
+
+    /// The track `ReplayGain` values relative to the [`REFERENCE_LOUDNESS`].
+    #[must_use]
+    pub fn replay_gain(&self) -> Option<ReplayGain> {
+        self.integrated_loudness().map(|loudness| ReplayGain {
+            track_gain: Some(REFERENCE_LOUDNESS - loudness),
+            track_peak: Some(self.peak),
+            ..Default::default()
+        })
+    }
+}
+
+struct LoudnessOutput {
+    analyzer: Arc<Mutex<Option<LoudnessAnalyzer>>>,
+}
+
+impl AudioDecode for LoudnessOutput {
+    fn decoded(
+        &mut self,
+        decoded: AudioBuffer<f32>,
+        _packet: &Packet,
+        _track: &Track,
+    ) -> Result<(), AudioDecodeError> {
+        if let Some(analyzer) = self.analyzer.lock().unwrap().as_mut() {
+            analyzer.process(&decoded);
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the audio file at `path` and computes the track `ReplayGain` values from its EBU R128
+/// integrated loudness.
+///
+/// # Panics
+///
+/// * If the analyzer `Mutex` is poisoned
+///
+/// # Errors
+///
+/// * If the audio file failed to decode
+pub fn analyze_track(path: &str) -> Result<Option<ReplayGain>, DecodeError> {
+    let analyzer = Arc::new(Mutex::new(None));
+
+    let mut handler = AudioDecodeHandler::new().with_output(Box::new({
+        let analyzer = analyzer.clone();
+        move |spec, _duration| {
+            analyzer
+                .lock()
+                .unwrap()
+                .replace(LoudnessAnalyzer::new(spec.rate, spec.channels.count()));
+
+            Ok(Box::new(LoudnessOutput {
+                analyzer: analyzer.clone(),
+            }))
+        }
+    }));
+
+    decode_file_path_str(path, &mut handler, true, true, None, None)?;
+
+    let analyzer = analyzer.lock().unwrap().take();
+
+    Ok(analyzer.and_then(|x| x.replay_gain()))
+}
@@ -37,6 +37,7 @@ pub enum ScanError {
 /// * If the scan fails
 pub async fn scan(
     api: &dyn MusicApi,
+    profile: &str,
     db: &LibraryDatabase,
     token: CancellationToken,
     scanner: Option<Scanner>,
@@ -96,7 +97,7 @@ pub async fn scan(
 
     let output = output.read().await;
     output.update_database(db).await?;
-    output.reindex_global_search_index(db).await?;
+    output.reindex_global_search_index(profile, db).await?;
     drop(output);
 
     let end = std::time::SystemTime::now();
@@ -162,6 +163,12 @@ pub async fn scan_albums(
                 )
                 .await
         };
+        if album.upc.is_some() {
+            let mut scan_album = scan_album.write().await;
+            if scan_album.upc.is_none() {
+                scan_album.upc.clone_from(&album.upc);
+            }
+        }
         {
             let read_album = { scan_album.read().await.clone() };
 
@@ -269,7 +276,7 @@ async fn scan_tracks(
 
     for track in tracks {
         #[allow(unreachable_code)]
-        let _ = scan_album
+        let scan_track = scan_album
             .write()
             .await
             .add_track(
@@ -297,6 +304,7 @@ async fn scan_tracks(
                 source,
             )
             .await;
+        scan_track.write().await.isrc.clone_from(&track.isrc);
         if let Some(scanner) = &scanner {
             scanner.on_scanned_track().await;
         }
@@ -13,14 +13,14 @@ use moosicbox_json_utils::database::DatabaseFetchError;
 use moosicbox_library::{
     db::{
         self, add_album_maps_and_get_albums, add_artist_maps_and_get_artists, add_tracks,
-        set_track_sizes, InsertTrack, SetTrackSize,
+        set_track_sizes, set_tracks_lyrics, InsertTrack, SetTrackLyrics, SetTrackSize,
     },
     models::{LibraryAlbum, LibraryArtist, LibraryTrack},
 };
 use moosicbox_music_api::models::ImageCoverSize;
 use moosicbox_music_models::{
     id::{Id, TryFromIdError},
-    Album, ApiSource, Artist, AudioFormat, PlaybackQuality, Track, TrackApiSource,
+    join_genres, Album, ApiSource, Artist, AudioFormat, ReplayGain, Track, TrackApiSource,
 };
 use moosicbox_search::{
     data::AsDataValues as _, populate_global_search_index, PopulateIndexError, RecreateIndexError,
@@ -73,6 +73,17 @@ pub struct ScanTrack {
     pub overall_bitrate: Option<u32>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
+    pub replay_gain: Option<ReplayGain>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    pub genres: Vec<String>,
+    pub composer: Option<String>,
+    pub track_artist: Option<String>,
+    pub isrc: Option<String>,
+    pub musicbrainz_recording_id: Option<String>,
+    pub start_offset: Option<f64>,
+    pub end_offset: Option<f64>,
+    pub lyrics: Option<String>,
     pub source: TrackApiSource,
     pub id: Option<Id>,
     pub api_source: ApiSource,
@@ -109,6 +120,17 @@ impl ScanTrack {
             overall_bitrate: *overall_bitrate,
             sample_rate: *sample_rate,
             channels: *channels,
+            replay_gain: None,
+            disc_number: None,
+            disc_total: None,
+            genres: vec![],
+            composer: None,
+            track_artist: None,
+            isrc: None,
+            musicbrainz_recording_id: None,
+            start_offset: None,
+            end_offset: None,
+            lyrics: None,
             source,
             id: id.cloned(),
             api_source,
@@ -125,6 +147,8 @@ pub struct ScanAlbum {
     pub date_released: Option<String>,
     pub directory: Option<String>,
     pub tracks: Arc<RwLock<Vec<Arc<RwLock<ScanTrack>>>>>,
+    pub musicbrainz_release_id: Option<String>,
+    pub upc: Option<String>,
     pub id: Option<Id>,
     pub api_source: ApiSource,
 }
@@ -148,11 +172,45 @@ impl ScanAlbum {
             date_released: date_released.clone(),
             directory: directory.map(ToString::to_string),
             tracks: Arc::new(RwLock::new(Vec::new())),
+            musicbrainz_release_id: None,
+            upc: None,
             id: id.cloned(),
             api_source,
         }
     }
 
+    /// The genres of all the tracks in the album, in the order they first appear.
+    pub async fn genres(&self) -> Vec<String> {
+        let mut genres: Vec<String> = vec![];
+
+        for track in self.tracks.read().await.iter() {
+            for genre in &track.read().await.genres {
+                if !genres.iter().any(|x| x.eq_ignore_ascii_case(genre)) {
+                    genres.push(genre.clone());
+                }
+            }
+        }
+
+        genres
+    }
+
+    /// The distinct ISRCs of the tracks in the album, or none if any of the tracks has no ISRC.
+    pub async fn isrcs(&self) -> Vec<String> {
+        let mut isrcs: Vec<String> = vec![];
+
+        for track in self.tracks.read().await.iter() {
+            let track = track.read().await;
+            let Some(isrc) = &track.isrc else {
+                return vec![];
+            };
+            if !isrcs.contains(isrc) {
+                isrcs.push(isrc.clone());
+            }
+        }
+
+        isrcs
+    }
+
     #[allow(unused, clippy::too_many_arguments, clippy::ref_option_ref)]
     #[must_use]
     pub async fn add_track(
@@ -180,9 +238,11 @@ impl ScanAlbum {
                 let is_match = if t.path.is_none() && path.is_none() {
                     t.number == number && t.name == name && t.source == source
                 } else {
-                    t.path
-                        .as_ref()
-                        .is_some_and(|p| path.is_some_and(|new_p| p == new_p))
+                    // Tracks split from one file by a CUE sheet share the path
+                    t.number == number
+                        && t.path
+                            .as_ref()
+                            .is_some_and(|p| path.is_some_and(|new_p| p == new_p))
                 };
                 if is_match {
                     maybe_track.replace(entry.clone());
@@ -315,6 +375,11 @@ impl ScanAlbum {
             ),
             ("artwork", DatabaseValue::StringOpt(self.cover)),
             ("directory", DatabaseValue::StringOpt(self.directory)),
+            (
+                "musicbrainz_release_id",
+                DatabaseValue::StringOpt(self.musicbrainz_release_id),
+            ),
+            ("upc", DatabaseValue::StringOpt(self.upc)),
         ]);
         #[allow(unused)]
         if let Some(id) = &self.id {
@@ -344,6 +409,7 @@ pub struct ScanArtist {
     pub cover: Option<String>,
     pub searched_cover: bool,
     pub albums: Arc<RwLock<Vec<Arc<RwLock<ScanAlbum>>>>>,
+    pub musicbrainz_artist_id: Option<String>,
     pub id: Option<Id>,
     pub api_source: ApiSource,
 }
@@ -357,6 +423,7 @@ impl ScanArtist {
             cover: None,
             searched_cover: false,
             albums: Arc::new(RwLock::new(Vec::new())),
+            musicbrainz_artist_id: None,
             id: id.cloned(),
             api_source,
         }
@@ -475,6 +542,12 @@ impl ScanArtist {
             ("title", DatabaseValue::String(self.name.clone())),
             ("cover", DatabaseValue::StringOpt(self.cover.clone())),
         ]);
+        if let Some(musicbrainz_artist_id) = &self.musicbrainz_artist_id {
+            values.insert(
+                "musicbrainz_artist_id",
+                DatabaseValue::String(musicbrainz_artist_id.clone()),
+            );
+        }
         #[allow(unused)]
         if let Some(id) = &self.id {
             match self.api_source {
@@ -501,6 +574,7 @@ pub struct UpdateDatabaseResults {
     pub artists: Vec<LibraryArtist>,
     pub albums: Vec<LibraryAlbum>,
     pub tracks: Vec<LibraryTrack>,
+    pub scanned_track_ids: Vec<u64>,
 }
 
 #[derive(Debug, Error)]
@@ -523,6 +597,144 @@ pub enum UpdateDatabaseError {
     TryFromId(#[from] TryFromIdError),
 }
 
+/// The `(artist_id, title)` that the albums table upserts an album's values on.
+fn album_key(values: &HashMap<&str, DatabaseValue>) -> Option<(u64, String)> {
+    let Some(DatabaseValue::Number(artist_id)) = values.get("artist_id") else {
+        return None;
+    };
+    let Some(DatabaseValue::String(title)) = values.get("title") else {
+        return None;
+    };
+
+    Some((u64::try_from(*artist_id).ok()?, title.clone()))
+}
+
+/// Whether the library album is the row that was stored for the scanned album, by its directory
+/// for local albums or by its id for albums from a remote `ApiSource`.
+#[allow(unreachable_patterns)]
+fn is_own_album(album: &ScanAlbum, existing: &LibraryAlbum) -> bool {
+    match album.api_source {
+        ApiSource::Library => album.directory.is_some() && existing.directory == album.directory,
+        source => album
+            .id
+            .as_ref()
+            .is_some_and(|id| existing.album_sources.get(source) == Some(id)),
+    }
+}
+
+/// Whether the library album doesn't come from the scanned album's `ApiSource` at all.
+#[allow(unreachable_patterns)]
+fn is_other_source_album(album: &ScanAlbum, existing: &LibraryAlbum) -> bool {
+    match album.api_source {
+        ApiSource::Library => existing.directory.is_none(),
+        source => existing.album_sources.get(source).is_none(),
+    }
+}
+
+/// Points each scanned album at the library album that is the same release, matched by its
+/// MusicBrainz release id, UPC or track ISRCs. Albums from different `ApiSource`s are merged
+/// this way even when their titles differ, and the values the scanned album is missing are kept
+/// from the library album instead of being cleared.
+///
+/// A scanned album that already has its own row keeps its scanned title and artist. When those
+/// were corrected since the last scan, its row is renamed so the upsert updates it.
+async fn link_matching_albums<'a>(
+    db: &LibraryDatabase,
+    albums: &[ScanAlbum],
+    album_maps: Vec<HashMap<&'a str, DatabaseValue>>,
+) -> Result<Vec<HashMap<&'a str, DatabaseValue>>, DatabaseFetchError> {
+    let mut releases = Vec::with_capacity(albums.len());
+    for album in albums {
+        releases.push(db::AlbumMatchIds {
+            musicbrainz_release_id: album.musicbrainz_release_id.clone(),
+            upc: album.upc.clone(),
+            isrcs: album.isrcs().await,
+        });
+    }
+    let matches = db::find_matching_albums(db, &releases).await?;
+
+    let keys = album_maps.iter().map(album_key).collect::<Vec<_>>();
+    let titles = keys
+        .iter()
+        .flatten()
+        .map(|(_, title)| title.clone())
+        .collect::<Vec<_>>();
+    let mut existing_keys = db::get_albums_by_titles(db, &titles)
+        .await?
+        .into_iter()
+        .map(|x| (x.artist_id, x.title))
+        .collect::<HashSet<_>>();
+
+    let mut linked = HashSet::new();
+    let mut results = Vec::with_capacity(album_maps.len());
+
+    for (((album, mut values), existing), key) in
+        albums.iter().zip(album_maps).zip(matches).zip(keys)
+    {
+        let Some(existing) = existing else {
+            results.push(values);
+            continue;
+        };
+
+        if is_own_album(album, &existing) {
+            if let Some((artist_id, title)) = key {
+                let existing_key = (existing.artist_id, existing.title.clone());
+                if existing_key != (artist_id, title.clone())
+                    && !existing_keys.contains(&(artist_id, title.clone()))
+                {
+                    log::debug!(
+                        "Renaming album_id={} from '{}' to '{title}' for scanned album",
+                        existing.id,
+                        existing.title,
+                    );
+                    db::set_album_artist_and_title(db, existing.id, artist_id, &title).await?;
+                    existing_keys.remove(&existing_key);
+                    existing_keys.insert((artist_id, title));
+                }
+            }
+            results.push(values);
+            continue;
+        }
+
+        let has_row = key.is_some_and(|key| existing_keys.contains(&key));
+
+        // Two scanned albums can't be upserted into the same row in a single statement
+        if (is_other_source_album(album, &existing) || !has_row) && linked.insert(existing.id) {
+            log::debug!(
+                "Linking scanned album '{}' ({}) to album_id={} '{}'",
+                album.name,
+                album.api_source,
+                existing.id,
+                existing.title
+            );
+
+            #[allow(clippy::cast_possible_wrap)]
+            values.insert(
+                "artist_id",
+                DatabaseValue::Number(existing.artist_id as i64),
+            );
+            values.insert("title", DatabaseValue::String(existing.title));
+
+            for (key, value) in [
+                ("date_released", existing.date_released),
+                ("artwork", existing.artwork),
+                ("directory", existing.directory),
+                ("musicbrainz_release_id", existing.musicbrainz_release_id),
+                ("upc", existing.upc),
+                ("genres", join_genres(&existing.genres)),
+            ] {
+                if matches!(values.get(key), Some(DatabaseValue::StringOpt(None))) {
+                    values.insert(key, DatabaseValue::StringOpt(value));
+                }
+            }
+        }
+
+        results.push(values);
+    }
+
+    Ok(results)
+}
+
 #[derive(Clone)]
 pub struct ScanOutput {
     pub artists: Arc<RwLock<Vec<Arc<RwLock<ScanArtist>>>>>,
@@ -666,7 +878,10 @@ impl ScanOutput {
             |(artist, db)| async {
                 join_all(artist.albums.read().await.iter().map(|album| async {
                     let album = album.read().await;
-                    album.clone().to_database_values(db.id)
+                    let genres = album.genres().await;
+                    let mut values = album.clone().to_database_values(db.id);
+                    values.insert("genres", DatabaseValue::StringOpt(join_genres(&genres)));
+                    values
                 }))
                 .await
             },
@@ -676,6 +891,8 @@ impl ScanOutput {
         .flatten()
         .collect::<Vec<_>>();
 
+        let album_maps = link_matching_albums(db, &albums, album_maps).await?;
+
         let db_albums = add_album_maps_and_get_albums(db, album_maps).await?;
 
         let db_albums_end = std::time::SystemTime::now();
@@ -740,6 +957,16 @@ impl ScanOutput {
                             title: track.name.clone(),
                             duration: track.duration,
                             format: Some(track.format),
+                            replay_gain: track.replay_gain,
+                            disc_number: track.disc_number,
+                            disc_total: track.disc_total,
+                            genres: track.genres.clone(),
+                            composer: track.composer.clone(),
+                            track_artist: track.track_artist.clone(),
+                            isrc: track.isrc.clone(),
+                            musicbrainz_recording_id: track.musicbrainz_recording_id.clone(),
+                            start_offset: track.start_offset,
+                            end_offset: track.end_offset,
                             source: track.source,
                             ..Default::default()
                         },
@@ -778,9 +1005,7 @@ impl ScanOutput {
             .zip(db_tracks.iter())
             .map(|(track, db_track)| SetTrackSize {
                 track_id: db_track.id,
-                quality: PlaybackQuality {
-                    format: track.format,
-                },
+                quality: track.format.into(),
                 bytes: Some(track.bytes),
                 bit_depth: Some(track.bit_depth),
                 audio_bitrate: Some(track.audio_bitrate),
@@ -801,12 +1026,26 @@ impl ScanOutput {
                 .as_millis()
         );
 
+        let track_lyrics = tracks
+            .iter()
+            .zip(db_tracks.iter())
+            .map(|(track, db_track)| SetTrackLyrics {
+                track_id: db_track.id.into(),
+                api_source: ApiSource::Library,
+                lyrics: track.lyrics.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        set_tracks_lyrics(db, &track_lyrics).await?;
+
         let end = std::time::SystemTime::now();
         log::info!(
             "Finished db update for scan in {}ms",
             end.duration_since(db_start).unwrap().as_millis(),
         );
 
+        let scanned_track_ids = db_tracks.iter().map(|track| track.id).collect::<Vec<_>>();
+
         Ok(UpdateDatabaseResults {
             artists: db_artists
                 .into_iter()
@@ -820,6 +1059,7 @@ impl ScanOutput {
                 .into_iter()
                 .filter(|track| !existing_track_ids.contains(&track.id))
                 .collect::<Vec<_>>(),
+            scanned_track_ids,
         })
     }
 
@@ -832,11 +1072,12 @@ impl ScanOutput {
     /// * If the reindex failed
     pub async fn reindex_global_search_index(
         &self,
+        profile: &str,
         db: &LibraryDatabase,
     ) -> Result<(), UpdateDatabaseError> {
         let reindex_start = std::time::SystemTime::now();
 
-        moosicbox_search::data::recreate_global_search_index().await?;
+        moosicbox_search::data::recreate_global_search_index(profile).await?;
 
         let artists = db::get_artists(db)
             .await?
@@ -845,7 +1086,7 @@ impl ScanOutput {
             .map(|artist: Artist| artist.as_data_values())
             .collect::<Vec<_>>();
 
-        populate_global_search_index(&artists, false).await?;
+        populate_global_search_index(profile, &artists, false).await?;
 
         let albums = db::get_albums(db)
             .await?
@@ -854,7 +1095,7 @@ impl ScanOutput {
             .map(|album: Album| album.as_data_values())
             .collect::<Vec<_>>();
 
-        populate_global_search_index(&albums, false).await?;
+        populate_global_search_index(profile, &albums, false).await?;
 
         let tracks = db::get_tracks(db, None)
             .await?
@@ -863,7 +1104,7 @@ impl ScanOutput {
             .map(|track: Track| track.as_data_values())
             .collect::<Vec<_>>();
 
-        populate_global_search_index(&tracks, false).await?;
+        populate_global_search_index(profile, &tracks, false).await?;
 
         let reindex_end = std::time::SystemTime::now();
         log::info!(
@@ -883,3 +1124,164 @@ impl Default for ScanOutput {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use moosicbox_json_utils::database::ToValue as _;
+
+    use super::*;
+
+    fn album_db() -> LibraryDatabase {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "
+                CREATE TABLE artists (
+                    id INTEGER PRIMARY KEY NOT NULL,
+                    title TEXT NOT NULL,
+                    tidal_id TEXT,
+                    qobuz_id TEXT
+                );
+                CREATE TABLE albums (
+                    id INTEGER PRIMARY KEY NOT NULL,
+                    artist_id INTEGER NOT NULL,
+                    title TEXT NOT NULL,
+                    date_released TEXT,
+                    date_added TEXT,
+                    artwork TEXT,
+                    directory TEXT,
+                    blur INTEGER NOT NULL DEFAULT 0,
+                    tidal_id TEXT,
+                    qobuz_id TEXT,
+                    genres TEXT,
+                    musicbrainz_release_id TEXT,
+                    upc TEXT,
+                    UNIQUE(artist_id, title)
+                );
+                CREATE TABLE tracks (
+                    id INTEGER PRIMARY KEY NOT NULL,
+                    album_id INTEGER NOT NULL,
+                    title TEXT NOT NULL,
+                    isrc TEXT
+                );
+                INSERT INTO artists (id, title) VALUES (1, 'artist');
+                ",
+            )
+            .unwrap();
+        let database: Box<dyn moosicbox_database::Database> =
+            Box::new(moosicbox_database::rusqlite::RusqliteDatabase::new(
+                std::sync::Arc::new(tokio::sync::Mutex::new(connection)),
+            ));
+
+        std::sync::Arc::new(database).into()
+    }
+
+    async fn add_album(
+        db: &LibraryDatabase,
+        title: &str,
+        directory: &str,
+        musicbrainz_release_id: &str,
+        upc: Option<&str>,
+    ) -> u64 {
+        db.insert("albums")
+            .value("artist_id", 1)
+            .value("title", title)
+            .value("directory", directory)
+            .value("musicbrainz_release_id", musicbrainz_release_id)
+            .value("upc", upc)
+            .execute(db)
+            .await
+            .unwrap()
+            .to_value("id")
+            .unwrap()
+    }
+
+    fn scan_album(title: &str, directory: &str, musicbrainz_release_id: &str) -> ScanAlbum {
+        let artist = ScanArtist::new("artist", &None, ApiSource::Library);
+        let mut album = ScanAlbum::new(
+            artist,
+            title,
+            &None,
+            Some(directory),
+            &None,
+            ApiSource::Library,
+        );
+        album.musicbrainz_release_id = Some(musicbrainz_release_id.to_string());
+        album
+    }
+
+    async fn link(
+        db: &LibraryDatabase,
+        albums: &[ScanAlbum],
+    ) -> Vec<HashMap<&'static str, DatabaseValue>> {
+        let maps = albums
+            .iter()
+            .map(|x| x.clone().to_database_values(1))
+            .collect::<Vec<_>>();
+
+        link_matching_albums(db, albums, maps).await.unwrap()
+    }
+
+    fn title(values: &HashMap<&str, DatabaseValue>) -> Option<&str> {
+        match values.get("title") {
+            Some(DatabaseValue::String(title)) => Some(title),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn link_matching_albums_keeps_a_corrected_title_for_the_albums_own_row() {
+        let db = album_db();
+        let id = add_album(&db, "Old Title", "/music/album", "mbid-1", None).await;
+
+        let maps = link(&db, &[scan_album("New Title", "/music/album", "mbid-1")]).await;
+        assert_eq!(title(&maps[0]), Some("New Title"));
+
+        let albums = add_album_maps_and_get_albums(&db, maps).await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].id, id);
+        assert_eq!(albums[0].title, "New Title");
+
+        let rows = db.select("albums").execute(&db).await.unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn link_matching_albums_links_an_album_without_a_row_to_the_matching_album() {
+        let db = album_db();
+        add_album(&db, "Library Title", "/music/a", "mbid-1", Some("upc-1")).await;
+
+        let maps = link(&db, &[scan_album("Scanned Title", "/music/b", "mbid-1")]).await;
+
+        assert_eq!(title(&maps[0]), Some("Library Title"));
+        assert_eq!(
+            maps[0].get("upc"),
+            Some(&DatabaseValue::StringOpt(Some("upc-1".to_string())))
+        );
+        assert_eq!(
+            maps[0].get("directory"),
+            Some(&DatabaseValue::StringOpt(Some("/music/b".to_string())))
+        );
+    }
+
+    #[tokio::test]
+    async fn link_matching_albums_keeps_the_scanned_tags_of_an_album_with_its_own_row() {
+        let db = album_db();
+        add_album(&db, "Library Title", "/music/a", "mbid-1", None).await;
+        add_album(&db, "Scanned Title", "/music/b", "mbid-2", None).await;
+
+        let maps = link(&db, &[scan_album("Scanned Title", "/music/b", "mbid-1")]).await;
+
+        assert_eq!(title(&maps[0]), Some("Scanned Title"));
+    }
+
+    #[tokio::test]
+    async fn link_matching_albums_leaves_unmatched_albums_alone() {
+        let db = album_db();
+        add_album(&db, "Library Title", "/music/a", "mbid-1", None).await;
+
+        let maps = link(&db, &[scan_album("Scanned Title", "/music/b", "mbid-2")]).await;
+
+        assert_eq!(title(&maps[0]), Some("Scanned Title"));
+    }
+}
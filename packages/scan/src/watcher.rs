@@ -0,0 +1,376 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::Duration,
+};
+
+use moosicbox_database::profiles::LibraryDatabase;
+use moosicbox_json_utils::database::DatabaseFetchError;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use thiserror::Error;
+use tokio::{sync::mpsc, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::{event::ScanTask, get_scan_paths, local, Scanner, CANCELLATION_TOKEN};
+
+const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(5);
+const SYNC_PATHS_INTERVAL: Duration = Duration::from_secs(60);
+
+static WATCHERS: LazyLock<std::sync::RwLock<HashMap<String, CancellationToken>>> =
+    LazyLock::new(|| std::sync::RwLock::new(HashMap::new()));
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+    #[error(transparent)]
+    Notify(#[from] notify::Error),
+    #[error(transparent)]
+    Scan(#[from] local::ScanError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatcherConfig {
+    /// How long the scan paths must be quiet before the changes are scanned
+    pub debounce: Duration,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            debounce: DEFAULT_DEBOUNCE,
+        }
+    }
+}
+
+impl WatcherConfig {
+    /// Reads the watcher config from the `MOOSICBOX_SCAN_WATCH` and
+    /// `MOOSICBOX_SCAN_WATCH_DEBOUNCE_MS` environment variables. Returns `None`
+    /// if watching is not enabled.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let enabled = std::env::var("MOOSICBOX_SCAN_WATCH")
+            .is_ok_and(|x| matches!(x.to_lowercase().as_str(), "1" | "true"));
+
+        if !enabled {
+            return None;
+        }
+
+        let debounce = std::env::var("MOOSICBOX_SCAN_WATCH_DEBOUNCE_MS")
+            .ok()
+            .and_then(|x| x.parse::<u64>().ok())
+            .map_or(DEFAULT_DEBOUNCE, Duration::from_millis);
+
+        Some(Self { debounce })
+    }
+}
+
+/// Starts watching the scan paths of the given profile, replacing any watcher
+/// that was already running for it.
+///
+/// # Panics
+///
+/// * If the `WATCHERS` `RwLock` is poisoned
+pub fn start(profile: &str, db: &LibraryDatabase, config: WatcherConfig) {
+    stop(profile);
+
+    let token = CANCELLATION_TOKEN.child_token();
+
+    WATCHERS
+        .write()
+        .unwrap()
+        .insert(profile.to_string(), token.clone());
+
+    let profile = profile.to_string();
+    let db = db.clone();
+
+    moosicbox_task::spawn(&format!("scan: watcher '{profile}'"), async move {
+        log::debug!("Starting scan watcher for profile={profile}");
+
+        if let Err(e) = watch(&profile, &db, config, token).await {
+            log::error!("Scan watcher for profile={profile} failed: {e:?}");
+        }
+
+        log::debug!("Stopped scan watcher for profile={profile}");
+    });
+}
+
+/// Stops the watcher for the given profile, if there is one.
+///
+/// # Panics
+///
+/// * If the `WATCHERS` `RwLock` is poisoned
+pub fn stop(profile: &str) {
+    if let Some(token) = WATCHERS.write().unwrap().remove(profile) {
+        token.cancel();
+    }
+}
+
+/// Watches the scan paths for filesystem changes and rescans the affected
+/// directories once the changes have settled for `config.debounce`.
+///
+/// The scan paths are re-read periodically so that added and removed scan
+/// paths are picked up without restarting the watcher.
+///
+/// Failing to re-read the scan paths or to scan the changed directories is
+/// logged and retried on the next sync or change, rather than stopping the
+/// watcher.
+///
+/// # Errors
+///
+/// * If the filesystem watcher fails to initialize
+pub async fn watch(
+    profile: &str,
+    db: &LibraryDatabase,
+    config: WatcherConfig,
+    token: CancellationToken,
+) -> Result<(), WatchError> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                if tx.send(event).is_err() {
+                    log::trace!("watch: receiver dropped");
+                }
+            }
+            Err(e) => log::error!("watch: filesystem watcher error: {e:?}"),
+        })?;
+
+    let mut watched = HashSet::new();
+    let mut pending = BTreeSet::new();
+    let mut last_event = Instant::now();
+    let mut sync_interval = tokio::time::interval(SYNC_PATHS_INTERVAL);
+
+    loop {
+        tokio::select! {
+            () = token.cancelled() => {
+                break;
+            }
+            _ = sync_interval.tick() => {
+                if let Err(e) = sync_watched_paths(db, &mut watcher, &mut watched).await {
+                    log::error!("watch: failed to sync watched scan paths: {e:?}");
+                }
+            }
+            event = rx.recv() => {
+                let Some(event) = event else {
+                    break;
+                };
+
+                if is_relevant_event(&event) {
+                    pending.extend(
+                        event
+                            .paths
+                            .iter()
+                            .filter(|path| is_relevant_path(path))
+                            .filter_map(|path| path.parent().map(Path::to_path_buf)),
+                    );
+                    last_event = Instant::now();
+                }
+            }
+            () = tokio::time::sleep_until(last_event + config.debounce), if !pending.is_empty() => {
+                let directories = collapse_directories(std::mem::take(&mut pending), &watched);
+
+                if !directories.is_empty() {
+                    if let Err(e) = scan_directories(profile, db, directories, &token).await {
+                        log::error!("watch: failed to scan changed directories: {e:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn sync_watched_paths(
+    db: &LibraryDatabase,
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+) -> Result<(), WatchError> {
+    let paths = get_scan_paths(db)
+        .await?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<HashSet<_>>();
+
+    for path in watched.difference(&paths) {
+        log::debug!("watch: unwatching {path:?}");
+        if let Err(e) = watcher.unwatch(path) {
+            log::warn!("watch: failed to unwatch {path:?}: {e:?}");
+        }
+    }
+
+    for path in paths.difference(watched) {
+        log::debug!("watch: watching {path:?}");
+        if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
+            log::warn!("watch: failed to watch {path:?}: {e:?}");
+        }
+    }
+
+    *watched = paths;
+
+    Ok(())
+}
+
+const fn is_relevant_event(event: &Event) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Any | EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    )
+}
+
+/// Music files, their `.cue` and `.lrc` sidecar files and directories are
+/// relevant. Paths that no longer exist are also relevant since they may have
+/// been music files or album directories.
+fn is_relevant_path(path: &Path) -> bool {
+    if !path.exists() || path.is_dir() {
+        return true;
+    }
+
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| local::MUSIC_FILE_PATTERN.is_match(name))
+        || path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("cue") || ext.eq_ignore_ascii_case("lrc"))
+}
+
+/// Drops the directories that are outside of the watched scan paths or nested
+/// inside of another pending directory, since scanning the parent covers them.
+fn collapse_directories(pending: BTreeSet<PathBuf>, watched: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    let mut directories: Vec<PathBuf> = vec![];
+
+    // `BTreeSet` iterates parents before their children
+    for directory in pending {
+        if !watched.iter().any(|root| directory.starts_with(root)) {
+            continue;
+        }
+        if directories
+            .iter()
+            .any(|parent| directory.starts_with(parent))
+        {
+            continue;
+        }
+        directories.push(directory);
+    }
+
+    directories
+}
+
+async fn scan_directories(
+    profile: &str,
+    db: &LibraryDatabase,
+    directories: Vec<PathBuf>,
+    token: &CancellationToken,
+) -> Result<(), WatchError> {
+    let paths = directories
+        .iter()
+        .filter_map(|directory| directory.to_str().map(ToString::to_string))
+        .collect::<Vec<_>>();
+
+    log::debug!("watch: scanning changed directories {paths:?}");
+
+    let scanner = Scanner::new(ScanTask::Local {
+        paths: paths.clone(),
+    });
+
+    for path in &paths {
+        if token.is_cancelled() {
+            break;
+        }
+
+        local::scan(path, profile, db, token.clone(), scanner.clone()).await?;
+    }
+
+    scanner.on_scan_finished().await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn collapse_directories_drops_directories_nested_in_another_pending_directory() {
+        let watched = [PathBuf::from("/music")].into_iter().collect();
+        let pending = paths(&[
+            "/music/artist/album",
+            "/music/artist",
+            "/music/other/album/disc 1",
+            "/music/other/album",
+        ])
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            collapse_directories(pending, &watched),
+            paths(&["/music/artist", "/music/other/album"])
+        );
+    }
+
+    #[test]
+    fn collapse_directories_drops_directories_outside_of_the_watched_paths() {
+        let watched = [PathBuf::from("/music"), PathBuf::from("/audiobooks")]
+            .into_iter()
+            .collect();
+        let pending = paths(&["/music/album", "/musical/album", "/audiobooks/book", "/tmp"])
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            collapse_directories(pending, &watched),
+            paths(&["/audiobooks/book", "/music/album"])
+        );
+    }
+
+    #[test]
+    fn collapse_directories_does_not_treat_sibling_prefixes_as_parents() {
+        let watched = [PathBuf::from("/music")].into_iter().collect();
+        let pending = paths(&["/music/album", "/music/album 2"])
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            collapse_directories(pending, &watched),
+            paths(&["/music/album", "/music/album 2"])
+        );
+    }
+
+    #[test]
+    fn is_relevant_path_matches_music_files_sidecars_directories_and_removed_paths() {
+        let directory = std::env::temp_dir().join(format!(
+            "moosicbox_scan_watcher_test_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        for name in [
+            "track.flac",
+            "album.cue",
+            "track.LRC",
+            "cover.jpg",
+            "notes.txt",
+        ] {
+            std::fs::write(directory.join(name), []).unwrap();
+        }
+
+        let relevant = |name: &str| is_relevant_path(&directory.join(name));
+
+        assert!(relevant("track.flac"));
+        assert!(relevant("album.cue"));
+        assert!(relevant("track.LRC"));
+        assert!(!relevant("cover.jpg"));
+        assert!(!relevant("notes.txt"));
+        assert!(relevant("removed.txt"));
+        assert!(is_relevant_path(&directory));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1 @@
+DROP TABLE transcoding_profiles;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS transcoding_profiles (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR(128) NOT NULL UNIQUE,
+    format VARCHAR(16) NOT NULL,
+    bitrate INTEGER DEFAULT NULL,
+    vbr_quality INTEGER DEFAULT NULL,
+    sample_rate INTEGER DEFAULT NULL,
+    channels INTEGER DEFAULT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1 @@
+DROP TABLE dsp_presets;
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS dsp_presets (
+    id BIGSERIAL PRIMARY KEY NOT NULL,
+    name VARCHAR(128) NOT NULL,
+    target_type VARCHAR(16) NOT NULL,
+    target_id BIGINT NOT NULL,
+    active BIGINT NOT NULL DEFAULT 0,
+    settings TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX ux_dsp_presets_props ON dsp_presets(
+    target_type,
+    target_id,
+    name
+);
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS dsp_presets (
+    `id` INTEGER PRIMARY KEY NOT NULL,
+    `name` VARCHAR(128) NOT NULL,
+    `target_type` VARCHAR(16) NOT NULL,
+    `target_id` INTEGER NOT NULL,
+    `active` INTEGER NOT NULL DEFAULT 0,
+    `settings` TEXT NOT NULL,
+    `created` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    `updated` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+);
+
+CREATE UNIQUE INDEX ux_dsp_presets_props ON dsp_presets(
+    target_type,
+    target_id,
+    name
+);
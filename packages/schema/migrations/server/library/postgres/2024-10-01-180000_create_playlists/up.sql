@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS playlists (
+    "id" BIGSERIAL PRIMARY KEY NOT NULL,
+    "name" TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS playlist_tracks (
+    "id" BIGSERIAL PRIMARY KEY NOT NULL,
+    playlist_id BIGINT NOT NULL,
+    "position" BIGINT NOT NULL,
+    track_id VARCHAR(64) NOT NULL,
+    api_source VARCHAR(64) NOT NULL DEFAULT 'LIBRARY',
+    "data" TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (playlist_id) REFERENCES playlists(id)
+);
+
+CREATE INDEX ix_playlist_tracks_playlist_id ON playlist_tracks(playlist_id);
@@ -0,0 +1,2 @@
+ALTER TABLE sessions DROP COLUMN repeat_mode;
+ALTER TABLE sessions DROP COLUMN shuffle;
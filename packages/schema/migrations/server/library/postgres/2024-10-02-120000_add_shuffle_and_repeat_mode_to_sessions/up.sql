@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN shuffle BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE sessions ADD COLUMN repeat_mode VARCHAR(32) NOT NULL DEFAULT 'OFF';
@@ -0,0 +1,4 @@
+ALTER TABLE tracks DROP COLUMN replay_gain_album_peak;
+ALTER TABLE tracks DROP COLUMN replay_gain_album_gain;
+ALTER TABLE tracks DROP COLUMN replay_gain_track_peak;
+ALTER TABLE tracks DROP COLUMN replay_gain_track_gain;
@@ -0,0 +1,4 @@
+ALTER TABLE tracks ADD COLUMN replay_gain_track_gain DOUBLE PRECISION DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN replay_gain_track_peak DOUBLE PRECISION DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN replay_gain_album_gain DOUBLE PRECISION DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN replay_gain_album_peak DOUBLE PRECISION DEFAULT NULL;
@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN crossfade DOUBLE PRECISION DEFAULT NULL;
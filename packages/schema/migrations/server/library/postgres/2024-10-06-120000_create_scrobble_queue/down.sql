@@ -0,0 +1 @@
+DROP TABLE scrobble_queue;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS scrobble_queue (
+    "id" BIGSERIAL PRIMARY KEY NOT NULL,
+    listen TEXT NOT NULL,
+    attempts BIGINT NOT NULL DEFAULT 0,
+    created TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS ix_tracks_isrc;
+DROP INDEX IF EXISTS ix_albums_upc;
+DROP INDEX IF EXISTS ix_albums_musicbrainz_release_id;
+ALTER TABLE tracks DROP COLUMN musicbrainz_recording_id;
+ALTER TABLE albums DROP COLUMN upc;
+ALTER TABLE albums DROP COLUMN musicbrainz_release_id;
+ALTER TABLE artists DROP COLUMN musicbrainz_artist_id;
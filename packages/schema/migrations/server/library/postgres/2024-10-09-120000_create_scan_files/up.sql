@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS scan_files (
+    "id" BIGSERIAL PRIMARY KEY NOT NULL,
+    path TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    modified BIGINT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX ux_scan_files_path ON scan_files(path);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS track_lyrics (
+    "id" BIGSERIAL PRIMARY KEY NOT NULL,
+    track_id VARCHAR(64) NOT NULL,
+    api_source VARCHAR(64) NOT NULL DEFAULT 'LIBRARY',
+    lyrics TEXT NOT NULL,
+    created TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX ux_track_lyrics ON track_lyrics(track_id, api_source);
@@ -0,0 +1 @@
+ALTER TABLE session_playlist_tracks DROP COLUMN unshuffled_position;
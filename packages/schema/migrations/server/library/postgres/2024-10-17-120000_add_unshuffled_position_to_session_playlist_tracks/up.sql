@@ -0,0 +1 @@
+ALTER TABLE session_playlist_tracks ADD COLUMN unshuffled_position BIGINT DEFAULT NULL;
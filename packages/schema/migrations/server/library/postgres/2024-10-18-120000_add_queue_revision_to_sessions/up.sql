@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN queue_revision BIGINT NOT NULL DEFAULT 0;
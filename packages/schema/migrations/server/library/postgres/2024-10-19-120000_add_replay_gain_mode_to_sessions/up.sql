@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN replay_gain_mode VARCHAR(32) NOT NULL DEFAULT 'OFF';
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS ix_playlist_tracks_playlist_id;
+DROP TABLE playlist_tracks;
+DROP TABLE playlists;
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS playlists (
+    `id` INTEGER PRIMARY KEY NOT NULL,
+    `name` TEXT NOT NULL,
+    `created` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    `updated` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS playlist_tracks (
+    `id` INTEGER PRIMARY KEY NOT NULL,
+    `playlist_id` INTEGER NOT NULL,
+    `position` INTEGER NOT NULL,
+    `track_id` VARCHAR(64) NOT NULL,
+    `api_source` VARCHAR(64) NOT NULL DEFAULT 'LIBRARY',
+    `data` TEXT NOT NULL,
+    `created` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    FOREIGN KEY (`playlist_id`) REFERENCES playlists(`id`)
+);
+
+CREATE INDEX ix_playlist_tracks_playlist_id ON playlist_tracks(`playlist_id`);
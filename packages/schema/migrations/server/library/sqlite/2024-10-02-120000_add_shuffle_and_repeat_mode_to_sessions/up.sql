@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN shuffle INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE sessions ADD COLUMN repeat_mode VARCHAR(32) NOT NULL DEFAULT 'OFF';
@@ -0,0 +1,4 @@
+ALTER TABLE tracks ADD COLUMN replay_gain_track_gain REAL DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN replay_gain_track_peak REAL DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN replay_gain_album_gain REAL DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN replay_gain_album_peak REAL DEFAULT NULL;
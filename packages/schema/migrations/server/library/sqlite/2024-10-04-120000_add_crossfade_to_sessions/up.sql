@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN crossfade REAL DEFAULT NULL;
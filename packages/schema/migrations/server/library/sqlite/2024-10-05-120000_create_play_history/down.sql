@@ -0,0 +1,3 @@
+ALTER TABLE tracks DROP COLUMN play_count;
+DROP INDEX IF EXISTS ix_play_history_played_at;
+DROP TABLE play_history;
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS play_history (
+    `id` INTEGER PRIMARY KEY NOT NULL,
+    `track_id` VARCHAR(64) NOT NULL,
+    `api_source` VARCHAR(64) NOT NULL DEFAULT 'LIBRARY',
+    `album_id` VARCHAR(64) NOT NULL,
+    `artist_id` VARCHAR(64) NOT NULL,
+    `profile` VARCHAR(64) NOT NULL,
+    `session_id` INTEGER NOT NULL,
+    `audio_zone_id` INTEGER DEFAULT NULL,
+    `played_at` INTEGER NOT NULL,
+    `data` TEXT NOT NULL
+);
+
+CREATE INDEX ix_play_history_played_at ON play_history(`played_at`);
+
+ALTER TABLE tracks ADD COLUMN play_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS scrobble_queue (
+    `id` INTEGER PRIMARY KEY NOT NULL,
+    `listen` TEXT NOT NULL,
+    `attempts` INTEGER NOT NULL DEFAULT 0,
+    `created` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+);
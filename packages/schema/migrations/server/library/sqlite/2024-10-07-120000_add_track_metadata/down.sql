@@ -0,0 +1,7 @@
+ALTER TABLE albums DROP COLUMN genres;
+ALTER TABLE tracks DROP COLUMN isrc;
+ALTER TABLE tracks DROP COLUMN track_artist;
+ALTER TABLE tracks DROP COLUMN composer;
+ALTER TABLE tracks DROP COLUMN genres;
+ALTER TABLE tracks DROP COLUMN disc_total;
+ALTER TABLE tracks DROP COLUMN disc_number;
@@ -0,0 +1,7 @@
+ALTER TABLE tracks ADD COLUMN disc_number INTEGER DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN disc_total INTEGER DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN genres TEXT DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN composer TEXT DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN track_artist TEXT DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN isrc VARCHAR(32) DEFAULT NULL;
+ALTER TABLE albums ADD COLUMN genres TEXT DEFAULT NULL;
@@ -0,0 +1,7 @@
+ALTER TABLE artists ADD COLUMN musicbrainz_artist_id VARCHAR(36) DEFAULT NULL;
+ALTER TABLE albums ADD COLUMN musicbrainz_release_id VARCHAR(36) DEFAULT NULL;
+ALTER TABLE albums ADD COLUMN upc VARCHAR(32) DEFAULT NULL;
+ALTER TABLE tracks ADD COLUMN musicbrainz_recording_id VARCHAR(36) DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS ix_albums_musicbrainz_release_id ON albums(musicbrainz_release_id);
+CREATE INDEX IF NOT EXISTS ix_albums_upc ON albums(upc);
+CREATE INDEX IF NOT EXISTS ix_tracks_isrc ON tracks(isrc);
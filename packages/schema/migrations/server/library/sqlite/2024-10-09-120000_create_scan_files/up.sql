@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS scan_files (
+    `id` INTEGER PRIMARY KEY NOT NULL,
+    `path` TEXT NOT NULL,
+    `size` INTEGER NOT NULL,
+    `modified` INTEGER NOT NULL,
+    `created` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    `updated` TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+);
+CREATE UNIQUE INDEX ux_scan_files_path ON scan_files(`path`);
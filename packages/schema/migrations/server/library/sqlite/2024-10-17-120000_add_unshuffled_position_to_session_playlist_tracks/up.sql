@@ -0,0 +1 @@
+ALTER TABLE session_playlist_tracks ADD COLUMN unshuffled_position INTEGER DEFAULT NULL;
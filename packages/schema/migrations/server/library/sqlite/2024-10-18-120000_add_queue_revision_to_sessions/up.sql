@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN queue_revision INTEGER NOT NULL DEFAULT 0;
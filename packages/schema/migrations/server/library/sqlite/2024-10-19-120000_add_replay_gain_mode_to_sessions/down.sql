@@ -0,0 +1 @@
+ALTER TABLE sessions DROP COLUMN replay_gain_mode;
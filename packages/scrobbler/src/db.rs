@@ -0,0 +1,205 @@
+use moosicbox_database::{
+    profiles::LibraryDatabase,
+    query::{literal, FilterableQuery, SortDirection},
+};
+use moosicbox_json_utils::{
+    database::{DatabaseFetchError, ToValue as _},
+    ParseError,
+};
+
+use crate::listenbrainz::Listen;
+
+/// # Errors
+///
+/// * If there was a database error
+/// * If the listen failed to serialize
+pub async fn add_queued_listen(
+    db: &LibraryDatabase,
+    listen: &Listen,
+) -> Result<(), DatabaseFetchError> {
+    db.insert("scrobble_queue")
+        .value(
+            "listen",
+            serde_json::to_string(listen).map_err(|e| {
+                DatabaseFetchError::Parse(ParseError::Parse(format!("listen: {e:?}")))
+            })?,
+        )
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Fetches the oldest queued listens along with their row ids.
+///
+/// # Errors
+///
+/// * If there was a database error
+/// * If a queued listen failed to deserialize
+pub async fn get_queued_listens(
+    db: &LibraryDatabase,
+    limit: usize,
+) -> Result<Vec<(u64, Listen)>, DatabaseFetchError> {
+    db.select("scrobble_queue")
+        .sort("id", SortDirection::Asc)
+        .limit(limit)
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| {
+            let listen = serde_json::from_str(&row.to_value::<String>("listen")?)
+                .map_err(|e| ParseError::Parse(format!("listen: {e:?}")))?;
+
+            Ok((row.to_value("id")?, listen))
+        })
+        .collect::<Result<Vec<_>, ParseError>>()
+        .map_err(DatabaseFetchError::Parse)
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn increment_queued_listen_attempts(
+    db: &LibraryDatabase,
+    ids: &[u64],
+) -> Result<(), DatabaseFetchError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    db.update("scrobble_queue")
+        .where_in("id", ids.to_vec())
+        .value("attempts", literal("attempts + 1"))
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes the queued listens that have failed to submit at least `max_attempts` times,
+/// returning how many were deleted.
+///
+/// # Errors
+///
+/// * If there was a database error
+pub async fn delete_exhausted_queued_listens(
+    db: &LibraryDatabase,
+    max_attempts: u32,
+) -> Result<usize, DatabaseFetchError> {
+    Ok(db
+        .delete("scrobble_queue")
+        .where_gte("attempts", max_attempts)
+        .execute(db)
+        .await?
+        .len())
+}
+
+/// # Errors
+///
+/// * If there was a database error
+pub async fn delete_queued_listens(
+    db: &LibraryDatabase,
+    ids: &[u64],
+) -> Result<(), DatabaseFetchError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    db.delete("scrobble_queue")
+        .where_in("id", ids.to_vec())
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use moosicbox_music_models::Track;
+
+    use super::*;
+
+    fn db() -> LibraryDatabase {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(include_str!(
+                "../../schema/migrations/server/library/sqlite/2024-10-06-120000_create_scrobble_queue/up.sql"
+            ))
+            .unwrap();
+        let database: Box<dyn moosicbox_database::Database> =
+            Box::new(moosicbox_database::rusqlite::RusqliteDatabase::new(
+                std::sync::Arc::new(tokio::sync::Mutex::new(connection)),
+            ));
+
+        std::sync::Arc::new(database).into()
+    }
+
+    fn listen(title: &str) -> Listen {
+        Listen::new(
+            &Track {
+                title: title.to_string(),
+                ..Default::default()
+            },
+            Some(1),
+        )
+    }
+
+    #[tokio::test]
+    async fn get_queued_listens_returns_the_oldest_listens_first() {
+        let db = db();
+        for title in ["a", "b", "c"] {
+            add_queued_listen(&db, &listen(title)).await.unwrap();
+        }
+
+        let queued = get_queued_listens(&db, 2).await.unwrap();
+
+        assert_eq!(
+            queued.into_iter().map(|(_, x)| x).collect::<Vec<_>>(),
+            vec![listen("a"), listen("b")]
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_exhausted_queued_listens_only_deletes_listens_at_the_max_attempts() {
+        let db = db();
+        for title in ["a", "b"] {
+            add_queued_listen(&db, &listen(title)).await.unwrap();
+        }
+        let ids = get_queued_listens(&db, 2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+
+        increment_queued_listen_attempts(&db, &ids).await.unwrap();
+        increment_queued_listen_attempts(&db, &ids[..1])
+            .await
+            .unwrap();
+
+        assert_eq!(delete_exhausted_queued_listens(&db, 3).await.unwrap(), 0);
+        assert_eq!(delete_exhausted_queued_listens(&db, 2).await.unwrap(), 1);
+
+        let queued = get_queued_listens(&db, 10).await.unwrap();
+        assert_eq!(queued, vec![(ids[1], listen("b"))]);
+    }
+
+    #[tokio::test]
+    async fn delete_queued_listens_deletes_the_given_listens() {
+        let db = db();
+        for title in ["a", "b"] {
+            add_queued_listen(&db, &listen(title)).await.unwrap();
+        }
+        let ids = get_queued_listens(&db, 2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+
+        delete_queued_listens(&db, &ids[..1]).await.unwrap();
+
+        let queued = get_queued_listens(&db, 10).await.unwrap();
+        assert_eq!(queued, vec![(ids[1], listen("b"))]);
+    }
+}
@@ -0,0 +1,341 @@
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use listenbrainz::{
+    submit_listens, Listen, ListenBrainzConfig, ListenType, SubmitListensError,
+    MAX_LISTENS_PER_REQUEST,
+};
+use moosicbox_database::profiles::{LibraryDatabase, PROFILES};
+use moosicbox_json_utils::database::DatabaseFetchError;
+use moosicbox_music_models::{id::Id, ApiSource, Track};
+use moosicbox_player::{history::play_threshold, Playback};
+use moosicbox_session::models::UpdateSession;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+pub mod db;
+pub mod listenbrainz;
+
+/// How often the listens that failed to submit are retried.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many times a queued listen is retried before it is dropped from the queue.
+pub const MAX_QUEUED_LISTEN_ATTEMPTS: u32 = 10;
+
+/// How long the scrobble state of a session is kept after its last playback update.
+const SESSION_TTL: Duration = Duration::from_secs(60 * 60);
+
+static CONFIG: LazyLock<RwLock<Option<ListenBrainzConfig>>> = LazyLock::new(|| RwLock::new(None));
+
+/// The scrobble state of the track that is currently playing in each session.
+static SESSIONS: LazyLock<Mutex<HashMap<u64, ScrobbleState>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct ScrobbleState {
+    playback_id: u64,
+    position: u16,
+    track_id: Id,
+    api_source: ApiSource,
+    progress: f64,
+    now_playing_sent: bool,
+    scrobbled: bool,
+    updated: Instant,
+}
+
+impl ScrobbleState {
+    fn new(playback: &Playback, track: &Track) -> Self {
+        Self {
+            playback_id: playback.id,
+            position: playback.position,
+            track_id: track.id.clone(),
+            api_source: track.api_source,
+            progress: playback.progress,
+            now_playing_sent: false,
+            scrobbled: false,
+            updated: Instant::now(),
+        }
+    }
+
+    fn is_playing(&self, playback: &Playback, track: &Track) -> bool {
+        self.playback_id == playback.id
+            && self.position == playback.position
+            && self.track_id == track.id
+            && self.api_source == track.api_source
+    }
+}
+
+/// Drops the scrobble state of the sessions that haven't had a playback update in the last
+/// [`SESSION_TTL`], e.g. sessions that have been deleted.
+fn prune_sessions(sessions: &mut HashMap<u64, ScrobbleState>, now: Instant) {
+    sessions.retain(|_, state| now.saturating_duration_since(state.updated) < SESSION_TTL);
+}
+
+#[derive(Debug, Error)]
+pub enum ScrobbleError {
+    #[error(transparent)]
+    DatabaseFetch(#[from] DatabaseFetchError),
+    #[error(transparent)]
+    SubmitListens(#[from] SubmitListensError),
+}
+
+/// # Panics
+///
+/// * If the `CONFIG` `RwLock` is poisoned
+#[must_use]
+pub fn config() -> Option<ListenBrainzConfig> {
+    CONFIG.read().unwrap().clone()
+}
+
+/// Starts scrobbling the playbacks of the player to the ListenBrainz-compatible API in the
+/// `config`, and retrying the listens that failed to submit until the `cancellation_token` is
+/// cancelled.
+///
+/// # Panics
+///
+/// * If the `CONFIG` `RwLock` is poisoned
+/// * If the `SESSIONS` `Mutex` is poisoned
+pub fn init(config: ListenBrainzConfig, cancellation_token: CancellationToken) -> JoinHandle<()> {
+    log::debug!("Scrobbling listens to {}", config.base_url);
+
+    CONFIG.write().unwrap().replace(config);
+
+    moosicbox_player::on_playback_event(on_playback_event);
+
+    moosicbox_task::spawn("scrobbler: Retry queued listens", async move {
+        let mut interval = tokio::time::interval(RETRY_INTERVAL);
+
+        loop {
+            tokio::select! {
+                () = cancellation_token.cancelled() => {
+                    log::debug!("scrobbler: Cancelled");
+                    break;
+                }
+                _ = interval.tick() => {
+                    prune_sessions(&mut SESSIONS.lock().unwrap(), Instant::now());
+                    retry_queued_listens().await;
+                }
+            }
+        }
+    })
+}
+
+/// Submits a "now playing" listen when a track starts playing, and a completed listen once the
+/// track has played through the `moosicbox_player` play threshold.
+///
+/// # Panics
+///
+/// * If the `SESSIONS` `Mutex` is poisoned
+pub fn on_playback_event(_update: &UpdateSession, current: &Playback) {
+    let Some(track) = current.tracks.get(current.position as usize) else {
+        SESSIONS.lock().unwrap().remove(&current.session_id);
+        return;
+    };
+    let Some(config) = config() else {
+        return;
+    };
+
+    let (now_playing, scrobble) = {
+        let mut sessions = SESSIONS.lock().unwrap();
+        let state = sessions
+            .entry(current.session_id)
+            .or_insert_with(|| ScrobbleState::new(current, track));
+
+        // A new track, or the same track starting over
+        if !state.is_playing(current, track) || (current.progress < 1.0 && state.progress >= 1.0) {
+            *state = ScrobbleState::new(current, track);
+        }
+
+        let now_playing = current.playing && !state.now_playing_sent;
+        let scrobble = !state.scrobbled
+            && play_threshold().played_through(track.duration, state.progress, current.progress);
+
+        state.now_playing_sent |= now_playing;
+        state.scrobbled |= scrobble;
+        state.progress = current.progress;
+        state.updated = Instant::now();
+
+        drop(sessions);
+
+        (now_playing, scrobble)
+    };
+
+    if now_playing {
+        let listen = Listen::new(track, None);
+
+        moosicbox_task::spawn("scrobbler: Submit now playing", {
+            let config = config.clone();
+            async move {
+                if let Err(e) = submit_listens(&config, ListenType::PlayingNow, &[listen]).await {
+                    log::warn!("Failed to submit now playing listen: {e:?}");
+                }
+            }
+        });
+    }
+
+    if scrobble {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let listened_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default()
+            .saturating_sub(current.progress as u64);
+        let listen = Listen::new(track, Some(listened_at));
+        let profile = current.profile.clone();
+
+        moosicbox_task::spawn("scrobbler: Submit listen", async move {
+            if let Err(e) = scrobble_listen(&config, &profile, listen).await {
+                log::error!("Failed to scrobble listen: {e:?}");
+            }
+        });
+    }
+}
+
+/// Submits a completed listen, queueing it in the profile database to be retried later if it
+/// could not be submitted.
+///
+/// # Errors
+///
+/// * If the listen failed to submit and could not be queued
+pub async fn scrobble_listen(
+    config: &ListenBrainzConfig,
+    profile: &str,
+    listen: Listen,
+) -> Result<(), ScrobbleError> {
+    match submit_listens(config, ListenType::Single, std::slice::from_ref(&listen)).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.is_retryable() => {
+            log::debug!("Failed to submit listen, queueing it to retry later: {e:?}");
+
+            let Some(db) = PROFILES.get(profile) else {
+                return Err(e.into());
+            };
+
+            Ok(db::add_queued_listen(&db, &listen).await?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn retry_queued_listens() {
+    let Some(config) = config() else {
+        return;
+    };
+
+    for profile in PROFILES.names() {
+        let Some(db) = PROFILES.get(&profile) else {
+            continue;
+        };
+
+        if let Err(e) = submit_queued_listens(&config, &db).await {
+            log::debug!("Failed to submit queued listens for profile '{profile}': {e:?}");
+        }
+    }
+}
+
+/// Submits the queued listens in the `db`, oldest first, until the queue is empty or a
+/// submission fails. Listens that the server rejects, or that failed to submit
+/// [`MAX_QUEUED_LISTEN_ATTEMPTS`] times, are dropped from the queue.
+///
+/// # Errors
+///
+/// * If there was a database error
+/// * If the listens failed to submit
+pub async fn submit_queued_listens(
+    config: &ListenBrainzConfig,
+    db: &LibraryDatabase,
+) -> Result<(), ScrobbleError> {
+    loop {
+        let queued = db::get_queued_listens(db, MAX_LISTENS_PER_REQUEST).await?;
+
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        let count = queued.len();
+        let (ids, listens): (Vec<_>, Vec<_>) = queued.into_iter().unzip();
+        let listen_type = if count == 1 {
+            ListenType::Single
+        } else {
+            ListenType::Import
+        };
+
+        match submit_listens(config, listen_type, &listens).await {
+            Ok(()) => {
+                log::debug!("Submitted {count} queued listen(s)");
+            }
+            Err(e) if e.is_retryable() => {
+                db::increment_queued_listen_attempts(db, &ids).await?;
+
+                let dropped =
+                    db::delete_exhausted_queued_listens(db, MAX_QUEUED_LISTEN_ATTEMPTS).await?;
+                if dropped > 0 {
+                    log::error!(
+                        "Dropping {dropped} queued listen(s) after {MAX_QUEUED_LISTEN_ATTEMPTS} failed attempts"
+                    );
+                }
+
+                return Err(e.into());
+            }
+            Err(e) => {
+                log::error!("Dropping {count} queued listen(s) rejected by the server: {e:?}");
+            }
+        }
+
+        db::delete_queued_listens(db, &ids).await?;
+
+        if count < MAX_LISTENS_PER_REQUEST {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state(updated: Instant) -> ScrobbleState {
+        ScrobbleState {
+            playback_id: 1,
+            position: 0,
+            track_id: Id::Number(1),
+            api_source: ApiSource::Library,
+            progress: 0.0,
+            now_playing_sent: false,
+            scrobbled: false,
+            updated,
+        }
+    }
+
+    #[test]
+    fn prune_sessions_drops_sessions_without_a_recent_playback_update() {
+        let now = Instant::now();
+        let mut sessions = HashMap::new();
+        sessions.insert(1, state(now));
+        sessions.insert(2, state(now.checked_sub(SESSION_TTL).unwrap()));
+        sessions.insert(3, state(now.checked_sub(SESSION_TTL / 2).unwrap()));
+
+        prune_sessions(&mut sessions, now);
+
+        let mut ids = sessions.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 3]);
+    }
+
+    #[test]
+    fn prune_sessions_keeps_sessions_updated_after_now() {
+        let now = Instant::now();
+        let mut sessions = HashMap::new();
+        sessions.insert(1, state(now + Duration::from_secs(1)));
+
+        prune_sessions(&mut sessions, now);
+
+        assert_eq!(sessions.len(), 1);
+    }
+}
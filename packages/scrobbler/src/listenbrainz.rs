@@ -0,0 +1,165 @@
+#![allow(clippy::module_name_repetitions)]
+
+use std::sync::LazyLock;
+
+use moosicbox_music_models::Track;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.listenbrainz.org";
+
+/// The maximum number of listens that can be submitted in a single request.
+pub const MAX_LISTENS_PER_REQUEST: usize = 100;
+
+static CLIENT: LazyLock<reqwest::Client> =
+    LazyLock::new(|| reqwest::Client::builder().build().unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenBrainzConfig {
+    /// The base URL of the ListenBrainz-compatible API, e.g. `https://api.listenbrainz.org`
+    pub base_url: String,
+    /// The user token to authenticate the submissions with
+    pub token: String,
+}
+
+impl ListenBrainzConfig {
+    /// Reads the config from the `LISTENBRAINZ_TOKEN` and `LISTENBRAINZ_API_URL` environment
+    /// variables. Returns `None` if no token is set.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let token = std::env::var("LISTENBRAINZ_TOKEN")
+            .ok()
+            .filter(|x| !x.is_empty())?;
+        let base_url = std::env::var("LISTENBRAINZ_API_URL")
+            .ok()
+            .filter(|x| !x.is_empty())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+
+        Some(Self { base_url, token })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListenType {
+    Single,
+    PlayingNow,
+    Import,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AdditionalInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracknumber: Option<u32>,
+    pub media_player: String,
+    pub submission_client: String,
+    pub submission_client_version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TrackMetadata {
+    pub artist_name: String,
+    pub track_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_name: Option<String>,
+    pub additional_info: AdditionalInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Listen {
+    /// Seconds since the unix epoch when the track started playing. Omitted for
+    /// [`ListenType::PlayingNow`] submissions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listened_at: Option<u64>,
+    pub track_metadata: TrackMetadata,
+}
+
+impl Listen {
+    #[must_use]
+    pub fn new(track: &Track, listened_at: Option<u64>) -> Self {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let duration_ms = (track.duration > 0.0).then(|| (track.duration * 1000.0) as u64);
+
+        Self {
+            listened_at,
+            track_metadata: TrackMetadata {
+                artist_name: track.artist.clone(),
+                track_name: track.title.clone(),
+                release_name: Some(track.album.clone()).filter(|x| !x.is_empty()),
+                additional_info: AdditionalInfo {
+                    duration_ms,
+                    tracknumber: Some(track.number).filter(|x| *x > 0),
+                    media_player: "MoosicBox".to_string(),
+                    submission_client: "MoosicBox".to_string(),
+                    submission_client_version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SubmitListens<'a> {
+    listen_type: ListenType,
+    payload: &'a [Listen],
+}
+
+#[derive(Debug, Error)]
+pub enum SubmitListensError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Request failed ({status}): {message}")]
+    Status { status: u16, message: String },
+}
+
+impl SubmitListensError {
+    /// Whether the submission could succeed if it were sent again later, e.g. when the server
+    /// could not be reached or is rate limiting the requests.
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::Reqwest(_) => true,
+            Self::Status { status, .. } => *status == 429 || *status >= 500,
+        }
+    }
+}
+
+/// # Errors
+///
+/// * If the request failed to send
+/// * If the server responded with an unsuccessful status
+pub async fn submit_listens(
+    config: &ListenBrainzConfig,
+    listen_type: ListenType,
+    listens: &[Listen],
+) -> Result<(), SubmitListensError> {
+    let url = format!("{}/1/submit-listens", config.base_url.trim_end_matches('/'));
+
+    log::debug!(
+        "submit_listens: Submitting {} listen(s) listen_type={listen_type:?} url={url}",
+        listens.len()
+    );
+
+    let response = CLIENT
+        .post(&url)
+        .header("Authorization", format!("Token {}", config.token))
+        .json(&SubmitListens {
+            listen_type,
+            payload: listens,
+        })
+        .send()
+        .await?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        return Err(SubmitListensError::Status {
+            status: status.as_u16(),
+            message: response.text().await.unwrap_or_default(),
+        });
+    }
+
+    Ok(())
+}
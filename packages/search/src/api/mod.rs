@@ -2,11 +2,12 @@ use std::str::FromStr;
 
 use actix_web::{
     dev::{ServiceFactory, ServiceRequest},
-    error::ErrorInternalServerError,
+    error::{ErrorBadRequest, ErrorInternalServerError},
     get,
     web::{self, Json},
     Result, Scope,
 };
+use moosicbox_database::profiles::api::ProfileName;
 use moosicbox_json_utils::{tantivy::ToValue, ParseError, ToValueType};
 use moosicbox_music_models::{api::ApiAlbumVersionQuality, AudioFormat, TrackApiSource};
 use serde::Deserialize;
@@ -14,10 +15,14 @@ use tantivy::schema::NamedFieldDocument;
 
 pub mod models;
 
-use crate::search_global_search_index;
+use crate::{
+    global_search_facets, search_global_search_index, suggest_global_search, SearchIndexError,
+    MAX_SUGGESTIONS,
+};
 use models::{
     ApiGlobalAlbumSearchResult, ApiGlobalArtistSearchResult, ApiGlobalSearchResult,
     ApiGlobalTrackSearchResult, ApiRawSearchResultsResponse, ApiSearchResultsResponse,
+    ApiSearchSuggestionsResponse,
 };
 
 pub fn bind_services<
@@ -28,6 +33,7 @@ pub fn bind_services<
     scope
         .service(search_global_search_endpoint)
         .service(search_raw_global_search_endpoint)
+        .service(search_suggest_endpoint)
 }
 
 #[derive(Deserialize, Clone)]
@@ -156,20 +162,31 @@ impl ApiGlobalSearchResult {
     }
 }
 
-#[get("/global-search")]
-pub async fn search_global_search_endpoint(
-    query: web::Query<SearchGlobalSearchQuery>,
-) -> Result<Json<ApiSearchResultsResponse>> {
-    let limit = query.limit.unwrap_or(10);
-    let offset = query.offset.unwrap_or(0);
+/// Searches the global search index of the profile, skipping results that
+/// fail to parse and duplicates of the same artist, album or track, and
+/// counts the facets of the matches.
+///
+/// # Panics
+///
+/// * If any `RwLock`s are poisoned
+///
+/// # Errors
+///
+/// * If failed to search the global search index
+pub fn global_search(
+    profile: &str,
+    query: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> std::result::Result<ApiSearchResultsResponse, SearchIndexError> {
+    let limit = limit.unwrap_or(10);
+    let offset = offset.unwrap_or(0);
 
     let mut position = offset;
     let mut results: Vec<ApiGlobalSearchResult> = vec![];
 
     while results.len() < limit {
-        let values = search_global_search_index(&query.query, position, limit).map_err(|e| {
-            ErrorInternalServerError(format!("Failed to search global search index: {e:?}"))
-        })?;
+        let values = search_global_search_index(profile, query, position, limit)?;
 
         if values.is_empty() {
             break;
@@ -196,7 +213,26 @@ pub async fn search_global_search_endpoint(
         }
     }
 
-    Ok(Json(ApiSearchResultsResponse { position, results }))
+    let facets = global_search_facets(profile, query)?;
+
+    Ok(ApiSearchResultsResponse {
+        position,
+        results,
+        facets: Some(facets.into()),
+    })
+}
+
+#[get("/global-search")]
+pub async fn search_global_search_endpoint(
+    query: web::Query<SearchGlobalSearchQuery>,
+    profile: ProfileName,
+) -> Result<Json<ApiSearchResultsResponse>> {
+    let results =
+        global_search(&profile.0, &query.query, query.offset, query.limit).map_err(|e| {
+            ErrorInternalServerError(format!("Failed to search global search index: {e:?}"))
+        })?;
+
+    Ok(Json(results))
 }
 
 #[derive(Deserialize, Clone)]
@@ -210,6 +246,7 @@ pub struct SearchRawGlobalSearchQuery {
 #[get("/raw-global-search")]
 pub async fn search_raw_global_search_endpoint(
     query: web::Query<SearchRawGlobalSearchQuery>,
+    profile: ProfileName,
 ) -> Result<Json<ApiRawSearchResultsResponse>> {
     let limit = query.limit.unwrap_or(10);
     let offset = query.offset.unwrap_or(0);
@@ -218,9 +255,10 @@ pub async fn search_raw_global_search_endpoint(
     let mut results: Vec<NamedFieldDocument> = vec![];
 
     while results.len() < limit {
-        let values = search_global_search_index(&query.query, position, limit).map_err(|e| {
-            ErrorInternalServerError(format!("Failed to search global search index: {e:?}"))
-        })?;
+        let values = search_global_search_index(&profile.0, &query.query, position, limit)
+            .map_err(|e| {
+                ErrorInternalServerError(format!("Failed to search global search index: {e:?}"))
+            })?;
 
         if values.is_empty() {
             break;
@@ -239,3 +277,34 @@ pub async fn search_raw_global_search_endpoint(
 
     Ok(Json(ApiRawSearchResultsResponse { position, results }))
 }
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSuggestQuery {
+    query: String,
+    limit: Option<usize>,
+}
+
+#[get("/suggest")]
+pub async fn search_suggest_endpoint(
+    query: web::Query<SearchSuggestQuery>,
+    profile: ProfileName,
+) -> Result<Json<ApiSearchSuggestionsResponse>> {
+    let limit = query.limit.unwrap_or(10);
+
+    if !(1..=MAX_SUGGESTIONS).contains(&limit) {
+        return Err(ErrorBadRequest(format!(
+            "Invalid limit {limit}, must be between 1 and {MAX_SUGGESTIONS}"
+        )));
+    }
+
+    let suggestions = suggest_global_search(&profile.0, &query.query, limit).map_err(|e| {
+        ErrorInternalServerError(format!(
+            "Failed to suggest global search completions: {e:?}"
+        ))
+    })?;
+
+    Ok(Json(ApiSearchSuggestionsResponse {
+        suggestions: suggestions.into_iter().map(Into::into).collect(),
+    }))
+}
@@ -2,6 +2,8 @@ use moosicbox_music_models::{api::ApiAlbumVersionQuality, id::Id, AudioFormat, T
 use serde::Serialize;
 use tantivy::schema::NamedFieldDocument;
 
+use crate::{SearchFacets, SearchSuggestion, SearchSuggestionType};
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -58,12 +60,54 @@ pub enum ApiGlobalSearchResult {
     Track(ApiGlobalTrackSearchResult),
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiSearchFacet {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiSearchFacets {
+    pub formats: Vec<ApiSearchFacet>,
+    pub sources: Vec<ApiSearchFacet>,
+    /// Decades are formatted like `1990s`
+    pub decades: Vec<ApiSearchFacet>,
+}
+
+fn to_api_facets(counts: impl IntoIterator<Item = (String, usize)>) -> Vec<ApiSearchFacet> {
+    counts
+        .into_iter()
+        .map(|(value, count)| ApiSearchFacet { value, count })
+        .collect()
+}
+
+impl From<SearchFacets> for ApiSearchFacets {
+    fn from(value: SearchFacets) -> Self {
+        Self {
+            formats: to_api_facets(value.formats),
+            sources: to_api_facets(value.sources),
+            decades: to_api_facets(
+                value
+                    .decades
+                    .into_iter()
+                    .map(|(decade, count)| (format!("{decade}s"), count)),
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ApiSearchResultsResponse {
     pub position: usize,
     pub results: Vec<ApiGlobalSearchResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<ApiSearchFacets>,
 }
 
 impl From<Vec<ApiGlobalSearchResult>> for ApiSearchResultsResponse {
@@ -71,6 +115,7 @@ impl From<Vec<ApiGlobalSearchResult>> for ApiSearchResultsResponse {
         Self {
             position: 0,
             results: value,
+            facets: None,
         }
     }
 }
@@ -81,3 +126,28 @@ pub struct ApiRawSearchResultsResponse {
     pub position: usize,
     pub results: Vec<NamedFieldDocument>,
 }
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiSearchSuggestion {
+    pub value: String,
+    #[serde(rename = "type")]
+    pub suggestion_type: SearchSuggestionType,
+}
+
+impl From<SearchSuggestion> for ApiSearchSuggestion {
+    fn from(value: SearchSuggestion) -> Self {
+        Self {
+            value: value.value,
+            suggestion_type: value.suggestion_type,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiSearchSuggestionsResponse {
+    pub suggestions: Vec<ApiSearchSuggestion>,
+}
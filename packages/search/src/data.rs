@@ -1,18 +1,25 @@
-use std::path::Path;
-
 use moosicbox_json_utils::database::DatabaseFetchError;
 use moosicbox_music_models::{Album, Artist, Track};
 use thiserror::Error;
 use tokio::task::JoinError;
 
-use crate::{
-    DataValue, PopulateIndexError, RecreateIndexError, GLOBAL_SEARCH_INDEX_PATH, SEMAPHORE,
-};
+use crate::{DataValue, PopulateIndexError, RecreateIndexError, SEMAPHORE};
 
 pub trait AsDataValues {
     fn as_data_values<'a>(&self) -> Vec<(&'a str, DataValue)>;
 }
 
+/// The year at the start of a release date, e.g. `2017-06-02T00:00:00`
+fn release_year<'a>(date_released: Option<&str>) -> Option<(&'a str, DataValue)> {
+    let year = date_released?.get(..4)?;
+
+    if !year.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(("release_year", DataValue::Number(year.parse().ok()?)))
+}
+
 impl AsDataValues for Artist {
     fn as_data_values<'a>(&self) -> Vec<(&'a str, DataValue)> {
         vec![
@@ -58,6 +65,8 @@ impl AsDataValues for Album {
             ),
         ];
 
+        data.extend(release_year(self.date_released.as_deref()));
+
         for version in &self.versions {
             data.extend_from_slice(&[
                 (
@@ -93,7 +102,7 @@ impl AsDataValues for Album {
 
 impl AsDataValues for Track {
     fn as_data_values<'a>(&self) -> Vec<(&'a str, DataValue)> {
-        vec![
+        let mut data = vec![
             ("document_type", DataValue::String("tracks".into())),
             ("artist_title", DataValue::String(self.artist.clone())),
             ("artist_id", DataValue::String(self.artist_id.to_string())),
@@ -137,7 +146,11 @@ impl AsDataValues for Track {
                 "version_sources",
                 DataValue::String(self.track_source.as_ref().to_string()),
             ),
-        ]
+        ];
+
+        data.extend(release_year(self.date_released.as_deref()));
+
+        data
     }
 }
 
@@ -179,11 +192,11 @@ pub enum ReindexFromDbError {
 ///
 /// * If failed to recreate the global search index
 /// * If the tokio task failed to join
-pub async fn recreate_global_search_index() -> Result<(), RecreateIndexError> {
+pub async fn recreate_global_search_index(profile: &str) -> Result<(), RecreateIndexError> {
     let permit = SEMAPHORE.acquire().await;
-    moosicbox_task::spawn_blocking("recreate_global_search_index", || {
-        let path: &Path = GLOBAL_SEARCH_INDEX_PATH.as_ref();
-        crate::recreate_global_search_index_sync(path)
+    moosicbox_task::spawn_blocking("recreate_global_search_index", {
+        let profile = profile.to_string();
+        move || crate::recreate_global_search_index_sync(&profile)
     })
     .await??;
     drop(permit);
@@ -1,18 +1,28 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
-use std::sync::{LazyLock, RwLock};
+use std::sync::{LazyLock, Once, RwLock};
 
 use itertools::Itertools;
-use tantivy::collector::TopDocs;
+use query::{NumberRange, SearchFilter, SearchQuery};
+use tantivy::collector::{FacetCollector, FacetCounts, TopDocs};
 use tantivy::directory::MmapDirectory;
-use tantivy::query::{BooleanQuery, BoostQuery, DisjunctionMaxQuery, QueryParser, TermQuery};
+use tantivy::query::{
+    AllQuery, BooleanQuery, BoostQuery, DisjunctionMaxQuery, FuzzyTermQuery, Query, QueryParser,
+    RangeQuery, TermQuery,
+};
 use tantivy::query_grammar::Occur;
+use tantivy::tokenizer::{
+    AsciiFoldingFilter, LowerCaser, RemoveLongFilter, SimpleTokenizer, TextAnalyzer,
+    TokenStream as _,
+};
 use tantivy::{
     schema::{
-        Document, Field, IndexRecordOption, NamedFieldDocument, Schema, TantivyDocument, Term,
-        STORED, STRING, TEXT,
+        Document, Facet, FacetOptions, Field, IndexRecordOption, NamedFieldDocument, OwnedValue,
+        Schema, TantivyDocument, Term, TextFieldIndexing, TextOptions, INDEXED, STORED, STRING,
+        TEXT,
     },
     Directory, IndexWriter,
 };
@@ -25,13 +35,14 @@ use tokio::task::JoinError;
 pub mod api;
 #[cfg(feature = "db")]
 pub mod data;
+pub mod query;
 
 static SEMAPHORE: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(1));
 
 #[cfg(test)]
 static TESTS_DIR_PATH: LazyLock<PathBuf> = LazyLock::new(moosicbox_config::get_tests_dir_path);
 
-static GLOBAL_SEARCH_INDEX_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+static SEARCH_INDICES_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
     #[cfg(test)]
     let base_path = TESTS_DIR_PATH.to_path_buf();
 
@@ -39,63 +50,181 @@ static GLOBAL_SEARCH_INDEX_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
     let base_path =
         moosicbox_config::get_config_dir_path().expect("Failed to get config directory");
 
-    base_path.join("search_indices").join("global_search_index")
+    base_path.join("search_indices").join("profiles")
+});
+
+/// Where the single global search index was stored before indices were split
+/// per profile
+static LEGACY_GLOBAL_SEARCH_INDEX_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    SEARCH_INDICES_PATH
+        .parent()
+        .expect("Search indices path has no parent")
+        .join("global_search_index")
 });
 
+static REMOVE_LEGACY_GLOBAL_SEARCH_INDEX: Once = Once::new();
+
+fn global_search_index_path(profile: &str) -> PathBuf {
+    SEARCH_INDICES_PATH
+        .join(profile)
+        .join("global_search_index")
+}
+
 static GLOBAL_SEARCH_INDEX_WRITER_MEMORY_BUDGET: LazyLock<RwLock<usize>> =
     LazyLock::new(|| RwLock::new(50_000_000));
 
 static GLOBAL_SEARCH_INDEX_WRITER_NUM_THREADS: LazyLock<RwLock<Option<usize>>> =
     LazyLock::new(|| RwLock::new(None));
 
-#[derive(Debug, Error, Clone)]
+#[derive(Clone)]
+struct GlobalSearchIndex {
+    index: Index,
+    reader: IndexReader,
+}
+
+/// The global search index of each profile, opened on first use
+static GLOBAL_SEARCH_INDICES: LazyLock<RwLock<BTreeMap<String, GlobalSearchIndex>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+/// The profiles whose global search index was created empty when it was
+/// opened and hasn't been repopulated since
+static UNPOPULATED_GLOBAL_SEARCH_INDICES: LazyLock<RwLock<BTreeSet<String>>> =
+    LazyLock::new(|| RwLock::new(BTreeSet::new()));
+
+#[derive(Debug, Error)]
 pub enum GetGlobalSearchIndexError {
     #[error(transparent)]
-    Tantivy(#[from] tantivy::error::TantivyError),
-    #[error("{0}")]
-    FailedToGetIndex(String),
+    CreateIndex(#[from] CreateIndexError),
+    #[error(transparent)]
+    GetIndexReader(#[from] GetIndexReaderError),
 }
 
-static GLOBAL_SEARCH_INDEX: LazyLock<RwLock<Result<Index, GetGlobalSearchIndexError>>> =
-    LazyLock::new(|| {
-        let path: &Path = GLOBAL_SEARCH_INDEX_PATH.as_ref();
-        RwLock::new(
-            create_global_search_index(path, false).map_err(|e| match e {
-                CreateIndexError::Tantivy(err) => GetGlobalSearchIndexError::Tantivy(err),
-                _ => GetGlobalSearchIndexError::FailedToGetIndex(e.to_string()),
-            }),
-        )
-    });
+/// Returns the opened index and whether it was newly created
+fn open_global_search_index(
+    profile: &str,
+    recreate_if_exists: bool,
+) -> Result<(GlobalSearchIndex, bool), GetGlobalSearchIndexError> {
+    let (index, created) =
+        create_global_search_index(&global_search_index_path(profile), recreate_if_exists)?;
+    let reader = get_index_reader(&index)?;
 
-#[derive(Debug, Error, Clone)]
-pub enum GetGlobalSearchReaderError {
-    #[error(transparent)]
-    Tantivy(#[from] tantivy::error::TantivyError),
-    #[error("{0}")]
-    FailedToGetReader(String),
+    Ok((GlobalSearchIndex { index, reader }, created))
 }
 
-static GLOBAL_SEARCH_READER: LazyLock<RwLock<Result<IndexReader, GetGlobalSearchReaderError>>> =
-    LazyLock::new(|| {
-        let binding = GLOBAL_SEARCH_INDEX.read().unwrap();
-        let index = match binding.as_ref() {
-            Ok(index) => index,
-            Err(err) => match err {
-                GetGlobalSearchIndexError::Tantivy(err) => {
-                    return RwLock::new(Err(GetGlobalSearchReaderError::Tantivy(err.clone())))
-                }
-                GetGlobalSearchIndexError::FailedToGetIndex(_) => {
-                    return RwLock::new(Err(GetGlobalSearchReaderError::FailedToGetReader(
-                        err.to_string(),
-                    )))
-                }
-            },
-        };
-        let index = get_index_reader(index)
-            .map_err(|e| GetGlobalSearchReaderError::FailedToGetReader(e.to_string()));
-        drop(binding);
-        RwLock::new(index)
-    });
+fn get_global_search_index(profile: &str) -> Result<GlobalSearchIndex, GetGlobalSearchIndexError> {
+    if let Some(index) = GLOBAL_SEARCH_INDICES.read().unwrap().get(profile) {
+        return Ok(index.clone());
+    }
+
+    let mut indices = GLOBAL_SEARCH_INDICES.write().unwrap();
+
+    if let Some(index) = indices.get(profile) {
+        return Ok(index.clone());
+    }
+
+    log::debug!("Opening global search index for profile={profile}");
+    let (index, created) = open_global_search_index(profile, false)?;
+    indices.insert(profile.to_string(), index.clone());
+    drop(indices);
+
+    if created {
+        UNPOPULATED_GLOBAL_SEARCH_INDICES
+            .write()
+            .unwrap()
+            .insert(profile.to_string());
+    }
+
+    Ok(index)
+}
+
+fn remove_legacy_global_search_index() {
+    let path = LEGACY_GLOBAL_SEARCH_INDEX_PATH.as_path();
+
+    if path.exists() {
+        log::info!("Removing legacy global search index at {path:?}");
+        if let Err(e) = std::fs::remove_dir_all(path) {
+            log::error!("Failed to remove legacy global search index at {path:?}: {e:?}");
+        }
+    }
+}
+
+/// Opens the global search index for the profile, creating it if it doesn't
+/// exist yet. The global index from before indices were split per profile is
+/// removed, since each profile's library is reindexed into its own index.
+///
+/// Returns whether the index was created empty (new profile, migrated from the
+/// legacy index or recreated for an outdated schema) and needs to be reindexed
+/// from the library.
+///
+/// # Panics
+///
+/// * If any `RwLock`s are poisoned
+///
+/// # Errors
+///
+/// * If the index failed to open
+pub fn init_global_search_index(profile: &str) -> Result<bool, GetGlobalSearchIndexError> {
+    REMOVE_LEGACY_GLOBAL_SEARCH_INDEX.call_once(remove_legacy_global_search_index);
+
+    get_global_search_index(profile)?;
+
+    Ok(UNPOPULATED_GLOBAL_SEARCH_INDICES
+        .read()
+        .unwrap()
+        .contains(profile))
+}
+
+/// Closes the global search index for the profile and deletes it from disk.
+///
+/// # Panics
+///
+/// * If any `RwLock`s are poisoned
+///
+/// # Errors
+///
+/// * If the index directory failed to be deleted
+pub fn drop_global_search_index(profile: &str) -> Result<(), std::io::Error> {
+    log::debug!("Dropping global search index for profile={profile}");
+    GLOBAL_SEARCH_INDICES.write().unwrap().remove(profile);
+    UNPOPULATED_GLOBAL_SEARCH_INDICES
+        .write()
+        .unwrap()
+        .remove(profile);
+
+    let path = SEARCH_INDICES_PATH.join(profile);
+
+    if path.exists() {
+        std::fs::remove_dir_all(path)?;
+    }
+
+    Ok(())
+}
+
+/// The tokenizer for the title search fields. It lowercases and ASCII folds
+/// terms so that e.g. `bjork` matches `Björk`.
+const FOLDED_TOKENIZER: &str = "folded";
+
+fn folded_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(AsciiFoldingFilter)
+        .build()
+}
+
+/// The terms `text` is indexed as in the fields using the `FOLDED_TOKENIZER`
+#[must_use]
+pub fn folded_terms(text: &str) -> Vec<String> {
+    let mut analyzer = folded_analyzer();
+    let mut stream = analyzer.token_stream(text);
+    let mut terms = vec![];
+
+    while stream.advance() {
+        terms.push(stream.token().text.clone());
+    }
+
+    terms
+}
 
 #[derive(Debug, Error)]
 pub enum CreateIndexError {
@@ -109,10 +238,11 @@ pub enum CreateIndexError {
     IO(#[from] std::io::Error),
 }
 
+/// Returns the index and whether it was newly created, i.e. is empty
 fn create_global_search_index(
     path: &Path,
     recreate_if_exists: bool,
-) -> Result<Index, CreateIndexError> {
+) -> Result<(Index, bool), CreateIndexError> {
     std::fs::create_dir_all(path)
         .unwrap_or_else(|_| panic!("Failed to create global search index directory at {path:?}"));
 
@@ -141,11 +271,17 @@ fn create_global_search_index(
     // This store is useful for reconstructing the
     // documents that were selected during the search phase.
 
+    let folded_text = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(FOLDED_TOKENIZER)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+
     schema_builder.add_text_field("document_type", TEXT | STORED);
     schema_builder.add_text_field("document_type_string", STRING);
 
     schema_builder.add_text_field("artist_title", STORED);
-    schema_builder.add_text_field("artist_title_search", TEXT);
+    schema_builder.add_text_field("artist_title_search", folded_text.clone());
     schema_builder.add_text_field("artist_title_string", STRING);
 
     schema_builder.add_text_field("artist_id", STORED);
@@ -153,7 +289,7 @@ fn create_global_search_index(
     schema_builder.add_text_field("artist_id_string", STRING);
 
     schema_builder.add_text_field("album_title", STORED);
-    schema_builder.add_text_field("album_title_search", TEXT);
+    schema_builder.add_text_field("album_title_search", folded_text.clone());
     schema_builder.add_text_field("album_title_string", STRING);
 
     schema_builder.add_text_field("album_id", STORED);
@@ -161,7 +297,7 @@ fn create_global_search_index(
     schema_builder.add_text_field("album_id_string", STRING);
 
     schema_builder.add_text_field("track_title", STORED);
-    schema_builder.add_text_field("track_title_search", TEXT);
+    schema_builder.add_text_field("track_title_search", folded_text.clone());
     schema_builder.add_text_field("track_title_string", STRING);
 
     schema_builder.add_text_field("track_id", STORED);
@@ -181,15 +317,20 @@ fn create_global_search_index(
 
     schema_builder.add_text_field("version_formats", TEXT | STORED);
     schema_builder.add_text_field("version_formats_string", STRING);
+    schema_builder.add_facet_field("version_formats_facet", FacetOptions::default());
 
-    schema_builder.add_u64_field("version_bit_depths", STORED);
+    schema_builder.add_u64_field("release_year", INDEXED | STORED);
+    schema_builder.add_facet_field("release_year_facet", FacetOptions::default());
 
-    schema_builder.add_u64_field("version_sample_rates", STORED);
+    schema_builder.add_u64_field("version_bit_depths", INDEXED | STORED);
+
+    schema_builder.add_u64_field("version_sample_rates", INDEXED | STORED);
 
     schema_builder.add_u64_field("version_channels", STORED);
 
     schema_builder.add_text_field("version_sources", TEXT | STORED);
     schema_builder.add_text_field("version_sources_string", STRING);
+    schema_builder.add_facet_field("version_sources_facet", FacetOptions::default());
 
     let schema = schema_builder.build();
 
@@ -202,7 +343,7 @@ fn create_global_search_index(
 
     let mmap_directory = MmapDirectory::open(path)?;
 
-    Ok(if recreate_if_exists {
+    let (index, created) = if recreate_if_exists {
         if Index::exists(&mmap_directory)? {
             log::debug!("Deleting existing index in dir {path:?}");
             std::fs::remove_dir_all(path)?;
@@ -211,32 +352,52 @@ fn create_global_search_index(
             log::trace!("No existing index in dir {path:?}");
         }
         log::debug!("Creating Index in dir {path:?}");
-        Index::create_in_dir(path, schema)?
+        (Index::create_in_dir(path, schema)?, true)
     } else {
+        let exists = Index::exists(&mmap_directory)?;
         let directory: Box<dyn Directory> = Box::new(mmap_directory);
         log::debug!("Opening or creating index in dir {path:?}");
-        Index::open_or_create(directory, schema)?
-    })
+        match Index::open_or_create(directory, schema.clone()) {
+            Ok(index) => (index, !exists),
+            Err(tantivy::TantivyError::SchemaError(e)) => {
+                log::warn!("Recreating index in dir {path:?} since its schema is outdated ({e})");
+                std::fs::remove_dir_all(path)?;
+                std::fs::create_dir_all(path)?;
+                (Index::create_in_dir(path, schema)?, true)
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    // Tokenizers aren't persisted with the index, so they need to be
+    // registered every time it is opened
+    index
+        .tokenizers()
+        .register(FOLDED_TOKENIZER, folded_analyzer());
+
+    Ok((index, created))
 }
 
 #[derive(Debug, Error)]
 pub enum RecreateIndexError {
     #[error(transparent)]
-    CreateIndex(#[from] CreateIndexError),
-    #[error(transparent)]
-    GetIndexReader(#[from] GetIndexReaderError),
+    GetGlobalSearchIndex(#[from] GetGlobalSearchIndexError),
     #[error(transparent)]
     Join(#[from] JoinError),
 }
 
-fn recreate_global_search_index_sync(path: &Path) -> Result<(), RecreateIndexError> {
-    let index = create_global_search_index(path, true)?;
-    let reader = get_index_reader(&index)?;
+fn recreate_global_search_index_sync(profile: &str) -> Result<(), RecreateIndexError> {
+    let (index, _) = open_global_search_index(profile, true)?;
 
-    log::trace!("Resetting GLOBAL_SEARCH_INDEX value");
-    *GLOBAL_SEARCH_INDEX.write().unwrap() = Ok(index);
-    log::trace!("Resetting GLOBAL_SEARCH_READER value");
-    *GLOBAL_SEARCH_READER.write().unwrap() = Ok(reader);
+    log::trace!("Resetting global search index for profile={profile}");
+    GLOBAL_SEARCH_INDICES
+        .write()
+        .unwrap()
+        .insert(profile.to_string(), index);
+    UNPOPULATED_GLOBAL_SEARCH_INDICES
+        .write()
+        .unwrap()
+        .remove(profile);
 
     Ok(())
 }
@@ -261,8 +422,6 @@ pub enum PopulateIndexError {
     #[error(transparent)]
     GetGlobalSearchIndex(#[from] GetGlobalSearchIndexError),
     #[error(transparent)]
-    GetGlobalSearchReader(#[from] GetGlobalSearchReaderError),
-    #[error(transparent)]
     Tantivy(#[from] tantivy::error::TantivyError),
     #[error(transparent)]
     Join(#[from] JoinError),
@@ -279,11 +438,13 @@ pub enum DataValue {
 ///
 /// * If failed to populate the global search index
 pub async fn populate_global_search_index(
+    profile: &str,
     data: &[Vec<(&str, DataValue)>],
     delete: bool,
 ) -> Result<(), PopulateIndexError> {
     let permit = SEMAPHORE.acquire().await;
     moosicbox_task::spawn_blocking("populate_global_search_index", {
+        let profile = profile.to_string();
         let data = data
             .iter()
             .map(|x| {
@@ -294,6 +455,7 @@ pub async fn populate_global_search_index(
             .collect::<Vec<_>>();
         move || {
             populate_global_search_index_sync(
+                &profile,
                 &data
                     .iter()
                     .map(|x| {
@@ -319,18 +481,18 @@ pub async fn populate_global_search_index(
 ///
 /// * If failed to populate the global search index
 pub fn populate_global_search_index_sync(
+    profile: &str,
     data: &[Vec<(&str, DataValue)>],
     delete: bool,
 ) -> Result<(), PopulateIndexError> {
-    log::debug!("Populating global search index...");
+    log::debug!("Populating global search index for profile={profile}...");
 
     if data.is_empty() {
         log::debug!("No data to populate.");
         return Ok(());
     }
 
-    let binding = GLOBAL_SEARCH_INDEX.read().unwrap();
-    let index = binding.as_ref().map_err(Clone::clone)?;
+    let GlobalSearchIndex { index, reader } = get_global_search_index(profile)?;
     let schema = index.schema();
     // To insert a document we will need an index writer.
     // There must be only one writer at a time.
@@ -365,6 +527,11 @@ pub fn populate_global_search_index_sync(
                     if let Ok(string_field) = schema.get_field(&format!("{key}_string")) {
                         doc.add_text(string_field, value.clone());
                     }
+                    if let Ok(facet_field) = schema.get_field(&format!("{key}_facet")) {
+                        if !value.is_empty() {
+                            doc.add_facet(facet_field, Facet::from_path([value]));
+                        }
+                    }
                     if let Ok(search_field) = schema.get_field(&format!("{key}_search")) {
                         doc.add_text(search_field, value.clone());
 
@@ -407,6 +574,10 @@ pub fn populate_global_search_index_sync(
                 }
                 DataValue::Number(value) => {
                     doc.add_u64(field, *value);
+                    if let Ok(facet_field) = schema.get_field(&format!("{key}_facet")) {
+                        // Numbers are faceted by their decade, then by themselves
+                        doc.add_facet(facet_field, Facet::from_path([value / 10 * 10, *value]));
+                    }
                 }
             }
         }
@@ -426,13 +597,7 @@ pub fn populate_global_search_index_sync(
     //
     // This call is blocking.
     index_writer.commit()?;
-    drop(binding);
-    GLOBAL_SEARCH_READER
-        .read()
-        .unwrap()
-        .as_ref()
-        .map_err(Clone::clone)?
-        .reload()?;
+    reader.reload()?;
 
     // If `.commit()` returns correctly, then all of the
     // documents that have been added are guaranteed to be
@@ -452,8 +617,6 @@ pub enum DeleteFromIndexError {
     #[error(transparent)]
     GetGlobalSearchIndex(#[from] GetGlobalSearchIndexError),
     #[error(transparent)]
-    GetGlobalSearchReader(#[from] GetGlobalSearchReaderError),
-    #[error(transparent)]
     Tantivy(#[from] tantivy::error::TantivyError),
 }
 
@@ -465,17 +628,17 @@ pub enum DeleteFromIndexError {
 ///
 /// * If failed to delete from the global search index
 pub fn delete_from_global_search_index(
+    profile: &str,
     data: &[(&str, DataValue)],
 ) -> Result<(), DeleteFromIndexError> {
-    log::debug!("Deleting from global search index...");
+    log::debug!("Deleting from global search index for profile={profile}...");
 
     if data.is_empty() {
         log::debug!("No data to delete.");
         return Ok(());
     }
 
-    let binding = GLOBAL_SEARCH_INDEX.read().unwrap();
-    let index = binding.as_ref().map_err(Clone::clone)?;
+    let GlobalSearchIndex { index, reader } = get_global_search_index(profile)?;
     let schema = index.schema();
     // To remove a document we will need an index writer.
     // There must be only one writer at a time.
@@ -517,13 +680,7 @@ pub fn delete_from_global_search_index(
     //
     // This call is blocking.
     index_writer.commit()?;
-    drop(binding);
-    GLOBAL_SEARCH_READER
-        .read()
-        .unwrap()
-        .as_ref()
-        .map_err(Clone::clone)?
-        .reload()?;
+    reader.reload()?;
 
     // If `.commit()` returns correctly, then all of the
     // documents that have been removed are guaranteed to be
@@ -552,10 +709,12 @@ pub enum ReindexError {
 ///
 /// * If failed to reindex the global search index
 pub async fn reindex_global_search_index(
+    profile: &str,
     data: &[Vec<(&str, DataValue)>],
 ) -> Result<(), ReindexError> {
     let permit = SEMAPHORE.acquire().await;
     moosicbox_task::spawn_blocking("reindex_global_search_index", {
+        let profile = profile.to_string();
         let data = data
             .iter()
             .map(|x| {
@@ -565,9 +724,9 @@ pub async fn reindex_global_search_index(
             })
             .collect::<Vec<_>>();
         move || {
-            let path: &Path = GLOBAL_SEARCH_INDEX_PATH.as_ref();
-            recreate_global_search_index_sync(path)?;
+            recreate_global_search_index_sync(&profile)?;
             populate_global_search_index_sync(
+                &profile,
                 &data
                     .iter()
                     .map(|x| {
@@ -588,10 +747,12 @@ pub async fn reindex_global_search_index(
 }
 
 #[cfg(test)]
-fn reindex_global_search_index_sync(data: &[Vec<(&str, DataValue)>]) -> Result<(), ReindexError> {
-    let path: &Path = GLOBAL_SEARCH_INDEX_PATH.as_ref();
-    recreate_global_search_index_sync(path)?;
-    populate_global_search_index_sync(data, false)?;
+fn reindex_global_search_index_sync(
+    profile: &str,
+    data: &[Vec<(&str, DataValue)>],
+) -> Result<(), ReindexError> {
+    recreate_global_search_index_sync(profile)?;
+    populate_global_search_index_sync(profile, data, false)?;
 
     Ok(())
 }
@@ -601,15 +762,13 @@ pub enum SearchIndexError {
     #[error(transparent)]
     GetGlobalSearchIndex(#[from] GetGlobalSearchIndexError),
     #[error(transparent)]
-    GetGlobalSearchReader(#[from] GetGlobalSearchReaderError),
-    #[error(transparent)]
     Tantivy(#[from] tantivy::error::TantivyError),
     #[error(transparent)]
     QueryParser(#[from] tantivy::query::QueryParserError),
 }
 
 static NON_ALPHA_NUMERIC_REGEX: LazyLock<regex::Regex> =
-    LazyLock::new(|| regex::Regex::new(r"[^A-Za-z0-9 ]").expect("Invalid Regex"));
+    LazyLock::new(|| regex::Regex::new(r"[^\p{L}\p{N} ]").expect("Invalid Regex"));
 
 fn sanitize_query(query: &str) -> String {
     NON_ALPHA_NUMERIC_REGEX
@@ -645,15 +804,11 @@ fn construct_query_for_fields(
         parts.push(boost_query);
     }
 
+    let words = folded_terms(search);
+
     // prefix match
     {
-        let mut query_parser =
-            QueryParser::for_index(index, fields.iter().map(|x| x.0).collect::<Vec<_>>());
-        for (field, boost) in fields {
-            query_parser.set_field_fuzzy(*field, true, 1, true);
-            query_parser.set_field_boost(*field, *boost);
-        }
-        let prefix_query = query_parser.parse_query(search).unwrap();
+        let prefix_query = construct_fuzzy_query(&words, fields, true);
         let boost_query = Box::new(BoostQuery::new(Box::new(prefix_query), 2.0));
 
         parts.push(boost_query);
@@ -661,13 +816,7 @@ fn construct_query_for_fields(
 
     // fuzzy match
     {
-        let mut query_parser =
-            QueryParser::for_index(index, fields.iter().map(|x| x.0).collect::<Vec<_>>());
-        for (field, boost) in fields {
-            query_parser.set_field_fuzzy(*field, false, 1, true);
-            query_parser.set_field_boost(*field, *boost);
-        }
-        let fuzzy_query = query_parser.parse_query(search).unwrap();
+        let fuzzy_query = construct_fuzzy_query(&words, fields, false);
         let boost_query = Box::new(BoostQuery::new(Box::new(fuzzy_query), 1.0));
 
         parts.push(boost_query);
@@ -676,6 +825,44 @@ fn construct_query_for_fields(
     DisjunctionMaxQuery::new(parts)
 }
 
+/// The maximum number of typos allowed when fuzzy matching `word`. Longer
+/// words tolerate more typos, up to 2 edits.
+fn fuzzy_distance(word: &str) -> u8 {
+    if word.chars().count() > 5 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Matches documents where any of `words` fuzzy matches any of the `fields`
+fn construct_fuzzy_query(words: &[String], fields: &[(Field, f32)], prefix: bool) -> BooleanQuery {
+    let queries = words
+        .iter()
+        .map(|word| {
+            let distance = fuzzy_distance(word);
+            let field_queries = fields
+                .iter()
+                .map(|(field, boost)| {
+                    let term = Term::from_field_text(*field, word);
+                    let fuzzy_query = if prefix {
+                        FuzzyTermQuery::new_prefix(term, distance, true)
+                    } else {
+                        FuzzyTermQuery::new(term, distance, true)
+                    };
+                    let query: Box<dyn Query> =
+                        Box::new(BoostQuery::new(Box::new(fuzzy_query), *boost));
+                    query
+                })
+                .collect::<Vec<_>>();
+            let query: Box<dyn Query> = Box::new(DisjunctionMaxQuery::new(field_queries));
+            (Occur::Should, query)
+        })
+        .collect::<Vec<_>>();
+
+    BooleanQuery::from(queries)
+}
+
 #[allow(clippy::too_many_lines)]
 fn construct_global_search_query(
     search: &str,
@@ -855,6 +1042,97 @@ fn construct_global_search_query(
     DisjunctionMaxQuery::new(queries)
 }
 
+/// Matches documents whose `field` contains every word in `value`
+fn construct_words_query(field: Field, value: &str) -> Option<Box<dyn Query>> {
+    let queries = folded_terms(value)
+        .into_iter()
+        .map(|word| {
+            let term = Term::from_field_text(field, &word);
+            let query: Box<dyn Query> = Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+            (Occur::Must, query)
+        })
+        .collect::<Vec<_>>();
+
+    if queries.is_empty() {
+        return None;
+    }
+
+    Some(Box::new(BooleanQuery::from(queries)))
+}
+
+fn construct_range_query(field: &str, range: NumberRange) -> Box<dyn Query> {
+    Box::new(RangeQuery::new_u64_bounds(
+        field.to_string(),
+        range.start,
+        range.end,
+    ))
+}
+
+fn construct_filter_query(filter: &SearchFilter, schema: &Schema) -> Option<Box<dyn Query>> {
+    let string_query = |field: &str, value: &str| -> Box<dyn Query> {
+        let term = Term::from_field_text(schema.get_field(field).unwrap(), value);
+        Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+    };
+
+    Some(match filter {
+        SearchFilter::Artist(value) => {
+            construct_words_query(schema.get_field("artist_title_search").unwrap(), value)?
+        }
+        SearchFilter::Album(value) => {
+            construct_words_query(schema.get_field("album_title_search").unwrap(), value)?
+        }
+        SearchFilter::Track(value) => {
+            construct_words_query(schema.get_field("track_title_search").unwrap(), value)?
+        }
+        SearchFilter::DocumentType(value) => string_query("document_type_string", value),
+        SearchFilter::Format(value) => string_query("version_formats_string", value),
+        SearchFilter::Source(value) => string_query("version_sources_string", value),
+        SearchFilter::Year(range) => construct_range_query("release_year", *range),
+        SearchFilter::BitDepth(range) => construct_range_query("version_bit_depths", *range),
+        SearchFilter::SampleRate(range) => construct_range_query("version_sample_rates", *range),
+    })
+}
+
+/// Constructs the query for a parsed search. Returns `None` if there is
+/// nothing to search for.
+fn construct_search_query(
+    search: &SearchQuery,
+    index: &Index,
+    schema: &Schema,
+) -> Option<Box<dyn Query>> {
+    let text = sanitize_query(&search.text);
+    let filters = search
+        .filters
+        .iter()
+        .filter_map(|filter| construct_filter_query(filter, schema))
+        .collect::<Vec<_>>();
+
+    if filters.is_empty() {
+        if text.is_empty() {
+            return None;
+        }
+        return Some(Box::new(construct_global_search_query(
+            &text, index, schema,
+        )));
+    }
+
+    let text_query: Box<dyn Query> = if text.is_empty() {
+        Box::new(AllQuery)
+    } else {
+        Box::new(construct_global_search_query(&text, index, schema))
+    };
+
+    let mut queries = vec![(Occur::Must, text_query)];
+
+    // Filters only narrow down the hits, so they don't affect the scoring
+    for filter in filters {
+        let filter: Box<dyn Query> = Box::new(BoostQuery::new(filter, 0.0));
+        queries.push((Occur::Must, filter));
+    }
+
+    Some(Box::new(BooleanQuery::from(queries)))
+}
+
 /// # Panics
 ///
 /// * If any `RwLock`s are poisoned
@@ -863,14 +1141,14 @@ fn construct_global_search_query(
 ///
 /// * If failed to search the global search index
 pub fn search_global_search_index(
+    profile: &str,
     search: &str,
     offset: usize,
     limit: usize,
 ) -> Result<Vec<NamedFieldDocument>, SearchIndexError> {
-    log::debug!("Searching global_search_index...");
-    let query = sanitize_query(search);
-    let binding = GLOBAL_SEARCH_INDEX.read().unwrap();
-    let index = binding.as_ref().map_err(Clone::clone)?;
+    log::debug!("Searching global_search_index for profile={profile}...");
+    let query = SearchQuery::parse(search);
+    let GlobalSearchIndex { index, reader } = get_global_search_index(profile)?;
     let schema = index.schema();
 
     // # Searching
@@ -886,8 +1164,6 @@ pub fn search_global_search_index(
     //
     // In the code below, we rely on the 'ON_COMMIT' policy: the reader
     // will reload the index automatically after each commit.
-    let reader_binding = GLOBAL_SEARCH_READER.read().unwrap();
-    let reader = reader_binding.as_ref().map_err(Clone::clone)?;
 
     // ### Searcher
     //
@@ -918,11 +1194,14 @@ pub fn search_global_search_index(
     // only in the top 10. Keeping track of our top 10 best documents
     // is the role of the `TopDocs` collector.
 
-    let global_search_query = construct_global_search_query(&query, index, &schema);
+    let Some(global_search_query) = construct_search_query(&query, &index, &schema) else {
+        log::debug!("Nothing to search for");
+        return Ok(vec![]);
+    };
 
     // We can now perform our query.
     let top_docs = searcher.search(
-        &global_search_query,
+        global_search_query.as_ref(),
         &TopDocs::with_limit(limit).and_offset(offset),
     )?;
 
@@ -945,22 +1224,247 @@ pub fn search_global_search_index(
         })
         .collect::<Result<Vec<_>, tantivy::error::TantivyError>>()?;
 
-    drop(reader_binding);
-    drop(binding);
-
     log::debug!("Searched global_search_index");
 
     Ok(results)
 }
 
+/// The number of documents matching a search for each format, source and
+/// decade.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFacets {
+    pub formats: BTreeMap<String, usize>,
+    pub sources: BTreeMap<String, usize>,
+    /// Keyed by the first year of the decade
+    pub decades: BTreeMap<u64, usize>,
+}
+
+fn stored_strings(doc: &NamedFieldDocument, field: &str) -> BTreeSet<String> {
+    doc.0
+        .get(field)
+        .into_iter()
+        .flatten()
+        .filter_map(|value| match value {
+            OwnedValue::Str(value) if !value.is_empty() => Some(value.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn facet_collector(field: &str) -> FacetCollector {
+    let mut collector = FacetCollector::for_field(field);
+    collector.add_facet(Facet::root());
+    collector
+}
+
+/// The document counts of the top level values of a facet
+fn top_facet_counts(counts: &FacetCounts) -> impl Iterator<Item = (String, usize)> + '_ {
+    counts.get(Facet::root()).filter_map(|(facet, count)| {
+        let value = facet.to_path().last()?.to_string();
+        Some((value, usize::try_from(count).ok()?))
+    })
+}
+
+/// Counts the documents matching `search` by format, source and decade using
+/// the facet fields, so no stored documents need to be loaded. A document with
+/// several versions (e.g. an album available in multiple formats) is counted
+/// once for each distinct value.
+///
+/// # Panics
+///
+/// * If any `RwLock`s are poisoned
+///
+/// # Errors
+///
+/// * If failed to search the global search index
+pub fn global_search_facets(profile: &str, search: &str) -> Result<SearchFacets, SearchIndexError> {
+    log::debug!("Counting global_search_index facets for profile={profile}...");
+    let query = SearchQuery::parse(search);
+    let GlobalSearchIndex { index, reader } = get_global_search_index(profile)?;
+    let schema = index.schema();
+
+    let Some(global_search_query) = construct_search_query(&query, &index, &schema) else {
+        return Ok(SearchFacets::default());
+    };
+
+    let searcher = reader.searcher();
+    let (formats, sources, decades) = searcher.search(
+        global_search_query.as_ref(),
+        &(
+            facet_collector("version_formats_facet"),
+            facet_collector("version_sources_facet"),
+            facet_collector("release_year_facet"),
+        ),
+    )?;
+
+    let facets = SearchFacets {
+        formats: top_facet_counts(&formats).collect(),
+        sources: top_facet_counts(&sources).collect(),
+        decades: top_facet_counts(&decades)
+            .filter_map(|(decade, count)| Some((decade.parse().ok()?, count)))
+            .collect(),
+    };
+
+    log::debug!("Counted global_search_index facets");
+
+    Ok(facets)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum SearchSuggestionType {
+    Artist,
+    Album,
+    Track,
+}
+
+/// A completion for a partially typed search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchSuggestion {
+    pub suggestion_type: SearchSuggestionType,
+    pub value: String,
+    pub score: f32,
+}
+
+/// The document type, search field, stored field and boost used to suggest
+/// each type of completion
+const SUGGESTION_FIELDS: [(SearchSuggestionType, &str, &str, &str, f32); 3] = [
+    (
+        SearchSuggestionType::Artist,
+        "artists",
+        "artist_title_search",
+        "artist_title",
+        3.0,
+    ),
+    (
+        SearchSuggestionType::Album,
+        "albums",
+        "album_title_search",
+        "album_title",
+        2.0,
+    ),
+    (
+        SearchSuggestionType::Track,
+        "tracks",
+        "track_title_search",
+        "track_title",
+        1.0,
+    ),
+];
+
+/// The most suggestions of each type `suggest_global_search` returns
+pub const MAX_SUGGESTIONS: usize = 50;
+
+/// Suggests artist, album and track titles that complete `search`. The last
+/// word is matched as a prefix since it is likely still being typed, while
+/// the words before it are fuzzy matched.
+/// At most `limit` (capped to `MAX_SUGGESTIONS`) suggestions of each type are
+/// returned.
+///
+/// # Panics
+///
+/// * If any `RwLock`s are poisoned
+///
+/// # Errors
+///
+/// * If failed to search the global search index
+pub fn suggest_global_search(
+    profile: &str,
+    search: &str,
+    limit: usize,
+) -> Result<Vec<SearchSuggestion>, SearchIndexError> {
+    log::debug!("Suggesting global_search_index completions for profile={profile}...");
+    let limit = limit.min(MAX_SUGGESTIONS);
+    let words = folded_terms(&sanitize_query(search));
+
+    let Some((last_word, words)) = words.split_last() else {
+        return Ok(vec![]);
+    };
+
+    if limit == 0 {
+        return Ok(vec![]);
+    }
+
+    let GlobalSearchIndex { index, reader } = get_global_search_index(profile)?;
+    let schema = index.schema();
+    let searcher = reader.searcher();
+    let document_type = schema.get_field("document_type_string").unwrap();
+
+    let mut suggestions: Vec<SearchSuggestion> = vec![];
+
+    for (suggestion_type, type_value, search_field, value_field, boost) in SUGGESTION_FIELDS {
+        let field = schema.get_field(search_field).unwrap();
+
+        let type_term = Term::from_field_text(document_type, type_value);
+        let mut queries: Vec<(Occur, Box<dyn Query>)> = vec![(
+            Occur::Must,
+            Box::new(TermQuery::new(type_term, IndexRecordOption::Basic)),
+        )];
+
+        for word in words {
+            let term = Term::from_field_text(field, word);
+            let distance = fuzzy_distance(word);
+            queries.push((
+                Occur::Must,
+                Box::new(FuzzyTermQuery::new(term, distance, true)),
+            ));
+        }
+
+        let last_term = Term::from_field_text(field, last_word);
+        queries.push((
+            Occur::Must,
+            Box::new(FuzzyTermQuery::new_prefix(last_term.clone(), 0, true)),
+        ));
+        // Rank titles where the last word is already complete first
+        queries.push((
+            Occur::Should,
+            Box::new(TermQuery::new(last_term, IndexRecordOption::Basic)),
+        ));
+
+        let query = BoostQuery::new(Box::new(BooleanQuery::from(queries)), boost);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+
+        for (score, doc_address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            let doc = doc.to_named_doc(&schema);
+
+            let Some(value) = stored_strings(&doc, value_field).into_iter().next() else {
+                continue;
+            };
+
+            if suggestions
+                .iter()
+                .any(|x| x.suggestion_type == suggestion_type && x.value == value)
+            {
+                continue;
+            }
+
+            suggestions.push(SearchSuggestion {
+                suggestion_type,
+                value,
+                score,
+            });
+        }
+    }
+
+    // Shorter titles are closer to what has been typed so far
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.value.len().cmp(&b.value.len()))
+            .then_with(|| a.value.cmp(&b.value))
+    });
+    suggestions.truncate(limit);
+
+    log::debug!("Suggested global_search_index completions");
+
+    Ok(suggestions)
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{
-        cmp::Ordering,
-        collections::BTreeMap,
-        path::PathBuf,
-        sync::{LazyLock, RwLock},
-    };
+    use std::{cmp::Ordering, collections::BTreeMap, sync::LazyLock};
 
     use pretty_assertions::assert_eq;
     use serial_test::serial;
@@ -969,6 +1473,8 @@ mod tests {
 
     use crate::*;
 
+    const PROFILE: &str = "master";
+
     static TEMP_DIRS: LazyLock<RwLock<Vec<PathBuf>>> = LazyLock::new(|| RwLock::new(vec![]));
 
     #[derive(Debug)]
@@ -989,7 +1495,10 @@ mod tests {
         fn drop(&mut self) {
             for path in TEMP_DIRS.read().unwrap().iter() {
                 log::debug!("Cleaning up temp directory {:?}", path.as_path());
-                std::fs::remove_dir_all(path.as_path()).expect("Failed to clean up temp directory");
+                if path.exists() {
+                    std::fs::remove_dir_all(path.as_path())
+                        .expect("Failed to clean up temp directory");
+                }
             }
             log::debug!("Cleaning up temp directory {:?}", TESTS_DIR_PATH.as_path());
             if TESTS_DIR_PATH.exists() {
@@ -999,17 +1508,17 @@ mod tests {
         }
     }
 
-    fn temp_index_path() -> PathBuf {
-        let path = moosicbox_config::get_tests_dir_path();
+    fn recreate_temp_index(profile: &str) {
+        TEMP_DIRS
+            .write()
+            .unwrap()
+            .push(SEARCH_INDICES_PATH.join(profile));
 
-        TEMP_DIRS.write().unwrap().push(path.clone());
-
-        path.join("search_indices").join("global_search_index")
+        recreate_global_search_index_sync(profile).expect("Failed to recreate_global_search_index");
     }
 
     fn before_each() {
-        recreate_global_search_index_sync(&temp_index_path())
-            .expect("Failed to recreate_global_search_index");
+        recreate_temp_index(PROFILE);
     }
 
     #[dynamic(drop)]
@@ -1116,6 +1625,92 @@ mod tests {
         ]
     });
 
+    fn album_data(
+        album_id: u64,
+        title: &str,
+        date_released: &str,
+        format: &str,
+        bit_depth: u64,
+        source: &str,
+    ) -> Vec<(&'static str, DataValue)> {
+        vec![
+            ("document_type", DataValue::String("albums".into())),
+            ("artist_title", DataValue::String("Elder".into())),
+            ("artist_id", DataValue::Number(51)),
+            ("album_title", DataValue::String(title.into())),
+            ("album_id", DataValue::Number(album_id)),
+            ("date_released", DataValue::String(date_released.into())),
+            (
+                "release_year",
+                DataValue::Number(date_released[..4].parse().unwrap()),
+            ),
+            ("version_formats", DataValue::String(format.into())),
+            ("version_bit_depths", DataValue::Number(bit_depth)),
+            ("version_sources", DataValue::String(source.into())),
+        ]
+    }
+
+    static QUALITY_TEST_DATA: LazyLock<Vec<Vec<(&'static str, DataValue)>>> = LazyLock::new(|| {
+        vec![
+            vec![
+                ("document_type", DataValue::String("artists".into())),
+                ("artist_title", DataValue::String("Elder".into())),
+                ("artist_id", DataValue::Number(51)),
+                ("version_formats", DataValue::String(String::new())),
+                ("version_sources", DataValue::String(String::new())),
+            ],
+            album_data(163, "Omens", "2017-06-02", "FLAC", 24, "LOCAL"),
+            album_data(164, "Lore", "2015-02-27", "FLAC", 16, "TIDAL"),
+            album_data(165, "Elder", "2008-03-11", "MP3", 0, "LOCAL"),
+        ]
+    });
+
+    static BJORK_TEST_DATA: LazyLock<Vec<Vec<(&'static str, DataValue)>>> = LazyLock::new(|| {
+        vec![
+            vec![
+                ("document_type", DataValue::String("artists".into())),
+                ("artist_title", DataValue::String("Björk".into())),
+                ("artist_id", DataValue::Number(7)),
+            ],
+            vec![
+                ("document_type", DataValue::String("albums".into())),
+                ("artist_title", DataValue::String("Björk".into())),
+                ("artist_id", DataValue::Number(7)),
+                ("album_title", DataValue::String("Homogenic".into())),
+                ("album_id", DataValue::Number(70)),
+            ],
+            vec![
+                ("document_type", DataValue::String("tracks".into())),
+                ("artist_title", DataValue::String("Björk".into())),
+                ("artist_id", DataValue::Number(7)),
+                ("album_title", DataValue::String("Homogenic".into())),
+                ("album_id", DataValue::Number(70)),
+                ("track_title", DataValue::String("Hunter".into())),
+                ("track_id", DataValue::Number(700)),
+            ],
+            vec![
+                ("document_type", DataValue::String("tracks".into())),
+                ("artist_title", DataValue::String("Björk".into())),
+                ("artist_id", DataValue::Number(7)),
+                ("album_title", DataValue::String("Homogenic".into())),
+                ("album_id", DataValue::Number(70)),
+                ("track_title", DataValue::String("Jóga".into())),
+                ("track_id", DataValue::Number(701)),
+            ],
+        ]
+    });
+
+    fn album_titles(results: &[NamedFieldDocument]) -> Vec<String> {
+        results
+            .iter()
+            .filter_map(|r| match r.0.get("album_title")?.first()? {
+                OwnedValue::Str(title) => Some(title.clone()),
+                _ => None,
+            })
+            .sorted()
+            .collect()
+    }
+
     fn to_btree(data: Vec<(&'static str, DataValue)>) -> BTreeMap<String, Vec<OwnedValue>> {
         let mut map = BTreeMap::new();
         for field in data {
@@ -1180,8 +1775,8 @@ mod tests {
     fn test_global_search() {
         before_each();
 
-        crate::populate_global_search_index_sync(&TEST_DATA, true).unwrap();
-        let results = crate::search_global_search_index("in procession", 0, 10).unwrap();
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
+        let results = crate::search_global_search_index(PROFILE, "in procession", 0, 10).unwrap();
 
         assert_eq!(results.len(), 4);
         assert_eq!(
@@ -1200,8 +1795,8 @@ mod tests {
     fn test_global_search_with_offset() {
         before_each();
 
-        crate::populate_global_search_index_sync(&TEST_DATA, true).unwrap();
-        let results = crate::search_global_search_index("in procession", 1, 10).unwrap();
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
+        let results = crate::search_global_search_index(PROFILE, "in procession", 1, 10).unwrap();
 
         assert_eq!(results.len(), 3);
         assert_eq!(
@@ -1219,8 +1814,8 @@ mod tests {
     fn test_global_search_with_limit() {
         before_each();
 
-        crate::populate_global_search_index_sync(&TEST_DATA, true).unwrap();
-        let results = crate::search_global_search_index("in procession", 0, 2).unwrap();
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
+        let results = crate::search_global_search_index(PROFILE, "in procession", 0, 2).unwrap();
 
         assert_eq!(results.len(), 2);
         assert_eq!(
@@ -1234,8 +1829,8 @@ mod tests {
     fn test_global_search_with_limit_and_offset() {
         before_each();
 
-        crate::populate_global_search_index_sync(&TEST_DATA, true).unwrap();
-        let results = crate::search_global_search_index("in procession", 1, 1).unwrap();
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
+        let results = crate::search_global_search_index(PROFILE, "in procession", 1, 1).unwrap();
 
         assert_eq!(results.len(), 1);
         assert_eq!(
@@ -1249,38 +1844,242 @@ mod tests {
     fn test_global_search_reindex() {
         before_each();
 
-        crate::populate_global_search_index_sync(&TEST_DATA, true).unwrap();
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
         assert_eq!(
-            crate::search_global_search_index("in procession", 0, 10)
+            crate::search_global_search_index(PROFILE, "in procession", 0, 10)
                 .unwrap()
                 .len(),
             4
         );
 
-        crate::recreate_global_search_index_sync(&temp_index_path())
+        crate::recreate_global_search_index_sync(PROFILE)
             .expect("Failed to recreate_global_search_index");
         assert_eq!(
-            crate::search_global_search_index("in procession", 0, 10)
+            crate::search_global_search_index(PROFILE, "in procession", 0, 10)
                 .unwrap()
                 .len(),
             0
         );
 
-        crate::populate_global_search_index_sync(&TEST_DATA, true).unwrap();
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
         assert_eq!(
-            crate::search_global_search_index("in procession", 0, 10)
+            crate::search_global_search_index(PROFILE, "in procession", 0, 10)
                 .unwrap()
                 .len(),
             4
         );
 
-        crate::reindex_global_search_index_sync(&TEST_DATA)
+        crate::reindex_global_search_index_sync(PROFILE, &TEST_DATA)
             .expect("Failed to reindex_global_search_index");
         assert_eq!(
-            crate::search_global_search_index("in procession", 0, 10)
+            crate::search_global_search_index(PROFILE, "in procession", 0, 10)
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_global_search_is_separate_per_profile() {
+        before_each();
+        recreate_temp_index("other");
+
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
+        assert_eq!(
+            crate::search_global_search_index(PROFILE, "in procession", 0, 10)
                 .unwrap()
                 .len(),
             4
         );
+        assert_eq!(
+            crate::search_global_search_index("other", "in procession", 0, 10)
+                .unwrap()
+                .len(),
+            0
+        );
+
+        crate::drop_global_search_index("other").expect("Failed to drop_global_search_index");
+        assert!(!SEARCH_INDICES_PATH.join("other").exists());
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_init_global_search_index_needs_reindex_until_recreated() {
+        before_each();
+        crate::drop_global_search_index("new").expect("Failed to drop_global_search_index");
+        TEMP_DIRS
+            .write()
+            .unwrap()
+            .push(SEARCH_INDICES_PATH.join("new"));
+
+        std::fs::create_dir_all(LEGACY_GLOBAL_SEARCH_INDEX_PATH.as_path()).unwrap();
+
+        assert!(crate::init_global_search_index("new").unwrap());
+        assert!(!LEGACY_GLOBAL_SEARCH_INDEX_PATH.exists());
+        assert!(crate::init_global_search_index("new").unwrap());
+
+        recreate_temp_index("new");
+        assert!(!crate::init_global_search_index("new").unwrap());
+
+        crate::drop_global_search_index("new").expect("Failed to drop_global_search_index");
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_global_search_with_filters() {
+        before_each();
+
+        crate::populate_global_search_index_sync(PROFILE, &QUALITY_TEST_DATA, true).unwrap();
+
+        let results =
+            crate::search_global_search_index(PROFILE, "format:flac bitdepth>=24", 0, 10).unwrap();
+        assert_eq!(album_titles(&results), vec!["Omens".to_string()]);
+
+        let results =
+            crate::search_global_search_index(PROFILE, "elder year:2000..2016 type:album", 0, 10)
+                .unwrap();
+        assert_eq!(
+            album_titles(&results),
+            vec!["Elder".to_string(), "Lore".to_string()]
+        );
+
+        let results =
+            crate::search_global_search_index(PROFILE, "artist:elder source:tidal", 0, 10).unwrap();
+        assert_eq!(album_titles(&results), vec!["Lore".to_string()]);
+
+        let results = crate::search_global_search_index(PROFILE, "artist:elder", 0, 10).unwrap();
+        assert_eq!(results.len(), 4);
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_global_search_facets() {
+        before_each();
+
+        crate::populate_global_search_index_sync(PROFILE, &QUALITY_TEST_DATA, true).unwrap();
+
+        assert_eq!(
+            crate::global_search_facets(PROFILE, "artist:elder").unwrap(),
+            SearchFacets {
+                formats: BTreeMap::from([("FLAC".to_string(), 2), ("MP3".to_string(), 1)]),
+                sources: BTreeMap::from([("LOCAL".to_string(), 2), ("TIDAL".to_string(), 1)]),
+                decades: BTreeMap::from([(2000, 1), (2010, 2)]),
+            }
+        );
+
+        assert_eq!(
+            crate::global_search_facets(PROFILE, "format:flac").unwrap(),
+            SearchFacets {
+                formats: BTreeMap::from([("FLAC".to_string(), 2)]),
+                sources: BTreeMap::from([("LOCAL".to_string(), 1), ("TIDAL".to_string(), 1)]),
+                decades: BTreeMap::from([(2010, 2)]),
+            }
+        );
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_global_search_is_accent_insensitive() {
+        before_each();
+
+        crate::populate_global_search_index_sync(PROFILE, &BJORK_TEST_DATA, true).unwrap();
+        let results = crate::search_global_search_index(PROFILE, "bjork", 0, 10).unwrap();
+
+        assert_eq!(results.len(), 4);
+
+        let results = crate::search_global_search_index(PROFILE, "joga", 0, 10).unwrap();
+
+        assert!(results
+            .iter()
+            .any(|r| r.0.get("track_title") == Some(&vec![OwnedValue::Str("Jóga".into())])));
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_global_search_tolerates_typos() {
+        before_each();
+
+        crate::populate_global_search_index_sync(PROFILE, &TEST_DATA, true).unwrap();
+        let results = crate::search_global_search_index(PROFILE, "in procesion", 0, 10).unwrap();
+
+        assert_eq!(
+            results
+                .iter()
+                .take(3)
+                .map(|r| r.0.clone())
+                .collect::<Vec<_>>(),
+            to_btree_vec(vec![
+                OMENS_TRACK_2.clone(),
+                OMENS_TRACK_3.clone(),
+                OMENS_TRACK_4.clone(),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_global_search_suggestions() {
+        before_each();
+
+        crate::populate_global_search_index_sync(PROFILE, &BJORK_TEST_DATA, true).unwrap();
+
+        let suggest = |search: &str| {
+            crate::suggest_global_search(PROFILE, search, 10)
+                .unwrap()
+                .into_iter()
+                .map(|x| (x.suggestion_type, x.value))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            suggest("bj"),
+            vec![(SearchSuggestionType::Artist, "Björk".to_string())]
+        );
+        assert_eq!(
+            suggest("homo"),
+            vec![(SearchSuggestionType::Album, "Homogenic".to_string())]
+        );
+        assert_eq!(
+            suggest("jo"),
+            vec![(SearchSuggestionType::Track, "Jóga".to_string())]
+        );
+        assert_eq!(suggest(""), vec![]);
+        assert_eq!(
+            crate::suggest_global_search(PROFILE, "bj", 0)
+                .unwrap()
+                .len(),
+            0
+        );
+    }
+
+    #[test_log::test]
+    #[serial]
+    fn test_init_global_search_index_needs_reindex_after_schema_change() {
+        before_each();
+        crate::drop_global_search_index("outdated").expect("Failed to drop_global_search_index");
+        TEMP_DIRS
+            .write()
+            .unwrap()
+            .push(SEARCH_INDICES_PATH.join("outdated"));
+
+        let path = global_search_index_path("outdated");
+        std::fs::create_dir_all(&path).unwrap();
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("document_type", TEXT | STORED);
+        Index::create_in_dir(&path, schema_builder.build()).unwrap();
+
+        assert!(crate::init_global_search_index("outdated").unwrap());
+        assert!(GLOBAL_SEARCH_INDICES
+            .read()
+            .unwrap()
+            .get("outdated")
+            .unwrap()
+            .index
+            .schema()
+            .get_field("release_year_facet")
+            .is_ok());
+
+        crate::drop_global_search_index("outdated").expect("Failed to drop_global_search_index");
     }
 }
@@ -0,0 +1,318 @@
+//! The structured query syntax accepted by the global search.
+//!
+//! A query is free text mixed with `field:value` filters, e.g.
+//! `artist:radiohead year:1995..2000 format:flac bitdepth>=24 type:album`.
+//! Numeric fields also accept the `>`, `>=`, `<` and `<=` operators, and a
+//! `start..end` range (either end may be omitted) after `:`. Values may be
+//! quoted to include whitespace (`album:"ok computer"`). Anything that isn't
+//! a recognized filter is searched as free text.
+
+use std::ops::Bound;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberRange {
+    pub start: Bound<u64>,
+    pub end: Bound<u64>,
+}
+
+impl NumberRange {
+    #[must_use]
+    pub const fn exact(value: u64) -> Self {
+        Self {
+            start: Bound::Included(value),
+            end: Bound::Included(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchFilter {
+    Artist(String),
+    Album(String),
+    Track(String),
+    /// The document type as it is indexed, i.e. `artists`, `albums` or
+    /// `tracks`
+    DocumentType(String),
+    /// An `AudioFormat`, e.g. `FLAC`
+    Format(String),
+    /// A `TrackApiSource`, e.g. `LOCAL`
+    Source(String),
+    Year(NumberRange),
+    BitDepth(NumberRange),
+    SampleRate(NumberRange),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// The free text part of the query
+    pub text: String,
+    pub filters: Vec<SearchFilter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl SearchQuery {
+    #[must_use]
+    pub fn parse(query: &str) -> Self {
+        let mut text = vec![];
+        let mut filters = vec![];
+
+        for token in tokenize(query) {
+            match parse_filter(&token) {
+                Some(filter) => filters.push(filter),
+                None => text.push(token),
+            }
+        }
+
+        Self {
+            text: text.join(" "),
+            filters,
+        }
+    }
+}
+
+/// Splits the query on whitespace, keeping quoted values together and
+/// stripping the quotes.
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut quoted = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn split_operator(token: &str) -> Option<(&str, Operator, &str)> {
+    let index = token.find([':', '=', '>', '<'])?;
+    let (key, rest) = token.split_at(index);
+
+    let (operator, value) = if let Some(value) = rest.strip_prefix(">=") {
+        (Operator::GreaterOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix("<=") {
+        (Operator::LessOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Operator::Greater, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Operator::Less, value)
+    } else {
+        (Operator::Equal, &rest[1..])
+    };
+
+    if key.is_empty() || value.is_empty() {
+        return None;
+    }
+
+    Some((key, operator, value))
+}
+
+fn parse_filter(token: &str) -> Option<SearchFilter> {
+    let (key, operator, value) = split_operator(token)?;
+    let key = key.to_lowercase().replace(['_', '-'], "");
+
+    Some(match (key.as_str(), operator) {
+        ("artist", Operator::Equal) => SearchFilter::Artist(value.to_string()),
+        ("album", Operator::Equal) => SearchFilter::Album(value.to_string()),
+        ("track" | "title", Operator::Equal) => SearchFilter::Track(value.to_string()),
+        ("type", Operator::Equal) => SearchFilter::DocumentType(parse_document_type(value)?),
+        ("format", Operator::Equal) => SearchFilter::Format(value.to_uppercase()),
+        ("source", Operator::Equal) => SearchFilter::Source(value.to_uppercase()),
+        ("year", _) => SearchFilter::Year(parse_range(operator, value, parse_number)?),
+        ("bitdepth", _) => SearchFilter::BitDepth(parse_range(operator, value, parse_number)?),
+        ("samplerate", _) => {
+            SearchFilter::SampleRate(parse_range(operator, value, parse_sample_rate)?)
+        }
+        _ => return None,
+    })
+}
+
+fn parse_document_type(value: &str) -> Option<String> {
+    Some(
+        match value.to_lowercase().as_str() {
+            "artist" | "artists" => "artists",
+            "album" | "albums" => "albums",
+            "track" | "tracks" => "tracks",
+            _ => return None,
+        }
+        .to_string(),
+    )
+}
+
+fn parse_range(
+    operator: Operator,
+    value: &str,
+    parse: impl Fn(&str) -> Option<u64>,
+) -> Option<NumberRange> {
+    Some(match operator {
+        Operator::Equal => {
+            if let Some((start, end)) = value.split_once("..") {
+                if start.is_empty() && end.is_empty() {
+                    return None;
+                }
+                NumberRange {
+                    start: if start.is_empty() {
+                        Bound::Unbounded
+                    } else {
+                        Bound::Included(parse(start)?)
+                    },
+                    end: if end.is_empty() {
+                        Bound::Unbounded
+                    } else {
+                        Bound::Included(parse(end)?)
+                    },
+                }
+            } else {
+                NumberRange::exact(parse(value)?)
+            }
+        }
+        Operator::Greater => NumberRange {
+            start: Bound::Excluded(parse(value)?),
+            end: Bound::Unbounded,
+        },
+        Operator::GreaterOrEqual => NumberRange {
+            start: Bound::Included(parse(value)?),
+            end: Bound::Unbounded,
+        },
+        Operator::Less => NumberRange {
+            start: Bound::Unbounded,
+            end: Bound::Excluded(parse(value)?),
+        },
+        Operator::LessOrEqual => NumberRange {
+            start: Bound::Unbounded,
+            end: Bound::Included(parse(value)?),
+        },
+    })
+}
+
+fn parse_number(value: &str) -> Option<u64> {
+    value.parse::<u64>().ok()
+}
+
+/// Parses a sample rate in Hz, also accepting kHz values such as `96k` or
+/// `44.1khz`
+fn parse_sample_rate(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let khz = value
+        .strip_suffix("khz")
+        .or_else(|| value.strip_suffix('k'));
+
+    match khz {
+        Some(khz) => {
+            let khz = khz
+                .parse::<f64>()
+                .ok()
+                .filter(|x| x.is_finite() && *x >= 0.0)?;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let hz = (khz * 1000.0).round() as u64;
+            Some(hz)
+        }
+        None => parse_number(&value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn can_parse_free_text_only() {
+        assert_eq!(
+            SearchQuery::parse("in procession"),
+            SearchQuery {
+                text: "in procession".into(),
+                filters: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_filters_mixed_with_free_text() {
+        assert_eq!(
+            SearchQuery::parse(
+                "artist:radiohead year:1995..2000 format:flac bitdepth>=24 type:album creep"
+            ),
+            SearchQuery {
+                text: "creep".into(),
+                filters: vec![
+                    SearchFilter::Artist("radiohead".into()),
+                    SearchFilter::Year(NumberRange {
+                        start: Bound::Included(1995),
+                        end: Bound::Included(2000),
+                    }),
+                    SearchFilter::Format("FLAC".into()),
+                    SearchFilter::BitDepth(NumberRange {
+                        start: Bound::Included(24),
+                        end: Bound::Unbounded,
+                    }),
+                    SearchFilter::DocumentType("albums".into()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_quoted_values() {
+        assert_eq!(
+            SearchQuery::parse("album:\"ok computer\" source:local"),
+            SearchQuery {
+                text: String::new(),
+                filters: vec![
+                    SearchFilter::Album("ok computer".into()),
+                    SearchFilter::Source("LOCAL".into()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_sample_rates() {
+        assert_eq!(
+            SearchQuery::parse("samplerate>96k sample_rate<=44.1khz samplerate:48000").filters,
+            vec![
+                SearchFilter::SampleRate(NumberRange {
+                    start: Bound::Excluded(96_000),
+                    end: Bound::Unbounded,
+                }),
+                SearchFilter::SampleRate(NumberRange {
+                    start: Bound::Unbounded,
+                    end: Bound::Included(44_100),
+                }),
+                SearchFilter::SampleRate(NumberRange::exact(48_000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn treats_unknown_and_invalid_filters_as_free_text() {
+        assert_eq!(
+            SearchQuery::parse("ac:dc year:soon type:playlist artist>=elder"),
+            SearchQuery {
+                text: "ac:dc year:soon type:playlist artist>=elder".into(),
+                filters: vec![],
+            }
+        );
+    }
+}
@@ -39,6 +39,15 @@ pub async fn init(config_db: &ConfigDatabase) {
                         log::error!("Failed to get database for profile '{profile}'");
                     }
                 }
+
+                // Audio zone presets follow the players in and out of the zone
+                #[cfg(feature = "player")]
+                {
+                    if let Err(err) = moosicbox_player::dsp::apply_dsp_presets(&config_db).await {
+                        log::error!("Failed to apply DSP presets: {err:?}");
+                    }
+                }
+
                 Ok(())
             }
         }
@@ -44,7 +44,8 @@ async fn add_profile(
 
     #[allow(clippy::redundant_clone)]
     #[cfg(feature = "library")]
-    let library_music_api = moosicbox_library::LibraryMusicApi::new(library_database.clone());
+    let library_music_api =
+        moosicbox_library::LibraryMusicApi::new(profile, library_database.clone());
 
     #[allow(unused_mut)]
     let mut apis_map: HashMap<ApiSource, Arc<Box<dyn MusicApi>>> = HashMap::new();
@@ -81,6 +82,35 @@ async fn add_profile(
     );
     moosicbox_music_api::profiles::PROFILES.add(profile.to_string(), Arc::new(apis_map));
 
+    #[cfg(feature = "search")]
+    match moosicbox_search::init_global_search_index(profile) {
+        #[cfg(feature = "library")]
+        Ok(true) => {
+            log::info!("Reindexing new global search index for profile={profile}");
+            let profile = profile.to_string();
+            let library_database = library_database.clone();
+            moosicbox_task::spawn("server: reindex_global_search_index", async move {
+                if let Err(e) =
+                    moosicbox_library::reindex_global_search_index(&profile, &library_database)
+                        .await
+                {
+                    log::error!(
+                        "Failed to reindex global search index for profile={profile}: {e:?}"
+                    );
+                }
+            });
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Failed to initialize search index for profile={profile}: {e:?}");
+        }
+    }
+
+    #[cfg(feature = "scan-watcher")]
+    if let Some(config) = moosicbox_scan::watcher::WatcherConfig::from_env() {
+        moosicbox_scan::watcher::start(profile, &library_database, config);
+    }
+
     #[cfg(feature = "library")]
     moosicbox_library::profiles::PROFILES.add(profile.to_string(), library_database);
 
@@ -94,11 +124,19 @@ async fn remove_profile(
 ) -> Result<(), std::io::Error> {
     log::debug!("remove_profile: app_type={app_type} profile={profile}");
 
+    #[cfg(feature = "scan-watcher")]
+    moosicbox_scan::watcher::stop(profile);
+
     moosicbox_database::profiles::PROFILES.remove(profile);
     moosicbox_music_api::profiles::PROFILES.remove(profile);
     #[cfg(feature = "library")]
     moosicbox_library::profiles::PROFILES.remove(profile);
 
+    #[cfg(feature = "search")]
+    if let Err(e) = moosicbox_search::drop_global_search_index(profile) {
+        log::error!("Failed to drop search index for profile={profile}: {e:?}");
+    }
+
     #[cfg(all(not(feature = "postgres"), feature = "sqlite"))]
     if let Some(path) = moosicbox_config::get_profile_dir_path(app_type, profile) {
         tokio::fs::remove_dir_all(path).await?;
@@ -37,4 +37,39 @@ pub async fn init() {
         }
     })
     .await;
+
+    moosicbox_session::events::on_session_queue_updated_event({
+        move |updated| {
+            let updated = updated.clone();
+            async move {
+                log::debug!(
+                    "on_session_queue_updated_event: Session queue updated session_id={}",
+                    updated.session_id
+                );
+                let handle = WS_SERVER_HANDLE
+                    .read()
+                    .await
+                    .clone()
+                    .ok_or_else(|| {
+                        moosicbox_ws::WebsocketSendError::Unknown("No ws server handle".into())
+                    })
+                    .map_err(|e| Box::new(e) as BoxErrorSend)?;
+                moosicbox_ws::send_session_queue_updated(&handle, &updated)
+                    .await
+                    .map_err(|e| Box::new(e) as BoxErrorSend)?;
+
+                crate::players::local::handle_session_queue_update(&updated)
+                    .await
+                    .map_err(|e| Box::new(e) as BoxErrorSend)?;
+
+                #[cfg(feature = "upnp")]
+                crate::players::upnp::handle_session_queue_update(&updated)
+                    .await
+                    .map_err(|e| Box::new(e) as BoxErrorSend)?;
+
+                Ok(())
+            }
+        }
+    })
+    .await;
 }
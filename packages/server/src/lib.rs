@@ -149,6 +149,11 @@ pub async fn run(
         moosicbox_player::on_playback_event(crate::events::playback_event::on_event);
     }
 
+    #[cfg(feature = "scrobbler")]
+    if let Some(config) = moosicbox_scrobbler::listenbrainz::ListenBrainzConfig::from_env() {
+        moosicbox_scrobbler::init(config, CANCELLATION_TOKEN.clone());
+    }
+
     #[cfg(feature = "downloader")]
     events::download_event::init().await;
 
@@ -360,6 +365,11 @@ pub async fn run(
                 "/menu",
             )));
 
+            #[cfg(feature = "music-api-api")]
+            let app = app.service(moosicbox_music_api::api::bind_services(
+                actix_web::web::scope("/music-api"),
+            ));
+
             #[cfg(feature = "player-api")]
             let app = app.service(moosicbox_player::api::bind_services(actix_web::web::scope(
                 "/player",
@@ -535,6 +545,10 @@ pub async fn run(
                             None,
                             None,
                             None,
+                            None,
+                            None,
+                            None,
+                            None,
                             true,
                             None,
                         )
@@ -571,6 +585,10 @@ pub async fn run(
                             None,
                             None,
                             None,
+                            None,
+                            None,
+                            None,
+                            None,
                             true,
                             None,
                         )
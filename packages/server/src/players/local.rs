@@ -58,6 +58,10 @@ pub async fn init(
         }
     }
 
+    if let Err(err) = moosicbox_player::dsp::apply_dsp_presets(config_db).await {
+        log::error!("Failed to apply DSP presets: {err:?}");
+    }
+
     Ok(())
 }
 
@@ -177,6 +181,10 @@ fn handle_server_playback_update(
                     .playlist
                     .map(|x| x.tracks.into_iter().map(Into::into).collect()),
                 None,
+                update.shuffle,
+                update.repeat_mode,
+                update.crossfade,
+                update.replay_gain_mode,
                 Some(update.session_id),
                 Some(update.profile),
                 Some(update.playback_target),
@@ -197,6 +205,39 @@ fn handle_server_playback_update(
     })
 }
 
+/// Applies a session queue edit to the server player playing the session, if
+/// there is one.
+///
+/// # Errors
+///
+/// * If the player fails to update its playback
+pub async fn handle_session_queue_update(
+    updated: &moosicbox_session::models::SessionQueueUpdated,
+) -> Result<(), moosicbox_player::PlayerError> {
+    let existing = {
+        SERVER_PLAYERS
+            .read()
+            .await
+            .get(&updated.session_id)
+            .cloned()
+    };
+
+    if let Some((_, mut player)) = existing {
+        log::debug!(
+            "handle_session_queue_update: Editing server player queue session_id={}",
+            updated.session_id
+        );
+        player
+            .edit_queue(
+                &updated.delta,
+                Some(moosicbox_player::DEFAULT_PLAYBACK_RETRY_OPTIONS),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
 pub async fn register_server_player(
     config_db: &ConfigDatabase,
     ws: crate::ws::server::WsServerHandle,
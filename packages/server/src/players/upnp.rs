@@ -228,6 +228,10 @@ fn handle_upnp_playback_update(
                         .collect::<Vec<_>>()
                 }),
                 None,
+                update.shuffle,
+                update.repeat_mode,
+                update.crossfade,
+                update.replay_gain_mode,
                 Some(update.session_id),
                 Some(update.profile),
                 Some(update.playback_target),
@@ -249,6 +253,39 @@ fn handle_upnp_playback_update(
 }
 
 #[allow(unused)]
+/// Applies a session queue edit to the UPnP player playing the session, if
+/// there is one.
+///
+/// # Errors
+///
+/// * If the player fails to update its playback
+pub async fn handle_session_queue_update(
+    updated: &moosicbox_session::models::SessionQueueUpdated,
+) -> Result<(), moosicbox_player::PlayerError> {
+    let existing = {
+        SESSION_UPNP_PLAYERS
+            .read()
+            .await
+            .get(&updated.session_id)
+            .cloned()
+    };
+
+    if let Some((_, mut player)) = existing {
+        log::debug!(
+            "handle_session_queue_update: Editing UPnP player queue session_id={}",
+            updated.session_id
+        );
+        player
+            .edit_queue(
+                &updated.delta,
+                Some(moosicbox_player::DEFAULT_PLAYBACK_RETRY_OPTIONS),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
 pub async fn register_upnp_player(
     ws: crate::ws::server::WsServerHandle,
     #[cfg(feature = "tunnel")] tunnel_handle: Option<
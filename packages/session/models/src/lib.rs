@@ -1,11 +1,11 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 
-use std::sync::LazyLock;
+use std::{str::FromStr as _, sync::LazyLock};
 
 use moosicbox_audio_zone_models::{ApiPlayer, Player};
 use moosicbox_database::{AsId, DatabaseValue};
-use moosicbox_json_utils::{database::ToValue as _, ParseError, ToValueType};
+use moosicbox_json_utils::{database::ToValue as _, MissingValue, ParseError, ToValueType};
 use moosicbox_music_models::{api::ApiTrack, PlaybackQuality};
 use serde::{Deserialize, Serialize};
 use strum_macros::{AsRefStr, EnumString};
@@ -86,6 +86,55 @@ impl From<ApiPlaybackTarget> for PlaybackTarget {
     }
 }
 
+#[derive(
+    Debug, Serialize, Deserialize, EnumString, AsRefStr, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    All,
+    One,
+}
+
+impl MissingValue<RepeatMode> for &moosicbox_database::Row {}
+impl ToValueType<RepeatMode> for DatabaseValue {
+    fn to_value_type(self) -> Result<RepeatMode, ParseError> {
+        RepeatMode::from_str(
+            self.as_str()
+                .ok_or_else(|| ParseError::ConvertType("RepeatMode".into()))?,
+        )
+        .map_err(|_| ParseError::ConvertType("RepeatMode".into()))
+    }
+}
+
+/// Which of the stored ReplayGain values are used to normalize the loudness of the played tracks
+#[derive(
+    Debug, Serialize, Deserialize, EnumString, AsRefStr, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+}
+
+impl MissingValue<ReplayGainMode> for &moosicbox_database::Row {}
+impl ToValueType<ReplayGainMode> for DatabaseValue {
+    fn to_value_type(self) -> Result<ReplayGainMode, ParseError> {
+        ReplayGainMode::from_str(
+            self.as_str()
+                .ok_or_else(|| ParseError::ConvertType("ReplayGainMode".into()))?,
+        )
+        .map_err(|_| ParseError::ConvertType("ReplayGainMode".into()))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateSession {
@@ -112,6 +161,14 @@ pub struct UpdateSession {
     pub playlist: Option<UpdateSessionPlaylist>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quality: Option<PlaybackQuality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shuffle: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_mode: Option<RepeatMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crossfade: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay_gain_mode: Option<ReplayGainMode>,
 }
 
 impl UpdateSession {
@@ -125,6 +182,10 @@ impl UpdateSession {
             || self.volume.is_some()
             || self.seek.is_some()
             || self.playlist.is_some()
+            || self.shuffle.is_some()
+            || self.repeat_mode.is_some()
+            || self.crossfade.is_some()
+            || self.replay_gain_mode.is_some()
     }
 }
 
@@ -144,6 +205,10 @@ impl From<ApiUpdateSession> for UpdateSession {
             volume: value.volume,
             playlist: value.playlist.map(Into::into),
             quality: value.quality,
+            shuffle: value.shuffle,
+            repeat_mode: value.repeat_mode,
+            crossfade: value.crossfade,
+            replay_gain_mode: value.replay_gain_mode,
         }
     }
 }
@@ -164,6 +229,10 @@ impl From<UpdateSession> for ApiUpdateSession {
             volume: value.volume,
             playlist: value.playlist.map(Into::into),
             quality: value.quality,
+            shuffle: value.shuffle,
+            repeat_mode: value.repeat_mode,
+            crossfade: value.crossfade,
+            replay_gain_mode: value.replay_gain_mode,
         }
     }
 }
@@ -173,6 +242,10 @@ impl From<UpdateSession> for ApiUpdateSession {
 pub struct UpdateSessionPlaylist {
     pub session_playlist_id: u64,
     pub tracks: Vec<ApiTrack>,
+    /// While shuffled, the index of each of the `tracks` in the original,
+    /// unshuffled order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unshuffled_positions: Option<Vec<u16>>,
 }
 
 impl From<UpdateSessionPlaylist> for ApiUpdateSessionPlaylist {
@@ -180,6 +253,7 @@ impl From<UpdateSessionPlaylist> for ApiUpdateSessionPlaylist {
         Self {
             session_playlist_id: value.session_playlist_id,
             tracks: value.tracks,
+            unshuffled_positions: value.unshuffled_positions,
         }
     }
 }
@@ -189,6 +263,7 @@ impl From<ApiUpdateSessionPlaylist> for UpdateSessionPlaylist {
         Self {
             session_playlist_id: value.session_playlist_id,
             tracks: value.tracks,
+            unshuffled_positions: value.unshuffled_positions,
         }
     }
 }
@@ -254,6 +329,14 @@ pub struct ApiUpdateSession {
     pub playlist: Option<ApiUpdateSessionPlaylist>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quality: Option<PlaybackQuality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shuffle: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_mode: Option<RepeatMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crossfade: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay_gain_mode: Option<ReplayGainMode>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
@@ -261,6 +344,8 @@ pub struct ApiUpdateSession {
 pub struct ApiUpdateSessionPlaylist {
     pub session_playlist_id: u64,
     pub tracks: Vec<ApiTrack>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unshuffled_positions: Option<Vec<u16>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
@@ -279,8 +364,16 @@ pub struct Session {
     pub position: Option<u16>,
     pub seek: Option<f64>,
     pub volume: Option<f64>,
+    pub shuffle: bool,
+    pub repeat_mode: RepeatMode,
+    pub crossfade: Option<f64>,
+    #[serde(default)]
+    pub replay_gain_mode: ReplayGainMode,
     pub playback_target: Option<PlaybackTarget>,
     pub playlist: SessionPlaylist,
+    /// The revision of the queue, bumped by every queue edit
+    #[serde(default)]
+    pub queue_revision: u64,
 }
 
 impl ToValueType<Session> for &moosicbox_database::Row {
@@ -298,6 +391,10 @@ impl ToValueType<Session> for &moosicbox_database::Row {
             #[allow(clippy::cast_precision_loss)]
             seek: self.to_value::<Option<i64>>("seek")?.map(|x| x as f64),
             volume: self.to_value("volume")?,
+            shuffle: self.to_value("shuffle")?,
+            repeat_mode: self.to_value("repeat_mode")?,
+            crossfade: self.to_value("crossfade")?,
+            replay_gain_mode: self.to_value("replay_gain_mode")?,
             playback_target: match playback_target_type {
                 Some(PlaybackTarget::AudioZone { .. }) => Some(PlaybackTarget::AudioZone {
                     audio_zone_id: self.to_value("audio_zone_id")?,
@@ -310,6 +407,9 @@ impl ToValueType<Session> for &moosicbox_database::Row {
                 }
                 None => None,
             },
+            queue_revision: self
+                .to_value::<Option<u64>>("queue_revision")?
+                .unwrap_or_default(),
             ..Default::default()
         })
     }
@@ -333,8 +433,17 @@ pub struct ApiSession {
     pub position: Option<u16>,
     pub seek: Option<f64>,
     pub volume: Option<f64>,
+    pub shuffle: bool,
+    pub repeat_mode: RepeatMode,
+    pub crossfade: Option<f64>,
+    #[serde(default)]
+    pub replay_gain_mode: ReplayGainMode,
     pub playback_target: Option<PlaybackTarget>,
     pub playlist: ApiSessionPlaylist,
+    /// The revision of the queue, so a client can tell whether the `playlist` already includes
+    /// a `SessionQueueUpdated` delta
+    #[serde(default)]
+    pub queue_revision: u64,
 }
 
 impl From<Session> for ApiSession {
@@ -347,8 +456,13 @@ impl From<Session> for ApiSession {
             position: value.position,
             seek: value.seek,
             volume: value.volume,
+            shuffle: value.shuffle,
+            repeat_mode: value.repeat_mode,
+            crossfade: value.crossfade,
+            replay_gain_mode: value.replay_gain_mode,
             playback_target: value.playback_target,
             playlist: value.playlist.into(),
+            queue_revision: value.queue_revision,
         }
     }
 }
@@ -358,6 +472,9 @@ impl From<Session> for ApiSession {
 pub struct SessionPlaylist {
     pub id: u64,
     pub tracks: Vec<ApiTrack>,
+    /// While shuffled, the index of each of the `tracks` in the original,
+    /// unshuffled order.
+    pub unshuffled_positions: Option<Vec<u16>>,
 }
 
 impl ToValueType<SessionPlaylist> for &moosicbox_database::Row {
@@ -385,6 +502,8 @@ impl AsId for SessionPlaylist {
 pub struct ApiSessionPlaylist {
     pub session_playlist_id: u64,
     pub tracks: Vec<ApiTrack>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unshuffled_positions: Option<Vec<u16>>,
 }
 
 impl From<SessionPlaylist> for ApiSessionPlaylist {
@@ -392,10 +511,224 @@ impl From<SessionPlaylist> for ApiSessionPlaylist {
         Self {
             session_playlist_id: value.id,
             tracks: value.tracks,
+            unshuffled_positions: value.unshuffled_positions,
+        }
+    }
+}
+
+/// An edit to the tracks queued in a session, relative to the current
+/// `position`. Ranges are end-exclusive.
+#[derive(Debug, Serialize, Deserialize, Clone, AsRefStr, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum QueueEdit {
+    /// Inserts the tracks right after the current track
+    #[serde(rename_all = "camelCase")]
+    PlayNext { tracks: Vec<ApiTrack> },
+    /// Adds the tracks to the end of the queue
+    #[serde(rename_all = "camelCase")]
+    Append { tracks: Vec<ApiTrack> },
+    /// Moves the track at index `from` so it ends up at index `to`
+    #[serde(rename_all = "camelCase")]
+    Move { from: u16, to: u16 },
+    /// Removes the tracks from index `start` up to `end`
+    #[serde(rename_all = "camelCase")]
+    Remove { start: u16, end: u16 },
+    /// Removes every track after the current track
+    ClearUpcoming,
+}
+
+impl QueueEdit {
+    /// The delta this edit makes to a queue of `len` tracks playing the track
+    /// at `position`, or `None` if the edit is out of the queue's bounds.
+    #[must_use]
+    pub fn to_delta(&self, len: u16, position: u16) -> Option<QueueDelta> {
+        Some(match self {
+            Self::PlayNext { tracks } => QueueDelta::Insert {
+                index: if len == 0 {
+                    0
+                } else {
+                    position.saturating_add(1).min(len)
+                },
+                tracks: tracks.clone(),
+            },
+            Self::Append { tracks } => QueueDelta::Insert {
+                index: len,
+                tracks: tracks.clone(),
+            },
+            Self::Move { from, to } => {
+                if *from >= len || *to >= len {
+                    return None;
+                }
+                QueueDelta::Move {
+                    from: *from,
+                    to: *to,
+                }
+            }
+            Self::Remove { start, end } => {
+                if start >= end || *end > len {
+                    return None;
+                }
+                QueueDelta::Remove {
+                    index: *start,
+                    count: end - start,
+                }
+            }
+            Self::ClearUpcoming => {
+                let index = position.saturating_add(1).min(len);
+                QueueDelta::Remove {
+                    index,
+                    count: len - index,
+                }
+            }
+        })
+    }
+}
+
+/// A change made to the tracks queued in a session, sent to clients instead
+/// of the whole playlist.
+#[derive(Debug, Serialize, Deserialize, Clone, AsRefStr, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum QueueDelta {
+    #[serde(rename_all = "camelCase")]
+    Insert { index: u16, tracks: Vec<ApiTrack> },
+    #[serde(rename_all = "camelCase")]
+    Remove { index: u16, count: u16 },
+    #[serde(rename_all = "camelCase")]
+    Move { from: u16, to: u16 },
+}
+
+impl QueueDelta {
+    /// Applies the delta to `tracks`, returning where the track at `position`
+    /// ends up. If the current track is removed, the track that takes its
+    /// place becomes the current track.
+    #[must_use]
+    pub fn apply<T: From<ApiTrack>>(&self, tracks: &mut Vec<T>, position: u16) -> u16 {
+        let position = usize::from(position);
+
+        let position = match self {
+            Self::Insert {
+                index,
+                tracks: inserted,
+            } => {
+                let index = usize::from(*index).min(tracks.len());
+                let shift = !tracks.is_empty() && index <= position;
+                tracks.splice(index..index, inserted.iter().cloned().map(Into::into));
+
+                if shift {
+                    position + inserted.len()
+                } else {
+                    position
+                }
+            }
+            Self::Remove { index, count } => {
+                let start = usize::from(*index).min(tracks.len());
+                let end = (start + usize::from(*count)).min(tracks.len());
+                tracks.drain(start..end);
+
+                if position >= end {
+                    position - (end - start)
+                } else if position >= start {
+                    start.min(tracks.len().saturating_sub(1))
+                } else {
+                    position
+                }
+            }
+            Self::Move { from, to } => {
+                let (from, to) = (usize::from(*from), usize::from(*to));
+                if from >= tracks.len() || to >= tracks.len() {
+                    return u16::try_from(position).unwrap_or(u16::MAX);
+                }
+                let track = tracks.remove(from);
+                tracks.insert(to, track);
+
+                if position == from {
+                    to
+                } else if from < position && to >= position {
+                    position - 1
+                } else if from > position && to <= position {
+                    position + 1
+                } else {
+                    position
+                }
+            }
+        };
+
+        u16::try_from(position).unwrap_or(u16::MAX)
+    }
+
+    /// Applies the delta to the `unshuffled_positions` of a shuffled queue, the
+    /// index of each of its tracks in the original, unshuffled order, so they
+    /// describe the tracks `apply` leaves behind. Inserted tracks are placed in
+    /// the unshuffled order right after the track they follow in the shuffled
+    /// order, and moves only change the shuffled order.
+    pub fn apply_unshuffled(&self, unshuffled_positions: &mut Vec<u16>) {
+        match self {
+            Self::Insert { index, tracks } => {
+                let index = usize::from(*index).min(unshuffled_positions.len());
+                let count = u16::try_from(tracks.len()).unwrap_or(u16::MAX);
+                let start = index
+                    .checked_sub(1)
+                    .map_or(0, |x| unshuffled_positions[x].saturating_add(1));
+
+                for position in unshuffled_positions.iter_mut() {
+                    if *position >= start {
+                        *position = position.saturating_add(count);
+                    }
+                }
+                unshuffled_positions
+                    .splice(index..index, (0..count).map(|x| start.saturating_add(x)));
+            }
+            Self::Remove { index, count } => {
+                let start = usize::from(*index).min(unshuffled_positions.len());
+                let end = (start + usize::from(*count)).min(unshuffled_positions.len());
+                let removed = unshuffled_positions.drain(start..end).collect::<Vec<_>>();
+
+                for position in unshuffled_positions.iter_mut() {
+                    let shift = removed.iter().filter(|x| **x < *position).count();
+                    *position = position.saturating_sub(u16::try_from(shift).unwrap_or(u16::MAX));
+                }
+            }
+            Self::Move { from, to } => {
+                let (from, to) = (usize::from(*from), usize::from(*to));
+                if from < unshuffled_positions.len() && to < unshuffled_positions.len() {
+                    let position = unshuffled_positions.remove(from);
+                    unshuffled_positions.insert(to, position);
+                }
+            }
         }
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EditSessionQueue {
+    pub session_id: u64,
+    pub profile: String,
+    pub edit: QueueEdit,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionQueueUpdated {
+    pub session_id: u64,
+    pub profile: String,
+    pub session_playlist_id: u64,
+    /// The position of the current track after the delta is applied
+    pub position: u16,
+    /// Increases by one with every edit to the session's queue, so a client
+    /// that sees a gap knows it missed a delta and should refetch the queue
+    pub revision: u64,
+    pub delta: QueueDelta,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterConnection {
@@ -460,3 +793,209 @@ pub struct RegisterPlayer {
     pub audio_output_id: String,
     pub name: String,
 }
+
+#[cfg(test)]
+mod test {
+    use moosicbox_music_models::{api::ApiTrack, id::Id};
+
+    use crate::{QueueDelta, QueueEdit};
+
+    fn track(id: u64) -> ApiTrack {
+        ApiTrack {
+            track_id: Id::Number(id),
+            ..Default::default()
+        }
+    }
+
+    fn tracks(ids: &[u64]) -> Vec<ApiTrack> {
+        ids.iter().copied().map(track).collect()
+    }
+
+    fn ids(tracks: &[ApiTrack]) -> Vec<u64> {
+        tracks
+            .iter()
+            .map(|x| match x.track_id {
+                Id::Number(id) => id,
+                Id::String(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn apply_insert_before_the_current_track_shifts_the_position() {
+        let mut queue = tracks(&[1, 2, 3]);
+        let delta = QueueDelta::Insert {
+            index: 1,
+            tracks: tracks(&[9]),
+        };
+
+        assert_eq!(delta.apply(&mut queue, 1), 2);
+        assert_eq!(ids(&queue), vec![1, 9, 2, 3]);
+    }
+
+    #[test]
+    fn apply_insert_after_the_current_track_keeps_the_position() {
+        let mut queue = tracks(&[1, 2, 3]);
+        let delta = QueueDelta::Insert {
+            index: 2,
+            tracks: tracks(&[9, 8]),
+        };
+
+        assert_eq!(delta.apply(&mut queue, 1), 1);
+        assert_eq!(ids(&queue), vec![1, 2, 9, 8, 3]);
+    }
+
+    #[test]
+    fn apply_insert_into_an_empty_queue_plays_the_first_track() {
+        let mut queue = vec![];
+        let delta = QueueDelta::Insert {
+            index: 0,
+            tracks: tracks(&[9, 8]),
+        };
+
+        assert_eq!(delta.apply(&mut queue, 0), 0);
+        assert_eq!(ids(&queue), vec![9, 8]);
+    }
+
+    #[test]
+    fn apply_remove_before_the_current_track_shifts_the_position() {
+        let mut queue = tracks(&[1, 2, 3, 4]);
+        let delta = QueueDelta::Remove { index: 0, count: 2 };
+
+        assert_eq!(delta.apply(&mut queue, 3), 1);
+        assert_eq!(ids(&queue), vec![3, 4]);
+    }
+
+    #[test]
+    fn apply_remove_of_the_current_track_plays_the_track_that_takes_its_place() {
+        let mut queue = tracks(&[1, 2, 3, 4]);
+        let delta = QueueDelta::Remove { index: 1, count: 2 };
+
+        assert_eq!(delta.apply(&mut queue, 1), 1);
+        assert_eq!(ids(&queue), vec![1, 4]);
+    }
+
+    #[test]
+    fn apply_remove_of_the_last_tracks_plays_the_new_last_track() {
+        let mut queue = tracks(&[1, 2, 3]);
+        let delta = QueueDelta::Remove { index: 1, count: 2 };
+
+        assert_eq!(delta.apply(&mut queue, 2), 0);
+        assert_eq!(ids(&queue), vec![1]);
+    }
+
+    #[test]
+    fn apply_move_of_the_current_track_follows_it() {
+        let mut queue = tracks(&[1, 2, 3, 4]);
+        let delta = QueueDelta::Move { from: 1, to: 3 };
+
+        assert_eq!(delta.apply(&mut queue, 1), 3);
+        assert_eq!(ids(&queue), vec![1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn apply_move_across_the_current_track_shifts_the_position() {
+        let mut queue = tracks(&[1, 2, 3, 4]);
+        assert_eq!(QueueDelta::Move { from: 0, to: 3 }.apply(&mut queue, 2), 1);
+        assert_eq!(ids(&queue), vec![2, 3, 4, 1]);
+
+        let mut queue = tracks(&[1, 2, 3, 4]);
+        assert_eq!(QueueDelta::Move { from: 3, to: 0 }.apply(&mut queue, 1), 2);
+        assert_eq!(ids(&queue), vec![4, 1, 2, 3]);
+    }
+
+    #[test]
+    fn apply_move_out_of_bounds_leaves_the_queue_unchanged() {
+        let mut queue = tracks(&[1, 2, 3]);
+
+        assert_eq!(QueueDelta::Move { from: 1, to: 3 }.apply(&mut queue, 1), 1);
+        assert_eq!(ids(&queue), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn apply_unshuffled_insert_follows_the_preceding_shuffled_track() {
+        // Shuffled [3, 1, 2] of the unshuffled [1, 2, 3]
+        let mut positions = vec![2, 0, 1];
+        QueueDelta::Insert {
+            index: 1,
+            tracks: tracks(&[9]),
+        }
+        .apply_unshuffled(&mut positions);
+        // Shuffled [3, 9, 1, 2] of the unshuffled [1, 2, 3, 9]
+        assert_eq!(positions, vec![2, 3, 0, 1]);
+
+        let mut positions = vec![2, 0, 1];
+        QueueDelta::Insert {
+            index: 2,
+            tracks: tracks(&[9]),
+        }
+        .apply_unshuffled(&mut positions);
+        // Shuffled [3, 1, 9, 2] of the unshuffled [1, 9, 2, 3]
+        assert_eq!(positions, vec![3, 0, 1, 2]);
+
+        let mut positions = vec![2, 0, 1];
+        QueueDelta::Insert {
+            index: 0,
+            tracks: tracks(&[9, 8]),
+        }
+        .apply_unshuffled(&mut positions);
+        // Shuffled [9, 8, 3, 1, 2] of the unshuffled [9, 8, 1, 2, 3]
+        assert_eq!(positions, vec![0, 1, 4, 2, 3]);
+    }
+
+    #[test]
+    fn apply_unshuffled_remove_closes_the_gaps_in_the_unshuffled_order() {
+        // Shuffled [3, 1, 2] of the unshuffled [1, 2, 3]
+        let mut positions = vec![2, 0, 1];
+        QueueDelta::Remove { index: 1, count: 1 }.apply_unshuffled(&mut positions);
+        // Shuffled [3, 2] of the unshuffled [2, 3]
+        assert_eq!(positions, vec![1, 0]);
+
+        let mut positions = vec![2, 0, 1];
+        QueueDelta::Remove { index: 0, count: 2 }.apply_unshuffled(&mut positions);
+        assert_eq!(positions, vec![0]);
+    }
+
+    #[test]
+    fn apply_unshuffled_move_only_reorders_the_shuffled_tracks() {
+        let mut positions = vec![2, 0, 1];
+        QueueDelta::Move { from: 0, to: 2 }.apply_unshuffled(&mut positions);
+
+        assert_eq!(positions, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn to_delta_is_relative_to_the_current_track() {
+        assert_eq!(
+            QueueEdit::PlayNext {
+                tracks: tracks(&[9])
+            }
+            .to_delta(3, 1),
+            Some(QueueDelta::Insert {
+                index: 2,
+                tracks: tracks(&[9]),
+            })
+        );
+        assert_eq!(
+            QueueEdit::PlayNext {
+                tracks: tracks(&[9])
+            }
+            .to_delta(0, 0),
+            Some(QueueDelta::Insert {
+                index: 0,
+                tracks: tracks(&[9]),
+            })
+        );
+        assert_eq!(
+            QueueEdit::ClearUpcoming.to_delta(5, 1),
+            Some(QueueDelta::Remove { index: 2, count: 3 })
+        );
+    }
+
+    #[test]
+    fn to_delta_rejects_edits_out_of_bounds() {
+        assert_eq!(QueueEdit::Move { from: 0, to: 3 }.to_delta(3, 0), None);
+        assert_eq!(QueueEdit::Remove { start: 2, end: 2 }.to_delta(3, 0), None);
+        assert_eq!(QueueEdit::Remove { start: 1, end: 4 }.to_delta(3, 0), None);
+    }
+}
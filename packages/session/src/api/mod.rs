@@ -1,20 +1,25 @@
 use actix_web::{
     dev::{ServiceFactory, ServiceRequest},
-    error::{ErrorInternalServerError, ErrorNotFound},
+    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound},
     route,
     web::{self, Json},
     Result, Scope,
 };
 use moosicbox_audio_zone::models::{ApiAudioZone, ApiPlayer};
-use moosicbox_database::{config::ConfigDatabase, profiles::LibraryDatabase};
+use moosicbox_database::{
+    config::ConfigDatabase,
+    profiles::{api::ProfileName, LibraryDatabase},
+};
 use moosicbox_music_models::api::ApiTrack;
 use moosicbox_paging::Page;
-use moosicbox_session_models::{ApiConnection, RegisterConnection};
+use moosicbox_session_models::{
+    ApiConnection, EditSessionQueue, QueueDelta, QueueEdit, RegisterConnection, SessionQueueUpdated,
+};
 use serde::Deserialize;
 
 use crate::{
     models::{ApiSession, ApiSessionPlaylist, RegisterPlayer},
-    CreatePlayersError,
+    CreatePlayersError, EditSessionQueueError,
 };
 
 pub mod models;
@@ -31,6 +36,11 @@ pub fn bind_services<
         .service(session_playing_endpoint)
         .service(session_endpoint)
         .service(sessions_endpoint)
+        .service(session_queue_play_next_endpoint)
+        .service(session_queue_append_endpoint)
+        .service(session_queue_move_endpoint)
+        .service(session_queue_remove_endpoint)
+        .service(session_queue_clear_upcoming_endpoint)
         .service(register_players_endpoint)
         .service(register_connection_endpoint)
 }
@@ -46,6 +56,11 @@ pub fn bind_services<
         session_playing_endpoint,
         session_endpoint,
         sessions_endpoint,
+        session_queue_play_next_endpoint,
+        session_queue_append_endpoint,
+        session_queue_move_endpoint,
+        session_queue_remove_endpoint,
+        session_queue_clear_upcoming_endpoint,
         register_players_endpoint,
     ),
     components(schemas(
@@ -53,6 +68,9 @@ pub fn bind_services<
         ApiPlayer,
         ApiSession,
         RegisterPlayer,
+        QueueDelta,
+        QueueEdit,
+        SessionQueueUpdated,
     ))
 )]
 pub struct Api;
@@ -315,6 +333,218 @@ pub async fn sessions_endpoint(
     }))
 }
 
+impl From<EditSessionQueueError> for actix_web::Error {
+    fn from(e: EditSessionQueueError) -> Self {
+        match e {
+            EditSessionQueueError::Db(e) => ErrorInternalServerError(e),
+            EditSessionQueueError::SessionNotFound(_) => ErrorNotFound(e),
+            EditSessionQueueError::InvalidEdit(_) => ErrorBadRequest(e),
+        }
+    }
+}
+
+async fn edit_session_queue(
+    db: &LibraryDatabase,
+    profile: ProfileName,
+    session_id: u64,
+    edit: QueueEdit,
+) -> Result<Json<SessionQueueUpdated>> {
+    let edit = EditSessionQueue {
+        session_id,
+        profile: profile.into(),
+        edit,
+    };
+    log::debug!("edit_session_queue: {edit:?}");
+
+    Ok(Json(crate::edit_session_queue(db, &edit).await?))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionQueueQuery {
+    session_id: u64,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Session"],
+        post,
+        path = "/session-queue/play-next",
+        description = "Insert tracks right after the current track of a session",
+        request_body = Vec<ApiTrack>,
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("sessionId" = u64, Query, description = "Session ID to queue the tracks in"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The change made to the session queue",
+                body = SessionQueueUpdated,
+            )
+        )
+    )
+)]
+#[route("/session-queue/play-next", method = "POST")]
+pub async fn session_queue_play_next_endpoint(
+    tracks: web::Json<Vec<ApiTrack>>,
+    query: web::Query<SessionQueueQuery>,
+    db: LibraryDatabase,
+    profile: ProfileName,
+) -> Result<Json<SessionQueueUpdated>> {
+    let tracks = tracks.into_inner();
+    edit_session_queue(
+        &db,
+        profile,
+        query.session_id,
+        QueueEdit::PlayNext { tracks },
+    )
+    .await
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Session"],
+        post,
+        path = "/session-queue/append",
+        description = "Add tracks to the end of a session's queue",
+        request_body = Vec<ApiTrack>,
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("sessionId" = u64, Query, description = "Session ID to queue the tracks in"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The change made to the session queue",
+                body = SessionQueueUpdated,
+            )
+        )
+    )
+)]
+#[route("/session-queue/append", method = "POST")]
+pub async fn session_queue_append_endpoint(
+    tracks: web::Json<Vec<ApiTrack>>,
+    query: web::Query<SessionQueueQuery>,
+    db: LibraryDatabase,
+    profile: ProfileName,
+) -> Result<Json<SessionQueueUpdated>> {
+    let tracks = tracks.into_inner();
+    edit_session_queue(&db, profile, query.session_id, QueueEdit::Append { tracks }).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionQueueMoveQuery {
+    session_id: u64,
+    from: u16,
+    to: u16,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Session"],
+        post,
+        path = "/session-queue/move",
+        description = "Move a track to another index in a session's queue",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("sessionId" = u64, Query, description = "Session ID to edit the queue of"),
+            ("from" = u16, Query, description = "Index of the track to move"),
+            ("to" = u16, Query, description = "Index to move the track to"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The change made to the session queue",
+                body = SessionQueueUpdated,
+            )
+        )
+    )
+)]
+#[route("/session-queue/move", method = "POST")]
+pub async fn session_queue_move_endpoint(
+    query: web::Query<SessionQueueMoveQuery>,
+    db: LibraryDatabase,
+    profile: ProfileName,
+) -> Result<Json<SessionQueueUpdated>> {
+    let edit = QueueEdit::Move {
+        from: query.from,
+        to: query.to,
+    };
+    edit_session_queue(&db, profile, query.session_id, edit).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionQueueRemoveQuery {
+    session_id: u64,
+    start: u16,
+    end: Option<u16>,
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Session"],
+        post,
+        path = "/session-queue/remove",
+        description = "Remove a range of tracks from a session's queue",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("sessionId" = u64, Query, description = "Session ID to edit the queue of"),
+            ("start" = u16, Query, description = "Index of the first track to remove"),
+            ("end" = Option<u16>, Query, description = "Index after the last track to remove. Defaults to removing only the track at start"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The change made to the session queue",
+                body = SessionQueueUpdated,
+            )
+        )
+    )
+)]
+#[route("/session-queue/remove", method = "POST")]
+pub async fn session_queue_remove_endpoint(
+    query: web::Query<SessionQueueRemoveQuery>,
+    db: LibraryDatabase,
+    profile: ProfileName,
+) -> Result<Json<SessionQueueUpdated>> {
+    let edit = QueueEdit::Remove {
+        start: query.start,
+        end: query.end.unwrap_or_else(|| query.start.saturating_add(1)),
+    };
+    edit_session_queue(&db, profile, query.session_id, edit).await
+}
+
+#[cfg_attr(
+    feature = "openapi", utoipa::path(
+        tags = ["Session"],
+        post,
+        path = "/session-queue/clear-upcoming",
+        description = "Remove every track after the current track of a session",
+        params(
+            ("moosicbox-profile" = String, Header, description = "MoosicBox profile"),
+            ("sessionId" = u64, Query, description = "Session ID to edit the queue of"),
+        ),
+        responses(
+            (
+                status = 200,
+                description = "The change made to the session queue",
+                body = SessionQueueUpdated,
+            )
+        )
+    )
+)]
+#[route("/session-queue/clear-upcoming", method = "POST")]
+pub async fn session_queue_clear_upcoming_endpoint(
+    query: web::Query<SessionQueueQuery>,
+    db: LibraryDatabase,
+    profile: ProfileName,
+) -> Result<Json<SessionQueueUpdated>> {
+    edit_session_queue(&db, profile, query.session_id, QueueEdit::ClearUpcoming).await
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterPlayers {
@@ -2,9 +2,13 @@ use std::sync::Arc;
 
 use moosicbox_audio_zone::{db::models::AudioZoneModel, models::Player};
 use moosicbox_database::{
+    boxed,
     config::ConfigDatabase,
     profiles::LibraryDatabase,
-    query::{select, where_in, FilterableQuery as _, SortDirection},
+    query::{
+        delete, identifier, select, update, upsert_multi, where_in, FilterableQuery as _,
+        SortDirection, TransactionStatement, UpsertMultiStatement,
+    },
     Database, DatabaseValue,
 };
 use moosicbox_json_utils::{
@@ -39,6 +43,26 @@ pub async fn get_session_playlist_tracks(
         .collect::<Result<Vec<_>, _>>()
 }
 
+/// The index of each of the session playlist's tracks in their original,
+/// unshuffled order. `None` if the playlist isn't shuffled.
+pub async fn get_session_playlist_unshuffled_positions(
+    db: &LibraryDatabase,
+    session_playlist_id: u64,
+) -> Result<Option<Vec<u16>>, DatabaseFetchError> {
+    let positions = db
+        .select("session_playlist_tracks")
+        .columns(&["unshuffled_position"])
+        .where_eq("session_playlist_id", session_playlist_id)
+        .sort("id", SortDirection::Asc)
+        .execute(db)
+        .await?
+        .iter()
+        .map(|row| row.to_value("unshuffled_position"))
+        .collect::<Result<Option<Vec<u16>>, _>>()?;
+
+    Ok(positions.filter(|x| !x.is_empty()))
+}
+
 pub async fn get_session_playlist(
     db: &LibraryDatabase,
     session_id: u64,
@@ -173,11 +197,16 @@ pub async fn create_session(
         position: new_session.position,
         seek: new_session.seek,
         volume: new_session.volume,
+        shuffle: new_session.shuffle,
+        repeat_mode: new_session.repeat_mode,
+        crossfade: new_session.crossfade,
+        replay_gain_mode: new_session.replay_gain_mode,
         name: new_session.name,
         playback_target: session
             .audio_zone_id
             .map(|audio_zone_id| PlaybackTarget::AudioZone { audio_zone_id }),
         playlist,
+        queue_revision: new_session.queue_revision,
     })
 }
 
@@ -208,28 +237,15 @@ pub async fn update_session(
         log::trace!("update_session: No playlist");
     }
 
-    let playlist_id = session
-        .playlist
-        .as_ref()
-        .map(|p| i64::try_from(p.session_playlist_id).unwrap());
-
-    if let Some(tracks) = session.playlist.as_ref().map(|p| &p.tracks) {
+    if let Some(playlist) = &session.playlist {
         log::trace!("update_session: Inserting new tracks");
-        for track in tracks {
-            log::trace!("update_session: Inserting track {track:?}");
-            db.insert("session_playlist_tracks")
-                .value("session_playlist_id", playlist_id)
-                .value("track_id", &track.track_id)
-                .value("type", track.api_source.to_string())
-                .value(
-                    "data",
-                    serde_json::to_string(track).map_err(|e| {
-                        DatabaseFetchError::Parse(ParseError::Parse(format!("data: {e:?}")))
-                    })?,
-                )
-                .execute(db)
-                .await?;
-        }
+        insert_session_playlist_tracks(
+            db,
+            playlist.session_playlist_id,
+            &playlist.tracks,
+            playlist.unshuffled_positions.as_deref(),
+        )
+        .await?;
     } else {
         log::trace!("update_session: No tracks to insert");
     }
@@ -274,6 +290,24 @@ pub async fn update_session(
     if let Some(volume) = session.volume {
         values.push(("volume", DatabaseValue::Real(volume)));
     }
+    if let Some(shuffle) = session.shuffle {
+        values.push(("shuffle", DatabaseValue::Bool(shuffle)));
+    }
+    if let Some(repeat_mode) = session.repeat_mode {
+        values.push((
+            "repeat_mode",
+            DatabaseValue::String(repeat_mode.as_ref().to_string()),
+        ));
+    }
+    if let Some(crossfade) = session.crossfade {
+        values.push(("crossfade", DatabaseValue::Real(crossfade)));
+    }
+    if let Some(replay_gain_mode) = session.replay_gain_mode {
+        values.push((
+            "replay_gain_mode",
+            DatabaseValue::String(replay_gain_mode.as_ref().to_string()),
+        ));
+    }
 
     if values.is_empty() {
         log::trace!("update_session: No values to update on the session");
@@ -290,6 +324,122 @@ pub async fn update_session(
     Ok(())
 }
 
+async fn insert_session_playlist_tracks(
+    db: &LibraryDatabase,
+    session_playlist_id: u64,
+    tracks: &[ApiTrack],
+    unshuffled_positions: Option<&[u16]>,
+) -> Result<(), DatabaseFetchError> {
+    if let Some(statement) =
+        insert_session_playlist_tracks_statement(session_playlist_id, tracks, unshuffled_positions)?
+    {
+        log::trace!(
+            "insert_session_playlist_tracks: Inserting {} tracks",
+            tracks.len()
+        );
+        statement.execute(db).await?;
+    }
+
+    Ok(())
+}
+
+/// A single statement inserting all of the `tracks` into the session playlist. `None` if there
+/// are no tracks to insert.
+fn insert_session_playlist_tracks_statement(
+    session_playlist_id: u64,
+    tracks: &[ApiTrack],
+    unshuffled_positions: Option<&[u16]>,
+) -> Result<Option<UpsertMultiStatement<'static>>, DatabaseFetchError> {
+    if tracks.is_empty() {
+        return Ok(None);
+    }
+
+    let unshuffled_positions = unshuffled_positions.filter(|x| x.len() == tracks.len());
+
+    let values = tracks
+        .iter()
+        .enumerate()
+        .map(|(i, track)| {
+            Ok(vec![
+                (
+                    "session_playlist_id",
+                    DatabaseValue::UNumber(session_playlist_id),
+                ),
+                ("track_id", DatabaseValue::from(&track.track_id)),
+                ("type", DatabaseValue::String(track.api_source.to_string())),
+                (
+                    "unshuffled_position",
+                    DatabaseValue::from(unshuffled_positions.map(|x| x[i])),
+                ),
+                (
+                    "data",
+                    DatabaseValue::String(serde_json::to_string(track).map_err(|e| {
+                        DatabaseFetchError::Parse(ParseError::Parse(format!("data: {e:?}")))
+                    })?),
+                ),
+            ])
+        })
+        .collect::<Result<Vec<_>, DatabaseFetchError>>()?;
+
+    // The rows have no id, so they never conflict and are all inserted
+    let mut statement = upsert_multi("session_playlist_tracks");
+    statement.unique(boxed![identifier("id")]).values(values);
+
+    Ok(Some(statement))
+}
+
+/// The revision of the session's queue, increased by every queue edit. `None` if the session
+/// doesn't exist.
+pub async fn get_session_queue_revision(
+    db: &LibraryDatabase,
+    session_id: u64,
+) -> Result<Option<u64>, DatabaseFetchError> {
+    Ok(db
+        .select("sessions")
+        .columns(&["queue_revision"])
+        .where_eq("id", session_id)
+        .execute_first(db)
+        .await?
+        .and_then(|row| row.get("queue_revision"))
+        .map(|x| x.to_value_type() as Result<u64, _>)
+        .transpose()?)
+}
+
+/// Replaces the session playlist's tracks and sets the session's position and queue revision in
+/// a single transaction, so a failure part way through can't leave the session with a partial
+/// queue.
+pub async fn set_session_queue(
+    db: &LibraryDatabase,
+    session_id: u64,
+    session_playlist_id: u64,
+    tracks: &[ApiTrack],
+    unshuffled_positions: Option<&[u16]>,
+    position: u16,
+    revision: u64,
+) -> Result<(), DatabaseFetchError> {
+    let mut statements: Vec<TransactionStatement> = vec![delete("session_playlist_tracks")
+        .where_eq("session_playlist_id", session_playlist_id)
+        .into()];
+
+    if let Some(insert) =
+        insert_session_playlist_tracks_statement(session_playlist_id, tracks, unshuffled_positions)?
+    {
+        statements.push(insert.into());
+    }
+
+    statements.push(
+        update("sessions")
+            .where_eq("id", session_id)
+            .value("position", i64::from(position))
+            .value("queue_revision", revision)
+            .into(),
+    );
+
+    db.exec_transaction(&statements).await?;
+
+    Ok(())
+}
+
 pub async fn delete_session(
     db: &LibraryDatabase,
     session_id: u64,
@@ -526,6 +676,10 @@ async fn session_as_model_query(
             #[allow(clippy::cast_precision_loss)]
             seek: row.to_value::<Option<i64>>("seek")?.map(|x| x as f64),
             volume: row.to_value("volume")?,
+            shuffle: row.to_value("shuffle")?,
+            repeat_mode: row.to_value("repeat_mode")?,
+            crossfade: row.to_value("crossfade")?,
+            replay_gain_mode: row.to_value("replay_gain_mode")?,
             playback_target: match playback_target_type {
                 Some(PlaybackTarget::AudioZone { .. }) => Some(PlaybackTarget::AudioZone {
                     audio_zone_id: row.to_value("audio_zone_id")?,
@@ -539,6 +693,9 @@ async fn session_as_model_query(
                 None => None,
             },
             playlist,
+            queue_revision: row
+                .to_value::<Option<u64>>("queue_revision")?
+                .unwrap_or_default(),
         }),
         None => Err(DatabaseFetchError::InvalidRequest),
     }
@@ -549,8 +706,14 @@ async fn session_playlist_as_model_query(
     db: Arc<Box<dyn Database>>,
 ) -> Result<SessionPlaylist, DatabaseFetchError> {
     let id = row.to_value("id")?;
-    let tracks = get_session_playlist_tracks(&db.clone().into(), id).await?;
+    let db: LibraryDatabase = db.into();
+    let tracks = get_session_playlist_tracks(&db, id).await?;
     log::trace!("Got SessionPlaylistTracks for session_playlist {id}: {tracks:?}");
+    let unshuffled_positions = get_session_playlist_unshuffled_positions(&db, id).await?;
 
-    Ok(SessionPlaylist { id, tracks })
+    Ok(SessionPlaylist {
+        id,
+        tracks,
+        unshuffled_positions,
+    })
 }
@@ -4,6 +4,7 @@ use std::{
     sync::{Arc, LazyLock},
 };
 
+use moosicbox_session_models::SessionQueueUpdated;
 use tokio::sync::RwLock;
 
 pub type BoxErrorSend = Box<dyn std::error::Error + Send>;
@@ -54,3 +55,57 @@ pub async fn send_players_updated_event() -> Result<(), Vec<Box<dyn std::error::
 
     Ok(())
 }
+
+pub type SessionQueueUpdatedSubscriptionAction = Box<
+    dyn (Fn(
+            &SessionQueueUpdated,
+        )
+            -> Pin<Box<dyn Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + Send>>)
+        + Send
+        + Sync,
+>;
+static SESSION_QUEUE_UPDATED_EVENT_LISTENERS: LazyLock<
+    Arc<RwLock<Vec<SessionQueueUpdatedSubscriptionAction>>>,
+> = LazyLock::new(|| Arc::new(RwLock::new(Vec::new())));
+
+pub async fn on_session_queue_updated_event<
+    F: Send + Future<Output = Result<(), Box<dyn std::error::Error + Send>>> + 'static,
+>(
+    listener: impl (Fn(&SessionQueueUpdated) -> F) + Send + Sync + 'static,
+) {
+    SESSION_QUEUE_UPDATED_EVENT_LISTENERS
+        .write()
+        .await
+        .push(Box::new(move |updated| Box::pin(listener(updated))));
+}
+
+/// # Errors
+///
+/// * If any of the event handlers produce errors
+pub async fn trigger_session_queue_updated_event(
+    updated: SessionQueueUpdated,
+) -> Result<(), Vec<Box<dyn std::error::Error + Send>>> {
+    send_session_queue_updated_event(updated).await
+}
+
+/// # Errors
+///
+/// * If any of the event handlers produce errors
+pub async fn send_session_queue_updated_event(
+    updated: SessionQueueUpdated,
+) -> Result<(), Vec<Box<dyn std::error::Error + Send>>> {
+    let mut errors = vec![];
+    let listeners = SESSION_QUEUE_UPDATED_EVENT_LISTENERS.read().await;
+    for listener in listeners.iter() {
+        if let Err(e) = listener(&updated).await {
+            errors.push(e);
+        }
+    }
+    drop(listeners);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(())
+}
@@ -1,6 +1,12 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, LazyLock},
+};
+
 use moosicbox_audio_zone::{
     db::audio_zone_try_from_db,
     models::{AudioZone, Player},
@@ -9,7 +15,8 @@ use moosicbox_database::{config::ConfigDatabase, profiles::LibraryDatabase};
 use moosicbox_json_utils::database::DatabaseFetchError;
 use moosicbox_music_models::api::ApiTrack;
 use moosicbox_session_models::{
-    CreateSession, PlaybackTarget, Session, SessionPlaylist, SetSessionAudioZone, UpdateSession,
+    CreateSession, EditSessionQueue, PlaybackTarget, Session, SessionPlaylist, SessionQueueUpdated,
+    SetSessionAudioZone, UpdateSession,
 };
 
 mod db;
@@ -108,11 +115,141 @@ pub async fn create_session(
 /// # Errors
 ///
 /// * If a database error occurs
+///
+/// # Panics
+///
+/// * If the `SESSION_QUEUE_LOCKS` `Mutex` is poisoned
 pub async fn update_session(
     db: &LibraryDatabase,
     session: &UpdateSession,
 ) -> Result<(), DatabaseFetchError> {
-    crate::db::update_session(db, session).await
+    if session.playlist.is_some() {
+        with_session_queue_lock(session.session_id, crate::db::update_session(db, session)).await
+    } else {
+        crate::db::update_session(db, session).await
+    }
+}
+
+/// Serializes the writes to the queue of each session, so concurrent edits
+/// each apply to the queue the previous write left behind.
+static SESSION_QUEUE_LOCKS: LazyLock<std::sync::Mutex<HashMap<u64, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Runs `f` while holding the session's queue lock, removing the lock once
+/// no other write is waiting on it.
+async fn with_session_queue_lock<T>(session_id: u64, f: impl Future<Output = T>) -> T {
+    let lock = SESSION_QUEUE_LOCKS
+        .lock()
+        .unwrap()
+        .entry(session_id)
+        .or_default()
+        .clone();
+
+    let result = {
+        let _guard = lock.lock().await;
+        f.await
+    };
+
+    let mut locks = SESSION_QUEUE_LOCKS.lock().unwrap();
+    if Arc::strong_count(&lock) == 2
+        && locks
+            .get(&session_id)
+            .is_some_and(|x| Arc::ptr_eq(x, &lock))
+    {
+        locks.remove(&session_id);
+    }
+    drop(locks);
+
+    result
+}
+
+#[derive(Debug, Error)]
+pub enum EditSessionQueueError {
+    #[error(transparent)]
+    Db(#[from] DatabaseFetchError),
+    #[error("Session {0} not found")]
+    SessionNotFound(u64),
+    #[error("Invalid queue edit: {0}")]
+    InvalidEdit(String),
+}
+
+/// Applies a queue edit to a session's playlist, returning the delta it made.
+///
+/// # Errors
+///
+/// * If a database error occurs
+/// * If the session doesn't exist
+/// * If the edit is out of the bounds of the session's playlist
+///
+/// # Panics
+///
+/// * If the `SESSION_QUEUE_LOCKS` `Mutex` is poisoned
+pub async fn edit_session_queue(
+    db: &LibraryDatabase,
+    edit: &EditSessionQueue,
+) -> Result<SessionQueueUpdated, EditSessionQueueError> {
+    with_session_queue_lock(edit.session_id, async {
+        let session = crate::db::get_session(db, edit.session_id)
+            .await?
+            .ok_or(EditSessionQueueError::SessionNotFound(edit.session_id))?;
+        let revision = crate::db::get_session_queue_revision(db, edit.session_id)
+            .await?
+            .unwrap_or_default()
+            + 1;
+
+        let mut tracks = session.playlist.tracks;
+        let position = session.position.unwrap_or_default();
+        let len = u16::try_from(tracks.len())
+            .map_err(|_| EditSessionQueueError::InvalidEdit("Queue is too long".to_string()))?;
+
+        let delta = edit.edit.to_delta(len, position).ok_or_else(|| {
+            EditSessionQueueError::InvalidEdit(format!(
+                "{} is out of bounds of a queue of {len} tracks",
+                edit.edit.as_ref()
+            ))
+        })?;
+        let unshuffled_positions = session
+            .playlist
+            .unshuffled_positions
+            .filter(|x| x.len() == tracks.len())
+            .map(|mut x| {
+                delta.apply_unshuffled(&mut x);
+                x
+            });
+        let position = delta.apply(&mut tracks, position);
+
+        crate::db::set_session_queue(
+            db,
+            edit.session_id,
+            session.playlist.id,
+            &tracks,
+            unshuffled_positions.as_deref(),
+            position,
+            revision,
+        )
+        .await?;
+
+        let updated = SessionQueueUpdated {
+            session_id: edit.session_id,
+            profile: edit.profile.clone(),
+            session_playlist_id: session.playlist.id,
+            position,
+            revision,
+            delta,
+        };
+
+        #[cfg(feature = "events")]
+        {
+            if let Err(e) =
+                crate::events::trigger_session_queue_updated_event(updated.clone()).await
+            {
+                log::error!("Failed to trigger session queue updated event: {e:?}");
+            }
+        }
+
+        Ok(updated)
+    })
+    .await
 }
 
 /// # Errors
@@ -196,6 +196,16 @@ impl From<ApiTidalTrack> for moosicbox_music_models::api::ApiTrack {
             overall_bitrate: None,
             sample_rate: None,
             channels: None,
+            replay_gain: None,
+            play_count: None,
+            disc_number: None,
+            disc_total: None,
+            genres: vec![],
+            composer: None,
+            track_artist: None,
+            isrc: None,
+            start_offset: None,
+            end_offset: None,
             track_source: TrackApiSource::Tidal,
             api_source: ApiSource::Tidal,
             sources: ApiSources::default().with_source(ApiSource::Tidal, value.id.into()),
@@ -14,7 +14,7 @@ use std::{
 };
 
 use itertools::Itertools as _;
-use models::{TidalAlbum, TidalArtist, TidalSearchResults, TidalTrack};
+use models::{TidalAlbum, TidalArtist, TidalLyrics, TidalPlaylist, TidalSearchResults, TidalTrack};
 #[cfg(feature = "db")]
 use moosicbox_database::profiles::LibraryDatabase;
 #[cfg(feature = "db")]
@@ -33,15 +33,17 @@ use moosicbox_music_api::{
         ImageCoverSize, ImageCoverSource, TrackAudioQuality, TrackOrder, TrackOrderDirection,
         TrackSource,
     },
-    AddAlbumError, AddArtistError, AddTrackError, AlbumError, AlbumsError, ArtistAlbumsError,
-    ArtistError, ArtistsError, MusicApi, RemoveAlbumError, RemoveArtistError, RemoveTrackError,
-    TrackError, TrackOrId, TracksError,
+    AddAlbumError, AddArtistError, AddPlaylistTrackError, AddTrackError, AlbumError, AlbumsError,
+    ArtistAlbumsError, ArtistError, ArtistsError, MusicApi, PlaylistError, PlaylistsError,
+    RemoveAlbumError, RemoveArtistError, RemovePlaylistTrackError, RemoveTrackError, SearchError,
+    TrackError, TrackLyricsError, TrackOrId, TracksError,
 };
 use moosicbox_music_models::{
-    id::Id, Album, AlbumSort, AlbumType, ApiSource, Artist, AudioFormat, PlaybackQuality, Track,
-    TrackApiSource,
+    id::Id, lyrics::Lyrics, Album, AlbumSort, AlbumType, ApiSource, Artist, AudioFormat,
+    PlaybackQuality, Playlist, Track, TrackApiSource,
 };
 use moosicbox_paging::{Page, PagingResponse, PagingResult};
+use moosicbox_search::api::models::ApiSearchResultsResponse;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use strum_macros::{AsRefStr, EnumString};
@@ -74,10 +76,16 @@ enum TidalApiEndpoint {
     RemoveFavoriteAlbum,
     ArtistAlbums,
     Track,
+    TrackLyrics,
     FavoriteTracks,
     AddFavoriteTrack,
     RemoveFavoriteTrack,
     AlbumTracks,
+    UserPlaylists,
+    Playlist,
+    PlaylistTracks,
+    AddPlaylistTracks,
+    RemovePlaylistTracks,
     TrackUrl,
     TrackPlaybackInfo,
     Search,
@@ -113,6 +121,7 @@ impl ToUrl for TidalApiEndpoint {
             }
             Self::ArtistAlbums => format!("{TIDAL_API_BASE_URL}/artists/:artistId/albums"),
             Self::Track => format!("{TIDAL_API_BASE_URL}/tracks/:trackId"),
+            Self::TrackLyrics => format!("{TIDAL_API_BASE_URL}/tracks/:trackId/lyrics"),
             Self::FavoriteTracks => format!("{TIDAL_API_BASE_URL}/users/:userId/favorites/tracks"),
             Self::AddFavoriteTrack => {
                 format!("{TIDAL_API_BASE_URL}/users/:userId/favorites/tracks")
@@ -121,6 +130,13 @@ impl ToUrl for TidalApiEndpoint {
                 format!("{TIDAL_API_BASE_URL}/users/:userId/favorites/tracks/:trackId")
             }
             Self::AlbumTracks => format!("{TIDAL_API_BASE_URL}/albums/:albumId/tracks"),
+            Self::UserPlaylists => format!("{TIDAL_API_BASE_URL}/users/:userId/playlists"),
+            Self::Playlist => format!("{TIDAL_API_BASE_URL}/playlists/:playlistId"),
+            Self::PlaylistTracks => format!("{TIDAL_API_BASE_URL}/playlists/:playlistId/tracks"),
+            Self::AddPlaylistTracks => format!("{TIDAL_API_BASE_URL}/playlists/:playlistId/items"),
+            Self::RemovePlaylistTracks => {
+                format!("{TIDAL_API_BASE_URL}/playlists/:playlistId/items/:indices")
+            }
             Self::TrackUrl => format!("{TIDAL_API_BASE_URL}/tracks/:trackId/urlpostpaywall"),
             Self::TrackPlaybackInfo => format!("{TIDAL_API_BASE_URL}/tracks/:trackId/playbackinfo"),
             Self::Search => format!("{TIDAL_API_BASE_URL}/search/top-hits"),
@@ -908,11 +924,13 @@ impl From<AlbumSort> for TidalAlbumOrderDirection {
             AlbumSort::ArtistAsc
             | AlbumSort::NameAsc
             | AlbumSort::ReleaseDateAsc
-            | AlbumSort::DateAddedAsc => Self::Asc,
+            | AlbumSort::DateAddedAsc
+            | AlbumSort::GenreAsc => Self::Asc,
             AlbumSort::NameDesc
             | AlbumSort::ArtistDesc
             | AlbumSort::ReleaseDateDesc
-            | AlbumSort::DateAddedDesc => Self::Desc,
+            | AlbumSort::DateAddedDesc
+            | AlbumSort::GenreDesc => Self::Desc,
         }
     }
 }
@@ -1919,111 +1937,33 @@ pub async fn track(
     Ok(value.as_model()?)
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, EnumString, AsRefStr)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
-#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
-pub enum SearchType {
-    Artists,
-    Albums,
-    Tracks,
-    Videos,
-    Playlists,
-    UserProfiles,
-}
-
-impl From<SearchType> for TidalSearchType {
-    fn from(value: SearchType) -> Self {
-        match value {
-            SearchType::Artists => Self::Artists,
-            SearchType::Albums => Self::Albums,
-            SearchType::Tracks => Self::Tracks,
-            SearchType::Videos => Self::Videos,
-            SearchType::Playlists => Self::Playlists,
-            SearchType::UserProfiles => Self::UserProfiles,
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, EnumString, AsRefStr)]
-#[serde(rename_all = "UPPERCASE")]
-#[strum(serialize_all = "UPPERCASE")]
-pub enum TidalSearchType {
-    Artists,
-    Albums,
-    Tracks,
-    Videos,
-    Playlists,
-    UserProfiles,
-}
-
 #[derive(Debug, Error)]
-pub enum TidalSearchError {
+pub enum TidalTrackLyricsError {
     #[error(transparent)]
     AuthenticatedRequest(#[from] AuthenticatedRequestError),
     #[error(transparent)]
     Parse(#[from] ParseError),
 }
 
+/// Fetches the lyrics of a track, if Tidal has any for it.
+///
 /// # Errors
 ///
 /// * If the HTTP request failed
 /// * If the JSON response failed to parse
 /// * If a database error occurred
-#[allow(clippy::too_many_arguments)]
-pub async fn search(
+pub async fn track_lyrics(
     #[cfg(feature = "db")] db: &LibraryDatabase,
-    query: &str,
-    offset: Option<usize>,
-    limit: Option<usize>,
-    include_contributions: Option<bool>,
-    include_did_you_mean: Option<bool>,
-    include_user_playlists: Option<bool>,
-    supports_user_data: Option<bool>,
-    types: Option<Vec<TidalSearchType>>,
+    track_id: &Id,
     country_code: Option<String>,
     locale: Option<String>,
     device_type: Option<TidalDeviceType>,
     access_token: Option<String>,
-) -> Result<TidalSearchResults, TidalSearchError> {
-    static DEFAULT_TYPES: [TidalSearchType; 3] = [
-        TidalSearchType::Artists,
-        TidalSearchType::Albums,
-        TidalSearchType::Tracks,
-    ];
-
+) -> Result<Option<TidalLyrics>, TidalTrackLyricsError> {
     let url = tidal_api_endpoint!(
-        Search,
-        &[],
+        TrackLyrics,
+        &[(":trackId", &track_id.to_string())],
         &[
-            ("query", query),
-            ("offset", &offset.unwrap_or(0).to_string()),
-            ("limit", &limit.unwrap_or(3).to_string()),
-            (
-                "includeContributions",
-                &include_contributions.unwrap_or(false).to_string()
-            ),
-            (
-                "includeDidYouMean",
-                &include_did_you_mean.unwrap_or(false).to_string()
-            ),
-            (
-                "includeUserPlaylists",
-                &include_user_playlists.unwrap_or(false).to_string()
-            ),
-            (
-                "supportsUserData",
-                &supports_user_data.unwrap_or(false).to_string()
-            ),
-            (
-                "types",
-                &types
-                    .unwrap_or_else(|| DEFAULT_TYPES.to_vec())
-                    .iter()
-                    .map(AsRef::as_ref)
-                    .collect::<Vec<_>>()
-                    .join(",")
-            ),
             (
                 "countryCode",
                 &country_code.clone().unwrap_or_else(|| "US".into())
@@ -2036,65 +1976,81 @@ pub async fn search(
         ]
     );
 
-    let value = authenticated_request(
+    let value = match authenticated_request(
         #[cfg(feature = "db")]
         db,
         &url,
         access_token,
     )
-    .await?;
-
-    log::trace!("Received search response: {value:?}");
-
-    Ok(value.as_model()?)
-}
+    .await
+    {
+        Ok(value) => value,
+        Err(AuthenticatedRequestError::RequestFailed(404, _)) => {
+            log::debug!("No lyrics for track {track_id}");
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
 
-#[derive(Debug, Serialize, Deserialize, EnumString, AsRefStr, PartialEq, Eq, Clone, Copy)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
-#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
-pub enum TidalAudioQuality {
-    High,
-    Lossless,
-    HiResLossless,
-}
+    log::trace!("Received track lyrics response: {value:?}");
 
-impl From<TrackAudioQuality> for TidalAudioQuality {
-    fn from(value: TrackAudioQuality) -> Self {
-        match value {
-            TrackAudioQuality::Low => Self::High,
-            TrackAudioQuality::FlacLossless => Self::Lossless,
-            TrackAudioQuality::FlacHiRes | TrackAudioQuality::FlacHighestRes => Self::HiResLossless,
-        }
-    }
+    Ok(Some(value.as_model()?))
 }
 
 #[derive(Debug, Error)]
-pub enum TidalTrackFileUrlError {
+pub enum TidalUserPlaylistsError {
     #[error(transparent)]
     AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error("No user ID available")]
+    NoUserIdAvailable,
+    #[error("Request failed: {0:?}")]
+    RequestFailed(String),
     #[error(transparent)]
     Parse(#[from] ParseError),
 }
 
-/// # Errors
-///
-/// * If the HTTP request failed
-/// * If the JSON response failed to parse
-/// * If a database error occurred
-pub async fn track_file_url(
+#[allow(clippy::too_many_arguments)]
+#[async_recursion]
+pub async fn user_playlists(
     #[cfg(feature = "db")] db: &LibraryDatabase,
-    audio_quality: TidalAudioQuality,
-    track_id: &Id,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<TidalDeviceType>,
     access_token: Option<String>,
-) -> Result<Vec<String>, TidalTrackFileUrlError> {
+    user_id: Option<u64>,
+) -> PagingResult<TidalPlaylist, TidalUserPlaylistsError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    #[cfg(feature = "db")]
+    let user_id = if let Some(user_id) = user_id {
+        Some(user_id)
+    } else {
+        match db::get_tidal_config(db).await {
+            Ok(Some(config)) => Some(config.user_id),
+            _ => None,
+        }
+    };
+
+    let user_id = user_id.ok_or(TidalUserPlaylistsError::NoUserIdAvailable)?;
+
     let url = tidal_api_endpoint!(
-        TrackUrl,
-        &[(":trackId", &track_id.to_string())],
+        UserPlaylists,
+        &[(":userId", &user_id.to_string())],
         &[
-            ("audioquality", audio_quality.as_ref()),
-            ("urlusagemode", "STREAM"),
-            ("assetpresentation", "FULL")
+            ("offset", &offset.to_string()),
+            ("limit", &limit.to_string()),
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(TidalDeviceType::Browser).as_ref(),
+            ),
         ]
     );
 
@@ -2102,41 +2058,58 @@ pub async fn track_file_url(
         #[cfg(feature = "db")]
         db,
         &url,
-        access_token,
+        access_token.clone(),
     )
     .await?;
 
-    log::trace!("Received track file url response: {value:?}");
+    log::trace!("Received user playlists response: {value:?}");
 
-    Ok(value.to_value("urls")?)
-}
+    let items = value
+        .to_value::<Option<_>>("items")?
+        .ok_or_else(|| TidalUserPlaylistsError::RequestFailed(format!("{value:?}")))?;
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
-pub struct TidalTrackPlaybackInfo {
-    pub album_peak_amplitude: f64,
-    pub album_replay_gain: f64,
-    pub asset_presentation: String,
-    pub audio_mode: String,
-    pub audio_quality: String,
-    pub bit_depth: Option<u8>,
-    pub manifest: String,
-    pub manifest_hash: String,
-    pub manifest_mime_type: String,
-    pub sample_rate: Option<u32>,
-    pub track_id: u64,
-    pub track_peak_amplitude: f64,
-    pub track_replay_gain: f64,
+    let total = value.to_value("totalNumberOfItems")?;
+
+    #[cfg(feature = "db")]
+    let db = db.clone();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            #[cfg(feature = "db")]
+            let db = db.clone();
+            let country_code = country_code.clone();
+            let locale = locale.clone();
+            let access_token = access_token.clone();
+
+            Box::pin(async move {
+                user_playlists(
+                    #[cfg(feature = "db")]
+                    &db,
+                    Some(offset),
+                    Some(limit),
+                    country_code,
+                    locale,
+                    device_type,
+                    access_token,
+                    Some(user_id),
+                )
+                .await
+            })
+        }))),
+    })
 }
 
 #[derive(Debug, Error)]
-pub enum TidalTrackPlaybackInfoError {
+pub enum TidalPlaylistError {
     #[error(transparent)]
     AuthenticatedRequest(#[from] AuthenticatedRequestError),
     #[error(transparent)]
-    Serde(#[from] serde_json::Error),
-    #[error(transparent)]
     Parse(#[from] ParseError),
 }
 
@@ -2145,19 +2118,27 @@ pub enum TidalTrackPlaybackInfoError {
 /// * If the HTTP request failed
 /// * If the JSON response failed to parse
 /// * If a database error occurred
-pub async fn track_playback_info(
+pub async fn playlist(
     #[cfg(feature = "db")] db: &LibraryDatabase,
-    audio_quality: TidalAudioQuality,
-    track_id: &Id,
+    playlist_id: &Id,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<TidalDeviceType>,
     access_token: Option<String>,
-) -> Result<TidalTrackPlaybackInfo, TidalTrackPlaybackInfoError> {
+) -> Result<TidalPlaylist, TidalPlaylistError> {
     let url = tidal_api_endpoint!(
-        TrackPlaybackInfo,
-        &[(":trackId", &track_id.to_string())],
+        Playlist,
+        &[(":playlistId", &playlist_id.to_string())],
         &[
-            ("audioquality", audio_quality.as_ref()),
-            ("playbackmode", "STREAM"),
-            ("assetpresentation", "FULL")
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(TidalDeviceType::Browser).as_ref(),
+            ),
         ]
     );
 
@@ -2169,9 +2150,499 @@ pub async fn track_playback_info(
     )
     .await?;
 
-    log::trace!("Received track playback info response: {value:?}");
+    log::trace!("Received playlist response: {value:?}");
 
-    Ok(serde_json::from_value(value)?)
+    Ok(value.as_model()?)
+}
+
+#[derive(Debug, Error)]
+pub enum TidalPlaylistTracksError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error("Request failed: {0:?}")]
+    RequestFailed(String),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+#[allow(clippy::too_many_arguments)]
+#[async_recursion]
+pub async fn playlist_tracks(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<TidalDeviceType>,
+    access_token: Option<String>,
+) -> PagingResult<TidalTrack, TidalPlaylistTracksError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let url = tidal_api_endpoint!(
+        PlaylistTracks,
+        &[(":playlistId", &playlist_id.to_string())],
+        &[
+            ("offset", &offset.to_string()),
+            ("limit", &limit.to_string()),
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(TidalDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token.clone(),
+    )
+    .await?;
+
+    log::trace!("Received playlist tracks response: {value:?}");
+
+    let items = value
+        .to_value::<Option<_>>("items")?
+        .ok_or_else(|| TidalPlaylistTracksError::RequestFailed(format!("{value:?}")))?;
+
+    let total = value.to_value("totalNumberOfItems")?;
+
+    #[cfg(feature = "db")]
+    let db = db.clone();
+    let playlist_id = playlist_id.clone();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            #[cfg(feature = "db")]
+            let db = db.clone();
+            let playlist_id = playlist_id.clone();
+            let country_code = country_code.clone();
+            let locale = locale.clone();
+            let access_token = access_token.clone();
+
+            Box::pin(async move {
+                playlist_tracks(
+                    #[cfg(feature = "db")]
+                    &db,
+                    &playlist_id,
+                    Some(offset),
+                    Some(limit),
+                    country_code,
+                    locale,
+                    device_type,
+                    access_token,
+                )
+                .await
+            })
+        }))),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum TidalAddPlaylistTrackError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+pub async fn add_playlist_track(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    track_id: &Id,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<TidalDeviceType>,
+    access_token: Option<String>,
+) -> Result<(), TidalAddPlaylistTrackError> {
+    let url = tidal_api_endpoint!(
+        AddPlaylistTracks,
+        &[(":playlistId", &playlist_id.to_string())],
+        &[
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(TidalDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_post_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+        None,
+        Some(vec![
+            ("trackIds", &track_id.to_string()),
+            ("onDupes", "ADD"),
+            ("onArtifactNotFound", "FAIL"),
+        ]),
+    )
+    .await?;
+
+    log::trace!("Received add playlist track response: {value:?}");
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum TidalRemovePlaylistTrackError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    PlaylistTracks(#[from] TidalPlaylistTracksError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// Removes every occurrence of the given track from the playlist.
+///
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+pub async fn remove_playlist_track(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    track_id: &Id,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<TidalDeviceType>,
+    access_token: Option<String>,
+) -> Result<(), TidalRemovePlaylistTrackError> {
+    let indices = playlist_tracks(
+        #[cfg(feature = "db")]
+        db,
+        playlist_id,
+        None,
+        None,
+        country_code.clone(),
+        locale.clone(),
+        device_type,
+        access_token.clone(),
+    )
+    .await?
+    .with_rest_of_items_in_batches()
+    .await?
+    .into_iter()
+    .enumerate()
+    .filter(|(_, track)| &Id::from(track.id) == track_id)
+    .map(|(index, _)| index.to_string())
+    .collect::<Vec<_>>();
+
+    if indices.is_empty() {
+        return Ok(());
+    }
+
+    let url = tidal_api_endpoint!(
+        RemovePlaylistTracks,
+        &[
+            (":playlistId", &playlist_id.to_string()),
+            (":indices", &indices.join(",")),
+        ],
+        &[
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(TidalDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_delete_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received remove playlist track response: {value:?}");
+
+    Ok(())
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, EnumString, AsRefStr)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum SearchType {
+    Artists,
+    Albums,
+    Tracks,
+    Videos,
+    Playlists,
+    UserProfiles,
+}
+
+impl From<SearchType> for TidalSearchType {
+    fn from(value: SearchType) -> Self {
+        match value {
+            SearchType::Artists => Self::Artists,
+            SearchType::Albums => Self::Albums,
+            SearchType::Tracks => Self::Tracks,
+            SearchType::Videos => Self::Videos,
+            SearchType::Playlists => Self::Playlists,
+            SearchType::UserProfiles => Self::UserProfiles,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, EnumString, AsRefStr)]
+#[serde(rename_all = "UPPERCASE")]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum TidalSearchType {
+    Artists,
+    Albums,
+    Tracks,
+    Videos,
+    Playlists,
+    UserProfiles,
+}
+
+#[derive(Debug, Error)]
+pub enum TidalSearchError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+#[allow(clippy::too_many_arguments)]
+pub async fn search(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    query: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    include_contributions: Option<bool>,
+    include_did_you_mean: Option<bool>,
+    include_user_playlists: Option<bool>,
+    supports_user_data: Option<bool>,
+    types: Option<Vec<TidalSearchType>>,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<TidalDeviceType>,
+    access_token: Option<String>,
+) -> Result<TidalSearchResults, TidalSearchError> {
+    static DEFAULT_TYPES: [TidalSearchType; 3] = [
+        TidalSearchType::Artists,
+        TidalSearchType::Albums,
+        TidalSearchType::Tracks,
+    ];
+
+    let url = tidal_api_endpoint!(
+        Search,
+        &[],
+        &[
+            ("query", query),
+            ("offset", &offset.unwrap_or(0).to_string()),
+            ("limit", &limit.unwrap_or(3).to_string()),
+            (
+                "includeContributions",
+                &include_contributions.unwrap_or(false).to_string()
+            ),
+            (
+                "includeDidYouMean",
+                &include_did_you_mean.unwrap_or(false).to_string()
+            ),
+            (
+                "includeUserPlaylists",
+                &include_user_playlists.unwrap_or(false).to_string()
+            ),
+            (
+                "supportsUserData",
+                &supports_user_data.unwrap_or(false).to_string()
+            ),
+            (
+                "types",
+                &types
+                    .unwrap_or_else(|| DEFAULT_TYPES.to_vec())
+                    .iter()
+                    .map(AsRef::as_ref)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(TidalDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received search response: {value:?}");
+
+    Ok(value.as_model()?)
+}
+
+#[derive(Debug, Serialize, Deserialize, EnumString, AsRefStr, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum TidalAudioQuality {
+    High,
+    Lossless,
+    HiResLossless,
+}
+
+impl From<TrackAudioQuality> for TidalAudioQuality {
+    fn from(value: TrackAudioQuality) -> Self {
+        match value {
+            TrackAudioQuality::Low => Self::High,
+            TrackAudioQuality::FlacLossless => Self::Lossless,
+            TrackAudioQuality::FlacHiRes | TrackAudioQuality::FlacHighestRes => Self::HiResLossless,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TidalTrackFileUrlError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+pub async fn track_file_url(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    audio_quality: TidalAudioQuality,
+    track_id: &Id,
+    access_token: Option<String>,
+) -> Result<Vec<String>, TidalTrackFileUrlError> {
+    let url = tidal_api_endpoint!(
+        TrackUrl,
+        &[(":trackId", &track_id.to_string())],
+        &[
+            ("audioquality", audio_quality.as_ref()),
+            ("urlusagemode", "STREAM"),
+            ("assetpresentation", "FULL")
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received track file url response: {value:?}");
+
+    Ok(value.to_value("urls")?)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TidalTrackPlaybackInfo {
+    pub album_peak_amplitude: f64,
+    pub album_replay_gain: f64,
+    pub asset_presentation: String,
+    pub audio_mode: String,
+    pub audio_quality: String,
+    pub bit_depth: Option<u8>,
+    pub manifest: String,
+    pub manifest_hash: String,
+    pub manifest_mime_type: String,
+    pub sample_rate: Option<u32>,
+    pub track_id: u64,
+    pub track_peak_amplitude: f64,
+    pub track_replay_gain: f64,
+}
+
+#[derive(Debug, Error)]
+pub enum TidalTrackPlaybackInfoError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+pub async fn track_playback_info(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    audio_quality: TidalAudioQuality,
+    track_id: &Id,
+    access_token: Option<String>,
+) -> Result<TidalTrackPlaybackInfo, TidalTrackPlaybackInfoError> {
+    let url = tidal_api_endpoint!(
+        TrackPlaybackInfo,
+        &[(":trackId", &track_id.to_string())],
+        &[
+            ("audioquality", audio_quality.as_ref()),
+            ("playbackmode", "STREAM"),
+            ("assetpresentation", "FULL")
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received track playback info response: {value:?}");
+
+    Ok(serde_json::from_value(value)?)
 }
 
 impl From<ArtistOrder> for TidalArtistOrder {
@@ -2328,6 +2799,12 @@ impl From<TidalTrackError> for TrackError {
     }
 }
 
+impl From<TidalTrackLyricsError> for TrackLyricsError {
+    fn from(err: TidalTrackLyricsError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
 impl From<TidalTrackFileUrlError> for TrackError {
     fn from(err: TidalTrackFileUrlError) -> Self {
         Self::Other(Box::new(err))
@@ -2346,6 +2823,42 @@ impl From<TidalRemoveFavoriteTrackError> for RemoveTrackError {
     }
 }
 
+impl From<TidalUserPlaylistsError> for PlaylistsError {
+    fn from(err: TidalUserPlaylistsError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<TidalPlaylistError> for PlaylistError {
+    fn from(err: TidalPlaylistError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<TidalPlaylistTracksError> for TracksError {
+    fn from(err: TidalPlaylistTracksError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<TidalAddPlaylistTrackError> for AddPlaylistTrackError {
+    fn from(err: TidalAddPlaylistTrackError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<TidalRemovePlaylistTrackError> for RemovePlaylistTrackError {
+    fn from(err: TidalRemovePlaylistTrackError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<TidalSearchError> for SearchError {
+    fn from(err: TidalSearchError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
 pub struct TidalMusicApi {
     #[cfg(feature = "db")]
     db: LibraryDatabase,
@@ -2923,4 +3436,151 @@ impl MusicApi for TidalMusicApi {
             .await
             .map_err(|e| TrackError::Other(Box::new(e)))?)
     }
+
+    async fn track_lyrics(&self, track_id: &Id) -> Result<Option<Lyrics>, TrackLyricsError> {
+        Ok(track_lyrics(
+            #[cfg(feature = "db")]
+            &self.db,
+            track_id,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?
+        .and_then(Into::into))
+    }
+
+    async fn playlists(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Playlist, PlaylistsError> {
+        Ok(user_playlists(
+            #[cfg(feature = "db")]
+            &self.db,
+            offset,
+            limit,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?
+        .inner_into())
+    }
+
+    async fn playlist(&self, playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError> {
+        Ok(
+            match playlist(
+                #[cfg(feature = "db")]
+                &self.db,
+                playlist_id,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            {
+                Ok(playlist) => Some(playlist.into()),
+                Err(e) => {
+                    if let TidalPlaylistError::AuthenticatedRequest(
+                        AuthenticatedRequestError::RequestFailed(status, _),
+                    ) = &e
+                    {
+                        if *status == 404 {
+                            return Ok(None);
+                        }
+                    }
+
+                    return Err(e.into());
+                }
+            },
+        )
+    }
+
+    async fn playlist_tracks(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Track, TracksError> {
+        Ok(playlist_tracks(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            offset,
+            limit,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?
+        .inner_into())
+    }
+
+    async fn add_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), AddPlaylistTrackError> {
+        Ok(add_playlist_track(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            track_id,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?)
+    }
+
+    async fn remove_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), RemovePlaylistTrackError> {
+        Ok(remove_playlist_track(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            track_id,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<ApiSearchResultsResponse, SearchError> {
+        Ok(search(
+            #[cfg(feature = "db")]
+            &self.db,
+            query,
+            offset.map(|x| x as usize),
+            limit.map(|x| x as usize),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?
+        .into())
+    }
 }
@@ -9,7 +9,8 @@ use moosicbox_music_api::models::ImageCoverSize;
 use moosicbox_music_models::{
     api::{ApiAlbum, ApiArtist},
     id::TryFromIdError,
-    Album, AlbumSource, ApiSource, ApiSources, Artist, Track, TrackApiSource,
+    lyrics::Lyrics,
+    Album, AlbumSource, ApiSource, ApiSources, Artist, Playlist, Track, TrackApiSource,
 };
 use moosicbox_search::api::models::{
     ApiGlobalAlbumSearchResult, ApiGlobalArtistSearchResult, ApiGlobalSearchResult,
@@ -216,6 +217,7 @@ pub struct TidalAlbum {
     pub popularity: u32,
     pub release_date: Option<String>,
     pub title: String,
+    pub upc: Option<String>,
     pub media_metadata_tags: Vec<String>,
 }
 
@@ -235,6 +237,7 @@ impl From<TidalAlbum> for Album {
             directory: None,
             blur: false,
             versions: vec![],
+            upc: value.upc.filter(|x| !x.is_empty()),
             album_source: AlbumSource::Tidal,
             api_source: ApiSource::Tidal,
             artist_sources: ApiSources::default()
@@ -430,6 +433,7 @@ impl AsModelResult<TidalAlbum, ParseError> for serde_json::Value {
             popularity: self.to_value("popularity")?,
             release_date: self.to_value("releaseDate")?,
             title: self.to_value("title")?,
+            upc: self.to_value("upc")?,
             media_metadata_tags: self.to_nested_value(&["mediaMetadata", "tags"])?,
         })
     }
@@ -481,6 +485,14 @@ impl From<TidalTrack> for Track {
             overall_bitrate: None,
             sample_rate: None,
             channels: None,
+            replay_gain: None,
+            play_count: None,
+            disc_number: None,
+            disc_total: None,
+            genres: vec![],
+            composer: None,
+            track_artist: None,
+            isrc: Some(value.isrc).filter(|x| !x.is_empty()),
             track_source: TrackApiSource::Tidal,
             api_source: ApiSource::Tidal,
             sources: ApiSources::default().with_source(ApiSource::Tidal, value.id.into()),
@@ -519,6 +531,109 @@ impl AsModelResult<TidalTrack, ParseError> for serde_json::Value {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TidalLyrics {
+    pub track_id: u64,
+    pub lyrics: Option<String>,
+    /// The lyrics in LRC format, when they are synced
+    pub subtitles: Option<String>,
+}
+
+impl From<TidalLyrics> for Option<Lyrics> {
+    fn from(value: TidalLyrics) -> Self {
+        if let Some(subtitles) = value.subtitles.filter(|x| !x.trim().is_empty()) {
+            return Some(Lyrics::parse(&subtitles));
+        }
+
+        value
+            .lyrics
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| Lyrics::plain(&x))
+    }
+}
+
+impl ToValueType<TidalLyrics> for &serde_json::Value {
+    fn to_value_type(self) -> Result<TidalLyrics, ParseError> {
+        self.as_model()
+    }
+}
+
+impl AsModelResult<TidalLyrics, ParseError> for serde_json::Value {
+    fn as_model(&self) -> Result<TidalLyrics, ParseError> {
+        Ok(TidalLyrics {
+            track_id: self.to_value("trackId")?,
+            lyrics: self.to_value("lyrics")?,
+            subtitles: self.to_value("subtitles")?,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TidalPlaylist {
+    pub uuid: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub square_image: Option<String>,
+    pub number_of_tracks: u32,
+    pub duration: u32,
+    pub created: Option<String>,
+    pub last_updated: Option<String>,
+}
+
+impl From<TidalPlaylist> for Playlist {
+    fn from(value: TidalPlaylist) -> Self {
+        let artwork = value.cover_url();
+
+        Self {
+            id: value.uuid.into(),
+            title: value.title,
+            description: value.description,
+            artwork,
+            track_count: Some(value.number_of_tracks),
+            api_source: ApiSource::Tidal,
+        }
+    }
+}
+
+impl TidalPlaylist {
+    #[must_use]
+    pub fn cover_url(&self) -> Option<String> {
+        self.square_image
+            .as_ref()
+            .map(|image| (image, "750x750"))
+            .or_else(|| self.image.as_ref().map(|image| (image, "1080x720")))
+            .map(|(image, size)| {
+                let image_path = image.replace('-', "/");
+                format!("https://resources.tidal.com/images/{image_path}/{size}.jpg")
+            })
+    }
+}
+
+impl ToValueType<TidalPlaylist> for &serde_json::Value {
+    fn to_value_type(self) -> Result<TidalPlaylist, ParseError> {
+        self.as_model()
+    }
+}
+
+impl AsModelResult<TidalPlaylist, ParseError> for serde_json::Value {
+    fn as_model(&self) -> Result<TidalPlaylist, ParseError> {
+        Ok(TidalPlaylist {
+            uuid: self.to_value("uuid")?,
+            title: self.to_value("title")?,
+            description: self.to_value("description")?,
+            image: self.to_value("image")?,
+            square_image: self.to_value("squareImage")?,
+            number_of_tracks: self.to_value("numberOfTracks")?,
+            duration: self.to_value("duration")?,
+            created: self.to_value("created")?,
+            last_updated: self.to_value("lastUpdated")?,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct TidalSearchTrack {
@@ -662,6 +777,7 @@ impl From<TidalSearchResults> for ApiSearchResultsResponse {
         Self {
             position,
             results: [artists, albums, tracks].concat(),
+            facets: None,
         }
     }
 }
@@ -1302,6 +1302,20 @@ impl TunnelSender {
                                         )
                                         .await?;
                                     }
+                                    #[cfg(feature = "alac")]
+                                    Some(AudioFormat::Alac) => {
+                                        self.send_stream(
+                                            request_id,
+                                            200,
+                                            &response_headers,
+                                            ranges,
+                                            moosicbox_audio_output::encoder::alac::encode_alac_stream(
+                                                &path,
+                                            ),
+                                            encoding,
+                                        )
+                                        .await?;
+                                    }
                                     #[cfg(feature = "mp3")]
                                     Some(AudioFormat::Mp3) => {
                                         self.send_stream(
@@ -1325,6 +1339,34 @@ impl TunnelSender {
                                         encoding,
                                     ).await?;
                                     }
+                                    #[cfg(feature = "vorbis")]
+                                    Some(AudioFormat::Vorbis) => {
+                                        self.send_stream(
+                                            request_id,
+                                            200,
+                                            &response_headers,
+                                            ranges,
+                                            moosicbox_audio_output::encoder::vorbis::encode_vorbis_stream(
+                                                &path,
+                                            ),
+                                            encoding,
+                                        )
+                                        .await?;
+                                    }
+                                    #[cfg(feature = "wav")]
+                                    Some(AudioFormat::Wav) => {
+                                        self.send_stream(
+                                            request_id,
+                                            200,
+                                            &response_headers,
+                                            ranges,
+                                            moosicbox_audio_output::encoder::wav::encode_wav_stream(
+                                                &path,
+                                            ),
+                                            encoding,
+                                        )
+                                        .await?;
+                                    }
                                     _ => {
                                         self.send(
                                             request_id,
@@ -1386,6 +1428,18 @@ impl TunnelSender {
                                                     ))
                                                 }));
                                         }
+                                        #[cfg(feature = "alac")]
+                                        AudioFormat::Alac => {
+                                            use moosicbox_audio_output::encoder::alac::AlacEncoder;
+                                            log::debug!("Using ALAC encoder for output");
+                                            audio_output_handler = audio_output_handler
+                                                .with_output(Box::new(move |spec, duration| {
+                                                    Ok(Box::new(
+                                                        AlacEncoder::with_writer(writer.clone())
+                                                            .open(spec, duration),
+                                                    ))
+                                                }));
+                                        }
                                         #[cfg(feature = "mp3")]
                                         AudioFormat::Mp3 => {
                                             use moosicbox_audio_output::encoder::mp3::Mp3Encoder;
@@ -1410,6 +1464,30 @@ impl TunnelSender {
                                                     ))
                                                 }));
                                         }
+                                        #[cfg(feature = "vorbis")]
+                                        AudioFormat::Vorbis => {
+                                            use moosicbox_audio_output::encoder::vorbis::VorbisEncoder;
+                                            log::debug!("Using Vorbis encoder for output");
+                                            audio_output_handler = audio_output_handler
+                                                .with_output(Box::new(move |spec, duration| {
+                                                    Ok(Box::new(
+                                                        VorbisEncoder::with_writer(writer.clone())
+                                                            .open(spec, duration),
+                                                    ))
+                                                }));
+                                        }
+                                        #[cfg(feature = "wav")]
+                                        AudioFormat::Wav => {
+                                            use moosicbox_audio_output::encoder::wav::WavEncoder;
+                                            log::debug!("Using WAV encoder for output");
+                                            audio_output_handler = audio_output_handler
+                                                .with_output(Box::new(move |spec, duration| {
+                                                    Ok(Box::new(
+                                                        WavEncoder::with_writer(writer.clone())
+                                                            .open(spec, duration),
+                                                    ))
+                                                }));
+                                        }
                                         _ => {}
                                     }
 
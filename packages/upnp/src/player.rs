@@ -305,6 +305,7 @@ impl Player for UpnpPlayer {
                 .unwrap()
                 .clone()
                 .map(Into::into),
+            bit_perfect: false,
         })
     }
 
@@ -569,6 +570,10 @@ impl UpnpPlayer {
                                             volume: None,
                                             playlist: None,
                                             quality: None,
+                                            shuffle: None,
+                                            repeat_mode: None,
+                                            crossfade: None,
+                                            replay_gain_mode: None,
                                         };
                                         send_playback_event(&update, playback);
                                     }
@@ -1,7 +1,7 @@
 use moosicbox_audio_zone::models::{ApiAudioZoneWithSession, CreateAudioZone};
 use moosicbox_session::models::{
     ApiConnection, ApiPlaybackTarget, ApiSession, ApiUpdateSession, CreateSession, DeleteSession,
-    RegisterConnection, RegisterPlayer, UpdateSession,
+    EditSessionQueue, RegisterConnection, RegisterPlayer, SessionQueueUpdated, UpdateSession,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -17,6 +17,7 @@ pub enum InboundPayload {
     CreateSession(CreateSessionPayload),
     UpdateSession(UpdateSessionPayload),
     DeleteSession(DeleteSessionPayload),
+    EditSessionQueue(EditSessionQueuePayload),
     RegisterConnection(RegisterConnectionPayload),
     RegisterPlayers(RegisterPlayersPayload),
     CreateAudioZone(CreateAudioZonePayload),
@@ -36,6 +37,7 @@ pub enum OutboundPayload {
     ConnectionId(ConnectionIdPayload),
     Sessions(SessionsPayload),
     SessionUpdated(SessionUpdatedPayload),
+    SessionQueueUpdated(SessionQueueUpdatedPayload),
     AudioZoneWithSessions(AudioZoneWithSessionsPayload),
     DownloadEvent(DownloadEventPayload),
     ScanEvent(ScanEventPayload),
@@ -71,6 +73,12 @@ pub struct DeleteSessionPayload {
     pub payload: DeleteSession,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EditSessionQueuePayload {
+    pub payload: EditSessionQueue,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterConnectionPayload {
@@ -119,6 +127,12 @@ pub struct SessionUpdatedPayload {
     pub payload: ApiUpdateSession,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionQueueUpdatedPayload {
+    pub payload: SessionQueueUpdated,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DownloadEventPayload {
@@ -18,9 +18,10 @@ use moosicbox_session::{
     get_session_playlist,
     models::{
         ApiConnection, ApiSessionPlaylist, ApiUpdateSession, ApiUpdateSessionPlaylist, Connection,
-        CreateSession, DeleteSession, PlaybackTarget, RegisterConnection, RegisterPlayer,
-        UpdateSession,
+        CreateSession, DeleteSession, EditSessionQueue, PlaybackTarget, RegisterConnection,
+        RegisterPlayer, SessionQueueUpdated, UpdateSession,
     },
+    EditSessionQueueError,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -28,7 +29,8 @@ use thiserror::Error;
 
 use crate::models::{
     AudioZoneWithSessionsPayload, ConnectionIdPayload, ConnectionsPayload, DownloadEventPayload,
-    InboundPayload, OutboundPayload, ScanEventPayload, SessionUpdatedPayload, SessionsPayload,
+    InboundPayload, OutboundPayload, ScanEventPayload, SessionQueueUpdatedPayload,
+    SessionUpdatedPayload, SessionsPayload,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -182,6 +184,8 @@ pub enum WebsocketMessageError {
     #[error(transparent)]
     UpdateSession(#[from] UpdateSessionError),
     #[error(transparent)]
+    EditSessionQueue(#[from] EditSessionQueueError),
+    #[error(transparent)]
     DatabaseFetch(#[from] DatabaseFetchError),
     #[error("Unknown {message:?}")]
     Unknown { message: String },
@@ -265,6 +269,11 @@ pub async fn message(
             delete_session(&db, sender, context, &payload.payload).await?;
             Ok(())
         }
+        InboundPayload::EditSessionQueue(payload) => {
+            let db = db.ok_or(WebsocketMessageError::MissingProfile)?;
+            edit_session_queue(&db, &payload.payload).await?;
+            Ok(())
+        }
         InboundPayload::Ping(_) => {
             log::trace!("Ping");
             Ok(())
@@ -597,6 +606,7 @@ pub async fn update_session(
             .map(|playlist: ApiSessionPlaylist| ApiUpdateSessionPlaylist {
                 session_playlist_id: playlist.session_playlist_id,
                 tracks: playlist.tracks,
+                unshuffled_positions: playlist.unshuffled_positions,
             })
     } else {
         None
@@ -616,6 +626,10 @@ pub async fn update_session(
         playback_target: payload.playback_target.clone().into(),
         playlist,
         quality: payload.quality,
+        shuffle: payload.shuffle,
+        repeat_mode: payload.repeat_mode,
+        crossfade: payload.crossfade,
+        replay_gain_mode: payload.replay_gain_mode,
     };
 
     let session_updated =
@@ -635,6 +649,41 @@ pub async fn update_session(
     Ok(())
 }
 
+/// Edits the session's queue. The resulting delta is sent to the clients by
+/// [`send_session_queue_updated`] from the session queue updated event, so
+/// edits made through the REST API are sent the same way.
+async fn edit_session_queue(
+    db: &LibraryDatabase,
+    payload: &EditSessionQueue,
+) -> Result<(), EditSessionQueueError> {
+    log::debug!(
+        "Editing session queue id={} edit={:?}",
+        payload.session_id,
+        payload.edit
+    );
+    moosicbox_session::edit_session_queue(db, payload).await?;
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// * If the json fails to serialize
+/// * If the ws message fails to broadcast
+pub async fn send_session_queue_updated(
+    sender: &impl WebsocketSender,
+    updated: &SessionQueueUpdated,
+) -> Result<(), WebsocketSendError> {
+    let session_queue_updated = serde_json::to_value(OutboundPayload::SessionQueueUpdated(
+        SessionQueueUpdatedPayload {
+            payload: updated.clone(),
+        },
+    ))?
+    .to_string();
+
+    sender.send_all(&session_queue_updated).await
+}
+
 async fn delete_session(
     db: &LibraryDatabase,
     sender: &impl WebsocketSender,
@@ -14,7 +14,7 @@ use std::{
     sync::{Arc, LazyLock},
 };
 
-use models::{YtAlbum, YtArtist, YtSearchResults, YtTrack};
+use models::{YtAlbum, YtArtist, YtPlaylist, YtSearchResults, YtTrack};
 #[cfg(feature = "db")]
 use moosicbox_database::profiles::LibraryDatabase;
 #[cfg(feature = "db")]
@@ -32,15 +32,17 @@ use moosicbox_music_api::{
         AlbumOrder, AlbumOrderDirection, AlbumsRequest, ArtistOrder, ArtistOrderDirection,
         TrackAudioQuality, TrackOrder, TrackOrderDirection, TrackSource,
     },
-    AddAlbumError, AddArtistError, AddTrackError, AlbumError, AlbumsError, ArtistAlbumsError,
-    ArtistError, ArtistsError, MusicApi, RemoveAlbumError, RemoveArtistError, RemoveTrackError,
+    AddAlbumError, AddArtistError, AddPlaylistTrackError, AddTrackError, AlbumError, AlbumsError,
+    ArtistAlbumsError, ArtistError, ArtistsError, MusicApi, PlaylistError, PlaylistsError,
+    RemoveAlbumError, RemoveArtistError, RemovePlaylistTrackError, RemoveTrackError, SearchError,
     TrackError, TrackOrId, TracksError,
 };
 use moosicbox_music_models::{
-    id::Id, Album, AlbumSort, AlbumType, ApiSource, Artist, AudioFormat, PlaybackQuality, Track,
-    TrackApiSource,
+    id::Id, Album, AlbumSort, AlbumType, ApiSource, Artist, AudioFormat, PlaybackQuality, Playlist,
+    Track, TrackApiSource,
 };
 use moosicbox_paging::{Page, PagingResponse, PagingResult};
+use moosicbox_search::api::models::ApiSearchResultsResponse;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use strum_macros::{AsRefStr, EnumString};
@@ -77,6 +79,11 @@ enum YtApiEndpoint {
     AddFavoriteTrack,
     RemoveFavoriteTrack,
     AlbumTracks,
+    UserPlaylists,
+    Playlist,
+    PlaylistTracks,
+    AddPlaylistTracks,
+    RemovePlaylistTracks,
     TrackUrl,
     TrackPlaybackInfo,
     Search,
@@ -123,6 +130,11 @@ impl ToUrl for YtApiEndpoint {
                 format!("{YT_API_BASE_URL}/")
             }
             Self::AlbumTracks => format!("{YT_API_BASE_URL}/"),
+            Self::UserPlaylists => format!("{YT_API_BASE_URL}/"),
+            Self::Playlist => format!("{YT_API_BASE_URL}/"),
+            Self::PlaylistTracks => format!("{YT_API_BASE_URL}/"),
+            Self::AddPlaylistTracks => format!("{YT_API_BASE_URL}/"),
+            Self::RemovePlaylistTracks => format!("{YT_API_BASE_URL}/"),
             Self::TrackUrl => format!("{YT_API_BASE_URL}/"),
             Self::TrackPlaybackInfo => format!("{YT_API_BASE_URL}/"),
             Self::Search => format!("{YT_API_BASE_URL}/music/get_search_suggestions"),
@@ -1019,11 +1031,13 @@ impl From<AlbumSort> for YtAlbumOrderDirection {
             AlbumSort::ArtistAsc
             | AlbumSort::NameAsc
             | AlbumSort::ReleaseDateAsc
-            | AlbumSort::DateAddedAsc => Self::Asc,
+            | AlbumSort::DateAddedAsc
+            | AlbumSort::GenreAsc => Self::Asc,
             AlbumSort::ArtistDesc
             | AlbumSort::NameDesc
             | AlbumSort::ReleaseDateDesc
-            | AlbumSort::DateAddedDesc => Self::Desc,
+            | AlbumSort::DateAddedDesc
+            | AlbumSort::GenreDesc => Self::Desc,
         }
     }
 }
@@ -2020,6 +2034,399 @@ pub async fn track(
     Ok(value.as_model()?)
 }
 
+#[derive(Debug, Error)]
+pub enum YtUserPlaylistsError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error("No user ID available")]
+    NoUserIdAvailable,
+    #[error("Request failed: {0:?}")]
+    RequestFailed(String),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+#[allow(clippy::too_many_arguments)]
+#[async_recursion]
+pub async fn user_playlists(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<YtDeviceType>,
+    access_token: Option<String>,
+    user_id: Option<u64>,
+) -> PagingResult<YtPlaylist, YtUserPlaylistsError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    #[cfg(feature = "db")]
+    let user_id = if let Some(user_id) = user_id {
+        Some(user_id)
+    } else {
+        match db::get_yt_config(db).await {
+            Ok(Some(config)) => Some(config.user_id),
+            _ => None,
+        }
+    };
+
+    let user_id = user_id.ok_or(YtUserPlaylistsError::NoUserIdAvailable)?;
+
+    let url = yt_api_endpoint!(
+        UserPlaylists,
+        &[(":userId", &user_id.to_string())],
+        &[
+            ("offset", &offset.to_string()),
+            ("limit", &limit.to_string()),
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(YtDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token.clone(),
+    )
+    .await?;
+
+    log::trace!("Received user playlists response: {value:?}");
+
+    let items = value
+        .to_value::<Option<_>>("items")?
+        .ok_or_else(|| YtUserPlaylistsError::RequestFailed(format!("{value:?}")))?;
+
+    let total = value.to_value("totalNumberOfItems")?;
+
+    #[cfg(feature = "db")]
+    let db = db.clone();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            #[cfg(feature = "db")]
+            let db = db.clone();
+            let country_code = country_code.clone();
+            let locale = locale.clone();
+            let access_token = access_token.clone();
+
+            Box::pin(async move {
+                user_playlists(
+                    #[cfg(feature = "db")]
+                    &db,
+                    Some(offset),
+                    Some(limit),
+                    country_code,
+                    locale,
+                    device_type,
+                    access_token,
+                    Some(user_id),
+                )
+                .await
+            })
+        }))),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum YtPlaylistError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+pub async fn playlist(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<YtDeviceType>,
+    access_token: Option<String>,
+) -> Result<YtPlaylist, YtPlaylistError> {
+    let url = yt_api_endpoint!(
+        Playlist,
+        &[(":playlistId", &playlist_id.to_string())],
+        &[
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(YtDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received playlist response: {value:?}");
+
+    Ok(value.as_model()?)
+}
+
+#[derive(Debug, Error)]
+pub enum YtPlaylistTracksError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error("Request failed: {0:?}")]
+    RequestFailed(String),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+#[allow(clippy::too_many_arguments)]
+#[async_recursion]
+pub async fn playlist_tracks(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<YtDeviceType>,
+    access_token: Option<String>,
+) -> PagingResult<YtTrack, YtPlaylistTracksError> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(100);
+
+    let url = yt_api_endpoint!(
+        PlaylistTracks,
+        &[(":playlistId", &playlist_id.to_string())],
+        &[
+            ("offset", &offset.to_string()),
+            ("limit", &limit.to_string()),
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(YtDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token.clone(),
+    )
+    .await?;
+
+    log::trace!("Received playlist tracks response: {value:?}");
+
+    let items = value
+        .to_value::<Option<_>>("items")?
+        .ok_or_else(|| YtPlaylistTracksError::RequestFailed(format!("{value:?}")))?;
+
+    let total = value.to_value("totalNumberOfItems")?;
+
+    #[cfg(feature = "db")]
+    let db = db.clone();
+    let playlist_id = playlist_id.clone();
+
+    Ok(PagingResponse {
+        page: Page::WithTotal {
+            items,
+            offset,
+            limit,
+            total,
+        },
+        fetch: Arc::new(Mutex::new(Box::new(move |offset, limit| {
+            #[cfg(feature = "db")]
+            let db = db.clone();
+            let playlist_id = playlist_id.clone();
+            let country_code = country_code.clone();
+            let locale = locale.clone();
+            let access_token = access_token.clone();
+
+            Box::pin(async move {
+                playlist_tracks(
+                    #[cfg(feature = "db")]
+                    &db,
+                    &playlist_id,
+                    Some(offset),
+                    Some(limit),
+                    country_code,
+                    locale,
+                    device_type,
+                    access_token,
+                )
+                .await
+            })
+        }))),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum YtAddPlaylistTrackError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+pub async fn add_playlist_track(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    track_id: &Id,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<YtDeviceType>,
+    access_token: Option<String>,
+) -> Result<(), YtAddPlaylistTrackError> {
+    let url = yt_api_endpoint!(
+        AddPlaylistTracks,
+        &[(":playlistId", &playlist_id.to_string())],
+        &[
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(YtDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_post_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+        None,
+        Some(vec![
+            ("trackIds", &track_id.to_string()),
+            ("onDupes", "ADD"),
+            ("onArtifactNotFound", "FAIL"),
+        ]),
+    )
+    .await?;
+
+    log::trace!("Received add playlist track response: {value:?}");
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum YtRemovePlaylistTrackError {
+    #[error(transparent)]
+    AuthenticatedRequest(#[from] AuthenticatedRequestError),
+    #[error(transparent)]
+    PlaylistTracks(#[from] YtPlaylistTracksError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// Removes every occurrence of the given track from the playlist.
+///
+/// # Errors
+///
+/// * If the HTTP request failed
+/// * If the JSON response failed to parse
+/// * If a database error occurred
+pub async fn remove_playlist_track(
+    #[cfg(feature = "db")] db: &LibraryDatabase,
+    playlist_id: &Id,
+    track_id: &Id,
+    country_code: Option<String>,
+    locale: Option<String>,
+    device_type: Option<YtDeviceType>,
+    access_token: Option<String>,
+) -> Result<(), YtRemovePlaylistTrackError> {
+    let indices = playlist_tracks(
+        #[cfg(feature = "db")]
+        db,
+        playlist_id,
+        None,
+        None,
+        country_code.clone(),
+        locale.clone(),
+        device_type,
+        access_token.clone(),
+    )
+    .await?
+    .with_rest_of_items_in_batches()
+    .await?
+    .into_iter()
+    .enumerate()
+    .filter(|(_, track)| &Id::from(track.id.as_str()) == track_id)
+    .map(|(index, _)| index.to_string())
+    .collect::<Vec<_>>();
+
+    if indices.is_empty() {
+        return Ok(());
+    }
+
+    let url = yt_api_endpoint!(
+        RemovePlaylistTracks,
+        &[
+            (":playlistId", &playlist_id.to_string()),
+            (":indices", &indices.join(",")),
+        ],
+        &[
+            (
+                "countryCode",
+                &country_code.clone().unwrap_or_else(|| "US".into())
+            ),
+            ("locale", &locale.clone().unwrap_or_else(|| "en_US".into())),
+            (
+                "deviceType",
+                device_type.unwrap_or(YtDeviceType::Browser).as_ref(),
+            ),
+        ]
+    );
+
+    let value = authenticated_delete_request(
+        #[cfg(feature = "db")]
+        db,
+        &url,
+        access_token,
+    )
+    .await?;
+
+    log::trace!("Received remove playlist track response: {value:?}");
+
+    Ok(())
+}
+
 #[derive(Debug, Error)]
 pub enum YtSearchError {
     #[error(transparent)]
@@ -2359,6 +2766,42 @@ impl From<YtRemoveFavoriteTrackError> for RemoveTrackError {
     }
 }
 
+impl From<YtUserPlaylistsError> for PlaylistsError {
+    fn from(err: YtUserPlaylistsError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<YtPlaylistError> for PlaylistError {
+    fn from(err: YtPlaylistError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<YtPlaylistTracksError> for TracksError {
+    fn from(err: YtPlaylistTracksError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<YtAddPlaylistTrackError> for AddPlaylistTrackError {
+    fn from(err: YtAddPlaylistTrackError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<YtRemovePlaylistTrackError> for RemovePlaylistTrackError {
+    fn from(err: YtRemovePlaylistTrackError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl From<YtSearchError> for SearchError {
+    fn from(err: YtSearchError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
 pub struct YtMusicApi {
     #[cfg(feature = "db")]
     db: LibraryDatabase,
@@ -2833,4 +3276,124 @@ impl MusicApi for YtMusicApi {
             .await
             .map_err(|e| TrackError::Other(Box::new(e)))?)
     }
+
+    async fn playlists(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Playlist, PlaylistsError> {
+        Ok(user_playlists(
+            #[cfg(feature = "db")]
+            &self.db,
+            offset,
+            limit,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?
+        .inner_into())
+    }
+
+    async fn playlist(&self, playlist_id: &Id) -> Result<Option<Playlist>, PlaylistError> {
+        Ok(
+            match playlist(
+                #[cfg(feature = "db")]
+                &self.db,
+                playlist_id,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            {
+                Ok(playlist) => Some(playlist.into()),
+                Err(e) => {
+                    if let YtPlaylistError::AuthenticatedRequest(
+                        AuthenticatedRequestError::RequestFailed(status, _),
+                    ) = &e
+                    {
+                        if *status == 404 {
+                            return Ok(None);
+                        }
+                    }
+
+                    return Err(e.into());
+                }
+            },
+        )
+    }
+
+    async fn playlist_tracks(
+        &self,
+        playlist_id: &Id,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> PagingResult<Track, TracksError> {
+        Ok(playlist_tracks(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            offset,
+            limit,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?
+        .inner_into())
+    }
+
+    async fn add_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), AddPlaylistTrackError> {
+        Ok(add_playlist_track(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            track_id,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?)
+    }
+
+    async fn remove_playlist_track(
+        &self,
+        playlist_id: &Id,
+        track_id: &Id,
+    ) -> Result<(), RemovePlaylistTrackError> {
+        Ok(remove_playlist_track(
+            #[cfg(feature = "db")]
+            &self.db,
+            playlist_id,
+            track_id,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<ApiSearchResultsResponse, SearchError> {
+        Ok(
+            search(query, offset.map(|x| x as usize), limit.map(|x| x as usize))
+                .await?
+                .into(),
+        )
+    }
 }
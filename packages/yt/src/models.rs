@@ -7,7 +7,7 @@ use moosicbox_json_utils::{
 };
 use moosicbox_music_models::{
     api::{ApiAlbum, ApiArtist},
-    Album, AlbumSource, ApiSource, ApiSources, Artist, Track, TrackApiSource,
+    Album, AlbumSource, ApiSource, ApiSources, Artist, Playlist, Track, TrackApiSource,
 };
 use moosicbox_search::api::models::{
     ApiGlobalAlbumSearchResult, ApiGlobalArtistSearchResult, ApiGlobalSearchResult,
@@ -193,6 +193,7 @@ impl From<YtAlbum> for Album {
             directory: None,
             blur: false,
             versions: vec![],
+            upc: None,
             album_source: AlbumSource::Yt,
             api_source: ApiSource::Yt,
             artist_sources: ApiSources::default()
@@ -402,6 +403,14 @@ impl From<YtTrack> for Track {
             overall_bitrate: None,
             sample_rate: None,
             channels: None,
+            replay_gain: None,
+            play_count: None,
+            disc_number: None,
+            disc_total: None,
+            genres: vec![],
+            composer: None,
+            track_artist: None,
+            isrc: Some(value.isrc).filter(|x| !x.is_empty()),
             track_source: TrackApiSource::Yt,
             api_source: ApiSource::Yt,
             sources: ApiSources::default().with_source(ApiSource::Yt, value.id.into()),
@@ -501,6 +510,61 @@ impl AsModelResult<YtVideo, ParseError> for serde_json::Value {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct YtPlaylist {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub number_of_tracks: u32,
+    pub duration: u32,
+}
+
+impl From<YtPlaylist> for Playlist {
+    fn from(value: YtPlaylist) -> Self {
+        let artwork = value.cover_url();
+
+        Self {
+            id: value.id.as_str().into(),
+            title: value.title,
+            description: value.description,
+            artwork,
+            track_count: Some(value.number_of_tracks),
+            api_source: ApiSource::Yt,
+        }
+    }
+}
+
+impl YtPlaylist {
+    #[must_use]
+    pub fn cover_url(&self) -> Option<String> {
+        self.image.as_ref().map(|image| {
+            let image_path = image.replace('-', "/");
+            format!("https://resources.yt.com/images/{image_path}/750x750.jpg")
+        })
+    }
+}
+
+impl ToValueType<YtPlaylist> for &serde_json::Value {
+    fn to_value_type(self) -> Result<YtPlaylist, ParseError> {
+        self.as_model()
+    }
+}
+
+impl AsModelResult<YtPlaylist, ParseError> for serde_json::Value {
+    fn as_model(&self) -> Result<YtPlaylist, ParseError> {
+        Ok(YtPlaylist {
+            id: self.to_value("id")?,
+            title: self.to_value("title")?,
+            description: self.to_value("description")?,
+            image: self.to_value("image")?,
+            number_of_tracks: self.to_value("numberOfTracks")?,
+            duration: self.to_value("duration")?,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct YtSearchTrack {
@@ -2141,6 +2205,7 @@ impl From<YtSearchResultsFormatted> for ApiSearchResultsResponse {
         Self {
             position,
             results: [artists, albums, tracks].concat(),
+            facets: None,
         }
     }
 }